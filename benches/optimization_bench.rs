@@ -1,27 +1,22 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use std::time::Duration;
+use atlas::workloads;
+use criterion::{criterion_group, criterion_main, Criterion};
 
-fn benchmark_config_loading(c: &mut Criterion) {
-    c.bench_function("config_loading", |b| {
-        b.iter(|| {
-            // Placeholder benchmark - replace with actual config loading
-            black_box(std::thread::sleep(Duration::from_micros(1)));
-        })
-    });
+fn benchmark_hot_paths(c: &mut Criterion) {
+    let mut workloads = workloads().expect("benchmark inputs");
+    let mut group = c.benchmark_group("atlas");
+    for workload in &mut workloads.workloads {
+        // System detection runs external tools and dominates wall time
+        if workload.name == "system_detection" {
+            group.sample_size(10);
+        } else {
+            group.sample_size(50);
+        }
+        group.bench_function(workload.name, |b| {
+            b.iter(|| workload.run().expect("workload failed"))
+        });
+    }
+    group.finish();
 }
 
-fn benchmark_system_detection(c: &mut Criterion) {
-    c.bench_function("system_detection", |b| {
-        b.iter(|| {
-            // Placeholder benchmark - replace with actual system detection
-            black_box(std::thread::sleep(Duration::from_micros(1)));
-        })
-    });
-}
-
-criterion_group!(
-    benches,
-    benchmark_config_loading,
-    benchmark_system_detection
-);
+criterion_group!(benches, benchmark_hot_paths);
 criterion_main!(benches);
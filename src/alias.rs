@@ -0,0 +1,168 @@
+//! User-defined command aliases
+//!
+//! `[aliases]` in the Atlas config maps a name to the arguments it stands
+//! for, e.g. `t = "build test --changed --stats"`. The alias is replaced by
+//! its arguments before the command line is parsed, so anything after it is
+//! passed on: `atlas t -p core` runs `atlas build test --changed --stats -p core`.
+//! Aliases may refer to other aliases; built-in commands always win.
+
+use crate::error::{OptimizerError, OptimizerResult};
+use clap::Command;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The arguments an alias stands for, as one string split at whitespace or
+/// as a list for arguments that contain spaces
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Alias {
+    Line(String),
+    Args(Vec<String>),
+}
+
+impl Alias {
+    pub fn args(&self) -> Vec<String> {
+        match self {
+            Self::Line(line) => line.split_whitespace().map(String::from).collect(),
+            Self::Args(args) => args.clone(),
+        }
+    }
+}
+
+/// Replace an alias in `args`, the full command line including the program
+/// name, with what it stands for
+pub fn expand(
+    mut args: Vec<String>,
+    aliases: &BTreeMap<String, Alias>,
+    cli: &Command,
+) -> OptimizerResult<Vec<String>> {
+    // Built, clap fills in argument actions and the help command
+    let mut cli = cli.clone();
+    cli.build();
+    let cli = &cli;
+    let Some(position) = command_position(&args, cli) else {
+        return Ok(args);
+    };
+    let mut seen = Vec::new();
+    loop {
+        let name = &args[position];
+        if is_builtin(name, cli) {
+            return Ok(args);
+        }
+        let Some(alias) = aliases.get(name) else {
+            return Ok(args);
+        };
+        if seen.contains(name) {
+            seen.push(name.clone());
+            return Err(OptimizerError::config(format!(
+                "Alias `{}` refers to itself: {}",
+                seen[0],
+                seen.join(" -> ")
+            )));
+        }
+        let expansion = alias.args();
+        if expansion.is_empty() {
+            return Err(OptimizerError::config(format!("Alias `{}` is empty", name)));
+        }
+        seen.push(name.clone());
+        args.splice(position..=position, expansion);
+    }
+}
+
+/// Aliases that are never used because a built-in command has their name
+pub fn shadowed<'a>(aliases: &'a BTreeMap<String, Alias>, cli: &Command) -> Vec<&'a str> {
+    let mut cli = cli.clone();
+    cli.build();
+    let cli = &cli;
+    aliases
+        .keys()
+        .filter(|name| is_builtin(name, cli))
+        .map(String::as_str)
+        .collect()
+}
+
+fn is_builtin(name: &str, cli: &Command) -> bool {
+    cli.find_subcommand(name).is_some()
+}
+
+/// Index of the command name in `args`: the first argument after the program
+/// name that is neither a global option nor the value of one
+fn command_position(args: &[String], cli: &Command) -> Option<usize> {
+    let takes_value = |arg: &str| {
+        cli.get_arguments()
+            .filter(|option| option.get_action().takes_values())
+            .any(|option| {
+                arg.strip_prefix("--")
+                    .is_some_and(|long| option.get_long() == Some(long))
+                    || arg
+                        .strip_prefix('-')
+                        .and_then(|short| short.parse::<char>().ok())
+                        .is_some_and(|short| option.get_short() == Some(short))
+            })
+    };
+    let mut index = 1;
+    while let Some(arg) = args.get(index) {
+        if arg == "--" {
+            return None;
+        }
+        if !arg.starts_with('-') {
+            return Some(index);
+        }
+        index += if takes_value(arg) { 2 } else { 1 };
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Arg;
+
+    fn cli() -> Command {
+        Command::new("atlas")
+            .arg(Arg::new("verbose").short('v').long("verbose").num_args(0))
+            .arg(Arg::new("project_dir").short('p').long("project-dir"))
+            .subcommand(Command::new("build"))
+            .subcommand(Command::new("status").alias("st"))
+    }
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_expand_alias_after_global_options() {
+        let aliases = BTreeMap::from([
+            (
+                "t".to_string(),
+                Alias::Line("build test --changed".to_string()),
+            ),
+            ("tt".to_string(), Alias::Args(vec!["t".to_string()])),
+            ("st".to_string(), Alias::Line("build check".to_string())),
+        ]);
+        assert_eq!(
+            expand(args("atlas -v -p t tt -p core"), &aliases, &cli()).unwrap(),
+            args("atlas -v -p t build test --changed -p core")
+        );
+        assert_eq!(
+            expand(args("atlas --project-dir=x tt"), &aliases, &cli()).unwrap(),
+            args("atlas --project-dir=x build test --changed")
+        );
+        // Built-in commands and their aliases win
+        assert_eq!(
+            expand(args("atlas st"), &aliases, &cli()).unwrap(),
+            args("atlas st")
+        );
+        assert_eq!(shadowed(&aliases, &cli()), vec!["st"]);
+    }
+
+    #[test]
+    fn test_recursive_alias_is_an_error() {
+        let aliases = BTreeMap::from([
+            ("a".to_string(), Alias::Line("b --fast".to_string())),
+            ("b".to_string(), Alias::Line("a".to_string())),
+        ]);
+        let error = expand(args("atlas a"), &aliases, &cli()).unwrap_err();
+        assert!(error.to_string().contains("a -> b -> a"), "{}", error);
+    }
+}
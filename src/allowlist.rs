@@ -0,0 +1,66 @@
+//! Restriction of the programs Atlas starts
+//!
+//! Organizations that do not want a build tool running `sudo` or package
+//! managers on its own set `allowed_commands` under `[tools]`. Every process
+//! Atlas starts is checked against it by program name, with `*` and `?`
+//! wildcards; anything else is refused. Version probes of programs that are
+//! not allowed find nothing, so those tools count as not installed.
+
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::members::glob_match;
+use std::path::Path;
+use std::sync::OnceLock;
+
+static ALLOWED: OnceLock<Vec<String>> = OnceLock::new();
+
+/// Refuse programs not in `allowed` from now on; without `init` every
+/// program may run
+pub fn init(allowed: Option<Vec<String>>) {
+    if let Some(allowed) = allowed {
+        let _ = ALLOWED.set(allowed);
+    }
+}
+
+/// Whether Atlas may start `program`
+pub fn check(program: &str) -> OptimizerResult<()> {
+    match ALLOWED.get() {
+        Some(allowed) if !is_allowed(allowed, program) => Err(OptimizerError::permission(format!(
+            "{} is not in allowed_commands under [tools] of the Atlas config",
+            program
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn is_allowed(allowed: &[String], program: &str) -> bool {
+    let path = Path::new(program);
+    let name = path
+        .file_stem()
+        .filter(|_| path.extension().is_some_and(|extension| extension == "exe"))
+        .or_else(|| path.file_name())
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    allowed
+        .iter()
+        .any(|pattern| pattern == program || glob_match(pattern, &name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_programs_match_by_name() {
+        let allowed: Vec<String> = ["cargo", "cargo-*", "rustup", "/opt/bin/mold"]
+            .map(String::from)
+            .to_vec();
+        assert!(is_allowed(&allowed, "cargo"));
+        assert!(is_allowed(&allowed, "/usr/bin/cargo"));
+        assert!(is_allowed(&allowed, "cargo.exe"));
+        assert!(is_allowed(&allowed, "cargo-nextest"));
+        assert!(is_allowed(&allowed, "/opt/bin/mold"));
+        assert!(!is_allowed(&allowed, "sudo"));
+        assert!(!is_allowed(&allowed, "brew"));
+        assert!(!is_allowed(&allowed, "mold"));
+    }
+}
@@ -0,0 +1,304 @@
+use crate::analysis::metadata::CargoMetadata;
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::utils::execute_command;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Files outside any member whose changes can affect every member
+const WORKSPACE_WIDE_FILES: &[&str] = &[
+    "Cargo.toml",
+    "Cargo.lock",
+    "rust-toolchain",
+    "rust-toolchain.toml",
+    ".cargo",
+];
+
+/// Workspace members affected by a set of changed files
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Affected {
+    /// Changed files, absolute
+    pub changed_files: Vec<PathBuf>,
+    /// Members containing a changed file
+    pub changed_members: BTreeSet<String>,
+    /// Changed members plus every member that depends on one, directly or not
+    pub affected_members: BTreeSet<String>,
+    /// Files outside the members, like `Cargo.lock`, that affect the whole workspace
+    pub workspace_wide: Vec<PathBuf>,
+}
+
+impl Affected {
+    /// Members affected by changes since `base`, or by uncommitted changes when
+    /// `base` is `None`. Untracked files count as changed.
+    pub fn detect(
+        project_root: &Path,
+        metadata: &CargoMetadata,
+        base: Option<&str>,
+    ) -> OptimizerResult<Self> {
+        let files = changed_files(project_root, base)?;
+        Ok(Self::resolve(metadata, &files))
+    }
+
+    /// Map changed files to members and close over reverse dependencies,
+    /// dev-dependencies included since their tests are affected too
+    pub fn resolve(metadata: &CargoMetadata, changed_files: &[PathBuf]) -> Self {
+        let members: Vec<(&str, &str, PathBuf)> = metadata
+            .members()
+            .filter_map(|package| {
+                let dir = package.manifest_path.parent()?.to_path_buf();
+                Some((package.id.as_str(), package.name.as_str(), dir))
+            })
+            .collect();
+
+        let mut affected = Self {
+            changed_files: changed_files.to_vec(),
+            ..Self::default()
+        };
+        let mut changed_ids: Vec<&str> = Vec::new();
+        for file in changed_files {
+            // Nested members belong to the innermost directory
+            let owner = members
+                .iter()
+                .filter(|(_, _, dir)| file.starts_with(dir))
+                .max_by_key(|(_, _, dir)| dir.components().count());
+
+            if let Some((id, name, _)) = owner {
+                if affected.changed_members.insert(name.to_string()) {
+                    changed_ids.push(id);
+                }
+            } else if is_workspace_wide(&metadata.workspace_root, file) {
+                affected.workspace_wide.push(file.clone());
+            }
+        }
+
+        // Reverse edges between members
+        let mut dependents: HashMap<&str, Vec<&str>> = HashMap::new();
+        for node in metadata.resolve.iter().flat_map(|resolve| &resolve.nodes) {
+            if !metadata.is_member(&node.id) {
+                continue;
+            }
+            for dep in &node.deps {
+                if metadata.is_member(&dep.pkg) {
+                    dependents
+                        .entry(dep.pkg.as_str())
+                        .or_default()
+                        .push(node.id.as_str());
+                }
+            }
+        }
+
+        let mut seen: HashSet<&str> = changed_ids.iter().copied().collect();
+        let mut queue = changed_ids;
+        while let Some(id) = queue.pop() {
+            for &dependent in dependents.get(id).into_iter().flatten() {
+                if seen.insert(dependent) {
+                    queue.push(dependent);
+                }
+            }
+        }
+
+        let names: HashMap<&str, &str> = members.iter().map(|(id, name, _)| (*id, *name)).collect();
+        affected.affected_members = seen
+            .into_iter()
+            .filter_map(|id| names.get(id).map(|name| name.to_string()))
+            .collect();
+        affected
+    }
+
+    /// Members to work on: all of them for workspace-wide changes
+    pub fn members_to_check<'a>(&'a self, metadata: &'a CargoMetadata) -> Vec<&'a str> {
+        if self.workspace_wide.is_empty() {
+            self.affected_members.iter().map(String::as_str).collect()
+        } else {
+            metadata
+                .members()
+                .map(|package| package.name.as_str())
+                .collect()
+        }
+    }
+}
+
+fn is_workspace_wide(workspace_root: &Path, file: &Path) -> bool {
+    let Ok(relative) = file.strip_prefix(workspace_root) else {
+        return false;
+    };
+    relative
+        .components()
+        .next()
+        .and_then(|first| first.as_os_str().to_str())
+        .is_some_and(|first| WORKSPACE_WIDE_FILES.contains(&first))
+}
+
+/// Whether Atlas itself wrote `path` into the project: its state under
+/// `.atlas/` and the backups it keeps of files it changes
+fn is_atlas_file(path: &Path) -> bool {
+    path.components()
+        .any(|component| component.as_os_str() == ".atlas")
+        || path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.ends_with(".backup"))
+}
+
+/// Files changed since `base` (or `HEAD`), including uncommitted and untracked
+/// files. Atlas's own state and backups are not changes to the code.
+pub fn changed_files(project_root: &Path, base: Option<&str>) -> OptimizerResult<Vec<PathBuf>> {
+    let toplevel = PathBuf::from(git(project_root, &["rev-parse", "--show-toplevel"])?.trim());
+
+    // Compare against where the branch left `base`, so unrelated commits on `base` do not count
+    let since = match base {
+        Some(base) => git(project_root, &["merge-base", base, "HEAD"])?
+            .trim()
+            .to_string(),
+        None => "HEAD".to_string(),
+    };
+
+    let diff = git(project_root, &["diff", "--name-only", &since])?;
+    let untracked = git(
+        project_root,
+        &["ls-files", "--others", "--exclude-standard", "--full-name"],
+    )?;
+
+    let files: BTreeSet<PathBuf> = diff
+        .lines()
+        .chain(untracked.lines())
+        .filter(|line| !line.is_empty() && !is_atlas_file(Path::new(line)))
+        .map(|line| toplevel.join(line))
+        .collect();
+    Ok(files.into_iter().collect())
+}
+
+fn git(project_root: &Path, args: &[&str]) -> OptimizerResult<String> {
+    let output = execute_command("git", args, Some(project_root))?;
+    if !output.status.success() {
+        return Err(OptimizerError::command_failed(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Workspace at /ws: `core`, `api` depends on `core`, `cli` dev-depends on `api`,
+    /// `tools` is unrelated. `serde` is a registry dependency.
+    fn workspace() -> CargoMetadata {
+        let package = |name: &str| {
+            format!(
+                r#"{{"id":"{0} 0.1.0 (path+file:///ws/{0})","name":"{0}","version":"0.1.0","manifest_path":"/ws/{0}/Cargo.toml"}}"#,
+                name
+            )
+        };
+        let id = |name: &str| format!("{0} 0.1.0 (path+file:///ws/{0})", name);
+        let dep = |name: &str, kind: &str| {
+            format!(
+                r#"{{"pkg":"{}","dep_kinds":[{{"kind":{}}}]}}"#,
+                id(name),
+                kind
+            )
+        };
+        let json = format!(
+            r#"{{
+                "packages": [{}, {}, {}, {}, {{"id":"serde 1.0.0","name":"serde","version":"1.0.0","manifest_path":"/registry/serde/Cargo.toml"}}],
+                "workspace_members": ["{}", "{}", "{}", "{}"],
+                "workspace_root": "/ws",
+                "target_directory": "/ws/target",
+                "resolve": {{"nodes": [
+                    {{"id":"{}","deps":[{}]}},
+                    {{"id":"{}","deps":[{}]}},
+                    {{"id":"{}","deps":[{{"pkg":"serde 1.0.0","dep_kinds":[{{"kind":null}}]}}]}}
+                ]}}
+            }}"#,
+            package("core"),
+            package("api"),
+            package("cli"),
+            package("tools"),
+            id("core"),
+            id("api"),
+            id("cli"),
+            id("tools"),
+            id("api"),
+            dep("core", "null"),
+            id("cli"),
+            dep("api", "\"dev\""),
+            id("core"),
+        );
+        CargoMetadata::parse(&json).unwrap()
+    }
+
+    fn names(set: &BTreeSet<String>) -> Vec<&str> {
+        set.iter().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn test_reverse_dependency_closure() {
+        let metadata = workspace();
+        let affected = Affected::resolve(&metadata, &[PathBuf::from("/ws/core/src/lib.rs")]);
+        assert_eq!(names(&affected.changed_members), vec!["core"]);
+        assert_eq!(
+            names(&affected.affected_members),
+            vec!["api", "cli", "core"]
+        );
+
+        let affected = Affected::resolve(
+            &metadata,
+            &[
+                PathBuf::from("/ws/tools/src/main.rs"),
+                PathBuf::from("/ws/README.md"),
+            ],
+        );
+        assert_eq!(names(&affected.affected_members), vec!["tools"]);
+        assert!(affected.workspace_wide.is_empty());
+        assert_eq!(affected.members_to_check(&metadata), vec!["tools"]);
+    }
+
+    #[test]
+    fn test_workspace_wide_changes() {
+        let metadata = workspace();
+        let affected = Affected::resolve(&metadata, &[PathBuf::from("/ws/Cargo.lock")]);
+        assert!(affected.affected_members.is_empty());
+        assert_eq!(affected.members_to_check(&metadata).len(), 4);
+
+        let affected = Affected::resolve(&metadata, &[PathBuf::from("/ws/docs/guide.md")]);
+        assert!(affected.members_to_check(&metadata).is_empty());
+    }
+
+    #[test]
+    fn test_changed_files_skip_atlas_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let git = |args: &[&str]| {
+            execute_command("git", args, Some(&root)).is_ok_and(|output| output.status.success())
+        };
+        std::fs::write(root.join("Cargo.toml"), "[package]\nname = \"app\"\n").unwrap();
+        if !(git(&["init", "-q"])
+            && git(&["add", "Cargo.toml"])
+            && git(&[
+                "-c",
+                "user.name=Atlas",
+                "-c",
+                "user.email=atlas@example.com",
+                "commit",
+                "-qm",
+                "init",
+            ]))
+        {
+            return;
+        }
+
+        std::fs::create_dir_all(root.join(".atlas")).unwrap();
+        std::fs::write(root.join(".atlas/history.jsonl"), "{}\n").unwrap();
+        std::fs::write(root.join("Cargo.toml.backup"), "").unwrap();
+        assert!(changed_files(&root, None).unwrap().is_empty());
+
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "").unwrap();
+        assert_eq!(
+            changed_files(&root, None).unwrap(),
+            vec![root.join("src/lib.rs")]
+        );
+    }
+}
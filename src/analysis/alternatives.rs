@@ -0,0 +1,123 @@
+//! Lighter crates to consider in place of dependencies that are slow to
+//! build, for `analyze cost`
+
+/// Share of the build a dependency has to account for before its
+/// alternatives are worth mentioning
+pub const DOMINANT_SHARE: f64 = 0.2;
+
+/// A lighter crate that covers some uses of a heavy one
+#[derive(Debug)]
+pub struct Alternative {
+    /// Crate it can replace
+    pub heavy: &'static str,
+    pub name: &'static str,
+    /// Uses it covers
+    pub suits: &'static str,
+    /// What is lost in the swap
+    pub caveat: &'static str,
+}
+
+pub const ALTERNATIVES: &[Alternative] = &[
+    Alternative {
+        heavy: "reqwest",
+        name: "ureq",
+        suits: "simple blocking requests, e.g. in CLIs and build tools",
+        caveat: "no async API; HTTP/2 and connection pooling across threads are limited",
+    },
+    Alternative {
+        heavy: "reqwest",
+        name: "minreq",
+        suits: "occasional requests with few options",
+        caveat: "no async, proxies or cookies; TLS is an optional feature",
+    },
+    Alternative {
+        heavy: "chrono",
+        name: "time",
+        suits: "timestamps, durations and formatting",
+        caveat: "different formatting and parsing API; no time zone database",
+    },
+    Alternative {
+        heavy: "chrono",
+        name: "jiff",
+        suits: "date arithmetic with time zones",
+        caveat: "newer crate with a different API; fewer integrations in other crates",
+    },
+    Alternative {
+        heavy: "clap",
+        name: "lexopt",
+        suits: "small command lines with a handful of options",
+        caveat: "parsing is written by hand; no generated help, validation or completions",
+    },
+    Alternative {
+        heavy: "clap",
+        name: "argh",
+        suits: "derive-based parsing of straightforward command lines",
+        caveat: "follows Fuchsia conventions; no short-flag grouping or completions",
+    },
+    Alternative {
+        heavy: "regex",
+        name: "regex-lite",
+        suits: "a few patterns on small inputs",
+        caveat: "slower matching and no Unicode character classes",
+    },
+    Alternative {
+        heavy: "serde_json",
+        name: "miniserde",
+        suits: "plain JSON data structures",
+        caveat: "own derives instead of serde's; no enums with data or borrowed strings",
+    },
+    Alternative {
+        heavy: "openssl",
+        name: "rustls",
+        suits: "TLS for clients and servers",
+        caveat: "builds ring or aws-lc-rs instead; no legacy protocols or system OpenSSL config",
+    },
+    Alternative {
+        heavy: "tokio",
+        name: "smol",
+        suits: "applications whose own code is the only async user",
+        caveat: "crates built on tokio, such as hyper, reqwest and tonic, still need it",
+    },
+    Alternative {
+        heavy: "futures",
+        name: "futures-lite",
+        suits: "common combinators, streams and block_on",
+        caveat: "no Sink, select! or join_all; some combinators are missing",
+    },
+    Alternative {
+        heavy: "rand",
+        name: "fastrand",
+        suits: "shuffling, sampling and test data",
+        caveat: "not cryptographically secure",
+    },
+];
+
+/// Lighter alternatives to the crate called `name`
+pub fn for_crate(name: &str) -> Vec<&'static Alternative> {
+    let name = name.replace('-', "_");
+    ALTERNATIVES
+        .iter()
+        .filter(|alternative| alternative.heavy.replace('-', "_") == name)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_crate() {
+        let names: Vec<&str> = for_crate("reqwest").iter().map(|a| a.name).collect();
+        assert_eq!(names, vec!["ureq", "minreq"]);
+        assert_eq!(for_crate("serde-json").len(), 1);
+        assert!(for_crate("ureq").is_empty());
+    }
+
+    #[test]
+    fn test_alternatives_have_caveats() {
+        for alternative in ALTERNATIVES {
+            assert_ne!(alternative.heavy, alternative.name);
+            assert!(!alternative.caveat.is_empty(), "{}", alternative.name);
+        }
+    }
+}
@@ -0,0 +1,357 @@
+use crate::analysis::metadata::{CargoMetadata, Package};
+use crate::analysis::outdated::is_crates_io;
+use crate::analysis::timings::TimingReport;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
+/// What a build script told cargo about when to rerun it, read from the
+/// `output` file cargo keeps in `target/<profile>/build/<crate>-<hash>/`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BuildScriptOutput {
+    pub package: String,
+    pub rerun_if_changed: Vec<PathBuf>,
+    pub rerun_if_env_changed: Vec<String>,
+}
+
+impl BuildScriptOutput {
+    /// Parse the directives of a build script run, in either the `cargo:`
+    /// or the `cargo::` syntax
+    pub fn parse(package: &str, output: &str) -> Self {
+        let mut parsed = Self {
+            package: package.to_string(),
+            ..Self::default()
+        };
+        for line in output.lines() {
+            let Some(directive) = line
+                .strip_prefix("cargo::")
+                .or_else(|| line.strip_prefix("cargo:"))
+            else {
+                continue;
+            };
+            if let Some(path) = directive.strip_prefix("rerun-if-changed=") {
+                parsed.rerun_if_changed.push(PathBuf::from(path.trim()));
+            } else if let Some(var) = directive.strip_prefix("rerun-if-env-changed=") {
+                parsed.rerun_if_env_changed.push(var.trim().to_string());
+            }
+        }
+        parsed
+    }
+
+    /// Without any rerun-if directive cargo reruns the script whenever any
+    /// file in its package changes
+    pub fn has_directives(&self) -> bool {
+        !self.rerun_if_changed.is_empty() || !self.rerun_if_env_changed.is_empty()
+    }
+}
+
+/// Why a build script reruns more often than its inputs change
+#[derive(Debug, Clone, PartialEq)]
+pub enum RerunCause {
+    /// No rerun-if directives: any edit in the package reruns it
+    NoDirectives,
+    /// Watches a path that does not exist, which cargo treats as always stale
+    MissingPath(PathBuf),
+    /// Watches a path that contains or lies in the target directory, which
+    /// every build writes to
+    WatchesTarget(PathBuf),
+}
+
+impl std::fmt::Display for RerunCause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoDirectives => write!(f, "no rerun-if directives; any file change reruns it"),
+            Self::MissingPath(path) => {
+                write!(f, "rerun-if-changed={} does not exist", path.display())
+            }
+            Self::WatchesTarget(path) => write!(
+                f,
+                "rerun-if-changed={} covers the target directory",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl RerunCause {
+    /// Whether the script reruns on every build, not only after edits
+    pub fn is_every_build(&self) -> bool {
+        !matches!(self, Self::NoDirectives)
+    }
+}
+
+/// Causes of needless reruns in a build script's directives. Missing
+/// directives only matter for local packages, whose files get edited.
+pub fn rerun_causes(
+    output: &BuildScriptOutput,
+    package_root: &Path,
+    target_dir: &Path,
+    is_local: bool,
+) -> Vec<RerunCause> {
+    let mut causes = Vec::new();
+    if !output.has_directives() && is_local {
+        causes.push(RerunCause::NoDirectives);
+    }
+    for watched in &output.rerun_if_changed {
+        let path = package_root.join(watched);
+        if !path.exists() {
+            causes.push(RerunCause::MissingPath(watched.clone()));
+        } else if target_dir.starts_with(&path) || path.starts_with(target_dir) {
+            causes.push(RerunCause::WatchesTarget(watched.clone()));
+        }
+    }
+    causes
+}
+
+/// The newest build script output of each crate under a target directory,
+/// across profiles (`target/<profile>/build`) and cross-compilation targets
+/// (`target/<triple>/<profile>/build`)
+pub fn load_outputs(target_dir: &Path) -> Vec<BuildScriptOutput> {
+    let subdirs = |dir: &Path| -> Vec<PathBuf> {
+        fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .filter_map(Result::ok)
+                    .map(|entry| entry.path())
+                    .filter(|path| path.is_dir())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let build_dirs = subdirs(target_dir)
+        .into_iter()
+        .flat_map(|dir| {
+            let mut nested = subdirs(&dir);
+            nested.push(dir);
+            nested
+        })
+        .map(|dir| dir.join("build"));
+
+    let mut newest: HashMap<String, (SystemTime, BuildScriptOutput)> = HashMap::new();
+    for run_dir in build_dirs.flat_map(|dir| subdirs(&dir)) {
+        let Some(package) = run_dir
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(strip_hash)
+        else {
+            continue;
+        };
+        // The directory holding the compiled script has no output
+        let path = run_dir.join("output");
+        let (Ok(content), Ok(modified)) = (
+            fs::read_to_string(&path),
+            fs::metadata(&path).and_then(|metadata| metadata.modified()),
+        ) else {
+            continue;
+        };
+        if newest
+            .get(package)
+            .map_or(true, |(seen, _)| modified > *seen)
+        {
+            newest.insert(
+                package.to_string(),
+                (modified, BuildScriptOutput::parse(package, &content)),
+            );
+        }
+    }
+
+    let mut outputs: Vec<_> = newest.into_values().map(|(_, output)| output).collect();
+    outputs.sort_by(|a, b| a.package.cmp(&b.package));
+    outputs
+}
+
+/// Crate name of a build directory such as `openssl-sys-0a1b2c3d4e5f6789`
+fn strip_hash(dir_name: &str) -> Option<&str> {
+    let (name, hash) = dir_name.rsplit_once('-')?;
+    (hash.len() == 16 && hash.chars().all(|c| c.is_ascii_hexdigit())).then_some(name)
+}
+
+/// Crates whose build scripts `cargo build -v` reports running
+pub fn scripts_run(verbose_output: &str) -> HashSet<String> {
+    static RUNNING: OnceLock<Regex> = OnceLock::new();
+    let running = RUNNING.get_or_init(|| {
+        Regex::new(r"[/\\]build[/\\]([^/\\\s`]+)[/\\]build-script-build").expect("valid regex")
+    });
+    verbose_output
+        .lines()
+        .filter(|line| line.trim_start().starts_with("Running"))
+        .filter_map(|line| running.captures(line))
+        .filter_map(|captures| strip_hash(&captures[1]).map(str::to_string))
+        .collect()
+}
+
+/// A build script and what its reruns cost
+#[derive(Debug, Clone)]
+pub struct BuildScriptReport {
+    pub package: String,
+    pub version: String,
+    /// Manifest of a local crate, its crates.io page or its git repository
+    pub link: String,
+    /// Seconds the script ran in the last timings report
+    pub run_time: Option<f64>,
+    pub causes: Vec<RerunCause>,
+    /// Whether it ran again on a build with nothing changed, when checked
+    pub reran: Option<bool>,
+}
+
+impl BuildScriptReport {
+    /// Whether the script reruns more often than its inputs change
+    pub fn is_offending(&self) -> bool {
+        !self.causes.is_empty() || self.reran == Some(true)
+    }
+}
+
+/// Every build script of the workspace's crates that has run, with the
+/// causes of needless reruns, the time each run takes and, given the crates
+/// whose scripts ran on a no-op build, whether it reran
+pub fn analyze(
+    metadata: &CargoMetadata,
+    report: Option<&TimingReport>,
+    reran: Option<&HashSet<String>>,
+) -> Vec<BuildScriptReport> {
+    let mut run_times: HashMap<String, f64> = HashMap::new();
+    for unit in report
+        .map(|report| report.units.as_slice())
+        .unwrap_or_default()
+    {
+        if unit.is_build_script_run() {
+            *run_times.entry(unit.name.replace('-', "_")).or_insert(0.0) += unit.duration;
+        }
+    }
+    let packages: HashMap<String, &Package> = metadata
+        .packages
+        .iter()
+        .filter(|package| {
+            package
+                .targets
+                .iter()
+                .any(|target| target.kind.iter().any(|kind| kind == "custom-build"))
+        })
+        .map(|package| (package.name.replace('-', "_"), package))
+        .collect();
+
+    let mut reports: Vec<_> = load_outputs(&metadata.target_directory)
+        .into_iter()
+        .filter_map(|output| {
+            let package = packages.get(&output.package.replace('-', "_"))?;
+            let package_root = package.manifest_path.parent()?;
+            let is_local = package.source.is_none();
+            Some(BuildScriptReport {
+                package: package.name.clone(),
+                version: package.version.clone(),
+                link: link(package),
+                run_time: run_times.get(&output.package.replace('-', "_")).copied(),
+                causes: rerun_causes(&output, package_root, &metadata.target_directory, is_local),
+                reran: reran.map(|reran| reran.contains(&output.package.replace('-', "_"))),
+            })
+        })
+        .collect();
+    reports.sort_by(|a, b| {
+        b.is_offending()
+            .cmp(&a.is_offending())
+            .then_with(|| {
+                b.run_time
+                    .unwrap_or(0.0)
+                    .total_cmp(&a.run_time.unwrap_or(0.0))
+            })
+            .then_with(|| a.package.cmp(&b.package))
+    });
+    reports
+}
+
+/// Where to look at a crate's build script
+fn link(package: &Package) -> String {
+    match package.source.as_deref() {
+        None => package.manifest_path.display().to_string(),
+        Some(source) if is_crates_io(Some(source)) => format!(
+            "https://crates.io/crates/{}/{}",
+            package.name, package.version
+        ),
+        Some(source) => source
+            .trim_start_matches("git+")
+            .split(['?', '#'])
+            .next()
+            .unwrap_or(source)
+            .to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_directives() {
+        let output = BuildScriptOutput::parse(
+            "app",
+            "cargo:rerun-if-changed=build.rs\ncargo::rerun-if-env-changed=CC\ncargo:rustc-link-lib=z\n",
+        );
+        assert_eq!(output.rerun_if_changed, vec![PathBuf::from("build.rs")]);
+        assert_eq!(output.rerun_if_env_changed, vec!["CC".to_string()]);
+        assert!(output.has_directives());
+        assert!(!BuildScriptOutput::parse("app", "cargo:rustc-cfg=x\n").has_directives());
+    }
+
+    #[test]
+    fn test_rerun_causes() {
+        let root = tempfile::tempdir().unwrap();
+        fs::write(root.path().join("build.rs"), "").unwrap();
+        let target = root.path().join("target");
+        fs::create_dir(&target).unwrap();
+
+        let output = BuildScriptOutput::parse(
+            "app",
+            "cargo:rerun-if-changed=build.rs\ncargo:rerun-if-changed=proto\ncargo:rerun-if-changed=.\n",
+        );
+        assert_eq!(
+            rerun_causes(&output, root.path(), &target, true),
+            vec![
+                RerunCause::MissingPath(PathBuf::from("proto")),
+                RerunCause::WatchesTarget(PathBuf::from(".")),
+            ]
+        );
+
+        let silent = BuildScriptOutput::parse("app", "");
+        assert_eq!(
+            rerun_causes(&silent, root.path(), &target, true),
+            vec![RerunCause::NoDirectives]
+        );
+        assert!(rerun_causes(&silent, root.path(), &target, false).is_empty());
+    }
+
+    #[test]
+    fn test_load_outputs_keeps_crate_names() {
+        let target = tempfile::tempdir().unwrap();
+        let run_dir = target
+            .path()
+            .join("debug")
+            .join("build")
+            .join("openssl-sys-0a1b2c3d4e5f6789");
+        fs::create_dir_all(&run_dir).unwrap();
+        fs::write(
+            run_dir.join("output"),
+            "cargo:rerun-if-env-changed=OPENSSL_DIR\n",
+        )
+        .unwrap();
+        // The directory of the compiled script has no output
+        fs::create_dir_all(
+            target
+                .path()
+                .join("debug/build/openssl-sys-1111111111111111"),
+        )
+        .unwrap();
+
+        let outputs = load_outputs(target.path());
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(outputs[0].package, "openssl-sys");
+    }
+
+    #[test]
+    fn test_scripts_run() {
+        let output = "     Running `/p/target/debug/build/app-0123456789abcdef/build-script-build`\n   Compiling app v0.1.0\n     Running `rustc --crate-name app src/main.rs`\n";
+        assert_eq!(scripts_run(output), HashSet::from(["app".to_string()]));
+    }
+}
@@ -0,0 +1,309 @@
+//! What a dependency costs the build: its own compile time, the crates it
+//! pulls in, and the build time saved by removing it or trimming its features
+
+use crate::analysis::critical_path::ScheduleModel;
+use crate::analysis::graph::DependencyGraph;
+use crate::analysis::metadata::CargoMetadata;
+use crate::analysis::timings::TimingReport;
+use crate::error::{OptimizerError, OptimizerResult};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// An optional dependency a feature turned on
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptionalDependency {
+    pub name: String,
+    /// Compile time of the crates only built because of it
+    pub exclusive_time: f64,
+}
+
+/// What a removal would save
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Savings {
+    /// Crates that would no longer be built
+    pub crates: usize,
+    /// Compile time of those crates, summed
+    pub work: f64,
+    /// Wall-clock savings projected from the build schedule
+    pub wall_time: Option<f64>,
+}
+
+/// Cost of one dependency, across every version of it in the graph
+#[derive(Debug, Clone)]
+pub struct DependencyCost {
+    pub name: String,
+    pub versions: Vec<String>,
+    /// Compile time of the crate itself
+    pub own_time: f64,
+    /// Transitive dependencies, excluding the crate itself
+    pub transitive: usize,
+    pub transitive_time: f64,
+    /// Packages that depend on it directly
+    pub dependents: Vec<String>,
+    pub features: Vec<String>,
+    pub optional: Vec<OptionalDependency>,
+    /// Removing the dependency altogether
+    pub removal: Savings,
+    /// Turning off every optional dependency it has enabled
+    pub trimming: Savings,
+    /// Recorded wall time of the build the times come from
+    pub wall_time: Option<f64>,
+    /// Whether the timings report covers the crate
+    pub timed: bool,
+}
+
+impl DependencyCost {
+    /// Share of the recorded build that removing the dependency would save
+    pub fn removal_share(&self) -> Option<f64> {
+        let wall_time = self.wall_time.filter(|time| *time > 0.0)?;
+        Some(self.removal.wall_time? / wall_time)
+    }
+}
+
+/// Cost of the dependency called `name` in the workspace's build graph
+pub fn analyze(
+    metadata: &CargoMetadata,
+    report: Option<&TimingReport>,
+    jobs: usize,
+    name: &str,
+) -> OptimizerResult<DependencyCost> {
+    let graph = metadata.dependency_graph(false);
+    let targets: Vec<usize> = (0..graph.nodes.len())
+        .filter(|&index| graph.nodes[index].name == name)
+        .collect();
+    if targets.is_empty() {
+        return Err(OptimizerError::invalid_input(format!(
+            "'{}' is not built as part of this workspace",
+            name
+        )));
+    }
+    let target_set: HashSet<usize> = targets.iter().copied().collect();
+
+    let times = report.map(TimingReport::crate_times).unwrap_or_default();
+    let time_of = |nodes: &HashSet<usize>| -> f64 {
+        nodes
+            .iter()
+            .filter_map(|&node| times.get(&graph.nodes[node].key()))
+            .sum()
+    };
+
+    let mut closure = graph.dependency_closure(&targets);
+    closure.retain(|node| !target_set.contains(node));
+
+    let dependents: BTreeSet<String> = graph
+        .edges
+        .iter()
+        .filter(|(from, to)| target_set.contains(to) && !target_set.contains(from))
+        .map(|(from, _)| graph.nodes[*from].name.clone())
+        .collect();
+
+    // Optional dependencies show up as edges to packages the manifest marks optional
+    let mut optional_edges = Vec::new();
+    let mut features = BTreeSet::new();
+    for &target in &targets {
+        let Some(package) = metadata.package(&graph.nodes[target].id) else {
+            continue;
+        };
+        features.extend(metadata.enabled_features(&package.id));
+        let optional: HashSet<&str> = package
+            .dependencies
+            .iter()
+            .filter(|dependency| dependency.optional)
+            .map(|dependency| dependency.name.as_str())
+            .collect();
+        for to in graph.dependencies(target) {
+            if optional.contains(graph.nodes[to].name.as_str()) {
+                optional_edges.push((target, to));
+            }
+        }
+    }
+    let mut optional: Vec<OptionalDependency> = optional_edges
+        .iter()
+        .map(|&(from, to)| OptionalDependency {
+            name: graph.nodes[to].name.clone(),
+            exclusive_time: time_of(
+                &graph.unreachable_without(|cut_from, cut_to| (cut_from, cut_to) == (from, to)),
+            ),
+        })
+        .collect();
+    optional.sort_by(|a, b| {
+        b.exclusive_time
+            .total_cmp(&a.exclusive_time)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    optional.dedup_by(|a, b| a.name == b.name);
+
+    let model = report.and_then(|report| ScheduleModel::new(report, jobs).ok());
+    let savings = |removed: HashSet<usize>| Savings {
+        crates: removed.len(),
+        work: time_of(&removed),
+        wall_time: model.as_ref().zip(report).map(|(model, report)| {
+            let keys: HashSet<String> = removed
+                .iter()
+                .map(|&node| graph.nodes[node].key())
+                .collect();
+            let positions: Vec<usize> = report
+                .units
+                .iter()
+                .enumerate()
+                .filter(|(_, unit)| keys.contains(&unit.key()))
+                .map(|(position, _)| position)
+                .collect();
+            (model.lower_bound() - model.lower_bound_without(&positions)).max(0.0)
+        }),
+    };
+
+    Ok(DependencyCost {
+        name: name.to_string(),
+        versions: targets
+            .iter()
+            .map(|&target| graph.nodes[target].version.clone())
+            .collect(),
+        own_time: time_of(&target_set),
+        transitive: closure.len(),
+        transitive_time: time_of(&closure),
+        dependents: dependents.into_iter().collect(),
+        features: features.into_iter().collect(),
+        optional,
+        removal: savings(graph.unreachable_without(|_, to| target_set.contains(&to))),
+        trimming: savings(
+            graph.unreachable_without(|from, to| optional_edges.contains(&(from, to))),
+        ),
+        wall_time: report.map(TimingReport::wall_time),
+        timed: targets
+            .iter()
+            .any(|&target| times.contains_key(&graph.nodes[target].key())),
+    })
+}
+
+/// Crates ranked by how much build time removing each would save, for
+/// choosing what to look at with `analyze cost`
+pub fn heaviest(
+    graph: &DependencyGraph,
+    times: &HashMap<String, f64>,
+    count: usize,
+) -> Vec<(String, f64)> {
+    let mut ranked: Vec<(String, f64)> = (0..graph.nodes.len())
+        .filter(|&index| !graph.nodes[index].is_member)
+        .map(|index| {
+            let removed = graph.unreachable_without(|_, to| to == index);
+            let work = removed
+                .iter()
+                .filter_map(|&node| times.get(&graph.nodes[node].key()))
+                .sum();
+            (graph.nodes[index].name.clone(), work)
+        })
+        .filter(|(_, work)| *work > 0.0)
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked.truncate(count);
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `app` depends on `reqwest` and `bytes`; `reqwest` depends on `hyper`,
+    /// which depends on `bytes`, and on the optional `cookie_store`
+    fn workspace() -> CargoMetadata {
+        let package = |name: &str, dependencies: &str| {
+            format!(
+                r#"{{"id":"{0} 1.0.0","name":"{0}","version":"1.0.0","dependencies":[{1}]}}"#,
+                name, dependencies
+            )
+        };
+        let dep = |name: &str| {
+            format!(
+                r#"{{"pkg":"{} 1.0.0","dep_kinds":[{{"kind":null}}]}}"#,
+                name
+            )
+        };
+        let json = format!(
+            r#"{{
+                "packages": [{}, {}, {}, {}, {}],
+                "workspace_members": ["app 1.0.0"],
+                "target_directory": "/ws/target",
+                "resolve": {{"nodes": [
+                    {{"id":"app 1.0.0","deps":[{},{}]}},
+                    {{"id":"reqwest 1.0.0","deps":[{},{}],"features":["cookies","default"]}},
+                    {{"id":"hyper 1.0.0","deps":[{}]}}
+                ]}}
+            }}"#,
+            package("app", ""),
+            package(
+                "reqwest",
+                r#"{"name":"hyper"},{"name":"cookie_store","optional":true}"#
+            ),
+            package("hyper", ""),
+            package("bytes", ""),
+            package("cookie_store", ""),
+            dep("reqwest"),
+            dep("bytes"),
+            dep("hyper"),
+            dep("cookie_store"),
+            dep("bytes"),
+        );
+        CargoMetadata::parse(&json).unwrap()
+    }
+
+    const HTML: &str = r#"const UNIT_DATA = [
+      {"i": 0, "name": "bytes", "version": "1.0.0", "target": "", "start": 0.0, "duration": 1.0,
+       "unblocked_units": [1, 4], "unblocked_rmeta_units": []},
+      {"i": 1, "name": "hyper", "version": "1.0.0", "target": "", "start": 1.0, "duration": 4.0,
+       "unblocked_units": [3], "unblocked_rmeta_units": []},
+      {"i": 2, "name": "cookie_store", "version": "1.0.0", "target": "", "start": 0.0, "duration": 2.0,
+       "unblocked_units": [3], "unblocked_rmeta_units": []},
+      {"i": 3, "name": "reqwest", "version": "1.0.0", "target": "", "start": 5.0, "duration": 3.0,
+       "unblocked_units": [4], "unblocked_rmeta_units": []},
+      {"i": 4, "name": "app", "version": "1.0.0", "target": " bin", "start": 8.0, "duration": 1.0,
+       "unblocked_units": [], "unblocked_rmeta_units": []}
+    ];"#;
+
+    #[test]
+    fn test_dependency_cost() {
+        let report = TimingReport::parse_html(HTML).unwrap();
+        let cost = analyze(&workspace(), Some(&report), 4, "reqwest").unwrap();
+
+        assert_eq!(cost.own_time, 3.0);
+        assert_eq!(cost.transitive, 3);
+        assert_eq!(cost.transitive_time, 7.0);
+        assert_eq!(cost.dependents, vec!["app".to_string()]);
+        assert_eq!(
+            cost.features,
+            vec!["cookies".to_string(), "default".to_string()]
+        );
+
+        // bytes stays: app uses it directly
+        assert_eq!(cost.removal.crates, 3);
+        assert_eq!(cost.removal.work, 9.0);
+        assert_eq!(cost.removal.wall_time, Some(7.0));
+        assert_eq!(cost.removal_share(), Some(7.0 / 9.0));
+
+        assert_eq!(
+            cost.optional,
+            vec![OptionalDependency {
+                name: "cookie_store".to_string(),
+                exclusive_time: 2.0,
+            }]
+        );
+        // cookie_store built in parallel with hyper, off the critical path
+        assert_eq!(cost.trimming.work, 2.0);
+        assert_eq!(cost.trimming.wall_time, Some(0.0));
+    }
+
+    #[test]
+    fn test_heaviest() {
+        let report = TimingReport::parse_html(HTML).unwrap();
+        let graph = workspace().dependency_graph(false);
+        let ranked = heaviest(&graph, &report.crate_times(), 2);
+        assert_eq!(
+            ranked,
+            vec![("reqwest".to_string(), 9.0), ("hyper".to_string(), 4.0)]
+        );
+    }
+
+    #[test]
+    fn test_unknown_dependency() {
+        assert!(analyze(&workspace(), None, 4, "tokio").is_err());
+    }
+}
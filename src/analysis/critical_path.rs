@@ -0,0 +1,296 @@
+use crate::analysis::timings::TimingReport;
+use crate::error::{OptimizerError, OptimizerResult};
+use std::collections::HashMap;
+
+/// Critical path and parallelism bounds of a recorded build
+#[derive(Debug, Clone)]
+pub struct CriticalPathAnalysis {
+    /// Wall-clock time of the recorded build
+    pub wall_time: f64,
+    /// Length of the longest dependency chain, i.e. the build time with unlimited cores
+    pub critical_path_time: f64,
+    /// Sum of all unit durations
+    pub total_work: f64,
+    pub jobs: usize,
+    /// Units on the critical path in build order
+    pub path: Vec<PathStep>,
+    /// Critical path units ranked by how much faster the build could get
+    pub opportunities: Vec<Opportunity>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PathStep {
+    pub unit: String,
+    pub start: f64,
+    pub duration: f64,
+    pub codegen: Option<f64>,
+}
+
+/// Estimated wall-clock savings from speeding up a single unit
+#[derive(Debug, Clone)]
+pub struct Opportunity {
+    pub unit: String,
+    pub duration: f64,
+    /// Savings if the unit compiled twice as fast
+    pub halved_savings: f64,
+    /// Savings if the unit took no time at all
+    pub max_savings: f64,
+}
+
+impl CriticalPathAnalysis {
+    /// Time needed to get through all work with `jobs` cores
+    pub fn work_bound(&self) -> f64 {
+        self.total_work / self.jobs.max(1) as f64
+    }
+
+    /// Theoretical minimum build time at the current parallelism
+    pub fn lower_bound(&self) -> f64 {
+        self.critical_path_time.max(self.work_bound())
+    }
+
+    /// Whether dependencies rather than core count limit the build
+    pub fn is_dependency_bound(&self) -> bool {
+        self.critical_path_time >= self.work_bound()
+    }
+}
+
+/// Edge into a unit: the predecessor and the fraction of its duration after
+/// which the unit could start (1.0 for full builds, less for metadata)
+type Edge = (usize, f64);
+
+struct Schedule {
+    finish: Vec<f64>,
+    via: Vec<Option<usize>>,
+}
+
+/// Dependency schedule reconstructed from a timings report, used to project
+/// how the build time changes when individual units get faster
+pub struct ScheduleModel {
+    order: Vec<usize>,
+    incoming: Vec<Vec<Edge>>,
+    durations: Vec<f64>,
+    jobs: usize,
+}
+
+impl ScheduleModel {
+    pub fn new(report: &TimingReport, jobs: usize) -> OptimizerResult<Self> {
+        if report.units.is_empty() || !report.has_schedule() {
+            return Err(OptimizerError::invalid_input(
+                "Timing report has no scheduling data. Regenerate it with `cargo build --timings`.",
+            ));
+        }
+
+        let units = &report.units;
+        let index: HashMap<usize, usize> = units
+            .iter()
+            .enumerate()
+            .map(|(position, unit)| (unit.id, position))
+            .collect();
+
+        let mut incoming: Vec<Vec<Edge>> = vec![Vec::new(); units.len()];
+        for (position, unit) in units.iter().enumerate() {
+            let rmeta_fraction = if unit.duration > 0.0 {
+                (unit.metadata_ready() / unit.duration).clamp(0.0, 1.0)
+            } else {
+                1.0
+            };
+            for id in &unit.unblocked_units {
+                if let Some(&next) = index.get(id) {
+                    incoming[next].push((position, 1.0));
+                }
+            }
+            for id in &unit.unblocked_rmeta_units {
+                if let Some(&next) = index.get(id) {
+                    incoming[next].push((position, rmeta_fraction));
+                }
+            }
+        }
+
+        // Units were unblocked by units that started earlier, so start order is topological
+        let mut order: Vec<usize> = (0..units.len()).collect();
+        order.sort_by(|&a, &b| units[a].start.total_cmp(&units[b].start));
+
+        Ok(Self {
+            order,
+            incoming,
+            durations: units.iter().map(|unit| unit.duration).collect(),
+            jobs: jobs.max(1),
+        })
+    }
+
+    pub fn total_work(&self) -> f64 {
+        self.durations.iter().sum()
+    }
+
+    fn bound(&self, path_time: f64, work: f64) -> f64 {
+        path_time.max(work / self.jobs as f64)
+    }
+
+    /// Lower bound on build time as recorded
+    pub fn lower_bound(&self) -> f64 {
+        let path_time = longest(&schedule(&self.order, &self.incoming, &self.durations));
+        self.bound(path_time, self.total_work())
+    }
+
+    /// Lower bound if the unit at `position` took `duration` seconds of its
+    /// own chain and the total work changed by `work_delta`
+    pub fn lower_bound_with(&self, position: usize, duration: f64, work_delta: f64) -> f64 {
+        let mut durations = self.durations.clone();
+        durations[position] = duration;
+        let path_time = longest(&schedule(&self.order, &self.incoming, &durations));
+        self.bound(path_time, self.total_work() + work_delta)
+    }
+
+    /// Lower bound if the units at `positions` were not built at all
+    pub fn lower_bound_without(&self, positions: &[usize]) -> f64 {
+        let mut durations = self.durations.clone();
+        let mut work_delta = 0.0;
+        for &position in positions {
+            work_delta -= durations[position];
+            durations[position] = 0.0;
+        }
+        let path_time = longest(&schedule(&self.order, &self.incoming, &durations));
+        self.bound(path_time, self.total_work() + work_delta)
+    }
+
+    /// Wall-clock savings if the unit at `position` compiled `factor` times as long
+    pub fn savings(&self, position: usize, factor: f64) -> f64 {
+        let duration = self.durations[position] * factor;
+        self.lower_bound()
+            - self.lower_bound_with(position, duration, duration - self.durations[position])
+    }
+}
+
+/// Compute the critical path of a timings report for a given job count
+pub fn analyze(report: &TimingReport, jobs: usize) -> OptimizerResult<CriticalPathAnalysis> {
+    let model = ScheduleModel::new(report, jobs)?;
+    let units = &report.units;
+    let baseline = schedule(&model.order, &model.incoming, &model.durations);
+
+    let path = trace_path(&baseline);
+
+    let mut opportunities: Vec<Opportunity> = path
+        .iter()
+        .map(|&position| Opportunity {
+            unit: units[position].label(),
+            duration: units[position].duration,
+            halved_savings: model.savings(position, 0.5),
+            max_savings: model.savings(position, 0.0),
+        })
+        .filter(|opportunity| opportunity.max_savings > 0.0)
+        .collect();
+    opportunities.sort_by(|a, b| b.max_savings.total_cmp(&a.max_savings));
+
+    Ok(CriticalPathAnalysis {
+        wall_time: report.wall_time(),
+        critical_path_time: longest(&baseline),
+        total_work: model.total_work(),
+        jobs: model.jobs,
+        path: path
+            .into_iter()
+            .map(|position| {
+                let unit = &units[position];
+                PathStep {
+                    unit: unit.label(),
+                    start: unit.start,
+                    duration: unit.duration,
+                    codegen: unit.section_time("codegen"),
+                }
+            })
+            .collect(),
+        opportunities,
+    })
+}
+
+fn longest(schedule: &Schedule) -> f64 {
+    schedule.finish.iter().copied().fold(0.0, f64::max)
+}
+
+/// Earliest finish time of every unit assuming unlimited parallelism
+fn schedule(order: &[usize], incoming: &[Vec<Edge>], durations: &[f64]) -> Schedule {
+    let mut finish = vec![0.0; durations.len()];
+    let mut via = vec![None; durations.len()];
+
+    for &unit in order {
+        let mut start = 0.0;
+        for &(pred, fraction) in &incoming[unit] {
+            let ready = finish[pred] - durations[pred] * (1.0 - fraction);
+            if ready > start || via[unit].is_none() {
+                start = f64::max(start, ready);
+                via[unit] = Some(pred);
+            }
+        }
+        finish[unit] = start + durations[unit];
+    }
+
+    Schedule { finish, via }
+}
+
+/// Units on the longest chain, from the first to the last
+fn trace_path(schedule: &Schedule) -> Vec<usize> {
+    let Some(mut current) = (0..schedule.finish.len())
+        .max_by(|&a, &b| schedule.finish[a].total_cmp(&schedule.finish[b]))
+    else {
+        return Vec::new();
+    };
+
+    let mut path = vec![current];
+    while let Some(pred) = schedule.via[current] {
+        path.push(pred);
+        current = pred;
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a (4s) -> b (2s) -> d (3s); a's metadata unblocks e (1s); c (1s) is independent
+    const HTML: &str = r#"const UNIT_DATA = [
+      {"i": 0, "name": "a", "version": "1.0.0", "target": "", "start": 0.0, "duration": 4.0,
+       "unblocked_units": [1], "unblocked_rmeta_units": [4],
+       "sections": [["frontend", {"start": 0.0, "end": 1.0}], ["codegen", {"start": 1.0, "end": 4.0}]]},
+      {"i": 1, "name": "b", "version": "1.0.0", "target": "", "start": 4.0, "duration": 2.0,
+       "unblocked_units": [3], "unblocked_rmeta_units": []},
+      {"i": 2, "name": "c", "version": "1.0.0", "target": "", "start": 0.0, "duration": 1.0,
+       "unblocked_units": [], "unblocked_rmeta_units": []},
+      {"i": 3, "name": "d", "version": "1.0.0", "target": " bin", "start": 6.0, "duration": 3.0,
+       "unblocked_units": [], "unblocked_rmeta_units": []},
+      {"i": 4, "name": "e", "version": "1.0.0", "target": "", "start": 1.0, "duration": 1.0,
+       "unblocked_units": [], "unblocked_rmeta_units": []}
+    ];"#;
+
+    #[test]
+    fn test_critical_path() {
+        let report = TimingReport::parse_html(HTML).unwrap();
+        let analysis = analyze(&report, 2).unwrap();
+
+        let path: Vec<&str> = analysis
+            .path
+            .iter()
+            .map(|step| step.unit.as_str())
+            .collect();
+        assert_eq!(path, vec!["a", "b", "d bin"]);
+        assert_eq!(analysis.critical_path_time, 9.0);
+        assert_eq!(analysis.wall_time, 9.0);
+        assert_eq!(analysis.total_work, 11.0);
+        assert_eq!(analysis.lower_bound(), 9.0);
+        assert!(analysis.is_dependency_bound());
+
+        let top = &analysis.opportunities[0];
+        assert_eq!(top.unit, "a");
+        assert_eq!(top.halved_savings, 2.0);
+        // Once `a` is free, the work bound (7s / 2 jobs) caps the savings
+        assert_eq!(top.max_savings, 4.0);
+    }
+
+    #[test]
+    fn test_requires_schedule() {
+        let report = TimingReport::parse_json_lines(
+            r#"{"reason":"timing-info","package_id":"a 1.0.0 (path+file:///a)","duration":1.0}"#,
+        );
+        assert!(analyze(&report, 4).is_err());
+    }
+}
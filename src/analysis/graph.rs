@@ -0,0 +1,315 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Write;
+
+/// A package in the resolved dependency graph
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphNode {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub is_member: bool,
+}
+
+impl GraphNode {
+    /// Key used to join graph nodes with timing data
+    pub fn key(&self) -> String {
+        crate_key(&self.name, &self.version)
+    }
+}
+
+/// Join key for a crate name and version
+pub fn crate_key(name: &str, version: &str) -> String {
+    format!("{} {}", name, version)
+}
+
+/// Directed dependency graph; an edge `(a, b)` means `a` depends on `b`
+#[derive(Debug, Clone, Default)]
+pub struct DependencyGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<(usize, usize)>,
+}
+
+impl DependencyGraph {
+    /// Direct dependencies of a node
+    pub fn dependencies(&self, node: usize) -> Vec<usize> {
+        self.edges
+            .iter()
+            .filter(|(from, _)| *from == node)
+            .map(|(_, to)| *to)
+            .collect()
+    }
+
+    /// All nodes reachable from `start` following `next`, including `start`
+    fn closure(&self, start: &[usize], next: impl Fn(usize) -> Vec<usize>) -> HashSet<usize> {
+        let mut seen: HashSet<usize> = start.iter().copied().collect();
+        let mut queue: VecDeque<usize> = start.iter().copied().collect();
+
+        while let Some(node) = queue.pop_front() {
+            for neighbour in next(node) {
+                if seen.insert(neighbour) {
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+
+        seen
+    }
+
+    /// Transitive dependencies of `nodes`, including the nodes themselves
+    pub fn dependency_closure(&self, nodes: &[usize]) -> HashSet<usize> {
+        self.closure(nodes, |node| self.dependencies(node))
+    }
+
+    /// Nodes that transitively depend on `nodes`, including the nodes themselves:
+    /// everything rebuilt when they change
+    pub fn dependents_closure(&self, nodes: &[usize]) -> HashSet<usize> {
+        self.closure(nodes, |node| {
+            self.edges
+                .iter()
+                .filter(|(_, to)| *to == node)
+                .map(|(from, _)| *from)
+                .collect()
+        })
+    }
+
+    /// Nodes reachable from workspace members that stop being reachable once
+    /// the edges for which `cut(from, to)` holds are removed: what would no
+    /// longer be built
+    pub fn unreachable_without(&self, cut: impl Fn(usize, usize) -> bool) -> HashSet<usize> {
+        let members = self.member_indices();
+        let before = self.dependency_closure(&members);
+        let after = self.closure(&members, |node| {
+            self.dependencies(node)
+                .into_iter()
+                .filter(|&to| !cut(node, to))
+                .collect()
+        });
+        before.difference(&after).copied().collect()
+    }
+
+    fn member_indices(&self) -> Vec<usize> {
+        (0..self.nodes.len())
+            .filter(|&index| self.nodes[index].is_member)
+            .collect()
+    }
+
+    /// Keep only the nodes reachable from workspace members
+    pub fn reachable_from_members(&self) -> Self {
+        let keep = self.dependency_closure(&self.member_indices());
+        self.retain(|index| keep.contains(&index))
+    }
+
+    /// Keep only workspace members and the edges between them
+    pub fn members_only(&self) -> Self {
+        self.retain(|index| self.nodes[index].is_member)
+    }
+
+    fn retain(&self, keep: impl Fn(usize) -> bool) -> Self {
+        let mut remap = HashMap::new();
+        let mut graph = Self::default();
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            if keep(index) {
+                remap.insert(index, graph.nodes.len());
+                graph.nodes.push(node.clone());
+            }
+        }
+        for (from, to) in &self.edges {
+            if let (Some(&from), Some(&to)) = (remap.get(from), remap.get(to)) {
+                if !graph.edges.contains(&(from, to)) {
+                    graph.edges.push((from, to));
+                }
+            }
+        }
+
+        graph
+    }
+
+    /// Render as Graphviz DOT, shading nodes by compile time
+    pub fn render_dot(&self, times: &HashMap<String, f64>) -> String {
+        let max_time = max_time(times);
+        let mut out = String::new();
+
+        out.push_str("digraph workspace {\n");
+        out.push_str("    rankdir=LR;\n");
+        out.push_str("    node [shape=box, style=\"rounded,filled\", fontname=\"Helvetica\"];\n");
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            let time = times.get(&node.key()).copied();
+            let label = match time {
+                Some(time) => format!("{} {}\\n{:.1}s", node.name, node.version, time),
+                None => format!("{} {}", node.name, node.version),
+            };
+            let color = heat(time, max_time).dot_color();
+            let border = if node.is_member { ", penwidth=2" } else { "" };
+            let _ = writeln!(
+                out,
+                "    n{} [label=\"{}\", fillcolor=\"{}\"{}];",
+                index, label, color, border
+            );
+        }
+
+        for (from, to) in &self.edges {
+            let _ = writeln!(out, "    n{} -> n{};", from, to);
+        }
+
+        out.push_str("}\n");
+        out
+    }
+
+    /// Render as a Mermaid flowchart, shading nodes by compile time
+    pub fn render_mermaid(&self, times: &HashMap<String, f64>) -> String {
+        let max_time = max_time(times);
+        let mut out = String::new();
+
+        out.push_str("flowchart LR\n");
+        for (index, node) in self.nodes.iter().enumerate() {
+            let time = times.get(&node.key()).copied();
+            let label = match time {
+                Some(time) => format!("{} {}<br/>{:.1}s", node.name, node.version, time),
+                None => format!("{} {}", node.name, node.version),
+            };
+            let _ = writeln!(
+                out,
+                "    n{}[\"{}\"]:::{}",
+                index,
+                label,
+                heat(time, max_time).class_name()
+            );
+        }
+
+        for (from, to) in &self.edges {
+            let _ = writeln!(out, "    n{} --> n{}", from, to);
+        }
+
+        for heat in [Heat::Unknown, Heat::Cold, Heat::Warm, Heat::Hot] {
+            let _ = writeln!(
+                out,
+                "    classDef {} fill:{}",
+                heat.class_name(),
+                heat.dot_color()
+            );
+        }
+        out
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Heat {
+    Unknown,
+    Cold,
+    Warm,
+    Hot,
+}
+
+impl Heat {
+    fn dot_color(self) -> &'static str {
+        match self {
+            Self::Unknown => "#eeeeee",
+            Self::Cold => "#c6e5ff",
+            Self::Warm => "#ffe08a",
+            Self::Hot => "#ff8a80",
+        }
+    }
+
+    fn class_name(self) -> &'static str {
+        match self {
+            Self::Unknown => "unknown",
+            Self::Cold => "cold",
+            Self::Warm => "warm",
+            Self::Hot => "hot",
+        }
+    }
+}
+
+fn max_time(times: &HashMap<String, f64>) -> f64 {
+    times.values().copied().fold(0.0, f64::max)
+}
+
+fn heat(time: Option<f64>, max_time: f64) -> Heat {
+    match time {
+        None => Heat::Unknown,
+        Some(_) if max_time <= 0.0 => Heat::Cold,
+        Some(time) if time / max_time >= 0.5 => Heat::Hot,
+        Some(time) if time / max_time >= 0.2 => Heat::Warm,
+        Some(_) => Heat::Cold,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, is_member: bool) -> GraphNode {
+        GraphNode {
+            id: name.to_string(),
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+            is_member,
+        }
+    }
+
+    fn sample_graph() -> DependencyGraph {
+        // app -> core -> serde, app -> log
+        DependencyGraph {
+            nodes: vec![
+                node("app", true),
+                node("core", true),
+                node("serde", false),
+                node("log", false),
+            ],
+            edges: vec![(0, 1), (1, 2), (0, 3)],
+        }
+    }
+
+    #[test]
+    fn test_dependency_closure() {
+        let graph = sample_graph();
+        assert_eq!(graph.dependency_closure(&[1]), HashSet::from([1, 2]));
+        assert_eq!(graph.dependency_closure(&[0]).len(), 4);
+    }
+
+    #[test]
+    fn test_unreachable_without() {
+        // app -> reqwest -> hyper -> bytes, app -> bytes
+        let graph = DependencyGraph {
+            nodes: vec![
+                node("app", true),
+                node("reqwest", false),
+                node("hyper", false),
+                node("bytes", false),
+            ],
+            edges: vec![(0, 1), (1, 2), (2, 3), (0, 3)],
+        };
+        // bytes is still needed by app itself
+        assert_eq!(
+            graph.unreachable_without(|_, to| to == 1),
+            HashSet::from([1, 2])
+        );
+        assert_eq!(
+            graph.unreachable_without(|from, to| (from, to) == (1, 2)),
+            HashSet::from([2])
+        );
+    }
+
+    #[test]
+    fn test_members_only() {
+        let graph = sample_graph().members_only();
+        assert_eq!(graph.nodes.len(), 2);
+        assert_eq!(graph.edges, vec![(0, 1)]);
+    }
+
+    #[test]
+    fn test_render_annotates_times() {
+        let graph = sample_graph();
+        let times = HashMap::from([("serde 1.0.0".to_string(), 4.0)]);
+
+        let dot = graph.render_dot(&times);
+        assert!(dot.contains("serde 1.0.0\\n4.0s"));
+        assert!(dot.contains("n1 -> n2;"));
+
+        let mermaid = graph.render_mermaid(&times);
+        assert!(mermaid.contains("n2[\"serde 1.0.0<br/>4.0s\"]:::hot"));
+        assert!(mermaid.contains("n0 --> n3"));
+    }
+}
@@ -0,0 +1,242 @@
+use crate::analysis::graph::{DependencyGraph, GraphNode};
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::utils::execute_command;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Subset of `cargo metadata --format-version 1` used by Atlas
+#[derive(Debug, Clone, Deserialize)]
+pub struct CargoMetadata {
+    pub packages: Vec<Package>,
+    pub workspace_members: Vec<String>,
+    pub resolve: Option<Resolve>,
+    pub target_directory: PathBuf,
+    #[serde(default)]
+    pub workspace_root: PathBuf,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Package {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    /// Native library this package links, from the `links` manifest key
+    #[serde(default)]
+    pub links: Option<String>,
+    #[serde(default)]
+    pub targets: Vec<Target>,
+    #[serde(default)]
+    pub manifest_path: PathBuf,
+    /// Registry or git source; `None` for path dependencies and members
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub dependencies: Vec<Dependency>,
+}
+
+/// A dependency as declared in a package's manifest
+#[derive(Debug, Clone, Deserialize)]
+pub struct Dependency {
+    pub name: String,
+    /// Only built when a feature enables it
+    #[serde(default)]
+    pub optional: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Target {
+    pub name: String,
+    pub kind: Vec<String>,
+}
+
+impl Package {
+    /// The library target, or the first binary if the package has no library
+    pub fn primary_target(&self) -> Option<&Target> {
+        self.targets
+            .iter()
+            .find(|target| target.is_lib())
+            .or_else(|| self.targets.iter().find(|target| target.is_bin()))
+    }
+}
+
+impl Target {
+    pub fn is_lib(&self) -> bool {
+        self.kind
+            .iter()
+            .any(|kind| matches!(kind.as_str(), "lib" | "rlib" | "dylib" | "proc-macro"))
+    }
+
+    pub fn is_bin(&self) -> bool {
+        self.kind.iter().any(|kind| kind == "bin")
+    }
+
+    /// Whether `cargo test` runs tests from this target by default
+    pub fn is_tested(&self) -> bool {
+        self.is_lib() || self.is_bin() || self.kind.iter().any(|kind| kind == "test")
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Resolve {
+    pub nodes: Vec<ResolveNode>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ResolveNode {
+    pub id: String,
+    #[serde(default)]
+    pub deps: Vec<NodeDep>,
+    /// Features enabled for this package in the resolved build
+    #[serde(default)]
+    pub features: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NodeDep {
+    pub pkg: String,
+    #[serde(default)]
+    pub dep_kinds: Vec<DepKind>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DepKind {
+    pub kind: Option<String>,
+}
+
+impl NodeDep {
+    /// Whether this edge is only used for tests, examples and benchmarks
+    pub fn is_dev_only(&self) -> bool {
+        !self.dep_kinds.is_empty()
+            && self
+                .dep_kinds
+                .iter()
+                .all(|kind| kind.kind.as_deref() == Some("dev"))
+    }
+}
+
+impl CargoMetadata {
+    /// Run `cargo metadata` for the project
+    pub fn load(project_root: &Path) -> OptimizerResult<Self> {
+        let output = execute_command(
+            "cargo",
+            &["metadata", "--format-version", "1"],
+            Some(project_root),
+        )?;
+
+        if !output.status.success() {
+            return Err(OptimizerError::command_failed(format!(
+                "cargo metadata failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        Self::parse(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    pub fn parse(json: &str) -> OptimizerResult<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn is_member(&self, id: &str) -> bool {
+        self.workspace_members.iter().any(|member| member == id)
+    }
+
+    /// Packages that belong to the workspace
+    pub fn members(&self) -> impl Iterator<Item = &Package> {
+        self.packages
+            .iter()
+            .filter(|package| self.is_member(&package.id))
+    }
+
+    pub fn package(&self, id: &str) -> Option<&Package> {
+        self.packages.iter().find(|package| package.id == id)
+    }
+
+    /// Features enabled for a package in the resolved build
+    pub fn enabled_features(&self, id: &str) -> Vec<String> {
+        self.resolve
+            .iter()
+            .flat_map(|resolve| &resolve.nodes)
+            .find(|node| node.id == id)
+            .map(|node| node.features.clone())
+            .unwrap_or_default()
+    }
+
+    /// Dependencies whose build scripts compile or link native code
+    pub fn native_packages(&self) -> Vec<&Package> {
+        let builders: HashSet<&str> = self
+            .packages
+            .iter()
+            .filter(|package| package.name == "cc" || package.name == "cmake")
+            .map(|package| package.id.as_str())
+            .collect();
+        let uses_builder: HashSet<&str> = self
+            .resolve
+            .iter()
+            .flat_map(|resolve| &resolve.nodes)
+            .filter(|node| {
+                node.deps.iter().any(|dep| {
+                    builders.contains(dep.pkg.as_str())
+                        && dep
+                            .dep_kinds
+                            .iter()
+                            .any(|kind| kind.kind.as_deref() == Some("build"))
+                })
+            })
+            .map(|node| node.id.as_str())
+            .collect();
+
+        let mut packages: Vec<&Package> = self
+            .packages
+            .iter()
+            .filter(|package| !self.is_member(&package.id))
+            .filter(|package| {
+                package.links.is_some()
+                    || package.name.ends_with("-sys")
+                    || uses_builder.contains(package.id.as_str())
+            })
+            .collect();
+        packages.sort_by(|a, b| a.name.cmp(&b.name));
+        packages
+    }
+
+    /// Resolved dependency graph. Edges point from a package to its dependencies.
+    pub fn dependency_graph(&self, include_dev: bool) -> DependencyGraph {
+        let mut graph = DependencyGraph::default();
+        let mut index: HashMap<&str, usize> = HashMap::new();
+
+        for package in &self.packages {
+            index.insert(package.id.as_str(), graph.nodes.len());
+            graph.nodes.push(GraphNode {
+                id: package.id.clone(),
+                name: package.name.clone(),
+                version: package.version.clone(),
+                is_member: self.is_member(&package.id),
+            });
+        }
+
+        if let Some(resolve) = &self.resolve {
+            for node in &resolve.nodes {
+                let Some(&from) = index.get(node.id.as_str()) else {
+                    continue;
+                };
+                for dep in &node.deps {
+                    if !include_dev && dep.is_dev_only() {
+                        continue;
+                    }
+                    if let Some(&to) = index.get(dep.pkg.as_str()) {
+                        graph.edges.push((from, to));
+                    }
+                }
+            }
+        }
+
+        // Dev-only dependencies are unreachable when dev edges are dropped
+        if !include_dev {
+            graph = graph.reachable_from_members();
+        }
+
+        graph
+    }
+}
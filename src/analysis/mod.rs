@@ -0,0 +1,14 @@
+//! Workspace analysis built on `cargo metadata` and cargo's timing reports
+
+pub mod affected;
+pub mod alternatives;
+pub mod build_scripts;
+pub mod cost;
+pub mod critical_path;
+pub mod graph;
+pub mod metadata;
+pub mod outdated;
+pub mod proc_macros;
+pub mod profiling;
+pub mod split;
+pub mod timings;
@@ -0,0 +1,261 @@
+//! Newer versions of locked dependencies, from the crates.io sparse index.
+//!
+//! Updates are split the way Cargo sees them: a semver-compatible release is
+//! picked up by `cargo update` alone, a breaking one needs a manifest edit.
+//! When the index cannot be reached, Cargo's local copy of it is used.
+
+use crate::utils::{cargo_home, execute_command, is_tool_available};
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::fs;
+use std::path::PathBuf;
+
+/// Registry sources of crates.io in `cargo metadata`
+const CRATES_IO_SOURCES: &[&str] = &[
+    "registry+https://github.com/rust-lang/crates.io-index",
+    "sparse+https://index.crates.io/",
+];
+
+const SPARSE_INDEX: &str = "https://index.crates.io";
+
+pub fn is_crates_io(source: Option<&str>) -> bool {
+    source.is_some_and(|source| CRATES_IO_SOURCES.contains(&source))
+}
+
+/// A semver version; build metadata is ignored and pre-release identifiers
+/// compare as plain strings, which is enough to rank releases of one crate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre: String,
+}
+
+impl Version {
+    pub fn parse(version: &str) -> Option<Self> {
+        let version = version.split('+').next()?;
+        let (release, pre) = match version.split_once('-') {
+            Some((release, pre)) => (release, pre.to_string()),
+            None => (version, String::new()),
+        };
+        let mut numbers = release.split('.').map(|part| part.parse::<u64>().ok());
+        let version = Self {
+            major: numbers.next()??,
+            minor: numbers.next()??,
+            patch: numbers.next()??,
+            pre,
+        };
+        numbers.next().is_none().then_some(version)
+    }
+
+    pub fn is_prerelease(&self) -> bool {
+        !self.pre.is_empty()
+    }
+
+    /// Whether Cargo's default `^` requirement for `self` accepts `other`
+    pub fn is_compatible(&self, other: &Self) -> bool {
+        match (self.major, self.minor) {
+            (0, 0) => other.major == 0 && other.minor == 0 && other.patch == self.patch,
+            (0, minor) => other.major == 0 && other.minor == minor,
+            (major, _) => other.major == major,
+        }
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => self.pre.cmp(&other.pre),
+            })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl std::fmt::Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if !self.pre.is_empty() {
+            write!(f, "-{}", self.pre)?;
+        }
+        Ok(())
+    }
+}
+
+/// One line of a crate's index file
+#[derive(Debug, Clone, Deserialize)]
+pub struct IndexEntry {
+    pub vers: String,
+    #[serde(default)]
+    pub yanked: bool,
+}
+
+/// Parse index entries. Cargo's cache of the sparse index stores the same JSON
+/// lines separated by NUL bytes among other fields, so both formats are read
+/// by splitting on either and keeping what parses.
+pub fn parse_index(content: &str) -> Vec<IndexEntry> {
+    content
+        .split(['\n', '\0'])
+        .filter(|line| line.starts_with('{'))
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// Path of a crate's file in the index, e.g. `se/rd/serde`
+pub fn index_path(name: &str) -> String {
+    let name = name.to_lowercase();
+    match name.len() {
+        1 => format!("1/{}", name),
+        2 => format!("2/{}", name),
+        3 => format!("3/{}/{}", &name[..1], name),
+        _ => format!("{}/{}/{}", &name[..2], &name[2..4], name),
+    }
+}
+
+/// Where the index was read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexSource {
+    Network,
+    /// Cargo's local copy, as fresh as the last build that updated it
+    LocalCache,
+}
+
+/// Released versions of `name`, from the sparse index or Cargo's cache of it
+pub fn fetch_versions(name: &str) -> Option<(Vec<IndexEntry>, IndexSource)> {
+    let path = index_path(name);
+    if is_tool_available("curl") {
+        let url = format!("{}/{}", SPARSE_INDEX, path);
+        let output = execute_command(
+            "curl",
+            &["--fail", "--silent", "--max-time", "10", &url],
+            None,
+        );
+        if let Some(output) = output.ok().filter(|output| output.status.success()) {
+            let entries = parse_index(&String::from_utf8_lossy(&output.stdout));
+            return Some((entries, IndexSource::Network));
+        }
+    }
+    let content = cached_index_files(&path)
+        .into_iter()
+        .find_map(|file| fs::read(file).ok())?;
+    Some((
+        parse_index(&String::from_utf8_lossy(&content)),
+        IndexSource::LocalCache,
+    ))
+}
+
+fn cached_index_files(path: &str) -> Vec<PathBuf> {
+    let Some(index_dir) = cargo_home().map(|home| home.join("registry").join("index")) else {
+        return Vec::new();
+    };
+    let Ok(registries) = fs::read_dir(index_dir) else {
+        return Vec::new();
+    };
+    registries
+        .flatten()
+        .filter(|registry| {
+            registry
+                .file_name()
+                .to_string_lossy()
+                .starts_with("index.crates.io")
+        })
+        .map(|registry| registry.path().join(".cache").join(path))
+        .filter(|file| file.exists())
+        .collect()
+}
+
+/// Newest semver-compatible release above `current`, and the newest release
+/// beyond that which needs a manifest change. Yanked releases never count;
+/// pre-releases only for a crate locked to one.
+pub fn newer_versions(
+    current: &Version,
+    entries: &[IndexEntry],
+) -> (Option<Version>, Option<Version>) {
+    let mut compatible: Option<Version> = None;
+    let mut breaking: Option<Version> = None;
+    for entry in entries.iter().filter(|entry| !entry.yanked) {
+        let Some(version) = Version::parse(&entry.vers) else {
+            continue;
+        };
+        if version <= *current || (version.is_prerelease() && !current.is_prerelease()) {
+            continue;
+        }
+        let slot = if current.is_compatible(&version) {
+            &mut compatible
+        } else {
+            &mut breaking
+        };
+        if slot.as_ref().map_or(true, |best| version > *best) {
+            *slot = Some(version);
+        }
+    }
+    (compatible, breaking)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(versions: &[(&str, bool)]) -> Vec<IndexEntry> {
+        versions
+            .iter()
+            .map(|(vers, yanked)| IndexEntry {
+                vers: vers.to_string(),
+                yanked: *yanked,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_newer_versions_by_compatibility() {
+        let index = entries(&[
+            ("1.0.100", false),
+            ("1.0.180", false),
+            ("1.0.190", true),
+            ("2.0.0-rc.1", false),
+            ("2.0.0", false),
+            ("2.1.3", false),
+        ]);
+        let current = Version::parse("1.0.100").unwrap();
+        let (compatible, breaking) = newer_versions(&current, &index);
+        assert_eq!(compatible.unwrap().to_string(), "1.0.180");
+        assert_eq!(breaking.unwrap().to_string(), "2.1.3");
+
+        // Below 1.0 the minor version is the breaking one
+        let index = entries(&[("0.3.1", false), ("0.3.9", false), ("0.4.0", false)]);
+        let (compatible, breaking) = newer_versions(&Version::parse("0.3.1").unwrap(), &index);
+        assert_eq!(compatible.unwrap().to_string(), "0.3.9");
+        assert_eq!(breaking.unwrap().to_string(), "0.4.0");
+
+        let (compatible, breaking) = newer_versions(&Version::parse("0.4.0").unwrap(), &index);
+        assert_eq!((compatible, breaking), (None, None));
+    }
+
+    #[test]
+    fn test_index_files() {
+        assert_eq!(index_path("a"), "1/a");
+        assert_eq!(index_path("cc"), "2/cc");
+        assert_eq!(index_path("syn"), "3/s/syn");
+        assert_eq!(index_path("Serde_JSON"), "se/rd/serde_json");
+
+        // Cargo's cache: a header, then version and JSON line pairs split by NUL
+        let cache = "\u{3}\u{2}\0\0\0etag\u{0}1.0.0\u{0}{\"name\":\"x\",\"vers\":\"1.0.0\",\"yanked\":false}\u{0}1.1.0\u{0}{\"name\":\"x\",\"vers\":\"1.1.0\",\"yanked\":true}\u{0}";
+        let parsed = parse_index(cache);
+        assert_eq!(parsed.len(), 2);
+        assert!(parsed[1].yanked);
+        assert!(Version::parse("1.2").is_none());
+        assert!(Version::parse("1.2.3-beta.1+build.5")
+            .unwrap()
+            .is_prerelease());
+    }
+}
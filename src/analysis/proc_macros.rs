@@ -0,0 +1,224 @@
+//! Experiments with how proc macros are built, for `analyze proc-macros`
+//!
+//! Proc macros are compiled for the host and loaded into rustc as dynamic
+//! libraries, which sccache does not cache, so every clean build compiles
+//! them again. What can change is how they are built: Cargo's
+//! `build-override` profile trades the time to compile them against the time
+//! they take to expand, and nightly rustc can run them on a thread of their
+//! own. Each variant is selected through environment variables, so trying
+//! one never edits a file.
+
+use crate::analysis::metadata::CargoMetadata;
+use crate::analysis::timings::TimingReport;
+use crate::rustflags::{self, RustflagsLayers};
+use crate::significance::{self, Comparison, Samples, Verdict};
+use std::collections::BTreeSet;
+
+/// Nightly rustc flag that runs proc macros on a separate thread
+pub const CROSS_THREAD_FLAG: &str = "-Zproc-macro-execution-strategy=cross-thread";
+
+/// A proc-macro crate in the build
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcMacro {
+    pub name: String,
+    pub version: String,
+    /// Compile time in the last timings report
+    pub compile_time: Option<f64>,
+    /// Packages that use it directly
+    pub dependents: Vec<String>,
+}
+
+/// Proc-macro crates the workspace builds, slowest to compile first
+pub fn proc_macros(metadata: &CargoMetadata, report: Option<&TimingReport>) -> Vec<ProcMacro> {
+    let graph = metadata.dependency_graph(false);
+    let times = report.map(TimingReport::crate_times).unwrap_or_default();
+    let mut macros: Vec<ProcMacro> = (0..graph.nodes.len())
+        .filter(|&index| {
+            metadata
+                .package(&graph.nodes[index].id)
+                .is_some_and(|package| {
+                    package
+                        .targets
+                        .iter()
+                        .any(|target| target.kind.iter().any(|kind| kind == "proc-macro"))
+                })
+        })
+        .map(|index| {
+            let node = &graph.nodes[index];
+            let dependents: BTreeSet<String> = graph
+                .edges
+                .iter()
+                .filter(|(_, to)| *to == index)
+                .map(|(from, _)| graph.nodes[*from].name.clone())
+                .collect();
+            ProcMacro {
+                name: node.name.clone(),
+                version: node.version.clone(),
+                compile_time: times.get(&node.key()).copied(),
+                dependents: dependents.into_iter().collect(),
+            }
+        })
+        .collect();
+    macros.sort_by(|a, b| {
+        b.compile_time
+            .unwrap_or(0.0)
+            .total_cmp(&a.compile_time.unwrap_or(0.0))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    macros
+}
+
+/// One way of building proc macros
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variant {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Environment that selects it for one cargo run
+    pub env: Vec<(String, String)>,
+    /// `[profile.dev.build-override]` opt-level that keeps it
+    pub opt_level: Option<&'static str>,
+}
+
+/// Variants to measure: the current settings first, then build-override
+/// opt-levels, then, on a nightly toolchain, cross-thread expansion
+pub fn variants(rustflags: &RustflagsLayers, nightly: bool) -> Vec<Variant> {
+    let optimized = |level: &'static str, name: &'static str, description: &'static str| Variant {
+        name,
+        description,
+        env: vec![(
+            "CARGO_PROFILE_DEV_BUILD_OVERRIDE_OPT_LEVEL".to_string(),
+            level.to_string(),
+        )],
+        opt_level: Some(level),
+    };
+    let mut variants = vec![
+        Variant {
+            name: "current",
+            description: "the project's settings",
+            env: Vec::new(),
+            opt_level: None,
+        },
+        optimized(
+            "1",
+            "opt-level 1",
+            "lightly optimized macros: quick to compile, faster to expand",
+        ),
+        optimized(
+            "3",
+            "opt-level 3",
+            "fully optimized macros: slowest to compile, fastest to expand",
+        ),
+    ];
+    if nightly {
+        // The encoded flags replace every other source, so they carry the
+        // flags Cargo would have used
+        let mut flags = match &rustflags.env {
+            Some(env) => env.flags.clone(),
+            None => rustflags.config_flags(),
+        };
+        flags.push(CROSS_THREAD_FLAG.to_string());
+        variants.push(Variant {
+            name: "cross-thread",
+            description: "nightly: expand proc macros on a separate thread",
+            env: vec![(
+                "CARGO_ENCODED_RUSTFLAGS".to_string(),
+                rustflags::encode(&flags),
+            )],
+            opt_level: None,
+        });
+    }
+    variants
+}
+
+/// The variant that builds significantly faster than the current settings,
+/// with its comparison; the fastest one when several do
+pub fn best<'a>(
+    current: &Samples,
+    trials: &'a [(Variant, Samples)],
+    confidence: f64,
+) -> Option<(&'a Variant, Comparison)> {
+    trials
+        .iter()
+        .filter_map(|(variant, samples)| {
+            significance::compare(current, samples, confidence)
+                .filter(|comparison| comparison.verdict == Verdict::Faster)
+                .map(|comparison| (variant, comparison))
+        })
+        .min_by(|a, b| a.1.means.1.total_cmp(&b.1.means.1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rustflags::{ConfigFlags, FlagsKey};
+
+    #[test]
+    fn test_proc_macros() {
+        let json = r#"{
+            "packages": [
+                {"id":"app 0.1.0","name":"app","version":"0.1.0",
+                 "targets":[{"name":"app","kind":["bin"]}]},
+                {"id":"serde_derive 1.0.0","name":"serde_derive","version":"1.0.0",
+                 "targets":[{"name":"serde_derive","kind":["proc-macro"]}]},
+                {"id":"serde 1.0.0","name":"serde","version":"1.0.0",
+                 "targets":[{"name":"serde","kind":["lib"]}]}
+            ],
+            "workspace_members": ["app 0.1.0"],
+            "target_directory": "/ws/target",
+            "resolve": {"nodes": [
+                {"id":"app 0.1.0","deps":[{"pkg":"serde 1.0.0","dep_kinds":[{"kind":null}]}]},
+                {"id":"serde 1.0.0","deps":[{"pkg":"serde_derive 1.0.0","dep_kinds":[{"kind":null}]}]}
+            ]}
+        }"#;
+        let metadata = CargoMetadata::parse(json).unwrap();
+        assert_eq!(
+            proc_macros(&metadata, None),
+            vec![ProcMacro {
+                name: "serde_derive".to_string(),
+                version: "1.0.0".to_string(),
+                compile_time: None,
+                dependents: vec!["serde".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_cross_thread_keeps_config_flags() {
+        let layers = RustflagsLayers {
+            env: None,
+            config: vec![ConfigFlags {
+                origin: ".cargo/config.toml".to_string(),
+                key: FlagsKey::Build,
+                flags: vec!["-Clink-arg=-fuse-ld=lld".to_string()],
+            }],
+        };
+        assert_eq!(variants(&layers, false).len(), 3);
+        let cross_thread = variants(&layers, true).pop().unwrap();
+        assert_eq!(
+            cross_thread.env,
+            vec![(
+                "CARGO_ENCODED_RUSTFLAGS".to_string(),
+                rustflags::encode(&[
+                    "-Clink-arg=-fuse-ld=lld".to_string(),
+                    CROSS_THREAD_FLAG.to_string()
+                ])
+            )]
+        );
+    }
+
+    #[test]
+    fn test_best_needs_a_significant_win() {
+        let layers = RustflagsLayers::default();
+        let [_, light, full] = <[Variant; 3]>::try_from(variants(&layers, false)).unwrap();
+        let current = Samples::new(vec![60.0, 61.0, 59.0]);
+        let trials = vec![
+            (light, Samples::new(vec![50.0, 51.0, 49.0])),
+            (full, Samples::new(vec![58.0, 66.0, 54.0])),
+        ];
+        let (variant, comparison) = best(&current, &trials, 0.95).unwrap();
+        assert_eq!(variant.name, "opt-level 1");
+        assert_eq!(comparison.verdict, Verdict::Faster);
+
+        assert!(best(&current, &trials[1..], 0.95).is_none());
+    }
+}
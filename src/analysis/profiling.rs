@@ -0,0 +1,124 @@
+//! Where a profiled build spent its time, per crate
+//!
+//! Cargo's timings report lists compilation units: a crate's library, its
+//! binaries and the run of its build script are separate units. Aggregating
+//! them per crate, and splitting rustc's time into the frontend (parsing,
+//! type checking, borrow checking) and codegen (LLVM), shows which crates to
+//! look at first and whether fewer generics or more codegen units would help.
+
+use crate::analysis::timings::TimingReport;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Compile time of one crate, summed over its units
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CrateTime {
+    pub name: String,
+    pub version: String,
+    pub units: usize,
+    pub total_secs: f64,
+    /// Frontend time of the units whose split is known
+    pub frontend_secs: Option<f64>,
+    /// Codegen time of the units whose split is known
+    pub codegen_secs: Option<f64>,
+    /// Time spent running the build script, including C/C++ compilation
+    pub build_script_secs: f64,
+}
+
+impl CrateTime {}
+
+/// A profiled build, slowest crates first
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BuildProfile {
+    /// `None` when the report has no schedule, as with `--timings=json`
+    pub wall_secs: Option<f64>,
+    /// Compile time of all units; exceeds the wall time on several cores
+    pub total_secs: f64,
+    pub crates: Vec<CrateTime>,
+}
+
+impl BuildProfile {
+    pub fn from_report(report: &TimingReport) -> Self {
+        let mut crates: HashMap<String, CrateTime> = HashMap::new();
+        for unit in &report.units {
+            let time = crates.entry(unit.key()).or_insert_with(|| CrateTime {
+                name: unit.name.clone(),
+                version: unit.version.clone(),
+                units: 0,
+                total_secs: 0.0,
+                frontend_secs: None,
+                codegen_secs: None,
+                build_script_secs: 0.0,
+            });
+            time.units += 1;
+            time.total_secs += unit.duration;
+            if unit.is_build_script_run() {
+                time.build_script_secs += unit.duration;
+            } else if let Some((frontend, codegen)) = unit.frontend_codegen() {
+                *time.frontend_secs.get_or_insert(0.0) += frontend;
+                *time.codegen_secs.get_or_insert(0.0) += codegen;
+            }
+        }
+
+        let mut crates: Vec<CrateTime> = crates.into_values().collect();
+        crates.sort_by(|a, b| {
+            b.total_secs
+                .total_cmp(&a.total_secs)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        Self {
+            wall_secs: report.has_schedule().then(|| report.wall_time()),
+            total_secs: report.units.iter().map(|unit| unit.duration).sum(),
+            crates,
+        }
+    }
+
+    /// Only the `count` slowest crates, keeping the totals of the whole build
+    pub fn top(mut self, count: usize) -> Self {
+        self.crates.truncate(count);
+        self
+    }
+
+    /// Share of all compile time spent on `time`, in percent
+    pub fn share(&self, time: &CrateTime) -> f64 {
+        if self.total_secs > 0.0 {
+            time.total_secs / self.total_secs * 100.0
+        } else {
+            0.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crates_are_aggregated_and_split() {
+        let text = r#"{"reason":"timing-info","package_id":"registry+https://github.com/rust-lang/crates.io-index#syn@2.0.0","target":{"name":"syn"},"mode":"build","duration":4.0,"rmeta_time":3.0}
+{"reason":"timing-info","package_id":"registry+https://github.com/rust-lang/crates.io-index#ring@0.17.0","target":{"name":"build-script-build"},"mode":"run-custom-build","duration":6.0}
+{"reason":"timing-info","package_id":"registry+https://github.com/rust-lang/crates.io-index#ring@0.17.0","target":{"name":"ring"},"mode":"build","duration":2.0,"rmeta_time":0.5}
+{"reason":"timing-info","package_id":"app 0.1.0 (path+file:///app)","target":{"name":"app"},"mode":"build","duration":1.0}"#;
+        let profile = BuildProfile::from_report(&TimingReport::parse_json_lines(text));
+
+        assert_eq!(profile.wall_secs, None);
+        assert_eq!(profile.total_secs, 13.0);
+        let names: Vec<&str> = profile
+            .crates
+            .iter()
+            .map(|time| time.name.as_str())
+            .collect();
+        assert_eq!(names, ["ring", "syn", "app"]);
+
+        let ring = &profile.crates[0];
+        assert_eq!(ring.units, 2);
+        assert_eq!(ring.build_script_secs, 6.0);
+        assert_eq!(ring.frontend_secs, Some(0.5));
+        assert_eq!(ring.codegen_secs, Some(1.5));
+        assert_eq!(profile.crates[2].codegen_secs, None);
+
+        let top = profile.top(1);
+        assert_eq!(top.crates.len(), 1);
+        assert_eq!(top.share(&top.crates[0]), 8.0 / 13.0 * 100.0);
+    }
+}
@@ -0,0 +1,230 @@
+//! Experimental crate splitting suggestions based on `cargo llvm-lines`
+
+use crate::analysis::critical_path::ScheduleModel;
+use crate::analysis::metadata::{Package, Target};
+use crate::analysis::timings::TimingReport;
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::utils::execute_command;
+use regex::Regex;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Minimum share of a crate's LLVM IR a module needs to be worth splitting out
+const MIN_MODULE_SHARE: f64 = 0.10;
+
+/// Crates smaller than this are cheap enough that splitting rarely pays off
+const MIN_CRATE_LINES: u64 = 5_000;
+
+/// Label for IR that cannot be attributed to a module, e.g. std generics
+const UNATTRIBUTED: &str = "(generic instantiations)";
+
+/// Label for functions defined at the crate root
+const CRATE_ROOT: &str = "(crate root)";
+
+/// LLVM IR lines generated per module of one crate
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleCost {
+    pub module: String,
+    pub lines: u64,
+    pub copies: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LlvmLines {
+    pub total_lines: u64,
+    /// Modules sorted by generated lines, largest first
+    pub modules: Vec<ModuleCost>,
+}
+
+/// A module that could become its own leaf crate
+#[derive(Debug, Clone)]
+pub struct SplitCandidate {
+    pub package: String,
+    pub module: String,
+    /// Fraction of the crate's LLVM IR generated for this module
+    pub share: f64,
+    /// Estimated wall-clock savings, if the crate appears in the timings report
+    pub projected_savings: Option<f64>,
+}
+
+impl LlvmLines {
+    /// Run `cargo llvm-lines` for a package target
+    pub fn measure(
+        project_root: &Path,
+        package: &Package,
+        target: &Target,
+    ) -> OptimizerResult<Self> {
+        let mut args = vec!["llvm-lines", "-p", package.name.as_str()];
+        if target.is_lib() {
+            args.push("--lib");
+        } else {
+            args.extend(["--bin", target.name.as_str()]);
+        }
+
+        let output = execute_command("cargo", &args, Some(project_root))?;
+        if !output.status.success() {
+            return Err(OptimizerError::command_failed(format!(
+                "cargo llvm-lines failed for {}: {}",
+                package.name,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        Ok(Self::parse(
+            &String::from_utf8_lossy(&output.stdout),
+            &target.name.replace('-', "_"),
+        ))
+    }
+
+    /// Aggregate `cargo llvm-lines` output by top-level module of `crate_name`
+    pub fn parse(output: &str, crate_name: &str) -> Self {
+        let row = Regex::new(r"^\s*(\d+)\s+\([^)]*\)\s+(\d+)\s+\([^)]*\)\s+(.+)$")
+            .expect("valid llvm-lines regex");
+        let path = Regex::new(&format!(
+            r"\b{}::([A-Za-z_][A-Za-z0-9_]*)(::)?",
+            regex::escape(crate_name)
+        ))
+        .expect("valid module regex");
+
+        let mut total_lines = 0;
+        let mut modules: HashMap<String, ModuleCost> = HashMap::new();
+
+        for line in output.lines() {
+            let Some(captures) = row.captures(line) else {
+                continue;
+            };
+            let function = captures[3].trim();
+            if function == "(TOTAL)" {
+                continue;
+            }
+            let lines: u64 = captures[1].parse().unwrap_or(0);
+            let copies: u64 = captures[2].parse().unwrap_or(0);
+            total_lines += lines;
+
+            let module = match path.captures(function) {
+                // `krate::module::item`
+                Some(segments) if segments.get(2).is_some() => segments[1].to_string(),
+                Some(_) => CRATE_ROOT.to_string(),
+                None => UNATTRIBUTED.to_string(),
+            };
+            let cost = modules.entry(module.clone()).or_insert(ModuleCost {
+                module,
+                lines: 0,
+                copies: 0,
+            });
+            cost.lines += lines;
+            cost.copies += copies;
+        }
+
+        let mut modules: Vec<ModuleCost> = modules.into_values().collect();
+        modules.sort_by(|a, b| b.lines.cmp(&a.lines).then_with(|| a.module.cmp(&b.module)));
+
+        Self {
+            total_lines,
+            modules,
+        }
+    }
+
+    pub fn share(&self, module: &ModuleCost) -> f64 {
+        if self.total_lines == 0 {
+            0.0
+        } else {
+            module.lines as f64 / self.total_lines as f64
+        }
+    }
+}
+
+/// Modules of a crate that are large enough to split into leaf crates
+pub fn suggest(
+    package: &str,
+    lines: &LlvmLines,
+    schedule: Option<(&ScheduleModel, &TimingReport)>,
+) -> Vec<SplitCandidate> {
+    if lines.total_lines < MIN_CRATE_LINES {
+        return Vec::new();
+    }
+
+    lines
+        .modules
+        .iter()
+        .filter(|module| module.module != UNATTRIBUTED && module.module != CRATE_ROOT)
+        .filter(|module| lines.share(module) >= MIN_MODULE_SHARE)
+        .map(|module| {
+            let share = lines.share(module);
+            SplitCandidate {
+                package: package.to_string(),
+                module: module.module.clone(),
+                share,
+                projected_savings: schedule
+                    .and_then(|(model, report)| projected_savings(model, report, package, share)),
+            }
+        })
+        .collect()
+}
+
+/// Estimate the wall-clock savings of moving `share` of a crate into a leaf crate.
+///
+/// Thanks to pipelining the remaining crate only waits for the leaf's metadata,
+/// so the leaf's codegen overlaps with the rest of the crate.
+fn projected_savings(
+    model: &ScheduleModel,
+    report: &TimingReport,
+    package: &str,
+    share: f64,
+) -> Option<f64> {
+    let (position, unit) = report
+        .units
+        .iter()
+        .enumerate()
+        .filter(|(_, unit)| unit.name == package && !unit.target.contains("build-script"))
+        .max_by(|(_, a), (_, b)| a.duration.total_cmp(&b.duration))?;
+
+    if unit.duration <= 0.0 {
+        return None;
+    }
+
+    let metadata_fraction = (unit.metadata_ready() / unit.duration).clamp(0.0, 1.0);
+    let chained = unit.duration * (1.0 - share) + unit.duration * share * metadata_fraction;
+    Some((model.lower_bound() - model.lower_bound_with(position, chained, 0.0)).max(0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTPUT: &str = "  Lines                 Copies              Function name
+  -----                 ------              -------------
+  10000                 100                 (TOTAL)
+   4000 (40.0%, 40.0%)     20 (20.0%, 20.0%)  my_crate::parser::parse_expr
+   2000 (20.0%, 60.0%)     10 (10.0%, 30.0%)  <my_crate::codegen::Emitter as core::fmt::Debug>::fmt
+   1500 (15.0%, 75.0%)     30 (30.0%, 60.0%)  core::ptr::drop_in_place<alloc::string::String>
+   1500 (15.0%, 90.0%)     30 (30.0%, 90.0%)  my_crate::parser::lex
+    500 ( 5.0%, 95.0%)      5 ( 5.0%, 95.0%)  my_crate::main
+    500 ( 5.0%,100.0%)      5 ( 5.0%,100.0%)  my_crate::util::helper";
+
+    #[test]
+    fn test_parse_groups_by_module() {
+        let lines = LlvmLines::parse(OUTPUT, "my_crate");
+        assert_eq!(lines.total_lines, 10000);
+        assert_eq!(lines.modules[0].module, "parser");
+        assert_eq!(lines.modules[0].lines, 5500);
+        assert_eq!(lines.modules[0].copies, 50);
+        assert!(lines
+            .modules
+            .iter()
+            .any(|module| module.module == CRATE_ROOT && module.lines == 500));
+        assert!(lines
+            .modules
+            .iter()
+            .any(|module| module.module == UNATTRIBUTED && module.lines == 1500));
+    }
+
+    #[test]
+    fn test_suggest_skips_small_modules() {
+        let lines = LlvmLines::parse(OUTPUT, "my_crate");
+        let candidates = suggest("my-crate", &lines, None);
+        let modules: Vec<&str> = candidates.iter().map(|c| c.module.as_str()).collect();
+        assert_eq!(modules, vec!["parser", "codegen"]);
+        assert!(candidates[0].projected_savings.is_none());
+    }
+}
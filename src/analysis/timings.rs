@@ -0,0 +1,292 @@
+use crate::analysis::graph::crate_key;
+use crate::error::{OptimizerError, OptimizerResult};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// One compilation unit from a cargo timings report
+#[derive(Debug, Clone, Deserialize)]
+pub struct TimingUnit {
+    /// Unit id referenced by `unblocked_units`
+    #[serde(rename = "i", default)]
+    pub id: usize,
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub target: String,
+    /// Compile mode, e.g. `todo` for rustc or `run-custom-build` for build scripts
+    #[serde(default)]
+    pub mode: String,
+    /// Seconds since the start of the build
+    #[serde(default)]
+    pub start: f64,
+    /// Seconds spent compiling this unit
+    pub duration: f64,
+    /// Seconds until metadata was available to dependents (older cargo)
+    #[serde(default)]
+    pub rmeta_time: Option<f64>,
+    /// Units that became ready once this unit finished
+    #[serde(default, alias = "unlocked_units")]
+    pub unblocked_units: Vec<usize>,
+    /// Units that became ready once this unit's metadata was available
+    #[serde(default, alias = "unlocked_rmeta_units")]
+    pub unblocked_rmeta_units: Vec<usize>,
+    /// Frontend/codegen split reported by newer cargo versions
+    #[serde(default)]
+    pub sections: Option<Vec<(String, SectionSpan)>>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct SectionSpan {
+    pub start: f64,
+    pub end: f64,
+}
+
+impl TimingUnit {
+    pub fn key(&self) -> String {
+        crate_key(&self.name, &self.version)
+    }
+
+    /// Human-readable unit label, e.g. `serde` or `app (bin)`
+    pub fn label(&self) -> String {
+        let target = self.target.trim();
+        if target.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{} {}", self.name, target)
+        }
+    }
+
+    /// Whether this unit runs a build script, where C/C++ code gets compiled
+    pub fn is_build_script_run(&self) -> bool {
+        self.mode == "run-custom-build" || self.target.contains("(run)")
+    }
+
+    /// Time spent in a named section (e.g. "frontend" or "codegen")
+    pub fn section_time(&self, name: &str) -> Option<f64> {
+        self.sections.as_ref().and_then(|sections| {
+            sections
+                .iter()
+                .find(|(section, _)| section == name)
+                .map(|(_, span)| span.end - span.start)
+        })
+    }
+
+    /// Seconds in the frontend and in codegen, from the sections of newer
+    /// reports or the metadata time of older ones
+    pub fn frontend_codegen(&self) -> Option<(f64, f64)> {
+        if let (Some(frontend), Some(codegen)) =
+            (self.section_time("frontend"), self.section_time("codegen"))
+        {
+            return Some((frontend, codegen));
+        }
+        let rmeta = self.rmeta_time?;
+        Some((rmeta, (self.duration - rmeta).max(0.0)))
+    }
+
+    /// Seconds after `start` at which dependents waiting on metadata can begin
+    pub fn metadata_ready(&self) -> f64 {
+        self.rmeta_time
+            .or_else(|| self.section_time("frontend"))
+            .unwrap_or(self.duration)
+    }
+
+    /// Whether the report carries scheduling information for this unit
+    pub fn has_schedule(&self) -> bool {
+        self.start > 0.0
+            || !self.unblocked_units.is_empty()
+            || !self.unblocked_rmeta_units.is_empty()
+    }
+}
+
+/// Parsed cargo `--timings` data
+#[derive(Debug, Clone, Default)]
+pub struct TimingReport {
+    pub units: Vec<TimingUnit>,
+    pub source: Option<PathBuf>,
+}
+
+impl TimingReport {
+    /// Parse the `UNIT_DATA` table embedded in `cargo-timing.html`
+    pub fn parse_html(html: &str) -> OptimizerResult<Self> {
+        const MARKER: &str = "const UNIT_DATA = ";
+
+        let start = html.find(MARKER).ok_or_else(|| {
+            OptimizerError::invalid_input("Timing report does not contain UNIT_DATA")
+        })? + MARKER.len();
+
+        let units = serde_json::Deserializer::from_str(&html[start..])
+            .into_iter::<Vec<TimingUnit>>()
+            .next()
+            .ok_or_else(|| OptimizerError::invalid_input("Timing report UNIT_DATA is empty"))??;
+
+        Ok(Self {
+            units,
+            source: None,
+        })
+    }
+
+    /// Parse `timing-info` messages from `cargo build --timings=json` output
+    pub fn parse_json_lines(text: &str) -> Self {
+        let units = text
+            .lines()
+            .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+            .filter(|message| message.get("reason").and_then(Value::as_str) == Some("timing-info"))
+            .enumerate()
+            .filter_map(|(id, message)| {
+                let package_id = message.get("package_id")?.as_str()?;
+                let (name, version) = parse_package_id(package_id)?;
+                Some(TimingUnit {
+                    id,
+                    name,
+                    version,
+                    target: message
+                        .get("target")
+                        .and_then(|target| target.get("name"))
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string(),
+                    mode: message
+                        .get("mode")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string(),
+                    start: 0.0,
+                    duration: message.get("duration")?.as_f64()?,
+                    rmeta_time: message.get("rmeta_time").and_then(Value::as_f64),
+                    unblocked_units: Vec::new(),
+                    unblocked_rmeta_units: Vec::new(),
+                    sections: None,
+                })
+            })
+            .collect();
+
+        Self {
+            units,
+            source: None,
+        }
+    }
+
+    /// Most recent timings report in a target directory
+    pub fn latest_report(target_dir: &Path) -> Option<PathBuf> {
+        let timings_dir = target_dir.join("cargo-timings");
+        let stable = timings_dir.join("cargo-timing.html");
+        if stable.exists() {
+            return Some(stable);
+        }
+
+        fs::read_dir(&timings_dir)
+            .ok()?
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("html"))
+            .max_by_key(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+    }
+
+    /// Load the most recent timings report from a target directory
+    pub fn load_latest(target_dir: &Path) -> OptimizerResult<Self> {
+        let path = Self::latest_report(target_dir).ok_or_else(|| {
+            OptimizerError::file_not_found(
+                "No cargo timings report found. Run `atlas dev profile` first.",
+            )
+        })?;
+        Self::load(&path)
+    }
+
+    pub fn load(path: &Path) -> OptimizerResult<Self> {
+        let content = fs::read_to_string(path)?;
+        let mut report = if path.extension().and_then(|ext| ext.to_str()) == Some("html") {
+            Self::parse_html(&content)?
+        } else {
+            Self::parse_json_lines(&content)
+        };
+        report.source = Some(path.to_path_buf());
+        Ok(report)
+    }
+
+    /// Total compile time per crate (`name version`), summed over its units
+    pub fn crate_times(&self) -> HashMap<String, f64> {
+        let mut times = HashMap::new();
+        for unit in &self.units {
+            *times.entry(unit.key()).or_insert(0.0) += unit.duration;
+        }
+        times
+    }
+
+    /// Wall-clock duration of the recorded build
+    pub fn wall_time(&self) -> f64 {
+        self.units
+            .iter()
+            .map(|unit| unit.start + unit.duration)
+            .fold(0.0, f64::max)
+    }
+
+    /// Whether the report contains enough data to reconstruct the schedule
+    pub fn has_schedule(&self) -> bool {
+        self.units.iter().any(TimingUnit::has_schedule)
+    }
+}
+
+/// Extract name and version from either package id format:
+/// `registry+https://...#serde@1.0.0` or `serde 1.0.0 (registry+...)`
+fn parse_package_id(id: &str) -> Option<(String, String)> {
+    if let Some((name, rest)) = id.split_once(' ') {
+        let version = rest.split_whitespace().next()?;
+        return Some((name.to_string(), version.to_string()));
+    }
+
+    let fragment = id.rsplit_once('#').map(|(_, fragment)| fragment)?;
+    match fragment.split_once('@') {
+        Some((name, version)) => Some((name.to_string(), version.to_string())),
+        // Path dependencies: `path+file:///x/my-crate#0.1.0`
+        None => {
+            let path = id.rsplit_once('#')?.0;
+            let name = path.trim_end_matches('/').rsplit('/').next()?;
+            Some((name.to_string(), fragment.to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HTML: &str = r#"<script>
+const UNIT_DATA = [
+  {"i": 0, "name": "serde", "version": "1.0.0", "mode": "todo", "target": "",
+   "start": 0.0, "duration": 2.0, "unblocked_units": [1], "unblocked_rmeta_units": [],
+   "sections": [["frontend", {"start": 0.0, "end": 1.5}], ["codegen", {"start": 1.5, "end": 2.0}]]},
+  {"i": 1, "name": "app", "version": "0.1.0", "mode": "todo", "target": " app \"bin\"",
+   "start": 2.0, "duration": 1.0, "unlocked_units": [], "sections": null}
+];
+const CONCURRENCY_DATA = [];
+</script>"#;
+
+    #[test]
+    fn test_parse_html() {
+        let report = TimingReport::parse_html(HTML).unwrap();
+        assert_eq!(report.units.len(), 2);
+        assert_eq!(report.units[0].unblocked_units, vec![1]);
+        assert_eq!(report.units[0].section_time("codegen"), Some(0.5));
+        assert_eq!(report.units[0].metadata_ready(), 1.5);
+        assert_eq!(report.wall_time(), 3.0);
+        assert_eq!(report.crate_times()["app 0.1.0"], 1.0);
+    }
+
+    #[test]
+    fn test_parse_json_lines() {
+        let text = r#"{"reason":"timing-info","package_id":"registry+https://github.com/rust-lang/crates.io-index#serde@1.0.0","target":{"name":"serde"},"mode":"build","duration":1.25,"rmeta_time":0.5}
+{"reason":"compiler-artifact","package_id":"x"}
+{"reason":"timing-info","package_id":"app 0.1.0 (path+file:///app)","target":{"name":"app"},"mode":"build","duration":0.5}"#;
+
+        let report = TimingReport::parse_json_lines(text);
+        assert_eq!(report.units.len(), 2);
+        assert_eq!(report.units[0].name, "serde");
+        assert_eq!(report.units[0].metadata_ready(), 0.5);
+        assert!(!report.units[0].is_build_script_run());
+        assert!(!report.has_schedule());
+        assert_eq!(report.units[1].version, "0.1.0");
+    }
+}
@@ -0,0 +1,210 @@
+//! Coarse artifact caching for teams without sccache: the compiled
+//! dependencies of a profile are archived and stored under a key derived
+//! from `Cargo.lock` and the toolchain, so a cold CI runner can start from
+//! the last build with the same inputs.
+//!
+//! Stores are a local or mounted directory, `s3://bucket/prefix` (through the
+//! `aws` CLI) or `gs://bucket/prefix` (through `gcloud storage`).
+
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::utils::stable_hash;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// `[artifacts]` in atlas.toml
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ArtifactsConfig {
+    /// Directory, `s3://bucket/prefix` or `gs://bucket/prefix`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub store: Option<String>,
+    /// Profiles whose artifacts are pushed and pulled
+    #[serde(default = "default_profiles")]
+    pub profiles: Vec<String>,
+}
+
+impl Default for ArtifactsConfig {
+    fn default() -> Self {
+        Self {
+            store: None,
+            profiles: default_profiles(),
+        }
+    }
+}
+
+fn default_profiles() -> Vec<String> {
+    vec!["dev".to_string()]
+}
+
+/// Parts of a profile directory that let cargo skip work: compiled crates,
+/// build script output and fingerprints. Incremental data and the final
+/// binaries are left out.
+pub const ARCHIVED_DIRS: &[&str] = &["deps", "build", ".fingerprint"];
+
+/// Where archives are kept
+#[derive(Debug, Clone, PartialEq)]
+pub enum Store {
+    Local(PathBuf),
+    S3(String),
+    Gcs(String),
+}
+
+impl Store {
+    pub fn parse(location: &str) -> Self {
+        let location = location.trim_end_matches('/');
+        if location.starts_with("s3://") {
+            Self::S3(location.to_string())
+        } else if location.starts_with("gs://") {
+            Self::Gcs(location.to_string())
+        } else {
+            Self::Local(PathBuf::from(location))
+        }
+    }
+
+    /// CLI the store is reached through, if any
+    pub fn program(&self) -> Option<&'static str> {
+        match self {
+            Self::Local(_) => None,
+            Self::S3(_) => Some("aws"),
+            Self::Gcs(_) => Some("gcloud"),
+        }
+    }
+
+    fn url(&self, name: &str) -> String {
+        match self {
+            Self::Local(dir) => dir.join(name).display().to_string(),
+            Self::S3(prefix) | Self::Gcs(prefix) => format!("{}/{}", prefix, name),
+        }
+    }
+
+    /// Arguments of [`Self::program`] copying `from` to `to`
+    fn copy_args(&self, from: String, to: String) -> Vec<String> {
+        match self {
+            Self::Local(_) => Vec::new(),
+            Self::S3(_) => vec![
+                "s3".into(),
+                "cp".into(),
+                "--only-show-errors".into(),
+                from,
+                to,
+            ],
+            Self::Gcs(_) => vec!["storage".into(), "cp".into(), from, to],
+        }
+    }
+
+    pub fn upload_args(&self, file: &Path, name: &str) -> Vec<String> {
+        self.copy_args(file.display().to_string(), self.url(name))
+    }
+
+    pub fn download_args(&self, name: &str, file: &Path) -> Vec<String> {
+        self.copy_args(self.url(name), file.display().to_string())
+    }
+
+    /// Arguments of [`Self::program`] that succeed only if `name` exists
+    pub fn exists_args(&self, name: &str) -> Vec<String> {
+        match self {
+            Self::Local(_) => Vec::new(),
+            Self::S3(_) => vec!["s3".into(), "ls".into(), self.url(name)],
+            Self::Gcs(_) => vec!["storage".into(), "ls".into(), self.url(name)],
+        }
+    }
+
+    /// Path of `name` in a local store
+    pub fn local_path(&self, name: &str) -> Option<PathBuf> {
+        match self {
+            Self::Local(dir) => Some(dir.join(name)),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Store {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Local(dir) => write!(f, "{}", dir.display()),
+            Self::S3(url) | Self::Gcs(url) => write!(f, "{}", url),
+        }
+    }
+}
+
+/// The store from `--store`, else atlas.toml
+pub fn store(flag: Option<&str>, config: &ArtifactsConfig) -> OptimizerResult<Store> {
+    flag.or(config.store.as_deref())
+        .map(Store::parse)
+        .ok_or_else(|| {
+            OptimizerError::config(
+                "No artifact store configured; pass --store or set `store` under [artifacts] in atlas.toml",
+            )
+        })
+}
+
+/// Archive name for a profile's artifacts: changes whenever the lockfile,
+/// the compiler or the profile does
+pub fn archive_name(project: &str, profile: &str, lockfile: &str, rustc_verbose: &str) -> String {
+    let key = stable_hash(format!("{}\n{}\n{}", profile, rustc_verbose, lockfile).as_bytes());
+    format!("{}-{}-{}.tar.gz", project, profile, key)
+}
+
+/// `tar` arguments archiving the [`ARCHIVED_DIRS`] of `profile_dir` that exist
+pub fn pack_args(profile_dir: &Path, archive: &Path) -> Vec<String> {
+    let mut args = vec![
+        "-czf".to_string(),
+        archive.display().to_string(),
+        "-C".to_string(),
+        profile_dir.display().to_string(),
+    ];
+    args.extend(
+        ARCHIVED_DIRS
+            .iter()
+            .filter(|dir| profile_dir.join(dir).is_dir())
+            .map(|dir| dir.to_string()),
+    );
+    args
+}
+
+/// `tar` arguments unpacking an archive into `profile_dir`
+pub fn unpack_args(profile_dir: &Path, archive: &Path) -> Vec<String> {
+    vec![
+        "-xzf".to_string(),
+        archive.display().to_string(),
+        "-C".to_string(),
+        profile_dir.display().to_string(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stores_and_keys() {
+        let s3 = Store::parse("s3://ci-cache/atlas/");
+        assert_eq!(s3.program(), Some("aws"));
+        assert_eq!(
+            s3.upload_args(Path::new("/tmp/a.tar.gz"), "a.tar.gz"),
+            vec![
+                "s3",
+                "cp",
+                "--only-show-errors",
+                "/tmp/a.tar.gz",
+                "s3://ci-cache/atlas/a.tar.gz"
+            ]
+        );
+        let gcs = Store::parse("gs://bucket");
+        assert_eq!(
+            gcs.exists_args("a.tar.gz"),
+            vec!["storage", "ls", "gs://bucket/a.tar.gz"]
+        );
+        let local = Store::parse("/mnt/cache");
+        assert_eq!(local.program(), None);
+        assert_eq!(
+            local.local_path("a.tar.gz"),
+            Some(PathBuf::from("/mnt/cache/a.tar.gz"))
+        );
+
+        let name = archive_name("app", "dev", "lock v1", "rustc 1.78.0");
+        assert!(name.starts_with("app-dev-") && name.ends_with(".tar.gz"));
+        assert_eq!(name, archive_name("app", "dev", "lock v1", "rustc 1.78.0"));
+        assert_ne!(name, archive_name("app", "dev", "lock v2", "rustc 1.78.0"));
+        assert_ne!(name, archive_name("app", "dev", "lock v1", "rustc 1.79.0"));
+    }
+}
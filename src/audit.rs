@@ -0,0 +1,161 @@
+//! Audit trail of the files Atlas changes
+//!
+//! Every file Atlas creates, modifies or removes on the user's behalf, such
+//! as `.cargo/config.toml`, `Cargo.toml`, editor settings or scheduler
+//! entries, is recorded in `.atlas/audit.jsonl` of the project with its
+//! SHA-256 before and after and the command that changed it. Outside a
+//! project the trail is kept next to the Atlas config. Atlas's own state,
+//! caches and logs are not recorded, and `.atlas` is ignored by git.
+
+use crate::checksum::sha256_hex;
+use crate::error::OptimizerResult;
+use crate::utils::create_parent_dirs;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Where changes are recorded, and the command making them
+struct Trail {
+    log: PathBuf,
+    command: String,
+}
+
+static TRAIL: OnceLock<Trail> = OnceLock::new();
+
+/// One change to a file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub recorded_at: DateTime<Utc>,
+    pub path: PathBuf,
+    /// SHA-256 of the previous content; `None` when the file was created
+    pub before: Option<String>,
+    /// SHA-256 of the new content; `None` when the file was removed
+    pub after: Option<String>,
+    /// Command line of the Atlas run that made the change
+    pub command: String,
+}
+
+impl AuditEntry {
+    pub fn change(&self) -> &'static str {
+        match (&self.before, &self.after) {
+            (None, _) => "created",
+            (_, None) => "removed",
+            _ => "modified",
+        }
+    }
+}
+
+/// The audit trail of `project_root`, or the machine-wide one without a project
+pub fn log_path(project_root: Option<&Path>) -> OptimizerResult<PathBuf> {
+    Ok(match project_root {
+        Some(root) => root.join(".atlas").join("audit.jsonl"),
+        None => crate::config::OptimizerConfig::get_config_path()?.with_file_name("audit.jsonl"),
+    })
+}
+
+/// Record changes made from now on in `log`, as made by `command`
+pub fn init(log: PathBuf, command: String) {
+    let _ = TRAIL.set(Trail { log, command });
+}
+
+/// Write `content` to `path` and record the change
+pub fn write(path: &Path, content: impl AsRef<[u8]>) -> OptimizerResult<()> {
+    write_in(TRAIL.get(), path, content.as_ref())
+}
+
+fn write_in(trail: Option<&Trail>, path: &Path, content: &[u8]) -> OptimizerResult<()> {
+    let before = hash_of(path);
+    fs::write(path, content)?;
+    record(trail, path, before, Some(sha256_hex(content)));
+    Ok(())
+}
+
+/// Remove `path` and record the change
+pub fn remove(path: &Path) -> OptimizerResult<()> {
+    remove_in(TRAIL.get(), path)
+}
+
+fn remove_in(trail: Option<&Trail>, path: &Path) -> OptimizerResult<()> {
+    let before = hash_of(path);
+    fs::remove_file(path)?;
+    record(trail, path, before, None);
+    Ok(())
+}
+
+fn hash_of(path: &Path) -> Option<String> {
+    fs::read(path).ok().map(|content| sha256_hex(&content))
+}
+
+/// Failing to record never fails the change itself
+fn record(trail: Option<&Trail>, path: &Path, before: Option<String>, after: Option<String>) {
+    let Some(trail) = trail else {
+        return;
+    };
+    if before == after {
+        return;
+    }
+    let entry = AuditEntry {
+        recorded_at: Utc::now(),
+        path: fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()),
+        before,
+        after,
+        command: trail.command.clone(),
+    };
+    if let Err(e) = append(&trail.log, &entry) {
+        log::warn!("Could not record the change to {}: {}", path.display(), e);
+    }
+}
+
+fn append(log: &Path, entry: &AuditEntry) -> OptimizerResult<()> {
+    create_parent_dirs(log)?;
+    let mut file = OpenOptions::new().create(true).append(true).open(log)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+/// Entries of the trail at `log`, oldest first; lines that do not parse are skipped
+pub fn load(log: &Path) -> OptimizerResult<Vec<AuditEntry>> {
+    if !log.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(fs::read_to_string(log)?
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changes_are_recorded_with_hashes() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join(".atlas").join("audit.jsonl");
+        let trail = Trail {
+            log: log.clone(),
+            command: "atlas init".to_string(),
+        };
+
+        let path = dir.path().join("Cargo.toml");
+        write_in(Some(&trail), &path, b"[package]\n").unwrap();
+        write_in(Some(&trail), &path, b"[package]\n").unwrap();
+        write_in(Some(&trail), &path, b"[package]\nname = \"demo\"\n").unwrap();
+        remove_in(Some(&trail), &path).unwrap();
+
+        let entries = load(&log).unwrap();
+        let changes: Vec<&str> = entries.iter().map(AuditEntry::change).collect();
+        // Writing the same content again is not a change
+        assert_eq!(changes, vec!["created", "modified", "removed"]);
+        assert_eq!(
+            entries[0].after.as_deref(),
+            Some(sha256_hex(b"[package]\n").as_str())
+        );
+        assert_eq!(entries[1].before, entries[0].after);
+        // The trail stays out of the project's commits
+        assert!(dir.path().join(".atlas").join(".gitignore").exists());
+    }
+}
@@ -0,0 +1,146 @@
+//! Builds that stay out of the way of foreground work
+//!
+//! A background build runs cargo with fewer jobs, at the lowest CPU priority
+//! and, on Linux, with idle I/O priority on the last cores of the machine.
+//! Child processes inherit all of it, so it covers cargo-watch and every
+//! cargo and rustc it starts.
+
+use std::fmt;
+
+/// CPU priority of background builds, the lowest there is
+pub const NICENESS: i32 = 19;
+
+/// Parallel jobs for a background build: the configured number, or half the
+/// cores, leaving the rest to the editor and everything else in the foreground
+pub fn jobs(cores: usize, configured: Option<usize>) -> usize {
+    let cores = cores.max(1);
+    configured.unwrap_or(cores / 2).clamp(1, cores)
+}
+
+/// Cores a background build is pinned to: the last `jobs` of them, as
+/// interactive work tends to be scheduled on the first
+pub fn cores(cores: usize, jobs: usize) -> Vec<usize> {
+    (cores.saturating_sub(jobs)..cores).collect()
+}
+
+/// What [`enter`] managed to apply
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Limits {
+    pub jobs: usize,
+    pub niceness: Option<i32>,
+    pub idle_io: bool,
+    pub cores: Vec<usize>,
+}
+
+impl fmt::Display for Limits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} job(s)", self.jobs)?;
+        if let Some(niceness) = self.niceness {
+            write!(f, ", niceness {}", niceness)?;
+        }
+        if self.idle_io {
+            write!(f, ", idle I/O")?;
+        }
+        match (self.cores.first(), self.cores.last()) {
+            (Some(first), Some(last)) if first == last => write!(f, ", core {}", first)?,
+            (Some(first), Some(last)) => write!(f, ", cores {}-{}", first, last)?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Limit this process, and so every build it starts from now on
+pub fn enter(configured_jobs: Option<usize>) -> Limits {
+    let cpus = std::thread::available_parallelism().map_or(1, |cores| cores.get());
+    // Never more jobs than a limit already in place, such as on battery
+    let limit = std::env::var("CARGO_BUILD_JOBS")
+        .ok()
+        .and_then(|jobs| jobs.parse().ok())
+        .filter(|&jobs| jobs > 0)
+        .unwrap_or(usize::MAX);
+    let jobs = jobs(cpus, configured_jobs).min(limit);
+    std::env::set_var("CARGO_BUILD_JOBS", jobs.to_string());
+    Limits {
+        jobs,
+        niceness: lower_priority().then_some(NICENESS),
+        idle_io: idle_io(),
+        cores: pin(&cores(cpus, jobs)),
+    }
+}
+
+#[cfg(unix)]
+fn lower_priority() -> bool {
+    unsafe { libc::setpriority(libc::PRIO_PROCESS as _, 0, NICENESS) == 0 }
+}
+
+#[cfg(not(unix))]
+fn lower_priority() -> bool {
+    false
+}
+
+#[cfg(target_os = "linux")]
+fn idle_io() -> bool {
+    const IOPRIO_WHO_PROCESS: libc::c_long = 1;
+    const IOPRIO_CLASS_IDLE: libc::c_long = 3;
+    const IOPRIO_CLASS_SHIFT: libc::c_long = 13;
+    unsafe {
+        libc::syscall(
+            libc::SYS_ioprio_set,
+            IOPRIO_WHO_PROCESS,
+            0,
+            IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT,
+        ) == 0
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn idle_io() -> bool {
+    false
+}
+
+/// Pin to `cores`, returning them when the platform allows it
+#[cfg(target_os = "linux")]
+fn pin(cores: &[usize]) -> Vec<usize> {
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for &core in cores {
+            libc::CPU_SET(core, &mut set);
+        }
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) == 0 {
+            cores.to_vec()
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pin(_cores: &[usize]) -> Vec<usize> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_background_jobs_and_cores() {
+        assert_eq!(jobs(16, None), 8);
+        assert_eq!(jobs(1, None), 1);
+        assert_eq!(jobs(8, Some(2)), 2);
+        assert_eq!(jobs(4, Some(32)), 4);
+        assert_eq!(cores(8, 3), vec![5, 6, 7]);
+
+        let limits = Limits {
+            jobs: 3,
+            niceness: Some(NICENESS),
+            idle_io: true,
+            cores: cores(8, 3),
+        };
+        assert_eq!(
+            limits.to_string(),
+            "3 job(s), niceness 19, idle I/O, cores 5-7"
+        );
+    }
+}
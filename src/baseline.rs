@@ -0,0 +1,384 @@
+use crate::config::OptimizerConfig;
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::fingerprint::Fingerprint;
+use crate::significance::Samples;
+use crate::thermal;
+use crate::utils::*;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// A named set of performance measurements for a project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Baseline {
+    pub name: String,
+    pub recorded_at: DateTime<Utc>,
+    pub metadata: BaselineMetadata,
+    pub measurements: Measurements,
+}
+
+/// Environment the measurements were taken in
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BaselineMetadata {
+    #[serde(flatten)]
+    pub fingerprint: Fingerprint,
+    /// Value of each setting `atlas explain` knows, when recorded
+    #[serde(default)]
+    pub settings: BTreeMap<String, String>,
+}
+
+/// Wall-clock durations in seconds plus binary sizes in bytes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Measurements {
+    pub clean_build_secs: Option<f64>,
+    pub incremental_build_secs: Option<f64>,
+    pub check_secs: Option<f64>,
+    pub test_secs: Option<f64>,
+    #[serde(default)]
+    pub binary_sizes: BTreeMap<String, u64>,
+    /// Steps that ran while the CPU was thermally throttled, with what was seen
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub throttled: BTreeMap<String, String>,
+    /// Every run of each step in seconds, when it ran more than once; the
+    /// durations above are the medians without outliers
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub samples: BTreeMap<String, Vec<f64>>,
+}
+
+/// Measured runs of each step unless asked otherwise; the fewest that give a
+/// confidence interval for both sides of a comparison
+pub const DEFAULT_ITERATIONS: u32 = 3;
+
+/// Which steps to include when measuring
+#[derive(Debug, Clone, Copy)]
+pub struct MeasureOptions {
+    pub clean_build: bool,
+    pub tests: bool,
+    /// Longest pause before each measured step for the CPU to cool down
+    pub cooldown: Option<Duration>,
+    /// Measured runs of each step
+    pub iterations: usize,
+    /// Unmeasured runs of each step before those
+    pub warmup: usize,
+}
+
+impl Default for MeasureOptions {
+    fn default() -> Self {
+        Self {
+            clean_build: true,
+            tests: true,
+            cooldown: None,
+            iterations: DEFAULT_ITERATIONS as usize,
+            warmup: 0,
+        }
+    }
+}
+
+impl Measurements {
+    /// Named durations in a fixed display order
+    pub fn durations(&self) -> Vec<(&'static str, Option<f64>)> {
+        vec![
+            ("Clean build", self.clean_build_secs),
+            ("Incremental build", self.incremental_build_secs),
+            ("Check", self.check_secs),
+            ("Test", self.test_secs),
+        ]
+    }
+
+    /// Runs of a step without outliers: every run when it ran more than
+    /// once, otherwise its single duration
+    pub fn samples(&self, label: &str, duration: Option<f64>) -> Samples {
+        match self.samples.get(label) {
+            Some(runs) => Samples::new(runs.clone()).without_outliers(),
+            None => Samples::new(duration.into_iter().collect()),
+        }
+    }
+}
+
+impl BaselineMetadata {
+    pub fn capture(project_root: &Path, config: &OptimizerConfig) -> OptimizerResult<Self> {
+        Ok(Self {
+            fingerprint: Fingerprint::capture(project_root, config)?,
+            settings: crate::knowledge::snapshot(project_root),
+        })
+    }
+}
+
+impl Baseline {
+    /// Directory holding all baselines for a project
+    pub fn directory(project_root: &Path) -> PathBuf {
+        project_root.join(".atlas").join("baselines")
+    }
+
+    fn path(project_root: &Path, name: &str) -> PathBuf {
+        Self::directory(project_root).join(format!("{}.json", name))
+    }
+
+    pub fn save(&self, project_root: &Path) -> OptimizerResult<PathBuf> {
+        validate_name(&self.name)?;
+        let path = Self::path(project_root, &self.name);
+        create_parent_dirs(&path)?;
+        fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(path)
+    }
+
+    pub fn load(project_root: &Path, name: &str) -> OptimizerResult<Self> {
+        validate_name(name)?;
+        let path = Self::path(project_root, name);
+        let content = fs::read_to_string(&path)
+            .map_err(|_| OptimizerError::file_not_found(path.display().to_string()))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// All stored baselines, oldest first
+    pub fn list(project_root: &Path) -> OptimizerResult<Vec<Self>> {
+        let dir = Self::directory(project_root);
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut baselines = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            match fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<Self>(&content).ok())
+            {
+                Some(baseline) => baselines.push(baseline),
+                None => log::warn!("Skipping unreadable baseline: {}", path.display()),
+            }
+        }
+        baselines.sort_by_key(|baseline| baseline.recorded_at);
+        Ok(baselines)
+    }
+}
+
+pub(crate) fn validate_name(name: &str) -> OptimizerResult<()> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+        && !name.starts_with('.');
+
+    if valid {
+        Ok(())
+    } else {
+        Err(OptimizerError::invalid_input(format!(
+            "Invalid baseline name '{}': use letters, digits, '-', '_' or '.'",
+            name
+        )))
+    }
+}
+
+/// Run the measurement suite against a project
+pub fn measure(project_root: &Path, options: MeasureOptions) -> OptimizerResult<Measurements> {
+    let mut steps = Steps::new(project_root, options);
+    let mut measurements = Measurements::default();
+    let touch = |_: &mut Steps| touch_source_file(project_root).map(|_| ());
+
+    if options.clean_build {
+        measurements.clean_build_secs = steps.sample(
+            "Clean build",
+            &["build", "--workspace"],
+            |steps: &mut Steps| steps.prepare("Cleaning target directory", &["clean"]),
+        )?;
+    } else {
+        // Make sure the incremental measurement starts from a warm cache
+        steps.prepare("Warming build cache", &["build", "--workspace"])?;
+    }
+
+    measurements.incremental_build_secs =
+        steps.sample("Incremental build", &["build", "--workspace"], touch)?;
+    measurements.check_secs = steps.sample("Check", &["check", "--workspace"], touch)?;
+    if options.tests {
+        measurements.test_secs = steps.sample("Test", &["test", "--workspace"], touch)?;
+    }
+
+    measurements.binary_sizes = collect_binary_sizes(&project_root.join("target").join("debug"))?;
+    measurements.throttled = steps.throttled;
+    measurements.samples = steps.samples;
+
+    Ok(measurements)
+}
+
+/// Runs the cargo commands of a measurement, watching for thermal throttling
+struct Steps<'a> {
+    project_root: &'a Path,
+    options: MeasureOptions,
+    /// CPU temperature before anything ran, the target of cooling down
+    idle_temperature: Option<f64>,
+    ran: bool,
+    throttled: BTreeMap<String, String>,
+    samples: BTreeMap<String, Vec<f64>>,
+}
+
+impl<'a> Steps<'a> {
+    fn new(project_root: &'a Path, options: MeasureOptions) -> Self {
+        Self {
+            project_root,
+            options,
+            idle_temperature: thermal::Sample::take().temperature,
+            ran: false,
+            throttled: BTreeMap::new(),
+            samples: BTreeMap::new(),
+        }
+    }
+
+    /// Run a step after its warm-up runs as often as asked, each time after
+    /// `setup`, and return the median duration without outliers
+    fn sample(
+        &mut self,
+        label: &str,
+        args: &[&str],
+        setup: impl Fn(&mut Self) -> OptimizerResult<()>,
+    ) -> OptimizerResult<Option<f64>> {
+        let (warmup, iterations) = (self.options.warmup, self.options.iterations.max(1));
+        for run in 1..=warmup {
+            setup(self)?;
+            self.prepare(&format!("{} (warm-up {}/{})", label, run, warmup), args)?;
+        }
+        let mut runs = Vec::with_capacity(iterations);
+        for run in 1..=iterations {
+            setup(self)?;
+            let run_label = if iterations > 1 {
+                format!("{} (run {}/{})", label, run, iterations)
+            } else {
+                label.to_string()
+            };
+            runs.push(self.measure(label, &run_label, args)?);
+        }
+        let median = Samples::new(runs.clone()).without_outliers().median();
+        if iterations > 1 {
+            self.samples.insert(label.to_string(), runs);
+        }
+        Ok(median)
+    }
+
+    /// Run a step whose duration does not count
+    fn prepare(&mut self, label: &str, args: &[&str]) -> OptimizerResult<()> {
+        self.ran = true;
+        timed_step(label, self.project_root, args).map(|_| ())
+    }
+
+    /// Run a measured step, after cooling down from the one before
+    fn measure(&mut self, label: &str, run_label: &str, args: &[&str]) -> OptimizerResult<f64> {
+        if let Some(cooldown) = self.options.cooldown.filter(|_| self.ran) {
+            let spinner = create_spinner("Cooling down...");
+            let waited = thermal::cool_down(cooldown, self.idle_temperature);
+            spinner.finish_and_clear();
+            print_status(&format!("Cooled down for {}", format_duration(waited)));
+        }
+        self.ran = true;
+
+        let monitor = thermal::Monitor::start();
+        let secs = timed_step(run_label, self.project_root, args);
+        let report = monitor.finish();
+        if report.throttled() {
+            print_warning(&format!(
+                "{} ran while the CPU was throttled ({}); its time is not comparable",
+                run_label, report
+            ));
+            self.throttled.insert(label.to_string(), report.to_string());
+        }
+        secs
+    }
+}
+
+fn timed_step(label: &str, project_root: &Path, args: &[&str]) -> OptimizerResult<f64> {
+    let spinner = create_spinner(&format!("{}...", label));
+    let start = Instant::now();
+    let output = execute_command("cargo", args, Some(project_root));
+    let elapsed = start.elapsed();
+    spinner.finish_and_clear();
+
+    let output = output?;
+    if !output.status.success() {
+        return Err(OptimizerError::build_failed(format!(
+            "cargo {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    print_status(&format!("{}: {}", label, format_duration(elapsed)));
+    Ok(elapsed.as_secs_f64())
+}
+
+/// Rewrite a source file in place so cargo sees it as modified
+pub fn touch_source_file(project_root: &Path) -> OptimizerResult<PathBuf> {
+    let candidates = [
+        project_root.join("src").join("lib.rs"),
+        project_root.join("src").join("main.rs"),
+    ];
+
+    let path = candidates
+        .into_iter()
+        .find(|path| path.exists())
+        .or_else(|| {
+            walkdir::WalkDir::new(project_root)
+                .into_iter()
+                .filter_entry(|entry| {
+                    let name = entry.file_name().to_string_lossy();
+                    name != "target" && !name.starts_with('.')
+                })
+                .filter_map(Result::ok)
+                .find(|entry| {
+                    entry.file_type().is_file()
+                        && entry.path().extension().and_then(|ext| ext.to_str()) == Some("rs")
+                        && entry.file_name() != "build.rs"
+                })
+                .map(|entry| entry.into_path())
+        })
+        .ok_or_else(|| OptimizerError::file_not_found("No Rust source file to touch"))?;
+
+    let content = fs::read(&path)?;
+    fs::write(&path, content)?;
+    Ok(path)
+}
+
+/// Sizes of the executables in a target profile directory
+pub fn collect_binary_sizes(profile_dir: &Path) -> OptimizerResult<BTreeMap<String, u64>> {
+    let mut sizes = BTreeMap::new();
+    if !profile_dir.exists() {
+        return Ok(sizes);
+    }
+
+    for entry in fs::read_dir(profile_dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_file() && is_executable(&entry.path(), &metadata) {
+            sizes.insert(
+                entry.file_name().to_string_lossy().to_string(),
+                metadata.len(),
+            );
+        }
+    }
+
+    Ok(sizes)
+}
+
+#[cfg(unix)]
+fn is_executable(_path: &Path, metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path, _metadata: &fs::Metadata) -> bool {
+    path.extension().and_then(|ext| ext.to_str()) == Some("exe")
+}
+
+/// Percentage change from `before` to `after`
+pub fn percent_change(before: f64, after: f64) -> Option<f64> {
+    if before == 0.0 {
+        None
+    } else {
+        Some((after - before) / before * 100.0)
+    }
+}
@@ -0,0 +1,306 @@
+//! Workloads for Atlas's own hot paths, shared by `benches/` and the hidden
+//! `atlas --bench-self` command so contributors can spot regressions.
+//!
+//! Inputs are synthesized, so results do not depend on the network or on the
+//! project Atlas runs in. Only system detection touches the real machine.
+
+use crate::analysis::metadata::CargoMetadata;
+use crate::analysis::timings::TimingReport;
+use crate::config::{generate_cargo_config, generate_cargo_profiles, ConfigScope, OptimizerConfig};
+use crate::error::OptimizerResult;
+use crate::host::Host;
+use crate::system::SystemInfo;
+use crate::utils::get_directory_size;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+/// Packages in the synthesized `cargo metadata` output
+const METADATA_PACKAGES: usize = 400;
+/// Units in the synthesized timing report
+const TIMING_UNITS: usize = 400;
+/// Files in the scanned directory tree, spread over a few levels
+const SCAN_FILES: usize = 2000;
+
+type Body = Box<dyn FnMut() -> OptimizerResult<()>>;
+
+/// One measured operation
+pub struct Workload {
+    pub name: &'static str,
+    body: Body,
+}
+
+impl Workload {
+    fn new(name: &'static str, body: impl FnMut() -> OptimizerResult<()> + 'static) -> Self {
+        Self {
+            name,
+            body: Box::new(body),
+        }
+    }
+
+    pub fn run(&mut self) -> OptimizerResult<()> {
+        (self.body)()
+    }
+}
+
+/// Every workload plus the scratch directory their inputs live in
+pub struct Workloads {
+    pub workloads: Vec<Workload>,
+    _scratch: TempDir,
+}
+
+/// Build every workload and its inputs
+pub fn workloads() -> OptimizerResult<Workloads> {
+    let scratch = tempfile::tempdir()?;
+    let system_info = SystemInfo::detect()?;
+    let config = OptimizerConfig::default();
+    let config_toml = toml::to_string_pretty(&config)?;
+    let metadata_json = metadata_fixture(METADATA_PACKAGES);
+    let timings_html = timings_html_fixture(TIMING_UNITS);
+    let timings_lines = timings_lines_fixture(TIMING_UNITS);
+    let scan_root = scratch.path().join("target");
+    write_tree_fixture(&scan_root, SCAN_FILES)?;
+
+    let generation_info = system_info.clone();
+    let workloads = vec![
+        Workload::new("config_load", move || {
+            let config: OptimizerConfig = toml::from_str(&config_toml)?;
+            config.validate()
+        }),
+        Workload::new("config_generation", move || {
+            std::hint::black_box(generate_cargo_config(
+                &config,
+                &generation_info,
+                ConfigScope::Project,
+            ));
+            std::hint::black_box(generate_cargo_profiles(&config, &generation_info.os));
+            Ok(())
+        }),
+        Workload::new("system_detection", || {
+            std::hint::black_box(SystemInfo::detect_with(&Host));
+            Ok(())
+        }),
+        Workload::new("system_detection_cached", || {
+            std::hint::black_box(SystemInfo::detect()?);
+            Ok(())
+        }),
+        Workload::new("metadata_parse", move || {
+            let metadata = CargoMetadata::parse(&metadata_json)?;
+            std::hint::black_box(metadata.dependency_graph(false));
+            Ok(())
+        }),
+        Workload::new("timings_parse_html", move || {
+            std::hint::black_box(TimingReport::parse_html(&timings_html)?.crate_times());
+            Ok(())
+        }),
+        Workload::new("timings_parse_json", move || {
+            std::hint::black_box(TimingReport::parse_json_lines(&timings_lines).wall_time());
+            Ok(())
+        }),
+        Workload::new("directory_scan", move || {
+            std::hint::black_box(get_directory_size(&scan_root)?);
+            Ok(())
+        }),
+    ];
+    Ok(Workloads {
+        workloads,
+        _scratch: scratch,
+    })
+}
+
+/// Median duration of `iterations` runs after one warm-up run
+pub fn measure(workload: &mut Workload, iterations: usize) -> OptimizerResult<Duration> {
+    workload.run()?;
+    let mut samples = Vec::with_capacity(iterations);
+    for _ in 0..iterations.max(1) {
+        let start = Instant::now();
+        workload.run()?;
+        samples.push(start.elapsed());
+    }
+    samples.sort();
+    Ok(samples[samples.len() / 2])
+}
+
+/// Slowdown against saved results reported as a regression
+pub const REGRESSION_THRESHOLD: f64 = 1.2;
+
+/// Medians saved with `atlas --bench-self --save`, to compare later runs against
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SavedResults {
+    pub atlas_version: String,
+    /// Median per workload in nanoseconds
+    pub medians: BTreeMap<String, u64>,
+}
+
+impl SavedResults {
+    pub fn path() -> OptimizerResult<PathBuf> {
+        Ok(OptimizerConfig::get_config_path()?.with_file_name("bench-self.json"))
+    }
+
+    pub fn load() -> OptimizerResult<Option<Self>> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&fs::read_to_string(path)?)?))
+    }
+
+    pub fn save(&self) -> OptimizerResult<()> {
+        let path = Self::path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Ratio of `median` to the saved median of `name`, if there is one
+    pub fn ratio(&self, name: &str, median: Duration) -> Option<f64> {
+        let saved = *self.medians.get(name)?;
+        (saved > 0).then(|| median.as_nanos() as f64 / saved as f64)
+    }
+}
+
+/// `cargo metadata` output for a workspace member depending on a chain of
+/// registry packages, each with a few dependencies
+fn metadata_fixture(packages: usize) -> String {
+    let id = |i: usize| {
+        format!(
+            "registry+https://github.com/rust-lang/crates.io-index#dep{}@1.0.{}",
+            i, i
+        )
+    };
+    let root = "path+file:///bench/app#0.1.0".to_string();
+
+    let mut package_list = vec![json!({
+        "id": root,
+        "name": "app",
+        "version": "0.1.0",
+        "targets": [{ "name": "app", "kind": ["bin"] }],
+        "manifest_path": "/bench/app/Cargo.toml"
+    })];
+    let mut nodes = vec![json!({
+        "id": root,
+        "deps": (0..packages.min(20)).map(|i| json!({ "pkg": id(i), "dep_kinds": [{ "kind": null }] })).collect::<Vec<_>>()
+    })];
+    for i in 0..packages {
+        package_list.push(json!({
+            "id": id(i),
+            "name": format!("dep{}", i),
+            "version": format!("1.0.{}", i),
+            "targets": [{ "name": format!("dep{}", i), "kind": ["lib"] }]
+        }));
+        let deps: Vec<_> = (i + 1..packages)
+            .step_by(7)
+            .take(4)
+            .map(|dep| json!({ "pkg": id(dep), "dep_kinds": [{ "kind": null }] }))
+            .collect();
+        nodes.push(json!({ "id": id(i), "deps": deps }));
+    }
+
+    json!({
+        "packages": package_list,
+        "workspace_members": [root],
+        "resolve": { "nodes": nodes },
+        "target_directory": "/bench/app/target",
+        "workspace_root": "/bench/app"
+    })
+    .to_string()
+}
+
+fn timing_unit(i: usize, units: usize) -> serde_json::Value {
+    json!({
+        "i": i,
+        "name": format!("dep{}", i),
+        "version": "1.0.0",
+        "mode": "todo",
+        "target": "",
+        "start": i as f64 * 0.1,
+        "duration": 0.5 + (i % 13) as f64 * 0.25,
+        "unblocked_units": if i + 1 < units { vec![i + 1] } else { vec![] },
+        "unblocked_rmeta_units": [],
+        "sections": [["frontend", { "start": 0.0, "end": 0.4 }], ["codegen", { "start": 0.4, "end": 0.5 }]]
+    })
+}
+
+/// A `cargo-timing.html` report with its embedded unit table
+fn timings_html_fixture(units: usize) -> String {
+    let data: Vec<_> = (0..units).map(|i| timing_unit(i, units)).collect();
+    format!(
+        "<html><body><script>\nconst UNIT_DATA = {};\nconst CONCURRENCY_DATA = [];\n</script></body></html>",
+        serde_json::Value::Array(data)
+    )
+}
+
+/// `cargo build --timings=json` output mixed with other messages
+fn timings_lines_fixture(units: usize) -> String {
+    (0..units)
+        .flat_map(|i| {
+            [
+                json!({
+                    "reason": "timing-info",
+                    "package_id": format!("registry+https://github.com/rust-lang/crates.io-index#dep{}@1.0.0", i),
+                    "target": { "name": format!("dep{}", i) },
+                    "mode": "build",
+                    "duration": 0.5 + (i % 13) as f64 * 0.25,
+                    "rmeta_time": 0.25
+                })
+                .to_string(),
+                json!({ "reason": "compiler-artifact", "package_id": format!("dep{}", i), "fresh": false })
+                    .to_string(),
+            ]
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A target-directory-like tree of small files
+fn write_tree_fixture(root: &std::path::Path, files: usize) -> OptimizerResult<()> {
+    for i in 0..files {
+        let dir = root
+            .join(format!("debug-{}", i % 4))
+            .join(format!("deps-{}", i % 25));
+        fs::create_dir_all(&dir)?;
+        fs::write(
+            dir.join(format!("unit-{}.rlib", i)),
+            vec![0u8; 64 + i % 512],
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixtures_parse() {
+        let metadata = CargoMetadata::parse(&metadata_fixture(30)).unwrap();
+        assert_eq!(metadata.packages.len(), 31);
+        assert_eq!(metadata.members().count(), 1);
+
+        let report = TimingReport::parse_html(&timings_html_fixture(10)).unwrap();
+        assert_eq!(report.units.len(), 10);
+        assert_eq!(
+            TimingReport::parse_json_lines(&timings_lines_fixture(10))
+                .units
+                .len(),
+            10
+        );
+    }
+
+    #[test]
+    fn test_workloads_run() {
+        let mut workloads = workloads().unwrap();
+        for workload in &mut workloads.workloads {
+            workload.run().unwrap();
+        }
+        let names: Vec<&str> = workloads.workloads.iter().map(|w| w.name).collect();
+        assert!(names.contains(&"metadata_parse"));
+        assert!(names.contains(&"directory_scan"));
+    }
+}
@@ -0,0 +1,181 @@
+//! Detection of Bazel and Buck builds that compile the Rust code instead of Cargo
+
+use crate::error::OptimizerResult;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Rule invocations that mean a BUILD or BUCK file builds Rust
+const RUST_RULES: &[&str] = &[
+    "rust_library(",
+    "rust_binary(",
+    "rust_test(",
+    "rust_proc_macro(",
+];
+
+/// Directories never searched for build files
+const SKIPPED_DIRS: &[&str] = &["target", ".git", "node_modules", "buck-out"];
+
+/// How deep below the build root to look for build files
+const MAX_DEPTH: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForeignBuildSystem {
+    Bazel,
+    Buck2,
+}
+
+impl ForeignBuildSystem {
+    /// Files marking the root of a build of this kind
+    fn root_markers(self) -> &'static [&'static str] {
+        match self {
+            Self::Bazel => &["MODULE.bazel", "WORKSPACE.bazel", "WORKSPACE"],
+            Self::Buck2 => &[".buckconfig"],
+        }
+    }
+
+    fn build_files(self) -> &'static [&'static str] {
+        match self {
+            Self::Bazel => &["BUILD.bazel", "BUILD"],
+            Self::Buck2 => &["BUCK", "TARGETS"],
+        }
+    }
+}
+
+impl fmt::Display for ForeignBuildSystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bazel => write!(f, "Bazel"),
+            Self::Buck2 => write!(f, "Buck2"),
+        }
+    }
+}
+
+/// A Bazel or Buck build with Rust rules around the Cargo project
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForeignBuild {
+    pub system: ForeignBuildSystem,
+    /// Directory holding the root marker, e.g. `MODULE.bazel`
+    pub root: PathBuf,
+    /// First build file found with a Rust rule
+    pub rust_rules: PathBuf,
+}
+
+impl ForeignBuild {
+    /// Look for a Bazel or Buck build with Rust rules in `project_root` or the
+    /// directories above it, stopping at the repository root
+    pub fn detect(project_root: &Path) -> OptimizerResult<Option<Self>> {
+        let project_root = project_root
+            .canonicalize()
+            .unwrap_or_else(|_| project_root.to_path_buf());
+
+        for dir in project_root.ancestors() {
+            for system in [ForeignBuildSystem::Bazel, ForeignBuildSystem::Buck2] {
+                let marked = system
+                    .root_markers()
+                    .iter()
+                    .any(|marker| dir.join(marker).is_file());
+                if !marked {
+                    continue;
+                }
+                if let Some(rust_rules) = find_rust_rules(dir, system, 0)? {
+                    return Ok(Some(Self {
+                        system,
+                        root: dir.to_path_buf(),
+                        rust_rules,
+                    }));
+                }
+            }
+            if dir.join(".git").exists() {
+                break;
+            }
+        }
+        Ok(None)
+    }
+}
+
+fn find_rust_rules(
+    dir: &Path,
+    system: ForeignBuildSystem,
+    depth: usize,
+) -> OptimizerResult<Option<PathBuf>> {
+    for name in system.build_files() {
+        let path = dir.join(name);
+        if path.is_file() {
+            let content = fs::read_to_string(&path)?;
+            if RUST_RULES.iter().any(|rule| content.contains(rule)) {
+                return Ok(Some(path));
+            }
+        }
+    }
+    if depth >= MAX_DEPTH {
+        return Ok(None);
+    }
+
+    let mut subdirs: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_dir()))
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            // Bazel's convenience symlinks are named bazel-<workspace>
+            !SKIPPED_DIRS.contains(&name.as_str()) && !name.starts_with("bazel-")
+        })
+        .map(|entry| entry.path())
+        .collect();
+    subdirs.sort();
+    for subdir in subdirs {
+        if let Some(found) = find_rust_rules(&subdir, system, depth + 1)? {
+            return Ok(Some(found));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_detects_bazel_rust_rules_above_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join(".git")).unwrap();
+        write(root, "MODULE.bazel", "bazel_dep(name = \"rules_rust\")\n");
+        write(
+            root,
+            "services/api/Cargo.toml",
+            "[package]\nname = \"api\"\n",
+        );
+        write(
+            root,
+            "services/api/BUILD.bazel",
+            "rust_binary(\n    name = \"api\",\n)\n",
+        );
+
+        let found = ForeignBuild::detect(&root.join("services/api"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.system, ForeignBuildSystem::Bazel);
+        assert!(found.rust_rules.ends_with("services/api/BUILD.bazel"));
+    }
+
+    #[test]
+    fn test_ignores_builds_without_rust_rules() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join(".git")).unwrap();
+        write(root, ".buckconfig", "[repositories]\n");
+        write(root, "web/BUCK", "js_library(name = \"web\")\n");
+        assert_eq!(ForeignBuild::detect(root).unwrap(), None);
+
+        write(root, "cli/BUCK", "rust_binary(name = \"cli\")\n");
+        let found = ForeignBuild::detect(root).unwrap().unwrap();
+        assert_eq!(found.system, ForeignBuildSystem::Buck2);
+    }
+}
@@ -0,0 +1,180 @@
+use crate::commands::tools::is_installable;
+use crate::config::OptimizerConfig;
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::system::SystemInfo;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Bundle format understood by this Atlas
+pub const BUNDLE_FORMAT: u32 = 1;
+
+/// The global Atlas config plus the tool versions of one machine, in a single
+/// file meant to live in a dotfiles repository. Nothing in it depends on the
+/// machine it was exported on or the time of export, so re-exporting an
+/// unchanged setup gives an identical file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigBundle {
+    pub format: u32,
+    /// Atlas version that exported the bundle
+    pub atlas_version: String,
+    /// Tool name to pinned version, e.g. `cargo-nextest = "0.9.72"`
+    #[serde(default)]
+    pub tools: BTreeMap<String, String>,
+    pub config: OptimizerConfig,
+}
+
+impl ConfigBundle {
+    /// Bundle the given config with the versions of the installed tools Atlas can install
+    pub fn capture(config: &OptimizerConfig, system_info: &SystemInfo) -> Self {
+        let tools = system_info
+            .available_tools
+            .all()
+            .into_iter()
+            .filter(|tool| tool.is_installed && is_installable(&tool.name))
+            .filter_map(|tool| {
+                let version = tool.version.as_deref().and_then(parse_version)?;
+                Some((tool.name, version))
+            })
+            .collect();
+
+        Self {
+            format: BUNDLE_FORMAT,
+            atlas_version: env!("CARGO_PKG_VERSION").to_string(),
+            tools,
+            config: config.clone(),
+        }
+    }
+
+    pub fn to_toml(&self) -> OptimizerResult<String> {
+        Ok(format!(
+            "# Atlas config bundle. Apply with `atlas config bundle import <file>`\n{}",
+            toml::to_string_pretty(self)?
+        ))
+    }
+
+    pub fn load(path: &Path) -> OptimizerResult<Self> {
+        let bundle: Self = toml::from_str(&fs::read_to_string(path)?)?;
+        if bundle.format > BUNDLE_FORMAT {
+            return Err(OptimizerError::config(format!(
+                "{} uses bundle format {}, but this Atlas only reads format {}. Update Atlas first",
+                path.display(),
+                bundle.format,
+                BUNDLE_FORMAT
+            )));
+        }
+        bundle.config.validate()?;
+        Ok(bundle)
+    }
+
+    /// Pinned tools whose installed version differs, with the installed version if any
+    pub fn tool_drift<'a>(
+        &'a self,
+        system_info: &SystemInfo,
+    ) -> Vec<(&'a str, &'a str, Option<String>)> {
+        self.tools
+            .iter()
+            .filter_map(|(name, pinned)| {
+                let installed = system_info
+                    .get_tool(name)
+                    .filter(|tool| tool.is_installed)
+                    .and_then(|tool| tool.version.as_deref().and_then(parse_version));
+                (installed.as_deref() != Some(pinned.as_str())).then_some((
+                    name.as_str(),
+                    pinned.as_str(),
+                    installed,
+                ))
+            })
+            .collect()
+    }
+}
+
+/// Version number in a tool's `--version` output, e.g. `0.9.72` in `cargo-nextest 0.9.72 (abc 2024-05-01)`
+pub fn parse_version(output: &str) -> Option<String> {
+    let pattern = Regex::new(r"\d+\.\d+(?:\.\d+)?").expect("valid version regex");
+    pattern.find(output).map(|found| found.as_str().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::{Architecture, AvailableTool, OperatingSystem};
+
+    fn system(tools: &[(&str, Option<&str>)]) -> SystemInfo {
+        SystemInfo {
+            os: OperatingSystem::Linux,
+            arch: Architecture::X86_64,
+            cpu_cores: 8,
+            rust_version: None,
+            cargo_version: None,
+            rust_llvm_version: None,
+            linux_package_manager: None,
+            available_tools: tools
+                .iter()
+                .map(|(name, version)| AvailableTool {
+                    name: name.to_string(),
+                    version: version.map(str::to_string),
+                    path: format!("/usr/bin/{}", name),
+                    is_installed: version.is_some(),
+                })
+                .collect::<Vec<_>>()
+                .into(),
+            rustup_components: None,
+            power: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let mut config = OptimizerConfig::default();
+        config.build.enable_sccache = true;
+        let exported = ConfigBundle::capture(
+            &config,
+            &system(&[
+                ("sccache", Some("sccache 0.8.1")),
+                (
+                    "cargo-nextest",
+                    Some("cargo-nextest 0.9.72 (abc 2024-05-01)"),
+                ),
+                ("mold", None),
+                ("gcc", Some("gcc (Debian 12.2.0-14) 12.2.0")),
+            ]),
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("atlas-bundle.toml");
+        fs::write(&path, exported.to_toml().unwrap()).unwrap();
+        let imported = ConfigBundle::load(&path).unwrap();
+
+        assert!(imported.config.build.enable_sccache);
+        assert_eq!(
+            imported.tools.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("cargo-nextest".to_string(), "0.9.72".to_string()),
+                ("sccache".to_string(), "0.8.1".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tool_drift() {
+        let bundle = ConfigBundle::capture(
+            &OptimizerConfig::default(),
+            &system(&[
+                ("sccache", Some("sccache 0.8.1")),
+                ("cargo-nextest", Some("cargo-nextest 0.9.72")),
+            ]),
+        );
+        let drift = bundle.tool_drift(&system(&[
+            ("sccache", Some("sccache 0.8.1")),
+            ("cargo-nextest", Some("cargo-nextest 0.9.70")),
+        ]));
+        assert_eq!(
+            drift,
+            vec![("cargo-nextest", "0.9.72", Some("0.9.70".to_string()))]
+        );
+        assert_eq!(bundle.tool_drift(&system(&[])).len(), 2);
+    }
+}
@@ -0,0 +1,264 @@
+//! The shared sccache cache: where it lives, the clean builds run by
+//! `atlas cache seed` to fill it before developers start their day, and the
+//! `atlas cache report` summary of how much this machine's builds gained.
+
+use crate::matrix::MatrixEntry;
+use crate::stats::{BuildRecord, RebuildCause, SccacheSnapshot};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Arguments that route every rustc call through sccache, which cannot cache
+/// incremental compilations
+pub const SEED_ARGS: &[&str] = &[
+    "--config=build.rustc-wrapper=\"sccache\"",
+    "--config=build.incremental=false",
+];
+
+/// `cache_location` of `sccache --show-stats --stats-format=json`, e.g.
+/// `S3, name: team-cache, prefix: /rust/`
+pub fn cache_location(stats_json: &str) -> Option<String> {
+    let value: Value = serde_json::from_str(stats_json).ok()?;
+    value
+        .get("cache_location")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
+/// Whether a cache location is shared beyond this machine
+pub fn is_remote(location: &str) -> bool {
+    !location.starts_with("Local disk")
+}
+
+/// Directory of a local disk cache, from its location
+pub fn local_dir(location: &str) -> Option<PathBuf> {
+    let path = location
+        .strip_prefix("Local disk:")?
+        .trim()
+        .trim_matches('"');
+    Some(PathBuf::from(path)).filter(|path| !path.as_os_str().is_empty())
+}
+
+/// Builds for every combination of target and profile; the host and the dev
+/// profile stand in for empty lists
+pub fn seed_jobs(targets: &[String], profiles: &[String]) -> Vec<MatrixEntry> {
+    let targets: Vec<Option<&String>> = if targets.is_empty() {
+        vec![None]
+    } else {
+        targets.iter().map(Some).collect()
+    };
+    let profiles: Vec<Option<&String>> = if profiles.is_empty() {
+        vec![None]
+    } else {
+        profiles.iter().map(Some).collect()
+    };
+    targets
+        .iter()
+        .flat_map(|target| {
+            profiles.iter().map(move |profile| MatrixEntry {
+                target: target.cloned(),
+                profile: profile.cloned(),
+                ..MatrixEntry::default()
+            })
+        })
+        .collect()
+}
+
+/// Target directory for seeding. It is stable so the seeding runs' unit times
+/// are comparable, and emptied before each run so every unit compiles.
+pub fn seed_target_dir(target_directory: &Path) -> PathBuf {
+    target_directory.join("atlas-seed")
+}
+
+/// Cargo arguments for one seeding build
+pub fn seed_args(job: &MatrixEntry, target_dir: &Path) -> Vec<String> {
+    let scope = ["--workspace".to_string(), "--all-targets".to_string()];
+    let mut args = job.cargo_args(&scope, Some(target_dir));
+    args.extend(SEED_ARGS.iter().map(|arg| arg.to_string()));
+    args
+}
+
+/// Hit rate from which the cache clearly pays for itself
+const GOOD_HIT_RATE: f64 = 50.0;
+/// Hit rate below which the cache barely helps
+const POOR_HIT_RATE: f64 = 20.0;
+
+/// Cache effectiveness over a set of build records
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CacheReport {
+    pub builds: usize,
+    pub failed: usize,
+    pub build_secs: f64,
+    /// Builds that ran with sccache
+    pub cached_builds: usize,
+    pub sccache: SccacheSnapshot,
+    pub crates_compiled: usize,
+    pub crates_fresh: usize,
+    pub causes: BTreeMap<RebuildCause, usize>,
+    /// Distinct toolchain, CPU and power combinations the builds ran in
+    pub environments: usize,
+}
+
+/// Whether the cache earns its keep
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    NoData,
+    /// No build went through sccache
+    NotInUse,
+    PayingOff,
+    Marginal,
+    NotPayingOff,
+}
+
+impl CacheReport {
+    pub fn from_records(records: &[BuildRecord]) -> Self {
+        let mut report = Self::default();
+        let mut environments = HashSet::new();
+        for record in records {
+            if let Some(fingerprint) = &record.fingerprint {
+                environments.insert(fingerprint.environment());
+            }
+            report.builds += 1;
+            report.failed += usize::from(!record.success);
+            report.build_secs += record.duration_secs;
+            report.crates_compiled += record.crates_compiled;
+            report.crates_fresh += record.crates_fresh;
+            *report.causes.entry(record.cause).or_default() += 1;
+            if let Some(sccache) = record.sccache.filter(|sccache| sccache.requests > 0) {
+                report.cached_builds += 1;
+                report.sccache.requests += sccache.requests;
+                report.sccache.hits += sccache.hits;
+                report.sccache.misses += sccache.misses;
+                report.sccache.errors += sccache.errors;
+            }
+        }
+        report.environments = environments.len();
+        report
+    }
+
+    /// Percentage of packages reused from the target directory
+    pub fn reuse_percentage(&self) -> f64 {
+        let total = self.crates_compiled + self.crates_fresh;
+        if total == 0 {
+            0.0
+        } else {
+            self.crates_fresh as f64 / total as f64 * 100.0
+        }
+    }
+
+    /// Rough compile time the cache hits saved: each hit is valued at the
+    /// average time builds spent per compiled package
+    pub fn estimated_secs_saved(&self) -> f64 {
+        if self.crates_compiled == 0 {
+            return 0.0;
+        }
+        self.sccache.hits as f64 * self.build_secs / self.crates_compiled as f64
+    }
+
+    pub fn verdict(&self) -> Verdict {
+        if self.builds == 0 {
+            Verdict::NoData
+        } else if self.cached_builds == 0 {
+            Verdict::NotInUse
+        } else if self.sccache.hit_rate() >= GOOD_HIT_RATE {
+            Verdict::PayingOff
+        } else if self.sccache.hit_rate() >= POOR_HIT_RATE {
+            Verdict::Marginal
+        } else {
+            Verdict::NotPayingOff
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seed_jobs() {
+        let jobs = seed_jobs(
+            &["x86_64-unknown-linux-gnu".to_string()],
+            &["dev".to_string(), "release".to_string()],
+        );
+        let labels: Vec<String> = jobs.iter().map(MatrixEntry::label).collect();
+        assert_eq!(
+            labels,
+            vec![
+                "build x86_64-unknown-linux-gnu/dev",
+                "build x86_64-unknown-linux-gnu/release"
+            ]
+        );
+        assert_eq!(seed_jobs(&[], &[]), vec![MatrixEntry::default()]);
+
+        let args = seed_args(&jobs[1], Path::new("/t/atlas-seed"));
+        assert_eq!(
+            args,
+            vec![
+                "build",
+                "--workspace",
+                "--all-targets",
+                "--target=x86_64-unknown-linux-gnu",
+                "--profile=release",
+                "--target-dir=/t/atlas-seed",
+                "--config=build.rustc-wrapper=\"sccache\"",
+                "--config=build.incremental=false",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cache_location() {
+        let remote = r#"{"stats": {}, "cache_location": "S3, name: team-cache, prefix: /rust/"}"#;
+        assert_eq!(
+            cache_location(remote).as_deref(),
+            Some("S3, name: team-cache, prefix: /rust/")
+        );
+        assert!(is_remote("S3, name: team-cache, prefix: /rust/"));
+        assert!(!is_remote("Local disk: \"/home/dev/.cache/sccache\""));
+        assert_eq!(
+            local_dir("Local disk: \"/home/dev/.cache/sccache\""),
+            Some(PathBuf::from("/home/dev/.cache/sccache"))
+        );
+        assert_eq!(local_dir("S3, name: team-cache, prefix: /rust/"), None);
+        assert_eq!(cache_location(r#"{"stats": {}}"#), None);
+    }
+
+    #[test]
+    fn test_cache_report() {
+        let record = |cause, compiled, fresh, sccache: Option<(u64, u64)>| BuildRecord {
+            recorded_at: chrono::Utc::now(),
+            project: PathBuf::from("/ws"),
+            command: "build".to_string(),
+            success: true,
+            duration_secs: 10.0,
+            crates_compiled: compiled,
+            crates_fresh: fresh,
+            cause,
+            sccache: sccache.map(|(hits, misses)| SccacheSnapshot {
+                requests: hits + misses,
+                hits,
+                misses,
+                errors: 0,
+            }),
+            fingerprint: None,
+            profile: None,
+        };
+        let report = CacheReport::from_records(&[
+            record(RebuildCause::Cold, 8, 0, Some((6, 2))),
+            record(RebuildCause::WorkspaceChanges, 2, 6, Some((0, 2))),
+            record(RebuildCause::UpToDate, 0, 4, None),
+        ]);
+        assert_eq!(report.builds, 3);
+        assert_eq!(report.cached_builds, 2);
+        assert_eq!(report.sccache.hit_rate(), 60.0);
+        assert_eq!(report.reuse_percentage(), 50.0);
+        assert_eq!(report.causes[&RebuildCause::Cold], 1);
+        assert_eq!(report.estimated_secs_saved(), 18.0);
+        assert_eq!(report.verdict(), Verdict::PayingOff);
+        assert_eq!(report.environments, 0);
+
+        let uncached = CacheReport::from_records(&[record(RebuildCause::Cold, 8, 0, None)]);
+        assert_eq!(uncached.verdict(), Verdict::NotInUse);
+        assert_eq!(CacheReport::from_records(&[]).verdict(), Verdict::NoData);
+    }
+}
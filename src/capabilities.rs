@@ -0,0 +1,113 @@
+//! What Atlas can do on the machine it runs on
+//!
+//! Linker setup and tool installation are written for macOS, Linux and
+//! Windows. On other platforms, such as illumos or FreeBSD, most of Atlas
+//! still works: build profiles, Cargo settings, statistics and analysis only
+//! need Cargo. Instead of failing there, commands show which parts apply and
+//! carry on with those.
+
+use crate::system::{OperatingSystem, SystemInfo};
+
+/// How much of a capability works here
+#[derive(Debug, Clone, PartialEq)]
+pub enum Support {
+    Full,
+    /// Works in part; says which part
+    Partial(String),
+    /// Does not work; says why
+    Unavailable(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capability {
+    pub name: &'static str,
+    pub support: Support,
+}
+
+/// Whether Atlas was written for `os`
+pub fn is_supported_platform(os: &OperatingSystem) -> bool {
+    !matches!(os, OperatingSystem::Unknown(_))
+}
+
+/// What works and what does not on the machine `system_info` describes
+pub fn matrix(system_info: &SystemInfo) -> Vec<Capability> {
+    let capability = |name, support| Capability { name, support };
+    let package_manager = system_info.get_package_manager();
+    vec![
+        capability("Build profiles in Cargo.toml", Support::Full),
+        capability(
+            "Cargo config (jobs, sccache, target directories)",
+            Support::Full,
+        ),
+        capability("Build statistics and history", Support::Full),
+        capability("Dependency and build analysis", Support::Full),
+        capability(
+            "Fast linker setup",
+            match system_info.get_recommended_linker() {
+                Some(_) => Support::Full,
+                None => Support::Unavailable(format!(
+                    "no known fast linker for {} on {}",
+                    system_info.os, system_info.arch
+                )),
+            },
+        ),
+        capability(
+            "Tool installation",
+            match package_manager {
+                Some(_) => Support::Full,
+                None => Support::Partial(
+                    "only tools installed with `cargo install` or rustup".to_string(),
+                ),
+            },
+        ),
+        capability(
+            "C compiler cache (ccache)",
+            match package_manager {
+                Some(_) => Support::Full,
+                None => Support::Unavailable("no supported package manager".to_string()),
+            },
+        ),
+        capability(
+            "Battery and power detection",
+            match system_info.os {
+                OperatingSystem::Linux | OperatingSystem::MacOS => Support::Full,
+                _ => Support::Unavailable(format!("not implemented for {}", system_info.os)),
+            },
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host::MockHost;
+    use crate::system::Architecture;
+
+    #[test]
+    fn test_unknown_platform_keeps_cargo_features() {
+        let illumos = MockHost::new(
+            OperatingSystem::Unknown("illumos".to_string()),
+            Architecture::X86_64,
+        );
+        let system_info = SystemInfo::detect_with(&illumos);
+        assert!(!is_supported_platform(&system_info.os));
+
+        let matrix = matrix(&system_info);
+        let support = |name: &str| {
+            matrix
+                .iter()
+                .find(|capability| capability.name.starts_with(name))
+                .map(|capability| capability.support.clone())
+                .unwrap()
+        };
+        assert_eq!(support("Build profiles"), Support::Full);
+        assert_eq!(support("Dependency and build analysis"), Support::Full);
+        assert!(matches!(support("Fast linker"), Support::Unavailable(_)));
+        assert!(matches!(support("Tool installation"), Support::Partial(_)));
+
+        let linux = SystemInfo::detect_with(&MockHost::linux().with_program("apt-get", None));
+        assert!(super::matrix(&linux)
+            .iter()
+            .all(|capability| capability.support == Support::Full));
+    }
+}
@@ -0,0 +1,213 @@
+//! Validation of `[env]` tables in Cargo config
+//!
+//! Cargo passes `[env]` to rustc, build scripts and the processes started by
+//! `cargo run` and `cargo test`. Cargo itself never reads it, so `CARGO_*`
+//! variables set there have no effect on the build.
+
+/// Variables that build scripts, the `cc` crate or rustc read from the environment
+pub const HONORED_VARIABLES: &[&str] = &[
+    "CC",
+    "CXX",
+    "AR",
+    "RANLIB",
+    "CFLAGS",
+    "CXXFLAGS",
+    "CPPFLAGS",
+    "LDFLAGS",
+    "CRATE_CC_NO_DEFAULTS",
+    "PKG_CONFIG",
+    "PKG_CONFIG_PATH",
+    "PKG_CONFIG_SYSROOT_DIR",
+    "LIBCLANG_PATH",
+    "BINDGEN_EXTRA_CLANG_ARGS",
+    "OPENSSL_DIR",
+    "OPENSSL_STATIC",
+    "MACOSX_DEPLOYMENT_TARGET",
+    "RUST_BACKTRACE",
+    "RUST_LOG",
+    "RUST_MIN_STACK",
+];
+
+/// Per-target variants the `cc` crate reads, e.g. `CC_x86_64_unknown_linux_gnu`
+const TARGET_PREFIXES: &[&str] = &["CC_", "CXX_", "AR_", "CFLAGS_", "CXXFLAGS_"];
+
+/// Keys Cargo accepts in a `{ value = "..." }` entry
+const VALUE_OPTIONS: &[&str] = &["value", "force", "relative"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EnvProblem {
+    /// Cargo reads the variable before `[env]` applies
+    CargoVariable,
+    /// Not a variable known to be read during a build
+    Unrecognized,
+    /// A table entry with an option Cargo does not support
+    UnknownOption(String),
+    /// Neither a string nor a `{ value = "..." }` table
+    InvalidValue,
+}
+
+/// An `[env]` entry that does not do what it appears to
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvIssue {
+    pub key: String,
+    pub problem: EnvProblem,
+}
+
+impl EnvIssue {
+    pub fn explanation(&self) -> String {
+        match &self.problem {
+            EnvProblem::CargoVariable => {
+                format!("{} is read by Cargo itself, which ignores [env]", self.key)
+            }
+            EnvProblem::Unrecognized => format!(
+                "{} is not a variable compilers or build scripts are known to read",
+                self.key
+            ),
+            EnvProblem::UnknownOption(option) => format!(
+                "{} has option '{}', but [env] entries only support {}",
+                self.key,
+                option,
+                VALUE_OPTIONS.join(", ")
+            ),
+            EnvProblem::InvalidValue => format!(
+                "{} must be a string or a {{ value = \"...\" }} table",
+                self.key
+            ),
+        }
+    }
+}
+
+/// Whether a build reacts to `name` being set in `[env]`
+pub fn is_honored(name: &str) -> bool {
+    HONORED_VARIABLES.contains(&name)
+        || TARGET_PREFIXES
+            .iter()
+            .any(|prefix| name.strip_prefix(prefix).is_some_and(is_target_suffix))
+}
+
+fn is_target_suffix(suffix: &str) -> bool {
+    !suffix.is_empty()
+        && suffix
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+}
+
+fn key_problem(name: &str) -> Option<EnvProblem> {
+    if is_honored(name) {
+        None
+    } else if name.starts_with("CARGO_") {
+        Some(EnvProblem::CargoVariable)
+    } else {
+        Some(EnvProblem::Unrecognized)
+    }
+}
+
+/// Problems with the entries of an `[env]` table
+pub fn validate_env(env: &toml::Table) -> Vec<EnvIssue> {
+    let mut issues = Vec::new();
+    for (key, value) in env {
+        let problem = match value {
+            toml::Value::String(_) => key_problem(key),
+            toml::Value::Table(entry) => match entry
+                .keys()
+                .find(|option| !VALUE_OPTIONS.contains(&option.as_str()))
+            {
+                Some(option) => Some(EnvProblem::UnknownOption(option.clone())),
+                None if !entry.get("value").is_some_and(toml::Value::is_str) => {
+                    Some(EnvProblem::InvalidValue)
+                }
+                None => key_problem(key),
+            },
+            _ => Some(EnvProblem::InvalidValue),
+        };
+        if let Some(problem) = problem {
+            issues.push(EnvIssue {
+                key: key.clone(),
+                problem,
+            });
+        }
+    }
+    issues
+}
+
+/// Problems with the `[env]` table of a Cargo config document
+pub fn validate_config_env(content: &str) -> crate::error::OptimizerResult<Vec<EnvIssue>> {
+    let value: toml::Value = toml::from_str(content)?;
+    Ok(match value.get("env").and_then(toml::Value::as_table) {
+        Some(env) => validate_env(env),
+        None => Vec::new(),
+    })
+}
+
+/// Render an `[env]` section from `entries`, dropping the ones a build would
+/// not honor. Empty when nothing is left.
+pub fn render_env(entries: &[(&str, String)]) -> String {
+    let mut section = String::new();
+    for (name, value) in entries {
+        if let Some(problem) = key_problem(name) {
+            let issue = EnvIssue {
+                key: name.to_string(),
+                problem,
+            };
+            log::warn!("Leaving out [env] entry: {}", issue.explanation());
+            continue;
+        }
+        section.push_str(&format!("{} = {:?}\n", name, value));
+    }
+
+    if section.is_empty() {
+        section
+    } else {
+        format!("[env]\n{}\n", section)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_env() {
+        let env: toml::Table = toml::from_str(
+            r#"
+CC = "ccache cc"
+CFLAGS_x86_64_unknown_linux_gnu = "-O2"
+RUST_LOG = { value = "debug", force = true }
+CARGO_BUILD_CACHE = "1"
+CARGO_TARGET_DIR = { value = "target/rust-analyzer", condition = "cfg(rust_analyzer)" }
+MY_TOKEN = "x"
+AR = 1
+"#,
+        )
+        .unwrap();
+
+        let mut issues = validate_env(&env);
+        issues.sort_by(|a, b| a.key.cmp(&b.key));
+        let problems: Vec<(&str, &EnvProblem)> = issues
+            .iter()
+            .map(|issue| (issue.key.as_str(), &issue.problem))
+            .collect();
+        assert_eq!(
+            problems,
+            vec![
+                ("AR", &EnvProblem::InvalidValue),
+                ("CARGO_BUILD_CACHE", &EnvProblem::CargoVariable),
+                (
+                    "CARGO_TARGET_DIR",
+                    &EnvProblem::UnknownOption("condition".to_string())
+                ),
+                ("MY_TOKEN", &EnvProblem::Unrecognized),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_env_drops_unhonored_entries() {
+        let rendered = render_env(&[
+            ("CC", "sccache clang".to_string()),
+            ("CARGO_INCREMENTAL", "1".to_string()),
+        ]);
+        assert_eq!(rendered, "[env]\nCC = \"sccache clang\"\n\n");
+        assert!(render_env(&[("CARGO_BUILD_CACHE", "1".to_string())]).is_empty());
+    }
+}
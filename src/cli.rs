@@ -0,0 +1,1434 @@
+//! Command-line interface: argument definitions and dispatch for the
+//! `atlas` binary
+
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use crate::commands::{self, *};
+use crate::error::OptimizerResult;
+use crate::{
+    alias, allowlist, audit, config, config_layers, events, history, i18n, lockfile, maintenance,
+    onboarding, process_env, project_config, redact, rules, session, signal, stats, style, table,
+    target_dirs, utils,
+};
+
+#[derive(Parser)]
+#[command(
+    name = "atlas",
+    about = "🚀 A comprehensive Rust build optimization tool",
+    long_about = "A comprehensive Rust build optimization tool that dramatically improves build times and development workflow through intelligent configuration, tool management, and performance monitoring.",
+    version
+)]
+pub(crate) struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+
+    /// Enable verbose output
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    /// Suppress all output except errors
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// Project directory (defaults to current directory)
+    #[arg(short, long, global = true)]
+    project_dir: Option<PathBuf>,
+
+    /// Emit machine-readable events (command started, progress, build finished)
+    #[arg(long, value_enum, global = true)]
+    events: Option<events::EventFormat>,
+
+    /// Where to write events: a file, `fd:N` or `-` for stderr
+    #[arg(long, global = true, value_name = "PATH|fd:N", requires = "events")]
+    events_to: Option<String>,
+
+    /// Pass --locked to every cargo command, failing if Cargo.lock is out of date
+    #[arg(long, global = true, conflicts_with = "frozen")]
+    locked: bool,
+
+    /// Pass --frozen to every cargo command: --locked without network access
+    #[arg(long, global = true)]
+    frozen: bool,
+
+    /// Disable colored output, here and in the cargo commands Atlas runs (also NO_COLOR)
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    /// Leave emoji out of the output, for screen readers and plain logs
+    #[arg(long, global = true)]
+    no_emoji: bool,
+
+    /// Print tables at full width instead of wrapping them to the terminal
+    #[arg(long, global = true)]
+    wide: bool,
+}
+
+#[derive(Subcommand)]
+pub enum Commands {
+    /// Initialize optimization for a Rust project
+    #[command(alias = "init")]
+    Initialize {
+        /// Skip backup of existing files
+        #[arg(long)]
+        no_backup: bool,
+
+        /// Skip installing tools
+        #[arg(long)]
+        no_tools: bool,
+
+        /// Force overwrite existing configurations
+        #[arg(long)]
+        force: bool,
+
+        /// Install settings for this project or for every project of the current user
+        #[arg(long, value_enum, default_value_t = config::ConfigScope::Project)]
+        scope: config::ConfigScope,
+
+        /// Also write a Nix devShell (atlas.nix) and a flake.nix using it
+        #[arg(long)]
+        nix: bool,
+
+        /// Write Cargo configuration even where Bazel or Buck builds the Rust code
+        #[arg(long)]
+        full: bool,
+
+        /// Only apply settings that do not change what is built: sccache, the
+        /// sparse registry and a nextest config
+        #[arg(long)]
+        minimal: bool,
+
+        /// Roll back what earlier runs of init changed in this project
+        #[arg(long, conflicts_with_all = ["no_backup", "no_tools", "force", "scope", "nix", "full", "minimal"])]
+        undo: bool,
+    },
+
+    /// Install required optimization tools
+    #[command(alias = "tools")]
+    InstallTools {
+        /// List available tools without installing
+        #[arg(long)]
+        list: bool,
+
+        /// Install specific tools only
+        #[arg(long, value_delimiter = ',')]
+        only: Option<Vec<String>>,
+
+        /// Print the commands that would install the tools, for running them by hand
+        #[arg(long, conflicts_with = "list")]
+        print_install_commands: bool,
+    },
+
+    /// Run optimized build commands
+    Build {
+        #[command(subcommand)]
+        build_type: BuildCommands,
+    },
+
+    /// Development workflow commands
+    #[command(alias = "dev")]
+    Development {
+        #[command(subcommand)]
+        dev_command: DevCommands,
+    },
+
+    /// Analyze and optimize workspace
+    Optimize {
+        /// Run all optimizations
+        #[arg(long)]
+        all: bool,
+
+        /// Clean target directory
+        #[arg(long)]
+        clean: bool,
+
+        /// Check for unused dependencies
+        #[arg(long)]
+        deps: bool,
+
+        /// Time a clean build, an incremental build and a check, and compare with the stored baseline
+        #[arg(long)]
+        benchmark: bool,
+
+        /// Baseline the benchmark is stored as and compared with
+        #[arg(long, value_name = "NAME", default_value = "optimize")]
+        baseline: String,
+    },
+
+    /// Show optimization status and statistics
+    Status {
+        /// Show detailed information
+        #[arg(long)]
+        detailed: bool,
+
+        /// Export status to JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Show how build times, crates compiled and cache hits changed week over week
+        #[arg(long, conflicts_with = "detailed")]
+        history: bool,
+
+        /// Number of weeks of history to show
+        #[arg(long, default_value_t = 8, requires = "history")]
+        weeks: u32,
+    },
+
+    /// Configuration management
+    Config {
+        #[command(subcommand)]
+        config_command: ConfigCommands,
+    },
+
+    /// Analyze the workspace dependency graph and build schedule
+    Analyze {
+        #[command(subcommand)]
+        analyze_command: AnalyzeCommands,
+    },
+
+    /// Explain a setting Atlas manages, its tradeoffs and its value in this project
+    Explain {
+        /// Setting name, e.g. codegen-units; lists the settings when omitted
+        setting: Option<String>,
+    },
+
+    /// Editor integration: VS Code tasks and a JSON status for extensions
+    Editor {
+        #[command(subcommand)]
+        editor_command: EditorCommands,
+    },
+
+    /// Dockerfile layer caching helpers
+    Docker {
+        #[command(subcommand)]
+        docker_command: DockerCommands,
+    },
+
+    /// Helpers for CI pipelines
+    Ci {
+        #[command(subcommand)]
+        ci_command: CiCommands,
+    },
+
+    /// Run the pre-release checks and builds, with per-step timing
+    Release {
+        /// Skip the steps that passed in the last unfinished run on this commit
+        #[arg(long)]
+        resume: bool,
+
+        /// Steps to leave out this time, e.g. `audit,doc`
+        #[arg(long, value_delimiter = ',')]
+        skip: Vec<String>,
+    },
+
+    /// Regenerate Atlas-managed blocks from the current configuration
+    Refresh {
+        /// Also regenerate blocks that were edited by hand
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Run a task defined in the project's atlas.toml
+    Run {
+        /// Task name; lists the available tasks when omitted
+        task: Option<String>,
+    },
+
+    /// Diagnose problems that slow down or break builds
+    Doctor {
+        /// Check system libraries and tools needed by -sys crates
+        #[arg(long)]
+        native_deps: bool,
+
+        /// Apply safe fixes, rolling back every file edit if one fails
+        #[arg(long)]
+        fix: bool,
+
+        /// List the recommendation rules and their IDs
+        #[arg(long)]
+        rules: bool,
+    },
+
+    /// Work with the shared sccache cache
+    Cache {
+        #[command(subcommand)]
+        cache_command: CacheCommands,
+    },
+
+    /// Push and pull archived dependency builds keyed by Cargo.lock and toolchain
+    Artifacts {
+        #[command(subcommand)]
+        artifacts_command: ArtifactsCommands,
+    },
+
+    /// Record and compare performance baselines
+    Baseline {
+        #[command(subcommand)]
+        baseline_command: BaselineCommands,
+    },
+
+    /// Manage opt-in anonymous usage statistics
+    Telemetry {
+        #[command(subcommand)]
+        telemetry_command: TelemetryCommands,
+    },
+
+    /// Review the files Atlas created, modified or removed
+    Audit {
+        #[command(subcommand)]
+        audit_command: AuditCommands,
+    },
+
+    /// Collect diagnostics for bug reports
+    Debug {
+        #[command(subcommand)]
+        debug_command: DebugCommands,
+    },
+
+    /// Initialize, refresh or check every repository listed in a fleet manifest
+    Fleet {
+        /// Fleet manifest (defaults to fleet.toml next to the Atlas config)
+        #[arg(long)]
+        manifest: Option<PathBuf>,
+
+        #[command(subcommand)]
+        fleet_command: FleetCommands,
+    },
+
+    /// Offload builds to a faster machine over SSH
+    Remote {
+        #[command(subcommand)]
+        remote_command: RemoteCommands,
+    },
+
+    /// Remove old build artifacts, crate sources, sccache entries and statistics
+    Maintain {
+        /// Show what would be removed without removing it
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Install maintenance to run on its own instead of running it now
+        #[arg(long, value_enum, value_name = "WHEN", conflicts_with = "unschedule")]
+        schedule: Option<maintenance::Schedule>,
+
+        /// Scheduler to install with (default: the platform's own)
+        #[arg(long, value_enum)]
+        scheduler: Option<maintenance::Scheduler>,
+
+        /// Remove the installed schedule
+        #[arg(long)]
+        unschedule: bool,
+    },
+
+    /// Run the last Atlas command of this project again
+    Again,
+
+    /// Show the last Atlas command of this project
+    Last {
+        /// Also show the builds and checks it ran
+        #[arg(long)]
+        stats: bool,
+    },
+
+    /// Update Atlas to the latest version
+    Update {
+        /// Check for updates without installing
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Benchmark Atlas's own hot paths to catch performance regressions
+    #[command(name = "bench-self", long_flag = "bench-self", hide = true)]
+    BenchSelf {
+        /// Measured runs per workload
+        #[arg(long, default_value_t = 20)]
+        iterations: usize,
+
+        /// Only run workloads whose name contains this
+        #[arg(long)]
+        filter: Option<String>,
+
+        /// Save the results to compare later runs against
+        #[arg(long)]
+        save: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum BuildCommands {
+    /// Fast cargo check
+    Check {
+        /// Show build statistics after completion
+        #[arg(long)]
+        stats: bool,
+
+        /// Only these workspace members: group names from atlas.toml or glob patterns
+        #[arg(long, value_delimiter = ',')]
+        members: Option<Vec<String>>,
+    },
+
+    /// Optimized cargo build
+    Build {
+        /// Build in release mode
+        #[arg(long)]
+        release: bool,
+
+        /// Show build statistics after completion
+        #[arg(long)]
+        stats: bool,
+
+        /// Only these workspace members: group names from atlas.toml or glob patterns
+        #[arg(long, value_delimiter = ',')]
+        members: Option<Vec<String>>,
+
+        /// Run at low CPU and I/O priority with fewer jobs, e.g. to warm the cache
+        #[arg(long, alias = "nice")]
+        background: bool,
+    },
+
+    /// Fast testing with cargo-nextest
+    Test {
+        /// Only test workspace members affected by uncommitted changes
+        #[arg(long)]
+        changed: bool,
+
+        /// Run the tests that failed last time first, and stop if they still fail
+        #[arg(long)]
+        failed_first: bool,
+
+        /// Show test statistics
+        #[arg(long)]
+        stats: bool,
+
+        /// Only these workspace members: group names from atlas.toml or glob patterns
+        #[arg(long, value_delimiter = ',')]
+        members: Option<Vec<String>>,
+    },
+
+    /// Build every [[matrix]] combination from atlas.toml and report on all of them
+    Matrix {
+        /// Only entries with these labels
+        #[arg(long, value_delimiter = ',')]
+        only: Option<Vec<String>>,
+
+        /// Entries built at the same time, each extra one in its own target directory
+        #[arg(long, default_value_t = 1)]
+        parallel: usize,
+
+        /// Stop at the first failing entry
+        #[arg(long)]
+        fail_fast: bool,
+
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Only these workspace members: group names from atlas.toml or glob patterns
+        #[arg(long, value_delimiter = ',')]
+        members: Option<Vec<String>>,
+    },
+
+    /// Format the workspace with cargo fmt and report what changed
+    Fmt {
+        /// Only report unformatted files and fail if there are any, e.g. in CI
+        #[arg(long)]
+        check: bool,
+
+        /// Only these workspace members: group names from atlas.toml or glob patterns
+        #[arg(long, value_delimiter = ',')]
+        members: Option<Vec<String>>,
+
+        /// Install a git pre-commit hook that runs `atlas build fmt --check`
+        #[arg(long, conflicts_with_all = ["check", "members"])]
+        install_hook: bool,
+    },
+
+    /// Clean build artifacts
+    Clean {
+        /// Clean everything including rust-analyzer cache
+        #[arg(long)]
+        all: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum FleetCommands {
+    /// Initialize every repository
+    Init {
+        /// Force overwrite existing configurations
+        #[arg(long)]
+        force: bool,
+
+        /// Skip installing tools
+        #[arg(long)]
+        no_tools: bool,
+    },
+
+    /// Regenerate the Atlas-managed blocks of every repository
+    Refresh {
+        /// Also regenerate blocks that were edited by hand
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Report drift and recommendations across the repositories
+    Status,
+}
+
+#[derive(Subcommand)]
+pub enum RemoteCommands {
+    /// Copy the workspace to the remote host, build it there and copy the artifacts back
+    Build {
+        /// Named host from the Atlas config (defaults to remote.default)
+        #[arg(long)]
+        host: Option<String>,
+
+        /// Build in release mode
+        #[arg(long)]
+        release: bool,
+
+        /// Leave the artifacts on the remote host
+        #[arg(long)]
+        no_sync_back: bool,
+
+        /// Extra arguments for cargo build, after `--`
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+
+    /// List the configured remote hosts
+    Hosts,
+
+    /// Check a host has rustup, the toolchain and the build tools this machine uses
+    Doctor {
+        /// Named host from the Atlas config (defaults to remote.default)
+        host: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ArtifactsCommands {
+    /// Archive the compiled dependencies and upload them, unless already stored
+    Push {
+        /// Directory, s3://bucket/prefix or gs://bucket/prefix (defaults to atlas.toml)
+        #[arg(long)]
+        store: Option<String>,
+
+        /// Profiles to push (defaults to [artifacts] profiles, or dev)
+        #[arg(long, value_delimiter = ',')]
+        profile: Vec<String>,
+
+        /// Upload even if an archive with the same key is stored
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Download and unpack the archive matching this Cargo.lock and toolchain
+    Pull {
+        /// Directory, s3://bucket/prefix or gs://bucket/prefix (defaults to atlas.toml)
+        #[arg(long)]
+        store: Option<String>,
+
+        /// Profiles to pull (defaults to [artifacts] profiles, or dev)
+        #[arg(long, value_delimiter = ',')]
+        profile: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum DevCommands {
+    /// Ultra-fast syntax check
+    QuickCheck,
+
+    /// Continuous development with auto-rebuild
+    Watch {
+        /// Watch specific files or directories
+        #[arg(long, value_delimiter = ',')]
+        paths: Option<Vec<PathBuf>>,
+
+        /// Only test workspace members affected by uncommitted changes
+        #[arg(long)]
+        changed: bool,
+
+        /// Run at low CPU and I/O priority with fewer jobs, out of the way of foreground work
+        #[arg(long, alias = "nice")]
+        background: bool,
+    },
+
+    /// Profile build performance
+    Profile {
+        /// Generate detailed timing report
+        #[arg(long)]
+        detailed: bool,
+
+        /// Number of slowest crates to list
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+
+        /// Print the slowest crates as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Clean build with maximum optimization
+    CleanBuild {
+        /// Build in release mode
+        #[arg(long)]
+        release: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AnalyzeCommands {
+    /// Export the dependency graph annotated with compile times
+    Graph {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = GraphFormat::Dot)]
+        format: GraphFormat,
+
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Only include workspace members
+        #[arg(long)]
+        members_only: bool,
+
+        /// Include dev-dependencies
+        #[arg(long)]
+        dev: bool,
+    },
+
+    /// Find the crates that bound wall-clock build time
+    CriticalPath {
+        /// Number of improvement opportunities to show
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+
+        /// Parallelism to compute the lower bound for (defaults to configured jobs)
+        #[arg(short, long)]
+        jobs: Option<usize>,
+    },
+
+    /// Report dependencies with newer releases and what upgrading each rebuilds
+    Outdated {
+        /// Only list direct dependencies of workspace members
+        #[arg(long)]
+        direct: bool,
+    },
+
+    /// Suggest modules to split into leaf crates (experimental, needs cargo-llvm-lines)
+    Split {
+        /// Only analyze this workspace member
+        #[arg(long)]
+        package: Option<String>,
+
+        /// Parallelism to project savings for (defaults to configured jobs)
+        #[arg(short, long)]
+        jobs: Option<usize>,
+    },
+
+    /// Rank dependencies by what they cost the build, or break down one of them
+    Cost {
+        /// Dependency to break down, e.g. `reqwest`
+        #[arg(value_name = "CRATE")]
+        package: Option<String>,
+
+        /// Number of dependencies to rank
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+
+        /// Parallelism to project savings for (defaults to configured jobs)
+        #[arg(short, long)]
+        jobs: Option<usize>,
+
+        /// Also count the LLVM IR lines the crate generates (needs cargo-llvm-lines)
+        #[arg(long, requires = "package")]
+        llvm_lines: bool,
+    },
+
+    /// Find build scripts that rerun more often than their inputs change
+    BuildScripts {
+        /// Build twice and report the scripts that run again with nothing changed
+        #[arg(long)]
+        verify: bool,
+    },
+
+    /// List proc-macro crates and measure ways of building them (experimental)
+    ProcMacros {
+        /// Clean-build with each variant and compare it with the current settings
+        #[arg(long)]
+        experiment: bool,
+
+        /// Measured clean builds per variant
+        #[arg(long, default_value_t = crate::baseline::DEFAULT_ITERATIONS, value_parser = clap::value_parser!(u32).range(1..), requires = "experiment")]
+        iterations: u32,
+
+        /// Keep the winning build-override opt-level in Cargo.toml
+        #[arg(long, requires = "experiment")]
+        apply: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum DockerCommands {
+    /// Add cargo-chef stages to the Dockerfile and report layer-ordering fixes
+    Chef {
+        /// Dockerfile to update (default: Dockerfile in the project root)
+        #[arg(long)]
+        dockerfile: Option<PathBuf>,
+        /// Only report problems; fails when any are found
+        #[arg(long)]
+        check: bool,
+        /// Replace the cargo-chef block even if it was edited by hand
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CiCommands {
+    /// Print the workspace members and test targets affected since a base ref as JSON
+    Affected {
+        /// Git ref to compare against, e.g. origin/main
+        #[arg(long)]
+        base: String,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum GraphFormat {
+    /// Graphviz DOT
+    Dot,
+    /// Mermaid flowchart
+    Mermaid,
+}
+
+#[derive(Subcommand)]
+pub enum EditorCommands {
+    /// Write .vscode/tasks.json and launch.json wired to Atlas commands
+    Vscode {
+        /// Replace files that cannot be merged, after backing them up
+        #[arg(long)]
+        force: bool,
+    },
+    /// Print setup status and the last build as one line of JSON
+    Status,
+}
+
+#[derive(Subcommand)]
+pub enum TelemetryCommands {
+    /// Show consent and what has been recorded
+    Status,
+    /// Record baseline comparisons locally
+    On,
+    /// Stop recording
+    Off,
+    /// Delete everything recorded
+    Purge,
+    /// Send per-setting aggregates, after showing them
+    Submit {
+        /// URL to POST to (default: $ATLAS_TELEMETRY_ENDPOINT)
+        #[arg(long)]
+        endpoint: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CacheCommands {
+    /// Clean-build the workspace through sccache to fill the shared cache, e.g. nightly in CI
+    Seed {
+        /// Target triple to build for; repeat for several (default: the host)
+        #[arg(long = "target", value_name = "TRIPLE")]
+        targets: Vec<String>,
+
+        /// Cargo profile to build; repeat for several (default: dev)
+        #[arg(long = "profile", value_name = "PROFILE")]
+        profiles: Vec<String>,
+
+        /// Build the [[matrix]] entries of atlas.toml instead
+        #[arg(long, conflicts_with_all = ["targets", "profiles"])]
+        matrix: bool,
+
+        /// Keep the target directory of the last seeding build
+        #[arg(long)]
+        keep: bool,
+    },
+
+    /// Summarize sccache hits, target directory reuse and rebuild causes of this machine's builds
+    Report {
+        /// Number of days to cover
+        #[arg(long, default_value_t = 7)]
+        days: u32,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AuditCommands {
+    /// List recent changes with the content hashes before and after
+    Show {
+        /// Number of changes to show
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+
+        /// Print the changes as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum DebugCommands {
+    /// Pack system info, config (secrets redacted), generated files, recent session logs and the last failure into a tar.gz
+    Bundle {
+        /// Archive path (default: atlas-debug-<timestamp>.tar.gz in the current directory)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum BaselineCommands {
+    /// Measure build performance and store it under a name
+    Record {
+        /// Baseline name
+        name: String,
+
+        /// Skip the clean build measurement
+        #[arg(long)]
+        no_clean: bool,
+
+        /// Skip the test measurement
+        #[arg(long)]
+        no_tests: bool,
+
+        /// Pause up to this many seconds before each measured step so the CPU can cool down
+        #[arg(long, value_name = "SECS")]
+        cooldown: Option<u64>,
+
+        /// Measured runs of each step
+        #[arg(long, default_value_t = crate::baseline::DEFAULT_ITERATIONS, value_parser = clap::value_parser!(u32).range(1..))]
+        iterations: u32,
+
+        /// Unmeasured runs of each step before the measured ones
+        #[arg(long, default_value_t = 0)]
+        warmup: u32,
+    },
+
+    /// Measure again and compare with a stored baseline
+    Compare {
+        /// Baseline name
+        name: String,
+
+        /// Skip the clean build measurement
+        #[arg(long)]
+        no_clean: bool,
+
+        /// Skip the test measurement
+        #[arg(long)]
+        no_tests: bool,
+
+        /// Pause up to this many seconds before each measured step so the CPU can cool down
+        #[arg(long, value_name = "SECS")]
+        cooldown: Option<u64>,
+
+        /// Measured runs of each step
+        #[arg(long, default_value_t = crate::baseline::DEFAULT_ITERATIONS, value_parser = clap::value_parser!(u32).range(1..))]
+        iterations: u32,
+
+        /// Unmeasured runs of each step before the measured ones
+        #[arg(long, default_value_t = 0)]
+        warmup: u32,
+
+        /// Confidence level in percent a difference must reach to count
+        #[arg(long, default_value_t = 95.0, value_name = "PERCENT")]
+        confidence: f64,
+    },
+
+    /// List stored baselines
+    List,
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    /// Show current configuration
+    Show {
+        /// Show the Cargo settings Atlas manages at this scope instead
+        #[arg(long, value_enum)]
+        scope: Option<config::ConfigScope>,
+
+        /// List every setting with the layer it came from: default, global, project or flag
+        #[arg(long, conflicts_with = "scope")]
+        effective: bool,
+    },
+
+    /// Edit configuration file
+    Edit,
+
+    /// Reset configuration to defaults
+    Reset {
+        /// Reset without confirmation
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Validate current configuration
+    Validate,
+
+    /// Export configuration template
+    Export {
+        /// Output file path
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Find settings made in several places with different values, and how to settle them
+    Doctor,
+
+    /// Set the dev-profile opt-level of a dependency, or list overrides and suggestions
+    PackageOpt {
+        /// Dependency to override, e.g. `image`
+        #[arg(value_name = "CRATE")]
+        package: Option<String>,
+
+        /// opt-level: 0, 1, 2, 3, s or z
+        #[arg(requires = "package")]
+        level: Option<String>,
+
+        /// Remove the override instead
+        #[arg(long, requires = "package", conflicts_with = "level")]
+        remove: bool,
+    },
+
+    /// List the templates replacing generated content and check they render
+    Templates {
+        /// Print what a template renders to here, e.g. `cargo-config.toml`
+        #[arg(long, value_name = "TEMPLATE")]
+        preview: Option<String>,
+    },
+
+    /// Move the global config and tool versions between machines
+    Bundle {
+        #[command(subcommand)]
+        bundle_command: BundleCommands,
+    },
+
+    /// Keep registry and cache credentials in the OS keychain
+    Secret {
+        #[command(subcommand)]
+        secret_command: SecretCommands,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SecretCommands {
+    /// Store a secret in the keychain, prompting for its value
+    Set {
+        /// Name to reference it by, as keychain:<name> in [env]
+        name: String,
+    },
+
+    /// Remove a secret from the keychain
+    Delete { name: String },
+
+    /// Check that every secret [env] references can be read
+    Check,
+}
+
+#[derive(Subcommand)]
+pub enum BundleCommands {
+    /// Write the global config and installed tool versions to one file
+    Export {
+        /// Output file path (defaults to stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Apply a bundle as the global config
+    Import {
+        /// Bundle file
+        file: PathBuf,
+
+        /// Install the bundle's tools at their pinned versions
+        #[arg(long)]
+        install_tools: bool,
+
+        /// Replace the current config without confirmation
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+/// Parse the command line and run the selected command
+pub async fn run() -> OptimizerResult<()> {
+    let config = config::OptimizerConfig::load_or_default();
+    let aliases = config
+        .as_ref()
+        .map(|config| config.aliases.clone())
+        .unwrap_or_default();
+    let args = alias::expand(std::env::args().collect(), &aliases, &Cli::command())?;
+    let matches = Cli::command().get_matches_from(&args);
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let command_name = matches.subcommand_name().unwrap_or_default().to_string();
+    let command_path = match matches.subcommand() {
+        Some((name, sub)) => match sub.subcommand_name() {
+            Some(subcommand) => format!("{} {}", name, subcommand),
+            None => name.to_string(),
+        },
+        None => String::new(),
+    };
+    style::init(cli.no_color, cli.no_emoji);
+    table::init(cli.wide);
+
+    // Initialize logging; quiet runs still log to the session log
+    let args: Vec<String> = args.into_iter().skip(1).collect();
+    let session = session::start(&args);
+    env_logger::Builder::from_default_env()
+        .filter_level(if cli.verbose {
+            log::LevelFilter::Debug
+        } else {
+            log::LevelFilter::Info
+        })
+        .target(env_logger::Target::Pipe(Box::new(session::SessionWriter {
+            echo: !cli.quiet,
+        })))
+        .init();
+    if let Err(e) = session {
+        log::debug!("Could not start the session log: {}", e);
+    }
+
+    if let Some(format) = cli.events {
+        let target = match &cli.events_to {
+            Some(value) => events::EventTarget::parse(value)?,
+            None => events::EventTarget::Stderr,
+        };
+        events::init(format, &target)?;
+    }
+    let lockfile_mode = if cli.frozen {
+        Some(lockfile::LockfileMode::Frozen)
+    } else if cli.locked {
+        Some(lockfile::LockfileMode::Locked)
+    } else {
+        None
+    };
+
+    // Aliases came from the working directory's project; everything else
+    // uses the layers of the project the command runs in
+    let project_root = utils::find_rust_project_root(
+        cli.project_dir
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(".")),
+    )
+    .ok();
+    let flags = lockfile_mode
+        .and_then(|mode| {
+            Some(config_layers::FlagOverride {
+                flag: mode.flag()?.to_string(),
+                key: "build.lockfile".to_string(),
+                value: toml::Value::try_from(mode).ok()?,
+            })
+        })
+        .into_iter()
+        .collect();
+    config_layers::init(
+        project_root
+            .as_ref()
+            .and_then(|root| root.canonicalize().ok()),
+        flags,
+    );
+    let config = config::OptimizerConfig::load_or_default();
+    let hints = config.as_ref().map_or(true, |config| config.hints);
+    if let Ok(config) = config {
+        i18n::init(config.locale.as_deref());
+        process_env::init(config.env.clone());
+        allowlist::init(config.tools.allowed_commands.clone());
+        if let Err(e) = redact::init(&config.redaction) {
+            log::warn!("{}; using the built-in redaction rules", e);
+        }
+        target_dirs::init(config.build.profile_target_dirs);
+        lockfile::init(config.build.lockfile);
+    } else {
+        i18n::init(None);
+        if let Some(mode) = lockfile_mode {
+            lockfile::init(mode);
+        }
+    }
+    let start_time = Instant::now();
+    let started_at = chrono::Utc::now();
+    events::emit(events::Event::CommandStarted {
+        command: command_name.clone(),
+        args: args.clone(),
+        atlas_version: env!("CARGO_PKG_VERSION").to_string(),
+    });
+
+    // Terminate child processes and restore the terminal on Ctrl-C
+    signal::install_handler();
+
+    // Commands other than these are recorded in the project's history
+    let repeating = matches!(cli.command, Commands::Again | Commands::Last { .. });
+    let recorded = !is_read_only(&cli.command);
+    let history_root = if repeating {
+        None
+    } else {
+        utils::find_rust_project_root(
+            cli.project_dir
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(".")),
+        )
+        .ok()
+    };
+
+    // Files the command changes are recorded in the project's audit trail
+    if let Ok(log) = audit::log_path(project_root.as_deref()) {
+        audit::init(log, format!("atlas {}", args.join(" ")));
+    }
+
+    // Output meant for scripts gets nothing else on stdout
+    let quiet = cli.quiet || is_machine_readable(&cli.command);
+
+    // Print banner unless quiet mode; a repeated command prints its own
+    if !quiet && !matches!(cli.command, Commands::Again) {
+        print_banner();
+    }
+
+    // Onboard interactive first-time users; scripts and CI never see it
+    if !quiet && std::io::stdin().is_terminal() && std::io::stdout().is_terminal() {
+        let first_run = onboarding::OnboardingState::load()
+            .map(|state| state.is_none())
+            .unwrap_or(false);
+        if first_run {
+            let dir = cli
+                .project_dir
+                .clone()
+                .unwrap_or_else(|| PathBuf::from("."));
+            if let Err(e) = onboarding::run(&dir) {
+                log::warn!("Skipping onboarding: {}", e);
+            }
+        }
+    }
+
+    // Execute command
+    let result = match cli.command {
+        Commands::Initialize { undo: true, .. } => initialize::undo(cli.project_dir),
+        Commands::Initialize {
+            no_backup,
+            no_tools,
+            force,
+            scope,
+            nix,
+            full,
+            minimal,
+            undo: false,
+        } => {
+            initialize::run(
+                cli.project_dir,
+                initialize::InitOptions {
+                    no_backup,
+                    no_tools,
+                    force,
+                    scope,
+                    nix,
+                    full,
+                    minimal,
+                },
+            )
+            .await
+        }
+        Commands::InstallTools {
+            list,
+            only,
+            print_install_commands,
+        } => tools::run(list, only, print_install_commands).await,
+        Commands::Build { build_type } => build::run(build_type, cli.project_dir).await,
+        Commands::Development { dev_command } => {
+            development::run(dev_command, cli.project_dir).await
+        }
+        Commands::Optimize {
+            all,
+            clean,
+            deps,
+            benchmark,
+            baseline,
+        } => optimize::run(all, clean, deps, benchmark, &baseline, cli.project_dir).await,
+        Commands::Status {
+            detailed,
+            json,
+            history,
+            weeks,
+        } => {
+            if history {
+                status::history(weeks, json, cli.project_dir).await
+            } else {
+                status::run(detailed, json, cli.project_dir).await
+            }
+        }
+        Commands::Config { config_command } => {
+            commands::config::run(config_command, cli.project_dir).await
+        }
+        Commands::Analyze { analyze_command } => {
+            analyze::run(analyze_command, cli.project_dir).await
+        }
+        Commands::Explain { setting } => explain::run(setting, cli.project_dir).await,
+        Commands::Editor { editor_command } => {
+            commands::editor::run(editor_command, cli.project_dir).await
+        }
+        Commands::Docker { docker_command } => {
+            commands::docker::run(docker_command, cli.project_dir).await
+        }
+        Commands::Ci { ci_command } => ci::run(ci_command, cli.project_dir).await,
+        Commands::Release { resume, skip } => {
+            commands::release::run(resume, skip, cli.project_dir).await
+        }
+        Commands::Refresh { force } => refresh::run(force, cli.project_dir).await,
+        Commands::Run { task } => commands::run::run(task, cli.project_dir).await,
+        Commands::Doctor {
+            native_deps,
+            fix,
+            rules,
+        } => doctor::run(native_deps, fix, rules, cli.project_dir).await,
+        Commands::Cache { cache_command } => {
+            commands::cache::run(cache_command, cli.project_dir).await
+        }
+        Commands::Artifacts { artifacts_command } => {
+            commands::artifacts::run(artifacts_command, cli.project_dir).await
+        }
+        Commands::Baseline { baseline_command } => {
+            commands::baseline::run(baseline_command, cli.project_dir).await
+        }
+        Commands::Telemetry { telemetry_command } => {
+            commands::telemetry::run(telemetry_command).await
+        }
+        Commands::Audit { audit_command } => {
+            commands::audit::run(audit_command, cli.project_dir).await
+        }
+        Commands::Debug { debug_command } => {
+            commands::debug::run(debug_command, cli.project_dir).await
+        }
+        Commands::Fleet {
+            manifest,
+            fleet_command,
+        } => commands::fleet::run(fleet_command, manifest).await,
+        Commands::Remote { remote_command } => {
+            commands::remote::run(remote_command, cli.project_dir).await
+        }
+        Commands::Maintain {
+            dry_run,
+            schedule,
+            scheduler,
+            unschedule,
+        } => {
+            commands::maintain::run(dry_run, schedule, scheduler, unschedule, cli.project_dir).await
+        }
+        Commands::Again => commands::history::again(cli.project_dir).await,
+        Commands::Last { stats } => commands::history::last(stats, cli.project_dir).await,
+        Commands::Update { check } => update::run(check).await,
+        Commands::BenchSelf {
+            iterations,
+            filter,
+            save,
+        } => bench_self::run(iterations, filter, save).await,
+    };
+
+    let error = result.as_ref().err().map(ToString::to_string);
+    session::finish(error.as_deref(), start_time.elapsed());
+    // Next steps, for people at a terminal
+    if hints && !quiet && std::io::stdout().is_terminal() {
+        let outcome = rules::Outcome {
+            command: &command_path,
+            args: &args,
+            success: result.is_ok(),
+            duration: start_time.elapsed(),
+            build: None,
+        };
+        print_hints(&outcome, history_root.as_deref(), started_at);
+    }
+    if let Some(project_root) = history_root.filter(|_| recorded) {
+        let invocation = history::Invocation {
+            started_at,
+            args: args.clone(),
+            cwd: std::env::current_dir().unwrap_or_default(),
+            success: result.is_ok(),
+            duration_secs: start_time.elapsed().as_secs_f64(),
+            error: error.clone(),
+        };
+        if let Err(e) = history::History::record(&project_root, &invocation) {
+            log::debug!("Could not record the command history: {}", e);
+        }
+    }
+    events::emit(events::Event::CommandFinished {
+        command: command_name,
+        success: result.is_ok(),
+        duration_secs: start_time.elapsed().as_secs_f64(),
+        error,
+    });
+    result
+}
+
+/// Footer with the hints for how a command went; the build it recorded, if
+/// any, is read back from the stats store
+fn print_hints(
+    outcome: &rules::Outcome,
+    project_root: Option<&std::path::Path>,
+    started_at: chrono::DateTime<chrono::Utc>,
+) {
+    let record = if outcome.command.starts_with("build ") {
+        let project = project_root.and_then(|root| std::fs::canonicalize(root).ok());
+        stats::StatsStore::load_since(started_at)
+            .unwrap_or_default()
+            .into_iter()
+            .rev()
+            .find(|record| Some(&record.project) == project.as_ref())
+    } else {
+        None
+    };
+    let outcome = rules::Outcome {
+        build: record.as_ref(),
+        ..*outcome
+    };
+    let ignored = project_root
+        .and_then(|root| project_config::ProjectConfig::load(root).ok())
+        .map(|config| config.ignore_rules)
+        .unwrap_or_default();
+    let hints = rules::hints(&outcome, &ignored);
+    if hints.is_empty() {
+        return;
+    }
+    println!();
+    for hint in hints {
+        let line = i18n::t_with(
+            "footer.hint",
+            &[
+                ("message", &hint.message),
+                (
+                    "hint",
+                    &style::code(hint.hint.as_deref().unwrap_or_default()),
+                ),
+            ],
+        );
+        println!("{}", style::text(&line));
+    }
+}
+
+/// Commands whose stdout is parsed by scripts and tools, including anything
+/// run with `--json`
+fn is_machine_readable(command: &Commands) -> bool {
+    matches!(
+        command,
+        Commands::Ci { .. }
+            | Commands::Editor {
+                editor_command: EditorCommands::Status
+            }
+            | Commands::Status { json: true, .. }
+            | Commands::Build {
+                build_type: BuildCommands::Matrix { json: true, .. }
+            }
+            | Commands::Development {
+                dev_command: DevCommands::Profile { json: true, .. }
+            }
+            | Commands::Audit {
+                audit_command: AuditCommands::Show { json: true, .. }
+            }
+            | Commands::Config {
+                config_command: ConfigCommands::Bundle {
+                    bundle_command: BundleCommands::Export { output: None }
+                }
+            }
+            | Commands::Analyze {
+                analyze_command: AnalyzeCommands::Graph { output: None, .. }
+            }
+    )
+}
+
+/// Commands that only show something, left out of the project's history so
+/// looking around does not push the last real command out of `atlas again`
+fn is_read_only(command: &Commands) -> bool {
+    matches!(
+        command,
+        Commands::Status { .. }
+            | Commands::Explain { .. }
+            | Commands::Last { .. }
+            | Commands::Config {
+                config_command: ConfigCommands::Show { .. }
+                    | ConfigCommands::Validate
+                    | ConfigCommands::Doctor
+            }
+            | Commands::Editor {
+                editor_command: EditorCommands::Status
+            }
+            | Commands::Audit {
+                audit_command: AuditCommands::Show { .. }
+            }
+    )
+}
+
+fn print_banner() {
+    println!("{}", style::title("🚀 Atlas"));
+    println!(
+        "{}",
+        style::dim("Dramatically improve your Rust build times and development workflow")
+    );
+    println!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_machine_readable_commands() {
+        let command = |args: &[&str]| Cli::try_parse_from(args).unwrap().command;
+        assert!(is_machine_readable(&command(&[
+            "atlas",
+            "ci",
+            "affected",
+            "--base",
+            "origin/main"
+        ])));
+        assert!(is_machine_readable(&command(&[
+            "atlas", "editor", "status"
+        ])));
+        assert!(!is_machine_readable(&command(&[
+            "atlas", "editor", "vscode"
+        ])));
+        assert!(!is_machine_readable(&command(&["atlas", "status"])));
+        assert!(is_machine_readable(&command(&[
+            "atlas", "status", "--json"
+        ])));
+        assert!(is_machine_readable(&command(&[
+            "atlas", "dev", "profile", "--json"
+        ])));
+        assert!(is_machine_readable(&command(&[
+            "atlas", "audit", "show", "--json"
+        ])));
+        assert!(is_machine_readable(&command(&[
+            "atlas", "config", "bundle", "export"
+        ])));
+        assert!(!is_machine_readable(&command(&[
+            "atlas",
+            "config",
+            "bundle",
+            "export",
+            "-o",
+            "bundle.toml"
+        ])));
+        assert!(is_machine_readable(&command(&[
+            "atlas", "analyze", "graph", "--format", "mermaid"
+        ])));
+    }
+
+    #[test]
+    fn test_read_only_commands() {
+        let command = |args: &[&str]| Cli::try_parse_from(args).unwrap().command;
+        assert!(is_read_only(&command(&["atlas", "status"])));
+        assert!(is_read_only(&command(&["atlas", "config", "show"])));
+        assert!(!is_read_only(&command(&["atlas", "config", "reset"])));
+        assert!(!is_read_only(&command(&["atlas", "refresh"])));
+    }
+}
@@ -0,0 +1,897 @@
+use crate::analysis::alternatives::{self, DOMINANT_SHARE};
+use crate::analysis::build_scripts::{self, RerunCause};
+use crate::analysis::cost;
+use crate::analysis::critical_path::{self, ScheduleModel};
+use crate::analysis::metadata::CargoMetadata;
+use crate::analysis::outdated::{self, IndexEntry, IndexSource, Version};
+use crate::analysis::proc_macros::{self, Variant};
+use crate::analysis::split::{self, LlvmLines};
+use crate::analysis::timings::TimingReport;
+use crate::commands::baseline::format_comparison;
+use crate::commands::tools::ensure_tool;
+use crate::config::OptimizerConfig;
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::manifest::Manifest;
+use crate::rustflags::RustflagsLayers;
+use crate::significance::{self, Samples};
+use crate::style;
+use crate::table::{Cell, Table};
+use crate::utils::*;
+use crate::{AnalyzeCommands, GraphFormat};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+pub async fn run(
+    analyze_command: AnalyzeCommands,
+    project_dir: Option<PathBuf>,
+) -> OptimizerResult<()> {
+    let project_root = if let Some(dir) = project_dir {
+        dir
+    } else {
+        find_rust_project_root(".")?
+    };
+
+    if !is_rust_project(&project_root) {
+        return Err(OptimizerError::project_validation(
+            "No Cargo.toml found. Please run this command from a Rust project directory.",
+        ));
+    }
+
+    match analyze_command {
+        AnalyzeCommands::Graph {
+            format,
+            output,
+            members_only,
+            dev,
+        } => run_graph(&project_root, format, output, members_only, dev),
+        AnalyzeCommands::CriticalPath { top, jobs } => run_critical_path(&project_root, top, jobs),
+        AnalyzeCommands::Outdated { direct } => run_outdated(&project_root, direct),
+        AnalyzeCommands::Split { package, jobs } => {
+            ensure_tool("cargo-llvm-lines").await?;
+            run_split(&project_root, package, jobs)
+        }
+        AnalyzeCommands::Cost {
+            package: None,
+            top,
+            jobs: _,
+            llvm_lines: _,
+        } => run_cost_ranking(&project_root, top),
+        AnalyzeCommands::Cost {
+            package: Some(package),
+            jobs,
+            llvm_lines,
+            ..
+        } => {
+            if llvm_lines {
+                ensure_tool("cargo-llvm-lines").await?;
+            }
+            run_cost(&project_root, &package, jobs, llvm_lines)
+        }
+        AnalyzeCommands::BuildScripts { verify } => run_build_scripts(&project_root, verify),
+        AnalyzeCommands::ProcMacros {
+            experiment,
+            iterations,
+            apply,
+        } => run_proc_macros(
+            &project_root,
+            experiment.then_some(iterations as usize),
+            apply,
+        ),
+    }
+}
+
+fn run_graph(
+    project_root: &Path,
+    format: GraphFormat,
+    output: Option<PathBuf>,
+    members_only: bool,
+    include_dev: bool,
+) -> OptimizerResult<()> {
+    let metadata = CargoMetadata::load(project_root)?;
+
+    let mut graph = metadata.dependency_graph(include_dev);
+    if members_only {
+        graph = graph.members_only();
+    }
+
+    let times = load_crate_times(&metadata.target_directory);
+    let rendered = match format {
+        GraphFormat::Dot => graph.render_dot(&times),
+        GraphFormat::Mermaid => graph.render_mermaid(&times),
+    };
+
+    if let Some(output_path) = output {
+        crate::audit::write(&output_path, rendered)?;
+        print_success(&format!(
+            "✅ Dependency graph ({} crates) written to {}",
+            graph.nodes.len(),
+            output_path.display()
+        ));
+    } else {
+        print!("{}", rendered);
+    }
+
+    Ok(())
+}
+
+fn run_critical_path(project_root: &Path, top: usize, jobs: Option<usize>) -> OptimizerResult<()> {
+    let metadata = CargoMetadata::load(project_root)?;
+    let report = TimingReport::load_latest(&metadata.target_directory)?;
+    let analysis = critical_path::analyze(&report, effective_jobs(jobs)?)?;
+    let secs = |secs: f64| format_duration(Duration::from_secs_f64(secs.max(0.0)));
+
+    println!("{}", style::title("🧭 Build Critical Path"));
+    if let Some(source) = &report.source {
+        println!("  Report: {}", source.display());
+    }
+    println!();
+    println!("  Recorded wall time:   {}", secs(analysis.wall_time));
+    println!(
+        "  Critical path:        {}",
+        secs(analysis.critical_path_time)
+    );
+    println!(
+        "  Total work:           {} ({} jobs → {})",
+        secs(analysis.total_work),
+        analysis.jobs,
+        secs(analysis.work_bound())
+    );
+    println!(
+        "  Lower bound:          {}",
+        style::good(&secs(analysis.lower_bound()))
+    );
+    if analysis.is_dependency_bound() {
+        print_status("The build is limited by its dependency chain; more cores will not help");
+    } else {
+        print_status("The build is limited by core count; shortening the chain will not help");
+    }
+
+    println!();
+    println!("{}", style::title("🔗 Chain"));
+    let mut chain = Table::new(&["Start", "Unit", "Time", "Codegen"])
+        .right(0)
+        .right(2)
+        .right(3);
+    for step in &analysis.path {
+        chain.add_row(vec![
+            secs(step.start).into(),
+            step.unit.as_str().into(),
+            secs(step.duration).into(),
+            step.codegen.map(secs).unwrap_or_default().into(),
+        ]);
+    }
+    chain.print();
+
+    if !analysis.opportunities.is_empty() {
+        println!();
+        println!("{}", style::title("🎯 Opportunities"));
+        let mut opportunities = Table::new(&["Unit", "Time", "2x faster", "Removed"])
+            .right(1)
+            .right(2)
+            .right(3);
+        for opportunity in analysis.opportunities.iter().take(top) {
+            opportunities.add_row(vec![
+                opportunity.unit.as_str().into(),
+                secs(opportunity.duration).into(),
+                format!("-{}", secs(opportunity.halved_savings)).into(),
+                format!("-{}", secs(opportunity.max_savings)).into(),
+            ]);
+        }
+        opportunities.print();
+        println!();
+        print_status("Speed these crates up or split them so dependents can start earlier");
+    }
+
+    Ok(())
+}
+
+/// A dependency with a newer release, and what upgrading it rebuilds
+struct Upgrade {
+    name: String,
+    current: Version,
+    target: Version,
+    direct: bool,
+    rebuilds: usize,
+    rebuild_secs: f64,
+    timed: bool,
+}
+
+fn run_outdated(project_root: &Path, direct_only: bool) -> OptimizerResult<()> {
+    let metadata = CargoMetadata::load(project_root)?;
+    let graph = metadata.dependency_graph(true);
+    let times = load_crate_times(&metadata.target_directory);
+
+    let direct: HashSet<usize> = graph
+        .edges
+        .iter()
+        .filter(|(from, _)| graph.nodes[*from].is_member)
+        .map(|(_, to)| *to)
+        .collect();
+    let sources: HashMap<&str, Option<&str>> = metadata
+        .packages
+        .iter()
+        .map(|package| (package.id.as_str(), package.source.as_deref()))
+        .collect();
+    let candidates: Vec<usize> = (0..graph.nodes.len())
+        .filter(|index| {
+            let node = &graph.nodes[*index];
+            outdated::is_crates_io(sources.get(node.id.as_str()).copied().flatten())
+                && (!direct_only || direct.contains(index))
+        })
+        .collect();
+    if candidates.is_empty() {
+        print_success("✅ No crates.io dependencies to check");
+        return Ok(());
+    }
+
+    let spinner = create_spinner(&format!(
+        "Checking {} dependencies against the crates.io index...",
+        candidates.len()
+    ));
+    let lookups: Mutex<HashMap<usize, (Vec<IndexEntry>, IndexSource)>> = Mutex::new(HashMap::new());
+    let workers = num_cpus::get().clamp(1, 8);
+    std::thread::scope(|scope| {
+        for chunk in candidates.chunks((candidates.len() + workers - 1) / workers) {
+            let (graph, lookups) = (&graph, &lookups);
+            scope.spawn(move || {
+                for &index in chunk {
+                    if let Some(found) = outdated::fetch_versions(&graph.nodes[index].name) {
+                        lookups.lock().unwrap().insert(index, found);
+                    }
+                }
+            });
+        }
+    });
+    spinner.finish_and_clear();
+    let lookups = lookups.into_inner().unwrap();
+
+    let mut compatible = Vec::new();
+    let mut breaking = Vec::new();
+    for &index in &candidates {
+        let node = &graph.nodes[index];
+        let (Some(current), Some((entries, _))) =
+            (Version::parse(&node.version), lookups.get(&index))
+        else {
+            continue;
+        };
+        let (newer_compatible, newer_breaking) = outdated::newer_versions(&current, entries);
+        let rebuilt = graph.dependents_closure(&[index]);
+        let upgrade = |target: Version| Upgrade {
+            name: node.name.clone(),
+            current: current.clone(),
+            target,
+            direct: direct.contains(&index),
+            rebuilds: rebuilt.len(),
+            rebuild_secs: rebuilt
+                .iter()
+                .filter_map(|rebuilt| times.get(&graph.nodes[*rebuilt].key()))
+                .sum(),
+            timed: times.contains_key(&node.key()),
+        };
+        if let Some(target) = newer_compatible {
+            compatible.push(upgrade(target));
+        }
+        // Breaking releases of transitive dependencies are for their dependents to adopt
+        if let Some(target) = newer_breaking.filter(|_| direct.contains(&index)) {
+            breaking.push(upgrade(target));
+        }
+    }
+
+    let unreachable = candidates.len() - lookups.len();
+    let from_cache = lookups
+        .values()
+        .filter(|(_, source)| *source == IndexSource::LocalCache)
+        .count();
+
+    println!("{}", style::title("📦 Outdated Dependencies"));
+    if from_cache > 0 {
+        print_warning(&format!(
+            "crates.io unreachable; {} crates checked against Cargo's local index copy, which may be stale",
+            from_cache
+        ));
+    }
+    if unreachable > 0 {
+        print_warning(&format!("{} crates could not be looked up", unreachable));
+    }
+    if compatible.is_empty() && breaking.is_empty() {
+        print_success("✅ Every dependency is on its newest release");
+        return Ok(());
+    }
+
+    print_upgrades(
+        "🔄 Compatible (picked up by `cargo update`)",
+        &mut compatible,
+    );
+    print_upgrades("⚠️  Breaking (needs a Cargo.toml change)", &mut breaking);
+    if times.is_empty() {
+        print_status("Run `atlas dev profile` to estimate rebuild times");
+    }
+    Ok(())
+}
+
+fn print_upgrades(title: &str, upgrades: &mut [Upgrade]) {
+    if upgrades.is_empty() {
+        return;
+    }
+    upgrades.sort_by(|a, b| {
+        b.rebuild_secs
+            .total_cmp(&a.rebuild_secs)
+            .then_with(|| b.rebuilds.cmp(&a.rebuilds))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    println!();
+    println!("{}", style::title(title));
+    let mut table = Table::new(&["Crate", "Current", "Newest", "Rebuild cost"])
+        .indent(3)
+        .right(1);
+    for upgrade in upgrades.iter() {
+        let cost = if upgrade.timed {
+            format!(
+                "rebuilds {} crate{} (~{})",
+                upgrade.rebuilds,
+                if upgrade.rebuilds == 1 { "" } else { "s" },
+                format_duration(Duration::from_secs_f64(upgrade.rebuild_secs))
+            )
+        } else {
+            format!(
+                "rebuilds {} crate{}",
+                upgrade.rebuilds,
+                if upgrade.rebuilds == 1 { "" } else { "s" }
+            )
+        };
+        table.add_row(vec![
+            upgrade.name.as_str().into(),
+            upgrade.current.to_string().into(),
+            Cell::styled(upgrade.target.to_string(), style::good),
+            format!(
+                "{}{}",
+                cost,
+                if upgrade.direct { "" } else { " (transitive)" }
+            )
+            .into(),
+        ]);
+    }
+    table.print();
+}
+
+fn run_split(
+    project_root: &Path,
+    package: Option<String>,
+    jobs: Option<usize>,
+) -> OptimizerResult<()> {
+    let metadata = CargoMetadata::load(project_root)?;
+    let members: Vec<_> = metadata
+        .members()
+        .filter(|member| package.as_ref().map_or(true, |name| &member.name == name))
+        .collect();
+    if members.is_empty() {
+        return Err(OptimizerError::invalid_input(format!(
+            "No workspace member named '{}'",
+            package.unwrap_or_default()
+        )));
+    }
+
+    // Projections are optional; without a timings report we only rank by size
+    let report = TimingReport::load_latest(&metadata.target_directory).ok();
+    let model = match &report {
+        Some(report) => ScheduleModel::new(report, effective_jobs(jobs)?).ok(),
+        None => None,
+    };
+    let schedule = model.as_ref().zip(report.as_ref());
+    if schedule.is_none() {
+        print_warning("No timings report found; run `atlas dev profile` to project savings");
+    }
+
+    println!(
+        "{}",
+        style::title("✂️  Crate Splitting Suggestions (experimental)")
+    );
+    let mut candidates = Vec::new();
+    for member in members {
+        let Some(target) = member.primary_target() else {
+            continue;
+        };
+        let spinner = create_spinner(&format!("Measuring LLVM IR for {}...", member.name));
+        let lines = LlvmLines::measure(project_root, member, target);
+        spinner.finish_and_clear();
+        let lines = lines?;
+
+        println!();
+        println!(
+            "  {} ({} lines of LLVM IR)",
+            style::code(&member.name),
+            lines.total_lines
+        );
+        let mut modules = Table::new(&["Module", "Lines", "Share"])
+            .indent(4)
+            .right(1)
+            .right(2);
+        for module in lines.modules.iter().take(5) {
+            modules.add_row(vec![
+                module.module.as_str().into(),
+                module.lines.to_string().into(),
+                format!("{:.1}%", lines.share(module) * 100.0).into(),
+            ]);
+        }
+        modules.print();
+        candidates.extend(split::suggest(&member.name, &lines, schedule));
+    }
+
+    println!();
+    if candidates.is_empty() {
+        print_success("✅ No module is large enough to be worth splitting out");
+        return Ok(());
+    }
+
+    candidates.sort_by(|a, b| {
+        b.projected_savings
+            .unwrap_or(0.0)
+            .total_cmp(&a.projected_savings.unwrap_or(0.0))
+            .then_with(|| b.share.total_cmp(&a.share))
+    });
+    println!("{}", style::title("🎯 Candidates"));
+    let mut table = Table::new(&["Module", "Share of crate", "Savings"]).right(1);
+    for candidate in &candidates {
+        let savings = match candidate.projected_savings {
+            Some(savings) => format!(
+                "projected -{}",
+                format_duration(Duration::from_secs_f64(savings))
+            ),
+            None => "no timing data".to_string(),
+        };
+        table.add_row(vec![
+            format!("{}::{}", candidate.package, candidate.module).into(),
+            format!("{:.0}%", candidate.share * 100.0).into(),
+            savings.into(),
+        ]);
+    }
+    table.print();
+    println!();
+    print_status(
+        "Moving a module into its own leaf crate lets its codegen run in parallel with the rest",
+    );
+
+    Ok(())
+}
+
+fn run_cost_ranking(project_root: &Path, top: usize) -> OptimizerResult<()> {
+    let metadata = CargoMetadata::load(project_root)?;
+    let report = TimingReport::load_latest(&metadata.target_directory)?;
+    let graph = metadata.dependency_graph(false);
+    let times = report.crate_times();
+    let ranked = cost::heaviest(&graph, &times, top);
+    let total: f64 = times.values().sum();
+
+    println!("{}", style::title("💰 Dependency Cost"));
+    if ranked.is_empty() {
+        print_warning("The timings report has no dependencies to rank");
+        return Ok(());
+    }
+    let mut table = Table::new(&["Crate", "Compile work", "Share"])
+        .right(1)
+        .right(2);
+    for (name, work) in &ranked {
+        table.add_row(vec![
+            name.as_str().into(),
+            format_duration(Duration::from_secs_f64(*work)).into(),
+            format!("{:.0}%", work / total.max(f64::EPSILON) * 100.0).into(),
+        ]);
+    }
+    table.print();
+    println!();
+    for (name, work) in &ranked {
+        let lighter = alternatives::for_crate(name);
+        if work / total.max(f64::EPSILON) >= DOMINANT_SHARE && !lighter.is_empty() {
+            let names: Vec<&str> = lighter.iter().map(|alternative| alternative.name).collect();
+            print_status(&format!(
+                "{} has lighter alternatives ({}); see `atlas analyze cost {}`",
+                name,
+                names.join(", "),
+                name
+            ));
+        }
+    }
+    print_status("Compile work counts each crate and the dependencies only it pulls in");
+    print_status("Run `atlas analyze cost <crate>` for what removing or trimming one would save");
+    Ok(())
+}
+
+fn run_cost(
+    project_root: &Path,
+    package: &str,
+    jobs: Option<usize>,
+    llvm_lines: bool,
+) -> OptimizerResult<()> {
+    let metadata = CargoMetadata::load(project_root)?;
+    let report = TimingReport::load_latest(&metadata.target_directory).ok();
+    let cost = cost::analyze(&metadata, report.as_ref(), effective_jobs(jobs)?, package)?;
+    let secs = |secs: f64| format_duration(Duration::from_secs_f64(secs));
+    let savings = |savings: &cost::Savings| match savings.wall_time {
+        Some(wall_time) => format!(
+            "-{} of build time ({} crates, {} of compile work)",
+            secs(wall_time),
+            savings.crates,
+            secs(savings.work)
+        ),
+        None => format!(
+            "{} crates, {} of compile work",
+            savings.crates,
+            secs(savings.work)
+        ),
+    };
+
+    println!(
+        "{}",
+        style::title(&format!(
+            "💰 Cost of {} {}",
+            cost.name,
+            cost.versions.join(", ")
+        ))
+    );
+    if report.is_none() {
+        print_warning("No timings report found; run `atlas dev profile` to measure compile times");
+    } else if !cost.timed {
+        print_warning(&format!(
+            "{} is not in the last timings report; run `atlas dev profile` to measure it",
+            cost.name
+        ));
+    }
+    println!("  Own compile time:      {}", secs(cost.own_time));
+    if llvm_lines {
+        let package = metadata
+            .packages
+            .iter()
+            .find(|candidate| candidate.name == cost.name)
+            .ok_or_else(|| {
+                OptimizerError::invalid_input(format!("No package named '{}'", cost.name))
+            })?;
+        if let Some(target) = package.primary_target() {
+            let spinner = create_spinner(&format!("Measuring LLVM IR for {}...", package.name));
+            let lines = LlvmLines::measure(project_root, package, target);
+            spinner.finish_and_clear();
+            println!("  LLVM lines:            {}", lines?.total_lines);
+        }
+    }
+    println!(
+        "  Dependencies:          {} crates, {}",
+        cost.transitive,
+        secs(cost.transitive_time)
+    );
+    println!("  Used by:               {}", cost.dependents.join(", "));
+    if !cost.features.is_empty() {
+        println!("  Features:              {}", cost.features.join(", "));
+    }
+    println!();
+    println!("{}", style::title("✂️  Savings"));
+    let removal = match cost.removal_share() {
+        Some(share) => format!("{} ({:.0}%)", savings(&cost.removal), share * 100.0),
+        None => savings(&cost.removal),
+    };
+    println!("  Removed:               {}", style::good(&removal));
+    if cost.optional.is_empty() {
+        println!(
+            "  Feature-trimmed:       {}",
+            style::dim("no optional dependencies enabled")
+        );
+    } else {
+        println!(
+            "  Feature-trimmed:       {}",
+            style::good(&savings(&cost.trimming))
+        );
+        let mut table = Table::new(&["Optional dependency", "Only built for it"])
+            .indent(3)
+            .right(1);
+        for optional in &cost.optional {
+            table.add_row(vec![
+                optional.name.as_str().into(),
+                secs(optional.exclusive_time).into(),
+            ]);
+        }
+        table.print();
+        println!();
+        print_status(&format!(
+            "Set `default-features = false` on {} and enable only the features you use",
+            cost.name
+        ));
+    }
+
+    // Only worth the churn of a swap when the crate dominates the build
+    let total_work: f64 = report
+        .as_ref()
+        .map(|report| report.crate_times().values().sum())
+        .unwrap_or_default();
+    let share = cost
+        .removal_share()
+        .unwrap_or_default()
+        .max(cost.removal.work / total_work.max(f64::EPSILON));
+    let lighter = alternatives::for_crate(&cost.name);
+    if share >= DOMINANT_SHARE && !lighter.is_empty() {
+        println!();
+        println!("{}", style::title("🪶 Lighter Alternatives"));
+        let mut table = Table::new(&["Crate", "Suits", "Caveat"]).indent(3);
+        for alternative in &lighter {
+            table.add_row(vec![
+                Cell::styled(alternative.name, style::code),
+                alternative.suits.into(),
+                Cell::styled(alternative.caveat, style::dim),
+            ]);
+        }
+        table.print();
+        println!();
+        print_status(&format!(
+            "{} accounts for {:.0}% of the build; check the caveats against how you use it",
+            cost.name,
+            share * 100.0
+        ));
+    }
+    Ok(())
+}
+
+fn run_build_scripts(project_root: &Path, verify: bool) -> OptimizerResult<()> {
+    let metadata = CargoMetadata::load(project_root)?;
+    let report = TimingReport::load_latest(&metadata.target_directory).ok();
+
+    // The first build settles anything stale, so scripts the second one
+    // runs are rerunning with nothing changed
+    let reran = if verify {
+        let spinner = create_spinner("Building twice to find build scripts that always rerun...");
+        let settled = execute_command("cargo", &["build"], Some(project_root));
+        let second =
+            settled.and_then(|_| execute_command("cargo", &["build", "-v"], Some(project_root)));
+        spinner.finish_and_clear();
+        let second = second?;
+        if !second.status.success() {
+            return Err(OptimizerError::command_failed(
+                "cargo build failed; fix the build before checking its build scripts",
+            ));
+        }
+        Some(build_scripts::scripts_run(&String::from_utf8_lossy(
+            &second.stderr,
+        )))
+    } else {
+        None
+    };
+
+    let scripts = build_scripts::analyze(&metadata, report.as_ref(), reran.as_ref());
+    println!("{}", style::title("🏗️  Build Scripts"));
+    if scripts.is_empty() {
+        print_warning("No build script output found; build the project first");
+        return Ok(());
+    }
+
+    let secs = |secs: f64| format_duration(Duration::from_secs_f64(secs));
+    let mut table = Table::new(&["Crate", "Run time", "Reruns"]).right(1);
+    for script in &scripts {
+        let reruns =
+            if script.reran == Some(true) || script.causes.iter().any(RerunCause::is_every_build) {
+                Cell::styled("every build", style::alert)
+            } else if script.causes.contains(&RerunCause::NoDirectives) {
+                Cell::styled("on any edit", style::attention)
+            } else {
+                Cell::styled("when inputs change", style::good)
+            };
+        table.add_row(vec![
+            format!("{} {}", script.package, script.version).into(),
+            script.run_time.map(secs).unwrap_or_default().into(),
+            reruns,
+        ]);
+    }
+    table.print();
+
+    let offending: Vec<_> = scripts
+        .iter()
+        .filter(|script| script.is_offending())
+        .collect();
+    println!();
+    if offending.is_empty() {
+        print_success("✅ Every build script reruns only when its inputs change");
+    } else {
+        for script in &offending {
+            println!(
+                "  {} {}",
+                style::code(&script.package),
+                style::dim(&script.link)
+            );
+            for cause in &script.causes {
+                println!("     • {}", cause);
+            }
+            if script.reran == Some(true) && script.causes.is_empty() {
+                println!("     • ran again on a build with nothing changed");
+            }
+        }
+        println!();
+        print_status(
+            "Have each build script print `cargo::rerun-if-changed=` for each existing file it reads, build.rs included",
+        );
+    }
+    if report.is_none() {
+        print_status("Run `atlas dev profile` to see how long each build script takes");
+    }
+    if !verify {
+        print_status("Run with --verify to check which scripts rerun on an unchanged build");
+    }
+
+    Ok(())
+}
+
+fn effective_jobs(jobs: Option<usize>) -> OptimizerResult<usize> {
+    match jobs {
+        Some(jobs) => Ok(jobs),
+        None => Ok(OptimizerConfig::load_or_default()?.get_effective_parallel_jobs()),
+    }
+}
+
+/// Per-crate compile times from the last timings run, if there is one
+fn load_crate_times(target_dir: &Path) -> HashMap<String, f64> {
+    match TimingReport::load_latest(target_dir) {
+        Ok(report) => report.crate_times(),
+        Err(_) => {
+            log::warn!(
+                "No timings report found; run `atlas dev profile` to annotate compile times"
+            );
+            HashMap::new()
+        }
+    }
+}
+
+/// Confidence a variant must be faster at before it is recommended
+const PROC_MACRO_CONFIDENCE: f64 = 0.95;
+
+fn run_proc_macros(
+    project_root: &Path,
+    iterations: Option<usize>,
+    apply: bool,
+) -> OptimizerResult<()> {
+    let metadata = CargoMetadata::load(project_root)?;
+    let report = TimingReport::load_latest(&metadata.target_directory).ok();
+    let macros = proc_macros::proc_macros(&metadata, report.as_ref());
+    let secs = |secs: f64| format_duration(Duration::from_secs_f64(secs));
+
+    println!("{}", style::title("🧪 Proc Macros (experimental)"));
+    if macros.is_empty() {
+        print_status("The workspace builds no proc-macro crates");
+        return Ok(());
+    }
+    let mut table = Table::new(&["Crate", "Compile time", "Used by"]).right(1);
+    for proc_macro in &macros {
+        table.add_row(vec![
+            format!("{} {}", proc_macro.name, proc_macro.version).into(),
+            proc_macro.compile_time.map(secs).unwrap_or_default().into(),
+            proc_macro.dependents.join(", ").into(),
+        ]);
+    }
+    table.print();
+    println!();
+    if report.is_none() {
+        print_status("Run `atlas dev profile` to see how long each takes to compile");
+    }
+    let Some(iterations) = iterations else {
+        print_status(
+            "Run with --experiment to measure whether building them differently speeds up clean builds",
+        );
+        return Ok(());
+    };
+
+    let nightly = command_stdout("rustc", &["-V"], project_root)
+        .is_some_and(|version| version.contains("nightly"));
+    let variants = proc_macros::variants(&RustflagsLayers::detect(project_root, None)?, nightly);
+    let mut current = None;
+    let mut trials = Vec::new();
+    for variant in variants {
+        match measure_variant(project_root, &variant, iterations) {
+            Ok(runs) if current.is_none() => current = Some(Samples::new(runs)),
+            Ok(runs) => trials.push((variant, Samples::new(runs))),
+            // A toolchain without the feature is a result, not a failure
+            Err(e) if current.is_some() => {
+                print_warning(&format!("Skipping {}: {}", variant.name, e));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    let current = current.unwrap_or_else(|| Samples::new(Vec::new()));
+
+    println!();
+    println!("{}", style::title("⏱️  Clean Builds"));
+    if let Some(mean) = current.mean() {
+        println!("  {:<14} {:>10}", "current", secs(mean));
+    }
+    for (variant, samples) in &trials {
+        if let Some(comparison) = significance::compare(&current, samples, PROC_MACRO_CONFIDENCE) {
+            println!(
+                "  {:<14} {:>10}  {}",
+                variant.name,
+                secs(comparison.means.1),
+                format_comparison(&comparison, PROC_MACRO_CONFIDENCE)
+            );
+            println!("  {:<14} {}", "", style::dim(variant.description));
+        }
+    }
+    println!();
+
+    let Some((winner, comparison)) = proc_macros::best(&current, &trials, PROC_MACRO_CONFIDENCE)
+    else {
+        print_status("No variant builds significantly faster; the current settings are fine");
+        if iterations < 2 {
+            print_status("Use --iterations 3 or more for a verdict");
+        }
+        return Ok(());
+    };
+    print_success(&format!(
+        "{} builds {:.0}% faster",
+        winner.name, -comparison.change
+    ));
+    keep_variant(project_root, winner, apply)
+}
+
+/// Clean builds of the workspace with a variant, in seconds
+fn measure_variant(
+    project_root: &Path,
+    variant: &Variant,
+    iterations: usize,
+) -> OptimizerResult<Vec<f64>> {
+    let mut runs = Vec::with_capacity(iterations);
+    for run in 1..=iterations {
+        execute_command("cargo", &["clean"], Some(project_root))?;
+        let spinner = create_spinner(&format!(
+            "Clean build with {} ({}/{})...",
+            variant.name, run, iterations
+        ));
+        let start = std::time::Instant::now();
+        let output = execute_command_with_env(
+            "cargo",
+            &["build", "--workspace"],
+            Some(project_root),
+            &variant.env,
+        );
+        let elapsed = start.elapsed();
+        spinner.finish_and_clear();
+        let output = output?;
+        if !output.status.success() {
+            return Err(OptimizerError::build_failed(
+                String::from_utf8_lossy(&output.stderr)
+                    .lines()
+                    .find(|line| line.starts_with("error"))
+                    .unwrap_or("cargo build failed")
+                    .to_string(),
+            ));
+        }
+        runs.push(elapsed.as_secs_f64());
+    }
+    Ok(runs)
+}
+
+/// Write or print the settings that keep a winning variant
+fn keep_variant(project_root: &Path, winner: &Variant, apply: bool) -> OptimizerResult<()> {
+    let Some(level) = winner.opt_level else {
+        print_status(&format!(
+            "Add {} to the rustflags of nightly builds to keep it",
+            style::code(proc_macros::CROSS_THREAD_FLAG)
+        ));
+        return Ok(());
+    };
+    if !apply {
+        print_status(&format!(
+            "Keep it with {} under {}, or rerun with --apply",
+            style::code(&format!("opt-level = {}", level)),
+            style::code("[profile.dev.build-override]")
+        ));
+        return Ok(());
+    }
+    let path = project_root.join("Cargo.toml");
+    let mut manifest = Manifest::load(&path)?;
+    if manifest.set_key("profile.dev.build-override", "opt-level", level)? {
+        manifest.save(&path)?;
+        print_success(&format!(
+            "Set opt-level = {} in [profile.dev.build-override] of {}",
+            level,
+            path.display()
+        ));
+    } else {
+        print_warning(
+            "[profile.dev.build-override] is managed by Atlas; set it with `atlas refresh`",
+        );
+    }
+    Ok(())
+}
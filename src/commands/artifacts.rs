@@ -0,0 +1,218 @@
+use crate::analysis::metadata::CargoMetadata;
+use crate::artifacts::{self, archive_name, pack_args, unpack_args, Store};
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::project_config::ProjectConfig;
+use crate::style;
+use crate::target_dirs::{for_profile, profile_dir};
+use crate::utils::*;
+use crate::ArtifactsCommands;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// What archives are keyed on and where a profile's artifacts live
+struct Workspace {
+    name: String,
+    lockfile: String,
+    rustc: String,
+    root: PathBuf,
+    target_directory: PathBuf,
+}
+
+impl Workspace {
+    fn load(project_root: &Path) -> OptimizerResult<Self> {
+        let metadata = CargoMetadata::load(project_root)?;
+        let root = metadata.workspace_root.clone();
+        let lockfile = fs::read_to_string(root.join("Cargo.lock")).map_err(|_| {
+            OptimizerError::project_validation(
+                "Artifacts are keyed on Cargo.lock; run `cargo generate-lockfile` first",
+            )
+        })?;
+        let rustc = command_stdout("rustc", &["-vV"], &root)
+            .ok_or_else(|| OptimizerError::tool_not_found("rustc"))?;
+        Ok(Self {
+            name: root
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| "workspace".to_string()),
+            lockfile,
+            rustc,
+            target_directory: metadata.target_directory,
+            root,
+        })
+    }
+
+    fn archive(&self, profile: &str) -> String {
+        archive_name(&self.name, profile, &self.lockfile, &self.rustc)
+    }
+
+    fn profile_dir(&self, profile: &str) -> PathBuf {
+        for_profile(&self.root, profile, &self.target_directory).join(profile_dir(profile))
+    }
+}
+
+pub async fn run(
+    artifacts_command: ArtifactsCommands,
+    project_dir: Option<PathBuf>,
+) -> OptimizerResult<()> {
+    let project_root = find_rust_project_root(project_dir.unwrap_or_else(|| PathBuf::from(".")))?;
+    let config = ProjectConfig::load(&project_root)?.artifacts;
+
+    let (push, store, profiles, force) = match artifacts_command {
+        ArtifactsCommands::Push {
+            store,
+            profile,
+            force,
+        } => (true, store, profile, force),
+        ArtifactsCommands::Pull { store, profile } => (false, store, profile, false),
+    };
+    let store = artifacts::store(store.as_deref(), &config)?;
+    for program in ["tar"].into_iter().chain(store.program()) {
+        if !is_tool_available(program) {
+            return Err(OptimizerError::tool_not_found(program));
+        }
+    }
+    let profiles = if profiles.is_empty() {
+        config.profiles
+    } else {
+        profiles
+    };
+    let workspace = Workspace::load(&project_root)?;
+
+    println!(
+        "{}",
+        style::title(if push {
+            "📦 Pushing Artifacts"
+        } else {
+            "📦 Pulling Artifacts"
+        })
+    );
+    println!("   • {}", style::code(&store.to_string()));
+    println!();
+
+    let scratch = tempfile::tempdir()?;
+    for profile in &profiles {
+        let name = workspace.archive(profile);
+        let archive = scratch.path().join(&name);
+        if push {
+            push_profile(&store, &workspace, profile, &name, &archive, force)?;
+        } else {
+            pull_profile(&store, &workspace, profile, &name, &archive)?;
+        }
+    }
+    Ok(())
+}
+
+fn push_profile(
+    store: &Store,
+    workspace: &Workspace,
+    profile: &str,
+    name: &str,
+    archive: &Path,
+    force: bool,
+) -> OptimizerResult<()> {
+    let profile_dir = workspace.profile_dir(profile);
+    if !profile_dir.join("deps").is_dir() {
+        print_warning(&format!(
+            "Nothing built for the {} profile in {}",
+            profile,
+            profile_dir.display()
+        ));
+        return Ok(());
+    }
+    if !force && exists(store, name)? {
+        print_status(&format!(
+            "{}: already stored as {}",
+            profile,
+            style::code(name)
+        ));
+        return Ok(());
+    }
+
+    let start = Instant::now();
+    run_tool("tar", &pack_args(&profile_dir, archive))?;
+    let size = fs::metadata(archive)?.len();
+    match store.local_path(name) {
+        Some(path) => {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            // Copy then rename, so a reader never sees half an archive
+            let partial = path.with_extension("partial");
+            fs::copy(archive, &partial)?;
+            fs::rename(&partial, &path)?;
+        }
+        None => run_store(store, &store.upload_args(archive, name))?,
+    }
+    print_success(&format!(
+        "{}: pushed {} as {} in {}",
+        profile,
+        format_bytes(size),
+        style::code(name),
+        format_duration(start.elapsed())
+    ));
+    Ok(())
+}
+
+fn pull_profile(
+    store: &Store,
+    workspace: &Workspace,
+    profile: &str,
+    name: &str,
+    archive: &Path,
+) -> OptimizerResult<()> {
+    if !exists(store, name)? {
+        print_warning(&format!(
+            "{}: nothing stored yet for this Cargo.lock and toolchain",
+            profile
+        ));
+        return Ok(());
+    }
+
+    let start = Instant::now();
+    let source = match store.local_path(name) {
+        Some(path) => path,
+        None => {
+            run_store(store, &store.download_args(name, archive))?;
+            archive.to_path_buf()
+        }
+    };
+    let profile_dir = workspace.profile_dir(profile);
+    fs::create_dir_all(&profile_dir)?;
+    run_tool("tar", &unpack_args(&profile_dir, &source))?;
+    print_success(&format!(
+        "{}: restored {} into {} in {}",
+        profile,
+        format_bytes(fs::metadata(&source)?.len()),
+        profile_dir.display(),
+        format_duration(start.elapsed())
+    ));
+    Ok(())
+}
+
+fn exists(store: &Store, name: &str) -> OptimizerResult<bool> {
+    if let Some(path) = store.local_path(name) {
+        return Ok(path.is_file());
+    }
+    let args = store.exists_args(name);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let output = execute_command(store.program().unwrap_or_default(), &args, None)?;
+    Ok(output.status.success())
+}
+
+fn run_store(store: &Store, args: &[String]) -> OptimizerResult<()> {
+    run_tool(store.program().unwrap_or_default(), args)
+}
+
+fn run_tool(program: &str, args: &[String]) -> OptimizerResult<()> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let output = execute_command(program, &args, None)?;
+    if !output.status.success() {
+        return Err(OptimizerError::command_failed(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use crate::audit::{self, AuditEntry};
+use crate::error::OptimizerResult;
+use crate::style;
+use crate::table::{Cell, Table};
+use crate::utils::*;
+use crate::AuditCommands;
+use std::path::{Path, PathBuf};
+
+pub async fn run(
+    audit_command: AuditCommands,
+    project_dir: Option<PathBuf>,
+) -> OptimizerResult<()> {
+    let project_root =
+        find_rust_project_root(project_dir.unwrap_or_else(|| PathBuf::from("."))).ok();
+    match audit_command {
+        AuditCommands::Show { limit, json } => show(project_root.as_deref(), limit, json),
+    }
+}
+
+/// List the most recent changes, oldest first
+fn show(project_root: Option<&Path>, limit: usize, json: bool) -> OptimizerResult<()> {
+    let log = audit::log_path(project_root)?;
+    let entries = audit::load(&log)?;
+    let recent = &entries[entries.len().saturating_sub(limit)..];
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(recent)?);
+        return Ok(());
+    }
+
+    println!("{}", style::title("🔏 File Changes"));
+    if recent.is_empty() {
+        print_status(&format!(
+            "Atlas has not changed any files yet; changes are recorded in {}",
+            log.display()
+        ));
+        return Ok(());
+    }
+    let mut table = Table::new(&["Time", "Change", "Path", "Before", "After", "Command"]);
+    for entry in recent {
+        let change = match entry.change() {
+            "removed" => Cell::styled(entry.change(), style::bad),
+            "created" => Cell::styled(entry.change(), style::good),
+            change => change.to_string().into(),
+        };
+        table.add_row(vec![
+            entry
+                .recorded_at
+                .with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+                .into(),
+            change,
+            display_path(entry, project_root).into(),
+            short_hash(entry.before.as_deref()).into(),
+            short_hash(entry.after.as_deref()).into(),
+            entry.command.clone().into(),
+        ]);
+    }
+    table.print();
+    if entries.len() > recent.len() {
+        print_status(&format!(
+            "{} earlier changes not shown; see {}",
+            entries.len() - recent.len(),
+            log.display()
+        ));
+    }
+    Ok(())
+}
+
+/// Paths inside the project relative to it
+fn display_path(entry: &AuditEntry, project_root: Option<&Path>) -> String {
+    let root = project_root.and_then(|root| std::fs::canonicalize(root).ok());
+    root.and_then(|root| entry.path.strip_prefix(root).ok().map(Path::to_path_buf))
+        .unwrap_or_else(|| entry.path.clone())
+        .display()
+        .to_string()
+}
+
+fn short_hash(hash: Option<&str>) -> String {
+    hash.map(|hash| hash.chars().take(12).collect())
+        .unwrap_or_else(|| "-".to_string())
+}
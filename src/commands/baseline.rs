@@ -0,0 +1,278 @@
+use crate::baseline::{measure, percent_change, Baseline, BaselineMetadata, MeasureOptions};
+use crate::config::OptimizerConfig;
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::significance::{self, Comparison, Verdict};
+use crate::style;
+use crate::utils::*;
+use crate::BaselineCommands;
+use chrono::Utc;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub async fn run(
+    baseline_command: BaselineCommands,
+    project_dir: Option<PathBuf>,
+) -> OptimizerResult<()> {
+    let project_root = if let Some(dir) = project_dir {
+        dir
+    } else {
+        find_rust_project_root(".")?
+    };
+
+    if !is_rust_project(&project_root) {
+        return Err(OptimizerError::project_validation(
+            "No Cargo.toml found. Please run this command from a Rust project directory.",
+        ));
+    }
+
+    match baseline_command {
+        BaselineCommands::Record {
+            name,
+            no_clean,
+            no_tests,
+            cooldown,
+            iterations,
+            warmup,
+        } => record(
+            &project_root,
+            &name,
+            measure_options(no_clean, no_tests, cooldown, iterations, warmup),
+        ),
+        BaselineCommands::Compare {
+            name,
+            no_clean,
+            no_tests,
+            cooldown,
+            iterations,
+            warmup,
+            confidence,
+        } => {
+            if !(50.0..100.0).contains(&confidence) {
+                return Err(OptimizerError::invalid_input(
+                    "--confidence must be at least 50 and below 100",
+                ));
+            }
+            compare(
+                &project_root,
+                &name,
+                measure_options(no_clean, no_tests, cooldown, iterations, warmup),
+                confidence / 100.0,
+            )
+        }
+        BaselineCommands::List => list(&project_root),
+    }
+}
+
+fn measure_options(
+    no_clean: bool,
+    no_tests: bool,
+    cooldown: Option<u64>,
+    iterations: u32,
+    warmup: u32,
+) -> MeasureOptions {
+    MeasureOptions {
+        clean_build: !no_clean,
+        tests: !no_tests,
+        cooldown: cooldown.map(Duration::from_secs),
+        iterations: iterations as usize,
+        warmup: warmup as usize,
+    }
+}
+
+pub(crate) fn record(
+    project_root: &Path,
+    name: &str,
+    options: MeasureOptions,
+) -> OptimizerResult<()> {
+    crate::baseline::validate_name(name)?;
+    print_status(&format!("Recording baseline '{}'...", name));
+
+    let config = OptimizerConfig::load_or_default()?;
+    let baseline = Baseline {
+        name: name.to_string(),
+        recorded_at: Utc::now(),
+        metadata: BaselineMetadata::capture(project_root, &config)?,
+        measurements: measure(project_root, options)?,
+    };
+
+    let path = baseline.save(project_root)?;
+    print_success(&format!(
+        "✅ Baseline '{}' saved to {}",
+        name,
+        path.display()
+    ));
+    Ok(())
+}
+
+pub(crate) fn compare(
+    project_root: &Path,
+    name: &str,
+    options: MeasureOptions,
+    confidence: f64,
+) -> OptimizerResult<()> {
+    crate::baseline::validate_name(name)?;
+    let baseline = Baseline::load(project_root, name)?;
+    print_status(&format!(
+        "Comparing against baseline '{}' recorded {}",
+        name,
+        baseline.recorded_at.format("%Y-%m-%d %H:%M UTC")
+    ));
+
+    let config = OptimizerConfig::load_or_default()?;
+    let metadata = BaselineMetadata::capture(project_root, &config)?;
+    let current = measure(project_root, options)?;
+
+    crate::telemetry::record_comparison(
+        (&baseline.metadata, &baseline.measurements),
+        (&metadata, &current),
+    );
+
+    println!();
+    println!("{}", style::title("📊 Baseline Comparison"));
+    print_metadata_changes(&baseline.metadata, &metadata);
+
+    let mut too_few_runs = false;
+    for ((label, before), (_, after)) in baseline
+        .measurements
+        .durations()
+        .into_iter()
+        .zip(current.durations())
+    {
+        let Some(comparison) = significance::compare(
+            &baseline.measurements.samples(label, before),
+            &current.samples(label, after),
+            confidence,
+        ) else {
+            continue;
+        };
+        too_few_runs |= comparison.verdict == Verdict::TooFewRuns;
+        let throttled = baseline.measurements.throttled.contains_key(label)
+            || current.throttled.contains_key(label);
+        println!(
+            "  {:<20} {:>10} → {:>10}  {}{}",
+            label,
+            format_duration(Duration::from_secs_f64(comparison.means.0)),
+            format_duration(Duration::from_secs_f64(comparison.means.1)),
+            format_comparison(&comparison, confidence),
+            if throttled {
+                style::caution("  (throttled)").to_string()
+            } else {
+                String::new()
+            }
+        );
+    }
+
+    for (binary, before) in &baseline.measurements.binary_sizes {
+        if let Some(after) = current.binary_sizes.get(binary) {
+            println!(
+                "  {:<20} {:>10} → {:>10}  {}",
+                binary,
+                format_bytes(*before),
+                format_bytes(*after),
+                format_change(percent_change(*before as f64, *after as f64))
+            );
+        }
+    }
+    if too_few_runs {
+        println!();
+        print_warning(
+            "A step ran only once on one side, so no winner is declared; record and compare with --iterations 3 or more",
+        );
+    }
+    if !baseline.measurements.throttled.is_empty() || !current.throttled.is_empty() {
+        println!();
+        print_warning(
+            "Steps marked throttled ran on a thermally throttled CPU; rerun with --cooldown or on AC power for a fair comparison",
+        );
+    }
+    println!();
+
+    Ok(())
+}
+
+fn list(project_root: &Path) -> OptimizerResult<()> {
+    let baselines = Baseline::list(project_root)?;
+    if baselines.is_empty() {
+        print_warning("No baselines recorded. Create one with: atlas baseline record <name>");
+        return Ok(());
+    }
+
+    println!("{}", style::title("📚 Recorded Baselines"));
+    for baseline in baselines {
+        println!(
+            "  {} - {} ({})",
+            style::code(&baseline.name),
+            baseline.recorded_at.format("%Y-%m-%d %H:%M UTC"),
+            baseline
+                .metadata
+                .fingerprint
+                .git_commit
+                .as_deref()
+                .map(|commit| &commit[..commit.len().min(8)])
+                .unwrap_or("no commit")
+        );
+    }
+    Ok(())
+}
+
+fn print_metadata_changes(before: &BaselineMetadata, after: &BaselineMetadata) {
+    let (before, after) = (&before.fingerprint, &after.fingerprint);
+    let incompatibilities = before.incompatibilities(after);
+    for difference in &incompatibilities {
+        print_warning(&format!("Environment changed, {}", difference));
+    }
+    if !incompatibilities.is_empty() {
+        print_warning(
+            "Times from different environments are not comparable; record a new baseline in this one",
+        );
+    }
+    if before.config_hash != after.config_hash {
+        print_status("Atlas configuration changed since the baseline was recorded");
+    }
+    if before.git_commit != after.git_commit {
+        print_status(&format!(
+            "Git commit changed: {} → {}",
+            before.git_commit.as_deref().unwrap_or("none"),
+            after.git_commit.as_deref().unwrap_or("none")
+        ));
+    }
+}
+
+/// Render the change of a step's time with its confidence interval, colored
+/// only when it is significant
+pub(crate) fn format_comparison(comparison: &Comparison, confidence: f64) -> String {
+    let change = format!("{:+.1}%", comparison.change);
+    let change = match comparison.verdict {
+        Verdict::Faster => style::good(&change),
+        Verdict::Slower => style::bad(&change),
+        Verdict::NoDifference | Verdict::TooFewRuns => style::plain(&change),
+    };
+    match comparison.interval {
+        Some((low, high)) => format!(
+            "{}  {}",
+            change,
+            style::dim(&format!(
+                "{:.0}% CI {:+.1}% to {:+.1}%, {}",
+                confidence * 100.0,
+                low,
+                high,
+                comparison.verdict
+            ))
+        ),
+        None => format!(
+            "{}  {}",
+            change,
+            style::dim(&comparison.verdict.to_string())
+        ),
+    }
+}
+
+/// Render a percentage change; lower is better for both time and size
+fn format_change(change: Option<f64>) -> style::ColoredString {
+    match change {
+        Some(change) if change < -1.0 => style::good(&format!("{:+.1}%", change)),
+        Some(change) if change > 1.0 => style::bad(&format!("{:+.1}%", change)),
+        Some(change) => style::plain(&format!("{:+.1}%", change)),
+        None => style::plain("n/a"),
+    }
+}
@@ -0,0 +1,75 @@
+use crate::bench::{measure, workloads, SavedResults, REGRESSION_THRESHOLD};
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::style;
+use crate::utils::*;
+
+pub async fn run(iterations: usize, filter: Option<String>, save: bool) -> OptimizerResult<()> {
+    print_status("Preparing benchmark inputs...");
+    let mut workloads = workloads()?;
+    let saved = SavedResults::load()?;
+    let mut results = SavedResults {
+        atlas_version: env!("CARGO_PKG_VERSION").to_string(),
+        ..SavedResults::default()
+    };
+
+    println!();
+    println!("{}", style::title("⏱️  Atlas hot paths (median)"));
+    let mut regressions = 0;
+    for workload in workloads.workloads.iter_mut().filter(|workload| {
+        filter
+            .as_deref()
+            .map_or(true, |f| workload.name.contains(f))
+    }) {
+        let median = measure(workload, iterations)?;
+        let comparison = match saved
+            .as_ref()
+            .and_then(|saved| saved.ratio(workload.name, median))
+        {
+            Some(ratio) if ratio > REGRESSION_THRESHOLD => {
+                regressions += 1;
+                style::bad(&format!("{:+.0}%", (ratio - 1.0) * 100.0))
+            }
+            Some(ratio) => style::dim(&format!("{:+.0}%", (ratio - 1.0) * 100.0)),
+            None => style::plain(""),
+        };
+        println!(
+            "   • {:<22} {:>12}  {}",
+            workload.name,
+            format!("{:.3?}", median),
+            comparison
+        );
+        results
+            .medians
+            .insert(workload.name.to_string(), median.as_nanos() as u64);
+    }
+
+    if results.medians.is_empty() {
+        return Err(OptimizerError::invalid_input(format!(
+            "No workload matches '{}'",
+            filter.unwrap_or_default()
+        )));
+    }
+
+    println!();
+    if save {
+        results.save()?;
+        print_success(&format!(
+            "Saved results to {}",
+            SavedResults::path()?.display()
+        ));
+    } else if let Some(saved) = &saved {
+        if regressions > 0 {
+            print_warning(&format!(
+                "{} workload(s) more than {:.0}% slower than the results saved with Atlas {}",
+                regressions,
+                (REGRESSION_THRESHOLD - 1.0) * 100.0,
+                saved.atlas_version
+            ));
+        } else {
+            print_success("No regressions against the saved results");
+        }
+    } else {
+        println!("   • Save these results with --save to compare later runs against them");
+    }
+    Ok(())
+}
@@ -1,8 +1,41 @@
+use crate::analysis::affected::Affected;
+use crate::analysis::metadata::CargoMetadata;
+use crate::background;
+use crate::commands::tools::ensure_tool;
+use crate::config::OptimizerConfig;
+use crate::config_layers;
+use crate::contention::{self, Contention};
+use crate::debug_bundle;
+use crate::editor::{LastBuild, RUST_ANALYZER_TARGET_DIR};
 use crate::error::{OptimizerError, OptimizerResult};
+use crate::eta::Progress;
+use crate::events::{self, Event};
+use crate::fingerprint::Fingerprint;
+use crate::fmt::{self, HookInstall};
+use crate::knowledge;
+use crate::matrix::{slot_target_dir, MatrixEntry};
+use crate::members::{package_args, select_members};
+use crate::power;
+use crate::project_config::ProjectConfig;
+use crate::redact;
+use crate::rustflags::{self, RustflagsLayers, ENCODED_RUSTFLAGS};
+use crate::schema::{MatrixReport, MatrixResult, MatrixStatus};
+use crate::signal::{self, CleanupGuard};
+use crate::stats::{BuildRecord, CompilationStats, SccacheSnapshot, StatsStore};
+use crate::style;
+use crate::system::host_triple;
+use crate::table::{Cell, Table};
+use crate::target_dirs;
+use crate::test_failures::{FailedTests, FailureParser};
+use crate::triage::{self, backoff_jobs, FailureKind};
 use crate::utils::*;
 use crate::BuildCommands;
-use std::path::PathBuf;
-use std::time::Instant;
+use chrono::Utc;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 pub async fn run(build_type: BuildCommands, project_dir: Option<PathBuf>) -> OptimizerResult<()> {
     let project_root = if let Some(dir) = project_dir {
@@ -17,27 +50,230 @@ pub async fn run(build_type: BuildCommands, project_dir: Option<PathBuf>) -> Opt
             "No Cargo.toml found. Please run this command from a Rust project directory.",
         ));
     }
+    let mut on_battery = false;
+    if !matches!(
+        build_type,
+        BuildCommands::Clean { .. } | BuildCommands::Fmt { .. }
+    ) {
+        crate::lockfile::preflight(&project_root)?;
+        reconcile_rustflags(&project_root);
+        on_battery = save_power()?;
+    }
 
     match build_type {
-        BuildCommands::Check { stats } => run_check(&project_root, stats).await,
-        BuildCommands::Build { release, stats } => run_build(&project_root, release, stats).await,
-        BuildCommands::Test { changed, stats } => run_test(&project_root, changed, stats).await,
+        BuildCommands::Check { stats, members } => {
+            let scope = workspace_scope(&project_root, members)?;
+            run_check(&project_root, &scope, stats).await
+        }
+        BuildCommands::Build {
+            release,
+            stats,
+            members,
+            background,
+        } => {
+            if background {
+                let config = OptimizerConfig::load_or_default()?;
+                if on_battery && config.power.skip_background_on_battery {
+                    print_status("Skipping the background build on battery power");
+                    return Ok(());
+                }
+                let limits = background::enter(config.development.background_jobs);
+                print_status(&format!("Building in the background: {}", limits));
+            }
+            let scope = workspace_scope(&project_root, members)?;
+            run_build(&project_root, &scope, release, stats).await
+        }
+        BuildCommands::Test {
+            changed,
+            failed_first,
+            stats,
+            members,
+        } => {
+            if failed_first {
+                run_failed_tests(&project_root)?;
+            }
+            let scope = if changed {
+                match affected_scope(&project_root, members)? {
+                    Some(scope) => scope,
+                    None => {
+                        print_success("✅ No workspace member is affected by uncommitted changes");
+                        return Ok(());
+                    }
+                }
+            } else {
+                workspace_scope(&project_root, members)?
+            };
+            run_test(&project_root, &scope, stats).await
+        }
+        BuildCommands::Matrix {
+            only,
+            parallel,
+            fail_fast,
+            json,
+            members,
+        } => {
+            let scope = workspace_scope(&project_root, members)?;
+            run_matrix(&project_root, &scope, only, parallel, fail_fast, json)
+        }
+        BuildCommands::Fmt {
+            check,
+            members,
+            install_hook,
+        } => {
+            if install_hook {
+                return install_fmt_hook(&project_root);
+            }
+            let scope = workspace_scope(&project_root, members)?;
+            run_fmt(&project_root, &scope, check).await
+        }
         BuildCommands::Clean { all } => run_clean(&project_root, all).await,
     }
 }
 
-async fn run_check(project_root: &PathBuf, show_stats: bool) -> OptimizerResult<()> {
+async fn run_fmt(project_root: &Path, scope: &[String], check: bool) -> OptimizerResult<()> {
+    ensure_tool("rustfmt").await?;
+    print_status(if check {
+        "Checking formatting..."
+    } else {
+        "Formatting..."
+    });
+    let timeout = ProjectConfig::load(project_root)?.timeout("fmt");
+    let report = fmt::run(project_root, scope, check, timeout)?;
+
+    // Annotate the files in the GitHub Actions summary
+    let annotate = check && std::env::var("GITHUB_ACTIONS").is_ok_and(|value| value == "true");
+    for file in &report.unformatted {
+        if annotate {
+            println!(
+                "::error file={}::File is not formatted; run `atlas build fmt`",
+                file.display()
+            );
+        } else {
+            println!("   • {}", file.display());
+        }
+    }
+    let summary = format!(
+        "{} of {} files in {}",
+        report.unformatted.len(),
+        report.files,
+        format_duration(report.duration)
+    );
+    match (check, report.unformatted.is_empty()) {
+        (true, true) => print_success(&format!("✅ All {} files are formatted", report.files)),
+        (true, false) => {
+            return Err(OptimizerError::command_failed(format!(
+                "{} need formatting; run `atlas build fmt`",
+                summary
+            )))
+        }
+        (false, _) => print_success(&format!("✅ Formatted {}", summary)),
+    }
+    Ok(())
+}
+
+fn install_fmt_hook(project_root: &Path) -> OptimizerResult<()> {
+    match fmt::install_hook(project_root)? {
+        HookInstall::Installed(path) => print_success(&format!(
+            "✅ Pre-commit hook installed at {}; commits with unformatted files are rejected",
+            path.display()
+        )),
+        HookInstall::AlreadyInstalled(path) => print_status(&format!(
+            "Pre-commit hook already installed at {}",
+            path.display()
+        )),
+        HookInstall::Existing(path) => print_warning(&format!(
+            "{} already exists and was left alone; add this line to it: {}",
+            path.display(),
+            fmt::HOOK_COMMAND
+        )),
+    }
+    Ok(())
+}
+
+/// Cargo arguments selecting the packages to work on: `--workspace`, or one
+/// `-p` per member matched by `--members`
+pub(crate) fn workspace_scope(
+    project_root: &Path,
+    members: Option<Vec<String>>,
+) -> OptimizerResult<Vec<String>> {
+    let Some(selectors) = members else {
+        return Ok(vec!["--workspace".to_string()]);
+    };
+
+    let groups = ProjectConfig::load(project_root)?.groups;
+    let metadata = CargoMetadata::load(project_root)?;
+    let names: Vec<&str> = metadata
+        .members()
+        .map(|package| package.name.as_str())
+        .collect();
+    let selected = select_members(&selectors, &groups, &names)?;
+
+    print_status(&format!(
+        "Selected {} of {} workspace members: {}",
+        selected.len(),
+        names.len(),
+        selected.join(", ")
+    ));
+    Ok(package_args(&selected))
+}
+
+/// Like [`workspace_scope`], narrowed to the members affected by uncommitted
+/// changes. `None` when no member is affected.
+pub(crate) fn affected_scope(
+    project_root: &Path,
+    members: Option<Vec<String>>,
+) -> OptimizerResult<Option<Vec<String>>> {
+    let metadata = CargoMetadata::load(project_root)?;
+    let affected = Affected::detect(project_root, &metadata, None)?;
+    let mut candidates = affected.members_to_check(&metadata);
+
+    if let Some(selectors) = members {
+        let groups = ProjectConfig::load(project_root)?.groups;
+        let names: Vec<&str> = metadata
+            .members()
+            .map(|package| package.name.as_str())
+            .collect();
+        let selected = select_members(&selectors, &groups, &names)?;
+        candidates.retain(|name| selected.iter().any(|selected| selected == name));
+    }
+
+    if candidates.is_empty() {
+        return Ok(None);
+    }
+    let candidates: Vec<String> = candidates.into_iter().map(str::to_string).collect();
+    print_status(&format!(
+        "{} changed files affect {}: {}",
+        affected.changed_files.len(),
+        if affected.workspace_wide.is_empty() {
+            format!("{} workspace members", candidates.len())
+        } else {
+            "the whole workspace".to_string()
+        },
+        candidates.join(", ")
+    ));
+    Ok(Some(package_args(&candidates)))
+}
+
+async fn run_check(project_root: &Path, scope: &[String], show_stats: bool) -> OptimizerResult<()> {
     print_status("Running optimized cargo check...");
 
     let start_time = Instant::now();
+    let _interrupt_guard = report_partial_on_interrupt("Check", start_time);
 
-    let result = execute_command_with_output(
-        "cargo",
-        &["check", "--workspace", "--all-targets"],
-        Some(project_root),
-    );
+    let mut args = vec!["check"];
+    args.extend(scope.iter().map(String::as_str));
+    args.push("--all-targets");
+    let (result, run_stats) = run_cargo_with_backoff(&args, project_root);
 
     let duration = start_time.elapsed();
+    record_build_finished(
+        project_root,
+        "check",
+        "dev",
+        &result,
+        duration,
+        run_stats.as_ref(),
+    );
 
     match result {
         Ok(_) => {
@@ -46,8 +282,8 @@ async fn run_check(project_root: &PathBuf, show_stats: bool) -> OptimizerResult<
                 format_duration(duration)
             ));
 
-            if show_stats {
-                show_build_stats(project_root, "check", duration).await?;
+            if let Some(run_stats) = run_stats.filter(|_| show_stats) {
+                show_build_stats(project_root, "check", duration, Some(&run_stats)).await?;
             }
 
             Ok(())
@@ -59,7 +295,12 @@ async fn run_check(project_root: &PathBuf, show_stats: bool) -> OptimizerResult<
     }
 }
 
-async fn run_build(project_root: &PathBuf, release: bool, show_stats: bool) -> OptimizerResult<()> {
+async fn run_build(
+    project_root: &Path,
+    scope: &[String],
+    release: bool,
+    show_stats: bool,
+) -> OptimizerResult<()> {
     let build_type = if release { "release" } else { "debug" };
     print_status(&format!(
         "Running optimized cargo build ({})...",
@@ -67,14 +308,24 @@ async fn run_build(project_root: &PathBuf, release: bool, show_stats: bool) -> O
     ));
 
     let start_time = Instant::now();
+    let _interrupt_guard = report_partial_on_interrupt("Build", start_time);
 
-    let mut args = vec!["build", "--workspace"];
+    let mut args = vec!["build"];
+    args.extend(scope.iter().map(String::as_str));
     if release {
         args.push("--release");
     }
 
-    let result = execute_command_with_output("cargo", &args, Some(project_root));
+    let (result, run_stats) = run_cargo_with_backoff(&args, project_root);
     let duration = start_time.elapsed();
+    record_build_finished(
+        project_root,
+        "build",
+        if release { "release" } else { "dev" },
+        &result,
+        duration,
+        run_stats.as_ref(),
+    );
 
     match result {
         Ok(_) => {
@@ -83,8 +334,8 @@ async fn run_build(project_root: &PathBuf, release: bool, show_stats: bool) -> O
                 format_duration(duration)
             ));
 
-            if show_stats {
-                show_build_stats(project_root, build_type, duration).await?;
+            if let Some(run_stats) = run_stats.filter(|_| show_stats) {
+                show_build_stats(project_root, build_type, duration, Some(&run_stats)).await?;
             }
 
             Ok(())
@@ -96,24 +347,594 @@ async fn run_build(project_root: &PathBuf, release: bool, show_stats: bool) -> O
     }
 }
 
-async fn run_test(project_root: &PathBuf, _changed: bool, show_stats: bool) -> OptimizerResult<()> {
+/// Use fewer jobs on battery, as `[power]` allows; returns whether builds
+/// save power
+pub(crate) fn save_power() -> OptimizerResult<bool> {
+    let config = OptimizerConfig::load_or_default()?;
+    let Some((state, jobs)) = power::enter(&config.power) else {
+        return Ok(false);
+    };
+    match jobs {
+        Some(jobs) => print_status(&format!(
+            "Saving power ({}): building with {} job(s)",
+            state, jobs
+        )),
+        None => print_status(&format!("Saving power ({})", state)),
+    }
+    Ok(true)
+}
+
+/// Warn about rustflags Cargo will ignore. When `RUSTFLAGS` would hide the
+/// config's flags, hand cargo both through `CARGO_ENCODED_RUSTFLAGS` instead,
+/// for the cargo commands that build for the host.
+pub(crate) fn reconcile_rustflags(project_root: &Path) {
+    let host = host_triple();
+    let layers = match RustflagsLayers::detect(project_root, host.as_deref()) {
+        Ok(layers) => layers,
+        Err(e) => {
+            log::warn!("Could not read rustflags from Cargo config: {}", e);
+            return;
+        }
+    };
+
+    for conflict in layers.conflicts() {
+        print_warning(&conflict.explanation());
+        for source in conflict.ignored() {
+            println!("   • {}: {}", source.origin, source.flags.join(" "));
+        }
+    }
+
+    if let Some(merged) = layers.merged() {
+        print_status(&format!(
+            "Passing config and environment rustflags together through {} to host builds",
+            ENCODED_RUSTFLAGS
+        ));
+        rustflags::init(host, &merged);
+    }
+}
+
+/// Statistics collected while a single cargo invocation runs
+pub struct RunStats {
+    pub compilation: Option<CompilationStats>,
+    pub sccache: Option<SccacheSnapshot>,
+    /// Likely cause when the run failed
+    pub failure: Option<FailureKind>,
+}
+
+/// Lines of plain cargo output kept for the timeout report
+const LAST_OUTPUT_LINES: usize = 10;
+
+/// Run a cargo command, collecting JSON-message statistics when requested.
+/// Failed runs with statistics are triaged to suggest what to try next.
+/// While it runs, progress and an ETA from earlier runs are shown.
+pub fn run_cargo(
+    args: &[&str],
+    project_root: &Path,
+    collect_stats: bool,
+) -> (OptimizerResult<()>, Option<RunStats>) {
+    let timeout = args.first().and_then(|command| {
+        ProjectConfig::load(project_root)
+            .ok()
+            .and_then(|config| config.timeout(command))
+    });
+    if !collect_stats {
+        return (
+            execute_command_with_timeout("cargo", args, Some(project_root), timeout),
+            None,
+        );
+    }
+
+    let sccache_before = SccacheSnapshot::capture();
+    let oom_kills_before = triage::oom_kills();
+
+    let mut json_args = args.to_vec();
+    json_args.push("--message-format=json-diagnostic-rendered-ansi");
+
+    // Cargo's own progress bar would fight ours, so its stderr is relayed
+    // around the bar; keep the colors it would have used on the terminal
+    let mut progress = Progress::start(project_root, args);
+    if progress.is_drawn() && std::env::var_os("CARGO_TERM_COLOR").is_none() {
+        json_args.push("--color=always");
+    }
+
+    let mut compilation = CompilationStats::default();
+    let mut last_output = VecDeque::with_capacity(LAST_OUTPUT_LINES);
+    let mut lock_explained = false;
+    let result = execute_command_streaming(
+        "cargo",
+        &json_args,
+        Some(project_root),
+        timeout,
+        progress.is_drawn(),
+        |stream, line| {
+            if let Some(rendered) = compilation.record_message(line) {
+                progress.suspend(|| eprint!("{}", rendered));
+            } else if !line.starts_with('{') {
+                progress.suspend(|| match stream {
+                    Stream::Stdout => println!("{}", line),
+                    Stream::Stderr => eprintln!("{}", line),
+                });
+                if !lock_explained && line.contains("Blocking waiting for file lock") {
+                    lock_explained = true;
+                    if let Some(hint) = lock_holder_hint(project_root) {
+                        progress.suspend(|| print_warning(&hint));
+                    }
+                }
+                if last_output.len() == LAST_OUTPUT_LINES {
+                    last_output.pop_front();
+                }
+                last_output.push_back(line.to_string());
+            } else {
+                progress.record_message(line);
+                if let Some(event) = events::progress(line, &compilation) {
+                    events::emit(event);
+                }
+            }
+        },
+    );
+    let complete = compilation.success == Some(true);
+    progress.finish(project_root, complete);
+
+    let sccache = SccacheSnapshot::delta_from(sccache_before);
+    let failure = match &result {
+        Err(OptimizerError::TimedOut(_)) => {
+            print_hang(&compilation, &last_output);
+            None
+        }
+        Err(_) if !crate::signal::is_interrupted() => {
+            triage::diagnose(project_root, &compilation, oom_kills_before)
+        }
+        _ => None,
+    };
+    if let Some(kind) = &failure {
+        triage::print(kind);
+    }
+    if let Err(e) = &result {
+        if let Err(e) =
+            debug_bundle::record_failure(project_root, args, e, &compilation, &last_output)
+        {
+            log::debug!("Could not record the failed command: {}", e);
+        }
+    }
+
+    (
+        result,
+        Some(RunStats {
+            compilation: Some(compilation),
+            sccache,
+            failure,
+        }),
+    )
+}
+
+/// Name rust-analyzer when it is what cargo waits for
+fn lock_holder_hint(project_root: &Path) -> Option<String> {
+    if contention::editor_separates(project_root) == Some(true) {
+        return None;
+    }
+    let contention = Contention::detect(&contention::processes(), project_root);
+    contention.is_running().then(|| {
+        format!(
+            "rust-analyzer is probably holding the lock; set {} to true so it builds into its own target directory",
+            RUST_ANALYZER_TARGET_DIR
+        )
+    })
+}
+
+/// Show how far a run got before its timeout killed it
+fn print_hang(compilation: &CompilationStats, last_output: &VecDeque<String>) {
+    print_warning("Build killed by the timeout in atlas.toml [timeouts]");
+    if let Some(target) = &compilation.last_artifact {
+        println!("   • Last finished: {}", target);
+    }
+    if !last_output.is_empty() {
+        println!("   • Last output:");
+        for line in last_output {
+            println!("       {}", line);
+        }
+    }
+    println!("   • A stage that hangs repeatedly often waits on the network or a lock; try it alone with `cargo build -vv`");
+}
+
+/// Run cargo with statistics. If the out-of-memory killer ended the run, retry
+/// once with half the parallel jobs and remember the safer setting.
+fn run_cargo_with_backoff(
+    args: &[&str],
+    project_root: &Path,
+) -> (OptimizerResult<()>, Option<RunStats>) {
+    let (result, run_stats) = run_cargo(args, project_root, true);
+    let out_of_memory = run_stats
+        .as_ref()
+        .and_then(|stats| stats.failure.as_ref())
+        .is_some_and(FailureKind::is_out_of_memory);
+    let explicit_jobs = args
+        .iter()
+        .any(|arg| arg.starts_with("--jobs") || arg.starts_with("-j"));
+    if result.is_ok() || !out_of_memory || explicit_jobs {
+        return (result, run_stats);
+    }
+
+    let configured = std::env::var("CARGO_BUILD_JOBS").ok().or_else(|| {
+        knowledge::lookup("jobs")
+            .and_then(|setting| setting.current_values(project_root).ok())
+            .and_then(|values| values.into_iter().next())
+            .map(|(_, value)| value)
+    });
+    let cores = std::thread::available_parallelism().map_or(1, |cores| cores.get());
+    let jobs = backoff_jobs(configured.as_deref(), cores);
+    print_warning(&format!(
+        "Retrying once with {} parallel jobs to use less memory",
+        jobs
+    ));
+
+    let jobs_arg = format!("--jobs={}", jobs);
+    let mut retry_args = args.to_vec();
+    retry_args.push(&jobs_arg);
+    let (retry_result, retry_stats) = run_cargo(&retry_args, project_root, true);
+    if retry_result.is_ok() {
+        remember_safe_jobs(project_root, jobs);
+    }
+    (retry_result, retry_stats)
+}
+
+/// Lower `build.parallel_jobs` in the Atlas config so generated Cargo config
+/// uses a job count that fits in memory
+fn remember_safe_jobs(project_root: &Path, jobs: usize) {
+    let result = OptimizerConfig::load_or_default().and_then(|config| {
+        if config
+            .build
+            .parallel_jobs
+            .is_some_and(|current| current <= jobs)
+        {
+            return Ok(None);
+        }
+        // The job count that fits depends on this machine, so it stays out
+        // of the repository
+        config_layers::save_local(project_root, "build", "parallel_jobs", &jobs.to_string())
+            .map(Some)
+    });
+    match result {
+        Ok(Some(path)) => print_status(&format!(
+            "Saved parallel_jobs = {} to {}; run `atlas refresh` to use it in generated Cargo config",
+            jobs,
+            path.display()
+        )),
+        Ok(None) => {}
+        Err(e) => log::warn!("Could not save the reduced job count: {}", e),
+    }
+}
+
+/// Build every `[[matrix]]` entry from atlas.toml, `parallel` at a time, and
+/// report on all of them. Entries run one after another share the project's
+/// target directory, where cargo keeps each target and profile apart.
+fn run_matrix(
+    project_root: &Path,
+    scope: &[String],
+    only: Option<Vec<String>>,
+    parallel: usize,
+    fail_fast: bool,
+    json: bool,
+) -> OptimizerResult<()> {
+    let config = ProjectConfig::load(project_root)?;
+    let mut entries = config.matrix.clone();
+    if entries.is_empty() {
+        return Err(OptimizerError::config(
+            "No [[matrix]] entries in atlas.toml; see `atlas init` for an example",
+        ));
+    }
+    if let Some(only) = only {
+        let labels: Vec<String> = entries.iter().map(MatrixEntry::label).collect();
+        if let Some(unknown) = only.iter().find(|label| !labels.contains(label)) {
+            return Err(OptimizerError::invalid_input(format!(
+                "Unknown matrix entry '{}'. Available entries: {}",
+                unknown,
+                labels.join(", ")
+            )));
+        }
+        entries.retain(|entry| only.contains(&entry.label()));
+    }
+    let parallel = parallel.clamp(1, entries.len());
+    // Output of entries built side by side, or of JSON runs, is captured and
+    // printed per entry instead of streamed
+    let captured = json || parallel > 1;
+
+    let target_directory = if parallel > 1 {
+        Some(CargoMetadata::load(project_root)?.target_directory)
+    } else {
+        None
+    };
+    if !json {
+        print_status(&format!(
+            "Building {} matrix entries{}",
+            entries.len(),
+            if parallel > 1 {
+                format!(", {} at a time", parallel)
+            } else {
+                String::new()
+            }
+        ));
+    }
+
+    let results: Mutex<Vec<Option<MatrixResult>>> = Mutex::new(vec![None; entries.len()]);
+    let stop = AtomicBool::new(false);
+    let output_lock = Mutex::new(());
+    let run_slot = |slot: usize| {
+        for (index, entry) in entries.iter().enumerate().skip(slot).step_by(parallel) {
+            if stop.load(Ordering::SeqCst) || signal::is_interrupted() {
+                break;
+            }
+            let target_dir = target_directory.as_deref().and_then(|target_directory| {
+                let profile = entry.profile.as_deref().unwrap_or("dev");
+                let base = target_dirs::for_profile(project_root, profile, target_directory);
+                slot_target_dir(&base, slot)
+            });
+            let cargo_args = entry.cargo_args(scope, target_dir.as_deref());
+            let args: Vec<&str> = cargo_args.iter().map(String::as_str).collect();
+            if !captured {
+                print_status(&format!(
+                    "[{}/{}] {}",
+                    index + 1,
+                    entries.len(),
+                    entry.label()
+                ));
+            }
+
+            let start_time = Instant::now();
+            let (result, compilation) = if captured {
+                let timeout = config.timeout(entry.command());
+                let (result, compilation, output) =
+                    run_cargo_captured(&args, project_root, timeout);
+                if !json {
+                    let _guard = output_lock
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner());
+                    print_matrix_outcome(&entry.label(), &result, start_time.elapsed());
+                    if result.is_err() {
+                        for line in &output {
+                            eprintln!("{}", line);
+                        }
+                    }
+                }
+                (result, Some(compilation))
+            } else {
+                let (result, run_stats) = run_cargo(&args, project_root, true);
+                print_matrix_outcome(&entry.label(), &result, start_time.elapsed());
+                (result, run_stats.and_then(|stats| stats.compilation))
+            };
+
+            if result.is_err() && fail_fast {
+                stop.store(true, Ordering::SeqCst);
+            }
+            let compilation = compilation.unwrap_or_default();
+            results
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())[index] = Some(MatrixResult {
+                label: entry.label(),
+                cargo_args: cargo_args.clone(),
+                status: if result.is_ok() {
+                    MatrixStatus::Passed
+                } else {
+                    MatrixStatus::Failed
+                },
+                duration_secs: start_time.elapsed().as_secs_f64(),
+                warnings: compilation.warnings,
+                errors: compilation.errors,
+                error: result.err().map(|e| e.to_string()),
+            });
+        }
+    };
+    std::thread::scope(|scope| {
+        for slot in 1..parallel {
+            scope.spawn(move || run_slot(slot));
+        }
+        run_slot(0);
+    });
+
+    if signal::is_interrupted() {
+        return Err(OptimizerError::Cancelled);
+    }
+    let results: Vec<MatrixResult> = results
+        .into_inner()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .into_iter()
+        .zip(&entries)
+        .map(|(result, entry)| {
+            result.unwrap_or_else(|| MatrixResult {
+                label: entry.label(),
+                cargo_args: entry.cargo_args(scope, None),
+                status: MatrixStatus::Skipped,
+                duration_secs: 0.0,
+                warnings: 0,
+                errors: 0,
+                error: None,
+            })
+        })
+        .collect();
+    let failed = results
+        .iter()
+        .filter(|result| result.status == MatrixStatus::Failed)
+        .count();
+
+    let report = MatrixReport::new(results);
+    if json {
+        println!("{}", redact::text(&serde_json::to_string_pretty(&report)?));
+    } else {
+        print_matrix_report(&report);
+    }
+
+    if failed > 0 {
+        return Err(OptimizerError::command_failed(format!(
+            "{} of {} matrix entries failed",
+            failed,
+            report.entries.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Run cargo with its output collected instead of shown: rendered
+/// diagnostics plus plain output lines
+fn run_cargo_captured(
+    args: &[&str],
+    project_root: &Path,
+    timeout: Option<Duration>,
+) -> (OptimizerResult<()>, CompilationStats, Vec<String>) {
+    let mut json_args = args.to_vec();
+    json_args.push("--message-format=json-diagnostic-rendered-ansi");
+
+    let mut compilation = CompilationStats::default();
+    let mut output = Vec::new();
+    let result = execute_command_streaming(
+        "cargo",
+        &json_args,
+        Some(project_root),
+        timeout,
+        true,
+        |_, line| {
+            if let Some(rendered) = compilation.record_message(line) {
+                output.push(rendered.trim_end().to_string());
+            } else if !line.starts_with('{') {
+                output.push(line.to_string());
+            }
+        },
+    );
+    (result, compilation, output)
+}
+
+fn print_matrix_outcome(label: &str, result: &OptimizerResult<()>, duration: Duration) {
+    match result {
+        Ok(()) => print_success(&format!("✅ {} in {}", label, format_duration(duration))),
+        Err(e) => print_error(&format!(
+            "❌ {} failed after {}: {}",
+            label,
+            format_duration(duration),
+            e
+        )),
+    }
+}
+
+fn print_matrix_report(report: &MatrixReport) {
+    println!();
+    println!("{}", style::title("📊 Matrix Report"));
+    let mut table = Table::new(&["", "Entry", "Time", "Result"])
+        .indent(3)
+        .right(2);
+    for entry in &report.entries {
+        let (status, result) = match entry.status {
+            MatrixStatus::Passed => (
+                Cell::styled("✅", style::good),
+                format!("{} warnings", entry.warnings),
+            ),
+            MatrixStatus::Failed => (
+                Cell::styled("❌", style::bad),
+                format!("{} errors, {} warnings", entry.errors, entry.warnings),
+            ),
+            MatrixStatus::Skipped => ("⏭".into(), "skipped".to_string()),
+        };
+        let duration = match entry.status {
+            MatrixStatus::Skipped => String::new(),
+            _ => format_duration(Duration::from_secs_f64(entry.duration_secs)),
+        };
+        table.add_row(vec![
+            status,
+            entry.label.as_str().into(),
+            duration.into(),
+            result.into(),
+        ]);
+    }
+    table.print();
+}
+
+/// Run the tests that failed last time across the workspace; an error when
+/// any of them still fails
+fn run_failed_tests(project_root: &Path) -> OptimizerResult<()> {
+    let previous = FailedTests::load(project_root);
+    if previous.is_empty() {
+        return Ok(());
+    }
+    print_status(&format!(
+        "Running {} test{} that failed last time...",
+        previous.tests.len(),
+        if previous.tests.len() == 1 { "" } else { "s" }
+    ));
+    let timeout = ProjectConfig::load(project_root)?.timeout("test");
+    let nextest = is_tool_available("cargo-nextest");
+    let mut args = vec!["--workspace".to_string()];
+    args.extend(previous.filter_args(nextest));
+    let (result, failed) = run_test_command(project_root, &args, nextest, timeout);
+    if let Err(e) = result {
+        if !failed.is_empty() {
+            failed.save(project_root)?;
+        }
+        print_error(&format!("❌ Previously failed tests still fail: {}", e));
+        return Err(e);
+    }
+    print_success("✅ Previously failed tests pass now");
+    Ok(())
+}
+
+/// Run cargo-nextest, or cargo test without it, echoing the output and
+/// noting which tests failed
+fn run_test_command(
+    project_root: &Path,
+    args: &[String],
+    nextest: bool,
+    timeout: Option<Duration>,
+) -> (OptimizerResult<()>, FailedTests) {
+    let mut command = if nextest {
+        vec!["nextest", "run"]
+    } else {
+        vec!["test"]
+    };
+    command.extend(args.iter().map(String::as_str));
+
+    let parser = FailureParser::new();
+    let mut failed = FailedTests::default();
+    let result = execute_command_streaming(
+        "cargo",
+        &command,
+        Some(project_root),
+        timeout,
+        true,
+        |stream, line| {
+            match stream {
+                Stream::Stdout => println!("{}", line),
+                Stream::Stderr => eprintln!("{}", line),
+            }
+            failed.record_line(line, &parser);
+        },
+    );
+    (result, failed)
+}
+
+async fn run_test(project_root: &Path, scope: &[String], show_stats: bool) -> OptimizerResult<()> {
     print_status("Running optimized tests...");
 
     let start_time = Instant::now();
+    let _interrupt_guard = report_partial_on_interrupt("Tests", start_time);
+    let sccache_before = if show_stats {
+        SccacheSnapshot::capture()
+    } else {
+        None
+    };
+
+    let timeout = ProjectConfig::load(project_root)?.timeout("test");
 
     // Try to use cargo-nextest if available, otherwise fall back to cargo test
-    let result = if is_tool_available("cargo-nextest") {
+    let nextest = is_tool_available("cargo-nextest");
+    if nextest {
         print_status("Using cargo-nextest for faster testing...");
-        execute_command_with_output(
-            "cargo",
-            &["nextest", "run", "--workspace"],
-            Some(project_root),
-        )
-    } else {
-        execute_command_with_output("cargo", &["test", "--workspace"], Some(project_root))
-    };
+    }
+    let (result, failed) = run_test_command(project_root, scope, nextest, timeout);
+    // A build failure reports no tests; keep the previous failures for it
+    if result.is_ok() || !failed.is_empty() {
+        if let Err(e) = failed.save(project_root) {
+            log::debug!("Could not record failed tests: {}", e);
+        }
+    }
 
     let duration = start_time.elapsed();
+    record_build_finished(project_root, "test", "test", &result, duration, None);
 
     match result {
         Ok(_) => {
@@ -123,7 +944,12 @@ async fn run_test(project_root: &PathBuf, _changed: bool, show_stats: bool) -> O
             ));
 
             if show_stats {
-                show_build_stats(project_root, "test", duration).await?;
+                let run_stats = RunStats {
+                    compilation: None,
+                    sccache: SccacheSnapshot::delta_from(sccache_before),
+                    failure: None,
+                };
+                show_build_stats(project_root, "test", duration, Some(&run_stats)).await?;
             }
 
             Ok(())
@@ -135,7 +961,67 @@ async fn run_test(project_root: &PathBuf, _changed: bool, show_stats: bool) -> O
     }
 }
 
-async fn run_clean(project_root: &PathBuf, all: bool) -> OptimizerResult<()> {
+/// Report a finished cargo run on the event stream, add it to the stats store
+/// for `atlas cache report`, and keep it for `atlas editor status`
+fn record_build_finished(
+    project_root: &Path,
+    command: &str,
+    profile: &str,
+    result: &OptimizerResult<()>,
+    duration: Duration,
+    run_stats: Option<&RunStats>,
+) {
+    let compilation = run_stats.and_then(|stats| stats.compilation.clone());
+    events::emit(Event::BuildFinished {
+        command: command.to_string(),
+        success: result.is_ok(),
+        duration_secs: duration.as_secs_f64(),
+        compilation: compilation.clone().map(Box::new),
+        sccache: run_stats.and_then(|stats| stats.sccache),
+    });
+
+    if let Some(compilation) = &compilation {
+        let mut record = BuildRecord::new(
+            project_root,
+            command,
+            result.is_ok(),
+            duration.as_secs_f64(),
+            compilation,
+            run_stats.and_then(|stats| stats.sccache),
+            OptimizerConfig::load_or_default()
+                .and_then(|config| Fingerprint::capture(project_root, &config))
+                .ok(),
+        );
+        record.profile = Some(profile.to_string());
+        if let Err(e) = StatsStore::append(&record) {
+            log::debug!("Could not record build statistics: {}", e);
+        }
+    }
+
+    let last_build = LastBuild {
+        finished_at: Utc::now(),
+        command: command.to_string(),
+        success: result.is_ok(),
+        duration_secs: duration.as_secs_f64(),
+        compilation,
+    };
+    if let Err(e) = last_build.save(project_root) {
+        log::debug!("Could not record the last build: {}", e);
+    }
+}
+
+/// Print how far a run got if the user interrupts it with Ctrl-C
+fn report_partial_on_interrupt(label: &'static str, start_time: Instant) -> CleanupGuard {
+    signal::on_interrupt(move || {
+        print_warning(&format!(
+            "{} interrupted after {}",
+            label,
+            format_duration(start_time.elapsed())
+        ));
+    })
+}
+
+async fn run_clean(project_root: &Path, all: bool) -> OptimizerResult<()> {
     print_status("Cleaning build artifacts...");
 
     if all {
@@ -178,30 +1064,73 @@ async fn run_clean(project_root: &PathBuf, all: bool) -> OptimizerResult<()> {
 }
 
 async fn show_build_stats(
-    project_root: &PathBuf,
+    project_root: &Path,
     build_type: &str,
-    duration: std::time::Duration,
+    duration: Duration,
+    run_stats: Option<&RunStats>,
 ) -> OptimizerResult<()> {
     println!();
     print_status("📊 Build Statistics:");
 
     // Show basic timing
-    println!("  ⏱️  Duration: {}", format_duration(duration));
-    println!("  🔧 Build type: {}", build_type);
+    println!(
+        "{}",
+        style::text(&format!("  ⏱️  Duration: {}", format_duration(duration)))
+    );
+    println!(
+        "{}",
+        style::text(&format!("  🔧 Build type: {}", build_type))
+    );
 
     // Show target directory size
     let target_dir = project_root.join("target");
     if target_dir.exists() {
         match get_directory_size(&target_dir) {
-            Ok(size) => println!("  📁 Target directory size: {}", format_bytes(size)),
-            Err(_) => println!("  📁 Target directory size: Unable to calculate"),
+            Ok(size) => println!(
+                "{}",
+                style::text(&format!(
+                    "  📁 Target directory size: {}",
+                    format_bytes(size)
+                ))
+            ),
+            Err(_) => println!(
+                "{}",
+                style::text("  📁 Target directory size: Unable to calculate")
+            ),
         }
     }
 
-    // Show sccache stats if available
-    if is_tool_available("sccache") {
-        print_status("sccache statistics:");
-        let _ = execute_command_with_output("sccache", &["--show-stats"], None);
+    if let Some(compilation) = run_stats.and_then(|stats| stats.compilation.as_ref()) {
+        println!(
+            "{}",
+            style::text(&format!(
+                "  ♻️  Crates reused: {}/{} ({:.0}%), recompiled: {}",
+                compilation.crates_fresh,
+                compilation.total_crates(),
+                compilation.reuse_percentage(),
+                compilation.crates_compiled
+            ))
+        );
+        println!(
+            "{}",
+            style::text(&format!(
+                "  ⚠️  Diagnostics: {} warnings, {} errors",
+                compilation.warnings, compilation.errors
+            ))
+        );
+    }
+
+    // sccache counters are server-lifetime totals, so only the delta
+    // captured around this invocation is meaningful
+    if let Some(sccache) = run_stats.and_then(|stats| stats.sccache.as_ref()) {
+        println!("{}", style::text(&format!(
+            "  🗄️  sccache (this run): {} requests, {} hits, {} misses, {} errors ({:.0}% hit rate)",
+            sccache.requests,
+            sccache.hits,
+            sccache.misses,
+            sccache.errors,
+            sccache.hit_rate()
+        )));
     }
 
     // Show dependency count
@@ -213,7 +1142,10 @@ async fn show_build_stats(
         if output.status.success() {
             if let Ok(stdout) = String::from_utf8(output.stdout) {
                 let dep_count = stdout.lines().count();
-                println!("  📦 Workspace dependencies: {}", dep_count);
+                println!(
+                    "{}",
+                    style::text(&format!("  📦 Workspace dependencies: {}", dep_count))
+                );
             }
         }
     }
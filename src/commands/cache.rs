@@ -0,0 +1,252 @@
+use crate::analysis::metadata::CargoMetadata;
+use crate::cache::{
+    cache_location, is_remote, seed_args, seed_jobs, seed_target_dir, CacheReport, Verdict,
+};
+use crate::commands::build::run_cargo;
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::matrix::MatrixEntry;
+use crate::project_config::ProjectConfig;
+use crate::stats::{RebuildCause, SccacheSnapshot, StatsStore};
+use crate::style;
+use crate::utils::*;
+use crate::CacheCommands;
+use chrono::{Duration, Utc};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration as StdDuration, Instant};
+
+pub async fn run(
+    cache_command: CacheCommands,
+    project_dir: Option<PathBuf>,
+) -> OptimizerResult<()> {
+    match cache_command {
+        CacheCommands::Seed {
+            targets,
+            profiles,
+            matrix,
+            keep,
+        } => {
+            let project_root = if let Some(dir) = project_dir {
+                dir
+            } else {
+                find_rust_project_root(".")?
+            };
+
+            if !is_rust_project(&project_root) {
+                return Err(OptimizerError::project_validation(
+                    "No Cargo.toml found. Please run this command from a Rust project directory.",
+                ));
+            }
+
+            seed(&project_root, &targets, &profiles, matrix, keep)
+        }
+        CacheCommands::Report { days } => report(days),
+    }
+}
+
+/// Clean-build each job through sccache so the shared cache holds every unit
+fn seed(
+    project_root: &Path,
+    targets: &[String],
+    profiles: &[String],
+    matrix: bool,
+    keep: bool,
+) -> OptimizerResult<()> {
+    if !is_tool_available("sccache") {
+        return Err(OptimizerError::tool_not_found(
+            "sccache (install with: atlas install-tools --only sccache)",
+        ));
+    }
+    crate::lockfile::preflight(project_root)?;
+
+    let jobs: Vec<MatrixEntry> = if matrix {
+        let config = ProjectConfig::load(project_root)?;
+        if config.matrix.is_empty() {
+            return Err(OptimizerError::config(
+                "No [[matrix]] entries in atlas.toml to seed",
+            ));
+        }
+        config.matrix
+    } else {
+        seed_jobs(targets, profiles)
+    };
+
+    // Start the server first so it reports where it stores the cache
+    let _ = execute_command("sccache", &["--start-server"], None);
+    let location = execute_command("sccache", &["--show-stats", "--stats-format=json"], None)
+        .ok()
+        .and_then(|output| cache_location(&String::from_utf8_lossy(&output.stdout)));
+    match &location {
+        Some(location) if is_remote(location) => {
+            print_status(&format!("Seeding the shared cache: {}", location))
+        }
+        Some(location) => print_warning(&format!(
+            "sccache stores to {}; seeding only helps others when the cache is shared \
+             (set SCCACHE_BUCKET, SCCACHE_REDIS_ENDPOINT, SCCACHE_GCS_BUCKET or similar)",
+            location
+        )),
+        None => print_warning("Could not read the sccache cache location"),
+    }
+
+    let target_dir = seed_target_dir(&CargoMetadata::load(project_root)?.target_directory);
+    let before = SccacheSnapshot::capture();
+    let start_time = Instant::now();
+    let mut failed = Vec::new();
+    for (index, job) in jobs.iter().enumerate() {
+        // A clean build every time: any unit left over would not be sent
+        if target_dir.exists() {
+            fs::remove_dir_all(&target_dir)?;
+        }
+        print_status(&format!("[{}/{}] {}", index + 1, jobs.len(), job.label()));
+        let args = seed_args(job, &target_dir);
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let job_start = Instant::now();
+        let (result, _) = run_cargo(&args, project_root, true);
+        match result {
+            Ok(()) => print_success(&format!(
+                "✅ {} in {}",
+                job.label(),
+                format_duration(job_start.elapsed())
+            )),
+            Err(e) => {
+                print_error(&format!("❌ {} failed: {}", job.label(), e));
+                failed.push(job.label());
+            }
+        }
+    }
+    if !keep && target_dir.exists() {
+        fs::remove_dir_all(&target_dir)?;
+    }
+
+    println!();
+    println!("{}", style::title("🗄️  Cache Seeding"));
+    println!(
+        "   • {} build{} in {}",
+        jobs.len(),
+        if jobs.len() == 1 { "" } else { "s" },
+        format_duration(start_time.elapsed())
+    );
+    if let Some(delta) = SccacheSnapshot::delta_from(before) {
+        println!(
+            "   • {} compilations: {} already cached, {} written to the cache, {} errors",
+            delta.requests, delta.hits, delta.misses, delta.errors
+        );
+    }
+    if keep {
+        println!("   • Build output kept in {}", target_dir.display());
+    }
+
+    if failed.is_empty() {
+        print_success("✅ Cache seeded");
+        Ok(())
+    } else {
+        Err(OptimizerError::command_failed(format!(
+            "Seeding failed for: {}",
+            failed.join(", ")
+        )))
+    }
+}
+
+/// How much the builds of the last `days` days on this machine gained from caching
+fn report(days: u32) -> OptimizerResult<()> {
+    let records = StatsStore::load_since(Utc::now() - Duration::days(i64::from(days)))?;
+    let report = CacheReport::from_records(&records);
+
+    println!(
+        "{}",
+        style::title(&format!("🗄️  Cache Report (last {} days)", days))
+    );
+    if report.builds == 0 {
+        println!("   • No builds recorded; `atlas build` and `atlas check` record them");
+        return Ok(());
+    }
+
+    let projects: HashSet<_> = records.iter().map(|record| &record.project).collect();
+    println!(
+        "   • {} builds in {} project{}, {} in total ({} failed)",
+        report.builds,
+        projects.len(),
+        if projects.len() == 1 { "" } else { "s" },
+        format_duration(StdDuration::from_secs_f64(report.build_secs)),
+        report.failed
+    );
+
+    if report.cached_builds > 0 {
+        println!(
+            "   • sccache: {:.1}% hit rate over {} compilations in {} builds ({} hits, {} misses, {} errors)",
+            report.sccache.hit_rate(),
+            report.sccache.requests,
+            report.cached_builds,
+            report.sccache.hits,
+            report.sccache.misses,
+            report.sccache.errors
+        );
+        println!(
+            "   • Estimated compile time saved by cache hits: {}",
+            format_duration(StdDuration::from_secs_f64(report.estimated_secs_saved()))
+        );
+    } else {
+        println!("   • sccache: not used by any recorded build");
+    }
+    println!(
+        "   • Incremental reuse: {:.1}% of packages were fresh in the target directory",
+        report.reuse_percentage()
+    );
+    if report.environments > 1 {
+        println!(
+            "   • Builds ran in {} environments (toolchain, CPU or power source), so their times are not directly comparable",
+            report.environments
+        );
+    }
+
+    println!();
+    println!("{}", style::title("🔁 Rebuild Causes"));
+    for (cause, count) in &report.causes {
+        println!(
+            "   • {}: {} build{} ({:.0}%)",
+            cause,
+            count,
+            if *count == 1 { "" } else { "s" },
+            *count as f64 / report.builds as f64 * 100.0
+        );
+    }
+
+    println!();
+    match report.verdict() {
+        Verdict::NoData => {}
+        Verdict::NotInUse => print_warning(
+            "No build used sccache, so a shared cache cannot pay off yet; \
+             install sccache and keep `enable_sccache` on in the Atlas config",
+        ),
+        Verdict::PayingOff => print_success(&format!(
+            "✅ The cache is paying off: {:.0}% of compilations were served from it",
+            report.sccache.hit_rate()
+        )),
+        Verdict::Marginal => print_warning(&format!(
+            "The cache helps somewhat ({:.0}% hits). Seeding it nightly with \
+             `atlas cache seed` for the targets and profiles you build raises that.",
+            report.sccache.hit_rate()
+        )),
+        Verdict::NotPayingOff => print_warning(&format!(
+            "The cache is not paying off ({:.0}% hits). Check that it is seeded \
+             (`atlas cache seed`) and that your toolchain, RUSTFLAGS and project path \
+             match the machine that fills it.",
+            report.sccache.hit_rate()
+        )),
+    }
+    let workspace_builds = report
+        .causes
+        .get(&RebuildCause::WorkspaceChanges)
+        .copied()
+        .unwrap_or(0);
+    if workspace_builds * 2 > report.builds {
+        println!(
+            "   • Most builds only recompiled workspace crates, which sccache does not cache \
+             while incremental compilation is on; the shared cache mainly speeds up \
+             fresh checkouts and dependency updates"
+        );
+    }
+
+    Ok(())
+}
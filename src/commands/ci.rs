@@ -0,0 +1,69 @@
+use crate::analysis::affected::Affected;
+use crate::analysis::metadata::CargoMetadata;
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::members::package_args;
+use crate::schema::{AffectedReport, TestTarget};
+use crate::utils::*;
+use crate::CiCommands;
+use std::path::{Path, PathBuf};
+
+pub async fn run(ci_command: CiCommands, project_dir: Option<PathBuf>) -> OptimizerResult<()> {
+    let project_root = if let Some(dir) = project_dir {
+        dir
+    } else {
+        find_rust_project_root(".")?
+    };
+
+    if !is_rust_project(&project_root) {
+        return Err(OptimizerError::project_validation(
+            "No Cargo.toml found. Please run this command from a Rust project directory.",
+        ));
+    }
+
+    match ci_command {
+        CiCommands::Affected { base } => run_affected(&project_root, &base),
+    }
+}
+
+/// Print the members and test targets affected since `base` as JSON, for CI
+/// pipelines that shard jobs over the impacted crates only
+fn run_affected(project_root: &Path, base: &str) -> OptimizerResult<()> {
+    let metadata = CargoMetadata::load(project_root)?;
+    let affected = Affected::detect(project_root, &metadata, Some(base))?;
+    let members = affected.members_to_check(&metadata);
+
+    let relative = |path: &PathBuf| {
+        path.strip_prefix(&metadata.workspace_root)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    };
+    let test_targets = metadata
+        .members()
+        .filter(|package| members.contains(&package.name.as_str()))
+        .flat_map(|package| {
+            package
+                .targets
+                .iter()
+                .filter(|target| target.is_tested())
+                .map(|target| TestTarget {
+                    package: package.name.clone(),
+                    name: target.name.clone(),
+                    kind: target.kind.clone(),
+                })
+        })
+        .collect();
+    let members: Vec<String> = members.into_iter().map(str::to_string).collect();
+
+    let report = AffectedReport {
+        changed_files: affected.changed_files.iter().map(relative).collect(),
+        changed_packages: affected.changed_members.iter().cloned().collect(),
+        workspace_wide_changes: affected.workspace_wide.iter().map(relative).collect(),
+        test_targets,
+        cargo_args: package_args(&members),
+        packages: members,
+        ..AffectedReport::new(base)
+    };
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
@@ -1,19 +1,49 @@
-use crate::config::OptimizerConfig;
+use crate::analysis::metadata::CargoMetadata;
+use crate::analysis::profiling::BuildProfile;
+use crate::analysis::timings::TimingReport;
+use crate::audit;
+use crate::bundle::ConfigBundle;
+use crate::commands::tools::install_pinned_tools;
+use crate::config::{generate_cargo_config, generate_cargo_profiles, ConfigScope, OptimizerConfig};
+use crate::config_layers::{ConfigLayer, LayeredConfig};
+use crate::contradictions::Sources;
+use crate::credentials::{self, SecretRef};
+use crate::error::OptimizerError;
 use crate::error::OptimizerResult;
+use crate::managed::ManagedRegistry;
+use crate::manifest::Manifest;
+use crate::nix;
+use crate::package_opt;
+use crate::process_env::EnvValue;
+use crate::project_config::{default_project_config, ProjectConfig};
+use crate::style;
+use crate::system::{host_triple, SystemInfo};
+use crate::table::{Cell, Table};
+use crate::templates::{self, render_generated, Generated, GENERATED};
 use crate::utils::*;
-use crate::ConfigCommands;
-use std::path::PathBuf;
+use crate::{BundleCommands, ConfigCommands, SecretCommands};
+use std::path::{Path, PathBuf};
 
 pub async fn run(
     config_command: ConfigCommands,
-    _project_dir: Option<PathBuf>,
+    project_dir: Option<PathBuf>,
 ) -> OptimizerResult<()> {
     match config_command {
-        ConfigCommands::Show => {
+        ConfigCommands::Show {
+            scope: None,
+            effective: false,
+        } => {
             let config = OptimizerConfig::load_or_default()?;
             println!("{}", toml::to_string_pretty(&config)?);
             Ok(())
         }
+        ConfigCommands::Show {
+            scope: None,
+            effective: true,
+        } => show_effective(),
+        ConfigCommands::Show {
+            scope: Some(scope), ..
+        } => show_managed(scope),
         ConfigCommands::Edit => {
             let config_path = OptimizerConfig::get_config_path()?;
             print_status(&format!(
@@ -23,7 +53,7 @@ pub async fn run(
 
             // Try to open with default editor
             if let Ok(editor) = std::env::var("EDITOR") {
-                execute_command_with_output(&editor, &[config_path.to_str().unwrap()], None)?;
+                execute_interactive_command(&editor, &[config_path.to_str().unwrap()], None)?;
             } else {
                 print_warning("No EDITOR environment variable set. Please edit manually:");
                 println!("{}", config_path.display());
@@ -37,9 +67,25 @@ pub async fn run(
             }
             Ok(())
         }
+        ConfigCommands::Templates { preview } => templates(preview.as_deref()),
+        ConfigCommands::Doctor => doctor(project_dir),
+        ConfigCommands::PackageOpt {
+            package,
+            level,
+            remove,
+        } => package_opt(package.as_deref(), level.as_deref(), remove, project_dir),
         ConfigCommands::Validate => {
             let config = OptimizerConfig::load_or_default()?;
             config.validate()?;
+            for name in crate::alias::shadowed(
+                &config.aliases,
+                &<crate::cli::Cli as clap::CommandFactory>::command(),
+            ) {
+                print_warning(&format!(
+                    "Alias '{}' is never used: a built-in command has that name",
+                    name
+                ));
+            }
             print_success("✅ Configuration is valid");
             Ok(())
         }
@@ -48,7 +94,7 @@ pub async fn run(
             let content = toml::to_string_pretty(&config)?;
 
             if let Some(output_path) = output {
-                std::fs::write(&output_path, content)?;
+                audit::write(&output_path, content)?;
                 print_success(&format!(
                     "✅ Configuration exported to {}",
                     output_path.display()
@@ -58,5 +104,464 @@ pub async fn run(
             }
             Ok(())
         }
+        ConfigCommands::Bundle { bundle_command } => match bundle_command {
+            BundleCommands::Export { output } => export_bundle(output),
+            BundleCommands::Import {
+                file,
+                install_tools,
+                force,
+            } => import_bundle(&file, install_tools, force).await,
+        },
+        ConfigCommands::Secret { secret_command } => match secret_command {
+            SecretCommands::Set { name } => {
+                credentials::store(&name)?;
+                print_success(&format!(
+                    "✅ Stored secret '{}'; reference it in [env] as {{ secret = \"{}\" }}",
+                    name,
+                    SecretRef::Keychain(name.clone())
+                ));
+                Ok(())
+            }
+            SecretCommands::Delete { name } => {
+                credentials::delete(&name)?;
+                print_success(&format!("✅ Removed secret '{}'", name));
+                Ok(())
+            }
+            SecretCommands::Check => check_secrets(),
+        },
+    }
+}
+
+/// Read every secret `[env]` references, without showing the values
+fn check_secrets() -> OptimizerResult<()> {
+    let config = OptimizerConfig::load_or_default()?;
+    let secrets: Vec<(&String, &SecretRef)> = config
+        .env
+        .iter()
+        .filter_map(|(name, value)| match value {
+            EnvValue::Secret { secret, .. } => Some((name, secret)),
+            _ => None,
+        })
+        .collect();
+    if secrets.is_empty() {
+        print_status("No secrets referenced in [env]");
+        return Ok(());
+    }
+
+    let mut unreadable = 0;
+    for (name, secret) in secrets {
+        match secret.resolve() {
+            Ok(_) => println!("  {} {} ({})", style::good("✓"), name, secret),
+            Err(e) => {
+                unreadable += 1;
+                println!("  {} {} ({}): {}", style::bad("✗"), name, secret, e);
+            }
+        }
+    }
+    if unreadable > 0 {
+        return Err(OptimizerError::config(format!(
+            "{} secret(s) cannot be read",
+            unreadable
+        )));
+    }
+    print_success("✅ All secrets can be read");
+    Ok(())
+}
+
+fn export_bundle(output: Option<PathBuf>) -> OptimizerResult<()> {
+    let config = OptimizerConfig::load_global()?;
+    let bundle = ConfigBundle::capture(&config, &SystemInfo::detect()?);
+    let content = bundle.to_toml()?;
+
+    if let Some(output_path) = output {
+        audit::write(&output_path, content)?;
+        print_success(&format!(
+            "✅ Config bundle with {} tool versions exported to {}",
+            bundle.tools.len(),
+            output_path.display()
+        ));
+    } else {
+        println!("{}", content);
+    }
+    Ok(())
+}
+
+async fn import_bundle(file: &Path, install_tools: bool, force: bool) -> OptimizerResult<()> {
+    let bundle = ConfigBundle::load(file)?;
+    let config_path = OptimizerConfig::get_config_path()?;
+
+    let replaces_config = config_path.exists()
+        && toml::to_string(&OptimizerConfig::load_global()?)? != toml::to_string(&bundle.config)?;
+    if replaces_config {
+        if !force
+            && !confirm(&format!(
+                "Replace the Atlas configuration at {} with the bundle's?",
+                config_path.display()
+            ))?
+        {
+            print_warning("Keeping the current configuration");
+            return Ok(());
+        }
+        backup_file(&config_path)?;
+    }
+
+    if let Some(parent) = config_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    bundle.config.save_to_file(&config_path)?;
+    print_success(&format!(
+        "✅ Configuration from {} applied to {}",
+        file.display(),
+        config_path.display()
+    ));
+
+    if install_tools && !bundle.tools.is_empty() {
+        install_pinned_tools(&bundle.tools).await?;
+    }
+
+    let drift = bundle.tool_drift(&SystemInfo::detect()?);
+    if !drift.is_empty() {
+        print_warning("Installed tools differ from the bundle:");
+        for (tool, pinned, installed) in &drift {
+            match installed {
+                Some(installed) => println!("   • {} {} (bundle pins {})", tool, installed, pinned),
+                None => println!("   • {} not installed (bundle pins {})", tool, pinned),
+            }
+        }
+        if !install_tools {
+            println!(
+                "   Run {} to install them",
+                style::code(&format!(
+                    "atlas config bundle import {} --install-tools",
+                    file.display()
+                ))
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Every effective setting and the layer that set it
+fn show_effective() -> OptimizerResult<()> {
+    let layered = LayeredConfig::load()?;
+    println!("{}", style::title("⚙️  Effective Configuration"));
+    println!(
+        "{}",
+        style::dim("Layers, lowest precedence first: default, global, atlas.toml, .atlas/config.toml, flags")
+    );
+    println!();
+
+    let mut table = Table::new(&["Setting", "Value", "From"]);
+    for (key, value, layer) in layered.entries() {
+        let from = match layer {
+            ConfigLayer::Default => Cell::styled(layer.to_string(), style::dim),
+            layer => layer.to_string().into(),
+        };
+        table.add_row(vec![key.into(), value.to_string().into(), from]);
+    }
+    table.print();
+    Ok(())
+}
+
+fn show_managed(scope: ConfigScope) -> OptimizerResult<()> {
+    let registry = ManagedRegistry::load()?;
+    let files: Vec<_> = registry.files(scope).collect();
+    if files.is_empty() {
+        print_status(&format!(
+            "Atlas manages no settings at {} scope. Install them with `atlas init --scope {}`",
+            scope, scope
+        ));
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        style::title(&format!("📁 Settings managed at {} scope", scope))
+    );
+    for file in files {
+        let missing = if file.path.exists() {
+            String::new()
+        } else {
+            style::bad(" (missing)").to_string()
+        };
+        println!();
+        println!(
+            "{}{} - {} settings",
+            style::code(&file.path.display().to_string()),
+            missing,
+            file.settings.len()
+        );
+        for setting in &file.settings {
+            println!("  • {}", setting);
+        }
+    }
+    Ok(())
+}
+
+/// What Atlas generates for `kind` without a template
+/// Look at every place a build setting can come from and report the ones
+/// set to different values, with a plan that leaves one
+/// Edit `[profile.dev.package.<crate>]` of the workspace manifest, or list
+/// the overrides and the dependencies worth optimizing
+fn package_opt(
+    package: Option<&str>,
+    level: Option<&str>,
+    remove: bool,
+    project_dir: Option<PathBuf>,
+) -> OptimizerResult<()> {
+    let project_root = find_rust_project_root(project_dir.unwrap_or_else(|| PathBuf::from(".")))?;
+    let metadata = CargoMetadata::load(&project_root)?;
+    // Cargo only reads profiles from the workspace root
+    let manifest_path = metadata.workspace_root.join("Cargo.toml");
+    let mut manifest = Manifest::load(&manifest_path)?;
+
+    let Some(package) = package else {
+        return list_package_opts(&manifest, &metadata);
+    };
+    if !metadata.packages.iter().any(|p| p.name == package) {
+        return Err(OptimizerError::invalid_input(format!(
+            "{} is not in the dependency graph of {}",
+            package,
+            metadata.workspace_root.display()
+        )));
+    }
+    if package_opt::is_managed(&manifest, package) {
+        print_warning(&format!(
+            "Atlas's profiles block sets the opt-level of {}; change hot_dependencies under [optimization] in the Atlas config and run `atlas refresh`",
+            package
+        ));
+        return Ok(());
+    }
+
+    if remove {
+        if package_opt::remove(&mut manifest, package) {
+            manifest.save(&manifest_path)?;
+            print_success(&format!("✅ Removed the opt-level override of {}", package));
+        } else {
+            print_status(&format!("{} has no opt-level override", package));
+        }
+        return Ok(());
+    }
+    let Some(level) = level else {
+        return Err(OptimizerError::invalid_input(format!(
+            "Give an opt-level for {} (0, 1, 2, 3, s or z), or --remove",
+            package
+        )));
+    };
+    package_opt::set(&mut manifest, package, level)?;
+    manifest.save(&manifest_path)?;
+    print_success(&format!(
+        "✅ {} builds with opt-level {} in the dev profile ({})",
+        package,
+        level,
+        manifest_path.display()
+    ));
+    print_status("The next build recompiles it once; later builds reuse it");
+    Ok(())
+}
+
+fn list_package_opts(manifest: &Manifest, metadata: &CargoMetadata) -> OptimizerResult<()> {
+    let overrides = package_opt::overrides(manifest);
+    println!(
+        "{}",
+        style::title("⚙️  Dependency opt-levels (dev profile)")
+    );
+    if overrides.is_empty() {
+        println!("   • {}", style::dim("No overrides"));
+    }
+    for (name, level) in &overrides {
+        println!("   • {} = {}", style::code(name), level);
+    }
+
+    let dependencies: Vec<String> = metadata
+        .packages
+        .iter()
+        .filter(|package| !metadata.is_member(&package.id))
+        .map(|package| package.name.clone())
+        .collect();
+    let configured: Vec<String> = overrides.into_iter().map(|(name, _)| name).collect();
+    let profile = TimingReport::load_latest(&metadata.target_directory)
+        .ok()
+        .map(|report| BuildProfile::from_report(&report));
+    let suggestions = package_opt::suggest(&dependencies, &configured, profile.as_ref());
+    if suggestions.is_empty() {
+        return Ok(());
+    }
+
+    println!();
+    println!(
+        "{}",
+        style::section("💡 Likely faster at runtime with opt-level 3")
+    );
+    let mut table = Table::new(&["Crate", "Work", "Compile time"])
+        .right(2)
+        .indent(3);
+    for suggestion in &suggestions {
+        table.add_row(vec![
+            Cell::styled(&suggestion.name, style::code),
+            suggestion.reason.into(),
+            suggestion
+                .compile_secs
+                .map(|secs| format_duration(std::time::Duration::from_secs_f64(secs)))
+                .unwrap_or_else(|| "-".to_string())
+                .into(),
+        ]);
+    }
+    table.print();
+    println!(
+        "   Apply one with {}",
+        style::code(&format!(
+            "atlas config package-opt {} 3",
+            suggestions[0].name
+        ))
+    );
+    if profile.is_none() {
+        println!(
+            "   Run {} to rank them by compile time",
+            style::code("atlas dev profile")
+        );
+    }
+    Ok(())
+}
+
+fn doctor(project_dir: Option<PathBuf>) -> OptimizerResult<()> {
+    let project_root = find_rust_project_root(project_dir.unwrap_or_else(|| PathBuf::from(".")))?
+        .canonicalize()?;
+    let config = OptimizerConfig::load_or_default()?;
+    // A broken atlas.toml is worth knowing about even where it sets nothing here
+    ProjectConfig::load(&project_root)?;
+    let sources = Sources::detect(&project_root, &config, host_triple())?;
+
+    println!("{}", style::title("🩺 Configuration Doctor"));
+    println!("{}", style::section("Sources (highest precedence first)"));
+    let variables = sources.variables();
+    println!(
+        "   • Environment: {}",
+        if variables.is_empty() {
+            style::dim("no Cargo variables")
+        } else {
+            style::code(&variables.join(", "))
+        }
+    );
+    for file in sources.files() {
+        println!("   • {}", file.display());
+    }
+    for layer in crate::config_layers::project_files(&project_root)
+        .iter()
+        .rev()
+    {
+        if layer.is_file() {
+            println!("   • Atlas project config: {}", layer.display());
+        }
+    }
+    println!(
+        "   • Atlas config: {}",
+        OptimizerConfig::get_config_path()?.display()
+    );
+    println!();
+
+    let contradictions = sources.contradictions();
+    if contradictions.is_empty() {
+        print_success("✅ No setting is made twice with different values");
+        return Ok(());
+    }
+    for (index, contradiction) in contradictions.iter().enumerate() {
+        println!(
+            "{} {}",
+            style::attention(&format!("{}. {}:", index + 1, contradiction.setting)),
+            contradiction.explanation
+        );
+        let mut table = Table::new(&["Set in", "Value"]);
+        for (position, layer) in contradiction.layers.iter().enumerate() {
+            let value = if position == 0 {
+                Cell::styled(&layer.value, style::good)
+            } else {
+                Cell::styled(&layer.value, style::dim)
+            };
+            table.add_row(vec![layer.origin.to_string().into(), value]);
+        }
+        table.print();
+        println!("{}", style::section("   Resolution"));
+        for (step, text) in contradiction.steps.iter().enumerate() {
+            println!("   {}. {}", step + 1, text);
+        }
+        println!();
+    }
+    Err(OptimizerError::config(format!(
+        "{} contradictory setting(s)",
+        contradictions.len()
+    )))
+}
+
+fn builtin(kind: Generated, config: &OptimizerConfig, system_info: &SystemInfo) -> String {
+    match kind {
+        Generated::CargoConfig => generate_cargo_config(config, system_info, ConfigScope::Project),
+        Generated::Profiles => generate_cargo_profiles(config, &system_info.os),
+        Generated::ProjectConfig => default_project_config().to_string(),
+        Generated::NixShell => nix::devshell(config),
+    }
+}
+
+fn templates(preview: Option<&str>) -> OptimizerResult<()> {
+    let config = OptimizerConfig::load_or_default()?;
+    let system_info = SystemInfo::detect()?;
+
+    if let Some(name) = preview {
+        let kind = templates::find(name).ok_or_else(|| {
+            OptimizerError::invalid_input(format!(
+                "Unknown template '{}'; templates are {}",
+                name,
+                GENERATED
+                    .iter()
+                    .filter_map(|kind| kind.file_name().strip_suffix(".hbs"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+        })?;
+        let content = render_generated(
+            kind,
+            &config,
+            Some(&system_info),
+            builtin(kind, &config, &system_info),
+        )?;
+        print!("{}", content);
+        return Ok(());
+    }
+
+    let dir = templates::templates_dir()?;
+    println!("{}", style::title("🧩 Templates"));
+    println!("   • {}", style::code(&dir.display().to_string()));
+    println!();
+    let mut table = Table::new(&["Template", "Replaces", "Status"]);
+    let mut failed = 0;
+    for kind in GENERATED {
+        let status = match templates::template(*kind) {
+            None => Cell::styled("built-in", style::dim),
+            Some(_) => match render_generated(
+                *kind,
+                &config,
+                Some(&system_info),
+                builtin(*kind, &config, &system_info),
+            ) {
+                Ok(_) => Cell::styled("installed", style::good),
+                Err(e) => {
+                    failed += 1;
+                    Cell::styled(e.to_string(), style::bad)
+                }
+            },
+        };
+        table.add_row(vec![
+            kind.file_name().into(),
+            kind.to_string().into(),
+            status,
+        ]);
+    }
+    table.print();
+    if failed > 0 {
+        return Err(OptimizerError::config(format!(
+            "{} template(s) do not render",
+            failed
+        )));
     }
+    Ok(())
 }
@@ -0,0 +1,39 @@
+use crate::debug_bundle::{default_archive_name, DebugBundle};
+use crate::error::OptimizerResult;
+use crate::utils::*;
+use crate::DebugCommands;
+use std::path::PathBuf;
+
+pub async fn run(
+    debug_command: DebugCommands,
+    project_dir: Option<PathBuf>,
+) -> OptimizerResult<()> {
+    match debug_command {
+        DebugCommands::Bundle { output } => bundle(output, project_dir),
+    }
+}
+
+/// Write the debug bundle; outside a Rust project only machine-wide files go in
+fn bundle(output: Option<PathBuf>, project_dir: Option<PathBuf>) -> OptimizerResult<()> {
+    let project_root = match project_dir {
+        Some(dir) => Some(dir),
+        None => find_rust_project_root(".").ok(),
+    }
+    .filter(|root| is_rust_project(root));
+
+    print_status("Collecting diagnostics...");
+    let bundle = DebugBundle::collect(project_root.as_deref())?;
+    let output = output.unwrap_or_else(|| PathBuf::from(default_archive_name()));
+    bundle.write_archive(&output)?;
+
+    println!("   • {} files included", bundle.entries.len());
+    if !bundle.missing.is_empty() {
+        println!("   • Not found: {}", bundle.missing.join(", "));
+    }
+    if project_root.is_none() {
+        println!("   • Not in a Rust project, so no project files were included");
+    }
+    print_success(&format!("✅ Debug bundle written to {}", output.display()));
+    println!("   • Secrets in config files were redacted; look through it before attaching it to an issue");
+    Ok(())
+}
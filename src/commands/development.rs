@@ -1,10 +1,21 @@
+use crate::analysis::metadata::CargoMetadata;
+use crate::analysis::profiling::BuildProfile;
+use crate::analysis::timings::{TimingReport, TimingUnit};
+use crate::background;
+use crate::commands::build::save_power;
+use crate::commands::tools::ensure_tool;
+use crate::config::OptimizerConfig;
 use crate::error::OptimizerResult;
+use crate::style;
+use crate::system::SystemInfo;
+use crate::table::Table;
 use crate::utils::*;
 use crate::DevCommands;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 pub async fn run(dev_command: DevCommands, project_dir: Option<PathBuf>) -> OptimizerResult<()> {
-    let _project_root = if let Some(dir) = project_dir {
+    let project_root = if let Some(dir) = project_dir {
         dir
     } else {
         find_rust_project_root(".")?
@@ -27,23 +38,43 @@ pub async fn run(dev_command: DevCommands, project_dir: Option<PathBuf>) -> Opti
             print_success("✅ Quick check completed");
             Ok(())
         }
-        DevCommands::Watch { paths: _ } => {
+        DevCommands::Watch {
+            paths,
+            changed,
+            background,
+        } => {
             print_status("Starting watch mode...");
-            if is_tool_available("cargo-watch") {
-                execute_command_with_output(
-                    "cargo",
-                    &["watch", "-x", "check --workspace --message-format=short"],
-                    None,
-                )?;
-            } else {
-                print_warning("cargo-watch not installed. Install with: cargo install cargo-watch");
+            ensure_tool("cargo-watch").await?;
+            let config = OptimizerConfig::load_or_default()?;
+            config.validate()?;
+            save_power()?;
+            if background {
+                let limits = background::enter(config.development.background_jobs);
+                print_status(&format!("Watching in the background: {}", limits));
             }
+
+            let watch_args = config.watch_args(&paths.unwrap_or_default(), changed);
+            let args: Vec<&str> = watch_args.iter().map(String::as_str).collect();
+            execute_command_with_output("cargo", &args, Some(&project_root))?;
             Ok(())
         }
-        DevCommands::Profile { detailed: _ } => {
-            print_status("Profiling build performance...");
-            execute_command_with_output("cargo", &["build", "--timings"], None)?;
+        DevCommands::Profile {
+            detailed,
+            top,
+            json,
+        } => {
+            if !json {
+                print_status("Profiling build performance...");
+            }
+            let report = profile_build(&project_root)?;
+            let profile = BuildProfile::from_report(&report);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&profile.top(top))?);
+                return Ok(());
+            }
             print_success("✅ Build profile generated (see cargo-timing.html)");
+            print_profile_summary(&report, detailed)?;
+            print_slowest_crates(&profile.top(top));
             Ok(())
         }
         DevCommands::CleanBuild { release } => {
@@ -59,3 +90,114 @@ pub async fn run(dev_command: DevCommands, project_dir: Option<PathBuf>) -> Opti
         }
     }
 }
+
+/// Build with timings and read them back. Nightly cargo reports them as JSON
+/// messages on stdout; stable cargo only writes the HTML report, which is also
+/// kept on nightly for `atlas analyze`.
+fn profile_build(project_root: &Path) -> OptimizerResult<TimingReport> {
+    let nightly = command_stdout("rustc", &["-V"], project_root)
+        .is_some_and(|version| version.contains("nightly"));
+    if !nightly {
+        execute_command_with_output("cargo", &["build", "--timings"], Some(project_root))?;
+        let metadata = CargoMetadata::load(project_root)?;
+        return TimingReport::load_latest(&metadata.target_directory);
+    }
+
+    let mut messages = String::new();
+    execute_command_streaming(
+        "cargo",
+        &[
+            "build",
+            "-Zunstable-options",
+            "--timings=html,json",
+            "--message-format=json-render-diagnostics",
+        ],
+        Some(project_root),
+        None,
+        false,
+        |_, line| {
+            messages.push_str(line);
+            messages.push('\n');
+        },
+    )?;
+    Ok(TimingReport::parse_json_lines(&messages))
+}
+
+/// Split the profiled build into Rust compilation and native build script time
+fn print_profile_summary(report: &TimingReport, detailed: bool) -> OptimizerResult<()> {
+    let (mut build_scripts, rust): (Vec<&TimingUnit>, Vec<&TimingUnit>) = report
+        .units
+        .iter()
+        .partition(|unit| unit.is_build_script_run());
+    let rust_time: f64 = rust.iter().map(|unit| unit.duration).sum();
+    let native_time: f64 = build_scripts.iter().map(|unit| unit.duration).sum();
+    let secs = |secs: f64| format_duration(Duration::from_secs_f64(secs));
+
+    println!();
+    println!("{}", style::title("📊 Build Profile"));
+    if report.has_schedule() {
+        println!("  Wall time:         {}", secs(report.wall_time()));
+    }
+    println!("  Rust compilation:  {}", secs(rust_time));
+    println!(
+        "  Build scripts:     {} across {} crates (includes C/C++ compilation)",
+        secs(native_time),
+        build_scripts.len()
+    );
+
+    build_scripts.sort_by(|a, b| b.duration.total_cmp(&a.duration));
+    let shown = if detailed { build_scripts.len() } else { 5 };
+    for unit in build_scripts.iter().take(shown) {
+        println!("    {:<32} {}", unit.name, secs(unit.duration));
+    }
+
+    // Flag native builds that dominate and are not cached
+    let total = rust_time + native_time;
+    if total > 0.0 && native_time / total >= 0.1 {
+        let config = OptimizerConfig::load_or_default()?;
+        if config.c_compiler_wrapper(&SystemInfo::detect()?).is_none() {
+            println!();
+            print_warning(
+                "Build scripts take a large share of CPU time. Install ccache and rerun `atlas init` to cache C/C++ compilation",
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// The crates that took longest, with the frontend/codegen split where known
+fn print_slowest_crates(profile: &BuildProfile) {
+    if profile.crates.is_empty() {
+        return;
+    }
+    let secs = |secs: f64| format_duration(Duration::from_secs_f64(secs));
+    println!();
+    println!("{}", style::section("🐢 Slowest crates"));
+    let mut table = Table::new(&[
+        "Crate",
+        "Time",
+        "Share",
+        "Frontend",
+        "Codegen",
+        "Build script",
+    ])
+    .right(1)
+    .right(2)
+    .right(3)
+    .right(4)
+    .right(5)
+    .indent(2);
+    for time in &profile.crates {
+        let optional = |value: Option<f64>| value.map(secs).unwrap_or_else(|| "-".to_string());
+        table.add_row(vec![
+            format!("{} {}", time.name, time.version).into(),
+            secs(time.total_secs).into(),
+            format!("{:.1}%", profile.share(time)).into(),
+            optional(time.frontend_secs).into(),
+            optional(time.codegen_secs).into(),
+            optional((time.build_script_secs > 0.0).then_some(time.build_script_secs)).into(),
+        ]);
+    }
+    table.print();
+}
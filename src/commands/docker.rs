@@ -0,0 +1,111 @@
+use crate::audit;
+use crate::config::OptimizerConfig;
+use crate::docker::{
+    analyze_dockerfile, chef_stages, upsert_chef_stages, CHEF_BLOCK, DEFAULT_RUST_IMAGE,
+};
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::managed::{BlockUpdate, Stamp};
+use crate::style;
+use crate::utils::*;
+use crate::DockerCommands;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub async fn run(
+    docker_command: DockerCommands,
+    project_dir: Option<PathBuf>,
+) -> OptimizerResult<()> {
+    let project_root = if let Some(dir) = project_dir {
+        dir
+    } else {
+        find_rust_project_root(".")?
+    };
+
+    if !is_rust_project(&project_root) {
+        return Err(OptimizerError::project_validation(
+            "No Cargo.toml found. Please run this command from a Rust project directory.",
+        ));
+    }
+
+    match docker_command {
+        DockerCommands::Chef {
+            dockerfile,
+            check,
+            force,
+        } => run_chef(&project_root, dockerfile, check, force),
+    }
+}
+
+/// Write the cargo-chef stages into the Dockerfile and report layer-ordering fixes
+fn run_chef(
+    project_root: &Path,
+    dockerfile: Option<PathBuf>,
+    check: bool,
+    force: bool,
+) -> OptimizerResult<()> {
+    let path = dockerfile.unwrap_or_else(|| project_root.join("Dockerfile"));
+    if !path.exists() {
+        print_warning(&format!("{} not found", path.display()));
+        println!();
+        println!("{}", style::topic("🐳 cargo-chef stages"));
+        println!("{}", chef_stages(DEFAULT_RUST_IMAGE));
+        println!("Build your crate in a stage starting with:");
+        println!("   FROM chef AS builder");
+        println!("   COPY --from=planner /app/recipe.json recipe.json");
+        println!("   RUN cargo chef cook --release --recipe-path recipe.json");
+        println!("   COPY . .");
+        println!("   RUN cargo build --release");
+        return Ok(());
+    }
+
+    let mut content = fs::read_to_string(&path)?;
+    if !check {
+        let stamp = Stamp::current(&OptimizerConfig::load_or_default()?)?;
+        match upsert_chef_stages(&content, &stamp, force)? {
+            BlockUpdate::Unchanged => print_success(&format!(
+                "cargo-chef stages in {} are current",
+                path.display()
+            )),
+            BlockUpdate::Modified => print_warning(&format!(
+                "The {} block in {} was edited by hand. Keeping it; use --force to regenerate it",
+                CHEF_BLOCK,
+                path.display()
+            )),
+            BlockUpdate::Updated(updated) => {
+                backup_file(&path)?;
+                audit::write(&path, &updated)?;
+                print_success(&format!("Wrote cargo-chef stages to {}", path.display()));
+                content = updated;
+            }
+        }
+    }
+
+    let dockerignore =
+        fs::read_to_string(path.parent().unwrap_or(project_root).join(".dockerignore")).ok();
+    let issues = analyze_dockerfile(&content, dockerignore.as_deref());
+
+    println!();
+    println!("{}", style::topic("🐳 Docker Layer Caching"));
+    if issues.is_empty() {
+        print_success("Dependencies are built in their own layer");
+        return Ok(());
+    }
+
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string());
+    for issue in &issues {
+        print_warning(&format!("{}:{}: {}", name, issue.line, issue.problem));
+        println!("   • {}", issue.fix);
+    }
+
+    if check {
+        return Err(OptimizerError::project_validation(format!(
+            "{} has {} layer caching issue(s)",
+            path.display(),
+            issues.len()
+        )));
+    }
+    Ok(())
+}
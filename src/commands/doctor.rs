@@ -0,0 +1,299 @@
+use crate::analysis::metadata::CargoMetadata;
+use crate::commands::tools;
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::fixes::{Fix, Transaction};
+use crate::i18n::{t, t_with};
+use crate::native_deps::{self, NativeCheck};
+use crate::rules::{self, Context, Finding, HINT_RULES, RULES};
+use crate::style;
+use crate::system::{rustup_component, SystemInfo};
+use crate::utils::*;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
+pub async fn run(
+    native_deps: bool,
+    fix: bool,
+    list_rules: bool,
+    project_dir: Option<PathBuf>,
+) -> OptimizerResult<()> {
+    if list_rules {
+        print_rules();
+        return Ok(());
+    }
+
+    let project_root = if let Some(dir) = project_dir {
+        dir
+    } else {
+        find_rust_project_root(".")?
+    };
+
+    if !is_rust_project(&project_root) {
+        return Err(OptimizerError::project_validation(
+            "No Cargo.toml found. Please run this command from a Rust project directory.",
+        ));
+    }
+
+    println!("{}", style::title(t("doctor.title")));
+    println!();
+
+    let system_info = SystemInfo::refresh()?;
+    if !native_deps {
+        check_toolchain(&system_info);
+        println!();
+    }
+    check_native_deps(&project_root, &system_info)?;
+
+    if native_deps && !fix {
+        return Ok(());
+    }
+    let context = Context {
+        project_root: &project_root,
+        system_info: &system_info,
+    };
+    let (findings, suppressed) = rules::evaluate(&context)?;
+    println!();
+    print_findings(&findings, suppressed);
+
+    if fix {
+        println!();
+        apply_fixes(findings).await?;
+    }
+    Ok(())
+}
+
+fn print_rules() {
+    println!("{}", style::title(t("doctor.rules")));
+    for rule in RULES {
+        println!(
+            "  {} {:<8} {}",
+            style::code(rule.id),
+            rule.severity.colored(),
+            rule.title
+        );
+    }
+    println!();
+    println!("{}", style::title(t("doctor.hint_rules")));
+    for rule in HINT_RULES {
+        println!("  {} {}", style::code(rule.id), rule.title);
+    }
+    println!();
+    println!(
+        "{}",
+        t_with(
+            "doctor.silence_rule",
+            &[("setting", &style::code("ignore_rules = [\"ATL003\"]"))]
+        )
+    );
+}
+
+/// Print every finding with its rule ID, and how to act on it
+pub(crate) fn print_findings(findings: &[Finding], suppressed: usize) {
+    println!("{}", style::attention(t("doctor.recommendations")));
+    if findings.is_empty() {
+        print_success(t("doctor.no_recommendations"));
+    }
+    for finding in findings {
+        println!(
+            "  {} {:<8} {}",
+            style::code(finding.rule),
+            finding.severity.colored(),
+            finding.message
+        );
+        if let Some(fix) = &finding.fix {
+            println!(
+                "    {}",
+                t_with("doctor.fix", &[("fix", &fix.description())])
+            );
+        } else if let Some(hint) = &finding.hint {
+            println!("    {}", hint);
+        }
+    }
+    if suppressed > 0 {
+        println!(
+            "  {}",
+            t_with("doctor.suppressed", &[("count", &suppressed)])
+        );
+    }
+}
+
+/// Apply the fixes of `findings`: file edits as one transaction, then tool installs
+async fn apply_fixes(findings: Vec<Finding>) -> OptimizerResult<()> {
+    println!("{}", style::section(t("doctor.fixes")));
+    let planned: Vec<Fix> = findings
+        .into_iter()
+        .filter_map(|finding| finding.fix)
+        .collect();
+    if planned.is_empty() {
+        print_success(t("doctor.nothing_to_fix"));
+        return Ok(());
+    }
+
+    let (edits, installs): (Vec<Fix>, Vec<Fix>) =
+        planned.into_iter().partition(|fix| fix.path().is_some());
+
+    let mut transaction = Transaction::default();
+    for fix in &edits {
+        if let Err(e) = transaction.apply(fix) {
+            print_error(&t_with("doctor.fix_failed", &[("error", &e)]));
+            transaction.rollback()?;
+            print_warning(t("doctor.rolled_back"));
+            return Err(e);
+        }
+    }
+
+    // install-tools reports its own failures; check what actually arrived
+    let mut installed = Vec::new();
+    for fix in &installs {
+        let Fix::InstallTool(tool) = fix else {
+            continue;
+        };
+        tools::install_tools(std::slice::from_ref(tool)).await?;
+        if is_tool_available(tool) {
+            installed.push(fix.description());
+        } else {
+            print_warning(&t_with(
+                "doctor.not_applied",
+                &[("fix", &fix.description())],
+            ));
+        }
+    }
+
+    println!();
+    println!("{}", style::section(t("doctor.applied")));
+    for description in transaction.applied().iter().chain(&installed) {
+        println!("   • {}", description);
+    }
+    Ok(())
+}
+
+fn check_toolchain(system_info: &SystemInfo) {
+    println!("{}", style::section(t("doctor.toolchain")));
+    print_check(
+        "rustc",
+        system_info.rust_version.as_deref(),
+        t("doctor.install_rust"),
+    );
+    print_check(
+        "cargo",
+        system_info.cargo_version.as_deref(),
+        t("doctor.install_rust"),
+    );
+    for tool in ["rustfmt", "cargo-clippy"] {
+        let version = system_info
+            .get_tool(tool)
+            .filter(|tool| tool.is_installed)
+            .and_then(|tool| tool.version);
+        print_check(
+            tool,
+            version.as_deref(),
+            &format!(
+                "rustup component add {}",
+                rustup_component(tool).unwrap_or(tool)
+            ),
+        );
+    }
+
+    if let Some(linker) = system_info
+        .get_recommended_linker()
+        .filter(|linker| *linker != "system")
+    {
+        let version = system_info
+            .get_tool(linker)
+            .filter(|tool| tool.is_installed)
+            .map(|tool| {
+                tool.version
+                    .unwrap_or_else(|| t("doctor.installed").to_string())
+            });
+        print_check(
+            linker,
+            version.as_deref(),
+            &format!("atlas install-tools --only {}", linker),
+        );
+    }
+}
+
+fn print_check(name: &str, version: Option<&str>, fix: &str) {
+    match version {
+        Some(version) => println!("  {:<24} {}", style::code(name), version),
+        None => println!(
+            "  {:<24} {} ({})",
+            style::code(name),
+            style::bad(t("doctor.missing")),
+            fix
+        ),
+    }
+}
+
+fn check_native_deps(project_root: &Path, system_info: &SystemInfo) -> OptimizerResult<()> {
+    let metadata = CargoMetadata::load(project_root)?;
+    let checks = native_deps::check(&metadata);
+
+    println!("{}", style::section(t("doctor.native_deps")));
+    if checks.is_empty() {
+        print_success(t("doctor.no_native_deps"));
+        return Ok(());
+    }
+
+    for check in &checks {
+        let dependency = check.dependency;
+        let status = if check.found {
+            style::good(t("doctor.found"))
+        } else if dependency.vendored_fallback {
+            style::caution(t("doctor.missing_bundled"))
+        } else {
+            style::bad(t("doctor.missing"))
+        };
+        println!(
+            "  {:<24} {} {:<14} {}",
+            style::code(dependency.crate_name),
+            t("doctor.needs"),
+            dependency.requirement_name(),
+            status
+        );
+    }
+    println!();
+
+    let missing: Vec<&NativeCheck> = checks.iter().filter(|check| !check.found).collect();
+    if missing.is_empty() {
+        print_success(t("doctor.native_complete"));
+        return Ok(());
+    }
+
+    offer_install(&missing, system_info)
+}
+
+fn offer_install(missing: &[&NativeCheck], system_info: &SystemInfo) -> OptimizerResult<()> {
+    let Some(package_manager) = system_info.get_package_manager() else {
+        print_warning(t("doctor.no_package_manager"));
+        return Ok(());
+    };
+
+    let mut packages: Vec<&str> = missing
+        .iter()
+        .filter_map(|check| check.dependency.package_for(package_manager))
+        .collect();
+    packages.sort_unstable();
+    packages.dedup();
+
+    let Some(command) = native_deps::install_command(system_info, &packages) else {
+        print_warning(&t_with(
+            "doctor.no_packages",
+            &[("manager", &package_manager)],
+        ));
+        return Ok(());
+    };
+
+    print_status(&t_with(
+        "doctor.install_with",
+        &[("command", &command.join(" "))],
+    ));
+    if !std::io::stdin().is_terminal() || !confirm(t("doctor.run_now"))? {
+        return Ok(());
+    }
+
+    let args: Vec<&str> = command[1..].iter().map(String::as_str).collect();
+    execute_interactive_command(&command[0], &args, None)?;
+    print_success(t("doctor.native_installed"));
+    Ok(())
+}
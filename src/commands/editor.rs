@@ -0,0 +1,143 @@
+use crate::analysis::metadata::CargoMetadata;
+use crate::audit;
+use crate::commands::status::stale_generated_files;
+use crate::config::OptimizerConfig;
+use crate::editor::{
+    launch_configurations, merge, tasks, with_setting, LastBuild, RUST_ANALYZER_TARGET_DIR,
+};
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::managed::generated_blocks;
+use crate::onboarding::suggest_next_command;
+use crate::schema::{EditorStatus, Suggestion};
+use crate::utils::*;
+use crate::EditorCommands;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub async fn run(
+    editor_command: EditorCommands,
+    project_dir: Option<PathBuf>,
+) -> OptimizerResult<()> {
+    let project_root = if let Some(dir) = project_dir {
+        dir
+    } else {
+        find_rust_project_root(".")?
+    };
+
+    if !is_rust_project(&project_root) {
+        return Err(OptimizerError::project_validation(
+            "No Cargo.toml found. Please run this command from a Rust project directory.",
+        ));
+    }
+
+    match editor_command {
+        EditorCommands::Vscode { force } => write_vscode(&project_root, force),
+        EditorCommands::Status => print_editor_status(&project_root),
+    }
+}
+
+/// Give rust-analyzer its own target directory so it does not wait for, or
+/// invalidate, command-line builds
+fn write_rust_analyzer_setting(path: &Path) -> OptimizerResult<()> {
+    let existing = fs::read_to_string(path).ok();
+    match with_setting(
+        existing.as_deref(),
+        RUST_ANALYZER_TARGET_DIR,
+        serde_json::Value::Bool(true),
+    ) {
+        Ok(Some(settings)) => {
+            audit::write(path, serde_json::to_string_pretty(&settings)? + "\n")?;
+            print_success(&format!("Wrote {}", path.display()));
+        }
+        Ok(None) => {}
+        Err(e) => print_warning(&format!(
+            "Skipping {}: {}; set \"{}\": true yourself",
+            path.display(),
+            e,
+            RUST_ANALYZER_TARGET_DIR
+        )),
+    }
+    Ok(())
+}
+
+/// Write `.vscode/tasks.json` and `.vscode/launch.json`, replacing only the
+/// entries Atlas generated before
+fn write_vscode(project_root: &Path, force: bool) -> OptimizerResult<()> {
+    let binaries: Vec<(String, String)> = CargoMetadata::load(project_root)?
+        .members()
+        .flat_map(|package| {
+            package
+                .targets
+                .iter()
+                .filter(|target| target.is_bin())
+                .map(|target| (package.name.clone(), target.name.clone()))
+        })
+        .collect();
+
+    let vscode_dir = project_root.join(".vscode");
+    fs::create_dir_all(&vscode_dir)?;
+    for (file, key, generated) in [
+        ("tasks.json", "tasks", tasks()),
+        (
+            "launch.json",
+            "configurations",
+            launch_configurations(&binaries),
+        ),
+    ] {
+        let path = vscode_dir.join(file);
+        let existing = fs::read_to_string(&path).ok();
+        let document = match merge(existing.as_deref(), key, generated.clone()) {
+            Ok(document) => document,
+            Err(e) if !force => {
+                print_warning(&format!("Skipping {}: {}", path.display(), e));
+                continue;
+            }
+            Err(_) => {
+                backup_file(&path)?;
+                merge(None, key, generated)?
+            }
+        };
+        audit::write(&path, serde_json::to_string_pretty(&document)? + "\n")?;
+        print_success(&format!("Wrote {}", path.display()));
+    }
+
+    if OptimizerConfig::load_or_default()?
+        .build
+        .separate_rust_analyzer_target
+    {
+        write_rust_analyzer_setting(&vscode_dir.join("settings.json"))?;
+    }
+
+    println!(
+        "   • Run the tasks with Terminal → Run Task; launch configurations need the CodeLLDB extension"
+    );
+    println!("   • Extensions can poll `atlas editor status` for build stats and setup status");
+    Ok(())
+}
+
+/// Print the stable JSON document editor extensions read
+fn print_editor_status(project_root: &Path) -> OptimizerResult<()> {
+    let (command, reason) = suggest_next_command(project_root);
+    let initialized = generated_blocks(project_root)
+        .map(|blocks| {
+            blocks
+                .iter()
+                .any(|(path, _)| path.starts_with(project_root))
+        })
+        .unwrap_or(false);
+
+    let status = EditorStatus::new(
+        project_root
+            .canonicalize()
+            .unwrap_or_else(|_| project_root.to_path_buf()),
+        initialized,
+        &stale_generated_files(project_root),
+        LastBuild::load(project_root),
+        Suggestion {
+            command: command.to_string(),
+            reason: reason.to_string(),
+        },
+    );
+    println!("{}", serde_json::to_string(&status)?);
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use crate::baseline::Baseline;
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::knowledge::{lookup, measured_impact, similar, Setting, SETTINGS};
+use crate::style;
+use crate::utils::*;
+use std::path::PathBuf;
+
+pub async fn run(setting: Option<String>, project_dir: Option<PathBuf>) -> OptimizerResult<()> {
+    let Some(name) = setting else {
+        list_settings();
+        return Ok(());
+    };
+
+    let setting = lookup(&name).ok_or_else(|| {
+        let suggestions = similar(&name);
+        OptimizerError::invalid_input(if suggestions.is_empty() {
+            format!(
+                "Unknown setting '{}'. Run `atlas explain` to list them",
+                name
+            )
+        } else {
+            format!(
+                "Unknown setting '{}'. Did you mean: {}?",
+                name,
+                suggestions.join(", ")
+            )
+        })
+    })?;
+
+    // Explanations work anywhere; project values only inside a Rust project
+    let project_root = match project_dir {
+        Some(dir) => Some(dir),
+        None => find_rust_project_root(".").ok(),
+    }
+    .filter(|root| is_rust_project(root));
+
+    explain(setting, project_root)
+}
+
+fn list_settings() {
+    println!("{}", style::topic("📚 Settings Atlas can explain"));
+    for setting in SETTINGS {
+        println!("   • {} - {}", style::good(setting.name), setting.summary);
+    }
+    println!();
+    println!("Run {} for details", style::code("atlas explain <setting>"));
+}
+
+fn explain(setting: &Setting, project_root: Option<PathBuf>) -> OptimizerResult<()> {
+    println!("{}", style::topic(&format!("📚 {}", setting.name)));
+    println!("{}", setting.summary);
+    println!();
+
+    println!("{}", style::section("⚖️  Tradeoffs"));
+    for tradeoff in setting.tradeoffs {
+        println!("   • {}", tradeoff);
+    }
+    println!();
+
+    println!("{}", style::section("🛠️  Atlas default"));
+    println!("   {}", setting.atlas_value);
+    println!();
+
+    let Some(project_root) = project_root else {
+        return Ok(());
+    };
+
+    println!("{}", style::section("📍 In this project"));
+    let values = setting.current_values(&project_root)?;
+    if values.is_empty() {
+        println!("   Not set; Cargo's default applies");
+    }
+    for (origin, value) in &values {
+        println!(
+            "   • {} = {} ({})",
+            setting.name,
+            style::caution(value),
+            origin
+        );
+    }
+    println!();
+
+    let baselines = Baseline::list(&project_root)?;
+    let impacts = measured_impact(setting, &baselines);
+    println!("{}", style::section("📊 Measured impact"));
+    if impacts.is_empty() {
+        println!(
+            "   No baselines differ in this setting. Record one with {} before and after changing it",
+            style::code("atlas baseline record <name>")
+        );
+        return Ok(());
+    }
+    for impact in impacts {
+        println!(
+            "   {} → {}",
+            style::code(&impact.before.name),
+            style::code(&impact.after.name)
+        );
+        for (label, change) in &impact.changes {
+            let text = format!("{:+.1}%", change);
+            let text = if *change <= 0.0 {
+                style::good(&text)
+            } else {
+                style::bad(&text)
+            };
+            println!("     {}: {}", label, text);
+        }
+        if !impact.also_changed.is_empty() {
+            print_warning(&format!(
+                "Also changed between these baselines: {}",
+                impact.also_changed.join(", ")
+            ));
+        }
+        if !impact.environment_changed.is_empty() {
+            print_warning(&format!(
+                "Measured in different environments, so not comparable: {}",
+                impact.environment_changed.join(", ")
+            ));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,181 @@
+use crate::commands::status::{rustflags_conflicts, stale_generated_files};
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::fleet::{FleetManifest, FleetSummary, RepoReport};
+use crate::managed::generated_blocks;
+use crate::rules::{self, Context};
+use crate::style;
+use crate::system::SystemInfo;
+use crate::table::{Cell, Table};
+use crate::utils::*;
+use crate::FleetCommands;
+use std::path::{Path, PathBuf};
+
+pub async fn run(fleet_command: FleetCommands, manifest: Option<PathBuf>) -> OptimizerResult<()> {
+    let manifest_path = match manifest {
+        Some(path) if path.is_relative() => std::env::current_dir()?.join(path),
+        Some(path) => path,
+        None => FleetManifest::default_path()?,
+    };
+    let fleet = FleetManifest::load(&manifest_path)?;
+
+    let (title, args) = match fleet_command {
+        FleetCommands::Init { force, no_tools } => {
+            let mut args = vec!["init"];
+            if force {
+                args.push("--force");
+            }
+            if no_tools {
+                args.push("--no-tools");
+            }
+            ("🚢 Fleet Init", Some(args))
+        }
+        FleetCommands::Refresh { force } => {
+            let mut args = vec!["refresh"];
+            if force {
+                args.push("--force");
+            }
+            ("🚢 Fleet Refresh", Some(args))
+        }
+        FleetCommands::Status => ("🚢 Fleet Status", None),
+    };
+    println!("{}", style::title(title));
+    println!(
+        "   • {} repositories from {}",
+        fleet.repos.len(),
+        style::code(&manifest_path.display().to_string())
+    );
+    println!();
+
+    let system_info = SystemInfo::detect()?;
+    let mut reports = Vec::new();
+    for repo in &fleet.repos {
+        let mut report = RepoReport::new(repo.name(), FleetManifest::root(repo, &manifest_path));
+        if !is_rust_project(&report.root) {
+            report.failure = Some("no Cargo.toml".to_string());
+        } else {
+            if let Some(args) = &args {
+                print_status(&format!("{}: atlas {}", report.name, args.join(" ")));
+                report.failure = run_atlas(&report.root, args).err();
+            }
+            check_drift(&mut report, &system_info);
+        }
+        reports.push(report);
+    }
+
+    print_report(&reports);
+    let summary = FleetSummary::new(&reports);
+    if summary.failed > 0 {
+        return Err(OptimizerError::command_failed(format!(
+            "{} of {} repositories failed",
+            summary.failed, summary.repos
+        )));
+    }
+    Ok(())
+}
+
+/// Run an Atlas command in `root` in its own process, so one repository's
+/// failure does not stop the rest; the error is the last line it printed to stderr
+fn run_atlas(root: &Path, args: &[&str]) -> Result<(), String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let root_arg = root.to_string_lossy();
+    let mut full_args = vec!["--quiet", "--project-dir", &root_arg];
+    full_args.extend_from_slice(args);
+    let output = execute_command(&exe.to_string_lossy(), &full_args, Some(root))
+        .map_err(|e| e.to_string())?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let last_line = |bytes: &[u8]| {
+        String::from_utf8_lossy(bytes)
+            .lines()
+            .map(str::trim)
+            .rev()
+            .find(|line| !line.is_empty())
+            .map(str::to_string)
+    };
+    Err(last_line(&output.stderr)
+        .or_else(|| last_line(&output.stdout))
+        .unwrap_or_else(|| format!("exited with {}", output.status)))
+}
+
+/// Record how the repository differs from what Atlas would set up now
+fn check_drift(report: &mut RepoReport, system_info: &SystemInfo) {
+    let root = report.root.as_path();
+    match generated_blocks(root) {
+        Ok(blocks) if blocks.is_empty() => report.drift.push("not initialized".to_string()),
+        Ok(_) => {}
+        Err(e) => report.drift.push(format!("unreadable Atlas blocks: {}", e)),
+    }
+    for (path, staleness) in stale_generated_files(root) {
+        let path = path
+            .strip_prefix(root)
+            .unwrap_or(&path)
+            .display()
+            .to_string();
+        report.drift.push(format!("{} {}", path, staleness));
+    }
+    for conflict in rustflags_conflicts(root) {
+        report.drift.push(conflict.explanation());
+    }
+    let context = Context {
+        project_root: root,
+        system_info,
+    };
+    report.findings = rules::evaluate(&context)
+        .map(|(findings, _)| findings.len())
+        .unwrap_or_else(|e| {
+            log::warn!(
+                "Could not evaluate recommendations for {}: {}",
+                report.name,
+                e
+            );
+            0
+        });
+}
+
+fn print_report(reports: &[RepoReport]) {
+    println!();
+    println!("{}", style::section("📋 Fleet Report"));
+    let mut table = Table::new(&["Repository", "Result", "Drift", "Findings"]).right(3);
+    for report in reports {
+        let result = match &report.failure {
+            Some(failure) => Cell::styled(format!("failed: {}", failure), style::bad),
+            None if report.drift.is_empty() => Cell::styled("ok", style::good),
+            None => Cell::styled("drifted", style::caution),
+        };
+        let drift = if report.drift.is_empty() {
+            "-".to_string()
+        } else {
+            report.drift.join("; ")
+        };
+        table.add_row(vec![
+            report.name.as_str().into(),
+            result,
+            drift.into(),
+            report.findings.to_string().into(),
+        ]);
+    }
+    table.print();
+
+    let summary = FleetSummary::new(reports);
+    println!();
+    let line = format!(
+        "{} repositories: {} ok, {} drifted, {} failed",
+        summary.repos,
+        summary.repos - summary.failed - summary.drifted,
+        summary.drifted,
+        summary.failed
+    );
+    if summary.failed > 0 {
+        print_error(&line);
+    } else if summary.drifted > 0 {
+        print_warning(&line);
+        println!(
+            "   Run {} to bring them in line, or {} for those not initialized",
+            style::code("atlas fleet refresh"),
+            style::code("atlas fleet init")
+        );
+    } else {
+        print_success(&line);
+    }
+}
@@ -0,0 +1,104 @@
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::history::{History, Invocation};
+use crate::style;
+use crate::table::{Cell, Table};
+use crate::utils::*;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+fn project_root(project_dir: Option<PathBuf>) -> OptimizerResult<PathBuf> {
+    let project_root = find_rust_project_root(project_dir.unwrap_or_else(|| PathBuf::from(".")))?;
+    if !is_rust_project(&project_root) {
+        return Err(OptimizerError::project_validation(
+            "No Cargo.toml found. Please run this command from a Rust project directory.",
+        ));
+    }
+    Ok(project_root)
+}
+
+fn last_invocation(project_root: &Path) -> OptimizerResult<Invocation> {
+    History::last(project_root)?.ok_or_else(|| {
+        OptimizerError::invalid_input("No Atlas command has been run in this project yet")
+    })
+}
+
+/// Run the project's last Atlas command again, from the directory it ran in
+pub async fn again(project_dir: Option<PathBuf>) -> OptimizerResult<()> {
+    let project_root = project_root(project_dir)?;
+    let invocation = last_invocation(&project_root)?;
+    print_status(&format!(
+        "Repeating {}",
+        style::code(&invocation.command_line())
+    ));
+
+    let status = Command::new(std::env::current_exe()?)
+        .args(&invocation.args)
+        .current_dir(&invocation.cwd)
+        .status()?;
+    if !status.success() {
+        return Err(OptimizerError::command_failed(format!(
+            "{} exited with {}",
+            invocation.command_line(),
+            status
+        )));
+    }
+    Ok(())
+}
+
+/// Show the project's last Atlas command and, with `stats`, the builds it ran
+pub async fn last(stats: bool, project_dir: Option<PathBuf>) -> OptimizerResult<()> {
+    let project_root = project_root(project_dir)?;
+    let invocation = last_invocation(&project_root)?;
+
+    println!("{}", style::title("⏮️  Last Command"));
+    println!("   • {}", style::code(&invocation.command_line()));
+    println!(
+        "   • Ran {} for {}",
+        invocation
+            .started_at
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M:%S"),
+        format_duration(Duration::from_secs_f64(invocation.duration_secs))
+    );
+    match &invocation.error {
+        None if invocation.success => println!("   • {}", style::good("Succeeded")),
+        Some(error) => println!("   • {} {}", style::bad("Failed:"), error),
+        None => println!("   • {}", style::bad("Failed")),
+    }
+
+    if stats {
+        let builds = invocation.builds(&project_root)?;
+        println!();
+        if builds.is_empty() {
+            print_status("No builds or checks were recorded during this command");
+            return Ok(());
+        }
+        println!("{}", style::section("📊 Builds"));
+        let mut table = Table::new(&["Command", "Time", "Compiled", "Reused", "Cause", "sccache"])
+            .right(1)
+            .right(2)
+            .right(3);
+        for build in &builds {
+            let command = if build.success {
+                Cell::styled(build.command.as_str(), style::good)
+            } else {
+                Cell::styled(build.command.as_str(), style::bad)
+            };
+            let sccache = build
+                .sccache
+                .map(|sccache| format!("{:.0}% hits", sccache.hit_rate()))
+                .unwrap_or_else(|| "-".to_string());
+            table.add_row(vec![
+                command,
+                format_duration(Duration::from_secs_f64(build.duration_secs)).into(),
+                build.crates_compiled.to_string().into(),
+                build.crates_fresh.to_string().into(),
+                build.cause.to_string().into(),
+                sccache.into(),
+            ]);
+        }
+        table.print();
+    }
+    Ok(())
+}
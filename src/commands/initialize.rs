@@ -1,26 +1,64 @@
-use crate::config::{generate_cargo_config, generate_cargo_profiles, OptimizerConfig};
+use crate::analysis::metadata::CargoMetadata;
+use crate::audit;
+use crate::build_system::{ForeignBuild, ForeignBuildSystem};
+use crate::capabilities::is_supported_platform;
+use crate::checksum::sha256_hex;
+use crate::commands::status::print_capabilities;
+use crate::commands::tools::is_tool_supported;
+use crate::config::{generate_nextest_config, CCompilerCache, ConfigScope, OptimizerConfig};
+use crate::config_layers;
 use crate::error::{OptimizerError, OptimizerResult};
-use crate::system::SystemInfo;
+use crate::existing_setup::{Disposition, ExistingSetup, SettingSource};
+use crate::generate;
+use crate::i18n::{t, t_with};
+use crate::init_record::{InitRecord, Undone};
+use crate::managed::{
+    find_block, upsert_block, user_cargo_config_path, BlockUpdate, ManagedRegistry, Stamp,
+    CARGO_CONFIG_BLOCK, PROFILES_BLOCK,
+};
+use crate::manifest::{KeyMerge, Manifest};
+use crate::nix;
+use crate::preflight::Preflight;
+use crate::project_config::{default_project_config, ProjectConfig};
+use crate::style;
+use crate::system::{OperatingSystem, SystemInfo};
+use crate::templates::{render_generated, Generated};
 use crate::utils::*;
-use colored::Colorize;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-pub async fn run(
-    project_dir: Option<PathBuf>,
-    no_backup: bool,
-    no_tools: bool,
-    force: bool,
-) -> OptimizerResult<()> {
+/// How `atlas init` sets up a project, from its flags
+pub struct InitOptions {
+    pub no_backup: bool,
+    pub no_tools: bool,
+    pub force: bool,
+    pub scope: ConfigScope,
+    pub nix: bool,
+    /// Write Cargo files even where Bazel or Buck builds the code
+    pub full: bool,
+    /// Only apply settings that do not change what is built
+    pub minimal: bool,
+}
+
+pub async fn run(project_dir: Option<PathBuf>, options: InitOptions) -> OptimizerResult<()> {
+    let InitOptions {
+        no_backup,
+        no_tools,
+        force,
+        scope,
+        nix,
+        full,
+        minimal,
+    } = options;
     let project_root = if let Some(dir) = project_dir {
         dir
     } else {
         find_rust_project_root(".")?
     };
 
-    print_status(&format!(
-        "Initializing optimization for project: {}",
-        project_root.display()
+    print_status(&t_with(
+        "init.initializing",
+        &[("path", &project_root.display())],
     ));
 
     // Validate that this is a Rust project
@@ -31,56 +69,448 @@ pub async fn run(
     }
 
     // Detect system information
-    let system_info = SystemInfo::detect()?;
-    print_status(&format!(
-        "Detected system: {} {} with {} CPU cores",
-        system_info.os, system_info.arch, system_info.cpu_cores
+    let mut system_info = SystemInfo::detect()?;
+    print_status(&t_with(
+        "init.detected",
+        &[
+            ("os", &system_info.os),
+            ("arch", &system_info.arch),
+            ("cores", &system_info.cpu_cores),
+        ],
     ));
 
+    // Elsewhere than macOS, Linux and Windows, do what works and say what does not
+    if !is_supported_platform(&system_info.os) {
+        print_warning(&t_with(
+            "init.unsupported_platform",
+            &[("os", &system_info.os)],
+        ));
+        print_capabilities(&system_info);
+    }
+    if minimal {
+        print_status(t("init.minimal"));
+    }
+
     // Load or create configuration
-    let config = OptimizerConfig::load_or_default()?;
+    let mut config = OptimizerConfig::load_or_default()?;
+
+    // Carry over optimizations the project already configured by hand.
+    // They are specific to this project, so they never move to user scope.
+    let loaded = config.clone();
+    let existing = match scope {
+        ConfigScope::Project => ExistingSetup::import(&project_root, &mut config)?,
+        ConfigScope::User => ExistingSetup::default(),
+    };
+    print_existing_setup(&existing);
     config.validate()?;
+    let imported = config_layers::changed_settings(&loaded, &config)?;
 
-    // Backup existing files if requested
-    if !no_backup {
-        backup_existing_files(&project_root)?;
+    // Cargo files would conflict with a Bazel or Buck build of the same code
+    let foreign = match scope {
+        ConfigScope::Project if !full => ForeignBuild::detect(&project_root)?,
+        _ => None,
+    };
+    if let Some(foreign) = &foreign {
+        print_warning(&t_with(
+            "init.foreign_build",
+            &[
+                ("system", &foreign.system),
+                ("rules", &foreign.rust_rules.display()),
+            ],
+        ));
     }
 
-    // Install configuration files
-    install_cargo_config(&project_root, &config, &system_info, force)?;
-    install_cargo_profiles(&project_root, force)?;
+    // Check every file and install up front, so a read-only file or a missing
+    // sudo cannot leave the project half configured
+    let ccache = !minimal && !no_tools && needs_ccache(&project_root, &config, &system_info);
+    let mut preflight = Preflight::new();
+    if foreign.is_none() {
+        let touched = match scope {
+            ConfigScope::Project if minimal => {
+                vec![project_root.join(".cargo").join("config.toml")]
+            }
+            ConfigScope::Project => vec![
+                project_root.join(".cargo").join("config.toml"),
+                project_root.join("Cargo.toml"),
+            ],
+            ConfigScope::User => vec![user_cargo_config_path()?],
+        };
+        for path in touched {
+            if !no_backup && path.exists() {
+                preflight.write(path.with_extension("toml.backup"));
+            }
+            preflight.write(path);
+        }
+        if minimal && scope == ConfigScope::Project {
+            let nextest = nextest_config_path(&project_root);
+            if !nextest.exists() {
+                preflight.write(nextest);
+            }
+        }
+    }
+    preflight
+        .write(ManagedRegistry::path()?)
+        .write(OptimizerConfig::get_config_path()?);
+    if !ProjectConfig::path(&project_root).exists() || !imported.is_empty() {
+        preflight.write(ProjectConfig::path(&project_root));
+    }
+    preflight.write(InitRecord::path(&project_root));
+    if nix {
+        preflight.write(project_root.join(nix::DEVSHELL_FILE));
+        if !project_root.join("flake.nix").exists() && !project_root.join("shell.nix").exists() {
+            preflight.write(project_root.join("flake.nix"));
+        }
+    }
+    let (tools, unsupported): (Vec<String>, Vec<String>) = config
+        .tools
+        .preferred_tools
+        .iter()
+        .filter(|tool| !minimal || MINIMAL_TOOLS.contains(&tool.as_str()))
+        .cloned()
+        .partition(|tool| is_tool_supported(tool, &system_info));
+    if !no_tools {
+        let mut tools = tools.clone();
+        if ccache {
+            tools.push("ccache".to_string());
+        }
+        preflight.installs(crate::commands::tools::planned_install_commands(&tools).await);
+    }
+    preflight.check("Initializing")?;
+
+    // C code in -sys crates is rebuilt from scratch without a compiler cache
+    if ccache {
+        print_status(t("init.installing_ccache"));
+        crate::commands::tools::install_tools(&["ccache".to_string()]).await?;
+        system_info = SystemInfo::refresh()?;
+    }
+
+    // Remember what each file looked like, for `atlas init --undo`, by
+    // absolute path so undo works from any directory
+    let record_root = project_root.canonicalize()?;
+    let mut record = InitRecord::load(&record_root)?;
+    let before: Vec<(PathBuf, Option<String>)> = init_outputs(&record_root, scope)?
+        .into_iter()
+        .map(|path| {
+            let hash = fs::read(&path).ok().map(|content| sha256_hex(&content));
+            (path, hash)
+        })
+        .collect();
+
+    // Backup existing files if requested
+    let backups = if !no_backup && foreign.is_none() {
+        backup_existing_files(&record_root, scope, minimal, &record)?
+    } else {
+        Vec::new()
+    };
+
+    // Install configuration files and record which scope owns them
+    let mut registry = ManagedRegistry::load()?;
+    match scope {
+        ConfigScope::Project if foreign.is_some() => {}
+        ConfigScope::Project => {
+            install_cargo_config(
+                &project_root,
+                &config,
+                &system_info,
+                &existing,
+                force,
+                minimal,
+                &mut registry,
+            )?;
+            if minimal {
+                install_nextest_config(&project_root)?;
+            } else {
+                install_cargo_profiles(&project_root, &config, &system_info, force, &mut registry)?;
+            }
+        }
+        ConfigScope::User => {
+            install_user_config(&config, &system_info, force, minimal, &mut registry)?
+        }
+    }
+    registry.save()?;
 
     // Install tools if requested
     if !no_tools {
-        print_status("Installing required optimization tools...");
-        crate::commands::tools::install_tools(&config.tools.preferred_tools).await?;
+        print_status(t("init.installing_tools"));
+        if !unsupported.is_empty() {
+            print_warning(&t_with(
+                "init.skipping_tools",
+                &[("tools", &unsupported.join(", "))],
+            ));
+        }
+        crate::commands::tools::install_tools(&tools).await?;
     }
 
-    // Create optimization scripts directory
-    create_scripts_directory(&project_root)?;
+    // Create project config with common tasks
+    create_project_config(&project_root, &config, &system_info)?;
+    save_imported_settings(&project_root, &imported)?;
 
-    // Save configuration
-    OptimizerConfig::save_default()?;
+    if nix {
+        install_nix_devshell(&project_root, &config, &system_info, force)?;
+    }
+
+    for (path, hash) in before {
+        let after = fs::read(&path).ok().map(|content| sha256_hex(&content));
+        if after.is_some() && after != hash {
+            let backup = backups
+                .iter()
+                .find(|(original, _)| *original == path)
+                .map(|(_, backup)| backup.clone());
+            record.record(&path, hash.is_none(), backup)?;
+        }
+    }
+    if !record.changes.is_empty() {
+        record.save(&record_root)?;
+    }
+
+    // Create the global config on first use; an existing one keeps the
+    // user's settings
+    if !OptimizerConfig::get_config_path()?.exists() {
+        OptimizerConfig::save_default()?;
+    }
+
+    print_success(t("init.success"));
+    match &foreign {
+        Some(foreign) => print_reduced_next_steps(foreign, &config),
+        None => print_next_steps(&config),
+    }
+    if minimal {
+        print_status(&t_with(
+            "init.minimal_next",
+            &[("command", &style::code("atlas init"))],
+        ));
+    }
+
+    Ok(())
+}
+
+/// Next steps when Bazel or Buck owns compilation: only tools, caching and
+/// analysis from `cargo metadata` apply
+fn print_reduced_next_steps(foreign: &ForeignBuild, config: &OptimizerConfig) {
+    println!();
+    println!(
+        "{}",
+        style::attention(&format!("🏗️  Reduced mode for {}", foreign.system))
+    );
+    if config.build.enable_sccache {
+        println!(
+            "   • Cache Cargo builds outside {} with {}",
+            foreign.system,
+            style::good("export RUSTC_WRAPPER=sccache")
+        );
+    }
+    match foreign.system {
+        ForeignBuildSystem::Bazel => println!(
+            "   • Bazel caches its own actions: add {} to .bazelrc",
+            style::good("build --disk_cache=~/.cache/bazel-disk")
+        ),
+        ForeignBuildSystem::Buck2 => println!(
+            "   • Buck2 caches its own actions: configure {} in .buckconfig",
+            style::good("[buck2_re_client]")
+        ),
+    }
+    println!("   • These commands only read cargo metadata and work as usual:");
+    for command in [
+        "atlas analyze graph",
+        "atlas ci affected --base origin/main",
+        "atlas status",
+    ] {
+        println!("       {}", style::code(command));
+    }
+}
 
-    print_success("🎉 Rust build optimization initialized successfully!");
-    print_next_steps();
+/// Write the Atlas devShell and a flake using it. Existing `flake.nix` and
+/// `shell.nix` files are left alone; the user gets the line that imports the devShell.
+fn install_nix_devshell(
+    project_root: &Path,
+    config: &OptimizerConfig,
+    system_info: &SystemInfo,
+    force: bool,
+) -> OptimizerResult<()> {
+    print_status("Writing Nix devShell...");
 
+    let devshell_path = project_root.join(nix::DEVSHELL_FILE);
+    let ours = |path: &Path| {
+        fs::read_to_string(path).is_ok_and(|content| content.starts_with(nix::HEADER))
+    };
+    if devshell_path.exists() && !ours(&devshell_path) && !force {
+        print_warning(&format!(
+            "{} was not written by Atlas. Keeping it; use --force to overwrite it",
+            devshell_path.display()
+        ));
+        return Ok(());
+    }
+    let devshell = render_generated(
+        Generated::NixShell,
+        config,
+        Some(system_info),
+        nix::devshell(config),
+    )?;
+    // The header marks the file as Atlas's, so later runs may overwrite it
+    let devshell = if devshell.starts_with(nix::HEADER) {
+        devshell
+    } else {
+        format!("{}\n{}", nix::HEADER, devshell)
+    };
+    audit::write(&devshell_path, devshell)?;
+    print_success(&format!("Wrote {}", devshell_path.display()));
+
+    let flake_path = project_root.join("flake.nix");
+    let shell_path = project_root.join("shell.nix");
+    if flake_path.exists() {
+        if nix::imports_devshell(&fs::read_to_string(&flake_path)?) {
+            print_success(&format!(
+                "{} already uses {}",
+                flake_path.display(),
+                nix::DEVSHELL_FILE
+            ));
+        } else {
+            print_warning(&format!(
+                "{} exists and was left unchanged. Add the Atlas devShell to its outputs:",
+                flake_path.display()
+            ));
+            println!("   {}", style::good(&nix::flake_devshell_line()));
+            println!(
+                "   then enter it with {}",
+                style::good("nix develop .#atlas")
+            );
+        }
+    } else if shell_path.exists() {
+        print_warning(&format!(
+            "{} exists and was left unchanged. Include the Atlas devShell in its mkShell:",
+            shell_path.display()
+        ));
+        println!(
+            "   {}",
+            style::good(&format!(
+                "inputsFrom = [ (import ./{} {{ inherit pkgs; }}) ];",
+                nix::DEVSHELL_FILE
+            ))
+        );
+    } else {
+        audit::write(&flake_path, nix::flake(project_root, system_info))?;
+        print_success(&format!("Wrote {}", flake_path.display()));
+        println!(
+            "   Pin nixpkgs and the toolchain with {}, then enter the shell with {}",
+            style::good("nix flake lock"),
+            style::good("nix develop")
+        );
+    }
     Ok(())
 }
 
-fn backup_existing_files(project_root: &Path) -> OptimizerResult<()> {
+/// Files `atlas init` may create or modify
+fn init_outputs(project_root: &Path, scope: ConfigScope) -> OptimizerResult<Vec<PathBuf>> {
+    let mut paths = vec![
+        project_root.join(".cargo").join("config.toml"),
+        project_root.join("Cargo.toml"),
+        nextest_config_path(project_root),
+        ProjectConfig::path(project_root),
+        project_root.join(nix::DEVSHELL_FILE),
+        project_root.join("flake.nix"),
+    ];
+    if scope == ConfigScope::User {
+        paths.push(user_cargo_config_path()?);
+    }
+    Ok(paths)
+}
+
+/// Back up the files init is about to change, returning each file with its
+/// backup. Files an earlier init changed already have the original backed up.
+fn backup_existing_files(
+    project_root: &Path,
+    scope: ConfigScope,
+    minimal: bool,
+    record: &InitRecord,
+) -> OptimizerResult<Vec<(PathBuf, PathBuf)>> {
     print_status("Backing up existing files...");
 
-    let cargo_config_path = project_root.join(".cargo").join("config.toml");
-    if cargo_config_path.exists() {
-        backup_file(&cargo_config_path)?;
+    let paths = match scope {
+        ConfigScope::User => vec![user_cargo_config_path()?],
+        // Minimal mode leaves Cargo.toml alone
+        ConfigScope::Project if minimal => vec![project_root.join(".cargo").join("config.toml")],
+        ConfigScope::Project => vec![
+            project_root.join(".cargo").join("config.toml"),
+            project_root.join("Cargo.toml"),
+        ],
+    };
+
+    let mut backups = Vec::new();
+    for path in paths {
+        if path.exists() && !record.contains(&path) {
+            let backup = backup_file(&path)?;
+            backups.push((path, backup));
+        }
     }
+    Ok(backups)
+}
 
-    let cargo_toml_path = project_root.join("Cargo.toml");
-    if cargo_toml_path.exists() {
-        backup_file(&cargo_toml_path)?;
+/// Roll back the changes recorded by `atlas init` in the project
+pub fn undo(project_dir: Option<PathBuf>) -> OptimizerResult<()> {
+    let project_root = match project_dir {
+        Some(dir) => dir,
+        None => find_rust_project_root(".")?,
+    }
+    .canonicalize()?;
+    let record = InitRecord::load(&project_root)?;
+    if record.changes.is_empty() {
+        return Err(OptimizerError::invalid_input(format!(
+            "Nothing to undo: {} has no record of `atlas init`",
+            project_root.display()
+        )));
     }
 
+    let mut preflight = Preflight::new();
+    for change in &record.changes {
+        preflight.write(change.path.clone());
+    }
+    preflight
+        .write(ManagedRegistry::path()?)
+        .write(InitRecord::path(&project_root));
+    preflight.check("Undoing init")?;
+
+    // The registry matches canonical paths, which removed files no longer have
+    let mut registry = ManagedRegistry::load()?;
+    for change in &record.changes {
+        registry.forget(&change.path);
+    }
+
+    for undone in record.undo()? {
+        match undone {
+            Undone::Restored(path) => {
+                print_success(&format!("Restored {} from its backup", path.display()))
+            }
+            Undone::Removed(path) => {
+                print_success(&format!("Removed {}", path.display()));
+                // Directories init created, such as .cargo, go too once empty
+                if let Some(parent) = path.parent().filter(|parent| *parent != project_root) {
+                    let _ = fs::remove_dir(parent);
+                }
+            }
+            Undone::Stripped(path) => print_success(&format!(
+                "Removed the Atlas block from {}, keeping later edits",
+                path.display()
+            )),
+            Undone::Kept(path) => print_warning(&format!(
+                "Keeping {}: it changed since `atlas init`",
+                path.display()
+            )),
+        }
+    }
+    let backups = record
+        .changes
+        .iter()
+        .filter_map(|change| change.backup.as_deref())
+        .filter(|backup| backup.exists());
+    for backup in backups {
+        print_status(&format!(
+            "Kept {}, the original from before `atlas init`",
+            backup.display()
+        ));
+    }
+    registry.save()?;
+    fs::remove_file(InitRecord::path(&project_root))?;
+    print_success("Undid `atlas init`");
     Ok(())
 }
 
@@ -88,7 +518,10 @@ fn install_cargo_config(
     project_root: &Path,
     config: &OptimizerConfig,
     system_info: &SystemInfo,
+    existing: &ExistingSetup,
     force: bool,
+    minimal: bool,
+    registry: &mut ManagedRegistry,
 ) -> OptimizerResult<()> {
     let cargo_dir = project_root.join(".cargo");
     let config_path = cargo_dir.join("config.toml");
@@ -96,173 +529,434 @@ fn install_cargo_config(
     // Create .cargo directory if it doesn't exist
     fs::create_dir_all(&cargo_dir)?;
 
-    // Check if config already exists
-    if config_path.exists() && !force {
-        if !confirm("Cargo config already exists. Overwrite?")? {
+    let mut current = read_if_exists(&config_path)?;
+    if find_block(&current, CARGO_CONFIG_BLOCK)?.is_none() && config_path.exists() {
+        // Regenerating the file would drop settings Atlas cannot express
+        let unsupported = existing.unsupported(SettingSource::CargoConfig);
+        if !unsupported.is_empty() && !force {
+            print_warning(&format!(
+                "Keeping {}: it contains settings Atlas does not manage",
+                config_path.display()
+            ));
+            for setting in unsupported {
+                println!("   • {} = {}", setting.key, setting.value);
+            }
+            println!("   Use --force to replace it with Atlas's configuration");
+            return Ok(());
+        }
+
+        if !force && !confirm("Cargo config already exists. Overwrite?")? {
             print_warning("Skipping Cargo config installation");
             return Ok(());
         }
+        current.clear();
+    }
+
+    if config.build.linker_plugin_lto && !minimal {
+        match system_info.linker_plugin_lto_llvm() {
+            Ok(llvm) => print_status(&format!("Configuring linker-plugin LTO with LLVM {}", llvm)),
+            Err(e) => print_warning(&format!("Skipping linker-plugin LTO: {}", e)),
+        }
     }
 
     // Generate optimized Cargo configuration
-    let config_content = generate_cargo_config(config, system_info);
+    let body = generate::cargo_config(config, system_info, ConfigScope::Project, minimal)?;
+    warn_overlapping(registry, ConfigScope::Project, &body)?;
+
+    let block = ManagedBlockWrite {
+        path: &config_path,
+        current: &current,
+        name: CARGO_CONFIG_BLOCK,
+        body: &body,
+        scope: ConfigScope::Project,
+        stamp: &Stamp::current(config)?,
+    };
+    if write_managed_block(block, force, registry)? {
+        print_success(&format!(
+            "Installed optimized Cargo config: {}",
+            config_path.display()
+        ));
+    }
 
-    // Write configuration file
-    fs::write(&config_path, config_content)?;
-    print_success(&format!(
-        "Installed optimized Cargo config: {}",
-        config_path.display()
-    ));
+    Ok(())
+}
 
+fn install_user_config(
+    config: &OptimizerConfig,
+    system_info: &SystemInfo,
+    force: bool,
+    minimal: bool,
+    registry: &mut ManagedRegistry,
+) -> OptimizerResult<()> {
+    let config_path = user_cargo_config_path()?;
+    if let Some(parent) = config_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    // Replacing a user config that has no Atlas block needs confirmation
+    let mut current = read_if_exists(&config_path)?;
+    if find_block(&current, CARGO_CONFIG_BLOCK)?.is_none() && config_path.exists() {
+        if !force
+            && !confirm(&format!(
+                "{} already exists. Replace it with Atlas's settings?",
+                config_path.display()
+            ))?
+        {
+            print_warning("Skipping user Cargo config installation");
+            return Ok(());
+        }
+        current.clear();
+    }
+
+    let body = generate::cargo_config(config, system_info, ConfigScope::User, minimal)?;
+    warn_overlapping(registry, ConfigScope::User, &body)?;
+
+    let block = ManagedBlockWrite {
+        path: &config_path,
+        current: &current,
+        name: CARGO_CONFIG_BLOCK,
+        body: &body,
+        scope: ConfigScope::User,
+        stamp: &Stamp::current(config)?,
+    };
+    if write_managed_block(block, force, registry)? {
+        print_success(&format!(
+            "Installed optimized Cargo config for all projects: {}",
+            config_path.display()
+        ));
+    }
     Ok(())
 }
 
-fn install_cargo_profiles(project_root: &Path, force: bool) -> OptimizerResult<()> {
-    let cargo_toml_path = project_root.join("Cargo.toml");
+/// A managed block to write into a file
+pub(crate) struct ManagedBlockWrite<'a> {
+    pub(crate) path: &'a Path,
+    /// Current file content, or empty to replace the whole file
+    pub(crate) current: &'a str,
+    pub(crate) name: &'a str,
+    pub(crate) body: &'a str,
+    pub(crate) scope: ConfigScope,
+    pub(crate) stamp: &'a Stamp,
+}
 
-    if !cargo_toml_path.exists() {
-        return Err(OptimizerError::file_not_found("Cargo.toml"));
+/// Write a managed block, leaving the rest of the file alone.
+/// Returns whether the file changed.
+pub(crate) fn write_managed_block(
+    block: ManagedBlockWrite,
+    force: bool,
+    registry: &mut ManagedRegistry,
+) -> OptimizerResult<bool> {
+    match upsert_block(block.current, block.name, block.body, block.stamp, force)? {
+        BlockUpdate::Unchanged => {
+            print_status(&format!("{} is up to date", block.path.display()));
+            Ok(false)
+        }
+        BlockUpdate::Modified => {
+            print_warning(&format!(
+                "The Atlas block in {} was edited by hand. Keeping it; use --force to regenerate it",
+                block.path.display()
+            ));
+            Ok(false)
+        }
+        BlockUpdate::Updated(content) => {
+            // Never leave a file Cargo cannot parse
+            toml::from_str::<toml::Value>(&content)?;
+            audit::write(block.path, content)?;
+            registry.record(block.path, block.scope, block.body)?;
+            Ok(true)
+        }
     }
+}
 
-    // Read existing Cargo.toml
-    let existing_content = fs::read_to_string(&cargo_toml_path)?;
+fn read_if_exists(path: &Path) -> OptimizerResult<String> {
+    if path.exists() {
+        Ok(fs::read_to_string(path)?)
+    } else {
+        Ok(String::new())
+    }
+}
 
-    // Check if profiles already exist
-    if existing_content.contains("[profile.dev]") && !force {
-        if !confirm("Cargo.toml already contains profiles. Add optimized profiles anyway?")? {
-            print_warning("Skipping Cargo.toml profile optimization");
-            return Ok(());
+/// Point out settings that the other scope also manages
+fn warn_overlapping(
+    registry: &ManagedRegistry,
+    scope: ConfigScope,
+    content: &str,
+) -> OptimizerResult<()> {
+    let overlapping = registry.overlapping(scope, content)?;
+    for (file, shared) in &overlapping {
+        print_warning(&format!(
+            "{} settings are also managed at {} scope in {}",
+            shared.len(),
+            file.scope,
+            file.path.display()
+        ));
+    }
+    if !overlapping.is_empty() {
+        println!("   Project settings override user settings, but rustflags from both are passed together");
+    }
+    Ok(())
+}
+
+/// Tools `atlas init --minimal` installs, if the config prefers them
+const MINIMAL_TOOLS: &[&str] = &["sccache", "cargo-nextest"];
+
+fn nextest_config_path(project_root: &Path) -> PathBuf {
+    project_root.join(".config").join("nextest.toml")
+}
+
+/// Write the nextest config, unless the project already has one
+fn install_nextest_config(project_root: &Path) -> OptimizerResult<()> {
+    let path = nextest_config_path(project_root);
+    if path.exists() {
+        print_status(&format!("Keeping existing {}", path.display()));
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    audit::write(&path, generate_nextest_config())?;
+    print_success(&format!("Wrote {}", path.display()));
+    Ok(())
+}
+
+fn needs_ccache(project_root: &Path, config: &OptimizerConfig, system_info: &SystemInfo) -> bool {
+    let metadata = match CargoMetadata::load(project_root) {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            log::warn!("Could not inspect dependencies for native code: {}", e);
+            return false;
         }
+    };
+
+    let native: Vec<&str> = metadata
+        .native_packages()
+        .into_iter()
+        .map(|package| package.name.as_str())
+        .collect();
+    if native.is_empty() {
+        return false;
     }
 
-    // Generate optimized profiles
-    let profiles_content = generate_cargo_profiles();
+    print_status(&format!(
+        "Found {} dependencies that compile native code: {}",
+        native.len(),
+        native.join(", ")
+    ));
+    config.build.c_compiler_cache == CCompilerCache::Auto
+        && system_info.os != OperatingSystem::Windows
+        && !system_info.is_tool_installed("ccache")
+        && is_tool_supported("ccache", system_info)
+}
 
-    // Append profiles to Cargo.toml
-    let mut new_content = existing_content;
-    if !new_content.ends_with('\n') {
-        new_content.push('\n');
+fn install_cargo_profiles(
+    project_root: &Path,
+    config: &OptimizerConfig,
+    system_info: &SystemInfo,
+    force: bool,
+    registry: &mut ManagedRegistry,
+) -> OptimizerResult<()> {
+    let cargo_toml_path = project_root.join("Cargo.toml");
+
+    if !cargo_toml_path.exists() {
+        return Err(OptimizerError::file_not_found("Cargo.toml"));
     }
-    new_content.push('\n');
-    new_content.push_str("# Optimized build profiles added by Atlas\n");
-    new_content.push_str(&profiles_content);
 
-    // Write updated Cargo.toml
-    fs::write(&cargo_toml_path, new_content)?;
-    print_success("Added optimized build profiles to Cargo.toml");
+    // Profile tables the project already defines get the optimized settings
+    // it has not set; the block holds the tables it does not define
+    let existing_content = fs::read_to_string(&cargo_toml_path)?;
+    let merge = merge_profile_keys(config, system_info, &existing_content)?;
+    print_merge(&merge);
+    let body = generate::profiles_for_manifest(config, system_info, &merge.content)?;
+    let wrote_block =
+        if !body.contains("[profile") && find_block(&merge.content, PROFILES_BLOCK)?.is_none() {
+            false
+        } else {
+            warn_overlapping(registry, ConfigScope::Project, &body)?;
+            let block = ManagedBlockWrite {
+                path: &cargo_toml_path,
+                current: &merge.content,
+                name: PROFILES_BLOCK,
+                body: &body,
+                scope: ConfigScope::Project,
+                stamp: &Stamp::current(config)?,
+            };
+            write_managed_block(block, force, registry)?
+        };
+
+    if wrote_block {
+        print_success("Added optimized build profiles to Cargo.toml");
+    } else if merge.changed > 0 {
+        audit::write(&cargo_toml_path, &merge.content)?;
+        print_success("Added optimized settings to the profiles in Cargo.toml");
+    } else if find_block(&merge.content, PROFILES_BLOCK)?.is_none() {
+        print_status("Cargo.toml already defines all optimized profiles");
+    }
 
     Ok(())
 }
 
-fn create_scripts_directory(project_root: &Path) -> OptimizerResult<()> {
-    let scripts_dir = project_root.join("scripts");
+/// A manifest with generated profile settings merged into the profile
+/// tables the project defines itself
+pub(crate) struct ProfileMerge {
+    pub(crate) content: String,
+    /// Settings added or updated
+    pub(crate) changed: usize,
+    /// Settings the project sets to other values, as `table.key = value`
+    pub(crate) kept: Vec<String>,
+}
 
-    if !scripts_dir.exists() {
-        fs::create_dir_all(&scripts_dir)?;
-        print_status(&format!(
-            "Created scripts directory: {}",
-            scripts_dir.display()
-        ));
+/// Merge generated profile settings into the project's own profile tables,
+/// keeping its comments, key order and values
+pub(crate) fn merge_profile_keys(
+    config: &OptimizerConfig,
+    system_info: &SystemInfo,
+    content: &str,
+) -> OptimizerResult<ProfileMerge> {
+    let mut manifest = Manifest::parse(content)?;
+    let mut merge = ProfileMerge {
+        content: String::new(),
+        changed: 0,
+        kept: Vec::new(),
+    };
+    for (table, key, value) in generate::profile_keys_for_manifest(config, system_info, content)? {
+        match manifest.merge_key(&table, &key, &value)? {
+            KeyMerge::Added | KeyMerge::Updated => merge.changed += 1,
+            KeyMerge::Unchanged => {}
+            KeyMerge::Kept(current) => merge.kept.push(format!("{}.{} = {}", table, key, current)),
+        }
     }
+    merge.content = manifest.to_string();
+    Ok(merge)
+}
 
-    // Create a simple build script as an example
-    let build_script_path = scripts_dir.join("fast-build.sh");
-    if !build_script_path.exists() {
-        let build_script_content = r#"#!/bin/bash
-# Fast build script generated by Atlas
-# Use Atlas for more advanced features
-
-set -e
-
-case "${1:-check}" in
-    "check")
-        echo "Running fast cargo check..."
-        cargo check --workspace --all-targets
-        ;;
-    "build")
-        echo "Running optimized cargo build..."
-        cargo build --workspace
-        ;;
-    "test")
-        echo "Running fast tests..."
-        if command -v cargo-nextest >/dev/null 2>&1; then
-            cargo nextest run --workspace
-        else
-            cargo test --workspace
-        fi
-        ;;
-    "release")
-        echo "Running optimized release build..."
-        cargo build --workspace --release
-        ;;
-    *)
-        echo "Usage: $0 [check|build|test|release]"
-        exit 1
-        ;;
-esac
-"#;
-        fs::write(&build_script_path, build_script_content)?;
-
-        // Make script executable on Unix systems
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let mut perms = fs::metadata(&build_script_path)?.permissions();
-            perms.set_mode(0o755);
-            fs::set_permissions(&build_script_path, perms)?;
+fn print_merge(merge: &ProfileMerge) {
+    if merge.kept.is_empty() {
+        return;
+    }
+    print_status(&format!(
+        "Keeping {} profile settings Cargo.toml already sets:",
+        merge.kept.len()
+    ));
+    for setting in &merge.kept {
+        println!("   • {}", setting);
+    }
+}
+
+fn print_existing_setup(existing: &ExistingSetup) {
+    if existing.is_empty() {
+        return;
+    }
+
+    print_status(&t_with(
+        "init.existing",
+        &[
+            ("imported", &existing.count(Disposition::Imported)),
+            ("covered", &existing.count(Disposition::Redundant)),
+            ("unsupported", &existing.count(Disposition::Unsupported)),
+        ],
+    ));
+    for setting in &existing.settings {
+        if setting.disposition == Disposition::Imported {
+            println!(
+                "   • {} {} = {}",
+                style::dim(setting.source.file_name()),
+                setting.key,
+                setting.value
+            );
         }
+    }
+}
 
-        print_success(&format!(
-            "Created build script: {}",
-            build_script_path.display()
-        ));
+fn create_project_config(
+    project_root: &Path,
+    config: &OptimizerConfig,
+    system_info: &SystemInfo,
+) -> OptimizerResult<()> {
+    let path = ProjectConfig::path(project_root);
+    if path.exists() {
+        return Ok(());
     }
 
+    let content = render_generated(
+        Generated::ProjectConfig,
+        config,
+        Some(system_info),
+        default_project_config().to_string(),
+    )?;
+    // A template must still produce an atlas.toml Atlas can read
+    toml::from_str::<ProjectConfig>(&content)
+        .map_err(|e| OptimizerError::config(format!("Templated atlas.toml is invalid: {}", e)))?;
+    audit::write(&path, content)?;
+    print_success(&t_with(
+        "init.created_project_config",
+        &[("path", &path.display())],
+    ));
+    Ok(())
+}
+
+/// Keep imported settings in the project's `atlas.toml`, so later
+/// regenerations by `atlas refresh` produce what init wrote. They are marked
+/// as Atlas's, for `atlas init --undo`.
+fn save_imported_settings(
+    project_root: &Path,
+    imported: &[(String, toml::Value)],
+) -> OptimizerResult<()> {
+    if imported.is_empty() {
+        return Ok(());
+    }
+    let path = ProjectConfig::path(project_root);
+    let mut project_config = Manifest::load(&path)?;
+    for (key, value) in imported {
+        let Some((table, key)) = key.rsplit_once('.') else {
+            continue;
+        };
+        project_config.merge_key(table, key, &value.to_string())?;
+    }
+    project_config.save(&path)?;
+    print_status(&format!(
+        "Saved the imported settings to {} so `atlas refresh` keeps them",
+        path.display()
+    ));
     Ok(())
 }
 
-fn print_next_steps() {
+fn print_next_steps(config: &OptimizerConfig) {
     println!();
-    print_success("🎉 Rust Build Optimization initialized successfully!");
+    print_success(t("init.success"));
     println!();
-    println!("📋 Next steps:");
-    println!(
-        "   1. Test the optimizations: {}",
-        "atlas build check".bright_green()
-    );
-    println!(
-        "   2. Run development workflow: {}",
-        "atlas dev watch".bright_green()
-    );
-    println!(
-        "   3. Check optimization status: {}",
-        "atlas status".bright_green()
-    );
-    println!(
-        "   4. View configuration: {}",
-        "atlas config show".bright_green()
-    );
+    println!("{}", style::text(t("init.next_steps")));
+    let mut steps = vec![
+        ("init.step_check", "atlas build check"),
+        ("init.step_watch", "atlas dev watch"),
+        ("init.step_status", "atlas status"),
+        ("init.step_config", "atlas config show"),
+    ]
+    .into_iter()
+    .map(|(key, command)| t_with(key, &[("command", &style::good(command))]))
+    .collect::<Vec<_>>();
+    if config.build.separate_rust_analyzer_target {
+        steps.push(t_with(
+            "init.step_rust_analyzer",
+            &[("setting", &style::good("rust-analyzer.cargo.targetDir"))],
+        ));
+    }
+    for (number, step) in steps.iter().enumerate() {
+        println!("   {}. {}", number + 1, step);
+    }
     println!();
-    println!("🚀 Quick commands:");
-    println!(
-        "   {} - Fast syntax check",
-        "atlas dev quick-check".bright_cyan()
-    );
-    println!(
-        "   {} - Optimized build",
-        "atlas build build".bright_cyan()
-    );
-    println!(
-        "   {} - Fast testing",
-        "atlas build test".bright_cyan()
-    );
-    println!(
-        "   {} - Continuous development",
-        "atlas dev watch".bright_cyan()
-    );
+    println!("{}", style::text(t("init.quick_commands")));
+    for (command, key) in [
+        ("atlas dev quick-check", "init.quick_check"),
+        ("atlas build build", "init.quick_build"),
+        ("atlas build test", "init.quick_test"),
+        ("atlas dev watch", "init.quick_watch"),
+        ("atlas run <task>", "init.quick_tasks"),
+    ] {
+        println!("   {} - {}", style::code(command), t(key));
+    }
     println!();
     println!(
-        "📚 For help: {}",
-        "atlas --help".bright_yellow()
+        "{}",
+        t_with("init.help", &[("command", &style::caution("atlas --help"))])
     );
 }
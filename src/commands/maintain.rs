@@ -0,0 +1,323 @@
+use crate::audit;
+use crate::cache::{cache_location, local_dir};
+use crate::config::OptimizerConfig;
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::maintenance::{
+    cron_line, launchd_plist, remove, schtasks_create_args, stale_artifacts, stale_files,
+    stale_sources, systemd_units, with_cron_line, Removable, Schedule, Scheduler, JOB_NAME,
+    LAUNCHD_LABEL,
+};
+use crate::stats::StatsStore;
+use crate::style;
+use crate::table::Table;
+use crate::utils::*;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+pub async fn run(
+    dry_run: bool,
+    schedule: Option<Schedule>,
+    scheduler: Option<Scheduler>,
+    unschedule: bool,
+    project_dir: Option<PathBuf>,
+) -> OptimizerResult<()> {
+    if let Some(schedule) = schedule {
+        return install(scheduler.unwrap_or_else(default_scheduler), schedule);
+    }
+    if unschedule {
+        return uninstall(scheduler.unwrap_or_else(default_scheduler));
+    }
+    maintain(dry_run, project_dir)
+}
+
+fn days_ago(days: u32) -> SystemTime {
+    SystemTime::now() - Duration::from_secs(u64::from(days) * 24 * 60 * 60)
+}
+
+fn maintain(dry_run: bool, project_dir: Option<PathBuf>) -> OptimizerResult<()> {
+    let config = OptimizerConfig::load_or_default()?;
+    println!(
+        "{}",
+        style::title(if dry_run {
+            "🧹 Maintenance (dry run)"
+        } else {
+            "🧹 Maintenance"
+        })
+    );
+    println!();
+
+    let mut table = Table::new(&["Task", "Removed", "Freed"]).right(1).right(2);
+    let mut total = 0;
+    let mut row = |task: &str, items: &[Removable]| {
+        let bytes = if dry_run {
+            items.iter().map(|item| item.bytes).sum()
+        } else {
+            remove(items)
+        };
+        total += bytes;
+        table.add_row(vec![
+            task.into(),
+            items.len().to_string().into(),
+            format_bytes(bytes).into(),
+        ]);
+    };
+
+    // Target directories of every project Atlas has built here
+    let retention = config.optimization.artifact_retention_days;
+    if config.optimization.clean_old_artifacts {
+        let cutoff = days_ago(retention);
+        let artifacts: Vec<Removable> = target_dirs(&config, project_dir)?
+            .iter()
+            .flat_map(|dir| stale_artifacts(dir, cutoff))
+            .collect();
+        row(
+            &format!("Target artifacts unused for {} days", retention),
+            &artifacts,
+        );
+    }
+
+    if let Some(cargo_home) = cargo_home() {
+        let days = config.maintenance.registry_retention_days;
+        let sources = stale_sources(&cargo_home, days_ago(days));
+        row(&format!("Crate sources unused for {} days", days), &sources);
+    }
+
+    if let Some(dir) = sccache_dir() {
+        let entries = stale_files(&dir, days_ago(retention));
+        let trimmed = !dry_run && !entries.is_empty();
+        row(
+            &format!("sccache entries unused for {} days", retention),
+            &entries,
+        );
+        if trimmed {
+            // The server keeps the cache size in memory; it is recounted on restart
+            let _ = execute_command("sccache", &["--stop-server"], None);
+        }
+    }
+    table.print();
+
+    let days = config.maintenance.stats_retention_days;
+    let since = Utc::now() - chrono::Duration::days(i64::from(days));
+    let dropped = if dry_run {
+        StatsStore::load_since(DateTime::<Utc>::MIN_UTC)?
+            .iter()
+            .filter(|record| record.recorded_at < since)
+            .count()
+    } else {
+        StatsStore::compact(since)?.1
+    };
+
+    println!();
+    print_status(&format!(
+        "{} {} build record(s) older than {} days",
+        if dry_run { "Would drop" } else { "Dropped" },
+        dropped,
+        days
+    ));
+    if dry_run {
+        print_success(&format!(
+            "✅ Maintenance would free {}",
+            format_bytes(total)
+        ));
+    } else {
+        print_success(&format!("✅ Maintenance freed {}", format_bytes(total)));
+    }
+    Ok(())
+}
+
+/// Target directories of the projects in the build statistics that still
+/// exist, and of the current project
+fn target_dirs(
+    config: &OptimizerConfig,
+    project_dir: Option<PathBuf>,
+) -> OptimizerResult<Vec<PathBuf>> {
+    let mut projects: BTreeSet<PathBuf> = StatsStore::load_since(DateTime::<Utc>::MIN_UTC)?
+        .into_iter()
+        .map(|record| record.project)
+        .collect();
+    if let Ok(root) = find_rust_project_root(project_dir.unwrap_or_else(|| PathBuf::from("."))) {
+        projects.insert(root.canonicalize().unwrap_or(root));
+    }
+    let mut dirs = BTreeSet::new();
+    for project in projects
+        .iter()
+        .filter(|project| project.join("Cargo.toml").is_file())
+    {
+        dirs.insert(project.join("target"));
+        dirs.extend(
+            config
+                .build
+                .profile_target_dirs
+                .values()
+                .map(|dir| project.join(dir)),
+        );
+    }
+    Ok(dirs.into_iter().filter(|dir| dir.is_dir()).collect())
+}
+
+/// Directory of sccache's cache when it is on local disk
+fn sccache_dir() -> Option<PathBuf> {
+    if !is_tool_available("sccache") {
+        return None;
+    }
+    let output = execute_command("sccache", &["--show-stats", "--stats-format=json"], None).ok()?;
+    cache_location(&String::from_utf8_lossy(&output.stdout))
+        .as_deref()
+        .and_then(local_dir)
+}
+
+/// The scheduler for per-user jobs on this platform
+fn default_scheduler() -> Scheduler {
+    match std::env::consts::OS {
+        "macos" => Scheduler::Launchd,
+        "windows" => Scheduler::Windows,
+        _ => {
+            let systemd = execute_command("systemctl", &["--user", "show-environment"], None)
+                .is_ok_and(|output| output.status.success());
+            if systemd {
+                Scheduler::Systemd
+            } else {
+                Scheduler::Cron
+            }
+        }
+    }
+}
+
+fn systemd_dir() -> OptimizerResult<PathBuf> {
+    dirs::config_dir()
+        .map(|dir| dir.join("systemd").join("user"))
+        .ok_or_else(|| OptimizerError::config("Could not determine config directory"))
+}
+
+fn launchd_plist_path() -> OptimizerResult<PathBuf> {
+    dirs::home_dir()
+        .map(|home| {
+            home.join("Library")
+                .join("LaunchAgents")
+                .join(format!("{}.plist", LAUNCHD_LABEL))
+        })
+        .ok_or_else(|| OptimizerError::config("Could not determine home directory"))
+}
+
+fn install(scheduler: Scheduler, schedule: Schedule) -> OptimizerResult<()> {
+    let exe = std::env::current_exe()?;
+    let installed = match scheduler {
+        Scheduler::Cron => {
+            write_crontab(Some(&cron_line(&exe, schedule)))?;
+            "crontab".to_string()
+        }
+        Scheduler::Systemd => {
+            let dir = systemd_dir()?;
+            fs::create_dir_all(&dir)?;
+            let (service, timer) = systemd_units(&exe, schedule);
+            audit::write(&dir.join(format!("{}.service", JOB_NAME)), service)?;
+            let timer_path = dir.join(format!("{}.timer", JOB_NAME));
+            audit::write(&timer_path, timer)?;
+            run_scheduler("systemctl", &["--user", "daemon-reload"])?;
+            run_scheduler(
+                "systemctl",
+                &["--user", "enable", "--now", &format!("{}.timer", JOB_NAME)],
+            )?;
+            timer_path.display().to_string()
+        }
+        Scheduler::Launchd => {
+            let path = launchd_plist_path()?;
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let path_arg = path.display().to_string();
+            // An agent installed before is replaced
+            let _ = execute_command("launchctl", &["unload", &path_arg], None);
+            audit::write(&path, launchd_plist(&exe, schedule))?;
+            run_scheduler("launchctl", &["load", "-w", &path_arg])?;
+            path_arg
+        }
+        Scheduler::Windows => {
+            let args = schtasks_create_args(&exe, schedule);
+            let args: Vec<&str> = args.iter().map(String::as_str).collect();
+            run_scheduler("schtasks", &args)?;
+            format!("task {}", JOB_NAME)
+        }
+    };
+    print_success(&format!(
+        "✅ Maintenance runs {} through a {} ({})",
+        match schedule {
+            Schedule::Daily => "daily",
+            Schedule::Weekly => "weekly",
+        },
+        scheduler,
+        installed
+    ));
+    Ok(())
+}
+
+fn uninstall(scheduler: Scheduler) -> OptimizerResult<()> {
+    match scheduler {
+        Scheduler::Cron => write_crontab(None)?,
+        Scheduler::Systemd => {
+            let _ = execute_command(
+                "systemctl",
+                &["--user", "disable", "--now", &format!("{}.timer", JOB_NAME)],
+                None,
+            );
+            let dir = systemd_dir()?;
+            for unit in ["service", "timer"] {
+                let path = dir.join(format!("{}.{}", JOB_NAME, unit));
+                if path.exists() {
+                    audit::remove(&path)?;
+                }
+            }
+            run_scheduler("systemctl", &["--user", "daemon-reload"])?;
+        }
+        Scheduler::Launchd => {
+            let path = launchd_plist_path()?;
+            if path.exists() {
+                let _ = execute_command(
+                    "launchctl",
+                    &["unload", "-w", &path.display().to_string()],
+                    None,
+                );
+                audit::remove(&path)?;
+            }
+        }
+        Scheduler::Windows => run_scheduler("schtasks", &["/Delete", "/F", "/TN", JOB_NAME])?,
+    }
+    print_success(&format!("✅ Removed the {} for maintenance", scheduler));
+    Ok(())
+}
+
+/// Replace Atlas's line in the user's crontab, or remove it
+fn write_crontab(line: Option<&str>) -> OptimizerResult<()> {
+    if !is_tool_available("crontab") {
+        return Err(OptimizerError::tool_not_found("crontab"));
+    }
+    // `crontab -l` fails when the user has no crontab yet
+    let current = execute_command("crontab", &["-l"], None)?;
+    let current = if current.status.success() {
+        String::from_utf8_lossy(&current.stdout).to_string()
+    } else {
+        String::new()
+    };
+    let file = tempfile::NamedTempFile::new()?;
+    fs::write(file.path(), with_cron_line(&current, line))?;
+    run_scheduler("crontab", &[&file.path().display().to_string()])
+}
+
+fn run_scheduler(program: &str, args: &[&str]) -> OptimizerResult<()> {
+    if !is_tool_available(program) {
+        return Err(OptimizerError::tool_not_found(program));
+    }
+    let output = execute_command(program, args, None)?;
+    if !output.status.success() {
+        return Err(OptimizerError::command_failed(format!(
+            "{} {} failed: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
@@ -1,11 +1,31 @@
+pub(crate) mod analyze;
+pub(crate) mod artifacts;
+pub(crate) mod audit;
+pub(crate) mod baseline;
+pub(crate) mod bench_self;
 pub mod build;
-pub mod config;
-pub mod development;
+pub(crate) mod cache;
+pub(crate) mod ci;
+pub(crate) mod config;
+pub(crate) mod debug;
+pub(crate) mod development;
+pub(crate) mod docker;
+pub(crate) mod doctor;
+pub(crate) mod editor;
+pub(crate) mod explain;
+pub(crate) mod fleet;
+pub(crate) mod history;
 pub mod initialize;
-pub mod optimize;
+pub(crate) mod maintain;
+pub(crate) mod optimize;
+pub mod refresh;
+pub(crate) mod release;
+pub(crate) mod remote;
+pub(crate) mod run;
 pub mod status;
-pub mod tools;
-pub mod update;
+pub(crate) mod telemetry;
+pub(crate) mod tools;
+pub(crate) mod update;
 
 // Re-export command functions for potential future use
 // pub use initialize::run as initialize;
@@ -1,20 +1,33 @@
+use crate::baseline::{validate_name, Baseline, MeasureOptions};
+use crate::commands::tools::{ensure_tool, planned_install_commands};
+use crate::config::OptimizerConfig;
 use crate::error::OptimizerResult;
+use crate::preflight::Preflight;
+use crate::style;
 use crate::utils::*;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub async fn run(
     all: bool,
     clean: bool,
     deps: bool,
     benchmark: bool,
+    baseline: &str,
     project_dir: Option<PathBuf>,
 ) -> OptimizerResult<()> {
-    let _project_root = if let Some(dir) = project_dir {
+    let project_root = if let Some(dir) = project_dir {
         dir
     } else {
         find_rust_project_root(".")?
     };
 
+    // cargo-udeps is installed on demand; check that it can be before starting
+    if (all || deps) && OptimizerConfig::load_or_default()?.tools.auto_install {
+        Preflight::new()
+            .installs(planned_install_commands(&["cargo-udeps".to_string()]).await)
+            .check("Optimizing")?;
+    }
+
     if all || clean {
         print_status("Cleaning old artifacts...");
         // Implementation for cleaning old artifacts
@@ -23,18 +36,20 @@ pub async fn run(
 
     if all || deps {
         print_status("Checking for unused dependencies...");
-        if is_tool_available("cargo-udeps") {
-            let _ =
-                execute_command_with_output("cargo", &["+nightly", "udeps", "--all-targets"], None);
-        } else {
-            print_warning("cargo-udeps not installed. Install with: cargo install cargo-udeps");
+        match ensure_tool("cargo-udeps").await {
+            Ok(_) => {
+                let _ = execute_command_with_output(
+                    "cargo",
+                    &["+nightly", "udeps", "--all-targets"],
+                    None,
+                );
+            }
+            Err(e) => print_warning(&e.to_string()),
         }
     }
 
     if all || benchmark {
-        print_status("Running performance benchmark...");
-        // Implementation for benchmarking
-        print_success("✅ Benchmark completed");
+        run_benchmark(&project_root, baseline)?;
     }
 
     if all {
@@ -43,3 +58,77 @@ pub async fn run(
 
     Ok(())
 }
+
+/// Measure the builds and compare them with `baseline`, or store them as
+/// `baseline` when there is none yet
+fn run_benchmark(project_root: &Path, baseline: &str) -> OptimizerResult<()> {
+    print_status("Running performance benchmark...");
+    let options = MeasureOptions {
+        tests: false,
+        ..MeasureOptions::default()
+    };
+    if has_baseline(project_root, baseline)? {
+        return crate::commands::baseline::compare(project_root, baseline, options, 0.95);
+    }
+    crate::commands::baseline::record(project_root, baseline, options)?;
+    print_status(&format!(
+        "Change settings, then run {} again to see what they gained",
+        style::code(&rerun_command(baseline))
+    ));
+    Ok(())
+}
+
+/// Whether `baseline` was stored before; an invalid name is rejected here,
+/// before minutes of measuring
+fn has_baseline(project_root: &Path, baseline: &str) -> OptimizerResult<bool> {
+    validate_name(baseline)?;
+    Ok(Baseline::list(project_root)?
+        .iter()
+        .any(|stored| stored.name == baseline))
+}
+
+fn rerun_command(baseline: &str) -> String {
+    if baseline == "optimize" {
+        "atlas optimize --benchmark".to_string()
+    } else {
+        format!("atlas optimize --benchmark --baseline {}", baseline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::baseline::{BaselineMetadata, Measurements};
+
+    #[test]
+    fn test_benchmark_compares_with_stored_baseline() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(!has_baseline(dir.path(), "optimize").unwrap());
+        assert_eq!(rerun_command("optimize"), "atlas optimize --benchmark");
+        assert_eq!(
+            rerun_command("lld"),
+            "atlas optimize --benchmark --baseline lld"
+        );
+
+        Baseline {
+            name: "optimize".to_string(),
+            recorded_at: chrono::Utc::now(),
+            metadata: BaselineMetadata {
+                fingerprint: Default::default(),
+                settings: Default::default(),
+            },
+            measurements: Measurements::default(),
+        }
+        .save(dir.path())
+        .unwrap();
+        assert!(has_baseline(dir.path(), "optimize").unwrap());
+        assert!(!has_baseline(dir.path(), "lld").unwrap());
+    }
+
+    #[test]
+    fn test_benchmark_rejects_invalid_baseline_name() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(has_baseline(dir.path(), "../optimize").is_err());
+        assert!(has_baseline(dir.path(), "").is_err());
+    }
+}
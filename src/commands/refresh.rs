@@ -0,0 +1,214 @@
+use crate::audit;
+use crate::cargo_env::validate_config_env;
+use crate::commands::initialize::{merge_profile_keys, write_managed_block, ManagedBlockWrite};
+use crate::config::{is_minimal_cargo_config, ConfigScope, OptimizerConfig};
+use crate::docker::{upsert_chef_stages, CHEF_BLOCK};
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::generate;
+use crate::init_record::InitRecord;
+use crate::managed::{
+    find_block, legacy_start, user_cargo_config_path, without_blocks, BlockUpdate, ManagedRegistry,
+    Stamp, CARGO_CONFIG_BLOCK, PROFILES_BLOCK,
+};
+use crate::manifest::ADDED_MARKER;
+use crate::migrations::find_deprecated;
+use crate::system::SystemInfo;
+use crate::utils::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub async fn run(force: bool, project_dir: Option<PathBuf>) -> OptimizerResult<()> {
+    let project_root = if let Some(dir) = project_dir {
+        dir
+    } else {
+        find_rust_project_root(".")?
+    };
+
+    if !is_rust_project(&project_root) {
+        return Err(OptimizerError::project_validation(
+            "No Cargo.toml found. Please run this command from a Rust project directory.",
+        ));
+    }
+
+    let system_info = SystemInfo::detect()?;
+    let config = OptimizerConfig::load_or_default()?;
+    config.validate()?;
+    let mut registry = ManagedRegistry::load()?;
+    let stamp = Stamp::current(&config)?;
+
+    print_status("Refreshing Atlas-managed blocks...");
+    let mut found = 0;
+
+    // Files init recorded that nobody edited since stay restorable by undo
+    let record_root = project_root.canonicalize()?;
+    let mut record = InitRecord::load(&record_root)?;
+    let untouched = record.untouched(&[
+        record_root.join(".cargo").join("config.toml"),
+        record_root.join("Cargo.toml"),
+    ]);
+
+    let cargo_config = project_root.join(".cargo").join("config.toml");
+    found += refresh_file(
+        &cargo_config,
+        CARGO_CONFIG_BLOCK,
+        ConfigScope::Project,
+        &stamp,
+        force,
+        &mut registry,
+        |content| {
+            generate::cargo_config(
+                &config,
+                &system_info,
+                ConfigScope::Project,
+                has_minimal_block(content)?,
+            )
+        },
+    )?;
+
+    let manifest = project_root.join("Cargo.toml");
+    // Settings init merged into the project's own profile tables
+    let content = fs::read_to_string(&manifest)?;
+    if content.contains(ADDED_MARKER) {
+        found += 1;
+        let merge = merge_profile_keys(&config, &system_info, &content)?;
+        if merge.changed > 0 {
+            audit::write(&manifest, &merge.content)?;
+            print_success(&format!(
+                "Refreshed the Atlas settings in the profiles of {}",
+                manifest.display()
+            ));
+        }
+    }
+    found += refresh_file(
+        &manifest,
+        PROFILES_BLOCK,
+        ConfigScope::Project,
+        &stamp,
+        force,
+        &mut registry,
+        |content| generate::profiles_for_manifest(&config, &system_info, content),
+    )?;
+
+    let user_config = user_cargo_config_path()?;
+    found += refresh_file(
+        &user_config,
+        CARGO_CONFIG_BLOCK,
+        ConfigScope::User,
+        &stamp,
+        force,
+        &mut registry,
+        |content| {
+            generate::cargo_config(
+                &config,
+                &system_info,
+                ConfigScope::User,
+                has_minimal_block(content)?,
+            )
+        },
+    )?;
+
+    registry.save()?;
+    record.rewritten(&record_root, &untouched)?;
+
+    let dockerfile = project_root.join("Dockerfile");
+    if dockerfile.exists() {
+        let content = fs::read_to_string(&dockerfile)?;
+        if find_block(&content, CHEF_BLOCK)?.is_some() {
+            found += 1;
+            match upsert_chef_stages(&content, &stamp, force)? {
+                BlockUpdate::Unchanged => {}
+                BlockUpdate::Modified => print_warning(&format!(
+                    "The {} block in {} was edited by hand. Keeping it; use --force to regenerate it",
+                    CHEF_BLOCK,
+                    dockerfile.display()
+                )),
+                BlockUpdate::Updated(updated) => {
+                    audit::write(&dockerfile, updated)?;
+                    print_success(&format!("Refreshed {}", dockerfile.display()));
+                }
+            }
+        }
+    }
+
+    if found == 0 {
+        print_warning("No Atlas-managed blocks found. Run `atlas init` to install them");
+    }
+    Ok(())
+}
+
+/// Whether `atlas init --minimal` wrote the Cargo config block, which then stays minimal
+fn has_minimal_block(content: &str) -> OptimizerResult<bool> {
+    Ok(find_block(content, CARGO_CONFIG_BLOCK)?
+        .is_some_and(|block| is_minimal_cargo_config(&block.body)))
+}
+
+/// Regenerate the block called `name` in `path`, if the file has one, and explain
+/// the deprecated settings that disappear with it. Returns the number of blocks found.
+fn refresh_file(
+    path: &Path,
+    name: &str,
+    scope: ConfigScope,
+    stamp: &Stamp,
+    force: bool,
+    registry: &mut ManagedRegistry,
+    body: impl FnOnce(&str) -> OptimizerResult<String>,
+) -> OptimizerResult<usize> {
+    if !path.exists() {
+        return Ok(0);
+    }
+    let content = fs::read_to_string(path)?;
+
+    // Releases before managed blocks wrote their output without markers
+    let (current, previous) = match find_block(&content, name)? {
+        Some(block) => (content.clone(), block.body),
+        None => match legacy_start(&content, name) {
+            Some(start) => {
+                print_status(&format!(
+                    "Converting Atlas output in {} to a managed block",
+                    path.display()
+                ));
+                backup_file(path)?;
+                (content[..start].to_string(), content[start..].to_string())
+            }
+            None => return Ok(0),
+        },
+    };
+
+    let body = body(&current)?;
+    let block = ManagedBlockWrite {
+        path,
+        current: &current,
+        name,
+        body: &body,
+        scope,
+        stamp,
+    };
+    if write_managed_block(block, force, registry)? {
+        print_success(&format!("Refreshed {}", path.display()));
+        let remaining = find_deprecated(&body)?;
+        for deprecation in find_deprecated(&previous)? {
+            if !remaining.contains(&deprecation) {
+                println!("   • {}", deprecation.describe());
+            }
+        }
+    }
+
+    // Settings outside Atlas's blocks belong to the user
+    let user_content = without_blocks(&current)?;
+    let deprecated = find_deprecated(&user_content)?;
+    for deprecation in &deprecated {
+        print_warning(&format!(
+            "{} sets {}, which is deprecated: {}",
+            path.display(),
+            deprecation.key,
+            deprecation.reason
+        ));
+    }
+    for issue in validate_config_env(&user_content)? {
+        let key = format!("env.{}", issue.key);
+        if !deprecated.iter().any(|deprecation| deprecation.key == key) {
+            print_warning(&format!("{}: {}", path.display(), issue.explanation()));
+        }
+    }
+    Ok(1)
+}
@@ -0,0 +1,626 @@
+use crate::analysis::metadata::CargoMetadata;
+use crate::baseline::collect_binary_sizes;
+use crate::checksum::sha256_file;
+use crate::commands::build::{reconcile_rustflags, run_cargo};
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::project_config::ProjectConfig;
+use crate::release::{
+    artifact_name, changelog_stub, sha256sums, symbol_build_args, validate_step_ids, ArchiveFormat,
+    ReleaseState, Step, SymbolFormat, STEPS,
+};
+use crate::style;
+use crate::system::host_triple;
+use crate::target_dirs;
+use crate::utils::*;
+use chrono::Utc;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+pub async fn run(
+    resume: bool,
+    skip: Vec<String>,
+    project_dir: Option<PathBuf>,
+) -> OptimizerResult<()> {
+    let project_root = if let Some(dir) = project_dir {
+        dir
+    } else {
+        find_rust_project_root(".")?
+    };
+
+    if !is_rust_project(&project_root) {
+        return Err(OptimizerError::project_validation(
+            "No Cargo.toml found. Please run this command from a Rust project directory.",
+        ));
+    }
+    crate::lockfile::preflight(&project_root)?;
+    validate_step_ids(&skip)?;
+
+    let config = ProjectConfig::load(&project_root)?;
+    let head = command_stdout("git", &["rev-parse", "HEAD"], &project_root);
+    let mut state = ReleaseState::default();
+    if resume {
+        match ReleaseState::load(&project_root) {
+            Some(saved) if saved.head == head => state = saved,
+            Some(_) => print_warning("The commit changed since the last run; starting over"),
+            None => {
+                print_status("No unfinished release run to resume; starting from the beginning")
+            }
+        }
+    }
+    state.head = head;
+
+    reconcile_rustflags(&project_root);
+    print_status(&format!("Running {} release steps", STEPS.len()));
+    let start_time = Instant::now();
+    let mut outcomes = Vec::new();
+
+    for (index, &step) in STEPS.iter().enumerate() {
+        println!(
+            "{} {}",
+            style::info(&format!("[{}/{}]", index + 1, STEPS.len())),
+            style::strong(step.description())
+        );
+
+        let skipped = skip
+            .iter()
+            .chain(&config.release.skip)
+            .any(|id| id == step.id());
+        if skipped {
+            println!("   • Skipped");
+            outcomes.push((step, Outcome::Skipped("skipped".to_string())));
+            continue;
+        }
+        if let Some(&secs) = state.passed.get(step.id()) {
+            println!("   • Passed in the previous run");
+            outcomes.push((step, Outcome::Earlier(Duration::from_secs_f64(secs))));
+            continue;
+        }
+
+        let step_start = Instant::now();
+        let result = run_step(&project_root, &config, step);
+        let duration = step_start.elapsed();
+        match result {
+            Ok(StepDone::Passed(detail)) => {
+                state
+                    .passed
+                    .insert(step.id().to_string(), duration.as_secs_f64());
+                if let Err(e) = state.save(&project_root) {
+                    log::warn!("Could not save release progress: {}", e);
+                }
+                outcomes.push((step, Outcome::Passed(duration, detail)));
+            }
+            Ok(StepDone::Unavailable(reason)) => {
+                print_warning(&reason);
+                outcomes.push((step, Outcome::Skipped(reason)));
+            }
+            Err(e) => {
+                outcomes.push((step, Outcome::Failed(duration)));
+                print_release_summary(&outcomes, start_time.elapsed());
+                print_error(&format!("❌ {} failed: {}", step.description(), e));
+                if !matches!(e, OptimizerError::Cancelled) {
+                    println!(
+                        "   • Fix it and run `atlas release --resume` to continue from this step"
+                    );
+                }
+                return Err(e);
+            }
+        }
+    }
+
+    ReleaseState::clear(&project_root);
+    print_release_summary(&outcomes, start_time.elapsed());
+    print_success(&format!(
+        "✅ Release checks passed in {}",
+        format_duration(start_time.elapsed())
+    ));
+    Ok(())
+}
+
+enum StepDone {
+    /// Passed, with an optional one-line detail for the summary
+    Passed(Option<String>),
+    /// Could not run here, e.g. a tool is missing
+    Unavailable(String),
+}
+
+enum Outcome {
+    Passed(Duration, Option<String>),
+    Earlier(Duration),
+    Skipped(String),
+    Failed(Duration),
+}
+
+fn run_step(project_root: &Path, config: &ProjectConfig, step: Step) -> OptimizerResult<StepDone> {
+    let cargo = |args: &[&str]| {
+        let timeout = args.first().and_then(|command| config.timeout(command));
+        execute_command_with_timeout("cargo", args, Some(project_root), timeout)
+    };
+
+    match step {
+        Step::Fmt => return check_formatting(project_root, config),
+        Step::Audit => {
+            if !is_tool_available("cargo-audit") {
+                return Ok(StepDone::Unavailable(
+                    "cargo-audit is not installed; install it with `cargo install cargo-audit`"
+                        .to_string(),
+                ));
+            }
+            cargo(&["audit"])?
+        }
+        Step::Clippy => cargo(&[
+            "clippy",
+            "--workspace",
+            "--all-targets",
+            "--",
+            "-D",
+            "warnings",
+        ])?,
+        Step::Test => {
+            if is_tool_available("cargo-nextest") {
+                cargo(&["nextest", "run", "--workspace"])?
+            } else {
+                cargo(&["test", "--workspace"])?
+            }
+        }
+        Step::Doc => {
+            let (result, _) = run_cargo(&["doc", "--workspace", "--no-deps"], project_root, true);
+            result?
+        }
+        Step::Build => {
+            for target in release_targets(config) {
+                let target_arg = target.map(|target| format!("--target={}", target));
+                let mut args = vec!["build", "--workspace", "--release"];
+                args.extend(target_arg.as_deref());
+                if config.release.symbols_dir.is_some() {
+                    args.extend(symbol_build_args());
+                }
+                print_status(&format!("Building for {}", target.unwrap_or("the host")));
+                let (result, _) = run_cargo(&args, project_root, true);
+                result?;
+            }
+        }
+        Step::Sizes => return report_binary_sizes(project_root, config),
+        Step::Package => return package_artifacts(project_root, config),
+        Step::Changelog => return write_changelog_stub(project_root),
+    }
+    Ok(StepDone::Passed(None))
+}
+
+fn check_formatting(project_root: &Path, config: &ProjectConfig) -> OptimizerResult<StepDone> {
+    let report = crate::fmt::run(
+        project_root,
+        &["--workspace".to_string()],
+        true,
+        config.timeout("fmt"),
+    )?;
+    if !report.unformatted.is_empty() {
+        let files: Vec<String> = report
+            .unformatted
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        return Err(OptimizerError::command_failed(format!(
+            "{} of {} files are not formatted: {}",
+            files.len(),
+            report.files,
+            files.join(", ")
+        )));
+    }
+    Ok(StepDone::Passed(Some(format!(
+        "{} files formatted",
+        report.files
+    ))))
+}
+
+/// Configured targets, or `None` for the host
+fn release_targets(config: &ProjectConfig) -> Vec<Option<&str>> {
+    if config.release.targets.is_empty() {
+        vec![None]
+    } else {
+        config
+            .release
+            .targets
+            .iter()
+            .map(|target| Some(target.as_str()))
+            .collect()
+    }
+}
+
+fn report_binary_sizes(project_root: &Path, config: &ProjectConfig) -> OptimizerResult<StepDone> {
+    let target_directory = target_dirs::for_profile(
+        project_root,
+        "release",
+        &CargoMetadata::load(project_root)?.target_directory,
+    );
+    let mut total = 0;
+    let mut count = 0;
+    for target in release_targets(config) {
+        let profile_dir = match target {
+            Some(target) => target_directory.join(target).join("release"),
+            None => target_directory.join("release"),
+        };
+        for (name, size) in collect_binary_sizes(&profile_dir)? {
+            println!(
+                "   • {:<24} {:>10}  {}",
+                name,
+                format_bytes(size),
+                target.unwrap_or("host")
+            );
+            total += size;
+            count += 1;
+        }
+    }
+    if count == 0 {
+        println!("   • No binaries; the workspace only builds libraries");
+        return Ok(StepDone::Passed(None));
+    }
+    Ok(StepDone::Passed(Some(format!(
+        "{} {}, {}",
+        count,
+        if count == 1 { "binary" } else { "binaries" },
+        format_bytes(total)
+    ))))
+}
+
+/// Copy each binary of every release target into the dist directory, strip
+/// and compress the copies as configured, and write `SHA256SUMS` next to them
+fn package_artifacts(project_root: &Path, config: &ProjectConfig) -> OptimizerResult<StepDone> {
+    let release = &config.release;
+    let metadata = CargoMetadata::load(project_root)?;
+    let version = release_version(project_root, &metadata);
+    let target_directory =
+        target_dirs::for_profile(project_root, "release", &metadata.target_directory);
+    let dist_dir = release
+        .dist_dir
+        .as_ref()
+        .map(|dir| project_root.join(dir))
+        .unwrap_or_else(|| target_directory.join("dist"))
+        .join(&version);
+    if dist_dir.exists() {
+        fs::remove_dir_all(&dist_dir)?;
+    }
+    fs::create_dir_all(&dist_dir)?;
+    let staging = tempfile::tempdir()?;
+
+    let binaries: BTreeSet<&str> = metadata
+        .members()
+        .flat_map(|package| &package.targets)
+        .filter(|target| target.is_bin())
+        .map(|target| target.name.as_str())
+        .collect();
+    if release.upx && !is_tool_available("upx") {
+        print_warning("upx is not installed; packaging the binaries uncompressed");
+    }
+
+    let host = host_triple().unwrap_or_else(|| "host".to_string());
+    let symbols_dir = release
+        .symbols_dir
+        .as_ref()
+        .map(|dir| project_root.join(dir).join(&version));
+    let mut digests = BTreeMap::new();
+    let mut symbol_files = 0;
+    for target in release_targets(config) {
+        let (triple, profile_dir) = match target {
+            Some(target) => (
+                target.to_string(),
+                target_directory.join(target).join("release"),
+            ),
+            None => (host.clone(), target_directory.join("release")),
+        };
+        let windows = triple.contains("windows");
+        for &binary in &binaries {
+            let file_name = if windows {
+                format!("{}.exe", binary)
+            } else {
+                binary.to_string()
+            };
+            let built = profile_dir.join(&file_name);
+            if !built.exists() {
+                print_warning(&format!("{} was not built for {}", binary, triple));
+                continue;
+            }
+
+            let stage = staging.path().join(&triple);
+            fs::create_dir_all(&stage)?;
+            let copy = stage.join(&file_name);
+            fs::copy(&built, &copy)?;
+            let kept = match &symbols_dir {
+                Some(symbols_dir) => keep_symbols(
+                    &built,
+                    &profile_dir,
+                    binary,
+                    &triple,
+                    &symbols_dir.join(&triple),
+                    triple == host,
+                )?,
+                None => None,
+            };
+            if release.strip && !windows {
+                strip_binary(&copy, triple == host);
+            }
+            if let Some(kept) = kept {
+                if SymbolFormat::for_target(&triple) == SymbolFormat::Dwarf {
+                    add_debuglink(&copy, &kept, triple == host);
+                }
+                symbol_files += 1;
+            }
+            if release.upx && is_tool_available("upx") {
+                if let Err(e) =
+                    execute_command_with_output("upx", &["--best", "-q", path_arg(&copy)?], None)
+                {
+                    print_warning(&format!("upx failed on {}: {}", file_name, e));
+                }
+            }
+
+            let name = artifact_name(binary, &version, &triple, release.archive);
+            let artifact = dist_dir.join(&name);
+            match release.archive {
+                ArchiveFormat::TarGz => execute_command_with_output(
+                    "tar",
+                    &[
+                        "-czf",
+                        path_arg(&artifact)?,
+                        "-C",
+                        path_arg(&stage)?,
+                        &file_name,
+                    ],
+                    None,
+                )?,
+                ArchiveFormat::Zip => execute_command_with_output(
+                    "zip",
+                    &["-j", "-q", path_arg(&artifact)?, path_arg(&copy)?],
+                    None,
+                )?,
+                ArchiveFormat::None => {
+                    fs::copy(&copy, &artifact)?;
+                }
+            }
+            println!(
+                "   • {:<48} {:>10}",
+                name,
+                format_bytes(fs::metadata(&artifact)?.len())
+            );
+            digests.insert(name, sha256_file(&artifact)?);
+        }
+    }
+
+    if digests.is_empty() {
+        println!("   • No binaries to package");
+        return Ok(StepDone::Passed(None));
+    }
+    fs::write(dist_dir.join("SHA256SUMS"), sha256sums(&digests))?;
+    println!("   • Ready to upload: {}", dist_dir.display());
+    if let Some(symbols_dir) = &symbols_dir {
+        println!(
+            "   • Debug symbols for {} binar{}: {}",
+            symbol_files,
+            if symbol_files == 1 { "y" } else { "ies" },
+            symbols_dir.display()
+        );
+    }
+    Ok(StepDone::Passed(Some(format!(
+        "{} artifact{}",
+        digests.len(),
+        if digests.len() == 1 { "" } else { "s" }
+    ))))
+}
+
+/// Keep the debug symbols of `built` in `symbols_dir`: the dSYM bundle or PDB
+/// cargo left next to it, or its DWARF copied out with objcopy. Returns the
+/// kept file, or `None` with a warning when there was nothing to keep.
+fn keep_symbols(
+    built: &Path,
+    profile_dir: &Path,
+    binary: &str,
+    triple: &str,
+    symbols_dir: &Path,
+    host_binary: bool,
+) -> OptimizerResult<Option<PathBuf>> {
+    let format = SymbolFormat::for_target(triple);
+    let file_name = format.file_name(binary);
+    let kept = symbols_dir.join(&file_name);
+    fs::create_dir_all(symbols_dir)?;
+
+    match format {
+        SymbolFormat::Dsym | SymbolFormat::Pdb => {
+            let source = profile_dir.join(&file_name);
+            if !source.exists() {
+                print_warning(&format!(
+                    "No {} for {}; was it built by this release run?",
+                    file_name, triple
+                ));
+                return Ok(None);
+            }
+            copy_recursively(&source, &kept)?;
+        }
+        SymbolFormat::Dwarf => {
+            let Some(objcopy) = objcopy_tool(host_binary) else {
+                print_warning(&format!(
+                    "No objcopy for {}; install llvm-tools to keep its debug symbols",
+                    triple
+                ));
+                return Ok(None);
+            };
+            let output = execute_command(
+                objcopy,
+                &["--only-keep-debug", path_arg(built)?, path_arg(&kept)?],
+                None,
+            )?;
+            if !output.status.success() {
+                print_warning(&format!(
+                    "Could not split the debug symbols of {}: {}",
+                    binary,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ));
+                return Ok(None);
+            }
+        }
+    }
+    Ok(Some(kept))
+}
+
+/// Record the split-out DWARF file in the stripped copy, so debuggers that
+/// are pointed at the symbols directory find it
+fn add_debuglink(copy: &Path, debug_file: &Path, host_binary: bool) {
+    let Some(objcopy) = objcopy_tool(host_binary) else {
+        return;
+    };
+    let link = format!("--add-gnu-debuglink={}", debug_file.display());
+    let result = path_arg(copy).and_then(|copy| execute_command(objcopy, &[&link, copy], None));
+    match result {
+        Ok(output) if output.status.success() => {}
+        Ok(output) => print_warning(&format!(
+            "Could not link {} to its debug symbols: {}",
+            copy.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )),
+        Err(e) => print_warning(&format!(
+            "Could not link {} to its debug symbols: {}",
+            copy.display(),
+            e
+        )),
+    }
+}
+
+/// llvm-objcopy reads every target; the system objcopy only the host's
+fn objcopy_tool(host_binary: bool) -> Option<&'static str> {
+    if is_tool_available("llvm-objcopy") {
+        Some("llvm-objcopy")
+    } else if host_binary && is_tool_available("objcopy") {
+        Some("objcopy")
+    } else {
+        None
+    }
+}
+
+/// Copy a file, or a directory such as a dSYM bundle, replacing what is there
+fn copy_recursively(source: &Path, destination: &Path) -> OptimizerResult<()> {
+    if destination.is_dir() {
+        fs::remove_dir_all(destination)?;
+    }
+    if source.is_file() {
+        fs::copy(source, destination)?;
+        return Ok(());
+    }
+    for entry in walkdir::WalkDir::new(source) {
+        let entry = entry.map_err(|e| OptimizerError::Io(e.into()))?;
+        let relative = entry.path().strip_prefix(source).unwrap_or(entry.path());
+        let target = destination.join(relative);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Strip symbols, preferring llvm-strip, which handles every target. The
+/// system strip only knows the host's format. Failures leave the copy as is.
+fn strip_binary(path: &Path, host_binary: bool) {
+    let program = if is_tool_available("llvm-strip") {
+        "llvm-strip"
+    } else if host_binary && is_tool_available("strip") {
+        "strip"
+    } else {
+        log::debug!("No strip tool for {}", path.display());
+        return;
+    };
+    let result = path_arg(path)
+        .and_then(|path| execute_command(program, &[path], None))
+        .and_then(|output| {
+            if output.status.success() {
+                Ok(())
+            } else {
+                Err(OptimizerError::command_failed(
+                    String::from_utf8_lossy(&output.stderr).trim().to_string(),
+                ))
+            }
+        });
+    if let Err(e) = result {
+        print_warning(&format!("Could not strip {}: {}", path.display(), e));
+    }
+}
+
+fn path_arg(path: &Path) -> OptimizerResult<&str> {
+    path.to_str().ok_or_else(|| {
+        OptimizerError::invalid_input(format!("Path is not UTF-8: {}", path.display()))
+    })
+}
+
+/// Version of the root package, or of the first member in a virtual workspace
+fn release_version(project_root: &Path, metadata: &CargoMetadata) -> String {
+    let root_manifest = project_root.join("Cargo.toml");
+    metadata
+        .members()
+        .find(|package| package.manifest_path == root_manifest)
+        .or_else(|| metadata.members().next())
+        .map(|package| package.version.clone())
+        .unwrap_or_else(|| "Unreleased".to_string())
+}
+
+/// Write a changelog section for the next version from the commits since the
+/// last tag, for the maintainer to edit into CHANGELOG.md
+fn write_changelog_stub(project_root: &Path) -> OptimizerResult<StepDone> {
+    let version = release_version(project_root, &CargoMetadata::load(project_root)?);
+
+    let last_tag = command_stdout("git", &["describe", "--tags", "--abbrev=0"], project_root);
+    let range = last_tag
+        .as_ref()
+        .map(|tag| format!("{}..HEAD", tag))
+        .unwrap_or_else(|| "HEAD".to_string());
+    let subjects: Vec<String> =
+        command_stdout("git", &["log", &range, "--format=%s"], project_root)
+            .map(|log| log.lines().map(str::to_string).collect())
+            .unwrap_or_default();
+
+    let stub = changelog_stub(
+        &version,
+        &Utc::now().format("%Y-%m-%d").to_string(),
+        &subjects,
+    );
+    let path = project_root.join(".atlas").join("changelog-stub.md");
+    create_parent_dirs(&path)?;
+    fs::write(&path, &stub)?;
+    println!(
+        "   • Commits since {}: {}, written to {}",
+        last_tag.as_deref().unwrap_or("the first commit"),
+        subjects.len(),
+        path.display()
+    );
+    Ok(StepDone::Passed(Some(format!(
+        "{} commit{}",
+        subjects.len(),
+        if subjects.len() == 1 { "" } else { "s" }
+    ))))
+}
+
+fn print_release_summary(outcomes: &[(Step, Outcome)], total: Duration) {
+    println!();
+    println!("{}", style::title("⏱️  Release"));
+    for (step, outcome) in outcomes {
+        let (time, note) = match outcome {
+            Outcome::Passed(duration, detail) => (
+                format_duration(*duration),
+                detail
+                    .as_ref()
+                    .map(|detail| format!(" ({})", detail))
+                    .unwrap_or_default(),
+            ),
+            Outcome::Earlier(duration) => (
+                format_duration(*duration),
+                " (passed in the previous run)".to_string(),
+            ),
+            Outcome::Skipped(reason) => ("-".to_string(), format!(" ({})", reason)),
+            Outcome::Failed(duration) => (
+                format_duration(*duration),
+                style::bad(" (failed)").to_string(),
+            ),
+        };
+        println!("  {:>8}  {}{}", time, step.id(), note);
+    }
+    println!("  {:>8}  total", format_duration(total));
+    println!();
+}
@@ -0,0 +1,367 @@
+use crate::config::{OptimizerConfig, RemoteHost};
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::remote::{pinned_toolchain, rustc_release, HostProbe, RemoteBuild, BUILD_TOOLS};
+use crate::style;
+use crate::table::{Cell, Table};
+use crate::utils::*;
+use crate::RemoteCommands;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+pub async fn run(
+    remote_command: RemoteCommands,
+    project_dir: Option<PathBuf>,
+) -> OptimizerResult<()> {
+    let config = OptimizerConfig::load_or_default()?;
+    let project_root = find_rust_project_root(project_dir.unwrap_or_else(|| PathBuf::from(".")))
+        .and_then(|root| Ok(root.canonicalize()?));
+
+    match remote_command {
+        RemoteCommands::Build {
+            host,
+            release,
+            no_sync_back,
+            args,
+        } => {
+            let host = config.remote.host(host.as_deref())?;
+            let build = RemoteBuild::new(&host, &project_root?);
+            let mut cargo_args = vec!["build"];
+            if release {
+                cargo_args.push("--release");
+            }
+            cargo_args.extend(args.iter().map(String::as_str));
+            remote_build(&build, &host, &cargo_args, !no_sync_back)
+        }
+        RemoteCommands::Hosts => {
+            list_hosts(&config);
+            Ok(())
+        }
+        RemoteCommands::Doctor { host } => {
+            let host = config.remote.host(host.as_deref())?;
+            doctor(&host, project_root.ok().as_deref())
+        }
+    }
+}
+
+fn list_hosts(config: &OptimizerConfig) {
+    let remote = &config.remote;
+    println!("{}", style::title("🛰️  Remote Hosts"));
+    if remote.hosts.is_empty() && remote.host.is_none() {
+        print_status(
+            "No remote hosts configured; add one under [remote.hosts.<name>] in the Atlas config",
+        );
+        return;
+    }
+    let mut table = Table::new(&["Name", "Destination", "Path", "Jobs"]).right(3);
+    if let Some(address) = &remote.host {
+        table.add_row(vec![
+            Cell::styled("(unnamed)", style::dim),
+            address.as_str().into(),
+            remote.path.as_str().into(),
+            "-".into(),
+        ]);
+    }
+    for (name, host) in &remote.hosts {
+        let name = if remote.default.as_deref() == Some(name.as_str()) {
+            format!("{} (default)", name)
+        } else {
+            name.clone()
+        };
+        table.add_row(vec![
+            name.into(),
+            host.destination().into(),
+            host.path.as_deref().unwrap_or(&remote.path).into(),
+            host.jobs
+                .map(|jobs| jobs.to_string())
+                .unwrap_or_else(|| "-".to_string())
+                .into(),
+        ]);
+    }
+    table.print();
+}
+
+/// Check a host can build like this machine before it is used: SSH access,
+/// rustup, the toolchain, rsync, the projects directory and build tools
+fn doctor(host: &RemoteHost, project_root: Option<&Path>) -> OptimizerResult<()> {
+    if !is_tool_available("ssh") {
+        return Err(OptimizerError::tool_not_found("ssh"));
+    }
+    let destination = host.destination();
+    println!("{}", style::title("🩺 Remote Host Check"));
+    println!("   • {}", style::code(&destination));
+    println!();
+
+    let local_tools: Vec<&str> = BUILD_TOOLS
+        .iter()
+        .copied()
+        .filter(|tool| is_tool_available(tool))
+        .collect();
+    let path = host.path.clone().unwrap_or_default();
+    let script = HostProbe::script(&path, &local_tools);
+    let output = execute_command(
+        "ssh",
+        &[
+            "-o",
+            "BatchMode=yes",
+            "-o",
+            "ConnectTimeout=10",
+            destination.as_str(),
+            script.as_str(),
+        ],
+        None,
+    )?;
+    if output.status.code() == Some(255) {
+        return Err(OptimizerError::network(format!(
+            "Could not connect to {}: {}",
+            destination,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    let probe = HostProbe::parse(&String::from_utf8_lossy(&output.stdout));
+
+    let local_rustc = command_stdout(
+        "rustc",
+        &["--version"],
+        project_root.unwrap_or_else(|| Path::new(".")),
+    );
+    let mut problems = 0;
+    let mut warnings = 0;
+    let mut table = Table::new(&["Check", "Here", "Host", "Result"]);
+    let mut row = |check: &str, here: String, remote: String, result: Outcome| {
+        let cell = match result {
+            Outcome::Ok => Cell::styled("ok", style::good),
+            Outcome::Warning(text) => {
+                warnings += 1;
+                Cell::styled(text, style::caution)
+            }
+            Outcome::Problem(text) => {
+                problems += 1;
+                Cell::styled(text, style::bad)
+            }
+        };
+        table.add_row(vec![check.into(), here.into(), remote.into(), cell]);
+    };
+    let show = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+
+    row(
+        "rustup",
+        "-".to_string(),
+        show(&probe.rustup),
+        if probe.rustup.is_some() {
+            Outcome::Ok
+        } else {
+            Outcome::Problem("missing")
+        },
+    );
+    let rustc = match (&probe.rustc, &local_rustc) {
+        (None, _) => Outcome::Problem("missing"),
+        (Some(remote), Some(local)) if rustc_release(remote) != rustc_release(local) => {
+            Outcome::Warning("different release")
+        }
+        _ => Outcome::Ok,
+    };
+    row("rustc", show(&local_rustc), show(&probe.rustc), rustc);
+    row(
+        "cargo",
+        "-".to_string(),
+        show(&probe.cargo),
+        if probe.cargo.is_some() {
+            Outcome::Ok
+        } else {
+            Outcome::Problem("missing")
+        },
+    );
+    if let Some(channel) = project_root.and_then(pinned_toolchain) {
+        let installed = probe.has_toolchain(&channel);
+        row(
+            "pinned toolchain",
+            channel,
+            if installed { "installed" } else { "-" }.to_string(),
+            if installed {
+                Outcome::Ok
+            } else {
+                Outcome::Warning("rustup installs it on first build")
+            },
+        );
+    }
+    row(
+        "rsync",
+        yes_no(is_tool_available("rsync")),
+        yes_no(probe.rsync),
+        match (is_tool_available("rsync"), probe.rsync) {
+            (true, true) => Outcome::Ok,
+            _ => Outcome::Problem("needed on both sides"),
+        },
+    );
+    row(
+        "projects directory",
+        "-".to_string(),
+        path.clone(),
+        if probe.path_writable {
+            Outcome::Ok
+        } else {
+            Outcome::Problem("not writable")
+        },
+    );
+    for tool in &local_tools {
+        let present = probe.tools.iter().any(|found| found == tool);
+        row(
+            tool,
+            yes_no(true),
+            yes_no(present),
+            if present {
+                Outcome::Ok
+            } else {
+                Outcome::Warning("missing, builds may fail or run slower")
+            },
+        );
+    }
+    table.print();
+
+    println!();
+    if problems > 0 {
+        return Err(OptimizerError::config(format!(
+            "{} is not ready for remote builds: {} problem(s)",
+            destination, problems
+        )));
+    }
+    if warnings > 0 {
+        print_warning(&format!(
+            "{} can build, with {} difference(s) from this machine",
+            destination, warnings
+        ));
+    } else {
+        print_success(&format!("{} is ready for remote builds", destination));
+    }
+    Ok(())
+}
+
+enum Outcome {
+    Ok,
+    Warning(&'static str),
+    Problem(&'static str),
+}
+
+fn yes_no(value: bool) -> String {
+    if value { "yes" } else { "no" }.to_string()
+}
+
+fn remote_build(
+    build: &RemoteBuild,
+    host: &RemoteHost,
+    cargo_args: &[&str],
+    sync_back: bool,
+) -> OptimizerResult<()> {
+    for tool in ["rsync", "ssh"] {
+        if !is_tool_available(tool) {
+            return Err(OptimizerError::tool_not_found(tool));
+        }
+    }
+    let cargo_args = crate::lockfile::with_flag(crate::lockfile::mode(), cargo_args);
+
+    println!("{}", style::title("🛰️  Remote Build"));
+    println!(
+        "   • {}:{}",
+        style::code(&build.destination),
+        build.remote_dir
+    );
+    println!();
+
+    let start = Instant::now();
+    print_status("Syncing workspace");
+    let upload = build.upload_args();
+    let upload: Vec<&str> = upload.iter().map(String::as_str).collect();
+    let output = execute_command("rsync", &upload, None)?;
+    if !output.status.success() {
+        return Err(OptimizerError::command_failed(format!(
+            "Copying the workspace to {} failed: {}",
+            build.destination,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    print_success(&format!("Synced in {}", format_duration(start.elapsed())));
+
+    if host.check_toolchain {
+        check_toolchain(build);
+    }
+
+    let remote_command = build.remote_command(&cargo_args);
+    print_status(&format!(
+        "Running cargo {} on {}",
+        cargo_args.join(" "),
+        build.destination
+    ));
+    let build_start = Instant::now();
+    execute_command_streaming(
+        "ssh",
+        &[build.destination.as_str(), remote_command.as_str()],
+        None,
+        None,
+        false,
+        |_, line| println!("{}", line),
+    )
+    .map_err(|e| match e {
+        OptimizerError::CommandFailed(_) => {
+            OptimizerError::build_failed(format!("Remote build on {} failed", build.destination))
+        }
+        other => other,
+    })?;
+    print_success(&format!(
+        "Built remotely in {}",
+        format_duration(build_start.elapsed())
+    ));
+
+    if sync_back {
+        let local = build.local_artifacts(&cargo_args);
+        std::fs::create_dir_all(&local)?;
+        let download = build.download_args(&cargo_args);
+        let download: Vec<&str> = download.iter().map(String::as_str).collect();
+        let output = execute_command("rsync", &download, None)?;
+        if !output.status.success() {
+            return Err(OptimizerError::command_failed(format!(
+                "Copying the artifacts back from {} failed: {}",
+                build.destination,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        print_success(&format!(
+            "Artifacts copied to {}",
+            style::code(&local.display().to_string())
+        ));
+    }
+
+    println!();
+    println!(
+        "{}",
+        style::text(&format!(
+            "✅ Remote build finished in {}",
+            format_duration(start.elapsed())
+        ))
+    );
+    Ok(())
+}
+
+/// Warn when the host would build with a different rustc release than this
+/// machine; the synced toolchain file applies on both sides
+fn check_toolchain(build: &RemoteBuild) {
+    let local = command_stdout("rustc", &["--version"], &build.project_root);
+    let remote = execute_command(
+        "ssh",
+        &[
+            build.destination.as_str(),
+            &build.shell("rustc", &["--version"]),
+        ],
+        None,
+    )
+    .ok()
+    .filter(|output| output.status.success())
+    .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string());
+    if let (Some(local), Some(remote)) = (local, remote) {
+        if rustc_release(&local) != rustc_release(&remote) {
+            print_warning(&format!(
+                "{} builds with {}, this machine with {}",
+                build.destination, remote, local
+            ));
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use crate::commands::build::run_cargo;
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::project_config::{ProjectConfig, Task, PROJECT_CONFIG_FILE};
+use crate::style;
+use crate::utils::*;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Cargo subcommands that accept `--message-format` and report compilation stats
+const CARGO_STATS_SUBCOMMANDS: &[&str] = &["build", "check", "clippy", "test", "bench", "doc"];
+
+pub async fn run(task: Option<String>, project_dir: Option<PathBuf>) -> OptimizerResult<()> {
+    let project_root = if let Some(dir) = project_dir {
+        dir
+    } else {
+        find_rust_project_root(".")?
+    };
+
+    if !is_rust_project(&project_root) {
+        return Err(OptimizerError::project_validation(
+            "No Cargo.toml found. Please run this command from a Rust project directory.",
+        ));
+    }
+
+    let config = ProjectConfig::load(&project_root)?;
+    match task {
+        Some(name) => run_task(&project_root, &name, config.task(&name)?),
+        None => {
+            list_tasks(&config);
+            Ok(())
+        }
+    }
+}
+
+fn list_tasks(config: &ProjectConfig) {
+    if config.tasks.is_empty() {
+        print_warning(&format!(
+            "No tasks defined. Add a [tasks] section to {}",
+            PROJECT_CONFIG_FILE
+        ));
+        return;
+    }
+
+    println!("{}", style::title("📋 Tasks"));
+    for (name, task) in &config.tasks {
+        let summary = task
+            .description()
+            .map(str::to_string)
+            .unwrap_or_else(|| task.steps().join(" && "));
+        println!("  {:<16} {}", style::code(name), summary);
+    }
+}
+
+struct StepResult {
+    command: String,
+    duration: Duration,
+    detail: Option<String>,
+}
+
+fn run_task(project_root: &Path, name: &str, task: &Task) -> OptimizerResult<()> {
+    let steps = task.steps();
+    print_status(&format!("Running task '{}' ({} steps)", name, steps.len()));
+    crate::commands::build::reconcile_rustflags(project_root);
+
+    let start_time = Instant::now();
+    let mut results = Vec::new();
+
+    for (index, step) in steps.iter().enumerate() {
+        println!(
+            "{} {}",
+            style::info(&format!("[{}/{}]", index + 1, steps.len())),
+            style::strong(step)
+        );
+
+        let step_start = Instant::now();
+        let (result, detail) = run_step(project_root, step)?;
+        results.push(StepResult {
+            command: step.to_string(),
+            duration: step_start.elapsed(),
+            detail,
+        });
+
+        if let Err(e) = result {
+            print_task_summary(name, &results, start_time.elapsed());
+            print_error(&format!(
+                "❌ Task '{}' failed at step {}: {}",
+                name,
+                index + 1,
+                e
+            ));
+            return Err(e);
+        }
+    }
+
+    print_task_summary(name, &results, start_time.elapsed());
+    print_success(&format!(
+        "✅ Task '{}' completed in {}",
+        name,
+        format_duration(start_time.elapsed())
+    ));
+    Ok(())
+}
+
+/// Run one step; cargo builds go through the stats-collecting runner
+fn run_step(
+    project_root: &Path,
+    step: &str,
+) -> OptimizerResult<(OptimizerResult<()>, Option<String>)> {
+    let argv = split_command_line(step)?;
+    let args: Vec<&str> = argv[1..].iter().map(String::as_str).collect();
+
+    let collects_stats = argv[0] == "cargo"
+        && args
+            .first()
+            .is_some_and(|subcommand| CARGO_STATS_SUBCOMMANDS.contains(subcommand))
+        && !args.iter().any(|arg| arg.starts_with("--message-format"));
+
+    if !collects_stats {
+        return Ok((
+            execute_command_with_output(&argv[0], &args, Some(project_root)),
+            None,
+        ));
+    }
+
+    let (result, run_stats) = run_cargo(&args, project_root, true);
+    let detail = run_stats
+        .and_then(|stats| stats.compilation)
+        .map(|compilation| {
+            format!(
+                "{} compiled, {} fresh, {} warnings",
+                compilation.crates_compiled, compilation.crates_fresh, compilation.warnings
+            )
+        });
+    Ok((result, detail))
+}
+
+fn print_task_summary(name: &str, results: &[StepResult], total: Duration) {
+    println!();
+    println!("{}", style::title(&format!("⏱️  Task '{}'", name)));
+    for result in results {
+        println!(
+            "  {:>8}  {}{}",
+            format_duration(result.duration),
+            result.command,
+            result
+                .detail
+                .as_ref()
+                .map(|detail| format!(" ({})", detail))
+                .unwrap_or_default()
+        );
+    }
+    println!("  {:>8}  total", format_duration(total));
+    println!();
+}
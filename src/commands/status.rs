@@ -1,92 +1,349 @@
+use crate::build_system::ForeignBuild;
+use crate::capabilities::{self, is_supported_platform, Support};
+use crate::commands::doctor::print_findings;
+use crate::config::OptimizerConfig;
+use crate::contradictions::Sources;
 use crate::error::OptimizerResult;
-use crate::system::SystemInfo;
+use crate::i18n::{t, t_with};
+use crate::managed::{generated_blocks, Staleness, Stamp};
+use crate::redact;
+use crate::rules::{self, Context};
+use crate::rustflags::{Conflict, RustflagsLayers};
+use crate::schema::StatusReport;
+use crate::stats::StatsStore;
+use crate::style;
+use crate::system::{host_triple, SystemInfo};
+use crate::table::{Cell, Table};
+use crate::trends;
 use crate::utils::*;
-use colored::*;
-use std::path::PathBuf;
+use chrono::{Duration as ChronoDuration, Utc};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 pub async fn run(detailed: bool, json: bool, project_dir: Option<PathBuf>) -> OptimizerResult<()> {
-    let _project_root = if let Some(dir) = project_dir {
+    let project_root = if let Some(dir) = project_dir {
         dir
     } else {
         find_rust_project_root(".")?
     };
 
     let system_info = SystemInfo::detect()?;
+    let conflicts = rustflags_conflicts(&project_root);
+    let stale = stale_generated_files(&project_root);
+    let foreign = ForeignBuild::detect(&project_root).ok().flatten();
+    let context = Context {
+        project_root: &project_root,
+        system_info: &system_info,
+    };
+    let (findings, suppressed) = rules::evaluate(&context).unwrap_or_else(|e| {
+        log::warn!("Could not evaluate recommendations: {}", e);
+        (Vec::new(), 0)
+    });
 
     if json {
-        let status = serde_json::json!({
-            "system": {
-                "os": system_info.os.to_string(),
-                "arch": system_info.arch.to_string(),
-                "cpu_cores": system_info.cpu_cores,
-                "rust_version": system_info.rust_version,
-                "cargo_version": system_info.cargo_version
-            },
-            "tools": system_info.available_tools
-        });
-        println!("{}", serde_json::to_string_pretty(&status)?);
+        let status = StatusReport::new(
+            &system_info,
+            foreign.as_ref().map(|foreign| foreign.system.to_string()),
+            &conflicts,
+            &stale,
+            &findings,
+            suppressed,
+        );
+        println!("{}", redact::text(&serde_json::to_string_pretty(&status)?));
     } else {
-        print_status_overview(&system_info, detailed);
+        print_status_overview(&system_info, foreign.as_ref(), &conflicts, &stale, detailed);
+        if detailed {
+            print_incremental(&project_root);
+        }
+        print_findings(&findings, suppressed);
+        println!();
+        println!("{}", style::text(t("status.completed")));
     }
 
     Ok(())
 }
 
-fn print_status_overview(system_info: &SystemInfo, detailed: bool) {
-    println!("{}", "🚀 Rust Build Optimizer Status".bright_blue().bold());
+/// Build times, crates compiled and cache hits of the project's last `weeks`
+/// weeks, per kind of build
+pub async fn history(weeks: u32, json: bool, project_dir: Option<PathBuf>) -> OptimizerResult<()> {
+    let project_root = if let Some(dir) = project_dir {
+        dir
+    } else {
+        find_rust_project_root(".")?
+    };
+    let project = std::fs::canonicalize(&project_root).unwrap_or(project_root);
+    let since = Utc::now() - ChronoDuration::weeks(i64::from(weeks));
+    let records: Vec<_> = StatsStore::load_since(since)?
+        .into_iter()
+        .filter(|record| record.project == project)
+        .collect();
+    let trends = trends::weekly(&records);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&trends)?);
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        style::title(&t_with("status.history_title", &[("weeks", &weeks)]))
+    );
+    if trends.is_empty() {
+        println!(
+            "   • {}",
+            t_with(
+                "status.history_empty",
+                &[
+                    ("build", &style::code("atlas build")),
+                    ("check", &style::code("atlas check"))
+                ]
+            )
+        );
+        return Ok(());
+    }
+    for trend in &trends {
+        println!();
+        println!("{}", style::section(&trend.kind));
+        let mut table = Table::new(&[
+            t("status.column_week"),
+            t("status.column_builds"),
+            t("status.column_median"),
+            t("status.column_compiled"),
+            t("status.column_cold"),
+            t("status.column_sccache"),
+        ])
+        .right(1)
+        .right(2)
+        .right(3)
+        .right(4)
+        .right(5);
+        for week in &trend.weeks {
+            let runs = if week.failed > 0 {
+                format!("{} ({} failed)", week.builds, week.failed)
+            } else {
+                week.builds.to_string()
+            };
+            table.add_row(vec![
+                week.week.to_string().into(),
+                runs.into(),
+                week.median_secs
+                    .map(|secs| format_duration(Duration::from_secs_f64(secs)))
+                    .unwrap_or_else(|| "-".to_string())
+                    .into(),
+                format!("{:.0}", week.crates_compiled).into(),
+                week.cold_builds.to_string().into(),
+                week.sccache_hit_rate
+                    .map(|rate| format!("{:.0}%", rate))
+                    .unwrap_or_else(|| "-".to_string())
+                    .into(),
+            ]);
+        }
+        table.print();
+        if let (Some(change), Some(first)) = (trend.change(), trend.weeks.first()) {
+            let args: [(&str, &dyn std::fmt::Display); 3] = [
+                ("kind", &trend.kind),
+                ("percent", &format!("{:.0}", change.abs())),
+                ("week", &first.week),
+            ];
+            if change <= -1.0 {
+                println!(
+                    "   • {}",
+                    style::good(&t_with("status.history_faster", &args))
+                );
+            } else if change >= 1.0 {
+                println!(
+                    "   • {}",
+                    style::bad(&t_with("status.history_slower", &args))
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// What works on this platform and what does not
+pub(crate) fn print_capabilities(system_info: &SystemInfo) {
+    let mut table = Table::new(&[t("status.column_capability"), t("status.column_support")]);
+    for capability in capabilities::matrix(system_info) {
+        let support = match &capability.support {
+            Support::Full => Cell::styled(t("status.supported"), style::good),
+            Support::Partial(reason) => Cell::styled(
+                t_with("status.partially_supported", &[("reason", reason)]),
+                style::caution,
+            ),
+            Support::Unavailable(reason) => Cell::styled(
+                t_with("status.unavailable", &[("reason", reason)]),
+                style::bad,
+            ),
+        };
+        table.add_row(vec![capability.name.into(), support]);
+    }
+    table.print();
+}
+
+pub(crate) fn rustflags_conflicts(project_root: &Path) -> Vec<Conflict> {
+    match RustflagsLayers::detect(project_root, host_triple().as_deref()) {
+        Ok(layers) => layers.conflicts(),
+        Err(e) => {
+            log::warn!("Could not read rustflags from Cargo config: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Whether each profile builds incrementally, and which setting decides it
+fn print_incremental(project_root: &Path) {
+    let sources = OptimizerConfig::load_or_default()
+        .and_then(|config| Sources::detect(project_root, &config, host_triple()));
+    let sources = match sources {
+        Ok(sources) => sources,
+        Err(e) => {
+            log::warn!("Could not read the incremental settings: {}", e);
+            return;
+        }
+    };
+
+    println!("{}", style::section(t("status.incremental")));
+    let mut table = Table::new(&[
+        t("status.column_profile"),
+        t("status.column_incremental"),
+        t("status.column_set_by"),
+    ]);
+    for profile in sources.profiles() {
+        let effective = sources.incremental_for(&profile);
+        table.add_row(vec![
+            Cell::styled(profile.as_str(), style::code),
+            if effective.enabled {
+                Cell::styled(t("status.on"), style::good)
+            } else {
+                Cell::styled(t("status.off"), style::dim)
+            },
+            match effective.origin {
+                Some(origin) => origin.to_string().into(),
+                None => Cell::styled(t("status.cargo_default"), style::dim),
+            },
+        ]);
+    }
+    table.print();
+    println!();
+}
+
+/// Generated files that no longer match the installed Atlas or its configuration
+pub fn stale_generated_files(project_root: &Path) -> Vec<(PathBuf, Staleness)> {
+    let stale = || -> OptimizerResult<Vec<(PathBuf, Staleness)>> {
+        let current = Stamp::current(&OptimizerConfig::load_or_default()?)?;
+        Ok(generated_blocks(project_root)?
+            .into_iter()
+            .filter_map(|(path, block)| Some((path, block.staleness(&current)?)))
+            .collect())
+    };
+    stale().unwrap_or_else(|e| {
+        log::warn!("Could not check generated files: {}", e);
+        Vec::new()
+    })
+}
+
+fn print_status_overview(
+    system_info: &SystemInfo,
+    foreign: Option<&ForeignBuild>,
+    conflicts: &[Conflict],
+    stale: &[(PathBuf, Staleness)],
+    detailed: bool,
+) {
+    println!("{}", style::title(t("status.title")));
     println!();
 
     // System information
-    println!("{}", "💻 System Information".bright_green().bold());
-    println!("  OS: {} {}", system_info.os, system_info.arch);
-    println!("  CPU Cores: {}", system_info.cpu_cores);
+    println!("{}", style::section(t("status.system")));
+    println!(
+        "  {}: {} {}",
+        t("status.os"),
+        system_info.os,
+        system_info.arch
+    );
+    println!("  {}: {}", t("status.cpu_cores"), system_info.cpu_cores);
+    println!("  {}: {}", t("status.power"), system_info.power);
     if let Some(ref rust_version) = system_info.rust_version {
         println!("  Rust: {}", rust_version);
     }
     if let Some(ref cargo_version) = system_info.cargo_version {
         println!("  Cargo: {}", cargo_version);
     }
+    if let Some(ref components) = system_info.rustup_components {
+        println!(
+            "  {}: {}",
+            t("status.rustup_components"),
+            components.join(", ")
+        );
+    }
+    if let Some(foreign) = foreign {
+        println!(
+            "  {}",
+            t_with("status.build_system", &[("system", &foreign.system)])
+        );
+    }
     println!();
 
+    // Parts of Atlas that do not work on this platform
+    if !is_supported_platform(&system_info.os) {
+        println!("{}", style::attention(t("status.capabilities")));
+        print_capabilities(system_info);
+        println!();
+    }
+
     // Tool status
-    println!("{}", "🛠️  Tool Status".bright_green().bold());
-    for tool in &system_info.available_tools {
+    println!("{}", style::section(t("status.tools")));
+    let mut table = if detailed {
+        Table::new(&[
+            t("status.column_tool"),
+            t("status.column_status"),
+            t("status.column_version"),
+        ])
+    } else {
+        Table::new(&[t("status.column_tool"), t("status.column_status")])
+    };
+    for tool in system_info.available_tools.all() {
         let status = if tool.is_installed {
-            "✅ Installed".bright_green()
+            Cell::styled(t("status.installed"), style::good)
         } else {
-            "❌ Not installed".bright_red()
+            Cell::styled(t("status.not_installed"), style::bad)
         };
-
-        if detailed && tool.is_installed {
-            if let Some(ref version) = tool.version {
-                println!("  {} - {} ({})", tool.name.bright_cyan(), status, version);
-            } else {
-                println!("  {} - {}", tool.name.bright_cyan(), status);
-            }
-        } else {
-            println!("  {} - {}", tool.name.bright_cyan(), status);
+        let mut row = vec![Cell::styled(tool.name.as_str(), style::code), status];
+        if detailed {
+            row.push(tool.version.as_deref().unwrap_or("").into());
         }
+        table.add_row(row);
     }
+    table.print();
     println!();
 
-    // Recommendations
-    let missing_tools: Vec<_> = system_info
-        .available_tools
-        .iter()
-        .filter(|tool| !tool.is_installed)
-        .collect();
+    // Rustflags sources Cargo ignores
+    if !conflicts.is_empty() {
+        println!("{}", style::attention(t("status.rustflags_conflicts")));
+        for conflict in conflicts {
+            println!("  {}", conflict.explanation());
+            for source in conflict.ignored() {
+                println!("    • {}: {}", source.origin, source.flags.join(" "));
+            }
+        }
+        println!("  {}", t("status.rustflags_precedence"));
+        println!("  {}", t("status.rustflags_combined"));
+        println!();
+    }
 
-    if !missing_tools.is_empty() {
-        println!("{}", "💡 Recommendations".bright_yellow().bold());
+    // Generated content that predates the installed Atlas or the current config
+    if !stale.is_empty() {
+        println!("{}", style::attention(t("status.outdated")));
+        for (path, staleness) in stale {
+            println!("  {}: {}", path.display(), staleness);
+        }
         println!(
-            "  Install missing tools with: {}",
-            "rust-build-optimizer install-tools".bright_cyan()
+            "  {}",
+            t_with(
+                "status.regenerate",
+                &[("command", &style::code("atlas refresh"))]
+            )
         );
-        for tool in missing_tools {
-            println!("    • {}", tool.name);
-        }
         println!();
     }
-
-    println!("🎉 Status check completed!");
 }
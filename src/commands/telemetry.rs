@@ -0,0 +1,140 @@
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::style;
+use crate::system::SystemInfo;
+use crate::telemetry::{enabled, set_enabled, Report, TelemetryStore};
+use crate::utils::*;
+use crate::TelemetryCommands;
+
+/// Environment variable naming the endpoint `submit` posts to
+const ENDPOINT_VARIABLE: &str = "ATLAS_TELEMETRY_ENDPOINT";
+
+pub async fn run(telemetry_command: TelemetryCommands) -> OptimizerResult<()> {
+    match telemetry_command {
+        TelemetryCommands::Status => status(),
+        TelemetryCommands::On => {
+            set_enabled(true)?;
+            print_success("Usage statistics are on. Baseline comparisons are recorded locally");
+            Ok(())
+        }
+        TelemetryCommands::Off => {
+            set_enabled(false)?;
+            print_success("Usage statistics are off. Nothing more is recorded");
+            println!(
+                "   Delete what was recorded with {}",
+                style::code("atlas telemetry purge")
+            );
+            Ok(())
+        }
+        TelemetryCommands::Purge => {
+            if TelemetryStore::purge()? {
+                print_success("Deleted all recorded usage statistics");
+            } else {
+                print_status("No usage statistics recorded");
+            }
+            Ok(())
+        }
+        TelemetryCommands::Submit { endpoint } => submit(endpoint),
+    }
+}
+
+fn status() -> OptimizerResult<()> {
+    let store = TelemetryStore::load()?;
+    println!("{}", style::title("📈 Usage Statistics"));
+    println!(
+        "  Consent: {}",
+        if enabled() {
+            style::good("on")
+        } else {
+            style::caution("off")
+        }
+    );
+    println!(
+        "  Recorded comparisons: {} ({})",
+        store.observations.len(),
+        TelemetryStore::path()?.display()
+    );
+
+    let aggregates = store.aggregate();
+    if !aggregates.is_empty() {
+        println!();
+        println!(
+            "{}",
+            style::strong("  Measured effect of single setting changes")
+        );
+        for aggregate in aggregates {
+            let changes: Vec<String> = aggregate
+                .mean_changes
+                .iter()
+                .map(|(label, change)| format!("{} {:+.1}%", label, change))
+                .collect();
+            println!(
+                "   • {} ({} samples): {}",
+                style::code(&aggregate.setting),
+                aggregate.samples,
+                changes.join(", ")
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Send the per-setting aggregates, after showing exactly what is sent
+fn submit(endpoint: Option<String>) -> OptimizerResult<()> {
+    if !enabled() {
+        return Err(OptimizerError::invalid_input(
+            "Usage statistics are off. Turn them on with `atlas telemetry on` first",
+        ));
+    }
+    let endpoint = endpoint
+        .or_else(|| std::env::var(ENDPOINT_VARIABLE).ok())
+        .ok_or_else(|| {
+            OptimizerError::invalid_input(format!(
+                "No endpoint to submit to. Pass --endpoint or set {}",
+                ENDPOINT_VARIABLE
+            ))
+        })?;
+
+    let store = TelemetryStore::load()?;
+    let system_info = SystemInfo::detect()?;
+    let report = Report {
+        atlas_version: env!("CARGO_PKG_VERSION").to_string(),
+        os: system_info.os.to_string(),
+        arch: system_info.arch.to_string(),
+        settings: store.aggregate(),
+    };
+    if report.settings.is_empty() {
+        print_status("Nothing to submit yet: no comparison changed a single setting");
+        return Ok(());
+    }
+
+    let payload = serde_json::to_string_pretty(&report)?;
+    println!("{}", payload);
+    if !confirm(&format!("Send this to {}?", endpoint))? {
+        return Err(OptimizerError::Cancelled);
+    }
+
+    let output = execute_command(
+        "curl",
+        &[
+            "--fail",
+            "--silent",
+            "--show-error",
+            "-X",
+            "POST",
+            "-H",
+            "Content-Type: application/json",
+            "--data",
+            &payload,
+            &endpoint,
+        ],
+        None,
+    )?;
+    if !output.status.success() {
+        return Err(OptimizerError::command_failed(format!(
+            "Submitting usage statistics failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    print_success("Thanks! Usage statistics submitted");
+    Ok(())
+}
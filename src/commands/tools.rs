@@ -1,11 +1,19 @@
+use crate::bundle::parse_version;
+use crate::config::OptimizerConfig;
 use crate::error::{OptimizerError, OptimizerResult};
-use crate::system::SystemInfo;
+use crate::host::{CommandRunner, Host, PlanningHost, SystemProbe};
+use crate::style;
+use crate::system::{rustup_component, AvailableTool, SystemInfo};
+use crate::table::{Cell, Table};
 use crate::utils::*;
-use colored::*;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
-pub async fn run(list: bool, only: Option<Vec<String>>) -> OptimizerResult<()> {
-    let system_info = SystemInfo::detect()?;
+pub async fn run(
+    list: bool,
+    only: Option<Vec<String>>,
+    print_commands: bool,
+) -> OptimizerResult<()> {
+    let system_info = SystemInfo::refresh()?;
 
     if list {
         list_available_tools(&system_info).await?;
@@ -18,24 +26,144 @@ pub async fn run(list: bool, only: Option<Vec<String>>) -> OptimizerResult<()> {
         get_recommended_tools(&system_info)
     };
 
+    if print_commands {
+        return print_install_commands(&tools_to_install).await;
+    }
     install_tools(&tools_to_install).await?;
 
     Ok(())
 }
 
+/// Print what installing `tools` would run, as a shell script, without
+/// running any of it
+async fn print_install_commands(tools: &[String]) -> OptimizerResult<()> {
+    let host = PlanningHost::default();
+    let system_info = SystemInfo::detect_with(&host);
+    println!("# Commands `atlas install-tools` would run on this machine");
+    for tool in tools {
+        println!("\n# {}", tool);
+        if system_info.is_tool_installed(tool) {
+            println!("# already installed");
+            continue;
+        }
+        let result = install_single_tool(tool, &system_info, &host).await;
+        let planned = host.take();
+        if let Err(e) = result {
+            println!("# cannot be installed here: {}", e);
+            continue;
+        }
+        for line in planned {
+            let line: Vec<String> = line.iter().map(|arg| shell_quote(arg)).collect();
+            println!("{}", line.join(" "));
+        }
+    }
+    Ok(())
+}
+
+/// Command lines installing the missing ones of `tools` would run; tools
+/// that cannot be installed here are left out
+pub(crate) async fn planned_install_commands(tools: &[String]) -> Vec<Vec<String>> {
+    let host = PlanningHost::default();
+    let system_info = SystemInfo::detect_with(&host);
+    let mut planned = Vec::new();
+    for tool in tools {
+        if system_info.is_tool_installed(tool) {
+            continue;
+        }
+        if install_single_tool(tool, &system_info, &host).await.is_ok() {
+            planned.extend(host.take());
+        } else {
+            host.take();
+        }
+    }
+    planned
+}
+
+/// `name` as installed on this machine. A missing tool is installed first
+/// when `auto_install` under `[tools]` allows it; otherwise the error says
+/// how to install it.
+pub async fn ensure_tool(name: &str) -> OptimizerResult<AvailableTool> {
+    // A program Atlas may not start cannot be found or used either
+    crate::allowlist::check(name)?;
+    let system_info = SystemInfo::detect()?;
+    if let Some(tool) = system_info.get_tool(name).filter(|tool| tool.is_installed) {
+        return Ok(tool);
+    }
+    if !OptimizerConfig::load_or_default()?.tools.auto_install {
+        return Err(OptimizerError::tool_not_found(format!(
+            "{} (install with: atlas install-tools --only {})",
+            name, name
+        )));
+    }
+
+    print_status(&format!(
+        "{} is needed but not installed; installing it (set auto_install = false under [tools] to stop this)",
+        style::code(name)
+    ));
+    if let Some(Err(e)) = install_tools_with(&[name.to_string()], &Host)
+        .await
+        .remove(name)
+    {
+        return Err(e);
+    }
+    system_info.available_tools.forget(name);
+    system_info
+        .get_tool(name)
+        .filter(|tool| tool.is_installed)
+        .ok_or_else(|| {
+            OptimizerError::tool_not_found(format!("{} (installed, but not on PATH)", name))
+        })
+}
+
 pub async fn install_tools(tools: &[String]) -> OptimizerResult<()> {
+    install_tools_with(tools, &Host).await;
+    Ok(())
+}
+
+/// Install tools through `host`, returning each tool's result
+pub async fn install_tools_with<H: SystemProbe + CommandRunner + Sync>(
+    tools: &[String],
+    host: &H,
+) -> HashMap<String, OptimizerResult<()>> {
+    let tools: Vec<(&str, Option<&str>)> = tools.iter().map(|tool| (tool.as_str(), None)).collect();
+    install_versions(&tools, host).await
+}
+
+/// Install tools from a config bundle. Cargo subcommands are installed at exactly
+/// the pinned version; other tools come at whatever version the platform provides.
+pub async fn install_pinned_tools(pins: &BTreeMap<String, String>) -> OptimizerResult<()> {
+    let tools: Vec<(&str, Option<&str>)> = pins
+        .iter()
+        .map(|(tool, version)| (tool.as_str(), Some(version.as_str())))
+        .collect();
+    install_versions(&tools, &Host).await;
+    Ok(())
+}
+
+async fn install_versions<H: SystemProbe + CommandRunner + Sync>(
+    tools: &[(&str, Option<&str>)],
+    host: &H,
+) -> HashMap<String, OptimizerResult<()>> {
     print_status("Installing optimization tools...");
 
-    let system_info = SystemInfo::detect()?;
+    let system_info = SystemInfo::detect_with(host);
     let mut results = HashMap::new();
 
-    for tool in tools {
-        print_status(&format!("Installing {}...", tool.bright_cyan()));
-
-        let result = install_single_tool(tool, &system_info).await;
+    for (tool, version) in tools {
+        let tool = tool.to_string();
+        let result = match version {
+            Some(version) if tool.starts_with("cargo-") => {
+                print_status(&format!("Installing {} {}...", style::code(&tool), version));
+                install_cargo_tool_version(&tool, version, &system_info, host).await
+            }
+            _ => {
+                print_status(&format!("Installing {}...", style::code(&tool)));
+                install_single_tool(&tool, &system_info, host).await
+            }
+        };
         results.insert(tool.clone(), result);
 
-        match &results[tool] {
+        match &results[&tool] {
             Ok(_) => print_success(&format!("✅ {} installed successfully", tool)),
             Err(e) => print_warning(&format!("⚠️  Failed to install {}: {}", tool, e)),
         }
@@ -44,10 +172,14 @@ pub async fn install_tools(tools: &[String]) -> OptimizerResult<()> {
     // Print summary
     print_installation_summary(&results);
 
-    Ok(())
+    results
 }
 
-async fn install_single_tool(tool: &str, system_info: &SystemInfo) -> OptimizerResult<()> {
+async fn install_single_tool(
+    tool: &str,
+    system_info: &SystemInfo,
+    runner: &dyn CommandRunner,
+) -> OptimizerResult<()> {
     // Check if tool is already installed
     if system_info.is_tool_installed(tool) {
         return Ok(());
@@ -56,16 +188,21 @@ async fn install_single_tool(tool: &str, system_info: &SystemInfo) -> OptimizerR
     let spinner = create_spinner(&format!("Installing {}", tool));
 
     let result = match tool {
-        "sccache" => install_sccache(system_info).await,
-        "cargo-nextest" => install_cargo_tool("cargo-nextest").await,
-        "cargo-udeps" => install_cargo_tool("cargo-udeps").await,
-        "cargo-hakari" => install_cargo_tool("cargo-hakari").await,
-        "cargo-watch" => install_cargo_tool("cargo-watch").await,
-        "cargo-expand" => install_cargo_tool("cargo-expand").await,
-        "cargo-bloat" => install_cargo_tool("cargo-bloat").await,
-        "mold" => install_mold(system_info).await,
-        "zld" => install_zld(system_info).await,
-        "lld" => install_lld(system_info).await,
+        "sccache" => install_sccache(system_info, runner).await,
+        "cargo-nextest" => install_cargo_tool("cargo-nextest", runner).await,
+        "cargo-udeps" => install_cargo_tool("cargo-udeps", runner).await,
+        "cargo-hakari" => install_cargo_tool("cargo-hakari", runner).await,
+        "cargo-watch" => install_cargo_tool("cargo-watch", runner).await,
+        "cargo-expand" => install_cargo_tool("cargo-expand", runner).await,
+        "cargo-bloat" => install_cargo_tool("cargo-bloat", runner).await,
+        "mold" => install_mold(system_info, runner).await,
+        "zld" => install_zld(system_info, runner).await,
+        "lld" => install_lld(system_info, runner).await,
+        "ccache" => install_ccache(system_info, runner).await,
+        "rustfmt" | "cargo-clippy" | "rust-analyzer" => install_component(tool, runner).await,
+        cargo_tool if cargo_tool.starts_with("cargo-") => {
+            install_cargo_tool(cargo_tool, runner).await
+        }
         _ => Err(OptimizerError::tool_not_found(format!(
             "Unknown tool: {}",
             tool
@@ -76,89 +213,100 @@ async fn install_single_tool(tool: &str, system_info: &SystemInfo) -> OptimizerR
     result
 }
 
-async fn install_sccache(system_info: &SystemInfo) -> OptimizerResult<()> {
+async fn install_sccache(
+    system_info: &SystemInfo,
+    runner: &dyn CommandRunner,
+) -> OptimizerResult<()> {
     match &system_info.os {
-        crate::system::OperatingSystem::MacOS => {
-            execute_command_with_output("brew", &["install", "sccache"], None)
-        }
+        crate::system::OperatingSystem::MacOS => runner.run("brew", &["install", "sccache"]),
         crate::system::OperatingSystem::Linux => {
             // Try package manager first, fall back to cargo
             if let Some(pm) = system_info.get_package_manager() {
                 match pm {
                     "apt" => {
-                        if execute_command_success("sudo", &["apt-get", "update"], None)? {
-                            execute_command_with_output(
-                                "sudo",
-                                &["apt-get", "install", "-y", "sccache"],
-                                None,
-                            )
+                        if runner
+                            .run_interactive("sudo", &["apt-get", "update"])
+                            .is_ok()
+                        {
+                            runner.run_interactive("sudo", &["apt-get", "install", "-y", "sccache"])
                         } else {
-                            install_cargo_tool("sccache").await
+                            install_cargo_tool("sccache", runner).await
                         }
                     }
                     "yum" => {
-                        if execute_command_with_output(
-                            "sudo",
-                            &["yum", "install", "-y", "sccache"],
-                            None,
-                        )
-                        .is_err()
+                        if runner
+                            .run_interactive("sudo", &["yum", "install", "-y", "sccache"])
+                            .is_err()
                         {
-                            install_cargo_tool("sccache").await?;
+                            install_cargo_tool("sccache", runner).await?;
                         }
                         Ok(())
                     }
                     "pacman" => {
-                        if execute_command_with_output(
-                            "sudo",
-                            &["pacman", "-S", "--noconfirm", "sccache"],
-                            None,
-                        )
-                        .is_err()
+                        if runner
+                            .run_interactive("sudo", &["pacman", "-S", "--noconfirm", "sccache"])
+                            .is_err()
                         {
-                            install_cargo_tool("sccache").await?;
+                            install_cargo_tool("sccache", runner).await?;
                         }
                         Ok(())
                     }
-                    _ => install_cargo_tool("sccache").await,
+                    _ => install_cargo_tool("sccache", runner).await,
                 }
             } else {
-                install_cargo_tool("sccache").await
+                install_cargo_tool("sccache", runner).await
             }
         }
         crate::system::OperatingSystem::Windows => {
-            if execute_command_with_output("winget", &["install", "Mozilla.sccache"], None).is_err()
+            if runner
+                .run("winget", &["install", "Mozilla.sccache"])
+                .is_err()
             {
-                install_cargo_tool("sccache").await?;
+                install_cargo_tool("sccache", runner).await?;
             }
             Ok(())
         }
-        _ => install_cargo_tool("sccache").await,
+        _ => install_cargo_tool("sccache", runner).await,
     }
 }
 
-async fn install_cargo_tool(tool: &str) -> OptimizerResult<()> {
-    execute_command_with_output("cargo", &["install", tool, "--locked"], None)
+async fn install_cargo_tool(tool: &str, runner: &dyn CommandRunner) -> OptimizerResult<()> {
+    runner.run("cargo", &["install", tool, "--locked"])
+}
+
+async fn install_cargo_tool_version(
+    tool: &str,
+    version: &str,
+    system_info: &SystemInfo,
+    runner: &dyn CommandRunner,
+) -> OptimizerResult<()> {
+    let installed = system_info
+        .get_tool(tool)
+        .filter(|available| available.is_installed)
+        .and_then(|available| available.version.as_deref().and_then(parse_version));
+    if installed.as_deref() == Some(version) {
+        return Ok(());
+    }
+
+    let spinner = create_spinner(&format!("Installing {} {}", tool, version));
+    let result = runner.run(
+        "cargo",
+        &["install", tool, "--version", version, "--locked"],
+    );
+    spinner.finish_and_clear();
+    result
 }
 
-async fn install_mold(system_info: &SystemInfo) -> OptimizerResult<()> {
+async fn install_mold(system_info: &SystemInfo, runner: &dyn CommandRunner) -> OptimizerResult<()> {
     match &system_info.os {
         crate::system::OperatingSystem::Linux => {
             if let Some(pm) = system_info.get_package_manager() {
                 match pm {
-                    "apt" => execute_command_with_output(
-                        "sudo",
-                        &["apt-get", "install", "-y", "mold"],
-                        None,
-                    ),
-                    "yum" => {
-                        execute_command_with_output("sudo", &["yum", "install", "-y", "mold"], None)
+                    "apt" => runner.run_interactive("sudo", &["apt-get", "install", "-y", "mold"]),
+                    "yum" => runner.run_interactive("sudo", &["yum", "install", "-y", "mold"]),
+                    "pacman" => {
+                        runner.run_interactive("sudo", &["pacman", "-S", "--noconfirm", "mold"])
                     }
-                    "pacman" => execute_command_with_output(
-                        "sudo",
-                        &["pacman", "-S", "--noconfirm", "mold"],
-                        None,
-                    ),
                     _ => Err(OptimizerError::unsupported_platform(
                         "Package manager not supported for mold installation",
                     )),
@@ -175,38 +323,67 @@ async fn install_mold(system_info: &SystemInfo) -> OptimizerResult<()> {
     }
 }
 
-async fn install_zld(system_info: &SystemInfo) -> OptimizerResult<()> {
+async fn install_zld(system_info: &SystemInfo, runner: &dyn CommandRunner) -> OptimizerResult<()> {
     match &system_info.os {
-        crate::system::OperatingSystem::MacOS => {
-            execute_command_with_output("brew", &["install", "zld"], None)
-        }
+        crate::system::OperatingSystem::MacOS => runner.run("brew", &["install", "zld"]),
         _ => Err(OptimizerError::unsupported_platform(
             "zld is only available on macOS",
         )),
     }
 }
 
-async fn install_lld(system_info: &SystemInfo) -> OptimizerResult<()> {
+async fn install_component(tool: &str, runner: &dyn CommandRunner) -> OptimizerResult<()> {
+    let component = rustup_component(tool)
+        .ok_or_else(|| OptimizerError::tool_not_found(format!("Unknown component: {}", tool)))?;
+    runner.run("rustup", &["component", "add", component])
+}
+
+async fn install_ccache(
+    system_info: &SystemInfo,
+    runner: &dyn CommandRunner,
+) -> OptimizerResult<()> {
     match &system_info.os {
-        crate::system::OperatingSystem::MacOS => {
-            execute_command_with_output("brew", &["install", "llvm"], None)
+        crate::system::OperatingSystem::MacOS => runner.run("brew", &["install", "ccache"]),
+        crate::system::OperatingSystem::Linux => {
+            if let Some(pm) = system_info.get_package_manager() {
+                match pm {
+                    "apt" => {
+                        runner.run_interactive("sudo", &["apt-get", "install", "-y", "ccache"])
+                    }
+                    "yum" => runner.run_interactive("sudo", &["yum", "install", "-y", "ccache"]),
+                    "pacman" => {
+                        runner.run_interactive("sudo", &["pacman", "-S", "--noconfirm", "ccache"])
+                    }
+                    _ => Err(OptimizerError::unsupported_platform(
+                        "Package manager not supported for ccache installation",
+                    )),
+                }
+            } else {
+                Err(OptimizerError::unsupported_platform(
+                    "No package manager found for ccache installation",
+                ))
+            }
         }
+        crate::system::OperatingSystem::Windows => {
+            runner.run("winget", &["install", "Ccache.Ccache"])
+        }
+        _ => Err(OptimizerError::unsupported_platform(
+            "ccache installation not supported on this platform",
+        )),
+    }
+}
+
+async fn install_lld(system_info: &SystemInfo, runner: &dyn CommandRunner) -> OptimizerResult<()> {
+    match &system_info.os {
+        crate::system::OperatingSystem::MacOS => runner.run("brew", &["install", "llvm"]),
         crate::system::OperatingSystem::Linux => {
             if let Some(pm) = system_info.get_package_manager() {
                 match pm {
-                    "apt" => execute_command_with_output(
-                        "sudo",
-                        &["apt-get", "install", "-y", "lld"],
-                        None,
-                    ),
-                    "yum" => {
-                        execute_command_with_output("sudo", &["yum", "install", "-y", "lld"], None)
+                    "apt" => runner.run_interactive("sudo", &["apt-get", "install", "-y", "lld"]),
+                    "yum" => runner.run_interactive("sudo", &["yum", "install", "-y", "lld"]),
+                    "pacman" => {
+                        runner.run_interactive("sudo", &["pacman", "-S", "--noconfirm", "lld"])
                     }
-                    "pacman" => execute_command_with_output(
-                        "sudo",
-                        &["pacman", "-S", "--noconfirm", "lld"],
-                        None,
-                    ),
                     _ => Err(OptimizerError::unsupported_platform(
                         "Package manager not supported for lld installation",
                     )),
@@ -219,7 +396,7 @@ async fn install_lld(system_info: &SystemInfo) -> OptimizerResult<()> {
         }
         crate::system::OperatingSystem::Windows => {
             // LLD comes with LLVM on Windows
-            execute_command_with_output("winget", &["install", "LLVM.LLVM"], None)
+            runner.run("winget", &["install", "LLVM.LLVM"])
         }
         _ => Err(OptimizerError::unsupported_platform(
             "lld installation not supported on this platform",
@@ -228,35 +405,36 @@ async fn install_lld(system_info: &SystemInfo) -> OptimizerResult<()> {
 }
 
 async fn list_available_tools(system_info: &SystemInfo) -> OptimizerResult<()> {
-    println!("{}", "📦 Available Optimization Tools".bright_blue().bold());
+    println!("{}", style::title("📦 Available Optimization Tools"));
     println!();
 
     let tools = get_all_tools();
 
     for (category, tool_list) in tools {
-        println!("{}", category.bright_green().bold());
+        println!("{}", style::section(category));
 
+        let mut table = Table::new(&["Tool", "Description", "Status", "Platform"]);
         for tool in tool_list {
             let status = if system_info.is_tool_installed(&tool.name) {
-                "✅ Installed".bright_green()
+                Cell::styled("✅ Installed", style::good)
             } else {
-                "❌ Not installed".bright_red()
+                Cell::styled("❌ Not installed", style::bad)
             };
 
             let platform_support = if is_tool_supported(&tool.name, system_info) {
-                "✅ Supported".bright_green()
+                Cell::styled("✅ Supported", style::good)
             } else {
-                "❌ Not supported".bright_red()
+                Cell::styled("❌ Not supported", style::bad)
             };
 
-            println!(
-                "  {} - {} | {} | {}",
-                tool.name.bright_cyan(),
-                tool.description,
+            table.add_row(vec![
+                Cell::styled(tool.name, style::code),
+                tool.description.into(),
                 status,
-                platform_support
-            );
+                platform_support,
+            ]);
         }
+        table.print();
         println!();
     }
 
@@ -274,10 +452,10 @@ fn get_recommended_tools(system_info: &SystemInfo) -> Vec<String> {
 
     // Add platform-specific linkers
     match &system_info.os {
-        crate::system::OperatingSystem::MacOS => {
-            if system_info.arch == crate::system::Architecture::X86_64 {
-                tools.push("zld".to_string());
-            }
+        crate::system::OperatingSystem::MacOS
+            if system_info.arch == crate::system::Architecture::X86_64 =>
+        {
+            tools.push("zld".to_string());
         }
         crate::system::OperatingSystem::Linux => {
             tools.push("mold".to_string());
@@ -296,6 +474,14 @@ struct Tool {
     description: String,
 }
 
+/// Whether `install-tools` knows how to install `tool`
+pub fn is_installable(tool: &str) -> bool {
+    get_all_tools()
+        .iter()
+        .flat_map(|(_, tools)| tools)
+        .any(|known| known.name == tool)
+}
+
 fn get_all_tools() -> Vec<(&'static str, Vec<Tool>)> {
     vec![
         (
@@ -309,6 +495,10 @@ fn get_all_tools() -> Vec<(&'static str, Vec<Tool>)> {
                     name: "cargo-nextest".to_string(),
                     description: "Fast test runner".to_string(),
                 },
+                Tool {
+                    name: "ccache".to_string(),
+                    description: "Compiler cache for C/C++ in -sys crates".to_string(),
+                },
             ],
         ),
         (
@@ -351,25 +541,42 @@ fn get_all_tools() -> Vec<(&'static str, Vec<Tool>)> {
         ),
         (
             "⚡ Development Tools",
-            vec![Tool {
-                name: "cargo-watch".to_string(),
-                description: "Auto-rebuild on file changes".to_string(),
-            }],
+            vec![
+                Tool {
+                    name: "cargo-watch".to_string(),
+                    description: "Auto-rebuild on file changes".to_string(),
+                },
+                Tool {
+                    name: "rustfmt".to_string(),
+                    description: "Code formatter (rustup component)".to_string(),
+                },
+                Tool {
+                    name: "cargo-clippy".to_string(),
+                    description: "Lints (rustup component clippy)".to_string(),
+                },
+                Tool {
+                    name: "rust-analyzer".to_string(),
+                    description: "Language server for editors (rustup component)".to_string(),
+                },
+            ],
         ),
     ]
 }
 
-fn is_tool_supported(tool: &str, system_info: &SystemInfo) -> bool {
+/// Whether Atlas can install `tool` on this platform
+pub fn is_tool_supported(tool: &str, system_info: &SystemInfo) -> bool {
     match tool {
         "mold" => matches!(system_info.os, crate::system::OperatingSystem::Linux),
         "zld" => matches!(system_info.os, crate::system::OperatingSystem::MacOS),
+        // Installed with the platform's package manager
+        "lld" | "ccache" => system_info.get_package_manager().is_some(),
         _ => true, // Most tools are cross-platform
     }
 }
 
 fn print_installation_summary(results: &HashMap<String, OptimizerResult<()>>) {
     println!();
-    println!("{}", "📊 Installation Summary".bright_blue().bold());
+    println!("{}", style::title("📊 Installation Summary"));
     println!();
 
     let successful: Vec<_> = results
@@ -382,24 +589,24 @@ fn print_installation_summary(results: &HashMap<String, OptimizerResult<()>>) {
         .collect();
 
     if !successful.is_empty() {
-        println!("{} Successfully installed:", "✅".bright_green());
+        println!("{}", style::good("✅ Successfully installed:"));
         for (tool, _) in successful {
-            println!("  • {}", tool.bright_green());
+            println!("  • {}", style::good(tool));
         }
         println!();
     }
 
     if !failed.is_empty() {
-        println!("{} Failed to install:", "❌".bright_red());
+        println!("{}", style::bad("❌ Failed to install:"));
         for (tool, error) in failed {
-            println!("  • {}: {}", tool.bright_red(), error.as_ref().unwrap_err());
+            println!("  • {}: {}", style::bad(tool), error.as_ref().unwrap_err());
         }
         println!();
         println!(
-            "{} You can install these tools manually or try again later.",
-            "💡".bright_yellow()
+            "{}",
+            style::text("💡 You can install these tools manually or try again later.")
         );
     }
 
-    println!("🎉 Tool installation completed!");
+    println!("{}", style::text("🎉 Tool installation completed!"));
 }
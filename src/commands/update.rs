@@ -8,11 +8,7 @@ pub async fn run(check: bool) -> OptimizerResult<()> {
         print_success("✅ You are running the latest version");
     } else {
         print_status("Updating Atlas...");
-        execute_command_with_output(
-            "cargo",
-            &["install", "atlas", "--force"],
-            None,
-        )?;
+        execute_command_with_output("cargo", &["install", "atlas", "--force"], None)?;
         print_success("✅ Atlas updated successfully");
     }
 
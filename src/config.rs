@@ -1,14 +1,42 @@
+use crate::alias::Alias;
 use crate::error::{OptimizerError, OptimizerResult};
+use crate::maintenance::MaintenanceConfig;
+use crate::power::PowerConfig;
+use crate::process_env::EnvValue;
+use crate::system::OperatingSystem;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OptimizerConfig {
+    /// Language of Atlas's messages, e.g. `de`; unset follows `LANG`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    /// Next-step hints after commands, e.g. when a build had a low cache hit rate
+    #[serde(default = "default_true")]
+    pub hints: bool,
     pub build: BuildConfig,
     pub tools: ToolsConfig,
     pub optimization: OptimizationConfig,
     pub development: DevelopmentConfig,
+    #[serde(default)]
+    pub profiles: ProfilesConfig,
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+    #[serde(default)]
+    pub remote: RemoteConfig,
+    #[serde(default)]
+    pub maintenance: MaintenanceConfig,
+    #[serde(default)]
+    pub power: PowerConfig,
+    /// Variables set on every process Atlas starts, as `[env]`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, EnvValue>,
+    /// Command aliases, as `[aliases]`, e.g. `t = "build test --changed"`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub aliases: BTreeMap<String, Alias>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +47,31 @@ pub struct BuildConfig {
     pub use_fast_linker: bool,
     pub separate_rust_analyzer_target: bool,
     pub enable_sccache: bool,
+    /// Cross-language LTO between Rust and C/C++ through clang and lld
+    #[serde(default)]
+    pub linker_plugin_lto: bool,
+    /// Compiler cache wrapped around `CC`/`CXX` for C code built by `-sys` crates
+    #[serde(default)]
+    pub c_compiler_cache: CCompilerCache,
+    /// Target directory per Cargo profile, e.g. `release = "/mnt/big/target"`;
+    /// relative paths are relative to the project
+    #[serde(default)]
+    pub profile_target_dirs: BTreeMap<String, PathBuf>,
+    /// Pass `--locked` or `--frozen` to every cargo command Atlas runs
+    #[serde(default)]
+    pub lockfile: crate::lockfile::LockfileMode,
+}
+
+/// Cache used for C/C++ compilation in build scripts
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CCompilerCache {
+    /// ccache if installed, otherwise sccache if it is enabled and installed
+    #[default]
+    Auto,
+    Ccache,
+    Sccache,
+    Off,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,6 +79,9 @@ pub struct ToolsConfig {
     pub auto_install: bool,
     pub preferred_tools: Vec<String>,
     pub install_timeout_seconds: u64,
+    /// Programs Atlas may start, by name or pattern such as `cargo-*`; unset allows any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_commands: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,6 +90,12 @@ pub struct OptimizationConfig {
     pub artifact_retention_days: u32,
     pub check_unused_deps: bool,
     pub optimize_profiles: bool,
+    /// `opt-level` for dependencies in the dev profile
+    #[serde(default = "default_dependency_opt_level")]
+    pub dependency_opt_level: u8,
+    /// Dependencies that are slow unoptimized and get `opt-level = 3`, e.g. `image`
+    #[serde(default)]
+    pub hot_dependencies: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,6 +104,333 @@ pub struct DevelopmentConfig {
     pub watch_paths: Vec<PathBuf>,
     pub auto_test_on_change: bool,
     pub quick_check_on_save: bool,
+    /// Commands run in order whenever a watched file changes
+    #[serde(default = "default_watch_commands")]
+    pub watch_commands: Vec<WatchCommand>,
+    /// Clear the terminal before each run
+    #[serde(default = "default_true")]
+    pub watch_clear_screen: bool,
+    /// Glob patterns ignored by the watcher
+    #[serde(default)]
+    pub watch_ignore: Vec<String>,
+    /// Run the tests that failed last time before the rest in watch mode
+    #[serde(default = "default_true")]
+    pub failed_tests_first: bool,
+    /// Parallel jobs of `--background` builds; half the cores when unset
+    #[serde(default)]
+    pub background_jobs: Option<usize>,
+}
+
+/// What is hidden in logs, JSON output and debug bundles, on top of the
+/// built-in token formats, secret environment variables and home directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Regular expressions whose matches are redacted
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    /// Environment variables whose values are redacted, beyond those whose
+    /// names contain TOKEN, SECRET, PASSWORD, API_KEY, CREDENTIAL or AUTH
+    #[serde(default)]
+    pub env_vars: Vec<String>,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            patterns: Vec::new(),
+            env_vars: Vec::new(),
+        }
+    }
+}
+
+/// Machines `atlas remote build` offloads builds to
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RemoteConfig {
+    /// SSH destination of an unnamed host, e.g. `me@buildbox` or a host from
+    /// `~/.ssh/config`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    /// Directory on a host that projects are copied into, relative to the
+    /// remote home directory unless absolute
+    #[serde(default = "default_remote_path")]
+    pub path: String,
+    /// Host used when none is named on the command line
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    /// Named hosts, as `[remote.hosts.<name>]`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub hosts: BTreeMap<String, RemoteHost>,
+}
+
+impl Default for RemoteConfig {
+    fn default() -> Self {
+        Self {
+            host: None,
+            path: default_remote_path(),
+            default: None,
+            hosts: BTreeMap::new(),
+        }
+    }
+}
+
+fn default_remote_path() -> String {
+    "atlas-builds".to_string()
+}
+
+/// A named remote build host
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RemoteHost {
+    /// Host name or address, or a host from `~/.ssh/config`
+    pub address: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// Overrides `remote.path` for this host
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Parallel jobs for cargo on this host; unset lets cargo decide
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jobs: Option<usize>,
+    /// Compare the host's rustc with the local one before each build
+    #[serde(default = "default_true")]
+    pub check_toolchain: bool,
+}
+
+impl RemoteHost {
+    /// SSH destination, `user@address` or just the address
+    pub fn destination(&self) -> String {
+        match &self.user {
+            Some(user) => format!("{}@{}", user, self.address),
+            None => self.address.clone(),
+        }
+    }
+}
+
+impl RemoteConfig {
+    /// Host called `name`, or the default one: `default`, then the unnamed
+    /// `host`, then the only named host. The path is filled in from `path`.
+    pub fn host(&self, name: Option<&str>) -> OptimizerResult<RemoteHost> {
+        let name = name.or(self.default.as_deref());
+        let mut host = match name {
+            Some(name) => self.hosts.get(name).cloned().ok_or_else(|| {
+                OptimizerError::config(format!(
+                    "No remote host named '{}'; configured hosts: {}",
+                    name,
+                    self.host_names().join(", ")
+                ))
+            })?,
+            None => match (&self.host, self.hosts.values().next()) {
+                (Some(address), _) => RemoteHost {
+                    address: address.clone(),
+                    user: None,
+                    path: None,
+                    jobs: None,
+                    check_toolchain: true,
+                },
+                (None, Some(only)) if self.hosts.len() == 1 => only.clone(),
+                (None, None) => {
+                    return Err(OptimizerError::config(
+                        "No remote build host configured; add one under [remote.hosts.<name>] in the Atlas config",
+                    ))
+                }
+                (None, Some(_)) => {
+                    return Err(OptimizerError::config(format!(
+                        "Several remote hosts are configured ({}); name one or set remote.default",
+                        self.host_names().join(", ")
+                    )))
+                }
+            },
+        };
+        if host.path.is_none() {
+            host.path = Some(self.path.clone());
+        }
+        Ok(host)
+    }
+
+    fn host_names(&self) -> Vec<String> {
+        self.hosts.keys().cloned().collect()
+    }
+}
+
+/// Settings emitted into the generated Cargo profiles
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProfilesConfig {
+    /// Debug info level for the dev and test profiles
+    #[serde(default)]
+    pub dev_debug: DebugInfo,
+    /// Where debug info is stored; `auto` picks the fastest mode for the platform
+    #[serde(default)]
+    pub split_debuginfo: SplitDebuginfo,
+    /// Incremental compilation per generated profile, e.g. `release = true`.
+    /// The dev profile follows `build.incremental`; others keep Cargo's
+    /// default, which is off for release and bench.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub incremental: BTreeMap<String, bool>,
+}
+
+/// Directory to keep the global config and Atlas's machine-wide state in
+/// instead of the platform's config directory, e.g. for tests
+pub const CONFIG_DIR_ENV: &str = "ATLAS_CONFIG_DIR";
+
+fn config_path_in(
+    atlas_dir: Option<std::ffi::OsString>,
+    config_dir: Option<PathBuf>,
+) -> OptimizerResult<PathBuf> {
+    let dir = match atlas_dir.filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => config_dir
+            .ok_or_else(|| OptimizerError::config("Could not determine config directory"))?
+            .join("atlas"),
+    };
+    Ok(dir.join("config.toml"))
+}
+
+/// Profiles in the generated Cargo.toml block
+pub const GENERATED_PROFILES: &[&str] = &["dev", "release", "release-with-debug", "bench", "test"];
+
+/// Value of the `debug` profile setting
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DebugInfo {
+    None,
+    /// File and line info only; enough for backtraces, not for inspecting variables
+    LineTablesOnly,
+    Limited,
+    #[default]
+    Full,
+}
+
+impl DebugInfo {
+    /// TOML value for the `debug` key
+    pub fn to_toml(self) -> &'static str {
+        match self {
+            Self::None => "false",
+            Self::LineTablesOnly => "\"line-tables-only\"",
+            Self::Limited => "\"limited\"",
+            Self::Full => "true",
+        }
+    }
+}
+
+/// Value of the `split-debuginfo` profile setting
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SplitDebuginfo {
+    #[default]
+    Auto,
+    Off,
+    Packed,
+    Unpacked,
+}
+
+impl SplitDebuginfo {
+    /// Concrete mode for a platform, or `None` to leave Cargo's default
+    pub fn resolve(self, os: &OperatingSystem) -> Option<&'static str> {
+        match self {
+            // Skipping the debug info relinking step is the cheapest option on both
+            Self::Auto => match os {
+                OperatingSystem::MacOS | OperatingSystem::Linux => Some("unpacked"),
+                _ => None,
+            },
+            Self::Off => Some("off"),
+            Self::Packed => Some("packed"),
+            Self::Unpacked => Some("unpacked"),
+        }
+    }
+}
+
+/// Where Atlas installs its Cargo settings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigScope {
+    /// The project's `.cargo/config.toml` and `Cargo.toml`
+    #[default]
+    Project,
+    /// `~/.cargo/config.toml`, applied to every project on this machine
+    User,
+}
+
+impl ConfigScope {
+    pub fn other(self) -> Self {
+        match self {
+            Self::Project => Self::User,
+            Self::User => Self::Project,
+        }
+    }
+}
+
+impl std::fmt::Display for ConfigScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Project => write!(f, "project"),
+            Self::User => write!(f, "user"),
+        }
+    }
+}
+
+/// A single step in the watch-mode command chain
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WatchCommand {
+    /// `cargo check` across the workspace
+    Check,
+    /// `cargo test`, optionally restricted to tests matching `filter`
+    Test {
+        #[serde(default)]
+        filter: Option<String>,
+    },
+    /// Arbitrary cargo subcommand, e.g. `clippy --all-targets`
+    Cargo { args: String },
+    /// Arbitrary shell command
+    Shell { command: String },
+}
+
+impl WatchCommand {
+    /// Convert this step into `cargo watch` arguments. With `changed_only`, unfiltered
+    /// tests run through Atlas for the members affected by uncommitted changes;
+    /// with `failed_first`, the tests that failed last time run before them.
+    pub fn to_watch_args(&self, changed_only: bool, failed_first: bool) -> Vec<String> {
+        match self {
+            Self::Test { filter: None } if changed_only || failed_first => {
+                let mut test = "atlas --quiet build test".to_string();
+                if changed_only {
+                    test.push_str(" --changed");
+                }
+                if failed_first {
+                    test.push_str(" --failed-first");
+                }
+                vec!["-s".to_string(), test]
+            }
+            Self::Check => vec![
+                "-x".to_string(),
+                "check --workspace --message-format=short".to_string(),
+            ],
+            Self::Test { filter } => {
+                let mut test = "test --workspace".to_string();
+                if let Some(filter) = filter {
+                    test.push(' ');
+                    test.push_str(filter);
+                }
+                vec!["-x".to_string(), test]
+            }
+            Self::Cargo { args } => vec!["-x".to_string(), args.clone()],
+            Self::Shell { command } => vec!["-s".to_string(), command.clone()],
+        }
+    }
+}
+
+fn default_watch_commands() -> Vec<WatchCommand> {
+    vec![WatchCommand::Check]
+}
+
+fn default_dependency_opt_level() -> u8 {
+    1
+}
+
+pub(crate) fn default_true() -> bool {
+    true
 }
 
 impl Default for OptimizerConfig {
@@ -54,6 +443,10 @@ impl Default for OptimizerConfig {
                 use_fast_linker: true,
                 separate_rust_analyzer_target: true,
                 enable_sccache: true,
+                linker_plugin_lto: false,
+                c_compiler_cache: CCompilerCache::Auto,
+                profile_target_dirs: BTreeMap::new(),
+                lockfile: crate::lockfile::LockfileMode::Off,
             },
             tools: ToolsConfig {
                 auto_install: true,
@@ -65,19 +458,36 @@ impl Default for OptimizerConfig {
                     "cargo-watch".to_string(),
                 ],
                 install_timeout_seconds: 300,
+                allowed_commands: None,
             },
             optimization: OptimizationConfig {
                 clean_old_artifacts: true,
                 artifact_retention_days: 7,
                 check_unused_deps: true,
                 optimize_profiles: true,
+                dependency_opt_level: default_dependency_opt_level(),
+                hot_dependencies: Vec::new(),
             },
             development: DevelopmentConfig {
                 watch_mode_enabled: true,
                 watch_paths: vec![PathBuf::from("src"), PathBuf::from("Cargo.toml")],
                 auto_test_on_change: false,
                 quick_check_on_save: true,
+                watch_commands: default_watch_commands(),
+                watch_clear_screen: true,
+                watch_ignore: Vec::new(),
+                failed_tests_first: true,
+                background_jobs: None,
             },
+            locale: None,
+            hints: true,
+            profiles: ProfilesConfig::default(),
+            redaction: RedactionConfig::default(),
+            remote: RemoteConfig::default(),
+            maintenance: MaintenanceConfig::default(),
+            power: PowerConfig::default(),
+            env: BTreeMap::new(),
+            aliases: BTreeMap::new(),
         }
     }
 }
@@ -92,18 +502,23 @@ impl OptimizerConfig {
 
     pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> OptimizerResult<()> {
         let content = toml::to_string_pretty(self)?;
-        fs::write(path, content)?;
-        Ok(())
+        crate::audit::write(path.as_ref(), content)
     }
 
+    /// The global config, in `atlas` under the platform's config directory
+    /// unless [`CONFIG_DIR_ENV`] names another directory
     pub fn get_config_path() -> OptimizerResult<PathBuf> {
-        let config_dir = dirs::config_dir()
-            .ok_or_else(|| OptimizerError::config("Could not determine config directory"))?;
-
-        Ok(config_dir.join("atlas").join("config.toml"))
+        config_path_in(std::env::var_os(CONFIG_DIR_ENV), dirs::config_dir())
     }
 
+    /// The effective configuration: the global config, then the project's
+    /// layers, then command-line flags
     pub fn load_or_default() -> OptimizerResult<Self> {
+        Ok(crate::config_layers::LayeredConfig::load()?.config)
+    }
+
+    /// The global config alone, for changes saved back to it
+    pub fn load_global() -> OptimizerResult<Self> {
         let config_path = Self::get_config_path()?;
 
         if config_path.exists() {
@@ -136,6 +551,68 @@ impl OptimizerConfig {
             }
         }
 
+        // Validate environment variable names
+        for name in self.env.keys() {
+            if name.is_empty() || name.contains(['=', '\0']) {
+                return Err(OptimizerError::config(format!(
+                    "Invalid environment variable name in [env]: '{}'",
+                    name
+                )));
+            }
+        }
+
+        // Validate alias names
+        for (name, alias) in &self.aliases {
+            if name.is_empty() || name.starts_with('-') || name.contains(char::is_whitespace) {
+                return Err(OptimizerError::config(format!(
+                    "Invalid alias name: '{}'",
+                    name
+                )));
+            }
+            if alias.args().is_empty() {
+                return Err(OptimizerError::config(format!("Alias '{}' is empty", name)));
+            }
+        }
+
+        // Validate per-profile target directories
+        for (profile, dir) in &self.build.profile_target_dirs {
+            if !is_valid_package_name(profile) {
+                return Err(OptimizerError::config(format!(
+                    "Invalid profile name in profile_target_dirs: '{}'",
+                    profile
+                )));
+            }
+            if dir.as_os_str().is_empty() {
+                return Err(OptimizerError::config(format!(
+                    "Target directory for profile '{}' cannot be empty",
+                    profile
+                )));
+            }
+        }
+
+        // Validate the locale
+        if let Some(locale) = &self.locale {
+            if crate::i18n::supported(locale).is_none() {
+                let available: Vec<&str> =
+                    crate::i18n::LOCALES.iter().map(|(name, _)| *name).collect();
+                return Err(OptimizerError::config(format!(
+                    "No messages for locale '{}'; available: {}",
+                    locale,
+                    available.join(", ")
+                )));
+            }
+        }
+
+        // Validate redaction patterns
+        for pattern in &self.redaction.patterns {
+            if let Err(e) = regex::Regex::new(pattern) {
+                return Err(OptimizerError::config(format!(
+                    "Invalid redaction pattern '{}': {}",
+                    pattern, e
+                )));
+            }
+        }
+
         // Validate artifact retention
         if self.optimization.artifact_retention_days > 365 {
             return Err(OptimizerError::config(
@@ -143,6 +620,38 @@ impl OptimizerConfig {
             ));
         }
 
+        // Validate dependency optimization
+        if self.optimization.dependency_opt_level > 3 {
+            return Err(OptimizerError::config(
+                "Dependency opt-level must be between 0 and 3",
+            ));
+        }
+        if let Some(name) = self
+            .optimization
+            .hot_dependencies
+            .iter()
+            .find(|name| !is_valid_package_name(name))
+        {
+            return Err(OptimizerError::config(format!(
+                "Invalid hot dependency name: '{}'",
+                name
+            )));
+        }
+
+        // Validate incremental compilation per profile
+        if let Some(profile) = self
+            .profiles
+            .incremental
+            .keys()
+            .find(|profile| !GENERATED_PROFILES.contains(&profile.as_str()))
+        {
+            return Err(OptimizerError::config(format!(
+                "Cannot set incremental for profile '{}'; Atlas generates {}",
+                profile,
+                GENERATED_PROFILES.join(", ")
+            )));
+        }
+
         // Validate tool install timeout
         if self.tools.install_timeout_seconds < 30 {
             return Err(OptimizerError::config(
@@ -150,6 +659,23 @@ impl OptimizerConfig {
             ));
         }
 
+        // Validate watch command chain
+        for command in &self.development.watch_commands {
+            match command {
+                WatchCommand::Cargo { args } if args.trim().is_empty() => {
+                    return Err(OptimizerError::config(
+                        "Watch cargo command cannot be empty",
+                    ));
+                }
+                WatchCommand::Shell { command } if command.trim().is_empty() => {
+                    return Err(OptimizerError::config(
+                        "Watch shell command cannot be empty",
+                    ));
+                }
+                _ => {}
+            }
+        }
+
         // Validate watch paths exist (if specified)
         for path in &self.development.watch_paths {
             if !path.exists() {
@@ -160,15 +686,94 @@ impl OptimizerConfig {
         Ok(())
     }
 
+    /// Wrapper to put in front of the C compiler, if any
+    pub fn c_compiler_wrapper(
+        &self,
+        system_info: &crate::system::SystemInfo,
+    ) -> Option<&'static str> {
+        // MSVC's cl.exe cannot be wrapped through CC
+        if system_info.os == OperatingSystem::Windows {
+            return None;
+        }
+        match self.build.c_compiler_cache {
+            CCompilerCache::Off => None,
+            CCompilerCache::Ccache => Some("ccache"),
+            CCompilerCache::Sccache => Some("sccache"),
+            CCompilerCache::Auto if system_info.is_tool_installed("ccache") => Some("ccache"),
+            CCompilerCache::Auto
+                if self.build.enable_sccache && system_info.is_tool_installed("sccache") =>
+            {
+                Some("sccache")
+            }
+            CCompilerCache::Auto => None,
+        }
+    }
+
     pub fn get_effective_parallel_jobs(&self) -> usize {
         self.build.parallel_jobs.unwrap_or_else(num_cpus::get)
     }
+
+    /// Build the full `cargo watch` argument list from the development config
+    pub fn watch_args(&self, paths: &[PathBuf], changed_only: bool) -> Vec<String> {
+        let dev = &self.development;
+        let mut args = vec!["watch".to_string()];
+
+        if dev.watch_clear_screen {
+            args.push("-c".to_string());
+        }
+
+        let watch_paths = if paths.is_empty() {
+            &dev.watch_paths
+        } else {
+            paths
+        };
+        for path in watch_paths {
+            args.push("-w".to_string());
+            args.push(path.display().to_string());
+        }
+
+        for pattern in &dev.watch_ignore {
+            args.push("-i".to_string());
+            args.push(pattern.clone());
+        }
+
+        let mut commands = dev.watch_commands.clone();
+        if dev.auto_test_on_change
+            && !commands
+                .iter()
+                .any(|command| matches!(command, WatchCommand::Test { .. }))
+        {
+            commands.push(WatchCommand::Test { filter: None });
+        }
+        for command in &commands {
+            args.extend(command.to_watch_args(changed_only, dev.failed_tests_first));
+        }
+
+        args
+    }
+
+    /// Incremental compilation Atlas writes into `profile`, or `None` to keep
+    /// what the profile inherits or Cargo's default
+    pub fn profile_incremental(&self, profile: &str) -> Option<bool> {
+        match self.profiles.incremental.get(profile) {
+            Some(incremental) => Some(*incremental),
+            None => (profile == "dev").then_some(self.build.incremental),
+        }
+    }
+}
+
+fn is_valid_package_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
 }
 
 /// Generate Cargo configuration content
 pub fn generate_cargo_config(
     config: &OptimizerConfig,
     system_info: &crate::system::SystemInfo,
+    scope: ConfigScope,
 ) -> String {
     let mut content = String::new();
 
@@ -181,25 +786,62 @@ pub fn generate_cargo_config(
         "jobs = {}\n",
         config.get_effective_parallel_jobs()
     ));
-    content.push_str("target-dir = \"target\"\n");
-    content.push_str("pipelining = true\n\n");
+    if config.build.enable_sccache && system_info.is_tool_installed("sccache") {
+        content.push_str("rustc-wrapper = \"sccache\"\n");
+    }
+    // A relative target-dir in the user config would put every project's output in ~/target
+    if scope == ConfigScope::Project {
+        content.push_str("target-dir = \"target\"\n");
+    }
+    // Incremental compilation is set per profile; `build.incremental` would
+    // also switch it on for release builds, which Cargo keeps off by design
+    content.push('\n');
 
-    // Environment section
-    content.push_str("[env]\n");
-    if config.build.separate_rust_analyzer_target {
-        content.push_str("CARGO_TARGET_DIR = { value = \"target/rust-analyzer\", condition = \"cfg(rust_analyzer)\" }\n");
+    // Environment for compilers and build scripts; Cargo itself ignores [env]
+    let mut env = Vec::new();
+
+    // Build C/C++ dependencies as LLVM bitcode so lld can optimize across languages
+    let linker_plugin_lto =
+        config.build.linker_plugin_lto && system_info.linker_plugin_lto_llvm().is_ok();
+    let (cc, cxx) = if linker_plugin_lto {
+        ("clang", "clang++")
+    } else {
+        ("cc", "c++")
+    };
+    match config.c_compiler_wrapper(system_info) {
+        Some(wrapper) => {
+            env.push(("CC", format!("{} {}", wrapper, cc)));
+            env.push(("CXX", format!("{} {}", wrapper, cxx)));
+        }
+        None if linker_plugin_lto => {
+            env.push(("CC", cc.to_string()));
+            env.push(("CXX", cxx.to_string()));
+        }
+        None => {}
     }
-    content.push_str(&format!(
-        "CARGO_INCREMENTAL = \"{}\"\n",
-        if config.build.incremental { "1" } else { "0" }
-    ));
-    content.push_str("CARGO_PROFILE_DEV_INCREMENTAL = \"true\"\n");
-    content.push_str("CARGO_BUILD_CACHE = \"1\"\n");
-    content.push_str("CARGO_NET_RETRY = \"3\"\n");
-    content.push_str("CARGO_NET_GIT_FETCH_WITH_CLI = \"true\"\n\n");
+    if linker_plugin_lto {
+        env.push(("AR", "llvm-ar".to_string()));
+        env.push(("CFLAGS", "-flto=thin".to_string()));
+        env.push(("CXXFLAGS", "-flto=thin".to_string()));
+    }
+    content.push_str(&crate::cargo_env::render_env(&env));
 
     // Target-specific configuration
-    if config.build.use_fast_linker {
+    if linker_plugin_lto {
+        content.push_str(&format!(
+            "[target.{}-unknown-linux-gnu]\n",
+            system_info.arch
+        ));
+        content.push_str("linker = \"clang\"\n");
+        content.push_str("rustflags = [\n");
+        content.push_str("    \"-C\", \"linker-plugin-lto\",\n");
+        content.push_str("    \"-C\", \"link-arg=-fuse-ld=lld\",\n");
+        content.push_str(&format!(
+            "    \"-C\", \"target-cpu={}\",\n",
+            config.build.target_cpu
+        ));
+        content.push_str("]\n\n");
+    } else if config.build.use_fast_linker {
         if let Some(_linker) = system_info.get_recommended_linker() {
             match (&system_info.os, &system_info.arch) {
                 (crate::system::OperatingSystem::MacOS, crate::system::Architecture::Aarch64) => {
@@ -243,6 +885,21 @@ pub fn generate_cargo_config(
         }
     }
 
+    // Cargo picks one target directory per invocation; Atlas routes its own
+    // commands through CARGO_TARGET_DIR, and these aliases do the same by hand
+    if !config.build.profile_target_dirs.is_empty() {
+        content.push_str("[alias]\n");
+        for (profile, dir) in &config.build.profile_target_dirs {
+            content.push_str(&format!(
+                "build-{} = [\"build\", \"--profile\", \"{}\", \"--target-dir\", {}]\n",
+                profile,
+                profile,
+                toml::Value::String(dir.display().to_string())
+            ));
+        }
+        content.push('\n');
+    }
+
     // Registry configuration
     content.push_str("[registries.crates-io]\n");
     content.push_str("protocol = \"sparse\"\n\n");
@@ -255,17 +912,91 @@ pub fn generate_cargo_config(
     content
 }
 
+/// First line of the Cargo config written by `atlas init --minimal`
+pub const MINIMAL_CONFIG_HEADER: &str =
+    "# Minimal Cargo Configuration: only settings that do not change what is built";
+
+/// Generate the Cargo configuration of `atlas init --minimal`. The compiler
+/// cache and the registry protocol change how crates are fetched and
+/// compiled, never the binaries, so they are safe to try on any project.
+pub fn generate_minimal_cargo_config(
+    config: &OptimizerConfig,
+    system_info: &crate::system::SystemInfo,
+) -> String {
+    let mut content = format!("{}\n# Generated by Atlas\n\n", MINIMAL_CONFIG_HEADER);
+
+    if config.build.enable_sccache && system_info.is_tool_installed("sccache") {
+        content.push_str("[build]\n");
+        content.push_str("rustc-wrapper = \"sccache\"\n\n");
+    }
+
+    content.push_str("[registries.crates-io]\n");
+    content.push_str("protocol = \"sparse\"\n");
+
+    content
+}
+
+/// Whether a generated Cargo config was written by `atlas init --minimal`
+pub fn is_minimal_cargo_config(content: &str) -> bool {
+    content.trim_start().starts_with(MINIMAL_CONFIG_HEADER)
+}
+
+/// Generate `.config/nextest.toml`; it only affects `cargo nextest` runs
+pub fn generate_nextest_config() -> String {
+    r#"# Test runner configuration for cargo-nextest
+# Generated by Atlas
+
+[profile.default]
+# Name tests that run for more than a minute
+slow-timeout = "60s"
+
+[profile.ci]
+# Run every test even after one fails, so CI reports all failures at once
+fail-fast = false
+
+[profile.ci.junit]
+path = "junit.xml"
+"#
+    .to_string()
+}
+
 /// Generate optimized Cargo.toml profiles
-pub fn generate_cargo_profiles() -> String {
-    r#"# Optimized build profiles for better performance and faster compilation
+pub fn generate_cargo_profiles(config: &OptimizerConfig, os: &OperatingSystem) -> String {
+    let dev_debug = config.profiles.dev_debug;
+    let split_debuginfo = match config.profiles.split_debuginfo.resolve(os) {
+        Some(mode) if dev_debug != DebugInfo::None => {
+            format!(
+                "# Keep debug info out of the link step\nsplit-debuginfo = \"{}\"\n",
+                mode
+            )
+        }
+        _ => String::new(),
+    };
+
+    let incremental = |profile: &str| match config.profile_incremental(profile) {
+        Some(incremental) if profile == "dev" => format!(
+            "# Enable incremental compilation for faster rebuilds\nincremental = {}\n",
+            incremental
+        ),
+        Some(incremental) => format!("incremental = {}\n", incremental),
+        None => String::new(),
+    };
+
+    let hot_dependencies: String = config
+        .optimization
+        .hot_dependencies
+        .iter()
+        .map(|name| format!("\n[profile.dev.package.{}]\nopt-level = 3\n", name))
+        .collect();
+
+    format!(
+        r#"# Optimized build profiles for better performance and faster compilation
 [profile.dev]
-# Enable incremental compilation for faster rebuilds
-incremental = true
-# Optimize for compilation speed in development
+{dev_incremental}# Optimize for compilation speed in development
 opt-level = 0
-# Enable debug info for better debugging experience
-debug = true
-# Reduce binary size in development
+# Debug info level for the debugging experience
+debug = {dev_debug}
+{split_debuginfo}# Reduce binary size in development
 strip = false
 # Use more codegen units for faster parallel compilation
 codegen-units = 512
@@ -279,11 +1010,11 @@ lto = false
 panic = "unwind"
 
 [profile.dev.package."*"]
-# Optimize dependencies even in dev mode for better performance
-opt-level = 3
+# Light optimization for dependencies keeps first builds fast
+opt-level = {dependency_opt_level}
 # Disable debug info for dependencies to speed up compilation
 debug = false
-
+{hot_dependencies}
 [profile.release]
 # Maximum optimization for production builds
 opt-level = 3
@@ -301,25 +1032,393 @@ overflow-checks = true
 debug-assertions = false
 # Abort on panic for smaller binary size
 panic = "abort"
-
+{release_incremental}
 [profile.release-with-debug]
 # Release profile with debug info for profiling
 inherits = "release"
 debug = true
 strip = false
-
+{release_with_debug_incremental}
 [profile.bench]
 # Optimized profile for benchmarking
 inherits = "release"
 debug = true
 lto = true
-
+{bench_incremental}
 [profile.test]
 # Optimized profile for testing
 inherits = "dev"
 opt-level = 1
 # Faster test compilation
 codegen-units = 512
-"#
-    .to_string()
+{test_incremental}"#,
+        dev_incremental = incremental("dev"),
+        release_incremental = incremental("release"),
+        release_with_debug_incremental = incremental("release-with-debug"),
+        bench_incremental = incremental("bench"),
+        test_incremental = incremental("test"),
+        dev_debug = dev_debug.to_toml(),
+        split_debuginfo = split_debuginfo,
+        dependency_opt_level = config.optimization.dependency_opt_level,
+        hot_dependencies = hot_dependencies,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_dir_override() {
+        let platform = Some(PathBuf::from("/home/me/.config"));
+        assert_eq!(
+            config_path_in(None, platform.clone()).unwrap(),
+            PathBuf::from("/home/me/.config/atlas/config.toml")
+        );
+        assert_eq!(
+            config_path_in(Some("/tmp/atlas".into()), platform.clone()).unwrap(),
+            PathBuf::from("/tmp/atlas/config.toml")
+        );
+        assert_eq!(
+            config_path_in(Some("".into()), platform).unwrap(),
+            PathBuf::from("/home/me/.config/atlas/config.toml")
+        );
+        assert!(config_path_in(None, None).is_err());
+    }
+
+    #[test]
+    fn test_generated_profiles_debuginfo() {
+        let mut config = OptimizerConfig::default();
+        config.profiles.dev_debug = DebugInfo::LineTablesOnly;
+
+        let profiles = generate_cargo_profiles(&config, &OperatingSystem::Linux);
+        let parsed: toml::Value = toml::from_str(&profiles).unwrap();
+        let dev = &parsed["profile"]["dev"];
+        assert_eq!(dev["debug"].as_str(), Some("line-tables-only"));
+        assert_eq!(dev["split-debuginfo"].as_str(), Some("unpacked"));
+
+        let windows = generate_cargo_profiles(&config, &OperatingSystem::Windows);
+        assert!(!windows.contains("split-debuginfo"));
+    }
+
+    #[test]
+    fn test_generated_profiles_dependency_opt_level() {
+        let mut config = OptimizerConfig::default();
+        config.optimization.hot_dependencies = vec!["image".to_string(), "regex".to_string()];
+        config.validate().unwrap();
+
+        let profiles = generate_cargo_profiles(&config, &OperatingSystem::Linux);
+        let parsed: toml::Value = toml::from_str(&profiles).unwrap();
+        let packages = &parsed["profile"]["dev"]["package"];
+        assert_eq!(packages["*"]["opt-level"].as_integer(), Some(1));
+        assert_eq!(packages["image"]["opt-level"].as_integer(), Some(3));
+        assert_eq!(packages["regex"]["opt-level"].as_integer(), Some(3));
+
+        config.optimization.hot_dependencies = vec!["bad name".to_string()];
+        assert!(config.validate().is_err());
+    }
+
+    /// Linux with an LLVM 18 toolchain, plus `extra` tools as (name, version)
+    fn system_with_llvm(clang: &str, extra: &[(&str, &str)]) -> crate::system::SystemInfo {
+        let mut host = crate::host::MockHost::linux()
+            .with_output("rustc -vV", "rustc 1.79.0\nLLVM version: 18.1.7\n")
+            .with_program("clang", Some(clang))
+            .with_program(
+                "ld.lld",
+                Some("Ubuntu LLD 18.1.3 (compatible with GNU linkers)"),
+            )
+            .with_program("llvm-ar", Some("Ubuntu LLVM version 18.1.3"));
+        for (tool, version) in extra {
+            host = host.with_program(tool, Some(version));
+        }
+        crate::system::SystemInfo::detect_with(&host)
+    }
+
+    #[test]
+    fn test_linker_plugin_lto_requires_matching_llvm() {
+        let mut config = OptimizerConfig::default();
+        config.build.linker_plugin_lto = true;
+
+        let matching = generate_cargo_config(
+            &config,
+            &system_with_llvm("clang version 18.1.3", &[]),
+            ConfigScope::Project,
+        );
+        assert!(matching.contains("\"-C\", \"linker-plugin-lto\""));
+        assert!(matching.contains("CFLAGS = \"-flto=thin\""));
+        toml::from_str::<toml::Value>(&matching).unwrap();
+
+        let mismatched = generate_cargo_config(
+            &config,
+            &system_with_llvm("clang version 17.0.6", &[]),
+            ConfigScope::Project,
+        );
+        assert!(!mismatched.contains("linker-plugin-lto"));
+        assert!(mismatched.contains("fuse-ld=mold"));
+    }
+
+    #[test]
+    fn test_c_compiler_cache_wraps_cc() {
+        let mut config = OptimizerConfig::default();
+        let system = system_with_llvm("clang version 18.1.3", &[]);
+        assert!(!generate_cargo_config(&config, &system, ConfigScope::Project).contains("CC ="));
+
+        let system = system_with_llvm(
+            "clang version 18.1.3",
+            &[("ccache", "ccache version 4.9.1")],
+        );
+        let generated = generate_cargo_config(&config, &system, ConfigScope::Project);
+        assert!(generated.contains("CC = \"ccache cc\""));
+        assert!(generated.contains("CXX = \"ccache c++\""));
+
+        config.build.linker_plugin_lto = true;
+        let generated = generate_cargo_config(&config, &system, ConfigScope::Project);
+        assert!(generated.contains("CC = \"ccache clang\""));
+
+        config.build.c_compiler_cache = CCompilerCache::Off;
+        assert!(
+            generate_cargo_config(&config, &system, ConfigScope::Project)
+                .contains("CC = \"clang\"")
+        );
+    }
+
+    #[test]
+    fn test_generated_env_is_honored() {
+        let system = system_with_llvm("clang version 18.1.3", &[("sccache", "sccache 0.8.1")]);
+
+        for linker_plugin_lto in [false, true] {
+            for c_compiler_cache in [CCompilerCache::Auto, CCompilerCache::Off] {
+                let mut config = OptimizerConfig::default();
+                config.build.linker_plugin_lto = linker_plugin_lto;
+                config.build.c_compiler_cache = c_compiler_cache;
+
+                let generated = generate_cargo_config(&config, &system, ConfigScope::Project);
+                assert!(!generated.contains("CARGO_"));
+                assert_eq!(
+                    crate::cargo_env::validate_config_env(&generated).unwrap(),
+                    Vec::new()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_incremental_per_profile() {
+        let mut config = OptimizerConfig::default();
+        let system = system_with_llvm("clang version 18.1.3", &[]);
+        assert!(
+            !generate_cargo_config(&config, &system, ConfigScope::Project).contains("incremental")
+        );
+
+        let parsed = |config: &OptimizerConfig| -> toml::Value {
+            toml::from_str(&generate_cargo_profiles(config, &OperatingSystem::Linux)).unwrap()
+        };
+        let profiles = parsed(&config);
+        assert_eq!(
+            profiles["profile"]["dev"]["incremental"].as_bool(),
+            Some(true)
+        );
+        assert!(profiles["profile"]["release"].get("incremental").is_none());
+
+        config.build.incremental = false;
+        config
+            .profiles
+            .incremental
+            .insert("release-with-debug".to_string(), true);
+        let profiles = parsed(&config);
+        assert_eq!(
+            profiles["profile"]["dev"]["incremental"].as_bool(),
+            Some(false)
+        );
+        assert_eq!(
+            profiles["profile"]["release-with-debug"]["incremental"].as_bool(),
+            Some(true)
+        );
+        assert!(config.validate().is_ok());
+
+        config.profiles.incremental.insert("ci".to_string(), true);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_minimal_config_changes_nothing_built() {
+        let config = OptimizerConfig::default();
+        let system = system_with_llvm("clang version 18.1.3", &[("sccache", "sccache 0.8.1")]);
+        let minimal = generate_minimal_cargo_config(&config, &system);
+        assert!(is_minimal_cargo_config(&minimal));
+        assert!(!is_minimal_cargo_config(&generate_cargo_config(
+            &config,
+            &system,
+            ConfigScope::Project
+        )));
+
+        let parsed: toml::Value = toml::from_str(&minimal).unwrap();
+        assert_eq!(parsed["build"]["rustc-wrapper"].as_str(), Some("sccache"));
+        assert_eq!(
+            parsed["registries"]["crates-io"]["protocol"].as_str(),
+            Some("sparse")
+        );
+        for risky in ["target", "profile", "env"] {
+            assert!(parsed.get(risky).is_none(), "{}", risky);
+        }
+        assert_eq!(parsed["build"].as_table().unwrap().len(), 1);
+        toml::from_str::<toml::Value>(&generate_nextest_config()).unwrap();
+    }
+
+    #[test]
+    fn test_user_scope_has_no_target_dir() {
+        let config = OptimizerConfig::default();
+        let system = system_with_llvm("clang version 18.1.3", &[]);
+        assert!(
+            generate_cargo_config(&config, &system, ConfigScope::Project)
+                .contains("target-dir = \"target\"")
+        );
+
+        let user = format!(
+            "{}\n{}",
+            generate_cargo_config(&config, &system, ConfigScope::User),
+            generate_cargo_profiles(&config, &system.os)
+        );
+        assert!(!user.contains("target-dir"));
+        assert!(toml::from_str::<toml::Value>(&user).is_ok());
+    }
+
+    #[test]
+    fn test_profile_target_dir_aliases() {
+        let mut config = OptimizerConfig::default();
+        config
+            .build
+            .profile_target_dirs
+            .insert("release".to_string(), PathBuf::from("/mnt/big/target"));
+        assert!(config.validate().is_ok());
+
+        let system = system_with_llvm("clang version 18.1.3", &[]);
+        let generated: toml::Value = toml::from_str(&generate_cargo_config(
+            &config,
+            &system,
+            ConfigScope::Project,
+        ))
+        .unwrap();
+        assert_eq!(
+            generated["alias"]["build-release"],
+            toml::Value::try_from([
+                "build",
+                "--profile",
+                "release",
+                "--target-dir",
+                "/mnt/big/target"
+            ])
+            .unwrap()
+        );
+
+        config
+            .build
+            .profile_target_dirs
+            .insert("not a profile".to_string(), PathBuf::from("/tmp"));
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_profiles_section_is_optional() {
+        let mut toml = toml::to_string(&OptimizerConfig::default()).unwrap();
+        let start = toml.find("[profiles]").unwrap();
+        toml.truncate(start);
+
+        let config: OptimizerConfig = toml::from_str(&toml).unwrap();
+        assert_eq!(config.profiles.dev_debug, DebugInfo::Full);
+        assert_eq!(config.profiles.split_debuginfo, SplitDebuginfo::Auto);
+    }
+
+    #[test]
+    fn test_remote_host_selection() {
+        let remote: RemoteConfig = toml::from_str(
+            "[hosts.big]\naddress = \"big.lan\"\nuser = \"me\"\njobs = 64\n\n[hosts.arm]\naddress = \"arm.lan\"\npath = \"/scratch\"\n",
+        )
+        .unwrap();
+        assert!(remote.host(None).is_err());
+        let big = remote.host(Some("big")).unwrap();
+        assert_eq!(big.destination(), "me@big.lan");
+        assert_eq!(big.path.as_deref(), Some("atlas-builds"));
+        assert!(big.check_toolchain);
+        assert_eq!(
+            remote.host(Some("arm")).unwrap().path.as_deref(),
+            Some("/scratch")
+        );
+        assert!(remote.host(Some("gone")).is_err());
+
+        let legacy: RemoteConfig = toml::from_str("host = \"me@box\"\n").unwrap();
+        assert_eq!(legacy.host(None).unwrap().destination(), "me@box");
+    }
+
+    #[test]
+    fn test_watch_args() {
+        let mut config = OptimizerConfig::default();
+        config.development.failed_tests_first = false;
+        assert_eq!(
+            config.watch_args(&[], false),
+            vec![
+                "watch",
+                "-c",
+                "-w",
+                "src",
+                "-w",
+                "Cargo.toml",
+                "-x",
+                "check --workspace --message-format=short",
+            ]
+        );
+
+        config.development.watch_clear_screen = false;
+        config.development.watch_ignore = vec!["*.md".to_string()];
+        config.development.auto_test_on_change = true;
+        config.development.watch_commands = vec![
+            WatchCommand::Cargo {
+                args: "clippy --all-targets".to_string(),
+            },
+            WatchCommand::Shell {
+                command: "make docs".to_string(),
+            },
+        ];
+        assert_eq!(
+            config.watch_args(&[PathBuf::from("crates")], false),
+            vec![
+                "watch",
+                "-w",
+                "crates",
+                "-i",
+                "*.md",
+                "-x",
+                "clippy --all-targets",
+                "-s",
+                "make docs",
+                "-x",
+                "test --workspace",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_watch_command_args() {
+        let filtered = WatchCommand::Test {
+            filter: Some("parser".to_string()),
+        };
+        assert_eq!(
+            filtered.to_watch_args(true, true),
+            vec!["-x", "test --workspace parser"]
+        );
+
+        let all = WatchCommand::Test { filter: None };
+        assert_eq!(
+            all.to_watch_args(false, false),
+            vec!["-x", "test --workspace"]
+        );
+        assert_eq!(
+            all.to_watch_args(true, false),
+            vec!["-s", "atlas --quiet build test --changed"]
+        );
+        assert_eq!(
+            all.to_watch_args(true, true),
+            vec!["-s", "atlas --quiet build test --changed --failed-first"]
+        );
+    }
 }
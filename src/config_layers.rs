@@ -0,0 +1,386 @@
+//! Layered Atlas configuration
+//!
+//! The effective configuration starts from the global config (or the
+//! defaults), then applies the project's `atlas.toml` and its uncommitted
+//! `.atlas/config.toml`, then settings given as command-line flags. Project
+//! files only need the keys they change, e.g. `[build] parallel_jobs = 4`.
+//! Each value remembers the layer it came from for `config show --effective`.
+
+use crate::config::OptimizerConfig;
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::manifest::Manifest;
+use crate::project_config::{ProjectConfig, PROJECT_CONFIG_FILE};
+use crate::utils::create_parent_dirs;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use toml::{Table, Value};
+
+/// Project settings kept out of the repository, overriding `atlas.toml`
+pub const LOCAL_CONFIG_FILE: &str = ".atlas/config.toml";
+
+static PROJECT_ROOT: OnceLock<Option<PathBuf>> = OnceLock::new();
+static FLAGS: OnceLock<Vec<FlagOverride>> = OnceLock::new();
+
+/// Where an effective setting came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigLayer {
+    Default,
+    Global(PathBuf),
+    Project(PathBuf),
+    Flag(String),
+}
+
+impl std::fmt::Display for ConfigLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::Global(path) | Self::Project(path) => write!(f, "{}", path.display()),
+            Self::Flag(flag) => write!(f, "{}", flag),
+        }
+    }
+}
+
+/// A setting given on the command line, e.g. `--locked` for `build.lockfile`
+#[derive(Debug, Clone)]
+pub struct FlagOverride {
+    pub flag: String,
+    /// Dotted key, e.g. `build.lockfile`
+    pub key: String,
+    pub value: Value,
+}
+
+/// Use the layers of `project_root` and the settings of command-line flags
+/// from now on; without `init` the project is found from the working directory
+pub fn init(project_root: Option<PathBuf>, flags: Vec<FlagOverride>) {
+    let _ = PROJECT_ROOT.set(project_root);
+    let _ = FLAGS.set(flags);
+}
+
+/// The effective configuration and the layer of each value
+#[derive(Debug, Clone)]
+pub struct LayeredConfig {
+    pub config: OptimizerConfig,
+    merged: Table,
+    sources: BTreeMap<String, ConfigLayer>,
+}
+
+impl LayeredConfig {
+    /// Load every layer for the project and flags given to [`init`]
+    pub fn load() -> OptimizerResult<Self> {
+        let project_root = match PROJECT_ROOT.get() {
+            Some(root) => root.clone(),
+            None => crate::utils::find_rust_project_root(".").ok(),
+        };
+        let flags = FLAGS.get().map(Vec::as_slice).unwrap_or_default();
+        Self::load_from(
+            Some(&OptimizerConfig::get_config_path()?),
+            project_root.as_deref(),
+            flags,
+        )
+    }
+
+    /// Load the global config at `global`, if it exists, then the project
+    /// files under `project_root`, then `flags`
+    pub fn load_from(
+        global: Option<&Path>,
+        project_root: Option<&Path>,
+        flags: &[FlagOverride],
+    ) -> OptimizerResult<Self> {
+        let mut sources = BTreeMap::new();
+        let base = match global.filter(|path| path.exists()) {
+            Some(path) => {
+                let config = OptimizerConfig::load_from_file(path)?;
+                let raw: Table = toml::from_str(&fs::read_to_string(path)?)?;
+                record_leaves(
+                    &raw,
+                    "",
+                    &ConfigLayer::Global(path.to_path_buf()),
+                    &mut sources,
+                );
+                config
+            }
+            None => OptimizerConfig::default(),
+        };
+        let mut merged = Table::try_from(&base)?;
+
+        for path in project_root.map(project_files).unwrap_or_default() {
+            if !path.exists() {
+                continue;
+            }
+            let mut layer: Table = toml::from_str(&fs::read_to_string(&path)?)
+                .map_err(|e| OptimizerError::config(format!("{}: {}", path.display(), e)))?;
+            // atlas.toml also holds the project's tasks, groups and the like
+            for key in project_only_keys()? {
+                layer.remove(&key);
+            }
+            record_leaves(
+                &layer,
+                "",
+                &ConfigLayer::Project(path.clone()),
+                &mut sources,
+            );
+            merge(&mut merged, layer);
+            // Blame the file that broke the config
+            Value::Table(merged.clone())
+                .try_into::<OptimizerConfig>()
+                .map_err(|e| OptimizerError::config(format!("{}: {}", path.display(), e)))?;
+        }
+
+        for flag in flags {
+            set(&mut merged, &flag.key, flag.value.clone());
+            sources.insert(flag.key.clone(), ConfigLayer::Flag(flag.flag.clone()));
+        }
+
+        let config = Value::Table(merged.clone()).try_into()?;
+        Ok(Self {
+            config,
+            merged,
+            sources,
+        })
+    }
+
+    /// Every effective setting as a dotted key, its value and its layer
+    pub fn entries(&self) -> Vec<(String, Value, ConfigLayer)> {
+        let mut leaves = Vec::new();
+        collect_leaves(&self.merged, "", &mut leaves);
+        leaves
+            .into_iter()
+            .map(|(key, value)| {
+                let layer = self.layer(&key);
+                (key, value, layer)
+            })
+            .collect()
+    }
+
+    /// Layer a dotted key came from
+    pub fn layer(&self, key: &str) -> ConfigLayer {
+        self.sources
+            .get(key)
+            .cloned()
+            .unwrap_or(ConfigLayer::Default)
+    }
+}
+
+/// Settings of `after` that differ from `before`, as dotted keys and values
+pub fn changed_settings(
+    before: &OptimizerConfig,
+    after: &OptimizerConfig,
+) -> OptimizerResult<Vec<(String, Value)>> {
+    let mut old = Vec::new();
+    collect_leaves(&Table::try_from(before)?, "", &mut old);
+    let old: BTreeMap<String, Value> = old.into_iter().collect();
+    let mut new = Vec::new();
+    collect_leaves(&Table::try_from(after)?, "", &mut new);
+    Ok(new
+        .into_iter()
+        .filter(|(key, value)| old.get(key) != Some(value))
+        .collect())
+}
+
+/// Set `key` of `table` to `value`, a TOML literal, in the project's
+/// `.atlas/config.toml`, which overrides every layer but flags
+pub fn save_local(
+    project_root: &Path,
+    table: &str,
+    key: &str,
+    value: &str,
+) -> OptimizerResult<PathBuf> {
+    let path = project_root.join(LOCAL_CONFIG_FILE);
+    let content = if path.exists() {
+        fs::read_to_string(&path)?
+    } else {
+        String::new()
+    };
+    let mut local = Manifest::parse(&content)?;
+    local.set_key(table, key, value)?;
+    create_parent_dirs(&path)?;
+    local.save(&path)?;
+    Ok(path)
+}
+
+/// Project layers, lowest precedence first
+pub fn project_files(project_root: &Path) -> Vec<PathBuf> {
+    vec![
+        project_root.join(PROJECT_CONFIG_FILE),
+        project_root.join(LOCAL_CONFIG_FILE),
+    ]
+}
+
+/// Top-level keys of `atlas.toml` that configure the project, not Atlas
+fn project_only_keys() -> OptimizerResult<Vec<String>> {
+    Ok(Table::try_from(ProjectConfig::default())?
+        .keys()
+        .cloned()
+        .collect())
+}
+
+/// Merge `layer` into `base`: tables merge key by key, anything else replaces
+fn merge(base: &mut Table, layer: Table) {
+    for (key, value) in layer {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(existing)), Value::Table(table)) => merge(existing, table),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+fn set(table: &mut Table, key: &str, value: Value) {
+    match key.split_once('.') {
+        Some((head, rest)) => {
+            let entry = table
+                .entry(head)
+                .or_insert_with(|| Value::Table(Table::new()));
+            if let Value::Table(nested) = entry {
+                set(nested, rest, value);
+            }
+        }
+        None => {
+            table.insert(key.to_string(), value);
+        }
+    }
+}
+
+fn dotted(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", prefix, key)
+    }
+}
+
+fn collect_leaves(table: &Table, prefix: &str, leaves: &mut Vec<(String, Value)>) {
+    for (key, value) in table {
+        let key = dotted(prefix, key);
+        match value {
+            Value::Table(nested) => collect_leaves(nested, &key, leaves),
+            value => leaves.push((key, value.clone())),
+        }
+    }
+}
+
+fn record_leaves(
+    table: &Table,
+    prefix: &str,
+    layer: &ConfigLayer,
+    sources: &mut BTreeMap<String, ConfigLayer>,
+) {
+    let mut leaves = Vec::new();
+    collect_leaves(table, prefix, &mut leaves);
+    for (key, _) in leaves {
+        sources.insert(key, layer.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_layers_override_global() {
+        let dir = tempfile::tempdir().unwrap();
+        let global = dir.path().join("config.toml");
+        let mut config = OptimizerConfig::default();
+        config.build.parallel_jobs = Some(16);
+        config.build.target_cpu = "x86-64-v3".to_string();
+        fs::write(&global, toml::to_string(&config).unwrap()).unwrap();
+
+        let project = dir.path().join("project");
+        fs::create_dir_all(project.join(".atlas")).unwrap();
+        fs::write(
+            project.join(PROJECT_CONFIG_FILE),
+            "[build]\nparallel_jobs = 4\n\n[tasks]\nci = \"build test\"\n",
+        )
+        .unwrap();
+        fs::write(project.join(LOCAL_CONFIG_FILE), "hints = false\n").unwrap();
+
+        let flags = [FlagOverride {
+            flag: "--locked".to_string(),
+            key: "build.lockfile".to_string(),
+            value: Value::String("locked".to_string()),
+        }];
+        let layered = LayeredConfig::load_from(Some(&global), Some(&project), &flags).unwrap();
+        assert_eq!(layered.config.build.parallel_jobs, Some(4));
+        assert_eq!(layered.config.build.target_cpu, "x86-64-v3");
+        assert!(!layered.config.hints);
+        assert_eq!(
+            layered.config.build.lockfile,
+            crate::lockfile::LockfileMode::Locked
+        );
+
+        assert_eq!(
+            layered.layer("build.parallel_jobs"),
+            ConfigLayer::Project(project.join(PROJECT_CONFIG_FILE))
+        );
+        assert_eq!(
+            layered.layer("build.target_cpu"),
+            ConfigLayer::Global(global)
+        );
+        assert_eq!(
+            layered.layer("hints"),
+            ConfigLayer::Project(project.join(LOCAL_CONFIG_FILE))
+        );
+        assert_eq!(
+            layered.layer("build.lockfile"),
+            ConfigLayer::Flag("--locked".to_string())
+        );
+        assert!(layered
+            .entries()
+            .iter()
+            .all(|(key, _, _)| !key.starts_with("tasks")));
+    }
+
+    #[test]
+    fn test_changed_settings() {
+        let before = OptimizerConfig::default();
+        let mut after = before.clone();
+        after.build.parallel_jobs = Some(4);
+        after.build.enable_sccache = !before.build.enable_sccache;
+        assert_eq!(
+            changed_settings(&before, &after).unwrap(),
+            vec![
+                (
+                    "build.enable_sccache".to_string(),
+                    Value::Boolean(after.build.enable_sccache)
+                ),
+                ("build.parallel_jobs".to_string(), Value::Integer(4)),
+            ]
+        );
+        assert!(changed_settings(&after, &after).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_save_local() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join(PROJECT_CONFIG_FILE),
+            "[build]\nparallel_jobs = 8\n",
+        )
+        .unwrap();
+
+        let path = save_local(dir.path(), "build", "parallel_jobs", "3").unwrap();
+        assert_eq!(path, dir.path().join(LOCAL_CONFIG_FILE));
+        let layered = LayeredConfig::load_from(None, Some(dir.path()), &[]).unwrap();
+        assert_eq!(layered.config.build.parallel_jobs, Some(3));
+        assert_eq!(
+            layered.layer("build.parallel_jobs"),
+            ConfigLayer::Project(path)
+        );
+    }
+
+    #[test]
+    fn test_broken_project_layer_is_named() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join(PROJECT_CONFIG_FILE),
+            "[build]\nparallel_jobs = \"many\"\n",
+        )
+        .unwrap();
+
+        let error = LayeredConfig::load_from(None, Some(dir.path()), &[]).unwrap_err();
+        assert!(error.to_string().contains(PROJECT_CONFIG_FILE));
+    }
+}
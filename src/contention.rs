@@ -0,0 +1,288 @@
+//! rust-analyzer and command-line builds competing for one target directory
+//!
+//! rust-analyzer runs `cargo check` on every save and builds build scripts
+//! and proc macros when it starts. Unless `rust-analyzer.cargo.targetDir` is
+//! set, it uses the same target directory as builds from the terminal: each
+//! waits for cargo's lock on the directory, and with different features or
+//! flags they keep invalidating each other's artifacts.
+
+use crate::utils::execute_command;
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A running process
+#[derive(Debug, Clone, PartialEq)]
+pub struct Process {
+    pub pid: u32,
+    pub parent: u32,
+    pub args: Vec<String>,
+    /// Working directory, where the platform reports it
+    pub cwd: Option<PathBuf>,
+}
+
+impl Process {
+    fn program(&self) -> &str {
+        let program = self.args.first().map(String::as_str).unwrap_or_default();
+        let name = program.rsplit(['/', '\\']).next().unwrap_or(program);
+        name.strip_suffix(".exe").unwrap_or(name)
+    }
+
+    fn is_rust_analyzer(&self) -> bool {
+        self.program() == "rust-analyzer"
+    }
+
+    fn is_cargo(&self) -> bool {
+        self.program() == "cargo"
+    }
+
+    fn has_target_dir(&self) -> bool {
+        self.args
+            .iter()
+            .any(|arg| arg == "--target-dir" || arg.starts_with("--target-dir="))
+    }
+
+    /// Whether it works in `project_root`, or may: an unknown directory counts
+    fn works_in(&self, project_root: &Path) -> bool {
+        match &self.cwd {
+            Some(cwd) => {
+                cwd.starts_with(project_root)
+                    || (project_root.starts_with(cwd) && cwd.join("Cargo.toml").is_file())
+            }
+            None => true,
+        }
+    }
+}
+
+/// Running processes: from `/proc` on Linux, `ps` on other Unix systems.
+/// Empty where neither is available.
+pub fn processes() -> Vec<Process> {
+    let proc = Path::new("/proc");
+    if proc.join("self").join("cmdline").is_file() {
+        return fs::read_dir(proc)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+                    .filter_map(proc_process)
+                    .collect()
+            })
+            .unwrap_or_default();
+    }
+    execute_command("ps", &["-axo", "pid=,ppid=,args="], None)
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| parse_ps(&String::from_utf8_lossy(&output.stdout)))
+        .unwrap_or_default()
+}
+
+fn proc_process(pid: u32) -> Option<Process> {
+    let dir = Path::new("/proc").join(pid.to_string());
+    let cmdline = fs::read(dir.join("cmdline")).ok()?;
+    let args: Vec<String> = cmdline
+        .split(|byte| *byte == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).to_string())
+        .collect();
+    // The parent is the second field after the parenthesized command name
+    let stat = fs::read_to_string(dir.join("stat")).ok()?;
+    let parent = stat
+        .rsplit_once(')')?
+        .1
+        .split_whitespace()
+        .nth(1)?
+        .parse()
+        .ok()?;
+    Some(Process {
+        pid,
+        parent,
+        args,
+        cwd: fs::read_link(dir.join("cwd")).ok(),
+    })
+}
+
+/// Parse `ps -axo pid=,ppid=,args=`; arguments with spaces cannot be told apart
+pub fn parse_ps(output: &str) -> Vec<Process> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            Some(Process {
+                pid: fields.next()?.parse().ok()?,
+                parent: fields.next()?.parse().ok()?,
+                args: fields.map(str::to_string).collect(),
+                cwd: None,
+            })
+        })
+        .collect()
+}
+
+/// rust-analyzer's activity in a project
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Contention {
+    /// rust-analyzer servers working on the project
+    pub analyzers: Vec<u32>,
+    /// cargo processes those servers started without a target directory of their own
+    pub shared_builds: Vec<u32>,
+}
+
+impl Contention {
+    pub fn detect(processes: &[Process], project_root: &Path) -> Self {
+        let project_root = project_root
+            .canonicalize()
+            .unwrap_or_else(|_| project_root.to_path_buf());
+        let analyzers: Vec<&Process> = processes
+            .iter()
+            .filter(|process| process.is_rust_analyzer() && process.works_in(&project_root))
+            .collect();
+        let shared_builds = processes
+            .iter()
+            .filter(|process| {
+                process.is_cargo()
+                    && !process.has_target_dir()
+                    && analyzers
+                        .iter()
+                        .any(|analyzer| analyzer.pid == process.parent)
+            })
+            .map(|process| process.pid)
+            .collect();
+        Self {
+            analyzers: analyzers.iter().map(|process| process.pid).collect(),
+            shared_builds,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.analyzers.is_empty()
+    }
+}
+
+/// Whether the project's VS Code settings give rust-analyzer its own target
+/// directory: `None` when they do not say
+pub fn editor_separates(project_root: &Path) -> Option<bool> {
+    let settings = fs::read_to_string(project_root.join(".vscode").join("settings.json")).ok()?;
+    // Settings files may have comments, so they are not parsed as JSON
+    let setting = Regex::new(r#""rust-analyzer\.cargo\.targetDir"\s*:\s*(true|false|"[^"]*")"#)
+        .expect("valid settings regex");
+    let value = setting.captures(&settings)?.get(1)?.as_str();
+    Some(value != "false" && value != "\"\"")
+}
+
+/// Profile directories of `target_dir` whose cargo lock another process holds
+pub fn locked_dirs(target_dir: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    for entry in fs::read_dir(target_dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        if path.join(".cargo-lock").is_file() {
+            dirs.push(path);
+        } else if path.is_dir() {
+            dirs.extend(
+                fs::read_dir(&path)
+                    .into_iter()
+                    .flatten()
+                    .flatten()
+                    .map(|entry| entry.path())
+                    .filter(|path| path.join(".cargo-lock").is_file()),
+            );
+        }
+    }
+    dirs.retain(|dir| is_locked(&dir.join(".cargo-lock")));
+    dirs.sort();
+    dirs
+}
+
+/// Whether another process holds the lock cargo takes on `lock_file`
+#[cfg(unix)]
+fn is_locked(lock_file: &Path) -> bool {
+    use std::os::unix::io::AsRawFd;
+    let Ok(file) = fs::File::open(lock_file) else {
+        return false;
+    };
+    // Released again when the file is closed
+    let result = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) };
+    result != 0
+}
+
+#[cfg(not(unix))]
+fn is_locked(_lock_file: &Path) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, parent: u32, command: &str, cwd: &str) -> Process {
+        Process {
+            pid,
+            parent,
+            args: command.split(' ').map(str::to_string).collect(),
+            cwd: Some(PathBuf::from(cwd)),
+        }
+    }
+
+    #[test]
+    fn test_detect_contention() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().to_str().unwrap();
+        let processes = vec![
+            process(
+                10,
+                1,
+                "/home/me/.vscode/extensions/rust-analyzer/server/rust-analyzer",
+                root,
+            ),
+            process(
+                11,
+                10,
+                "/home/me/.cargo/bin/cargo check --workspace --message-format=json",
+                root,
+            ),
+            process(
+                12,
+                10,
+                "cargo check --workspace --target-dir target/rust-analyzer",
+                root,
+            ),
+            process(20, 1, "rust-analyzer", "/elsewhere/other-project"),
+            process(
+                21,
+                20,
+                "cargo check --workspace",
+                "/elsewhere/other-project",
+            ),
+            process(30, 1, "cargo build", root),
+        ];
+        let contention = Contention::detect(&processes, project.path());
+        assert_eq!(contention.analyzers, vec![10]);
+        assert_eq!(contention.shared_builds, vec![11]);
+        assert!(!Contention::detect(&processes[3..], project.path()).is_running());
+
+        let ps =
+            parse_ps("  412     1 /usr/bin/rust-analyzer\n  413   412 cargo check --workspace\n");
+        assert_eq!(ps[1].parent, 412);
+        assert!(ps[0].is_rust_analyzer() && ps[0].cwd.is_none());
+    }
+
+    #[test]
+    fn test_editor_separates() {
+        let project = tempfile::tempdir().unwrap();
+        assert_eq!(editor_separates(project.path()), None);
+        fs::create_dir(project.path().join(".vscode")).unwrap();
+        let settings = project.path().join(".vscode").join("settings.json");
+        fs::write(
+            &settings,
+            "{\n  // own target dir\n  \"rust-analyzer.cargo.targetDir\": true,\n}\n",
+        )
+        .unwrap();
+        assert_eq!(editor_separates(project.path()), Some(true));
+        fs::write(
+            &settings,
+            "{ \"rust-analyzer.cargo.targetDir\": \"target/ra\" }",
+        )
+        .unwrap();
+        assert_eq!(editor_separates(project.path()), Some(true));
+        fs::write(&settings, "{ \"rust-analyzer.cargo.targetDir\": false }").unwrap();
+        assert_eq!(editor_separates(project.path()), Some(false));
+    }
+}
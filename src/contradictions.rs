@@ -0,0 +1,848 @@
+//! Settings made in more than one place with different values
+//!
+//! The same build setting can come from the environment, any Cargo config
+//! file from the project up to `$CARGO_HOME`, `Cargo.toml` profiles and the
+//! Atlas config. Cargo quietly picks one, so the others look like they apply
+//! but do not. Each [`Contradiction`] lists every place a setting is made,
+//! highest precedence first, with the steps that leave only one value.
+//!
+//! The plan keeps the value Atlas manages for the project when there is one,
+//! since `atlas refresh` would write it back; otherwise it keeps the value
+//! that applies today.
+
+use crate::config::OptimizerConfig;
+use crate::error::OptimizerResult;
+use crate::manifest::Manifest;
+use crate::rustflags::{
+    config_files, ConfigFlags, Conflict, FlagsKey, RustflagsLayers, ENCODED_RUSTFLAGS, RUSTFLAGS,
+};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    Incremental,
+    TargetDir,
+    Rustflags,
+}
+
+impl fmt::Display for Setting {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Incremental => "incremental",
+            Self::TargetDir => "target-dir",
+            Self::Rustflags => "rustflags",
+        })
+    }
+}
+
+/// Where a value was set
+#[derive(Debug, Clone, PartialEq)]
+pub enum Origin {
+    Env(String),
+    CargoConfig {
+        path: PathBuf,
+        /// Dotted key, e.g. `build.incremental`
+        key: String,
+        /// Written by the Atlas block of the file
+        managed: bool,
+    },
+    Profile {
+        path: PathBuf,
+        profile: String,
+    },
+    Atlas(&'static str),
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Env(name) => write!(f, "${}", name),
+            Self::CargoConfig { path, key, managed } => {
+                write!(f, "{} {}", path.display(), key)?;
+                if *managed {
+                    f.write_str(" (Atlas block)")?;
+                }
+                Ok(())
+            }
+            Self::Profile { path, profile } => {
+                write!(f, "{} [profile.{}]", path.display(), profile)
+            }
+            Self::Atlas(key) => write!(f, "Atlas config {}", key),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layer {
+    pub origin: Origin,
+    pub value: String,
+}
+
+impl Layer {
+    fn is_managed(&self) -> bool {
+        matches!(self.origin, Origin::CargoConfig { managed: true, .. })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contradiction {
+    pub setting: Setting,
+    /// Every place the setting is made, highest precedence first
+    pub layers: Vec<Layer>,
+    pub explanation: String,
+    /// Resolution plan, in order
+    pub steps: Vec<String>,
+}
+
+/// The value of a setting that applies to one profile
+#[derive(Debug, Clone, PartialEq)]
+pub struct Effective {
+    pub profile: String,
+    pub enabled: bool,
+    /// Where it is set; `None` for Cargo's default
+    pub origin: Option<Origin>,
+}
+
+/// A parsed Cargo config file
+struct CargoConfigFile {
+    path: PathBuf,
+    value: toml::Value,
+    manifest: Manifest,
+}
+
+impl CargoConfigFile {
+    fn get(&self, table: &str, key: &str) -> Option<&toml::Value> {
+        table
+            .split('.')
+            .try_fold(&self.value, |value, part| value.get(part))?
+            .get(key)
+    }
+
+    fn origin(&self, table: &str, key: &str) -> Origin {
+        Origin::CargoConfig {
+            path: self.path.clone(),
+            key: format!("{}.{}", table, key),
+            managed: self.manifest.is_managed(table, key),
+        }
+    }
+}
+
+/// Everything a build's settings can come from
+pub struct Sources {
+    env: BTreeMap<String, String>,
+    project_root: PathBuf,
+    /// Highest precedence first
+    cargo_configs: Vec<CargoConfigFile>,
+    manifest: Option<(PathBuf, toml::Value)>,
+    incremental: bool,
+    target_dirs: BTreeMap<String, PathBuf>,
+    host: Option<String>,
+}
+
+impl Sources {
+    pub fn detect(
+        project_root: &Path,
+        config: &OptimizerConfig,
+        host: Option<String>,
+    ) -> OptimizerResult<Self> {
+        let env = std::env::vars()
+            .filter(|(name, _)| is_build_variable(name))
+            .collect();
+        let mut files = Vec::new();
+        for path in config_files(project_root) {
+            let content = fs::read_to_string(&path)?;
+            files.push((path, content));
+        }
+        let manifest_path = project_root.join("Cargo.toml");
+        let manifest = fs::read_to_string(&manifest_path)
+            .ok()
+            .map(|content| (manifest_path, content));
+        Self::new(env, project_root, files, manifest, config, host)
+    }
+
+    fn new(
+        env: BTreeMap<String, String>,
+        project_root: &Path,
+        cargo_configs: Vec<(PathBuf, String)>,
+        manifest: Option<(PathBuf, String)>,
+        config: &OptimizerConfig,
+        host: Option<String>,
+    ) -> OptimizerResult<Self> {
+        let mut files = Vec::new();
+        for (path, content) in cargo_configs {
+            files.push(CargoConfigFile {
+                value: toml::from_str(&content)?,
+                manifest: Manifest::parse(&content)?,
+                path,
+            });
+        }
+        let manifest = match manifest {
+            Some((path, content)) => Some((path, toml::from_str(&content)?)),
+            None => None,
+        };
+        Ok(Self {
+            env,
+            project_root: project_root.to_path_buf(),
+            cargo_configs: files,
+            manifest,
+            incremental: config.build.incremental,
+            target_dirs: config.build.profile_target_dirs.clone(),
+            host,
+        })
+    }
+
+    /// Where each setting is made, for an overview of the sources
+    pub fn files(&self) -> Vec<&Path> {
+        self.cargo_configs
+            .iter()
+            .map(|file| file.path.as_path())
+            .chain(self.manifest.iter().map(|(path, _)| path.as_path()))
+            .collect()
+    }
+
+    /// Build-related variables set in the environment
+    pub fn variables(&self) -> Vec<&str> {
+        self.env.keys().map(String::as_str).collect()
+    }
+
+    pub fn contradictions(&self) -> Vec<Contradiction> {
+        let mut contradictions = Vec::new();
+        contradictions.extend(self.incremental());
+        contradictions.extend(self.target_dir());
+        contradictions.extend(self.rustflags());
+        contradictions
+    }
+
+    /// Cargo's built-in profiles, then those the project defines
+    pub fn profiles(&self) -> Vec<String> {
+        let mut profiles: Vec<String> = ["dev", "test", "release", "bench"]
+            .iter()
+            .map(|profile| profile.to_string())
+            .collect();
+        let defined = self
+            .cargo_configs
+            .iter()
+            .map(|file| &file.value)
+            .chain(self.manifest.iter().map(|(_, manifest)| manifest))
+            .filter_map(|value| value.get("profile").and_then(toml::Value::as_table))
+            .flat_map(|table| table.keys());
+        for profile in defined {
+            if !profiles.contains(profile) {
+                profiles.push(profile.clone());
+            }
+        }
+        profiles
+    }
+
+    /// Whether `profile` builds incrementally, resolved the way Cargo does:
+    /// the environment, then `build.incremental`, then the profile and the
+    /// profiles it inherits from, then Cargo's default
+    pub fn incremental_for(&self, profile: &str) -> Effective {
+        let effective = |enabled, origin| Effective {
+            profile: profile.to_string(),
+            enabled,
+            origin,
+        };
+        if let Some(value) = self.env.get("CARGO_INCREMENTAL") {
+            return effective(
+                value != "0",
+                Some(Origin::Env("CARGO_INCREMENTAL".to_string())),
+            );
+        }
+        if let Some(value) = self.env.get("CARGO_BUILD_INCREMENTAL") {
+            return effective(
+                value == "true",
+                Some(Origin::Env("CARGO_BUILD_INCREMENTAL".to_string())),
+            );
+        }
+        for file in &self.cargo_configs {
+            if let Some(value) = file
+                .get("build", "incremental")
+                .and_then(toml::Value::as_bool)
+            {
+                return effective(value, Some(file.origin("build", "incremental")));
+            }
+        }
+
+        let mut current = profile.to_string();
+        // A profile inheriting from itself is an error Cargo reports; stop anyway
+        for _ in 0..8 {
+            let variable = format!(
+                "CARGO_PROFILE_{}_INCREMENTAL",
+                current.to_uppercase().replace('-', "_")
+            );
+            if let Some(value) = self.env.get(&variable) {
+                return effective(value == "true", Some(Origin::Env(variable)));
+            }
+            let table = format!("profile.{}", current);
+            for file in &self.cargo_configs {
+                if let Some(value) = file
+                    .get(&table, "incremental")
+                    .and_then(toml::Value::as_bool)
+                {
+                    return effective(value, Some(file.origin(&table, "incremental")));
+                }
+            }
+            let manifest_profile = self
+                .manifest
+                .as_ref()
+                .and_then(|(path, manifest)| Some((path, manifest.get("profile")?.get(&current)?)));
+            if let Some((path, settings)) = manifest_profile {
+                if let Some(value) = settings.get("incremental").and_then(toml::Value::as_bool) {
+                    return effective(
+                        value,
+                        Some(Origin::Profile {
+                            path: path.clone(),
+                            profile: current.clone(),
+                        }),
+                    );
+                }
+            }
+
+            let inherits = self
+                .cargo_configs
+                .iter()
+                .find_map(|file| file.get(&table, "inherits"))
+                .or_else(|| manifest_profile.and_then(|(_, settings)| settings.get("inherits")))
+                .and_then(toml::Value::as_str)
+                .map(str::to_string);
+            current = match current.as_str() {
+                "dev" => break,
+                "release" => return effective(false, None),
+                "test" => "dev".to_string(),
+                "bench" => "release".to_string(),
+                _ => match inherits {
+                    Some(inherits) => inherits,
+                    None => break,
+                },
+            };
+        }
+        effective(true, None)
+    }
+
+    fn incremental(&self) -> Option<Contradiction> {
+        let mut layers = Vec::new();
+        if let Some(value) = self.env.get("CARGO_INCREMENTAL") {
+            layers.push(Layer {
+                origin: Origin::Env("CARGO_INCREMENTAL".to_string()),
+                value: (value != "0").to_string(),
+            });
+        }
+        if let Some(value) = self.env.get("CARGO_BUILD_INCREMENTAL") {
+            layers.push(Layer {
+                origin: Origin::Env("CARGO_BUILD_INCREMENTAL".to_string()),
+                value: value.clone(),
+            });
+        }
+        for file in &self.cargo_configs {
+            if let Some(value) = file.get("build", "incremental") {
+                layers.push(Layer {
+                    origin: file.origin("build", "incremental"),
+                    value: value.to_string(),
+                });
+            }
+        }
+        // Profiles only disagree with each other by design; they matter once
+        // something above overrides all of them
+        if layers.is_empty() {
+            return None;
+        }
+        if let Some((path, manifest)) = &self.manifest {
+            let profiles = manifest.get("profile").and_then(toml::Value::as_table);
+            for (profile, settings) in profiles.into_iter().flatten() {
+                if let Some(value) = settings.get("incremental") {
+                    layers.push(Layer {
+                        origin: Origin::Profile {
+                            path: path.clone(),
+                            profile: profile.clone(),
+                        },
+                        value: value.to_string(),
+                    });
+                }
+            }
+        }
+        layers.push(Layer {
+            origin: Origin::Atlas("build.incremental"),
+            value: self.incremental.to_string(),
+        });
+
+        plan(
+            Setting::Incremental,
+            layers,
+            "Incremental compilation is switched on in some places and off in others",
+        )
+    }
+
+    fn target_dir(&self) -> Option<Contradiction> {
+        let mut layers = Vec::new();
+        for name in ["CARGO_TARGET_DIR", "CARGO_BUILD_TARGET_DIR"] {
+            if let Some(value) = self.env.get(name) {
+                layers.push(Layer {
+                    origin: Origin::Env(name.to_string()),
+                    value: self.project_root.join(value).display().to_string(),
+                });
+            }
+        }
+        for file in &self.cargo_configs {
+            if let Some(value) = file
+                .get("build", "target-dir")
+                .and_then(toml::Value::as_str)
+            {
+                // Relative to the directory holding `.cargo`
+                let base = file
+                    .path
+                    .parent()
+                    .and_then(Path::parent)
+                    .unwrap_or(&self.project_root);
+                layers.push(Layer {
+                    origin: file.origin("build", "target-dir"),
+                    value: base.join(value).display().to_string(),
+                });
+            }
+        }
+        // Atlas routes profiles through CARGO_TARGET_DIR unless it is already set
+        if self.env.contains_key("CARGO_TARGET_DIR") && !self.target_dirs.is_empty() {
+            let routes: Vec<String> = self
+                .target_dirs
+                .iter()
+                .map(|(profile, dir)| format!("{} → {}", profile, dir.display()))
+                .collect();
+            layers.push(Layer {
+                origin: Origin::Atlas("build.profile_target_dirs"),
+                value: routes.join(", "),
+            });
+        }
+
+        plan(
+            Setting::TargetDir,
+            layers,
+            "Build output is sent to more than one target directory",
+        )
+    }
+
+    fn rustflags(&self) -> Vec<Contradiction> {
+        let files: Vec<(String, toml::Value)> = self
+            .cargo_configs
+            .iter()
+            .map(|file| (file.path.display().to_string(), file.value.clone()))
+            .collect();
+        let rustflags = RustflagsLayers::from_sources(
+            |name| self.env.get(name).cloned(),
+            &files,
+            self.host.as_deref(),
+        );
+
+        let mut contradictions = Vec::new();
+        for conflict in rustflags.conflicts() {
+            let mut layers = Vec::new();
+            let mut steps = Vec::new();
+            match &conflict {
+                Conflict::EnvOverridesConfig { variable, .. } => {
+                    let flags = rustflags.env.iter().flat_map(|env| env.flags.clone());
+                    layers.push(Layer {
+                        origin: Origin::Env(variable.to_string()),
+                        value: flags.collect::<Vec<_>>().join(" "),
+                    });
+                    steps.push(format!(
+                        "Unset {} and move its flags into the rustflags of the project's .cargo/config.toml",
+                        variable
+                    ));
+                    steps.push(format!(
+                        "Or keep it: `atlas build` passes both through {}, plain cargo does not",
+                        ENCODED_RUSTFLAGS
+                    ));
+                }
+                Conflict::TargetOverridesBuild { ignored } => {
+                    let host = self.host.as_deref().unwrap_or("<triple>");
+                    layers.extend(
+                        rustflags
+                            .config
+                            .iter()
+                            .filter(|source| source.key == FlagsKey::Target)
+                            .map(|source| self.flags_layer(source)),
+                    );
+                    steps.extend(ignored.iter().map(|source| {
+                        format!(
+                            "Move `{}` from build.rustflags to target.{}.rustflags in {}",
+                            source.flags.join(" "),
+                            host,
+                            source.origin
+                        )
+                    }));
+                }
+            }
+            layers.extend(
+                conflict
+                    .ignored()
+                    .iter()
+                    .map(|source| self.flags_layer(source)),
+            );
+            contradictions.push(Contradiction {
+                setting: Setting::Rustflags,
+                layers,
+                explanation: conflict.explanation(),
+                steps,
+            });
+        }
+
+        // Flags repeated among those rustc is actually given
+        let applied: Vec<(Origin, &[String])> = match &rustflags.env {
+            Some(env) => vec![(Origin::Env(env.variable.to_string()), env.flags.as_slice())],
+            None => rustflags
+                .config
+                .iter()
+                .filter(|source| source.key == rustflags.config_key())
+                .rev()
+                .map(|source| (self.flags_layer(source).origin, source.flags.as_slice()))
+                .collect(),
+        };
+        contradictions.extend(repeated_flags(&applied).into_iter().map(|(option, uses)| {
+            let last = uses
+                .last()
+                .map(|(_, flag)| flag.clone())
+                .unwrap_or_default();
+            let same = uses.iter().all(|(_, flag)| *flag == last);
+            let explanation = if same {
+                format!("`{}` is passed to rustc {} times", last, uses.len())
+            } else {
+                format!(
+                    "`{}` is given {} different values; rustc uses the last, `{}`",
+                    option,
+                    uses.len(),
+                    last
+                )
+            };
+            // The last use is the one that counts
+            let layers: Vec<Layer> = uses
+                .into_iter()
+                .rev()
+                .map(|(origin, value)| Layer { origin, value })
+                .collect();
+            let steps = layers[1..]
+                .iter()
+                .map(|layer| format!("Remove `{}` from {}", layer.value, layer.origin))
+                .collect();
+            Contradiction {
+                setting: Setting::Rustflags,
+                layers,
+                explanation,
+                steps,
+            }
+        }));
+        contradictions
+    }
+
+    /// Rustflags from a config file or config environment variable
+    fn flags_layer(&self, source: &ConfigFlags) -> Layer {
+        let table = match source.key {
+            FlagsKey::Target => target_table(self.host.as_deref().unwrap_or_default()),
+            FlagsKey::Build => "build".to_string(),
+        };
+        let origin = match self
+            .cargo_configs
+            .iter()
+            .find(|file| file.path.display().to_string() == source.origin)
+        {
+            Some(file) => file.origin(&table, "rustflags"),
+            None => Origin::Env(source.origin.clone()),
+        };
+        Layer {
+            origin,
+            value: source.flags.join(" "),
+        }
+    }
+}
+
+/// Variables that change the settings checked here
+fn is_build_variable(name: &str) -> bool {
+    name.starts_with("CARGO_BUILD_")
+        || name.starts_with("CARGO_TARGET_")
+        || name.starts_with("CARGO_PROFILE_")
+        || [RUSTFLAGS, ENCODED_RUSTFLAGS, "CARGO_INCREMENTAL"].contains(&name)
+}
+
+fn target_table(host: &str) -> String {
+    format!("target.{}", host)
+}
+
+/// A contradiction when the layers hold more than one value, with steps
+/// leaving only the one kept
+fn plan(setting: Setting, layers: Vec<Layer>, explanation: &str) -> Option<Contradiction> {
+    let first = layers.first()?;
+    if layers.iter().all(|layer| layer.value == first.value) {
+        return None;
+    }
+    let managed = layers.iter().find(|layer| layer.is_managed());
+    let keep = match managed {
+        // The Atlas config is what `atlas refresh` writes into its block
+        Some(_) if setting == Setting::Incremental => layers
+            .iter()
+            .find(|layer| matches!(layer.origin, Origin::Atlas(_)))
+            .or(managed),
+        Some(_) => managed,
+        None => Some(first),
+    }
+    .map(|layer| layer.value.clone())
+    .unwrap_or_default();
+
+    let steps = layers
+        .iter()
+        .filter(|layer| layer.value != keep)
+        .map(|layer| match &layer.origin {
+            Origin::Env(name) => format!(
+                "Unset {} where it is exported (shell profile, CI settings)",
+                name
+            ),
+            Origin::CargoConfig {
+                path,
+                managed: true,
+                ..
+            } => format!(
+                "Run `atlas refresh` to rewrite the Atlas block in {}",
+                path.display()
+            ),
+            Origin::CargoConfig { path, key, .. } => {
+                format!("Remove `{}` from {}", key, path.display())
+            }
+            Origin::Profile { path, profile } => format!(
+                "Remove `incremental` from [profile.{}] in {}; the setting above applies to every profile",
+                profile,
+                path.display()
+            ),
+            Origin::Atlas(key) if setting == Setting::Incremental => format!(
+                "Set `{} = {}` in the Atlas config (`atlas config edit`)",
+                key, keep
+            ),
+            Origin::Atlas(key) => format!(
+                "Remove `{}` from the Atlas config (`atlas config edit`); it is ignored while CARGO_TARGET_DIR is set",
+                key
+            ),
+        })
+        .collect();
+
+    Some(Contradiction {
+        setting,
+        explanation: format!("{}; {} applies: {}", explanation, first.origin, first.value),
+        layers,
+        steps,
+    })
+}
+
+/// rustc options given more than once, with the origin and text of each use
+/// in the order rustc sees them
+fn repeated_flags(applied: &[(Origin, &[String])]) -> Vec<(String, Vec<(Origin, String)>)> {
+    let mut options: Vec<(String, Vec<(Origin, String)>)> = Vec::new();
+    for (origin, flags) in applied {
+        for flag in options_of(flags) {
+            let key = option_key(&flag);
+            let position = match options.iter().position(|(existing, _)| *existing == key) {
+                Some(position) => position,
+                None => {
+                    options.push((key, Vec::new()));
+                    options.len() - 1
+                }
+            };
+            options[position].1.push((origin.clone(), flag));
+        }
+    }
+    options.retain(|(_, uses)| uses.len() > 1);
+    options
+}
+
+/// Flags joined with their values, e.g. `-C opt-level=3` for `-Copt-level=3`
+fn options_of(flags: &[String]) -> Vec<String> {
+    const WITH_VALUE: &[&str] = &[
+        "-C",
+        "-Z",
+        "--cfg",
+        "-L",
+        "-l",
+        "-A",
+        "-W",
+        "-D",
+        "-F",
+        "--cap-lints",
+        "--check-cfg",
+    ];
+    let mut options = Vec::new();
+    let mut flags = flags.iter();
+    while let Some(flag) = flags.next() {
+        if WITH_VALUE.contains(&flag.as_str()) {
+            match flags.next() {
+                Some(value) => options.push(format!("{} {}", flag, value)),
+                None => options.push(flag.clone()),
+            }
+        } else if let Some(value) = ["-C", "-Z"]
+            .iter()
+            .find_map(|prefix| flag.strip_prefix(prefix).map(|value| (prefix, value)))
+        {
+            options.push(format!("{} {}", value.0, value.1));
+        } else {
+            options.push(flag.clone());
+        }
+    }
+    options
+}
+
+/// What makes two options the same setting: the name of a codegen or
+/// unstable option, which rustc keeps only once; the whole option otherwise
+fn option_key(option: &str) -> String {
+    const REPEATABLE: &[&str] = &["link-arg", "link-args", "llvm-args", "pre-link-arg"];
+    match option.split_once(' ') {
+        Some((prefix @ ("-C" | "-Z"), value)) => {
+            let name = value.split('=').next().unwrap_or(value);
+            if REPEATABLE.contains(&name) {
+                option.to_string()
+            } else {
+                format!("{} {}", prefix, name)
+            }
+        }
+        _ => option.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::managed::{render_block, Stamp, CARGO_CONFIG_BLOCK};
+
+    const HOST: &str = "x86_64-unknown-linux-gnu";
+
+    fn sources(env: &[(&str, &str)], files: &[(&str, &str)], manifest: &str) -> Sources {
+        Sources::new(
+            env.iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            Path::new("/work/app"),
+            files
+                .iter()
+                .map(|(path, content)| (PathBuf::from(path), content.to_string()))
+                .collect(),
+            Some((PathBuf::from("/work/app/Cargo.toml"), manifest.to_string())),
+            &OptimizerConfig::default(),
+            Some(HOST.to_string()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_incremental_per_profile() {
+        let manifest = "[package]\nname = \"app\"\n\n[profile.release]\nincremental = true\n\n[profile.ci]\ninherits = \"release\"\n\n[profile.fast]\ninherits = \"dev\"\n";
+        let defaults = sources(&[], &[], manifest);
+        assert_eq!(
+            defaults.profiles(),
+            ["dev", "test", "release", "bench", "ci", "fast"]
+        );
+        let enabled = |profile: &str| defaults.incremental_for(profile).enabled;
+        assert!(enabled("dev") && enabled("test") && enabled("fast"));
+        assert!(enabled("release") && enabled("ci"));
+        assert!(defaults.incremental_for("dev").origin.is_none());
+        assert_eq!(
+            defaults.incremental_for("ci").origin.unwrap().to_string(),
+            "/work/app/Cargo.toml [profile.release]"
+        );
+        assert!(
+            !sources(&[], &[], "[package]\nname = \"app\"\n")
+                .incremental_for("bench")
+                .enabled
+        );
+
+        // build.incremental overrides every profile
+        let overridden = sources(
+            &[("CARGO_PROFILE_DEV_INCREMENTAL", "false")],
+            &[(
+                "/home/me/.cargo/config.toml",
+                "[build]\nincremental = false\n",
+            )],
+            manifest,
+        );
+        assert!(!overridden.incremental_for("release").enabled);
+    }
+
+    #[test]
+    fn test_incremental_and_target_dir() {
+        let config = OptimizerConfig::default();
+        let project = render_block(
+            CARGO_CONFIG_BLOCK,
+            "[build]\nincremental = true\ntarget-dir = \"target\"\n",
+            &Stamp::current(&config).unwrap(),
+        );
+        let sources = sources(
+            &[
+                ("CARGO_INCREMENTAL", "0"),
+                ("CARGO_TARGET_DIR", "/tmp/shared"),
+            ],
+            &[
+                ("/work/app/.cargo/config.toml", &project),
+                (
+                    "/home/me/.cargo/config.toml",
+                    "[build]\ntarget-dir = \"/tmp/shared\"\n",
+                ),
+            ],
+            "[package]\nname = \"app\"\n\n[profile.release]\nincremental = false\n",
+        );
+        let contradictions = sources.contradictions();
+        assert_eq!(contradictions.len(), 2);
+
+        let incremental = &contradictions[0];
+        assert_eq!(incremental.setting, Setting::Incremental);
+        assert_eq!(incremental.layers.len(), 4);
+        assert_eq!(incremental.layers[0].value, "false");
+        assert!(incremental
+            .explanation
+            .ends_with("$CARGO_INCREMENTAL applies: false"));
+        // Atlas wants it on, so the variable and the profile go
+        assert_eq!(incremental.steps.len(), 2);
+        assert!(incremental.steps[0].starts_with("Unset CARGO_INCREMENTAL"));
+        assert!(incremental.steps[1].contains("[profile.release]"));
+
+        let target_dir = &contradictions[1];
+        assert_eq!(target_dir.setting, Setting::TargetDir);
+        assert_eq!(target_dir.layers[1].value, "/work/app/target");
+        assert!(target_dir.layers[1].is_managed());
+        assert_eq!(
+            target_dir.steps,
+            vec![
+                "Unset CARGO_TARGET_DIR where it is exported (shell profile, CI settings)",
+                "Remove `build.target-dir` from /home/me/.cargo/config.toml",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rustflags_contradictions() {
+        let home = "[target.x86_64-unknown-linux-gnu]\nrustflags = [\"-C\", \"link-arg=-fuse-ld=mold\", \"-Ctarget-cpu=native\"]\n";
+        let project = "[target.x86_64-unknown-linux-gnu]\nrustflags = [\"-C\", \"link-arg=-fuse-ld=mold\", \"-C\", \"target-cpu=x86-64-v3\", \"-C\", \"link-arg=-Wl,--as-needed\"]\n[build]\nrustflags = [\"--cfg\", \"tokio_unstable\"]\n";
+        let sources = sources(
+            &[],
+            &[
+                ("/work/app/.cargo/config.toml", project),
+                ("/home/me/.cargo/config.toml", home),
+            ],
+            "[package]\nname = \"app\"\n",
+        );
+        let contradictions = sources.contradictions();
+        assert_eq!(contradictions.len(), 3);
+        assert!(contradictions[0]
+            .explanation
+            .contains("ignores build.rustflags"));
+        assert!(contradictions[0].steps[0].starts_with("Move `--cfg tokio_unstable`"));
+        assert_eq!(
+            contradictions[1].explanation,
+            "`-C link-arg=-fuse-ld=mold` is passed to rustc 2 times"
+        );
+        assert_eq!(
+            contradictions[2].explanation,
+            "`-C target-cpu` is given 2 different values; rustc uses the last, `-C target-cpu=x86-64-v3`"
+        );
+        assert_eq!(
+            contradictions[2].steps,
+            vec!["Remove `-C target-cpu=native` from /home/me/.cargo/config.toml target.x86_64-unknown-linux-gnu.rustflags"]
+        );
+
+        assert!(self::sources(&[], &[], "").contradictions().is_empty());
+    }
+}
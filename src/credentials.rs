@@ -0,0 +1,236 @@
+//! Credentials referenced from the Atlas config
+//!
+//! Tokens for private registries and remote cache backends do not belong in
+//! plain-text TOML. The config names them instead: `keychain:<name>` is read
+//! from the OS keychain (the login keychain on macOS, the Secret Service via
+//! `secret-tool` on Linux), `env:<VARIABLE>` from another environment
+//! variable, such as a CI secret. Values are only read when a process that
+//! needs them starts, and are redacted from everything Atlas writes.
+
+use crate::error::{OptimizerError, OptimizerResult};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use std::sync::Mutex;
+
+/// Keychain service Atlas stores its secrets under
+const SERVICE: &str = "atlas";
+
+/// Secret values read so far, for redaction
+static RESOLVED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Where a secret is kept
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum SecretRef {
+    Keychain(String),
+    Env(String),
+}
+
+impl FromStr for SecretRef {
+    type Err = OptimizerError;
+
+    fn from_str(reference: &str) -> OptimizerResult<Self> {
+        let secret = match reference.split_once(':') {
+            Some(("keychain", name)) if !name.is_empty() => Self::Keychain(name.to_string()),
+            Some(("env", name)) if !name.is_empty() => Self::Env(name.to_string()),
+            _ => {
+                return Err(OptimizerError::config(format!(
+                    "Invalid secret reference '{}': use keychain:<name> or env:<VARIABLE>",
+                    reference
+                )))
+            }
+        };
+        Ok(secret)
+    }
+}
+
+impl TryFrom<String> for SecretRef {
+    type Error = OptimizerError;
+
+    fn try_from(reference: String) -> OptimizerResult<Self> {
+        reference.parse()
+    }
+}
+
+impl From<SecretRef> for String {
+    fn from(secret: SecretRef) -> Self {
+        secret.to_string()
+    }
+}
+
+impl fmt::Display for SecretRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Keychain(name) => write!(f, "keychain:{}", name),
+            Self::Env(name) => write!(f, "env:{}", name),
+        }
+    }
+}
+
+impl SecretRef {
+    /// The secret's value
+    pub fn resolve(&self) -> OptimizerResult<String> {
+        let value = match self {
+            Self::Env(name) => std::env::var(name)
+                .map_err(|_| OptimizerError::config(format!("{} is not set", name)))?,
+            Self::Keychain(name) => read(name)?,
+        };
+        if let Ok(mut resolved) = RESOLVED.lock() {
+            resolved.push(value.clone());
+        }
+        Ok(value)
+    }
+}
+
+/// Secret values read by this process
+pub fn resolved() -> Vec<String> {
+    RESOLVED
+        .lock()
+        .map(|resolved| resolved.clone())
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Action {
+    Read,
+    Store,
+    Delete,
+}
+
+/// The keychain command for `action` on `os`, as in `std::env::consts::OS`.
+/// Storing prompts for the secret, so it never appears in a command line.
+fn keychain_command(os: &str, action: Action, name: &str) -> Option<(&'static str, Vec<String>)> {
+    let args: &[&str] = match (os, action) {
+        ("macos", Action::Read) => &["find-generic-password", "-s", SERVICE, "-a", name, "-w"],
+        ("macos", Action::Store) => &[
+            "add-generic-password",
+            "-U",
+            "-s",
+            SERVICE,
+            "-a",
+            name,
+            "-w",
+        ],
+        ("macos", Action::Delete) => &["delete-generic-password", "-s", SERVICE, "-a", name],
+        ("linux", Action::Read) => &["lookup", "service", SERVICE, "account", name],
+        ("linux", Action::Store) => &[
+            "store", "--label", "Atlas", "service", SERVICE, "account", name,
+        ],
+        ("linux", Action::Delete) => &["clear", "service", SERVICE, "account", name],
+        _ => return None,
+    };
+    let program = if os == "macos" {
+        "security"
+    } else {
+        "secret-tool"
+    };
+    Some((program, args.iter().map(|arg| arg.to_string()).collect()))
+}
+
+fn unsupported() -> OptimizerError {
+    OptimizerError::config(format!(
+        "No supported keychain on {}; reference an environment variable with env:<VARIABLE> instead",
+        std::env::consts::OS
+    ))
+}
+
+// The keychain tools run without the `[env]` variables, which may be what
+// is being resolved
+fn read(name: &str) -> OptimizerResult<String> {
+    let (program, args) =
+        keychain_command(std::env::consts::OS, Action::Read, name).ok_or_else(unsupported)?;
+    crate::allowlist::check(program)?;
+    let output = Command::new(program)
+        .args(&args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| OptimizerError::tool_not_found(format!("{} ({})", program, e)))?;
+    let value = String::from_utf8_lossy(&output.stdout)
+        .trim_end_matches(['\r', '\n'])
+        .to_string();
+    if !output.status.success() || value.is_empty() {
+        return Err(OptimizerError::config(format!(
+            "No secret '{}' in the keychain; add it with `atlas config secret set {}`",
+            name, name
+        )));
+    }
+    Ok(value)
+}
+
+/// Store a secret under `name`, prompting for its value
+pub fn store(name: &str) -> OptimizerResult<()> {
+    run_interactive(Action::Store, name)
+}
+
+pub fn delete(name: &str) -> OptimizerResult<()> {
+    run_interactive(Action::Delete, name)
+}
+
+fn run_interactive(action: Action, name: &str) -> OptimizerResult<()> {
+    let (program, args) =
+        keychain_command(std::env::consts::OS, action, name).ok_or_else(unsupported)?;
+    crate::allowlist::check(program)?;
+    let status = Command::new(program)
+        .args(&args)
+        .status()
+        .map_err(|e| OptimizerError::tool_not_found(format!("{} ({})", program, e)))?;
+    if !status.success() {
+        return Err(OptimizerError::command_failed(format!(
+            "{} failed with exit code: {:?}",
+            program,
+            status.code()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_references() {
+        let secret: SecretRef = "keychain:internal-registry".parse().unwrap();
+        assert_eq!(secret, SecretRef::Keychain("internal-registry".to_string()));
+        assert_eq!(secret.to_string(), "keychain:internal-registry");
+        assert_eq!(
+            "env:CI_TOKEN".parse::<SecretRef>().unwrap(),
+            SecretRef::Env("CI_TOKEN".to_string())
+        );
+        assert!("keychain:".parse::<SecretRef>().is_err());
+        assert!("hunter2".parse::<SecretRef>().is_err());
+
+        std::env::set_var("ATLAS_TEST_CREDENTIAL", "s3cr3t-value");
+        let value = SecretRef::Env("ATLAS_TEST_CREDENTIAL".to_string())
+            .resolve()
+            .unwrap();
+        assert_eq!(value, "s3cr3t-value");
+        assert!(resolved().contains(&value));
+    }
+
+    #[test]
+    fn test_keychain_commands() {
+        let (program, args) = keychain_command("macos", Action::Read, "registry").unwrap();
+        assert_eq!(program, "security");
+        assert_eq!(
+            args,
+            [
+                "find-generic-password",
+                "-s",
+                "atlas",
+                "-a",
+                "registry",
+                "-w"
+            ]
+        );
+        // The secret is prompted for, never passed as an argument
+        let (_, args) = keychain_command("macos", Action::Store, "registry").unwrap();
+        assert_eq!(args.last().map(String::as_str), Some("-w"));
+        let (program, args) = keychain_command("linux", Action::Read, "registry").unwrap();
+        assert_eq!(program, "secret-tool");
+        assert_eq!(args, ["lookup", "service", "atlas", "account", "registry"]);
+        assert_eq!(keychain_command("windows", Action::Read, "registry"), None);
+    }
+}
@@ -0,0 +1,221 @@
+//! `atlas debug bundle`: system details, configuration, generated files,
+//! recent session logs and the output of the last failing cargo command in one
+//! archive to attach to a bug report. Everything is redacted on the way in.
+
+use crate::config::OptimizerConfig;
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::managed::{user_cargo_config_path, ManagedRegistry};
+use crate::redact;
+use crate::session;
+use crate::stats::CompilationStats;
+use crate::system::SystemInfo;
+use crate::utils::{create_parent_dirs, execute_command, is_tool_available};
+use chrono::Utc;
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Session logs included in a bundle
+pub const BUNDLED_SESSIONS: usize = 5;
+
+/// Text without terminal color sequences
+pub fn strip_ansi(text: &str) -> String {
+    Regex::new(r"\x1b\[[0-9;]*[A-Za-z]")
+        .expect("valid ANSI regex")
+        .replace_all(text, "")
+        .into_owned()
+}
+
+pub fn last_failure_path(project_root: &Path) -> PathBuf {
+    project_root.join(".atlas").join("last-failure.log")
+}
+
+/// Keep the errors and last output lines of a failed cargo run for the bundle
+pub fn record_failure<'a>(
+    project_root: &Path,
+    args: &[&str],
+    error: &OptimizerError,
+    compilation: &CompilationStats,
+    last_output: impl IntoIterator<Item = &'a String>,
+) -> OptimizerResult<()> {
+    let mut report = format!(
+        "cargo {}\nfailed at {}: {}\n",
+        args.join(" "),
+        Utc::now().to_rfc3339(),
+        error
+    );
+    if !compilation.error_diagnostics.is_empty() {
+        report.push_str("\nerrors:\n");
+        for diagnostic in &compilation.error_diagnostics {
+            report.push_str(&strip_ansi(&diagnostic.rendered));
+            report.push('\n');
+        }
+    }
+    let last_output: Vec<String> = last_output
+        .into_iter()
+        .map(|line| strip_ansi(line))
+        .collect();
+    if !last_output.is_empty() {
+        report.push_str("\nlast output:\n");
+        report.push_str(&last_output.join("\n"));
+        report.push('\n');
+    }
+
+    let path = last_failure_path(project_root);
+    create_parent_dirs(&path)?;
+    fs::write(path, report)?;
+    Ok(())
+}
+
+/// One file of the bundle, by its path inside the archive
+#[derive(Debug, Clone, PartialEq)]
+pub struct BundleEntry {
+    pub name: String,
+    pub content: String,
+}
+
+/// Everything that goes into a bundle, and what was looked for but not found
+#[derive(Debug, Default)]
+pub struct DebugBundle {
+    pub entries: Vec<BundleEntry>,
+    pub missing: Vec<String>,
+}
+
+impl DebugBundle {
+    /// Gather the bundle for this machine and, when given, one project
+    pub fn collect(project_root: Option<&Path>) -> OptimizerResult<Self> {
+        let mut bundle = Self::default();
+
+        let system = SystemInfo::detect()?;
+        bundle.add("system.json", serde_json::to_string_pretty(&system)?);
+        let mut versions = format!("atlas {}\n", env!("CARGO_PKG_VERSION"));
+        for (program, args) in [
+            ("rustc", &["-vV"][..]),
+            ("cargo", &["-vV"][..]),
+            ("rustup", &["show"][..]),
+        ] {
+            if !is_tool_available(program) {
+                continue;
+            }
+            if let Ok(output) = execute_command(program, args, None) {
+                versions.push_str(&format!("\n$ {} {}\n", program, args.join(" ")));
+                versions.push_str(&String::from_utf8_lossy(&output.stdout));
+            }
+        }
+        bundle.add("versions.txt", versions);
+
+        let config_path = OptimizerConfig::get_config_path()?;
+        bundle.add_file("config/atlas-config.toml", &config_path);
+        bundle.add_file("config/managed.toml", &ManagedRegistry::path()?);
+        bundle.add_file("config/cargo-config.toml", &user_cargo_config_path()?);
+
+        if let Some(root) = project_root {
+            for file in [
+                "Cargo.toml",
+                "atlas.toml",
+                ".cargo/config.toml",
+                "rust-toolchain.toml",
+                "rust-toolchain",
+                ".atlas/last-build.json",
+                ".atlas/last-failure.log",
+            ] {
+                bundle.add_file(&format!("project/{}", file), &root.join(file));
+            }
+        }
+
+        for log in session::recent(BUNDLED_SESSIONS)? {
+            if let Some(name) = log.file_name() {
+                bundle.add_file(&format!("sessions/{}", name.to_string_lossy()), &log);
+            }
+        }
+        Ok(bundle)
+    }
+
+    fn add(&mut self, name: &str, content: String) {
+        self.entries.push(BundleEntry {
+            name: name.to_string(),
+            content: redact::config(&content),
+        });
+    }
+
+    fn add_file(&mut self, name: &str, path: &Path) {
+        match fs::read_to_string(path) {
+            Ok(content) => self.add(name, content),
+            Err(_) => self.missing.push(name.to_string()),
+        }
+    }
+
+    /// Write the bundle as a gzipped tarball with a single top-level directory
+    pub fn write_archive(&self, output: &Path) -> OptimizerResult<()> {
+        if !is_tool_available("tar") {
+            return Err(OptimizerError::tool_not_found("tar"));
+        }
+        let staging = tempfile::tempdir()?;
+        let top = output
+            .file_name()
+            .map(|name| {
+                name.to_string_lossy()
+                    .trim_end_matches(".tar.gz")
+                    .to_string()
+            })
+            .unwrap_or_else(|| "atlas-debug".to_string());
+        let root = staging.path().join(&top);
+        for entry in &self.entries {
+            let path = root.join(&entry.name);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, &entry.content)?;
+        }
+        let mut manifest = String::from("Included:\n");
+        for entry in &self.entries {
+            manifest.push_str(&format!("  {}\n", entry.name));
+        }
+        if !self.missing.is_empty() {
+            manifest.push_str("Not found:\n");
+            for name in &self.missing {
+                manifest.push_str(&format!("  {}\n", name));
+            }
+        }
+        fs::create_dir_all(&root)?;
+        fs::write(root.join("MANIFEST.txt"), manifest)?;
+
+        let output = if output.is_absolute() {
+            output.to_path_buf()
+        } else {
+            std::env::current_dir()?.join(output)
+        };
+        let output_arg = output.to_string_lossy().to_string();
+        let staging_arg = staging.path().to_string_lossy().to_string();
+        let result = execute_command(
+            "tar",
+            &["-czf", &output_arg, "-C", &staging_arg, &top],
+            None,
+        )?;
+        if !result.status.success() {
+            return Err(OptimizerError::command_failed(format!(
+                "tar failed: {}",
+                String::from_utf8_lossy(&result.stderr).trim()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Default archive name, e.g. `atlas-debug-20261015-142233.tar.gz`
+pub fn default_archive_name() -> String {
+    format!("atlas-debug-{}.tar.gz", Utc::now().format("%Y%m%d-%H%M%S"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_ansi() {
+        assert_eq!(
+            strip_ansi("\u{1b}[1m\u{1b}[38;5;9merror\u{1b}[0m: boom"),
+            "error: boom"
+        );
+    }
+}
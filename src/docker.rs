@@ -0,0 +1,297 @@
+//! Dockerfile layer analysis and cargo-chef stages
+
+use crate::error::OptimizerResult;
+use crate::managed::{find_block, render_block, upsert_block, BlockUpdate, Stamp};
+
+/// Managed block holding the cargo-chef base and planner stages
+pub const CHEF_BLOCK: &str = "cargo-chef";
+
+/// Base image used when the Dockerfile does not build from a Rust image
+pub const DEFAULT_RUST_IMAGE: &str = "rust:1";
+
+/// One instruction, with continuation lines joined
+#[derive(Debug, Clone, PartialEq)]
+struct Instruction {
+    /// 1-based line the instruction starts on
+    line: usize,
+    keyword: String,
+    arguments: String,
+    /// Index of the build stage, counting `FROM`s
+    stage: usize,
+}
+
+/// A Dockerfile pattern that defeats layer caching, and how to reorder it
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerIssue {
+    pub line: usize,
+    pub problem: String,
+    pub fix: String,
+}
+
+fn parse(content: &str) -> Vec<Instruction> {
+    let mut instructions = Vec::new();
+    let mut stage = 0;
+    let mut pending: Option<(usize, String)> = None;
+
+    for (index, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        if pending.is_none() && (trimmed.is_empty() || trimmed.starts_with('#')) {
+            continue;
+        }
+
+        let (start, mut text) = pending.take().unwrap_or((index + 1, String::new()));
+        if let Some(continued) = trimmed.strip_suffix('\\') {
+            text.push_str(continued);
+            text.push(' ');
+            pending = Some((start, text));
+            continue;
+        }
+        text.push_str(trimmed);
+
+        let (keyword, arguments) = text.split_once(char::is_whitespace).unwrap_or((&text, ""));
+        let keyword = keyword.to_uppercase();
+        if keyword == "FROM" {
+            stage += 1;
+        }
+        instructions.push(Instruction {
+            line: start,
+            keyword,
+            arguments: arguments.trim().to_string(),
+            stage,
+        });
+    }
+    instructions
+}
+
+/// Whether a `COPY`/`ADD` copies the whole build context
+fn copies_context(instruction: &Instruction) -> bool {
+    if !matches!(instruction.keyword.as_str(), "COPY" | "ADD") {
+        return false;
+    }
+    let paths: Vec<&str> = instruction
+        .arguments
+        .split_whitespace()
+        .filter(|word| !word.starts_with("--"))
+        .collect();
+    // Everything but the destination is a source
+    paths.len() >= 2
+        && paths[..paths.len() - 1]
+            .iter()
+            .any(|source| matches!(*source, "." | "./" | "*"))
+}
+
+fn runs(instruction: &Instruction, command: &str) -> bool {
+    instruction.keyword == "RUN" && instruction.arguments.contains(command)
+}
+
+fn builds_crate(instruction: &Instruction) -> bool {
+    ["cargo build", "cargo test", "cargo install --path"]
+        .iter()
+        .any(|command| runs(instruction, command))
+}
+
+/// Find layer-ordering problems in a Dockerfile. `dockerignore` is the content
+/// of `.dockerignore`, if there is one.
+pub fn analyze_dockerfile(content: &str, dockerignore: Option<&str>) -> Vec<LayerIssue> {
+    let instructions = parse(content);
+    let mut issues = Vec::new();
+
+    let stages = instructions.last().map_or(0, |last| last.stage);
+    for stage in 1..=stages {
+        let stage_instructions: Vec<&Instruction> = instructions
+            .iter()
+            .filter(|instruction| instruction.stage == stage)
+            .collect();
+
+        let cook = stage_instructions
+            .iter()
+            .position(|instruction| runs(instruction, "cargo chef cook"));
+        let copy = stage_instructions
+            .iter()
+            .position(|instruction| copies_context(instruction));
+        let build = stage_instructions
+            .iter()
+            .position(|instruction| builds_crate(instruction));
+
+        if let (Some(copy), Some(build)) = (copy, build) {
+            let cooked_first = cook.is_some_and(|cook| cook < copy);
+            if copy < build && !cooked_first {
+                issues.push(LayerIssue {
+                    line: stage_instructions[copy].line,
+                    problem: format!(
+                        "`{} {}` comes before dependencies are built, so any source change rebuilds every dependency",
+                        stage_instructions[copy].keyword, stage_instructions[copy].arguments
+                    ),
+                    fix: format!(
+                        "Start this stage with `FROM chef AS ...`, then add `COPY --from=planner /app/recipe.json recipe.json` and `RUN cargo chef cook --release --recipe-path recipe.json` before line {}",
+                        stage_instructions[copy].line
+                    ),
+                });
+            }
+        }
+
+        if let Some(cook) = cook {
+            let has_recipe = stage_instructions[..cook].iter().any(|instruction| {
+                instruction.keyword == "COPY" && instruction.arguments.contains("recipe.json")
+            });
+            if !has_recipe {
+                issues.push(LayerIssue {
+                    line: stage_instructions[cook].line,
+                    problem: "`cargo chef cook` runs without the planner's recipe".to_string(),
+                    fix: "Add `COPY --from=planner /app/recipe.json recipe.json` before it"
+                        .to_string(),
+                });
+            }
+        }
+    }
+
+    let ignores_target = dockerignore.is_some_and(|ignore| {
+        ignore
+            .lines()
+            .map(|line| line.trim().trim_start_matches('/').trim_end_matches('/'))
+            .any(|pattern| pattern == "target" || pattern == "**/target")
+    });
+    if let Some(copy) = instructions
+        .iter()
+        .find(|instruction| copies_context(instruction))
+    {
+        if !ignores_target {
+            issues.push(LayerIssue {
+                line: copy.line,
+                problem:
+                    "the build context includes `target/`, so local builds invalidate this layer"
+                        .to_string(),
+                fix: "Add `target` to .dockerignore".to_string(),
+            });
+        }
+    }
+
+    issues.sort_by_key(|issue| issue.line);
+    issues
+}
+
+/// The Rust image the Dockerfile builds from, if any
+fn rust_base_image(content: &str) -> Option<String> {
+    parse(content)
+        .into_iter()
+        .filter(|instruction| instruction.keyword == "FROM")
+        .filter_map(|instruction| {
+            instruction
+                .arguments
+                .split_whitespace()
+                .find(|word| !word.starts_with("--"))
+                .map(str::to_string)
+        })
+        .find(|image| {
+            let name = image.rsplit('/').next().unwrap_or(image);
+            name == "rust" || name.starts_with("rust:")
+        })
+}
+
+/// cargo-chef base and planner stages, building from `image`
+pub fn chef_stages(image: &str) -> String {
+    format!(
+        r#"FROM {} AS chef
+RUN cargo install cargo-chef --locked
+WORKDIR /app
+
+FROM chef AS planner
+COPY . .
+RUN cargo chef prepare --recipe-path recipe.json
+"#,
+        image
+    )
+}
+
+/// Write the chef stages into `content`: a new block goes before the first
+/// stage so later stages can build `FROM chef`.
+pub fn upsert_chef_stages(
+    content: &str,
+    stamp: &Stamp,
+    force: bool,
+) -> OptimizerResult<BlockUpdate> {
+    let body = chef_stages(
+        &rust_base_image(&crate::managed::without_blocks(content)?)
+            .unwrap_or_else(|| DEFAULT_RUST_IMAGE.to_string()),
+    );
+    if find_block(content, CHEF_BLOCK)?.is_some() {
+        return upsert_block(content, CHEF_BLOCK, &body, stamp, force);
+    }
+
+    // Parser directives and global ARGs must stay above the first FROM
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        if line.trim_start().to_uppercase().starts_with("FROM ") {
+            break;
+        }
+        offset += line.len();
+    }
+    let mut updated = content.to_string();
+    updated.insert_str(
+        offset,
+        &format!("{}\n", render_block(CHEF_BLOCK, &body, stamp)),
+    );
+    Ok(BlockUpdate::Updated(updated))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NAIVE: &str = r#"# syntax=docker/dockerfile:1
+FROM rust:1.79 AS builder
+WORKDIR /app
+COPY . .
+RUN cargo build --release \
+    --locked
+
+FROM debian:bookworm-slim
+COPY --from=builder /app/target/release/app /usr/local/bin/app
+"#;
+
+    const CHEF_BUILDER: &str = r#"FROM chef AS builder
+COPY --from=planner /app/recipe.json recipe.json
+RUN cargo chef cook --release --recipe-path recipe.json
+COPY . .
+RUN cargo build --release
+"#;
+
+    #[test]
+    fn test_copy_before_dependency_build() {
+        let issues = analyze_dockerfile(NAIVE, Some("target\n"));
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].line, 4);
+        assert!(issues[0].fix.contains("cargo chef cook"));
+
+        let issues = analyze_dockerfile(NAIVE, None);
+        assert_eq!(issues.len(), 2);
+        assert!(issues[1].fix.contains(".dockerignore"));
+    }
+
+    #[test]
+    fn test_chef_stages_fix_layer_order() {
+        let stamp = Stamp::current(&crate::config::OptimizerConfig::default()).unwrap();
+        let with_builder = NAIVE.replace(
+            "FROM rust:1.79 AS builder\nWORKDIR /app\nCOPY . .\nRUN cargo build --release \\\n    --locked\n",
+            CHEF_BUILDER,
+        );
+
+        let BlockUpdate::Updated(updated) = upsert_chef_stages(NAIVE, &stamp, false).unwrap()
+        else {
+            panic!("chef stages were not written");
+        };
+        assert!(updated.starts_with("# syntax=docker/dockerfile:1\n# BEGIN ATLAS cargo-chef"));
+        assert!(updated.contains("FROM rust:1.79 AS chef"));
+        assert_eq!(
+            upsert_chef_stages(&updated, &stamp, false).unwrap(),
+            BlockUpdate::Unchanged
+        );
+
+        let BlockUpdate::Updated(fixed) = upsert_chef_stages(&with_builder, &stamp, false).unwrap()
+        else {
+            panic!("chef stages were not written");
+        };
+        assert!(fixed.contains("FROM rust:1 AS chef"));
+        assert_eq!(analyze_dockerfile(&fixed, Some("/target/\n")), Vec::new());
+    }
+}
@@ -0,0 +1,233 @@
+//! Editor integration: VS Code tasks and launch configurations wired to Atlas
+//! commands, and the last build result an editor extension shows inline.
+
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::stats::CompilationStats;
+use crate::utils::create_parent_dirs;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Labels of generated tasks and launch configurations start with this, so
+/// regenerating replaces them and leaves everything else alone
+pub const LABEL_PREFIX: &str = "atlas: ";
+
+/// The VS Code setting that gives rust-analyzer its own target directory
+pub const RUST_ANALYZER_TARGET_DIR: &str = "rust-analyzer.cargo.targetDir";
+
+/// The most recent `atlas build` run in a project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LastBuild {
+    pub finished_at: DateTime<Utc>,
+    pub command: String,
+    pub success: bool,
+    pub duration_secs: f64,
+    pub compilation: Option<CompilationStats>,
+}
+
+impl LastBuild {
+    pub fn path(project_root: &Path) -> PathBuf {
+        project_root.join(".atlas").join("last-build.json")
+    }
+
+    pub fn load(project_root: &Path) -> Option<Self> {
+        serde_json::from_str(&fs::read_to_string(Self::path(project_root)).ok()?).ok()
+    }
+
+    pub fn save(&self, project_root: &Path) -> OptimizerResult<()> {
+        let path = Self::path(project_root);
+        create_parent_dirs(&path)?;
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+fn task(label: &str, args: &[&str], group: &str, is_default: bool) -> Value {
+    let mut task = json!({
+        "label": format!("{}{}", LABEL_PREFIX, label),
+        "type": "shell",
+        "command": "atlas",
+        "args": args,
+        "group": { "kind": group, "isDefault": is_default },
+        "problemMatcher": ["$rustc"],
+    });
+    if args.contains(&"watch") {
+        task["isBackground"] = json!(true);
+    }
+    task
+}
+
+/// Tasks running the optimized Atlas commands
+pub fn tasks() -> Vec<Value> {
+    vec![
+        task("check", &["build", "check"], "build", true),
+        task("build", &["build", "build"], "build", false),
+        task(
+            "build release",
+            &["build", "build", "--release"],
+            "build",
+            false,
+        ),
+        task("test", &["build", "test"], "test", true),
+        task(
+            "test changed",
+            &["build", "test", "--changed"],
+            "test",
+            false,
+        ),
+        task("watch", &["dev", "watch"], "build", false),
+    ]
+}
+
+/// CodeLLDB launch configurations for each binary, built through cargo with
+/// the project's Atlas configuration
+pub fn launch_configurations(binaries: &[(String, String)]) -> Vec<Value> {
+    binaries
+        .iter()
+        .map(|(package, binary)| {
+            json!({
+                "name": format!("{}debug {}", LABEL_PREFIX, binary),
+                "type": "lldb",
+                "request": "launch",
+                "cargo": {
+                    "args": ["build", format!("--bin={}", binary), format!("--package={}", package)],
+                    "filter": { "name": binary, "kind": "bin" }
+                },
+                "args": [],
+                "cwd": "${workspaceFolder}"
+            })
+        })
+        .collect()
+}
+
+/// Replace the generated entries of a VS Code JSON file, keeping the user's own.
+///
+/// `key` is `tasks` or `configurations`; entries are matched by `label` or
+/// `name`. Files with comments are not plain JSON and are rejected.
+pub fn merge(existing: Option<&str>, key: &str, generated: Vec<Value>) -> OptimizerResult<Value> {
+    let mut document = match existing {
+        Some(content) => serde_json::from_str::<Value>(content).map_err(|_| {
+            OptimizerError::invalid_input(format!(
+                "Cannot merge {} into a file with comments or invalid JSON; rerun with --force to replace it",
+                key
+            ))
+        })?,
+        None => json!({}),
+    };
+    let object = document.as_object_mut().ok_or_else(|| {
+        OptimizerError::invalid_input(format!("Expected a JSON object holding {}", key))
+    })?;
+    object.insert("version".to_string(), json!(version_for(key)));
+
+    let generated_label = |entry: &Value| {
+        ["label", "name"].iter().any(|field| {
+            entry
+                .get(field)
+                .and_then(Value::as_str)
+                .is_some_and(|label| label.starts_with(LABEL_PREFIX))
+        })
+    };
+    let mut entries: Vec<Value> = object
+        .get(key)
+        .and_then(Value::as_array)
+        .map(|entries| {
+            entries
+                .iter()
+                .filter(|entry| !generated_label(entry))
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default();
+    entries.extend(generated);
+    object.insert(key.to_string(), Value::Array(entries));
+    Ok(document)
+}
+
+/// Add `key` to a VS Code settings file unless the user set it already:
+/// `None` when the file needs no change. Files with comments are rejected.
+pub fn with_setting(
+    existing: Option<&str>,
+    key: &str,
+    value: Value,
+) -> OptimizerResult<Option<Value>> {
+    let mut document = match existing {
+        Some(content) => serde_json::from_str::<Value>(content).map_err(|_| {
+            OptimizerError::invalid_input(format!(
+                "Cannot add {} to a file with comments or invalid JSON",
+                key
+            ))
+        })?,
+        None => json!({}),
+    };
+    let object = document
+        .as_object_mut()
+        .ok_or_else(|| OptimizerError::invalid_input("Expected a JSON object of settings"))?;
+    if object.contains_key(key) {
+        return Ok(None);
+    }
+    object.insert(key.to_string(), value);
+    Ok(Some(document))
+}
+
+fn version_for(key: &str) -> &'static str {
+    match key {
+        "tasks" => "2.0.0",
+        _ => "0.2.0",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_keeps_user_tasks() {
+        let existing = r#"{
+            "version": "2.0.0",
+            "tasks": [
+                { "label": "lint", "command": "make lint" },
+                { "label": "atlas: old", "command": "atlas" }
+            ]
+        }"#;
+        let merged = merge(Some(existing), "tasks", tasks()).unwrap();
+        let labels: Vec<&str> = merged["tasks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|task| task["label"].as_str().unwrap())
+            .collect();
+        assert_eq!(labels[0], "lint");
+        assert!(!labels.contains(&"atlas: old"));
+        assert!(labels.contains(&"atlas: check"));
+
+        assert!(merge(Some("// comment\n{}"), "tasks", tasks()).is_err());
+    }
+
+    #[test]
+    fn test_with_setting_keeps_user_value() {
+        let key = "rust-analyzer.cargo.targetDir";
+        let added = with_setting(Some(r#"{ "editor.formatOnSave": true }"#), key, json!(true))
+            .unwrap()
+            .unwrap();
+        assert_eq!(added[key], true);
+        assert_eq!(added["editor.formatOnSave"], true);
+        let own = r#"{ "rust-analyzer.cargo.targetDir": "target/ra" }"#;
+        assert!(with_setting(Some(own), key, json!(true)).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_launch_configurations() {
+        let merged = merge(
+            None,
+            "configurations",
+            launch_configurations(&[("demo".to_string(), "demo-cli".to_string())]),
+        )
+        .unwrap();
+        assert_eq!(merged["version"], "0.2.0");
+        let configuration = &merged["configurations"][0];
+        assert_eq!(configuration["name"], "atlas: debug demo-cli");
+        assert_eq!(configuration["cargo"]["args"][1], "--bin=demo-cli");
+    }
+}
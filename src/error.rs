@@ -44,6 +44,9 @@ pub enum OptimizerError {
     #[error("Operation cancelled by user")]
     Cancelled,
 
+    #[error("Timed out: {0}")]
+    TimedOut(String),
+
     #[error("Unsupported platform: {0}")]
     UnsupportedPlatform(String),
 
@@ -96,6 +99,10 @@ impl OptimizerError {
         Self::InvalidInput(msg.into())
     }
 
+    pub fn timed_out<S: Into<String>>(msg: S) -> Self {
+        Self::TimedOut(msg.into())
+    }
+
     pub fn unsupported_platform<S: Into<String>>(platform: S) -> Self {
         Self::UnsupportedPlatform(platform.into())
     }
@@ -135,6 +142,7 @@ impl OptimizerError {
             Self::FileNotFound(_) => true,
             Self::InvalidInput(_) => true,
             Self::Cancelled => true,
+            Self::TimedOut(_) => true,
             Self::UnsupportedPlatform(_) => false,
             Self::ToolInstallation { .. } => true,
             Self::BuildFailed(_) => true,
@@ -0,0 +1,356 @@
+//! Progress and ETA for cargo runs, from how long each unit took in earlier
+//! runs of the same command.
+//!
+//! Each compiled unit (a target of a package) is credited with the wall-clock
+//! time since the previous unit finished. The credits of one run add up to its
+//! wall time, so the credits of the units still to come estimate the time left
+//! whatever the parallelism. Fresh units keep the credit of their last compile.
+
+use crate::error::OptimizerResult;
+use crate::signal;
+use crate::utils::create_parent_dirs;
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Seconds credited to each unit, per cargo command line
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UnitTimes {
+    pub commands: BTreeMap<String, BTreeMap<String, f64>>,
+}
+
+impl UnitTimes {
+    pub fn path(project_root: &Path) -> PathBuf {
+        project_root.join(".atlas").join("unit-times.json")
+    }
+
+    /// Saved times, or none if they are missing or unreadable
+    pub fn load(project_root: &Path) -> Self {
+        fs::read_to_string(Self::path(project_root))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, project_root: &Path) -> OptimizerResult<()> {
+        let path = Self::path(project_root);
+        create_parent_dirs(&path)?;
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Merge what a run observed. Compiled units average with their previous
+    /// time; after a complete run, units it did not build are forgotten.
+    pub fn update(&mut self, command: &str, estimator: &Estimator, complete: bool) {
+        let times = self.commands.entry(command.to_string()).or_default();
+        if complete {
+            times.retain(|unit, _| estimator.finished.contains(unit));
+        }
+        for unit in &estimator.finished {
+            match (estimator.compiled.get(unit), times.get_mut(unit)) {
+                (Some(&secs), Some(previous)) => *previous = (*previous + secs) / 2.0,
+                (Some(&secs), None) => {
+                    times.insert(unit.clone(), secs);
+                }
+                (None, Some(_)) => {}
+                (None, None) => {
+                    times.insert(unit.clone(), 0.0);
+                }
+            }
+        }
+    }
+}
+
+/// Key for a cargo command line, ignoring job counts which do not change the
+/// set of units
+pub fn command_key(args: &[&str]) -> String {
+    args.iter()
+        .filter(|arg| !arg.starts_with("--jobs") && !arg.starts_with("-j"))
+        .copied()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Follows one run against the unit times of earlier runs
+#[derive(Debug)]
+pub struct Estimator {
+    expected: BTreeMap<String, f64>,
+    total: f64,
+    done: f64,
+    finished: BTreeSet<String>,
+    /// Seconds credited to units compiled in this run
+    compiled: BTreeMap<String, f64>,
+    last_finish: Duration,
+}
+
+impl Estimator {
+    pub fn new(expected: BTreeMap<String, f64>) -> Self {
+        Self {
+            total: expected.values().sum(),
+            expected,
+            done: 0.0,
+            finished: BTreeSet::new(),
+            compiled: BTreeMap::new(),
+            last_finish: Duration::ZERO,
+        }
+    }
+
+    /// Record a line of cargo's JSON output `elapsed` into the run. Returns the
+    /// name of the target that finished, if the line reports one.
+    pub fn record_message(&mut self, line: &str, elapsed: Duration) -> Option<String> {
+        let message: Value = serde_json::from_str(line).ok()?;
+        if message.get("reason")?.as_str()? != "compiler-artifact" {
+            return None;
+        }
+        let package_id = message.get("package_id")?.as_str()?;
+        let target = message.get("target")?.get("name")?.as_str()?;
+        let test = message
+            .get("profile")
+            .and_then(|profile| profile.get("test"))
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let fresh = message
+            .get("fresh")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+
+        let unit = format!(
+            "{} {}{}",
+            package_id,
+            target,
+            if test { " (test)" } else { "" }
+        );
+        if self.finished.insert(unit.clone()) {
+            self.done += self.expected.get(&unit).copied().unwrap_or(0.0);
+        }
+        if !fresh {
+            let credit = elapsed.saturating_sub(self.last_finish).as_secs_f64();
+            *self.compiled.entry(unit).or_default() += credit;
+        }
+        self.last_finish = elapsed;
+        Some(target.to_string())
+    }
+
+    /// Whether earlier runs give anything to estimate against
+    pub fn has_history(&self) -> bool {
+        !self.expected.is_empty()
+    }
+
+    /// Share of the expected work done, by time when earlier runs took any
+    /// and by unit count otherwise
+    pub fn fraction(&self) -> Option<f64> {
+        if !self.has_history() {
+            return None;
+        }
+        let fraction = if self.total > 0.0 {
+            self.done / self.total
+        } else {
+            let done = self
+                .finished
+                .iter()
+                .filter(|unit| self.expected.contains_key(*unit))
+                .count();
+            done as f64 / self.expected.len() as f64
+        };
+        Some(fraction.min(1.0))
+    }
+
+    /// Time left `elapsed` into the run
+    pub fn remaining(&self, elapsed: Duration) -> Option<Duration> {
+        if self.total <= 0.0 {
+            return None;
+        }
+        let left = (self.total - self.done).max(0.0);
+        let waited = elapsed.saturating_sub(self.last_finish).as_secs_f64();
+        Some(Duration::from_secs_f64((left - waited).max(0.0)))
+    }
+
+    pub fn units_finished(&self) -> usize {
+        self.finished.len()
+    }
+}
+
+/// The progress shown while cargo runs, and the unit times it learns from
+pub struct Progress {
+    command: String,
+    estimator: Estimator,
+    started: Instant,
+    bar: Option<ProgressBar>,
+}
+
+impl Progress {
+    /// Start following `args` in `project_root`. The bar is only drawn on a
+    /// terminal and when no event stream could be interleaved with it.
+    pub fn start(project_root: &Path, args: &[&str]) -> Self {
+        let command = command_key(args);
+        let expected = UnitTimes::load(project_root)
+            .commands
+            .remove(&command)
+            .unwrap_or_default();
+        let estimator = Estimator::new(expected);
+        let bar = (std::io::stderr().is_terminal() && !crate::events::enabled())
+            .then(|| progress_bar(estimator.has_history()));
+        Self {
+            command,
+            estimator,
+            started: Instant::now(),
+            bar,
+        }
+    }
+
+    /// Whether a bar is drawn, so cargo's stderr has to go through [`Progress::suspend`]
+    pub fn is_drawn(&self) -> bool {
+        self.bar.is_some()
+    }
+
+    /// Record a line of cargo's JSON output
+    pub fn record_message(&mut self, line: &str) {
+        let elapsed = self.started.elapsed();
+        let Some(target) = self.estimator.record_message(line, elapsed) else {
+            return;
+        };
+        let Some(bar) = &self.bar else {
+            return;
+        };
+        if let Some(fraction) = self.estimator.fraction() {
+            bar.set_position((fraction * 1000.0) as u64);
+        }
+        let eta = match self.estimator.remaining(elapsed) {
+            Some(remaining) if remaining.as_secs() > 0 => {
+                format!("ETA {} · ", crate::utils::format_duration(remaining))
+            }
+            Some(_) => "almost done · ".to_string(),
+            None => String::new(),
+        };
+        bar.set_message(format!(
+            "{}{} done · last: {}",
+            eta,
+            self.estimator.units_finished(),
+            target
+        ));
+    }
+
+    /// Print without tearing the bar
+    pub fn suspend<F: FnOnce()>(&self, print: F) {
+        match &self.bar {
+            Some(bar) => bar.suspend(print),
+            None => print(),
+        }
+    }
+
+    /// Clear the bar and save what this run learned. Only a complete run
+    /// forgets units it did not build.
+    pub fn finish(self, project_root: &Path, complete: bool) {
+        if let Some(bar) = &self.bar {
+            bar.finish_and_clear();
+        }
+        if self.estimator.units_finished() == 0 {
+            return;
+        }
+        let mut times = UnitTimes::load(project_root);
+        times.update(&self.command, &self.estimator, complete);
+        if let Err(e) = times.save(project_root) {
+            log::warn!("Could not save build unit times: {}", e);
+        }
+    }
+}
+
+fn progress_bar(has_history: bool) -> ProgressBar {
+    let (bar, template) = if has_history {
+        (
+            ProgressBar::new(1000),
+            "{spinner:.green} [{elapsed_precise}] [{bar:30.cyan/blue}] {percent:>3}% {wide_msg}",
+        )
+    } else {
+        (
+            ProgressBar::new_spinner(),
+            "{spinner:.green} [{elapsed_precise}] {wide_msg}",
+        )
+    };
+    bar.set_style(ProgressStyle::default_bar().template(template).unwrap());
+    bar.enable_steady_tick(Duration::from_millis(100));
+    signal::track_spinner(&bar);
+    bar
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn artifact(package: &str, fresh: bool) -> String {
+        serde_json::json!({
+            "reason": "compiler-artifact",
+            "package_id": format!("{} 1.0.0", package),
+            "target": { "name": package, "kind": ["lib"] },
+            "profile": { "test": false },
+            "fresh": fresh
+        })
+        .to_string()
+    }
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn test_first_run_learns_credits() {
+        let mut estimator = Estimator::new(BTreeMap::new());
+        assert_eq!(
+            estimator.record_message(&artifact("libc", false), secs(4)),
+            Some("libc".to_string())
+        );
+        estimator.record_message(&artifact("serde", false), secs(10));
+        estimator.record_message(&artifact("log", true), secs(10));
+        estimator.record_message(r#"{"reason":"build-finished","success":true}"#, secs(11));
+        assert_eq!(estimator.fraction(), None);
+        assert_eq!(estimator.remaining(secs(11)), None);
+
+        let mut times = UnitTimes::default();
+        times.commands.insert(
+            "build".to_string(),
+            BTreeMap::from([("gone 1.0.0 gone".to_string(), 3.0)]),
+        );
+        times.update("build", &estimator, true);
+        assert_eq!(
+            times.commands["build"],
+            BTreeMap::from([
+                ("libc 1.0.0 libc".to_string(), 4.0),
+                ("log 1.0.0 log".to_string(), 0.0),
+                ("serde 1.0.0 serde".to_string(), 6.0),
+            ])
+        );
+        assert_eq!(
+            command_key(&["build", "--release", "--jobs=4"]),
+            "build --release"
+        );
+    }
+
+    #[test]
+    fn test_estimate_against_history() {
+        let expected = BTreeMap::from([
+            ("libc 1.0.0 libc".to_string(), 4.0),
+            ("serde 1.0.0 serde".to_string(), 6.0),
+            ("app 1.0.0 app".to_string(), 10.0),
+        ]);
+        let mut estimator = Estimator::new(expected);
+        assert_eq!(estimator.fraction(), Some(0.0));
+        assert_eq!(estimator.remaining(secs(0)), Some(secs(20)));
+
+        // Fresh units finish at once and take their share of the work with them
+        estimator.record_message(&artifact("libc", true), secs(0));
+        estimator.record_message(&artifact("serde", false), secs(5));
+        assert_eq!(estimator.fraction(), Some(0.5));
+        assert_eq!(estimator.remaining(secs(5)), Some(secs(10)));
+        assert_eq!(estimator.remaining(secs(8)), Some(secs(7)));
+        assert_eq!(estimator.remaining(secs(30)), Some(secs(0)));
+
+        estimator.record_message(&artifact("app", false), secs(14));
+        assert_eq!(estimator.fraction(), Some(1.0));
+        assert_eq!(estimator.units_finished(), 3);
+    }
+}
@@ -0,0 +1,236 @@
+//! Machine-readable event stream for IDE extensions and wrapper scripts.
+//!
+//! With `--events jsonl`, every event is written as one JSON object per line
+//! to stderr, a file or an inherited file descriptor. Each line carries an
+//! `event` tag and a `timestamp`; the remaining fields depend on the event.
+
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::redact;
+use crate::stats::{CompilationStats, SccacheSnapshot};
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+
+/// Event stream encodings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum EventFormat {
+    /// One JSON object per line
+    Jsonl,
+}
+
+/// Where events are written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventTarget {
+    Stderr,
+    /// An already open file descriptor, e.g. `fd:3`
+    Fd(i32),
+    /// A file (or FIFO), appended to
+    Path(String),
+}
+
+impl EventTarget {
+    /// Parse `--events-to`: `-` for stderr, `fd:N` or a path
+    pub fn parse(value: &str) -> OptimizerResult<Self> {
+        if value == "-" {
+            return Ok(Self::Stderr);
+        }
+        match value.strip_prefix("fd:") {
+            Some(fd) => fd.parse().map(Self::Fd).map_err(|_| {
+                OptimizerError::invalid_input(format!(
+                    "Invalid file descriptor '{}' in --events-to",
+                    fd
+                ))
+            }),
+            None => Ok(Self::Path(value.to_string())),
+        }
+    }
+
+    fn open(&self) -> OptimizerResult<Box<dyn Write + Send>> {
+        match self {
+            Self::Stderr => Ok(Box::new(std::io::stderr())),
+            Self::Path(path) => Ok(Box::new(
+                OpenOptions::new().create(true).append(true).open(path)?,
+            )),
+            #[cfg(unix)]
+            Self::Fd(fd) => {
+                use std::os::unix::io::FromRawFd;
+                // SAFETY: the caller handed us this descriptor to write events to
+                // and it stays open for the lifetime of the process.
+                Ok(Box::new(unsafe { std::fs::File::from_raw_fd(*fd) }))
+            }
+            #[cfg(not(unix))]
+            Self::Fd(_) => Err(OptimizerError::invalid_input(
+                "--events-to fd:N is only supported on Unix",
+            )),
+        }
+    }
+}
+
+/// Severity of a human-readable message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageLevel {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// Atlas started running a command
+    CommandStarted {
+        command: String,
+        args: Vec<String>,
+        atlas_version: String,
+    },
+    /// A status line also shown to the user
+    Message { level: MessageLevel, text: String },
+    /// Cargo finished a package during a build
+    Progress {
+        package: String,
+        fresh: bool,
+        crates_compiled: usize,
+        crates_fresh: usize,
+    },
+    /// A cargo check, build or test run finished
+    BuildFinished {
+        command: String,
+        success: bool,
+        duration_secs: f64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        compilation: Option<Box<CompilationStats>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        sccache: Option<SccacheSnapshot>,
+    },
+    /// The command finished; `error` is set when it failed
+    CommandFinished {
+        command: String,
+        success: bool,
+        duration_secs: f64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+}
+
+#[derive(Serialize)]
+struct Record<'a> {
+    timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+static SINK: OnceLock<Mutex<Box<dyn Write + Send>>> = OnceLock::new();
+
+/// Start writing events to `target`. Only the first call has an effect.
+pub fn init(format: EventFormat, target: &EventTarget) -> OptimizerResult<()> {
+    match format {
+        EventFormat::Jsonl => {
+            let _ = SINK.set(Mutex::new(target.open()?));
+        }
+    }
+    Ok(())
+}
+
+/// Whether anyone is listening for events
+pub fn enabled() -> bool {
+    SINK.get().is_some()
+}
+
+/// Write one event if the stream is enabled. Never fails the command.
+pub fn emit(event: Event) {
+    let Some(sink) = SINK.get() else {
+        return;
+    };
+    let mut writer = sink.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Err(e) = write_event(&mut *writer, &event) {
+        log::debug!("Could not write event: {}", e);
+    }
+}
+
+fn write_event(writer: &mut dyn Write, event: &Event) -> OptimizerResult<()> {
+    let record = Record {
+        timestamp: Utc::now(),
+        event,
+    };
+    writeln!(writer, "{}", redact::text(&serde_json::to_string(&record)?))?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Emit a message event for a line shown to the user
+pub fn message(level: MessageLevel, text: &str) {
+    if enabled() {
+        emit(Event::Message {
+            level,
+            text: text.to_string(),
+        });
+    }
+}
+
+/// The progress event for a `compiler-artifact` line of cargo's JSON output,
+/// or `None` when nobody listens for events
+pub fn progress(line: &str, compilation: &CompilationStats) -> Option<Event> {
+    if !enabled() {
+        return None;
+    }
+    let message: serde_json::Value = serde_json::from_str(line).ok()?;
+    if message.get("reason")?.as_str()? != "compiler-artifact" {
+        return None;
+    }
+    Some(Event::Progress {
+        package: message.get("target")?.get("name")?.as_str()?.to_string(),
+        fresh: message
+            .get("fresh")
+            .and_then(serde_json::Value::as_bool)
+            .unwrap_or(false),
+        crates_compiled: compilation.crates_compiled,
+        crates_fresh: compilation.crates_fresh,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn test_events_are_tagged_json_lines() {
+        let mut buffer = Vec::new();
+        write_event(
+            &mut buffer,
+            &Event::BuildFinished {
+                command: "check".to_string(),
+                success: true,
+                duration_secs: 1.5,
+                compilation: None,
+                sccache: None,
+            },
+        )
+        .unwrap();
+
+        let text = String::from_utf8(buffer).unwrap();
+        assert!(text.ends_with('\n'));
+        assert_eq!(text.lines().count(), 1);
+        let value: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(value["event"], "build_finished");
+        assert_eq!(value["duration_secs"], 1.5);
+        assert!(value["timestamp"].is_string());
+        assert!(value.get("sccache").is_none());
+    }
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(EventTarget::parse("-").unwrap(), EventTarget::Stderr);
+        assert_eq!(EventTarget::parse("fd:3").unwrap(), EventTarget::Fd(3));
+        assert_eq!(
+            EventTarget::parse("events.jsonl").unwrap(),
+            EventTarget::Path("events.jsonl".to_string())
+        );
+        assert!(EventTarget::parse("fd:x").is_err());
+    }
+}
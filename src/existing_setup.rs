@@ -0,0 +1,568 @@
+//! Detection of optimizations configured by hand before `atlas init`
+
+use crate::config::{CCompilerCache, DebugInfo, OptimizerConfig, SplitDebuginfo};
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::managed::without_blocks;
+use crate::manifest::Manifest;
+use std::fs;
+use std::path::Path;
+
+/// File an existing setting was found in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingSource {
+    CargoConfig,
+    Manifest,
+}
+
+impl SettingSource {
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Self::CargoConfig => ".cargo/config.toml",
+            Self::Manifest => "Cargo.toml",
+        }
+    }
+}
+
+/// How an existing setting relates to what Atlas generates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Disposition {
+    /// Carried over into Atlas's configuration
+    Imported,
+    /// Atlas writes the same setting itself
+    Redundant,
+    /// Has no equivalent in Atlas's configuration and would be lost on overwrite
+    Unsupported,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExistingSetting {
+    pub source: SettingSource,
+    /// Dotted TOML key, e.g. `target.x86_64-unknown-linux-gnu.rustflags`
+    pub key: String,
+    pub value: String,
+    pub disposition: Disposition,
+}
+
+/// Settings found in a project's Cargo configuration and manifest
+#[derive(Debug, Clone, Default)]
+pub struct ExistingSetup {
+    pub settings: Vec<ExistingSetting>,
+}
+
+impl ExistingSetup {
+    /// Classify the project's existing settings and import compatible ones into `config`.
+    /// Blocks Atlas generated itself and keys it marked are skipped.
+    pub fn import(project_root: &Path, config: &mut OptimizerConfig) -> OptimizerResult<Self> {
+        let mut setup = Self::default();
+
+        let cargo_config = project_root.join(".cargo").join("config.toml");
+        if cargo_config.exists() {
+            let value: toml::Value =
+                toml::from_str(&without_blocks(&fs::read_to_string(&cargo_config)?)?)?;
+            setup.import_cargo_config(&value, config);
+        }
+
+        let manifest = project_root.join("Cargo.toml");
+        if manifest.exists() {
+            // Keys Atlas merged into the user's profile tables are its own output
+            let mut user_settings =
+                Manifest::parse(&without_blocks(&fs::read_to_string(&manifest)?)?)?;
+            user_settings.remove_added_keys();
+            let value: toml::Value = toml::from_str(&user_settings.to_string())?;
+            setup.import_profiles(&value, config);
+        }
+
+        Ok(setup)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.settings.is_empty()
+    }
+
+    pub fn count(&self, disposition: Disposition) -> usize {
+        self.settings
+            .iter()
+            .filter(|setting| setting.disposition == disposition)
+            .count()
+    }
+
+    /// Settings in `source` that Atlas cannot reproduce
+    pub fn unsupported(&self, source: SettingSource) -> Vec<&ExistingSetting> {
+        self.settings
+            .iter()
+            .filter(|setting| {
+                setting.source == source && setting.disposition == Disposition::Unsupported
+            })
+            .collect()
+    }
+
+    fn record(
+        &mut self,
+        source: SettingSource,
+        key: &str,
+        value: &toml::Value,
+        disposition: Disposition,
+    ) {
+        self.settings.push(ExistingSetting {
+            source,
+            key: key.to_string(),
+            value: value.to_string(),
+            disposition,
+        });
+    }
+
+    fn import_cargo_config(&mut self, value: &toml::Value, config: &mut OptimizerConfig) {
+        let mut leaves = Vec::new();
+        flatten(value, String::new(), &mut leaves);
+
+        for (key, value) in leaves {
+            let disposition = if key.starts_with("target.") && key.ends_with(".rustflags")
+                || key == "build.rustflags"
+            {
+                import_rustflags(value, config)
+            } else if key.starts_with("target.") && key.ends_with(".linker") {
+                match value.as_str() {
+                    Some("clang") => Disposition::Redundant,
+                    _ => Disposition::Unsupported,
+                }
+            } else if let Some(name) = key.strip_prefix("env.") {
+                import_env(name, value, config)
+            } else {
+                import_cargo_key(&key, value, config)
+            };
+            self.record(SettingSource::CargoConfig, &key, value, disposition);
+        }
+    }
+
+    fn import_profiles(&mut self, manifest: &toml::Value, config: &mut OptimizerConfig) {
+        let Some(dev) = manifest
+            .get("profile")
+            .and_then(|profile| profile.get("dev"))
+        else {
+            return;
+        };
+
+        if let Some(value) = dev.get("debug") {
+            if let Some(debug) = parse_debug(value) {
+                config.profiles.dev_debug = debug;
+                self.record(
+                    SettingSource::Manifest,
+                    "profile.dev.debug",
+                    value,
+                    Disposition::Imported,
+                );
+            }
+        }
+
+        if let Some(value) = dev.get("split-debuginfo") {
+            let split = match value.as_str() {
+                Some("off") => Some(SplitDebuginfo::Off),
+                Some("packed") => Some(SplitDebuginfo::Packed),
+                Some("unpacked") => Some(SplitDebuginfo::Unpacked),
+                _ => None,
+            };
+            if let Some(split) = split {
+                config.profiles.split_debuginfo = split;
+                self.record(
+                    SettingSource::Manifest,
+                    "profile.dev.split-debuginfo",
+                    value,
+                    Disposition::Imported,
+                );
+            }
+        }
+
+        let Some(packages) = dev.get("package").and_then(toml::Value::as_table) else {
+            return;
+        };
+        for (name, package) in packages {
+            let Some(value) = package.get("opt-level") else {
+                continue;
+            };
+            let key = format!("profile.dev.package.\"{}\".opt-level", name);
+            match (name.as_str(), value.as_integer()) {
+                ("*", Some(level @ 0..=3)) => {
+                    config.optimization.dependency_opt_level = level as u8;
+                    self.record(SettingSource::Manifest, &key, value, Disposition::Imported);
+                }
+                (_, Some(3)) => {
+                    if !config.optimization.hot_dependencies.contains(name) {
+                        config.optimization.hot_dependencies.push(name.clone());
+                    }
+                    self.record(SettingSource::Manifest, &key, value, Disposition::Imported);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Collect leaf values with dotted keys; `[env]` entries stay whole
+fn flatten<'a>(
+    value: &'a toml::Value,
+    prefix: String,
+    leaves: &mut Vec<(String, &'a toml::Value)>,
+) {
+    match value.as_table() {
+        Some(table) => {
+            for (key, value) in table {
+                let key = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                if key.starts_with("env.") {
+                    leaves.push((key, value));
+                } else {
+                    flatten(value, key, leaves);
+                }
+            }
+        }
+        _ => leaves.push((prefix, value)),
+    }
+}
+
+fn import_cargo_key(key: &str, value: &toml::Value, config: &mut OptimizerConfig) -> Disposition {
+    match (key, value) {
+        ("build.jobs", toml::Value::Integer(jobs)) if *jobs > 0 => {
+            config.build.parallel_jobs = Some(*jobs as usize);
+            Disposition::Imported
+        }
+        ("build.incremental", toml::Value::Boolean(incremental)) => {
+            config.build.incremental = *incremental;
+            Disposition::Imported
+        }
+        ("build.rustc-wrapper", toml::Value::String(wrapper)) => {
+            import_rustc_wrapper(wrapper, config)
+        }
+        ("build.target-dir", toml::Value::String(dir)) if dir == "target" => Disposition::Redundant,
+        ("build.pipelining", _)
+        | ("registries.crates-io.protocol", _)
+        | ("net.retry", _)
+        | ("net.git-fetch-with-cli", _) => Disposition::Redundant,
+        _ => Disposition::Unsupported,
+    }
+}
+
+fn import_rustc_wrapper(wrapper: &str, config: &mut OptimizerConfig) -> Disposition {
+    if wrapper_name(wrapper) == "sccache" {
+        config.build.enable_sccache = true;
+        Disposition::Imported
+    } else {
+        Disposition::Unsupported
+    }
+}
+
+fn import_env(name: &str, value: &toml::Value, config: &mut OptimizerConfig) -> Disposition {
+    // `[env]` values are either strings or `{ value = "...", ... }` tables
+    let text = value
+        .as_str()
+        .or_else(|| value.get("value").and_then(toml::Value::as_str))
+        .unwrap_or_default();
+
+    match name {
+        "CARGO_INCREMENTAL" => {
+            config.build.incremental = text != "0";
+            Disposition::Imported
+        }
+        "RUSTC_WRAPPER" => import_rustc_wrapper(text, config),
+        "CARGO_TARGET_DIR" if value.get("condition").is_some() => {
+            config.build.separate_rust_analyzer_target = true;
+            Disposition::Imported
+        }
+        "CC" | "CXX" => {
+            let mut parts = text.split_whitespace();
+            match (parts.next().map(wrapper_name), parts.next()) {
+                (Some("ccache"), Some(_)) => {
+                    config.build.c_compiler_cache = CCompilerCache::Ccache;
+                    Disposition::Imported
+                }
+                (Some("sccache"), Some(_)) => {
+                    config.build.c_compiler_cache = CCompilerCache::Sccache;
+                    Disposition::Imported
+                }
+                (Some("cc" | "c++" | "clang" | "clang++"), None) => Disposition::Redundant,
+                _ => Disposition::Unsupported,
+            }
+        }
+        "AR" if text == "llvm-ar" => Disposition::Redundant,
+        "CFLAGS" | "CXXFLAGS" if text == "-flto=thin" => Disposition::Redundant,
+        "CARGO_PROFILE_DEV_INCREMENTAL"
+        | "CARGO_BUILD_CACHE"
+        | "CARGO_NET_RETRY"
+        | "CARGO_NET_GIT_FETCH_WITH_CLI" => Disposition::Redundant,
+        _ => Disposition::Unsupported,
+    }
+}
+
+/// Import rustflags; any flag Atlas does not generate makes the whole list unsupported
+fn import_rustflags(value: &toml::Value, config: &mut OptimizerConfig) -> Disposition {
+    let tokens: Vec<&str> = match value {
+        toml::Value::String(flags) => flags.split_whitespace().collect(),
+        toml::Value::Array(flags) => flags.iter().filter_map(toml::Value::as_str).collect(),
+        _ => return Disposition::Unsupported,
+    };
+    let options = match codegen_options(&tokens) {
+        Some(options) => options,
+        None => return Disposition::Unsupported,
+    };
+
+    let uses_plugin_lto = options.contains(&"linker-plugin-lto");
+    let mut imported = config.clone();
+    let mut disposition = Disposition::Redundant;
+    for option in options {
+        if let Some(cpu) = option.strip_prefix("target-cpu=") {
+            imported.build.target_cpu = cpu.to_string();
+            disposition = Disposition::Imported;
+        } else if option == "linker-plugin-lto" {
+            imported.build.linker_plugin_lto = true;
+            disposition = Disposition::Imported;
+        } else if let Some(linker) = option.strip_prefix("link-arg=-fuse-ld=") {
+            match wrapper_name(linker) {
+                "mold" | "zld" => imported.build.use_fast_linker = true,
+                "lld" if uses_plugin_lto => {}
+                _ => return Disposition::Unsupported,
+            }
+            disposition = Disposition::Imported;
+        } else if !(option.starts_with("codegen-units=")
+            || option == "link-arg=-Wl,-dead_strip"
+            || option == "link-arg=-Wl,-no_compact_unwind")
+        {
+            return Disposition::Unsupported;
+        }
+    }
+
+    *config = imported;
+    disposition
+}
+
+/// `-C` options in a rustflags list, or `None` if it contains any other flag
+fn codegen_options<'a>(tokens: &[&'a str]) -> Option<Vec<&'a str>> {
+    let mut options = Vec::new();
+    let mut tokens = tokens.iter();
+    while let Some(token) = tokens.next() {
+        match *token {
+            "-C" | "--codegen" => options.push(*tokens.next()?),
+            token => options.push(token.strip_prefix("-C")?),
+        }
+    }
+    Some(options)
+}
+
+/// File name of a program given as a name or path
+fn wrapper_name(program: &str) -> &str {
+    Path::new(program)
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .unwrap_or(program)
+}
+
+fn parse_debug(value: &toml::Value) -> Option<DebugInfo> {
+    match value {
+        toml::Value::Boolean(true) | toml::Value::Integer(2) => Some(DebugInfo::Full),
+        toml::Value::Boolean(false) | toml::Value::Integer(0) => Some(DebugInfo::None),
+        toml::Value::Integer(1) => Some(DebugInfo::Limited),
+        toml::Value::String(level) => match level.as_str() {
+            "none" => Some(DebugInfo::None),
+            "line-tables-only" | "line-directives-only" => Some(DebugInfo::LineTablesOnly),
+            "limited" => Some(DebugInfo::Limited),
+            "full" => Some(DebugInfo::Full),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Drop the tables of `generated` that `manifest` already defines, so appending
+/// the rest never produces duplicate tables
+pub fn without_existing_tables(generated: &str, manifest: &str) -> OptimizerResult<String> {
+    let existing: toml::Value = toml::from_str(manifest)?;
+
+    let mut output = String::new();
+    let mut keep = true;
+    for line in generated.lines() {
+        if let Some(header) = table_header(line) {
+            keep = !has_table(&existing, &header);
+        }
+        if keep {
+            output.push_str(line);
+            output.push('\n');
+        }
+    }
+    Ok(output)
+}
+
+/// Settings of `generated` in the tables `manifest` already defines, which
+/// [`without_existing_tables`] leaves out, as (table, key, TOML value) in the
+/// order they are generated
+pub fn existing_table_keys(
+    generated: &str,
+    manifest: &str,
+) -> OptimizerResult<Vec<(String, String, String)>> {
+    fn collect(
+        table: &toml_edit::Table,
+        path: &mut Vec<String>,
+        existing: &toml::Value,
+        keys: &mut Vec<(String, String, String)>,
+    ) {
+        for (key, item) in table.iter() {
+            if let Some(child) = item.as_table() {
+                path.push(key.to_string());
+                collect(child, path, existing, keys);
+                path.pop();
+            } else if let Some(value) = item.as_value() {
+                if !path.is_empty() && has_table(existing, path) {
+                    keys.push((
+                        path.join("."),
+                        key.to_string(),
+                        value.clone().decorated("", "").to_string(),
+                    ));
+                }
+            }
+        }
+    }
+
+    let existing: toml::Value = toml::from_str(manifest)?;
+    let generated: toml_edit::DocumentMut = generated
+        .parse()
+        .map_err(|e| OptimizerError::config(format!("Invalid TOML: {}", e)))?;
+    let mut keys = Vec::new();
+    collect(generated.as_table(), &mut Vec::new(), &existing, &mut keys);
+    Ok(keys)
+}
+
+/// Key path of a `[table.header]` line
+fn table_header(line: &str) -> Option<Vec<String>> {
+    let header = line.trim().strip_prefix('[')?.strip_suffix(']')?;
+    if header.starts_with('[') {
+        return None;
+    }
+
+    let mut path = Vec::new();
+    let mut segment = String::new();
+    let mut quoted = false;
+    for c in header.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            '.' if !quoted => path.push(std::mem::take(&mut segment).trim().to_string()),
+            c => segment.push(c),
+        }
+    }
+    path.push(segment.trim().to_string());
+    Some(path)
+}
+
+fn has_table(value: &toml::Value, path: &[String]) -> bool {
+    path.iter()
+        .try_fold(value, |value, key| value.get(key))
+        .is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_cargo_config() {
+        let value: toml::Value = toml::from_str(
+            r#"
+[build]
+jobs = 6
+rustc-wrapper = "/usr/local/bin/sccache"
+
+[target.x86_64-unknown-linux-gnu]
+linker = "clang"
+rustflags = ["-C", "link-arg=-fuse-ld=mold", "-Ctarget-cpu=x86-64-v3"]
+
+[env]
+CC = "ccache cc"
+
+[alias]
+xtask = "run -p xtask --"
+"#,
+        )
+        .unwrap();
+
+        let mut config = OptimizerConfig::default();
+        config.build.use_fast_linker = false;
+        let mut setup = ExistingSetup::default();
+        setup.import_cargo_config(&value, &mut config);
+
+        assert_eq!(config.build.parallel_jobs, Some(6));
+        assert!(config.build.use_fast_linker);
+        assert_eq!(config.build.target_cpu, "x86-64-v3");
+        assert_eq!(config.build.c_compiler_cache, CCompilerCache::Ccache);
+        assert_eq!(setup.count(Disposition::Imported), 4);
+        assert_eq!(setup.count(Disposition::Redundant), 1);
+
+        let unsupported = setup.unsupported(SettingSource::CargoConfig);
+        assert_eq!(unsupported.len(), 1);
+        assert_eq!(unsupported[0].key, "alias.xtask");
+    }
+
+    #[test]
+    fn test_unknown_rustflags_are_not_imported() {
+        let value: toml::Value = toml::from_str(
+            "[target.x86_64-unknown-linux-gnu]\nrustflags = \"-C target-cpu=znver3 --cfg tokio_unstable\"\n",
+        )
+        .unwrap();
+
+        let mut config = OptimizerConfig::default();
+        let mut setup = ExistingSetup::default();
+        setup.import_cargo_config(&value, &mut config);
+
+        assert_eq!(config.build.target_cpu, "native");
+        assert_eq!(setup.count(Disposition::Unsupported), 1);
+    }
+
+    #[test]
+    fn test_import_profiles() {
+        let manifest: toml::Value = toml::from_str(
+            r#"
+[profile.dev]
+debug = 1
+split-debuginfo = "packed"
+
+[profile.dev.package."*"]
+opt-level = 2
+
+[profile.dev.package.image]
+opt-level = 3
+"#,
+        )
+        .unwrap();
+
+        let mut config = OptimizerConfig::default();
+        let mut setup = ExistingSetup::default();
+        setup.import_profiles(&manifest, &mut config);
+
+        assert_eq!(config.profiles.dev_debug, DebugInfo::Limited);
+        assert_eq!(config.profiles.split_debuginfo, SplitDebuginfo::Packed);
+        assert_eq!(config.optimization.dependency_opt_level, 2);
+        assert_eq!(config.optimization.hot_dependencies, vec!["image"]);
+        assert_eq!(setup.count(Disposition::Imported), 4);
+    }
+
+    #[test]
+    fn test_without_existing_tables() {
+        let generated = "# profiles\n[profile.dev]\nopt-level = 0\n\n[profile.dev.package.\"*\"]\nopt-level = 1\n\n[profile.release]\nlto = \"thin\"\n";
+        let manifest = "[package]\nname = \"demo\"\n\n[profile.release]\nlto = true\n";
+
+        let remaining = without_existing_tables(generated, manifest).unwrap();
+        assert!(remaining.contains("[profile.dev]"));
+        assert!(remaining.contains("[profile.dev.package.\"*\"]"));
+        assert!(!remaining.contains("[profile.release]"));
+
+        let combined = format!("{}\n{}", manifest, remaining);
+        assert!(toml::from_str::<toml::Value>(&combined).is_ok());
+
+        assert_eq!(
+            existing_table_keys(generated, manifest).unwrap(),
+            vec![(
+                "profile.release".to_string(),
+                "lto".to_string(),
+                "\"thin\"".to_string()
+            )]
+        );
+    }
+}
@@ -0,0 +1,160 @@
+//! The environment a measurement was taken in
+//!
+//! Build times only compare within the same environment: a new toolchain, a
+//! different CPU or a laptop on battery changes them as much as any setting.
+//! Baselines and build statistics store a fingerprint, so mixing measurements
+//! across environments is flagged instead of passing unnoticed.
+
+use crate::config::OptimizerConfig;
+use crate::error::OptimizerResult;
+use crate::host::{Host, SystemProbe};
+use crate::power::PowerState;
+use crate::utils::{command_stdout, execute_command, stable_hash};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    /// `rustc --version`
+    pub toolchain: Option<String>,
+    /// Hash of the Atlas config
+    pub config_hash: String,
+    pub git_commit: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_battery: Option<bool>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub low_power_mode: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+impl Fingerprint {
+    pub fn capture(project_root: &Path, config: &OptimizerConfig) -> OptimizerResult<Self> {
+        let config_toml = toml::to_string(config)?;
+        let power = PowerState::detect(&Host.os(), &Host);
+        Ok(Self {
+            toolchain: command_stdout("rustc", &["--version"], project_root),
+            config_hash: stable_hash(config_toml.as_bytes()),
+            git_commit: command_stdout("git", &["rev-parse", "HEAD"], project_root),
+            cpu_model: cpu_model(),
+            on_battery: power.on_battery,
+            low_power_mode: power.low_power_mode,
+        })
+    }
+
+    /// Differences that make times incomparable, as `what: before → after`.
+    /// Commits and config differ on purpose between measurements and are not
+    /// included; neither is anything one side did not record.
+    pub fn incompatibilities(&self, other: &Self) -> Vec<String> {
+        let mut differences = Vec::new();
+        let mut compare = |what: &str, before: Option<String>, after: Option<String>| {
+            if let (Some(before), Some(after)) = (before, after) {
+                if before != after {
+                    differences.push(format!("{}: {} → {}", what, before, after));
+                }
+            }
+        };
+        compare("toolchain", self.toolchain.clone(), other.toolchain.clone());
+        compare("CPU", self.cpu_model.clone(), other.cpu_model.clone());
+        compare("power", self.power(), other.power());
+        differences
+    }
+
+    /// The parts that make up the environment, for grouping measurements
+    pub fn environment(&self) -> (Option<&str>, Option<&str>, Option<String>) {
+        (
+            self.toolchain.as_deref(),
+            self.cpu_model.as_deref(),
+            self.power(),
+        )
+    }
+
+    fn power(&self) -> Option<String> {
+        let source = match self.on_battery? {
+            true => "battery",
+            false => "AC",
+        };
+        Some(if self.low_power_mode {
+            format!("{}, low power mode", source)
+        } else {
+            source.to_string()
+        })
+    }
+}
+
+/// Marketing name of the CPU, e.g. `AMD Ryzen 9 7950X 16-Core Processor`
+pub fn cpu_model() -> Option<String> {
+    if let Ok(cpuinfo) = fs::read_to_string("/proc/cpuinfo") {
+        return cpu_model_from_cpuinfo(&cpuinfo);
+    }
+    if cfg!(target_os = "macos") {
+        return execute_command("sysctl", &["-n", "machdep.cpu.brand_string"], None)
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .filter(|model| !model.is_empty());
+    }
+    std::env::var("PROCESSOR_IDENTIFIER").ok()
+}
+
+/// `model name` on x86, `Model` or `Hardware` on ARM boards
+fn cpu_model_from_cpuinfo(cpuinfo: &str) -> Option<String> {
+    ["model name", "Model", "Hardware"].iter().find_map(|key| {
+        cpuinfo.lines().find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            (name.trim() == *key && !value.trim().is_empty()).then(|| value.trim().to_string())
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_incompatibilities() {
+        let before = Fingerprint {
+            toolchain: Some("rustc 1.80.0".to_string()),
+            config_hash: "a".to_string(),
+            git_commit: Some("1111".to_string()),
+            cpu_model: Some("Apple M2".to_string()),
+            on_battery: Some(false),
+            low_power_mode: false,
+        };
+        let after = Fingerprint {
+            config_hash: "b".to_string(),
+            git_commit: Some("2222".to_string()),
+            on_battery: Some(true),
+            ..before.clone()
+        };
+        assert_eq!(
+            before.incompatibilities(&after),
+            vec!["power: AC → battery"]
+        );
+        // Baselines from before fingerprints only know the toolchain
+        let old = Fingerprint {
+            toolchain: Some("rustc 1.79.0".to_string()),
+            ..Fingerprint::default()
+        };
+        assert_eq!(
+            old.incompatibilities(&before),
+            vec!["toolchain: rustc 1.79.0 → rustc 1.80.0"]
+        );
+
+        let cpuinfo = "processor\t: 0\nvendor_id\t: GenuineIntel\nmodel name\t: Intel(R) Core(TM) i7-1165G7 @ 2.80GHz\n";
+        assert_eq!(
+            cpu_model_from_cpuinfo(cpuinfo).as_deref(),
+            Some("Intel(R) Core(TM) i7-1165G7 @ 2.80GHz")
+        );
+        assert_eq!(
+            cpu_model_from_cpuinfo("processor : 0\nModel : Raspberry Pi 4 Model B Rev 1.4\n")
+                .as_deref(),
+            Some("Raspberry Pi 4 Model B Rev 1.4")
+        );
+    }
+}
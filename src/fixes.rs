@@ -0,0 +1,270 @@
+//! Guarded automatic fixes applied by `atlas doctor --fix`. Which fixes
+//! apply is decided by the rules in [`crate::rules`].
+//!
+//! File edits are made with [`Manifest`], which keeps comments and formatting,
+//! and go through a [`Transaction`]: every edited file must still parse as
+//! TOML, and if any edit fails all files are restored to their original
+//! content. Tool installations run afterwards and cannot be rolled back.
+
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::manifest::Manifest;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Tables whose keys Cargo spells with dashes only
+const DASHED_TABLES: &[&str] = &["build", "net", "http", "profile.*"];
+
+/// One change `doctor --fix` can make
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fix {
+    /// Rename a key Cargo ignores, e.g. `codegen_units` to `codegen-units`
+    RenameKey {
+        path: PathBuf,
+        table: String,
+        from: String,
+        to: String,
+    },
+    /// Add `key = value` to a table; `value` is a TOML literal
+    SetKey {
+        path: PathBuf,
+        table: String,
+        key: String,
+        value: String,
+    },
+    /// Install a tool with `atlas install-tools`
+    InstallTool(String),
+}
+
+impl Fix {
+    pub fn description(&self) -> String {
+        match self {
+            Self::RenameKey {
+                path,
+                table,
+                from,
+                to,
+            } => format!(
+                "Rename [{}] {} to {} in {}",
+                table,
+                from,
+                to,
+                path.display()
+            ),
+            Self::SetKey {
+                path,
+                table,
+                key,
+                value,
+            } => format!("Set [{}] {} = {} in {}", table, key, value, path.display()),
+            Self::InstallTool(tool) => format!("Install {}", tool),
+        }
+    }
+
+    /// The file this fix edits, if any
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::RenameKey { path, .. } | Self::SetKey { path, .. } => Some(path),
+            Self::InstallTool(_) => None,
+        }
+    }
+
+    /// New content for the edited file, or `None` if the fix does not apply to `content`
+    pub fn edit(&self, content: &str) -> Option<String> {
+        match self {
+            Self::RenameKey {
+                table, from, to, ..
+            } => rename_key(content, table, from, to),
+            Self::SetKey {
+                table, key, value, ..
+            } => set_key(content, table, key, value),
+            Self::InstallTool(_) => None,
+        }
+    }
+}
+
+/// Keys with underscores in tables where Cargo only reads dashed keys
+pub fn misspelled_keys(path: &Path) -> OptimizerResult<Vec<Fix>> {
+    let Ok(content) = fs::read_to_string(path) else {
+        return Ok(Vec::new());
+    };
+    let document: toml::Table = toml::from_str(&content)?;
+
+    let mut fixes = Vec::new();
+    let mut check = |table_name: String, table: &toml::Table| {
+        for key in table.keys().filter(|key| key.contains('_')) {
+            let dashed = key.replace('_', "-");
+            if !table.contains_key(&dashed) {
+                fixes.push(Fix::RenameKey {
+                    path: path.to_path_buf(),
+                    table: table_name.clone(),
+                    from: key.clone(),
+                    to: dashed,
+                });
+            }
+        }
+    };
+    for name in DASHED_TABLES {
+        match name.strip_suffix(".*") {
+            Some(parent) => {
+                let tables = document.get(parent).and_then(toml::Value::as_table);
+                for (child, value) in tables.into_iter().flatten() {
+                    if let Some(table) = value.as_table() {
+                        check(format!("{}.{}", parent, child), table);
+                    }
+                }
+            }
+            None => {
+                if let Some(table) = document.get(*name).and_then(toml::Value::as_table) {
+                    check(name.to_string(), table);
+                }
+            }
+        }
+    }
+    Ok(fixes)
+}
+
+fn rename_key(content: &str, table: &str, from: &str, to: &str) -> Option<String> {
+    let mut manifest = Manifest::parse(content).ok()?;
+    manifest
+        .rename_key(table, from, to)
+        .then(|| manifest.to_string())
+}
+
+/// Add `key`; a key that is already set is left to the user
+fn set_key(content: &str, table: &str, key: &str, value: &str) -> Option<String> {
+    let mut manifest = Manifest::parse(content).ok()?;
+    if manifest.contains_key(table, key) {
+        return None;
+    }
+    manifest
+        .set_key(table, key, value)
+        .ok()?
+        .then(|| manifest.to_string())
+}
+
+/// File edits that are all kept or all undone
+#[derive(Debug, Default)]
+pub struct Transaction {
+    /// Content of each edited file before its first edit; `None` if it did not exist
+    originals: Vec<(PathBuf, Option<String>)>,
+    applied: Vec<String>,
+}
+
+impl Transaction {
+    /// Apply a file-editing fix, checking that the result is still valid TOML
+    pub fn apply(&mut self, fix: &Fix) -> OptimizerResult<()> {
+        let path = fix
+            .path()
+            .ok_or_else(|| OptimizerError::invalid_input("Only file edits run in a transaction"))?;
+        let current = fs::read_to_string(path).ok();
+        let updated = fix
+            .edit(current.as_deref().unwrap_or_default())
+            .ok_or_else(|| {
+                OptimizerError::config(format!("No longer applies: {}", fix.description()))
+            })?;
+        toml::from_str::<toml::Table>(&updated).map_err(|e| {
+            OptimizerError::config(format!(
+                "{} would leave invalid TOML: {}",
+                fix.description(),
+                e
+            ))
+        })?;
+
+        if !self.originals.iter().any(|(edited, _)| edited == path) {
+            self.originals.push((path.to_path_buf(), current));
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        crate::audit::write(path, updated)?;
+        self.applied.push(fix.description());
+        Ok(())
+    }
+
+    /// Descriptions of the fixes applied so far
+    pub fn applied(&self) -> &[String] {
+        &self.applied
+    }
+
+    /// Restore every edited file to its original content
+    pub fn rollback(self) -> OptimizerResult<()> {
+        for (path, original) in self.originals.into_iter().rev() {
+            match original {
+                Some(content) => crate::audit::write(&path, content)?,
+                None => crate::audit::remove(&path)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edits_skip_atlas_blocks() {
+        let content = "[profile.dev]\nopt_level = 1\n";
+        assert_eq!(
+            rename_key(content, "profile.dev", "opt_level", "opt-level").unwrap(),
+            "[profile.dev]\nopt-level = 1\n"
+        );
+        assert_eq!(
+            set_key("[package]\nname = \"demo\"\n", "build", "jobs", "4").unwrap(),
+            "[package]\nname = \"demo\"\n\n[build]\njobs = 4\n"
+        );
+
+        let stamp = crate::managed::Stamp {
+            version: "0.1.0".to_string(),
+            config_hash: "0".repeat(16),
+            generated: chrono::Utc::now(),
+        };
+        let managed =
+            crate::managed::render_block(crate::managed::PROFILES_BLOCK, "[profile.dev]\n", &stamp);
+        assert_eq!(set_key(&managed, "profile.dev", "debug", "1"), None);
+    }
+
+    #[test]
+    fn test_rollback_restores_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = dir.path().join("Cargo.toml");
+        let config = dir.path().join(".cargo").join("config.toml");
+        fs::write(&manifest, "[profile.dev]\ncodegen_units = 4\n").unwrap();
+
+        let mut transaction = Transaction::default();
+        transaction
+            .apply(&Fix::RenameKey {
+                path: manifest.clone(),
+                table: "profile.dev".to_string(),
+                from: "codegen_units".to_string(),
+                to: "codegen-units".to_string(),
+            })
+            .unwrap();
+        transaction
+            .apply(&Fix::SetKey {
+                path: config.clone(),
+                table: "build".to_string(),
+                key: "rustc-wrapper".to_string(),
+                value: "\"sccache\"".to_string(),
+            })
+            .unwrap();
+        assert_eq!(transaction.applied().len(), 2);
+
+        // A key that is already set is not overwritten
+        assert!(transaction
+            .apply(&Fix::SetKey {
+                path: manifest.clone(),
+                table: "profile.dev".to_string(),
+                key: "codegen-units".to_string(),
+                value: "1".to_string(),
+            })
+            .is_err());
+
+        transaction.rollback().unwrap();
+        assert_eq!(
+            fs::read_to_string(&manifest).unwrap(),
+            "[profile.dev]\ncodegen_units = 4\n"
+        );
+        assert!(!config.exists());
+    }
+}
@@ -0,0 +1,172 @@
+//! Fleets: repositories that share one Atlas setup, listed in a manifest so
+//! `atlas fleet` can initialize, refresh and check all of them in one run
+
+use crate::config::OptimizerConfig;
+use crate::error::{OptimizerError, OptimizerResult};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The repositories of a fleet, read from `fleet.toml`:
+///
+/// ```toml
+/// [[repo]]
+/// path = "~/src/api"
+///
+/// [[repo]]
+/// name = "web"
+/// path = "../frontend-server"
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct FleetManifest {
+    #[serde(default, rename = "repo")]
+    pub repos: Vec<FleetRepo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FleetRepo {
+    /// Defaults to the last component of the path
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Relative paths are relative to the manifest; `~` is the home directory
+    pub path: PathBuf,
+}
+
+impl FleetRepo {
+    pub fn name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| {
+            self.path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| self.path.display().to_string())
+        })
+    }
+}
+
+impl FleetManifest {
+    /// Default manifest, next to the Atlas config
+    pub fn default_path() -> OptimizerResult<PathBuf> {
+        Ok(OptimizerConfig::get_config_path()?.with_file_name("fleet.toml"))
+    }
+
+    pub fn load(path: &Path) -> OptimizerResult<Self> {
+        if !path.exists() {
+            return Err(OptimizerError::file_not_found(format!(
+                "{} (list the repositories as [[repo]] entries with a path)",
+                path.display()
+            )));
+        }
+        let manifest: Self = toml::from_str(&fs::read_to_string(path)?)?;
+        if manifest.repos.is_empty() {
+            return Err(OptimizerError::config(format!(
+                "{} lists no repositories",
+                path.display()
+            )));
+        }
+        Ok(manifest)
+    }
+
+    /// Where `repo` lives, given the manifest was read from `manifest_path`
+    pub fn root(repo: &FleetRepo, manifest_path: &Path) -> PathBuf {
+        let path = match repo.path.strip_prefix("~") {
+            Ok(rest) => match dirs::home_dir() {
+                Some(home) => home.join(rest),
+                None => repo.path.clone(),
+            },
+            Err(_) => repo.path.clone(),
+        };
+        if path.is_absolute() {
+            path
+        } else {
+            manifest_path
+                .parent()
+                .unwrap_or_else(|| Path::new("."))
+                .join(path)
+        }
+    }
+}
+
+/// How one repository fared in a fleet run
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepoReport {
+    pub name: String,
+    pub root: PathBuf,
+    /// Why the command failed in this repository
+    pub failure: Option<String>,
+    /// Ways the repository differs from the shared setup
+    pub drift: Vec<String>,
+    /// Recommendations `atlas status` would show
+    pub findings: usize,
+}
+
+impl RepoReport {
+    pub fn new(name: String, root: PathBuf) -> Self {
+        Self {
+            name,
+            root,
+            failure: None,
+            drift: Vec::new(),
+            findings: 0,
+        }
+    }
+}
+
+/// Counts over every repository of a fleet run
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FleetSummary {
+    pub repos: usize,
+    pub failed: usize,
+    pub drifted: usize,
+}
+
+impl FleetSummary {
+    pub fn new(reports: &[RepoReport]) -> Self {
+        Self {
+            repos: reports.len(),
+            failed: reports.iter().filter(|r| r.failure.is_some()).count(),
+            drifted: reports
+                .iter()
+                .filter(|r| r.failure.is_none() && !r.drift.is_empty())
+                .count(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_paths_and_summary() {
+        let manifest: FleetManifest = toml::from_str(
+            "[[repo]]\npath = \"api\"\n\n[[repo]]\nname = \"web\"\npath = \"/srv/frontend\"\n",
+        )
+        .unwrap();
+        let manifest_path = Path::new("/home/dev/fleet.toml");
+        assert_eq!(manifest.repos[0].name(), "api");
+        assert_eq!(manifest.repos[1].name(), "web");
+        assert_eq!(
+            FleetManifest::root(&manifest.repos[0], manifest_path),
+            PathBuf::from("/home/dev/api")
+        );
+        assert_eq!(
+            FleetManifest::root(&manifest.repos[1], manifest_path),
+            PathBuf::from("/srv/frontend")
+        );
+
+        let mut failed = RepoReport::new("api".into(), "/home/dev/api".into());
+        failed.failure = Some("no Cargo.toml".into());
+        failed.drift.push("not initialized".into());
+        let mut drifted = RepoReport::new("web".into(), "/srv/frontend".into());
+        drifted.drift.push("stale".into());
+        let clean = RepoReport::new("cli".into(), "/srv/cli".into());
+        assert_eq!(
+            FleetSummary::new(&[failed, drifted, clean]),
+            FleetSummary {
+                repos: 3,
+                failed: 1,
+                drifted: 1
+            }
+        );
+    }
+}
@@ -0,0 +1,192 @@
+//! `cargo fmt` across the workspace with statistics
+//!
+//! rustfmt runs with `--verbose`, which names every file it formats, and
+//! `-l`, which names those whose formatting changed or, with `--check`,
+//! would change. A pre-commit hook runs the check before every commit.
+
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::utils::{command_stdout, execute_command_streaming, Stream};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Line identifying a pre-commit hook written by Atlas
+const HOOK_MARKER: &str = "# Installed by `atlas build fmt --install-hook`";
+
+/// Files rustfmt looked at, and which of them were not formatted
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FmtReport {
+    pub files: usize,
+    /// Reformatted, or with `--check` still in need of it, relative to the project
+    pub unformatted: Vec<PathBuf>,
+    pub duration: Duration,
+}
+
+impl FmtReport {
+    /// Take in one line of rustfmt's `--verbose -l` output
+    pub fn observe(&mut self, line: &str, project_root: &Path) {
+        if line.starts_with("Formatting ") {
+            self.files += 1;
+        } else if line.ends_with(".rs") && !line.contains(' ') {
+            let path = Path::new(line);
+            self.unformatted.push(
+                path.strip_prefix(project_root)
+                    .unwrap_or(path)
+                    .to_path_buf(),
+            );
+        }
+    }
+}
+
+/// Cargo arguments for formatting the members in `scope`; `cargo fmt`
+/// spells `--workspace` as `--all`
+pub fn cargo_args(scope: &[String], check: bool) -> Vec<String> {
+    let mut args = vec!["fmt".to_string()];
+    args.extend(scope.iter().map(|arg| match arg.as_str() {
+        "--workspace" => "--all".to_string(),
+        _ => arg.clone(),
+    }));
+    args.extend(["--", "--verbose", "-l"].map(String::from));
+    if check {
+        args.push("--check".to_string());
+    }
+    args
+}
+
+/// Format the members in `scope`, or with `check` only report what is not
+/// formatted. Unformatted files are not an error; rustfmt failing, e.g. on
+/// a syntax error, is.
+pub fn run(
+    project_root: &Path,
+    scope: &[String],
+    check: bool,
+    timeout: Option<Duration>,
+) -> OptimizerResult<FmtReport> {
+    let args = cargo_args(scope, check);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    // Canonical, as rustfmt prints absolute paths with symlinks resolved
+    let root = fs::canonicalize(project_root).unwrap_or_else(|_| project_root.to_path_buf());
+    let mut report = FmtReport::default();
+    let start = Instant::now();
+    let result = execute_command_streaming(
+        "cargo",
+        &args,
+        Some(project_root),
+        timeout,
+        false,
+        |stream, line| {
+            if stream == Stream::Stdout {
+                report.observe(line, &root);
+            }
+        },
+    );
+    report.duration = start.elapsed();
+    match result {
+        Err(OptimizerError::CommandFailed(_)) if check && !report.unformatted.is_empty() => {
+            Ok(report)
+        }
+        result => result.map(|()| report),
+    }
+}
+
+/// What [`install_hook`] did
+#[derive(Debug, Clone, PartialEq)]
+pub enum HookInstall {
+    Installed(PathBuf),
+    AlreadyInstalled(PathBuf),
+    /// Another pre-commit hook is in place and was left alone
+    Existing(PathBuf),
+}
+
+/// The command the pre-commit hook runs
+pub const HOOK_COMMAND: &str = "atlas --quiet build fmt --check";
+
+fn hook_script() -> String {
+    format!("#!/bin/sh\n{}\nexec {}\n", HOOK_MARKER, HOOK_COMMAND)
+}
+
+/// Write a pre-commit hook that rejects commits with unformatted files. An
+/// existing hook of another origin is never overwritten.
+pub fn install_hook(project_root: &Path) -> OptimizerResult<HookInstall> {
+    // Honors core.hooksPath and worktrees
+    let hooks = command_stdout("git", &["rev-parse", "--git-path", "hooks"], project_root)
+        .ok_or_else(|| OptimizerError::invalid_input("Not a git repository"))?;
+    let path = project_root.join(hooks).join("pre-commit");
+    if let Ok(existing) = fs::read_to_string(&path) {
+        return Ok(if existing.contains(HOOK_MARKER) {
+            HookInstall::AlreadyInstalled(path)
+        } else {
+            HookInstall::Existing(path)
+        });
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    crate::audit::write(&path, hook_script())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(HookInstall::Installed(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_from_rustfmt_output() {
+        let root = Path::new("/ws");
+        let mut report = FmtReport::default();
+        for line in [
+            "Formatting /ws/crates/core/src/lib.rs",
+            "/ws/crates/core/src/lib.rs",
+            "Spent 0.001 secs in the parsing phase, and 0.002 secs in the formatting phase",
+            "Formatting /ws/src/main.rs",
+            "Spent 0.000 secs in the parsing phase, and 0.000 secs in the formatting phase",
+        ] {
+            report.observe(line, root);
+        }
+        assert_eq!(report.files, 2);
+        assert_eq!(
+            report.unformatted,
+            vec![PathBuf::from("crates/core/src/lib.rs")]
+        );
+
+        assert_eq!(
+            cargo_args(&["--workspace".to_string()], true),
+            vec!["fmt", "--all", "--", "--verbose", "-l", "--check"]
+        );
+        assert_eq!(
+            cargo_args(&["-p".to_string(), "core".to_string()], false),
+            vec!["fmt", "-p", "core", "--", "--verbose", "-l"]
+        );
+    }
+
+    #[test]
+    fn test_install_hook_keeps_foreign_hooks() {
+        let dir = tempfile::tempdir().unwrap();
+        if crate::utils::execute_command("git", &["init", "-q"], Some(dir.path()))
+            .map_or(true, |output| !output.status.success())
+        {
+            return;
+        }
+        let installed = install_hook(dir.path()).unwrap();
+        let HookInstall::Installed(path) = installed else {
+            panic!("hook not installed: {:?}", installed);
+        };
+        assert!(fs::read_to_string(&path).unwrap().contains(HOOK_COMMAND));
+        assert_eq!(
+            install_hook(dir.path()).unwrap(),
+            HookInstall::AlreadyInstalled(path.clone())
+        );
+
+        fs::write(&path, "#!/bin/sh\nmake lint\n").unwrap();
+        assert_eq!(
+            install_hook(dir.path()).unwrap(),
+            HookInstall::Existing(path)
+        );
+    }
+}
@@ -0,0 +1,66 @@
+//! The files Atlas generates, as strings. `init` and `refresh` write these
+//! into managed blocks; tools embedding Atlas can call them directly.
+
+use crate::config::{
+    generate_cargo_config, generate_cargo_profiles, generate_minimal_cargo_config, ConfigScope,
+    OptimizerConfig,
+};
+use crate::error::OptimizerResult;
+use crate::existing_setup::{existing_table_keys, without_existing_tables};
+use crate::managed::without_blocks;
+use crate::system::SystemInfo;
+use crate::templates::{render_generated, Generated};
+
+/// Generated Cargo config for a scope. The user config also carries the build
+/// profiles, since Cargo config files accept the same `[profile]` tables as
+/// Cargo.toml, unless it is `minimal`.
+pub fn cargo_config(
+    config: &OptimizerConfig,
+    system_info: &SystemInfo,
+    scope: ConfigScope,
+    minimal: bool,
+) -> OptimizerResult<String> {
+    if minimal {
+        return Ok(generate_minimal_cargo_config(config, system_info));
+    }
+    let content = render_generated(
+        Generated::CargoConfig,
+        config,
+        Some(system_info),
+        generate_cargo_config(config, system_info, scope),
+    )?;
+    Ok(match scope {
+        ConfigScope::Project => content,
+        ConfigScope::User => format!("{}\n{}", content, profiles(config, system_info)?),
+    })
+}
+
+/// Generated profiles, through the user's template if there is one
+pub fn profiles(config: &OptimizerConfig, system_info: &SystemInfo) -> OptimizerResult<String> {
+    render_generated(
+        Generated::Profiles,
+        config,
+        Some(system_info),
+        generate_cargo_profiles(config, &system_info.os),
+    )
+}
+
+/// Generated profiles for a manifest, minus the tables the user defined
+/// outside Atlas's block
+pub fn profiles_for_manifest(
+    config: &OptimizerConfig,
+    system_info: &SystemInfo,
+    manifest: &str,
+) -> OptimizerResult<String> {
+    without_existing_tables(&profiles(config, system_info)?, &without_blocks(manifest)?)
+}
+
+/// Generated profile settings for the tables the user defined outside
+/// Atlas's block, as (table, key, TOML value); the block holds the rest
+pub fn profile_keys_for_manifest(
+    config: &OptimizerConfig,
+    system_info: &SystemInfo,
+    manifest: &str,
+) -> OptimizerResult<Vec<(String, String, String)>> {
+    existing_table_keys(&profiles(config, system_info)?, &without_blocks(manifest)?)
+}
@@ -0,0 +1,137 @@
+//! Per-project command history: every Atlas run inside a project is recorded
+//! in `.atlas/history.jsonl`, so `atlas again` can repeat the last command and
+//! `atlas last` can show what it did.
+
+use crate::error::OptimizerResult;
+use crate::stats::{BuildRecord, StatsStore};
+use crate::utils::create_parent_dirs;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Number of invocations kept per project
+pub const HISTORY_SIZE: usize = 50;
+
+/// One Atlas run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Invocation {
+    pub started_at: DateTime<Utc>,
+    /// Arguments after the program name
+    pub args: Vec<String>,
+    /// Working directory the arguments are relative to
+    pub cwd: PathBuf,
+    pub success: bool,
+    pub duration_secs: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Invocation {
+    pub fn finished_at(&self) -> DateTime<Utc> {
+        self.started_at + Duration::microseconds((self.duration_secs * 1_000_000.0).ceil() as i64)
+    }
+
+    pub fn command_line(&self) -> String {
+        let mut line = String::from("atlas");
+        for arg in &self.args {
+            line.push(' ');
+            if arg.is_empty() || arg.contains(char::is_whitespace) {
+                line.push_str(&format!("'{}'", arg));
+            } else {
+                line.push_str(arg);
+            }
+        }
+        line
+    }
+
+    /// Builds and checks this run recorded in the machine's build stats
+    pub fn builds(&self, project_root: &Path) -> OptimizerResult<Vec<BuildRecord>> {
+        let project = fs::canonicalize(project_root).unwrap_or_else(|_| project_root.to_path_buf());
+        let finished_at = self.finished_at();
+        Ok(StatsStore::load_since(self.started_at)?
+            .into_iter()
+            .filter(|record| record.project == project && record.recorded_at <= finished_at)
+            .collect())
+    }
+}
+
+pub struct History;
+
+impl History {
+    pub fn path(project_root: &Path) -> PathBuf {
+        project_root.join(".atlas").join("history.jsonl")
+    }
+
+    /// Invocations oldest first; lines that do not parse are skipped
+    pub fn load(project_root: &Path) -> OptimizerResult<Vec<Invocation>> {
+        let path = Self::path(project_root);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        Ok(fs::read_to_string(path)?
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    pub fn last(project_root: &Path) -> OptimizerResult<Option<Invocation>> {
+        Ok(Self::load(project_root)?.pop())
+    }
+
+    /// Append `invocation`, dropping the oldest once there are more than
+    /// [`HISTORY_SIZE`]
+    pub fn record(project_root: &Path, invocation: &Invocation) -> OptimizerResult<()> {
+        let path = Self::path(project_root);
+        create_parent_dirs(&path)?;
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        writeln!(file, "{}", serde_json::to_string(invocation)?)?;
+        drop(file);
+
+        let history = Self::load(project_root)?;
+        if history.len() > HISTORY_SIZE {
+            let mut kept = String::new();
+            for invocation in &history[history.len() - HISTORY_SIZE..] {
+                kept.push_str(&serde_json::to_string(invocation)?);
+                kept.push('\n');
+            }
+            fs::write(path, kept)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invocation(args: &[&str]) -> Invocation {
+        Invocation {
+            started_at: Utc::now(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            cwd: PathBuf::from("/work/app"),
+            success: true,
+            duration_secs: 1.5,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_record_keeps_latest() {
+        let dir = tempfile::tempdir().unwrap();
+        for index in 0..HISTORY_SIZE + 3 {
+            History::record(dir.path(), &invocation(&["run", &index.to_string()])).unwrap();
+        }
+        let history = History::load(dir.path()).unwrap();
+        assert_eq!(history.len(), HISTORY_SIZE);
+        assert_eq!(history[0].args, vec!["run", "3"]);
+
+        let last = History::last(dir.path()).unwrap().unwrap();
+        assert_eq!(last.args[1], (HISTORY_SIZE + 2).to_string());
+        assert_eq!(
+            invocation(&["run", "bench all"]).command_line(),
+            "atlas run 'bench all'"
+        );
+    }
+}
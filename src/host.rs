@@ -0,0 +1,320 @@
+//! The machine Atlas runs on. System detection and tool installation go
+//! through [`SystemProbe`] and [`CommandRunner`], so their logic can be
+//! unit-tested against a [`MockHost`] without probing or changing the real system.
+
+#[cfg(test)]
+use crate::error::OptimizerError;
+use crate::error::OptimizerResult;
+use crate::system::{Architecture, OperatingSystem};
+use crate::utils::{execute_command_with_output, execute_interactive_command};
+#[cfg(test)]
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Mutex;
+
+/// Facts about the machine
+pub trait SystemProbe {
+    fn os(&self) -> OperatingSystem;
+    fn arch(&self) -> Architecture;
+    fn cpu_cores(&self) -> usize;
+    /// Full path of `program` if it is on PATH
+    fn find_program(&self, program: &str) -> Option<PathBuf>;
+}
+
+/// Running external programs
+pub trait CommandRunner {
+    /// Stdout of a successful run, used for version probes
+    fn output(&self, program: &str, args: &[&str]) -> Option<String>;
+    /// Run with its output shown to the user
+    fn run(&self, program: &str, args: &[&str]) -> OptimizerResult<()>;
+    /// Run in the foreground so it can prompt, e.g. `sudo`
+    fn run_interactive(&self, program: &str, args: &[&str]) -> OptimizerResult<()>;
+}
+
+/// The real machine
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Host;
+
+impl SystemProbe for Host {
+    fn os(&self) -> OperatingSystem {
+        match std::env::consts::OS {
+            "macos" => OperatingSystem::MacOS,
+            "linux" => OperatingSystem::Linux,
+            "windows" => OperatingSystem::Windows,
+            other => OperatingSystem::Unknown(other.to_string()),
+        }
+    }
+
+    fn arch(&self) -> Architecture {
+        match std::env::consts::ARCH {
+            "x86_64" => Architecture::X86_64,
+            "aarch64" => Architecture::Aarch64,
+            other => Architecture::Unknown(other.to_string()),
+        }
+    }
+
+    fn cpu_cores(&self) -> usize {
+        num_cpus::get()
+    }
+
+    fn find_program(&self, program: &str) -> Option<PathBuf> {
+        which::which(program).ok()
+    }
+}
+
+impl CommandRunner for Host {
+    fn output(&self, program: &str, args: &[&str]) -> Option<String> {
+        crate::allowlist::check(program).ok()?;
+        let output = Command::new(program).args(args).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        String::from_utf8(output.stdout).ok()
+    }
+
+    fn run(&self, program: &str, args: &[&str]) -> OptimizerResult<()> {
+        execute_command_with_output(program, args, None)
+    }
+
+    fn run_interactive(&self, program: &str, args: &[&str]) -> OptimizerResult<()> {
+        execute_interactive_command(program, args, None)
+    }
+}
+
+/// The real machine, except that commands that would change it are collected
+/// instead of run, for `install-tools --print-install-commands`
+#[derive(Debug, Default)]
+pub struct PlanningHost {
+    planned: Mutex<Vec<Vec<String>>>,
+}
+
+impl PlanningHost {
+    /// Command lines collected since the last call, as arguments
+    pub fn take(&self) -> Vec<Vec<String>> {
+        std::mem::take(&mut *self.planned.lock().unwrap())
+    }
+
+    fn plan(&self, program: &str, args: &[&str]) -> OptimizerResult<()> {
+        let mut line = vec![program.to_string()];
+        line.extend(args.iter().map(|arg| arg.to_string()));
+        self.planned.lock().unwrap().push(line);
+        Ok(())
+    }
+}
+
+impl SystemProbe for PlanningHost {
+    fn os(&self) -> OperatingSystem {
+        Host.os()
+    }
+
+    fn arch(&self) -> Architecture {
+        Host.arch()
+    }
+
+    fn cpu_cores(&self) -> usize {
+        Host.cpu_cores()
+    }
+
+    fn find_program(&self, program: &str) -> Option<PathBuf> {
+        Host.find_program(program)
+    }
+}
+
+impl CommandRunner for PlanningHost {
+    fn output(&self, program: &str, args: &[&str]) -> Option<String> {
+        Host.output(program, args)
+    }
+
+    fn run(&self, program: &str, args: &[&str]) -> OptimizerResult<()> {
+        self.plan(program, args)
+    }
+
+    fn run_interactive(&self, program: &str, args: &[&str]) -> OptimizerResult<()> {
+        self.plan(program, args)
+    }
+}
+
+/// A scripted machine for tests: only the programs and outputs it was given
+/// exist, and every command it is asked to run is recorded instead of executed
+#[cfg(test)]
+#[derive(Debug)]
+pub struct MockHost {
+    pub os: OperatingSystem,
+    pub arch: Architecture,
+    pub cpu_cores: usize,
+    programs: BTreeMap<String, PathBuf>,
+    /// Stdout by command line, e.g. `rustc --version`
+    outputs: BTreeMap<String, String>,
+    /// Programs whose runs fail
+    failing: BTreeSet<String>,
+    ran: Mutex<Vec<String>>,
+}
+
+#[cfg(test)]
+impl MockHost {
+    pub fn new(os: OperatingSystem, arch: Architecture) -> Self {
+        Self {
+            os,
+            arch,
+            cpu_cores: 8,
+            programs: BTreeMap::new(),
+            outputs: BTreeMap::new(),
+            failing: BTreeSet::new(),
+            ran: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// An x86_64 Linux machine with nothing installed
+    pub fn linux() -> Self {
+        Self::new(OperatingSystem::Linux, Architecture::X86_64)
+    }
+
+    /// Put `program` on PATH, answering `--version` with `version` if given
+    pub fn with_program(mut self, program: &str, version: Option<&str>) -> Self {
+        self.programs
+            .insert(program.to_string(), PathBuf::from("/usr/bin").join(program));
+        if let Some(version) = version {
+            self.outputs
+                .insert(format!("{} --version", program), format!("{}\n", version));
+        }
+        self
+    }
+
+    /// Answer `command_line` with `stdout`
+    pub fn with_output(mut self, command_line: &str, stdout: &str) -> Self {
+        self.outputs
+            .insert(command_line.to_string(), stdout.to_string());
+        self
+    }
+
+    /// Make every run of `program` fail
+    pub fn failing(mut self, program: &str) -> Self {
+        self.failing.insert(program.to_string());
+        self
+    }
+
+    /// Command lines run so far, in order
+    pub fn commands(&self) -> Vec<String> {
+        self.ran.lock().unwrap().clone()
+    }
+
+    fn record(&self, program: &str, args: &[&str]) -> OptimizerResult<()> {
+        let mut line = vec![program];
+        line.extend_from_slice(args);
+        self.ran.lock().unwrap().push(line.join(" "));
+        if self.failing.contains(program) {
+            return Err(OptimizerError::command_failed(format!(
+                "Command {} failed with exit code: Some(1)",
+                program
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl SystemProbe for MockHost {
+    fn os(&self) -> OperatingSystem {
+        self.os.clone()
+    }
+
+    fn arch(&self) -> Architecture {
+        self.arch.clone()
+    }
+
+    fn cpu_cores(&self) -> usize {
+        self.cpu_cores
+    }
+
+    fn find_program(&self, program: &str) -> Option<PathBuf> {
+        self.programs.get(program).cloned()
+    }
+}
+
+#[cfg(test)]
+impl CommandRunner for MockHost {
+    fn output(&self, program: &str, args: &[&str]) -> Option<String> {
+        let mut line = vec![program];
+        line.extend_from_slice(args);
+        self.outputs.get(&line.join(" ")).cloned()
+    }
+
+    fn run(&self, program: &str, args: &[&str]) -> OptimizerResult<()> {
+        self.record(program, args)
+    }
+
+    fn run_interactive(&self, program: &str, args: &[&str]) -> OptimizerResult<()> {
+        self.record(program, args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::tools::install_tools_with;
+    use crate::system::SystemInfo;
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn test_detect_with_mock() {
+        let host = MockHost::linux()
+            .with_program("apt-get", None)
+            .with_program("mold", Some("mold 2.30.0 (compatible with GNU ld)"))
+            .with_output("rustc --version", "rustc 1.79.0 (129f3b996 2024-06-10)\n")
+            .with_output(
+                "rustc -vV",
+                "rustc 1.79.0\nhost: x86_64-unknown-linux-gnu\nLLVM version: 18.1.7\n",
+            );
+        let system_info = SystemInfo::detect_with(&host);
+
+        assert_eq!(system_info.os, OperatingSystem::Linux);
+        assert_eq!(
+            system_info.rust_version.as_deref(),
+            Some("rustc 1.79.0 (129f3b996 2024-06-10)")
+        );
+        assert_eq!(system_info.rust_llvm_version.as_deref(), Some("18.1.7"));
+        assert!(system_info.is_tool_installed("mold"));
+        assert_eq!(
+            system_info.get_tool("mold").unwrap().version.as_deref(),
+            Some("mold 2.30.0 (compatible with GNU ld)")
+        );
+        assert!(!system_info.is_tool_installed("sccache"));
+        assert_eq!(system_info.get_package_manager(), Some("apt"));
+        assert!(host.commands().is_empty());
+    }
+
+    #[test]
+    fn test_install_with_mock() {
+        let host = MockHost::linux()
+            .with_program("apt-get", None)
+            .with_program("sccache", Some("sccache 0.8.1"))
+            .failing("sudo");
+        let results = block_on(install_tools_with(
+            &[
+                "sccache".to_string(),
+                "cargo-nextest".to_string(),
+                "mold".to_string(),
+            ],
+            &host,
+        ));
+
+        // sccache is already installed, mold needs sudo and fails
+        assert!(results["sccache"].is_ok());
+        assert!(results["cargo-nextest"].is_ok());
+        assert!(results["mold"].is_err());
+        assert_eq!(
+            host.commands(),
+            vec![
+                "cargo install cargo-nextest --locked",
+                "sudo apt-get install -y mold",
+            ]
+        );
+    }
+}
@@ -0,0 +1,512 @@
+//! Message catalogs for user-facing text, selected by the `locale` setting of
+//! the Atlas config or else the `LC_ALL`, `LC_MESSAGES` and `LANG` variables.
+//!
+//! Messages are looked up by key, e.g. `status.title`, and may contain named
+//! placeholders such as `{path}`. A key missing from a catalog falls back to
+//! English, so a partial translation never hides output. The status, init and
+//! doctor flows are translated so far.
+
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+pub const DEFAULT_LOCALE: &str = "en";
+
+type Catalog = &'static [(&'static str, &'static str)];
+
+/// Every locale with a catalog; English is complete by definition
+pub const LOCALES: &[(&str, Catalog)] = &[("en", EN), ("de", DE)];
+
+static LOCALE: OnceLock<&'static str> = OnceLock::new();
+
+/// Select the locale for this run
+pub fn init(configured: Option<&str>) {
+    let _ = LOCALE.set(resolve(configured, |name| std::env::var(name).ok()));
+}
+
+/// The configured locale if it has a catalog, otherwise the first locale
+/// variable that names one, otherwise English
+pub fn resolve(configured: Option<&str>, env: impl Fn(&str) -> Option<String>) -> &'static str {
+    configured
+        .and_then(supported)
+        .or_else(|| {
+            ["LC_ALL", "LC_MESSAGES", "LANG"]
+                .iter()
+                .filter_map(|name| env(name))
+                .find(|value| !value.is_empty())
+                .and_then(|value| supported(&value))
+        })
+        .unwrap_or(DEFAULT_LOCALE)
+}
+
+/// The catalog name for a locale tag such as `de`, `de-AT` or `de_DE.UTF-8`
+pub fn supported(tag: &str) -> Option<&'static str> {
+    let language = tag
+        .split(['_', '-', '.', '@'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    LOCALES
+        .iter()
+        .map(|(name, _)| *name)
+        .find(|name| *name == language)
+}
+
+fn catalog(locale: &str) -> Catalog {
+    LOCALES
+        .iter()
+        .find(|(name, _)| *name == locale)
+        .map_or(EN, |(_, catalog)| catalog)
+}
+
+fn lookup(catalog: Catalog, key: &str) -> Option<&'static str> {
+    catalog
+        .iter()
+        .find(|(candidate, _)| *candidate == key)
+        .map(|(_, message)| *message)
+}
+
+/// The message for `key` in `locale`, falling back to English and then to the key
+pub fn message_in(locale: &str, key: &'static str) -> &'static str {
+    lookup(catalog(locale), key)
+        .or_else(|| lookup(EN, key))
+        .unwrap_or(key)
+}
+
+/// The message for `key` in the current locale
+pub fn t(key: &'static str) -> &'static str {
+    message_in(LOCALE.get().copied().unwrap_or(DEFAULT_LOCALE), key)
+}
+
+/// The message for `key` with its `{name}` placeholders filled in
+pub fn t_with(key: &'static str, args: &[(&str, &dyn Display)]) -> String {
+    fill(t(key), args)
+}
+
+fn fill(message: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut text = message.to_string();
+    for (name, value) in args {
+        text = text.replace(&format!("{{{}}}", name), &value.to_string());
+    }
+    text
+}
+
+const EN: Catalog = &[
+    // atlas status
+    ("status.title", "🚀 Rust Build Optimizer Status"),
+    ("status.system", "💻 System Information"),
+    ("status.os", "OS"),
+    ("status.cpu_cores", "CPU Cores"),
+    ("status.power", "Power"),
+    ("status.rustup_components", "Rustup components"),
+    (
+        "status.build_system",
+        "Build system: {system} (reduced mode, Cargo files are not generated)",
+    ),
+    ("status.tools", "🛠️  Tool Status"),
+    ("status.column_tool", "Tool"),
+    ("status.column_status", "Status"),
+    ("status.column_version", "Version"),
+    ("status.installed", "✅ Installed"),
+    ("status.not_installed", "❌ Not installed"),
+    ("status.rustflags_conflicts", "⚠️  Rustflags Conflicts"),
+    (
+        "status.rustflags_precedence",
+        "Precedence: CARGO_ENCODED_RUSTFLAGS > RUSTFLAGS > target.<triple>.rustflags > build.rustflags",
+    ),
+    (
+        "status.rustflags_combined",
+        "Atlas build commands pass both config and environment flags through CARGO_ENCODED_RUSTFLAGS",
+    ),
+    ("status.outdated", "🔄 Outdated Generated Files"),
+    ("status.regenerate", "Run {command} to regenerate them"),
+    ("status.completed", "🎉 Status check completed!"),
+    ("status.incremental", "🔁 Incremental Compilation"),
+    ("status.column_profile", "Profile"),
+    ("status.column_incremental", "Incremental"),
+    ("status.column_set_by", "Set by"),
+    ("status.on", "on"),
+    ("status.off", "off"),
+    ("status.cargo_default", "Cargo default"),
+    ("status.capabilities", "🧭 Platform Support"),
+    ("status.column_capability", "Capability"),
+    ("status.column_support", "Support"),
+    ("status.supported", "✅ Supported"),
+    ("status.partially_supported", "⚠️  Partly: {reason}"),
+    ("status.unavailable", "❌ Unavailable: {reason}"),
+    ("status.history_title", "📈 Build History (last {weeks} weeks)"),
+    (
+        "status.history_empty",
+        "No builds of this project recorded yet; {build} and {check} record them",
+    ),
+    ("status.column_week", "Week of"),
+    ("status.column_builds", "Runs"),
+    ("status.column_median", "Median"),
+    ("status.column_compiled", "Crates compiled"),
+    ("status.column_cold", "Cold"),
+    ("status.column_sccache", "sccache hits"),
+    (
+        "status.history_faster",
+        "{kind}: {percent}% faster than in the week of {week}",
+    ),
+    (
+        "status.history_slower",
+        "{kind}: {percent}% slower than in the week of {week}",
+    ),
+    // atlas init
+    ("init.initializing", "Initializing optimization for project: {path}"),
+    (
+        "init.detected",
+        "Detected system: {os} {arch} with {cores} CPU cores",
+    ),
+    (
+        "init.existing",
+        "Found existing build settings: {imported} imported, {covered} already covered, {unsupported} not managed by Atlas",
+    ),
+    (
+        "init.foreign_build",
+        "{system} builds Rust in this repository ({rules}). Skipping Cargo configuration and profiles; use --full to write them anyway",
+    ),
+    (
+        "init.installing_ccache",
+        "Installing ccache for C/C++ code in -sys crates...",
+    ),
+    (
+        "init.installing_tools",
+        "Installing required optimization tools...",
+    ),
+    (
+        "init.unsupported_platform",
+        "Atlas does not fully support {os}; continuing with what works here",
+    ),
+    (
+        "init.skipping_tools",
+        "Skipping tools that cannot be installed on this platform: {tools}",
+    ),
+    (
+        "init.minimal",
+        "Minimal mode: only sccache, the sparse registry and nextest settings; profiles, target-cpu and linkers stay as they are",
+    ),
+    (
+        "init.minimal_next",
+        "Run {command} without --minimal to add build profiles and linker settings",
+    ),
+    (
+        "init.created_project_config",
+        "Created {path} with common tasks (run them with `atlas run <task>`)",
+    ),
+    (
+        "init.success",
+        "🎉 Rust build optimization initialized successfully!",
+    ),
+    ("init.next_steps", "📋 Next steps:"),
+    ("init.step_check", "Test the optimizations: {command}"),
+    ("init.step_watch", "Run development workflow: {command}"),
+    ("init.step_status", "Check optimization status: {command}"),
+    ("init.step_config", "View configuration: {command}"),
+    (
+        "init.step_rust_analyzer",
+        "Keep rust-analyzer from locking your builds: set {setting} to true in your editor",
+    ),
+    ("init.quick_commands", "🚀 Quick commands:"),
+    ("init.quick_check", "Fast syntax check"),
+    ("init.quick_build", "Optimized build"),
+    ("init.quick_test", "Fast testing"),
+    ("init.quick_watch", "Continuous development"),
+    ("init.quick_tasks", "Project tasks from atlas.toml"),
+    ("init.help", "📚 For help: {command}"),
+    // atlas doctor
+    ("doctor.title", "🩺 Atlas Doctor"),
+    ("doctor.rules", "📏 Recommendation Rules"),
+    ("doctor.hint_rules", "💡 Hints After Commands"),
+    ("footer.hint", "💡 {message}; next: {hint}"),
+    (
+        "doctor.silence_rule",
+        "Silence a rule for a project with {setting} in atlas.toml",
+    ),
+    ("doctor.recommendations", "💡 Recommendations"),
+    ("doctor.no_recommendations", "✅ No recommendations"),
+    ("doctor.fix", "fix (atlas doctor --fix): {fix}"),
+    (
+        "doctor.suppressed",
+        "{count} suppressed by ignore_rules in atlas.toml",
+    ),
+    ("doctor.fixes", "🔧 Fixes"),
+    ("doctor.nothing_to_fix", "✅ Nothing to fix"),
+    ("doctor.fix_failed", "Fix failed: {error}"),
+    (
+        "doctor.rolled_back",
+        "Rolled back every file change; nothing was modified",
+    ),
+    ("doctor.not_applied", "Not applied: {fix}"),
+    ("doctor.applied", "📋 Applied changes"),
+    ("doctor.toolchain", "🦀 Toolchain"),
+    ("doctor.install_rust", "install Rust via https://rustup.rs"),
+    ("doctor.installed", "installed"),
+    ("doctor.missing", "❌ missing"),
+    ("doctor.native_deps", "📦 Native Dependencies"),
+    (
+        "doctor.no_native_deps",
+        "✅ No dependencies with known system prerequisites",
+    ),
+    ("doctor.needs", "needs"),
+    ("doctor.found", "✅ found"),
+    (
+        "doctor.missing_bundled",
+        "⚠️  missing (bundled copy will be built)",
+    ),
+    (
+        "doctor.native_complete",
+        "✅ All native prerequisites are installed",
+    ),
+    (
+        "doctor.no_package_manager",
+        "No supported package manager found; install the missing libraries manually",
+    ),
+    (
+        "doctor.no_packages",
+        "No {manager} packages known for the missing libraries; install them manually",
+    ),
+    ("doctor.install_with", "Install with: {command}"),
+    ("doctor.run_now", "Run this command now?"),
+    ("doctor.native_installed", "✅ Native prerequisites installed"),
+];
+
+const DE: Catalog = &[
+    // atlas status
+    ("status.title", "🚀 Status des Rust Build Optimizers"),
+    ("status.system", "💻 Systeminformationen"),
+    ("status.os", "Betriebssystem"),
+    ("status.cpu_cores", "CPU-Kerne"),
+    ("status.power", "Stromversorgung"),
+    ("status.rustup_components", "Rustup-Komponenten"),
+    (
+        "status.build_system",
+        "Build-System: {system} (reduzierter Modus, es werden keine Cargo-Dateien erzeugt)",
+    ),
+    ("status.tools", "🛠️  Werkzeuge"),
+    ("status.column_tool", "Werkzeug"),
+    ("status.column_status", "Status"),
+    ("status.column_version", "Version"),
+    ("status.installed", "✅ Installiert"),
+    ("status.not_installed", "❌ Nicht installiert"),
+    ("status.rustflags_conflicts", "⚠️  Rustflags-Konflikte"),
+    (
+        "status.rustflags_precedence",
+        "Vorrang: CARGO_ENCODED_RUSTFLAGS > RUSTFLAGS > target.<triple>.rustflags > build.rustflags",
+    ),
+    (
+        "status.rustflags_combined",
+        "Atlas-Build-Befehle übergeben Flags aus Konfiguration und Umgebung gemeinsam über CARGO_ENCODED_RUSTFLAGS",
+    ),
+    ("status.outdated", "🔄 Veraltete generierte Dateien"),
+    ("status.regenerate", "Mit {command} neu erzeugen"),
+    ("status.completed", "🎉 Statusprüfung abgeschlossen!"),
+    ("status.incremental", "🔁 Inkrementelle Kompilierung"),
+    ("status.column_profile", "Profil"),
+    ("status.column_incremental", "Inkrementell"),
+    ("status.column_set_by", "Festgelegt durch"),
+    ("status.on", "an"),
+    ("status.off", "aus"),
+    ("status.cargo_default", "Cargo-Standard"),
+    ("status.capabilities", "🧭 Plattformunterstützung"),
+    ("status.column_capability", "Funktion"),
+    ("status.column_support", "Unterstützung"),
+    ("status.supported", "✅ Unterstützt"),
+    ("status.partially_supported", "⚠️  Teilweise: {reason}"),
+    ("status.unavailable", "❌ Nicht verfügbar: {reason}"),
+    ("status.history_title", "📈 Build-Verlauf (letzte {weeks} Wochen)"),
+    (
+        "status.history_empty",
+        "Noch keine Builds dieses Projekts aufgezeichnet; {build} und {check} zeichnen sie auf",
+    ),
+    ("status.column_week", "Woche ab"),
+    ("status.column_builds", "Läufe"),
+    ("status.column_median", "Median"),
+    ("status.column_compiled", "Kompilierte Crates"),
+    ("status.column_cold", "Kalt"),
+    ("status.column_sccache", "sccache-Treffer"),
+    (
+        "status.history_faster",
+        "{kind}: {percent}% schneller als in der Woche ab {week}",
+    ),
+    (
+        "status.history_slower",
+        "{kind}: {percent}% langsamer als in der Woche ab {week}",
+    ),
+    // atlas init
+    ("init.initializing", "Optimierung wird eingerichtet für Projekt: {path}"),
+    (
+        "init.detected",
+        "Erkanntes System: {os} {arch} mit {cores} CPU-Kernen",
+    ),
+    (
+        "init.existing",
+        "Vorhandene Build-Einstellungen: {imported} übernommen, {covered} bereits abgedeckt, {unsupported} nicht von Atlas verwaltet",
+    ),
+    (
+        "init.foreign_build",
+        "{system} baut den Rust-Code in diesem Repository ({rules}). Cargo-Konfiguration und Profile werden übersprungen; mit --full trotzdem schreiben",
+    ),
+    (
+        "init.installing_ccache",
+        "ccache wird für C/C++-Code in -sys-Crates installiert...",
+    ),
+    (
+        "init.installing_tools",
+        "Benötigte Optimierungswerkzeuge werden installiert...",
+    ),
+    (
+        "init.unsupported_platform",
+        "Atlas unterstützt {os} nicht vollständig; es wird fortgesetzt, was hier funktioniert",
+    ),
+    (
+        "init.skipping_tools",
+        "Werkzeuge, die auf dieser Plattform nicht installiert werden können, werden übersprungen: {tools}",
+    ),
+    (
+        "init.minimal",
+        "Minimaler Modus: nur sccache, die Sparse-Registry und nextest-Einstellungen; Profile, target-cpu und Linker bleiben unverändert",
+    ),
+    (
+        "init.minimal_next",
+        "Führen Sie {command} ohne --minimal aus, um Build-Profile und Linker-Einstellungen hinzuzufügen",
+    ),
+    (
+        "init.created_project_config",
+        "{path} mit üblichen Aufgaben erstellt (ausführen mit `atlas run <task>`)",
+    ),
+    (
+        "init.success",
+        "🎉 Rust-Build-Optimierung erfolgreich eingerichtet!",
+    ),
+    ("init.next_steps", "📋 Nächste Schritte:"),
+    ("init.step_check", "Optimierungen testen: {command}"),
+    ("init.step_watch", "Entwicklungsmodus starten: {command}"),
+    ("init.step_status", "Optimierungsstatus prüfen: {command}"),
+    ("init.step_config", "Konfiguration anzeigen: {command}"),
+    (
+        "init.step_rust_analyzer",
+        "Damit rust-analyzer deine Builds nicht blockiert: {setting} im Editor auf true setzen",
+    ),
+    ("init.quick_commands", "🚀 Schnellbefehle:"),
+    ("init.quick_check", "Schnelle Syntaxprüfung"),
+    ("init.quick_build", "Optimierter Build"),
+    ("init.quick_test", "Schnelles Testen"),
+    ("init.quick_watch", "Fortlaufende Entwicklung"),
+    ("init.quick_tasks", "Projektaufgaben aus atlas.toml"),
+    ("init.help", "📚 Hilfe: {command}"),
+    // atlas doctor
+    ("doctor.title", "🩺 Atlas Doctor"),
+    ("doctor.rules", "📏 Empfehlungsregeln"),
+    ("doctor.hint_rules", "💡 Hinweise nach Befehlen"),
+    ("footer.hint", "💡 {message}; als Nächstes: {hint}"),
+    (
+        "doctor.silence_rule",
+        "Eine Regel für ein Projekt stummschalten: {setting} in atlas.toml",
+    ),
+    ("doctor.recommendations", "💡 Empfehlungen"),
+    ("doctor.no_recommendations", "✅ Keine Empfehlungen"),
+    ("doctor.fix", "Korrektur (atlas doctor --fix): {fix}"),
+    (
+        "doctor.suppressed",
+        "{count} durch ignore_rules in atlas.toml ausgeblendet",
+    ),
+    ("doctor.fixes", "🔧 Korrekturen"),
+    ("doctor.nothing_to_fix", "✅ Nichts zu korrigieren"),
+    ("doctor.fix_failed", "Korrektur fehlgeschlagen: {error}"),
+    (
+        "doctor.rolled_back",
+        "Alle Dateiänderungen zurückgenommen; nichts wurde verändert",
+    ),
+    ("doctor.not_applied", "Nicht angewendet: {fix}"),
+    ("doctor.applied", "📋 Angewendete Änderungen"),
+    ("doctor.toolchain", "🦀 Toolchain"),
+    ("doctor.install_rust", "Rust über https://rustup.rs installieren"),
+    ("doctor.installed", "installiert"),
+    ("doctor.missing", "❌ fehlt"),
+    ("doctor.native_deps", "📦 Native Abhängigkeiten"),
+    (
+        "doctor.no_native_deps",
+        "✅ Keine Abhängigkeiten mit bekannten Systemvoraussetzungen",
+    ),
+    ("doctor.needs", "braucht"),
+    ("doctor.found", "✅ gefunden"),
+    (
+        "doctor.missing_bundled",
+        "⚠️  fehlt (mitgelieferte Kopie wird gebaut)",
+    ),
+    (
+        "doctor.native_complete",
+        "✅ Alle nativen Voraussetzungen sind installiert",
+    ),
+    (
+        "doctor.no_package_manager",
+        "Kein unterstützter Paketmanager gefunden; fehlende Bibliotheken bitte von Hand installieren",
+    ),
+    (
+        "doctor.no_packages",
+        "Keine {manager}-Pakete für die fehlenden Bibliotheken bekannt; bitte von Hand installieren",
+    ),
+    ("doctor.install_with", "Installieren mit: {command}"),
+    ("doctor.run_now", "Diesen Befehl jetzt ausführen?"),
+    (
+        "doctor.native_installed",
+        "✅ Native Voraussetzungen installiert",
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn test_catalogs_match_english() {
+        let placeholder = Regex::new(r"\{(\w+)\}").unwrap();
+        let placeholders = |message: &str| -> BTreeSet<String> {
+            placeholder
+                .captures_iter(message)
+                .map(|capture| capture[1].to_string())
+                .collect()
+        };
+        let english: BTreeSet<&str> = EN.iter().map(|(key, _)| *key).collect();
+        assert_eq!(
+            english.len(),
+            EN.len(),
+            "duplicate key in the English catalog"
+        );
+
+        for (locale, catalog) in LOCALES {
+            for (key, message) in catalog.iter() {
+                let original = lookup(EN, key)
+                    .unwrap_or_else(|| panic!("{}: {} is not an English key", locale, key));
+                assert_eq!(
+                    placeholders(message),
+                    placeholders(original),
+                    "{}: placeholders of {}",
+                    locale,
+                    key
+                );
+            }
+        }
+        assert_eq!(
+            fill(
+                message_in("de", "init.detected"),
+                &[("os", &"Linux"), ("arch", &"x86_64"), ("cores", &8)]
+            ),
+            "Erkanntes System: Linux x86_64 mit 8 CPU-Kernen"
+        );
+    }
+
+    #[test]
+    fn test_resolve_locale() {
+        let env = |lang: &'static str| move |name: &str| (name == "LANG").then(|| lang.to_string());
+        assert_eq!(resolve(Some("de"), env("en_US.UTF-8")), "de");
+        assert_eq!(resolve(None, env("de_AT.UTF-8")), "de");
+        assert_eq!(resolve(Some("xx"), env("C.UTF-8")), "en");
+        assert_eq!(resolve(None, |_| None), "en");
+        assert_eq!(supported("de-CH"), Some("de"));
+        assert_eq!(supported("fr_FR"), None);
+    }
+}
@@ -0,0 +1,325 @@
+//! Record of the files `atlas init` changed, for `atlas init --undo`
+//!
+//! Init writes `.atlas/init.toml` in the project with every file it created
+//! or modified, the backup of the original and the Atlas block it wrote.
+//! Undoing restores files nobody touched since from their backups, removes
+//! the ones init created, and otherwise strips only the Atlas block and the
+//! keys Atlas marked in the user's own tables, so edits made after init
+//! survive.
+
+use crate::audit;
+use crate::checksum::sha256_hex;
+use crate::error::OptimizerResult;
+use crate::managed::{find_block, CARGO_CONFIG_BLOCK, PROFILES_BLOCK};
+use crate::manifest::Manifest;
+use crate::utils::create_parent_dirs;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where the record is kept, relative to the project root
+pub const INIT_RECORD_FILE: &str = ".atlas/init.toml";
+
+/// A file `atlas init` created or modified
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InitChange {
+    pub path: PathBuf,
+    /// Whether the file did not exist before
+    pub created: bool,
+    /// Copy of the content before the first init
+    pub backup: Option<PathBuf>,
+    /// Atlas block written into the file
+    pub block: Option<String>,
+    /// SHA-256 of the content init left
+    pub sha256: String,
+}
+
+/// What undoing did to a file
+#[derive(Debug, Clone, PartialEq)]
+pub enum Undone {
+    /// Restored from its backup
+    Restored(PathBuf),
+    /// Init created it, so it is gone
+    Removed(PathBuf),
+    /// Edited since init: only Atlas's block and keys were taken out
+    Stripped(PathBuf),
+    /// Edited since init and without anything of Atlas's, so left alone
+    Kept(PathBuf),
+}
+
+/// The files init changed in one project
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InitRecord {
+    #[serde(default)]
+    pub changes: Vec<InitChange>,
+}
+
+impl InitRecord {
+    pub fn path(project_root: &Path) -> PathBuf {
+        project_root.join(INIT_RECORD_FILE)
+    }
+
+    pub fn load(project_root: &Path) -> OptimizerResult<Self> {
+        let path = Self::path(project_root);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(toml::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, project_root: &Path) -> OptimizerResult<()> {
+        let path = Self::path(project_root);
+        create_parent_dirs(&path)?;
+        fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Whether an earlier init changed `path`
+    pub fn contains(&self, path: &Path) -> bool {
+        self.changes.iter().any(|change| change.path == path)
+    }
+
+    /// Record that init left `path` as it is now. A later init starts from
+    /// Atlas's own changes, so the first record of whether the file existed
+    /// and of its backup is kept.
+    pub fn record(
+        &mut self,
+        path: &Path,
+        created: bool,
+        backup: Option<PathBuf>,
+    ) -> OptimizerResult<()> {
+        let content = fs::read_to_string(path)?;
+        let mut block = None;
+        for name in [CARGO_CONFIG_BLOCK, PROFILES_BLOCK] {
+            if find_block(&content, name)?.is_some() {
+                block = Some(name.to_string());
+            }
+        }
+        let sha256 = sha256_hex(content.as_bytes());
+        match self.changes.iter_mut().find(|change| change.path == path) {
+            Some(change) => {
+                change.block = block.or(change.block.take());
+                change.sha256 = sha256;
+            }
+            None => self.changes.push(InitChange {
+                path: path.to_path_buf(),
+                created,
+                backup,
+                block,
+                sha256,
+            }),
+        }
+        Ok(())
+    }
+
+    /// Recorded files among `paths` that still have the content init left
+    pub fn untouched(&self, paths: &[PathBuf]) -> Vec<PathBuf> {
+        paths
+            .iter()
+            .filter(|path| {
+                self.changes.iter().any(|change| {
+                    change.path == **path
+                        && fs::read(path).is_ok_and(|content| sha256_hex(&content) == change.sha256)
+                })
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Record that Atlas rewrote `paths`, files it found [untouched], for
+    /// example in `atlas refresh`, so undo still restores them from their
+    /// backups
+    ///
+    /// [untouched]: InitRecord::untouched
+    pub fn rewritten(&mut self, project_root: &Path, paths: &[PathBuf]) -> OptimizerResult<()> {
+        if paths.is_empty() {
+            return Ok(());
+        }
+        for path in paths.iter().filter(|path| path.exists()) {
+            self.record(path, false, None)?;
+        }
+        self.save(project_root)
+    }
+
+    /// Roll back every recorded change, removing the backups of the files
+    /// restored from them. Other backups are kept, as the only copy of the
+    /// original.
+    pub fn undo(&self) -> OptimizerResult<Vec<Undone>> {
+        let mut undone = Vec::new();
+        for change in self.changes.iter().rev() {
+            let Some(result) = undo_change(change)? else {
+                continue;
+            };
+            if let Undone::Restored(_) = result {
+                if let Some(backup) = change.backup.as_deref().filter(|backup| backup.exists()) {
+                    audit::remove(backup)?;
+                }
+            }
+            undone.push(result);
+        }
+        Ok(undone)
+    }
+}
+
+fn undo_change(change: &InitChange) -> OptimizerResult<Option<Undone>> {
+    let path = &change.path;
+    let Ok(content) = fs::read_to_string(path) else {
+        return Ok(None);
+    };
+
+    if sha256_hex(content.as_bytes()) == change.sha256 {
+        if change.created {
+            audit::remove(path)?;
+            return Ok(Some(Undone::Removed(path.clone())));
+        }
+        if let Some(backup) = change.backup.as_deref().filter(|backup| backup.exists()) {
+            audit::write(path, fs::read(backup)?)?;
+            return Ok(Some(Undone::Restored(path.clone())));
+        }
+    }
+
+    let block = match &change.block {
+        Some(name) => find_block(&content, name)?,
+        None => None,
+    };
+    let mut remaining = content.clone();
+    if let Some(block) = block {
+        remaining.replace_range(block.range, "");
+    }
+    // Settings merged into the user's own tables are marked as Atlas's
+    if let Ok(mut manifest) = Manifest::parse(&remaining) {
+        if manifest.remove_added_keys() > 0 {
+            remaining = manifest.to_string();
+        }
+    }
+    if remaining == content {
+        return Ok(Some(Undone::Kept(path.clone())));
+    }
+    if change.created && remaining.trim().is_empty() {
+        audit::remove(path)?;
+        return Ok(Some(Undone::Removed(path.clone())));
+    }
+    audit::write(path, remaining)?;
+    Ok(Some(Undone::Stripped(path.clone())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::managed::{render_block, Stamp};
+
+    fn block(name: &str, body: &str) -> String {
+        let stamp = Stamp {
+            version: "0.1.0".to_string(),
+            config_hash: "0".repeat(16),
+            generated: chrono::Utc::now(),
+        };
+        render_block(name, body, &stamp)
+    }
+
+    #[test]
+    fn test_undo_restores_and_removes() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = dir.path().join("Cargo.toml");
+        let backup = dir.path().join("Cargo.toml.backup");
+        let created = dir.path().join("atlas.toml");
+        let original = "[package]\nname = \"app\"\n";
+        fs::write(&backup, original).unwrap();
+        fs::write(
+            &manifest,
+            format!("{}\n{}", original, block(PROFILES_BLOCK, "[profile.dev]\n")),
+        )
+        .unwrap();
+        fs::write(&created, "[tasks]\n").unwrap();
+
+        let mut record = InitRecord::default();
+        record
+            .record(&manifest, false, Some(backup.clone()))
+            .unwrap();
+        record.record(&created, true, None).unwrap();
+        assert_eq!(record.changes[0].block.as_deref(), Some(PROFILES_BLOCK));
+
+        let undone = record.undo().unwrap();
+        assert_eq!(
+            undone,
+            vec![
+                Undone::Removed(created.clone()),
+                Undone::Restored(manifest.clone())
+            ]
+        );
+        assert_eq!(fs::read_to_string(&manifest).unwrap(), original);
+        assert!(!created.exists());
+        assert!(!backup.exists());
+    }
+
+    #[test]
+    fn test_undo_after_rewrite_restores() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("config.toml");
+        let backup = dir.path().join("config.toml.backup");
+        let original = "[build]\njobs = 4\n";
+        fs::write(&backup, original).unwrap();
+        fs::write(&config, block(CARGO_CONFIG_BLOCK, "[build]\njobs = 2\n")).unwrap();
+
+        let mut record = InitRecord::default();
+        record.record(&config, false, Some(backup.clone())).unwrap();
+        record.save(dir.path()).unwrap();
+
+        // Regenerated by Atlas, as refresh does
+        let paths = vec![config.clone()];
+        let untouched = record.untouched(&paths);
+        assert_eq!(untouched, paths);
+        fs::write(&config, block(CARGO_CONFIG_BLOCK, "[build]\njobs = 3\n")).unwrap();
+        record.rewritten(dir.path(), &untouched).unwrap();
+
+        let record = InitRecord::load(dir.path()).unwrap();
+        assert_eq!(
+            record.undo().unwrap(),
+            vec![Undone::Restored(config.clone())]
+        );
+        assert_eq!(fs::read_to_string(&config).unwrap(), original);
+        assert!(!backup.exists());
+    }
+
+    #[test]
+    fn test_undo_keeps_later_edits() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("config.toml");
+        let backup = dir.path().join("config.toml.backup");
+        let notes = dir.path().join("atlas.toml");
+        fs::write(&backup, "").unwrap();
+        fs::write(&config, block(CARGO_CONFIG_BLOCK, "[build]\njobs = 4\n")).unwrap();
+        fs::write(&notes, "[tasks]\n").unwrap();
+
+        let mut record = InitRecord::default();
+        record.record(&config, false, Some(backup.clone())).unwrap();
+        record.record(&notes, true, None).unwrap();
+
+        // Both edited after init
+        let edited = format!(
+            "{}\n[alias]\nb = \"build\"\n",
+            fs::read_to_string(&config).unwrap()
+        );
+        fs::write(&config, edited).unwrap();
+        fs::write(&notes, "[tasks]\nci = \"build\"\n").unwrap();
+        assert!(record
+            .untouched(&[config.clone(), notes.clone()])
+            .is_empty());
+
+        let undone = record.undo().unwrap();
+        assert_eq!(
+            undone,
+            vec![
+                Undone::Kept(notes.clone()),
+                Undone::Stripped(config.clone())
+            ]
+        );
+        assert_eq!(
+            fs::read_to_string(&config).unwrap(),
+            "\n[alias]\nb = \"build\"\n"
+        );
+        assert!(notes.exists());
+        // The original is only in the backup now
+        assert!(backup.exists());
+    }
+}
@@ -0,0 +1,508 @@
+//! Built-in explanations of the settings Atlas manages, for `atlas explain`
+
+use crate::baseline::{percent_change, Baseline};
+use crate::error::OptimizerResult;
+use crate::rustflags::{config_files, RustflagsLayers};
+use crate::system::host_triple;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Profiles whose values are reported for profile settings
+const PROFILES: &[&str] = &["dev", "release", "test", "bench"];
+
+/// Where a setting's value lives
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SettingLocation {
+    /// Key in the `[profile.*]` tables of `Cargo.toml`
+    Profile(&'static str),
+    /// Path of keys in `Cargo.toml`
+    Manifest(&'static [&'static str]),
+    /// Path of keys in the Cargo config files
+    CargoConfig(&'static [&'static str]),
+    /// `-C` option in the effective rustflags, e.g. `target-cpu`
+    Codegen(&'static str),
+}
+
+/// One setting Atlas manages
+#[derive(Debug)]
+pub struct Setting {
+    pub name: &'static str,
+    /// Other names people look it up by
+    pub aliases: &'static [&'static str],
+    pub location: SettingLocation,
+    pub summary: &'static str,
+    pub tradeoffs: &'static [&'static str],
+    /// What Atlas writes
+    pub atlas_value: &'static str,
+}
+
+pub const SETTINGS: &[Setting] = &[
+    Setting {
+        name: "codegen-units",
+        aliases: &["cgu"],
+        location: SettingLocation::Profile("codegen-units"),
+        summary: "How many pieces rustc splits each crate into for parallel code generation.",
+        tradeoffs: &[
+            "More units compile faster on many cores but optimize less across function boundaries",
+            "A single unit gives the fastest and smallest release binaries at the slowest build",
+        ],
+        atlas_value: "512 for dev and test, 1 for release",
+    },
+    Setting {
+        name: "opt-level",
+        aliases: &["optimization"],
+        location: SettingLocation::Profile("opt-level"),
+        summary: "How hard rustc optimizes: 0 is none, 3 is maximum, \"s\" and \"z\" favor size.",
+        tradeoffs: &[
+            "Higher levels make code faster and compilation slower",
+            "opt-level 1 for tests keeps them fast to build while avoiding very slow debug code",
+        ],
+        atlas_value: "0 for dev, 1 for test, 3 for release",
+    },
+    Setting {
+        name: "dependency-opt-level",
+        aliases: &["dependency_opt_level", "deps-opt-level"],
+        location: SettingLocation::Manifest(&["profile", "dev", "package", "*", "opt-level"]),
+        summary: "Optimization level for dependencies in dev builds, separate from your own crates.",
+        tradeoffs: &[
+            "Optimized dependencies make debug binaries much faster at runtime",
+            "They are only rebuilt when dependencies change, so the cost is mostly on the first build",
+        ],
+        atlas_value: "optimization.dependency_opt_level from the Atlas config (default 1)",
+    },
+    Setting {
+        name: "lto",
+        aliases: &["link-time-optimization"],
+        location: SettingLocation::Profile("lto"),
+        summary: "Link-time optimization across crates.",
+        tradeoffs: &[
+            "\"thin\" gets most of the runtime benefit of \"fat\" at a fraction of the link time",
+            "Any LTO makes linking slower, so it is off in dev builds",
+        ],
+        atlas_value: "false for dev, \"thin\" for release, true for bench",
+    },
+    Setting {
+        name: "debug",
+        aliases: &["debuginfo", "dev_debug"],
+        location: SettingLocation::Profile("debug"),
+        summary: "How much debug information rustc emits.",
+        tradeoffs: &[
+            "Full debug info makes debuggers work best but slows linking and bloats target/",
+            "\"line-tables-only\" keeps backtraces readable at a fraction of the cost",
+        ],
+        atlas_value: "profiles.dev_debug from the Atlas config for dev, false for release",
+    },
+    Setting {
+        name: "split-debuginfo",
+        aliases: &["split_debuginfo"],
+        location: SettingLocation::Profile("split-debuginfo"),
+        summary: "Whether debug info is kept out of the final binary.",
+        tradeoffs: &[
+            "\"unpacked\" skips copying debug info at link time, which speeds up incremental builds",
+            "Debuggers need to find the separate files, which some older tools cannot",
+        ],
+        atlas_value: "\"unpacked\" where the platform supports it and dev builds have debug info",
+    },
+    Setting {
+        name: "strip",
+        aliases: &[],
+        location: SettingLocation::Profile("strip"),
+        summary: "Whether symbols or debug info are removed from the final binary.",
+        tradeoffs: &[
+            "Stripping shrinks release binaries considerably",
+            "Stripped binaries produce backtraces without function names",
+        ],
+        atlas_value: "false for dev, \"symbols\" for release",
+    },
+    Setting {
+        name: "incremental",
+        aliases: &["incremental-compilation"],
+        location: SettingLocation::Profile("incremental"),
+        summary: "Whether rustc reuses work from the previous build of a crate.",
+        tradeoffs: &[
+            "Speeds up rebuilds after small edits considerably",
+            "Takes disk space in target/ and does not combine with sccache, which skips incremental crates",
+        ],
+        atlas_value: "true for dev",
+    },
+    Setting {
+        name: "panic",
+        aliases: &[],
+        location: SettingLocation::Profile("panic"),
+        summary: "Whether a panic unwinds the stack or aborts the process.",
+        tradeoffs: &[
+            "\"abort\" gives smaller, slightly faster binaries",
+            "With \"abort\", panics cannot be caught and destructors do not run",
+        ],
+        atlas_value: "\"unwind\" for dev, \"abort\" for release",
+    },
+    Setting {
+        name: "jobs",
+        aliases: &["parallel-jobs", "parallel_jobs"],
+        location: SettingLocation::CargoConfig(&["build", "jobs"]),
+        summary: "How many compiler processes Cargo runs at once.",
+        tradeoffs: &[
+            "Defaults to the number of CPUs, which is usually right",
+            "Lower it when builds run out of memory or compete with rust-analyzer",
+        ],
+        atlas_value: "build.parallel_jobs from the Atlas config, or the number of CPUs",
+    },
+    Setting {
+        name: "rustc-wrapper",
+        aliases: &["sccache", "wrapper"],
+        location: SettingLocation::CargoConfig(&["build", "rustc-wrapper"]),
+        summary: "A program Cargo runs rustc through, such as the sccache compilation cache.",
+        tradeoffs: &[
+            "sccache reuses compiled dependencies across projects, branches and clean builds",
+            "It cannot cache incremental crates, so your own crates gain little",
+        ],
+        atlas_value: "sccache when build.enable_sccache is set and sccache is installed",
+    },
+    Setting {
+        name: "target-dir",
+        aliases: &["target_dir"],
+        location: SettingLocation::CargoConfig(&["build", "target-dir"]),
+        summary: "Where Cargo writes build output.",
+        tradeoffs: &[
+            "A shared target directory lets projects reuse compiled dependencies",
+            "Projects sharing it block each other while building",
+        ],
+        atlas_value: "\"target\" in the project config; unset in the user config",
+    },
+    Setting {
+        name: "linker",
+        aliases: &["mold", "lld", "fuse-ld"],
+        location: SettingLocation::Codegen("link-arg=-fuse-ld"),
+        summary: "The linker used for the final link step.",
+        tradeoffs: &[
+            "mold and lld link several times faster than the system default",
+            "They must be installed on every machine that builds with this config",
+        ],
+        atlas_value: "mold on Linux, zld on Intel macOS, lld on Windows when build.use_fast_linker is set",
+    },
+    Setting {
+        name: "target-cpu",
+        aliases: &["target_cpu"],
+        location: SettingLocation::Codegen("target-cpu"),
+        summary: "Which CPU instructions rustc may use.",
+        tradeoffs: &[
+            "\"native\" uses everything this machine supports for faster code",
+            "Binaries built with \"native\" may crash on older CPUs, so avoid it for distributed builds",
+        ],
+        atlas_value: "build.target_cpu from the Atlas config (default \"native\")",
+    },
+    Setting {
+        name: "linker-plugin-lto",
+        aliases: &["linker_plugin_lto", "cross-language-lto"],
+        location: SettingLocation::Codegen("linker-plugin-lto"),
+        summary: "LTO across Rust and C/C++ code, done by the linker.",
+        tradeoffs: &[
+            "Inlines across the language boundary of -sys crates",
+            "Needs clang and lld built against the same LLVM as rustc",
+        ],
+        atlas_value: "enabled when build.linker_plugin_lto is set and a matching LLVM is found",
+    },
+    Setting {
+        name: "registry-protocol",
+        aliases: &["sparse", "protocol"],
+        location: SettingLocation::CargoConfig(&["registries", "crates-io", "protocol"]),
+        summary: "How Cargo downloads the crates.io index.",
+        tradeoffs: &[
+            "\"sparse\" fetches only the index entries a build needs",
+            "\"git\" clones the whole index, which is slow the first time",
+        ],
+        atlas_value: "\"sparse\"",
+    },
+    Setting {
+        name: "git-fetch-with-cli",
+        aliases: &["git_fetch_with_cli"],
+        location: SettingLocation::CargoConfig(&["net", "git-fetch-with-cli"]),
+        summary: "Whether Cargo uses the git command line for git dependencies.",
+        tradeoffs: &[
+            "The git CLI honors your SSH agent and credential helpers",
+            "It needs git installed",
+        ],
+        atlas_value: "true",
+    },
+];
+
+/// The setting called `name` or one of its aliases, ignoring case and `-`/`_`
+pub fn lookup(name: &str) -> Option<&'static Setting> {
+    let wanted = normalize(name);
+    SETTINGS.iter().find(|setting| {
+        normalize(setting.name) == wanted
+            || setting
+                .aliases
+                .iter()
+                .any(|alias| normalize(alias) == wanted)
+    })
+}
+
+/// Settings whose name contains `name`, for suggestions after a failed lookup
+pub fn similar(name: &str) -> Vec<&'static str> {
+    let wanted = normalize(name);
+    SETTINGS
+        .iter()
+        .filter(|setting| {
+            let candidate = normalize(setting.name);
+            candidate.contains(&wanted) || wanted.contains(&candidate)
+        })
+        .map(|setting| setting.name)
+        .collect()
+}
+
+fn normalize(name: &str) -> String {
+    name.trim().to_lowercase().replace('_', "-")
+}
+
+impl Setting {
+    /// Where the setting is set for `project_root` and to what, in precedence order
+    pub fn current_values(&self, project_root: &Path) -> OptimizerResult<Vec<(String, String)>> {
+        let mut values = Vec::new();
+        match self.location {
+            SettingLocation::Profile(key) => {
+                let manifest = read_manifest(project_root)?;
+                for profile in PROFILES {
+                    if let Some(value) = lookup_path(&manifest, &["profile", profile, key]) {
+                        values.push((format!("Cargo.toml [profile.{}]", profile), value));
+                    }
+                }
+            }
+            SettingLocation::Manifest(path) => {
+                if let Some(value) = lookup_path(&read_manifest(project_root)?, path) {
+                    values.push(("Cargo.toml".to_string(), value));
+                }
+            }
+            SettingLocation::CargoConfig(path) => {
+                // Scalars from the highest-precedence file win
+                for file in config_files(project_root) {
+                    let config: toml::Value = toml::from_str(&fs::read_to_string(&file)?)?;
+                    if let Some(value) = lookup_path(&config, path) {
+                        values.push((file.display().to_string(), value));
+                        break;
+                    }
+                }
+            }
+            SettingLocation::Codegen(option) => {
+                let layers = RustflagsLayers::detect(project_root, host_triple().as_deref())?;
+                let (origin, flags) = match &layers.env {
+                    Some(env) => (env.variable.to_string(), env.flags.clone()),
+                    None => ("rustflags".to_string(), layers.config_flags()),
+                };
+                if let Some(value) = codegen_value(&flags, option) {
+                    values.push((origin, value));
+                }
+            }
+        }
+        Ok(values)
+    }
+}
+
+fn read_manifest(project_root: &Path) -> OptimizerResult<toml::Value> {
+    Ok(toml::from_str(&fs::read_to_string(
+        project_root.join("Cargo.toml"),
+    )?)?)
+}
+
+fn lookup_path(value: &toml::Value, path: &[&str]) -> Option<String> {
+    let found = path
+        .iter()
+        .try_fold(value, |value, key| value.as_table()?.get(*key))?;
+    Some(match found {
+        toml::Value::String(text) => format!("\"{}\"", text),
+        other => other.to_string(),
+    })
+}
+
+/// Value of the last `-C option[=value]` in `flags`; flags without a value read as `enabled`
+fn codegen_value(flags: &[String], option: &str) -> Option<String> {
+    let mut value = None;
+    let mut previous: Option<&str> = None;
+    for flag in flags {
+        let codegen = match previous {
+            Some("-C") => Some(flag.as_str()),
+            _ => flag.strip_prefix("-C"),
+        };
+        if let Some(codegen) = codegen {
+            if codegen == option {
+                value = Some("enabled".to_string());
+            } else if let Some(rest) = codegen.strip_prefix(option) {
+                if let Some(rest) = rest.strip_prefix('=') {
+                    value = Some(rest.to_string());
+                }
+            }
+        }
+        previous = Some(flag.as_str());
+    }
+    value
+}
+
+/// Current value of every setting, recorded with baselines so `atlas explain`
+/// can attribute measured changes to the settings that changed
+pub fn snapshot(project_root: &Path) -> BTreeMap<String, String> {
+    SETTINGS
+        .iter()
+        .map(|setting| {
+            let value = match setting.current_values(project_root) {
+                Ok(values) if values.is_empty() => "unset".to_string(),
+                Ok(values) => values
+                    .iter()
+                    .map(|(origin, value)| format!("{}: {}", origin, value))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                Err(_) => "unknown".to_string(),
+            };
+            (setting.name.to_string(), value)
+        })
+        .collect()
+}
+
+/// Two consecutive baselines between which a setting changed
+#[derive(Debug)]
+pub struct Impact<'a> {
+    pub before: &'a Baseline,
+    pub after: &'a Baseline,
+    /// Other settings that changed between the two baselines
+    pub also_changed: Vec<&'a str>,
+    /// Differences in toolchain, CPU or power that make the times incomparable
+    pub environment_changed: Vec<String>,
+    /// Duration label and percentage change
+    pub changes: Vec<(&'static str, f64)>,
+}
+
+/// Measured effect of changing `setting`, from baselines sorted oldest first
+pub fn measured_impact<'a>(setting: &Setting, baselines: &'a [Baseline]) -> Vec<Impact<'a>> {
+    baselines
+        .windows(2)
+        .filter_map(|pair| {
+            let (before, after) = (&pair[0], &pair[1]);
+            let old = before.metadata.settings.get(setting.name)?;
+            let new = after.metadata.settings.get(setting.name)?;
+            if old == new {
+                return None;
+            }
+
+            let also_changed = after
+                .metadata
+                .settings
+                .iter()
+                .filter(|(name, value)| {
+                    name.as_str() != setting.name
+                        && before.metadata.settings.get(name.as_str()) != Some(value)
+                })
+                .map(|(name, _)| name.as_str())
+                .collect();
+            let changes = before
+                .measurements
+                .durations()
+                .into_iter()
+                .zip(after.measurements.durations())
+                .filter_map(|((label, old), (_, new))| Some((label, percent_change(old?, new?)?)))
+                .collect();
+            Some(Impact {
+                before,
+                after,
+                also_changed,
+                environment_changed: before
+                    .metadata
+                    .fingerprint
+                    .incompatibilities(&after.metadata.fingerprint),
+                changes,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_and_current_values() {
+        assert_eq!(lookup("CGU").unwrap().name, "codegen-units");
+        assert_eq!(lookup("target_cpu").unwrap().name, "target-cpu");
+        assert!(lookup("codegen").is_none());
+        assert_eq!(similar("codegen"), vec!["codegen-units"]);
+
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"demo\"\n\n[profile.dev]\ncodegen-units = 256\n\n[profile.release]\ncodegen-units = 1\nlto = \"thin\"\n",
+        )
+        .unwrap();
+        let values = lookup("codegen-units")
+            .unwrap()
+            .current_values(dir.path())
+            .unwrap();
+        assert_eq!(
+            values,
+            vec![
+                ("Cargo.toml [profile.dev]".to_string(), "256".to_string()),
+                ("Cargo.toml [profile.release]".to_string(), "1".to_string()),
+            ]
+        );
+        assert_eq!(
+            snapshot(dir.path())["lto"],
+            "Cargo.toml [profile.release]: \"thin\""
+        );
+    }
+
+    #[test]
+    fn test_codegen_value() {
+        let flags: Vec<String> = [
+            "-C",
+            "target-cpu=x86-64-v2",
+            "-Ctarget-cpu=native",
+            "-C",
+            "linker-plugin-lto",
+        ]
+        .iter()
+        .map(|flag| flag.to_string())
+        .collect();
+        assert_eq!(
+            codegen_value(&flags, "target-cpu").as_deref(),
+            Some("native")
+        );
+        assert_eq!(
+            codegen_value(&flags, "linker-plugin-lto").as_deref(),
+            Some("enabled")
+        );
+        assert_eq!(codegen_value(&flags, "link-arg=-fuse-ld"), None);
+    }
+
+    #[test]
+    fn test_measured_impact() {
+        use crate::baseline::{BaselineMetadata, Measurements};
+
+        let baseline = |name: &str, hour: u32, codegen_units: &str, incremental: f64| Baseline {
+            name: name.to_string(),
+            recorded_at: chrono::TimeZone::with_ymd_and_hms(&chrono::Utc, 2026, 1, 1, hour, 0, 0)
+                .unwrap(),
+            metadata: BaselineMetadata {
+                fingerprint: crate::fingerprint::Fingerprint::default(),
+                settings: BTreeMap::from([
+                    ("codegen-units".to_string(), codegen_units.to_string()),
+                    ("lto".to_string(), "unset".to_string()),
+                ]),
+            },
+            measurements: Measurements {
+                incremental_build_secs: Some(incremental),
+                ..Measurements::default()
+            },
+        };
+        let baselines = [
+            baseline("a", 1, "16", 10.0),
+            baseline("b", 2, "16", 9.0),
+            baseline("c", 3, "256", 6.0),
+        ];
+
+        let impacts = measured_impact(lookup("codegen-units").unwrap(), &baselines);
+        assert_eq!(impacts.len(), 1);
+        assert_eq!(impacts[0].before.name, "b");
+        assert!(impacts[0].also_changed.is_empty());
+        assert_eq!(impacts[0].changes.len(), 1);
+        assert!((impacts[0].changes[0].1 + 33.3).abs() < 0.1);
+        assert!(measured_impact(lookup("lto").unwrap(), &baselines).is_empty());
+    }
+}
@@ -0,0 +1,117 @@
+//! Atlas: Rust build optimization. The `atlas` binary is a thin wrapper
+//! around [`run`]; everything it does is also available as a library, so
+//! Atlas can be embedded in other tools such as an `xtask`:
+//!
+//! ```no_run
+//! use atlas::{generate, ConfigScope, OptimizerConfig, SystemInfo};
+//!
+//! # fn main() -> atlas::OptimizerResult<()> {
+//! let system_info = SystemInfo::detect()?;
+//! let config = OptimizerConfig::load_or_default()?;
+//! let cargo_config = generate::cargo_config(&config, &system_info, ConfigScope::Project, false)?;
+//! std::fs::write(".cargo/config.toml", cargo_config)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! - [`SystemInfo`] detects the platform and the installed tools
+//! - [`OptimizerConfig`] is Atlas's configuration
+//! - [`generate`] renders the Cargo config and profiles `init` installs
+//! - [`run_cargo`] runs cargo the way `atlas build` does
+
+pub(crate) mod alias;
+pub(crate) mod allowlist;
+pub(crate) mod analysis;
+pub(crate) mod artifacts;
+pub(crate) mod audit;
+pub(crate) mod background;
+pub(crate) mod baseline;
+pub(crate) mod bench;
+pub(crate) mod build_system;
+pub(crate) mod bundle;
+pub(crate) mod cache;
+pub(crate) mod capabilities;
+pub(crate) mod cargo_env;
+pub(crate) mod checksum;
+pub(crate) mod cli;
+pub mod commands;
+pub(crate) mod config;
+pub(crate) mod config_layers;
+pub(crate) mod contention;
+pub(crate) mod contradictions;
+pub(crate) mod credentials;
+pub(crate) mod debug_bundle;
+pub(crate) mod docker;
+pub(crate) mod editor;
+pub(crate) mod error;
+pub(crate) mod eta;
+pub(crate) mod events;
+pub(crate) mod existing_setup;
+pub(crate) mod fingerprint;
+pub(crate) mod fixes;
+pub(crate) mod fleet;
+pub(crate) mod fmt;
+pub mod generate;
+pub(crate) mod history;
+pub(crate) mod host;
+pub(crate) mod i18n;
+pub(crate) mod init_record;
+pub(crate) mod knowledge;
+pub(crate) mod lockfile;
+pub(crate) mod maintenance;
+pub(crate) mod managed;
+pub(crate) mod manifest;
+pub(crate) mod matrix;
+pub(crate) mod members;
+pub(crate) mod migrations;
+pub(crate) mod native_deps;
+pub(crate) mod nix;
+pub(crate) mod onboarding;
+pub(crate) mod package_opt;
+pub(crate) mod power;
+pub(crate) mod preflight;
+pub(crate) mod process_env;
+pub(crate) mod project_config;
+pub(crate) mod redact;
+pub(crate) mod release;
+pub(crate) mod remote;
+pub(crate) mod rules;
+pub(crate) mod rustflags;
+pub mod schema;
+pub(crate) mod session;
+pub(crate) mod signal;
+pub(crate) mod significance;
+pub(crate) mod stats;
+pub(crate) mod style;
+pub(crate) mod system;
+pub(crate) mod system_cache;
+pub(crate) mod table;
+pub(crate) mod target_dirs;
+pub(crate) mod telemetry;
+pub(crate) mod templates;
+pub(crate) mod test_failures;
+pub(crate) mod thermal;
+pub(crate) mod trends;
+pub(crate) mod triage;
+pub(crate) mod utils;
+pub(crate) mod watchdog;
+
+pub use bench::{workloads, Workload, Workloads};
+pub use cli::{
+    run, AnalyzeCommands, ArtifactsCommands, AuditCommands, BaselineCommands, BuildCommands,
+    BundleCommands, CacheCommands, CiCommands, Commands, ConfigCommands, DebugCommands,
+    DevCommands, DockerCommands, EditorCommands, FleetCommands, GraphFormat, RemoteCommands,
+    SecretCommands, TelemetryCommands,
+};
+pub use commands::build::{run_cargo, RunStats};
+pub use commands::tools::{ensure_tool, is_tool_supported};
+pub use config::{ConfigScope, OptimizerConfig};
+pub use editor::LastBuild;
+pub use error::{OptimizerError, OptimizerResult};
+pub use init_record::INIT_RECORD_FILE;
+pub use managed::{
+    find_block, without_blocks, ManagedBlock, Staleness, CARGO_CONFIG_BLOCK, PROFILES_BLOCK,
+};
+pub use manifest::{KeyMerge, Manifest};
+pub use project_config::PROJECT_CONFIG_FILE;
+pub use system::{AvailableTool, OperatingSystem, SystemInfo};
@@ -0,0 +1,140 @@
+//! Lockfile enforcement: with `--locked` or `--frozen` (or `lockfile` in the
+//! Atlas config), every cargo command Atlas runs gets the flag, so a build
+//! machine never resolves dependencies the lockfile does not pin.
+
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::utils::execute_command;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// How strictly cargo commands must stick to Cargo.lock
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LockfileMode {
+    /// Cargo may update Cargo.lock as needed
+    #[default]
+    Off,
+    /// Fail instead of updating Cargo.lock
+    Locked,
+    /// As `locked`, and never touch the network
+    Frozen,
+}
+
+impl LockfileMode {
+    pub fn flag(self) -> Option<&'static str> {
+        match self {
+            Self::Off => None,
+            Self::Locked => Some("--locked"),
+            Self::Frozen => Some("--frozen"),
+        }
+    }
+}
+
+/// Cargo subcommands that accept `--locked` and `--frozen`
+const ACCEPTING: &[&str] = &[
+    "bench", "build", "check", "clippy", "doc", "fetch", "fix", "metadata", "nextest", "package",
+    "run", "rustc", "rustdoc", "test", "tree",
+];
+
+static MODE: OnceLock<LockfileMode> = OnceLock::new();
+
+pub fn init(mode: LockfileMode) {
+    let _ = MODE.set(mode);
+}
+
+pub fn mode() -> LockfileMode {
+    MODE.get().copied().unwrap_or_default()
+}
+
+/// `args` of a cargo command with the flag of `mode` added before any `--`,
+/// if the subcommand accepts it and does not choose already
+pub fn with_flag<'a>(mode: LockfileMode, args: &[&'a str]) -> Vec<&'a str> {
+    let mut args = args.to_vec();
+    let Some(flag) = mode.flag() else {
+        return args;
+    };
+    let end = args
+        .iter()
+        .position(|arg| *arg == "--")
+        .unwrap_or(args.len());
+    let subcommand = args[..end].iter().find(|arg| !arg.starts_with('+'));
+    let chosen = args[..end]
+        .iter()
+        .any(|arg| *arg == "--locked" || *arg == "--frozen");
+    if subcommand.is_some_and(|subcommand| ACCEPTING.contains(subcommand)) && !chosen {
+        args.insert(end, flag);
+    }
+    args
+}
+
+/// Whether cargo's error says Cargo.lock would have to be created or changed
+pub fn is_outdated_error(error: &str) -> bool {
+    error.contains("lock file")
+        && (error.contains("--locked was passed") || error.contains("--frozen was passed"))
+}
+
+/// Fail before any build starts when Cargo.lock is missing or out of date
+pub fn preflight(project_root: &Path) -> OptimizerResult<()> {
+    let Some(flag) = mode().flag() else {
+        return Ok(());
+    };
+    let output = execute_command(
+        "cargo",
+        &["metadata", "--format-version", "1"],
+        Some(project_root),
+    )?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if output.status.success() || !is_outdated_error(&stderr) {
+        return Ok(());
+    }
+    Err(OptimizerError::project_validation(format!(
+        "Cargo.lock is missing or out of date and {} forbids updating it. \
+         Run `cargo update --workspace` and commit Cargo.lock, or drop {} for this run.",
+        flag, flag
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flag_placement() {
+        assert_eq!(
+            with_flag(LockfileMode::Locked, &["build", "--release"]),
+            vec!["build", "--release", "--locked"]
+        );
+        assert_eq!(
+            with_flag(LockfileMode::Frozen, &["+nightly", "run", "--", "--help"]),
+            vec!["+nightly", "run", "--frozen", "--", "--help"]
+        );
+        assert_eq!(
+            with_flag(LockfileMode::Locked, &["nextest", "run", "--workspace"]),
+            vec!["nextest", "run", "--workspace", "--locked"]
+        );
+        assert_eq!(
+            with_flag(LockfileMode::Locked, &["fmt", "--all"]),
+            vec!["fmt", "--all"]
+        );
+        assert_eq!(
+            with_flag(LockfileMode::Locked, &["test", "--frozen"]),
+            vec!["test", "--frozen"]
+        );
+        assert_eq!(with_flag(LockfileMode::Off, &["build"]), vec!["build"]);
+    }
+
+    #[test]
+    fn test_outdated_error() {
+        assert!(is_outdated_error(
+            "error: the lock file /ws/Cargo.lock needs to be updated but --locked was passed to prevent this"
+        ));
+        assert!(is_outdated_error(
+            "error: cannot create the lock file /ws/Cargo.lock because --frozen was passed to prevent this"
+        ));
+        assert!(!is_outdated_error(
+            "error: failed to select a version for the requirement `serde = \"^9\"`"
+        ));
+    }
+}
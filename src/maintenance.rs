@@ -0,0 +1,498 @@
+//! Housekeeping run by `atlas maintain`: old build artifacts in the target
+//! directories of projects Atlas has built, extracted registry sources and
+//! git checkouts Cargo can recreate, old sccache entries and old build
+//! statistics. Nothing is removed that a later build cannot produce again.
+//!
+//! The task can be installed to run on its own, through the scheduler each
+//! platform has for per-user jobs.
+
+use crate::utils::get_directory_size;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// `[maintenance]` in the Atlas config
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MaintenanceConfig {
+    /// Extracted crate sources and git checkouts unused for this long are removed
+    #[serde(default = "default_registry_retention_days")]
+    pub registry_retention_days: u32,
+    /// Build statistics older than this are dropped
+    #[serde(default = "default_stats_retention_days")]
+    pub stats_retention_days: u32,
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            registry_retention_days: default_registry_retention_days(),
+            stats_retention_days: default_stats_retention_days(),
+        }
+    }
+}
+
+fn default_registry_retention_days() -> u32 {
+    90
+}
+
+fn default_stats_retention_days() -> u32 {
+    180
+}
+
+/// A file or directory that can go, with its size
+#[derive(Debug, Clone, PartialEq)]
+pub struct Removable {
+    pub path: PathBuf,
+    pub bytes: u64,
+}
+
+/// Artifacts in a target directory no build used since `cutoff`. Every
+/// directory with a `.fingerprint` directory is a profile directory, including
+/// those under `target/<triple>/`.
+pub fn stale_artifacts(target_dir: &Path, cutoff: SystemTime) -> Vec<Removable> {
+    let mut profiles = Vec::new();
+    for entry in read_dirs(target_dir) {
+        if entry.join(".fingerprint").is_dir() {
+            profiles.push(entry);
+        } else {
+            profiles.extend(
+                read_dirs(&entry)
+                    .into_iter()
+                    .filter(|dir| dir.join(".fingerprint").is_dir()),
+            );
+        }
+    }
+    profiles
+        .iter()
+        .flat_map(|profile| stale_units(profile, cutoff))
+        .collect()
+}
+
+/// Units of a profile directory no build used since `cutoff`, with their
+/// files in `deps` and `build`. Cargo never rewrites the artifacts of a fresh
+/// unit, so their modification time is when it was compiled; the fingerprint
+/// files it reads on every build that needs the unit tell when it was last
+/// used, as long as the file system records access times.
+fn stale_units(profile: &Path, cutoff: SystemTime) -> Vec<Removable> {
+    let units: Vec<String> = read_dirs(&profile.join(".fingerprint"))
+        .iter()
+        .filter(|unit| last_used(unit).is_some_and(|used| used < cutoff))
+        .filter_map(|unit| Some(unit.file_name()?.to_string_lossy().into_owned()))
+        .collect();
+
+    let mut stale = Vec::new();
+    for dir in [".fingerprint", "build", "deps"] {
+        let Ok(entries) = fs::read_dir(profile.join(dir)) else {
+            continue;
+        };
+        stale.extend(
+            entries
+                .flatten()
+                .filter(|entry| {
+                    let name = entry.file_name().to_string_lossy().into_owned();
+                    units.iter().any(|unit| belongs_to(&name, unit))
+                })
+                .map(|entry| removable(entry.path())),
+        );
+    }
+    // Incremental sessions are read only when their crate recompiles
+    stale.extend(
+        read_dirs(&profile.join("incremental"))
+            .into_iter()
+            .filter(|session| last_used(session).is_some_and(|used| used < cutoff))
+            .map(removable),
+    );
+    stale
+}
+
+/// Whether `file_name` in `deps` or `build` is an output of `unit`, e.g.
+/// `libserde-1a2b.rlib` or `serde-1a2b.d` of `serde-1a2b`
+fn belongs_to(file_name: &str, unit: &str) -> bool {
+    let name = file_name
+        .strip_prefix("lib")
+        .filter(|name| name.starts_with(unit))
+        .unwrap_or(file_name);
+    name == unit
+        || name
+            .strip_prefix(unit)
+            .is_some_and(|rest| rest.starts_with('.'))
+}
+
+/// When a file under `dir` was last read or written; the directory's own
+/// time when it holds no files
+fn last_used(dir: &Path) -> Option<SystemTime> {
+    let used = walkdir::WalkDir::new(dir)
+        .into_iter()
+        .flatten()
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            let modified = metadata.modified().ok()?;
+            Some(
+                metadata
+                    .accessed()
+                    .map_or(modified, |accessed| accessed.max(modified)),
+            )
+        })
+        .max();
+    used.or_else(|| {
+        fs::metadata(dir)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    })
+}
+
+/// Extracted registry sources and git checkouts under `cargo_home` not
+/// touched since `cutoff`. Cargo extracts or checks them out again from its
+/// download cache and git database when a build needs them.
+pub fn stale_sources(cargo_home: &Path, cutoff: SystemTime) -> Vec<Removable> {
+    [
+        cargo_home.join("registry").join("src"),
+        cargo_home.join("git").join("checkouts"),
+    ]
+    .iter()
+    .flat_map(|dir| read_dirs(dir))
+    .flat_map(|dir| stale_entries(&dir, cutoff))
+    .collect()
+}
+
+/// Entries of `dir` last modified before `cutoff`
+fn stale_entries(dir: &Path, cutoff: SystemTime) -> Vec<Removable> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| {
+            entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .is_ok_and(|modified| modified < cutoff)
+        })
+        .map(|entry| removable(entry.path()))
+        .collect()
+}
+
+fn removable(path: PathBuf) -> Removable {
+    let bytes = if path.is_dir() {
+        get_directory_size(&path).unwrap_or(0)
+    } else {
+        fs::metadata(&path)
+            .map(|metadata| metadata.len())
+            .unwrap_or(0)
+    };
+    Removable { path, bytes }
+}
+
+/// Files anywhere under `dir` last modified before `cutoff`, e.g. entries of
+/// a local sccache cache, which updates their times on every hit
+pub fn stale_files(dir: &Path, cutoff: SystemTime) -> Vec<Removable> {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .flatten()
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            (metadata.modified().ok()? < cutoff).then(|| Removable {
+                path: entry.into_path(),
+                bytes: metadata.len(),
+            })
+        })
+        .collect()
+}
+
+/// Remove what the functions above found, returning the bytes freed
+pub fn remove(items: &[Removable]) -> u64 {
+    items
+        .iter()
+        .filter(|item| {
+            let removed = if item.path.is_dir() {
+                fs::remove_dir_all(&item.path)
+            } else {
+                fs::remove_file(&item.path)
+            };
+            if let Err(e) = &removed {
+                log::debug!("Could not remove {}: {}", item.path.display(), e);
+            }
+            removed.is_ok()
+        })
+        .map(|item| item.bytes)
+        .sum()
+}
+
+fn read_dirs(dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// How often scheduled maintenance runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Schedule {
+    Daily,
+    Weekly,
+}
+
+/// Per-user job schedulers Atlas can install maintenance with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Scheduler {
+    Cron,
+    Systemd,
+    Launchd,
+    /// Windows Task Scheduler
+    Windows,
+}
+
+impl fmt::Display for Scheduler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Cron => "cron",
+            Self::Systemd => "systemd timer",
+            Self::Launchd => "launchd agent",
+            Self::Windows => "Windows scheduled task",
+        })
+    }
+}
+
+/// Scheduled runs start at this hour, local time
+const HOUR: u32 = 3;
+
+/// Name of the job in every scheduler
+pub const JOB_NAME: &str = "atlas-maintain";
+
+/// Marks Atlas's line in a crontab
+pub const CRON_MARKER: &str = "# atlas maintain";
+
+/// Label of the launchd agent
+pub const LAUNCHD_LABEL: &str = "dev.atlas.maintain";
+
+/// The job's command line
+fn command(exe: &Path) -> String {
+    format!("\"{}\" --quiet maintain", exe.display())
+}
+
+/// Crontab line running maintenance
+pub fn cron_line(exe: &Path, schedule: Schedule) -> String {
+    let day_of_week = match schedule {
+        Schedule::Daily => "*",
+        Schedule::Weekly => "0",
+    };
+    format!(
+        "0 {} * * {} {} {}",
+        HOUR,
+        day_of_week,
+        command(exe),
+        CRON_MARKER
+    )
+}
+
+/// `crontab` contents with Atlas's line replaced by `line`, or removed
+pub fn with_cron_line(crontab: &str, line: Option<&str>) -> String {
+    let mut lines: Vec<&str> = crontab
+        .lines()
+        .filter(|existing| !existing.ends_with(CRON_MARKER))
+        .collect();
+    lines.extend(line);
+    let mut content = lines.join("\n");
+    if !content.is_empty() {
+        content.push('\n');
+    }
+    content
+}
+
+/// Service and timer units for a systemd user timer
+pub fn systemd_units(exe: &Path, schedule: Schedule) -> (String, String) {
+    let service = format!(
+        "[Unit]\nDescription=Atlas build hygiene\n\n[Service]\nType=oneshot\nExecStart={}\nNice=19\nIOSchedulingClass=idle\n",
+        command(exe)
+    );
+    let calendar = match schedule {
+        Schedule::Daily => format!("*-*-* {:02}:00:00", HOUR),
+        Schedule::Weekly => format!("Sun *-*-* {:02}:00:00", HOUR),
+    };
+    let timer = format!(
+        "[Unit]\nDescription=Run Atlas build hygiene {}\n\n[Timer]\nOnCalendar={}\nPersistent=true\n\n[Install]\nWantedBy=timers.target\n",
+        match schedule {
+            Schedule::Daily => "daily",
+            Schedule::Weekly => "weekly",
+        },
+        calendar
+    );
+    (service, timer)
+}
+
+/// Property list for a launchd user agent
+pub fn launchd_plist(exe: &Path, schedule: Schedule) -> String {
+    let weekday = match schedule {
+        Schedule::Daily => String::new(),
+        Schedule::Weekly => {
+            "\n        <key>Weekday</key>\n        <integer>0</integer>".to_string()
+        }
+    };
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{}</string>
+        <string>--quiet</string>
+        <string>maintain</string>
+    </array>
+    <key>StartCalendarInterval</key>
+    <dict>
+        <key>Hour</key>
+        <integer>{}</integer>
+        <key>Minute</key>
+        <integer>0</integer>{}
+    </dict>
+    <key>LowPriorityIO</key>
+    <true/>
+    <key>Nice</key>
+    <integer>19</integer>
+</dict>
+</plist>
+"#,
+        LAUNCHD_LABEL,
+        exe.display(),
+        HOUR,
+        weekday
+    )
+}
+
+/// `schtasks` arguments creating the scheduled task
+pub fn schtasks_create_args(exe: &Path, schedule: Schedule) -> Vec<String> {
+    vec![
+        "/Create".to_string(),
+        "/F".to_string(),
+        "/TN".to_string(),
+        JOB_NAME.to_string(),
+        "/TR".to_string(),
+        command(exe),
+        "/SC".to_string(),
+        match schedule {
+            Schedule::Daily => "DAILY",
+            Schedule::Weekly => "WEEKLY",
+        }
+        .to_string(),
+        "/ST".to_string(),
+        format!("{:02}:00", HOUR),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn write_aged(path: &Path, content: &str, accessed: SystemTime, modified: SystemTime) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_times(
+                fs::FileTimes::new()
+                    .set_accessed(accessed)
+                    .set_modified(modified),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_stale_artifacts() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path();
+        let day = Duration::from_secs(24 * 60 * 60);
+        let now = SystemTime::now();
+        let old = now - 30 * day;
+        for profile in ["debug", "x86_64-unknown-linux-gnu/release"] {
+            let profile = target.join(profile);
+            // Compiled a month ago and not needed since
+            write_aged(
+                &profile.join(".fingerprint/serde-1a2b/lib-serde"),
+                "1",
+                old,
+                old,
+            );
+            write_aged(&profile.join("deps/libserde-1a2b.rlib"), "12345", old, old);
+            write_aged(&profile.join("deps/serde-1a2b.d"), "deps", old, old);
+            write_aged(&profile.join("build/serde-1a2b/output"), "", old, old);
+            // Compiled a month ago, still fresh and used by today's build
+            write_aged(
+                &profile.join(".fingerprint/libc-3c4d/lib-libc"),
+                "1",
+                now,
+                old,
+            );
+            write_aged(&profile.join("deps/liblibc-3c4d.rlib"), "12345", old, old);
+            write_aged(&profile.join("deps/libc-3c4d.d"), "deps", old, old);
+        }
+        write_aged(&target.join("doc/index.html"), "docs", old, old);
+
+        let mut stale = stale_artifacts(target, now - 7 * day);
+        stale.sort_by(|a, b| a.path.cmp(&b.path));
+        let names: Vec<String> = stale
+            .iter()
+            .map(|item| {
+                item.path
+                    .strip_prefix(target)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "debug/.fingerprint/serde-1a2b",
+                "debug/build/serde-1a2b",
+                "debug/deps/libserde-1a2b.rlib",
+                "debug/deps/serde-1a2b.d",
+                "x86_64-unknown-linux-gnu/release/.fingerprint/serde-1a2b",
+                "x86_64-unknown-linux-gnu/release/build/serde-1a2b",
+                "x86_64-unknown-linux-gnu/release/deps/libserde-1a2b.rlib",
+                "x86_64-unknown-linux-gnu/release/deps/serde-1a2b.d",
+            ]
+        );
+        assert!(stale_artifacts(target, SystemTime::UNIX_EPOCH).is_empty());
+
+        assert!(remove(&stale) >= 10);
+        assert!(!target.join("debug/deps/libserde-1a2b.rlib").exists());
+        assert!(target.join("debug/deps/liblibc-3c4d.rlib").exists());
+        assert!(target.join("doc/index.html").exists());
+    }
+
+    #[test]
+    fn test_scheduler_entries() {
+        let exe = Path::new("/usr/local/bin/atlas");
+        let line = cron_line(exe, Schedule::Weekly);
+        assert_eq!(
+            line,
+            "0 3 * * 0 \"/usr/local/bin/atlas\" --quiet maintain # atlas maintain"
+        );
+        let crontab = with_cron_line("MAILTO=me\n0 1 * * * old # atlas maintain\n", Some(&line));
+        assert_eq!(crontab, format!("MAILTO=me\n{}\n", line));
+        assert_eq!(with_cron_line(&crontab, None), "MAILTO=me\n");
+
+        let (service, timer) = systemd_units(exe, Schedule::Daily);
+        assert!(service.contains("ExecStart=\"/usr/local/bin/atlas\" --quiet maintain"));
+        assert!(timer.contains("OnCalendar=*-*-* 03:00:00"));
+        assert!(launchd_plist(exe, Schedule::Weekly).contains("<key>Weekday</key>"));
+        assert!(!launchd_plist(exe, Schedule::Daily).contains("Weekday"));
+        assert_eq!(schtasks_create_args(exe, Schedule::Daily)[7], "DAILY");
+    }
+}
@@ -0,0 +1,598 @@
+//! Record of the files Atlas writes and which scope owns their settings
+
+use crate::config::{ConfigScope, OptimizerConfig};
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::utils::{cargo_home, stable_hash};
+use chrono::{DateTime, SecondsFormat, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Cargo config file that holds user-scope settings
+pub fn user_cargo_config_path() -> OptimizerResult<PathBuf> {
+    cargo_home()
+        .map(|home| home.join("config.toml"))
+        .ok_or_else(|| OptimizerError::config("Could not determine the Cargo home directory"))
+}
+
+/// Managed block holding the generated Cargo config
+pub const CARGO_CONFIG_BLOCK: &str = "cargo-config";
+
+/// Managed block holding the generated build profiles
+pub const PROFILES_BLOCK: &str = "profiles";
+
+const BEGIN_MARKER: &str = "# BEGIN ATLAS ";
+const END_MARKER: &str = "# END ATLAS ";
+const STAMP_PREFIX: &str = "# Written by Atlas ";
+
+/// Which Atlas wrote a block, from which configuration, and when
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stamp {
+    pub version: String,
+    /// Hash of the Atlas configuration and templates the block was generated from
+    pub config_hash: String,
+    pub generated: DateTime<Utc>,
+}
+
+impl Stamp {
+    /// Stamp for content generated now by this Atlas from `config`
+    pub fn current(config: &OptimizerConfig) -> OptimizerResult<Self> {
+        Ok(Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            config_hash: stable_hash(
+                (toml::to_string(config)? + &crate::templates::fingerprint()).as_bytes(),
+            ),
+            generated: Utc::now(),
+        })
+    }
+
+    fn render(&self) -> String {
+        format!(
+            "{}{} from config {} at {}",
+            STAMP_PREFIX,
+            self.version,
+            self.config_hash,
+            self.generated.to_rfc3339_opts(SecondsFormat::Secs, true)
+        )
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let pattern = Regex::new(r"^# Written by Atlas (\S+) from config ([0-9a-f]{16}) at (\S+)$")
+            .expect("valid stamp regex");
+        let captures = pattern.captures(line)?;
+        Some(Self {
+            version: captures[1].to_string(),
+            config_hash: captures[2].to_string(),
+            generated: DateTime::parse_from_rfc3339(&captures[3])
+                .ok()?
+                .with_timezone(&Utc),
+        })
+    }
+
+    /// Whether both stamps come from the same Atlas and configuration, whenever written
+    fn same_source(&self, other: &Stamp) -> bool {
+        self.version == other.version && self.config_hash == other.config_hash
+    }
+}
+
+/// Why a block no longer matches what this Atlas would generate
+#[derive(Debug, Clone, PartialEq)]
+pub enum Staleness {
+    /// Written before blocks were stamped
+    Unstamped,
+    /// Written by an older Atlas release
+    OlderAtlas(String),
+    /// The Atlas configuration changed since the block was written
+    ConfigChanged,
+}
+
+impl std::fmt::Display for Staleness {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unstamped => write!(f, "written before Atlas stamped generated files"),
+            Self::OlderAtlas(version) => write!(
+                f,
+                "written by Atlas {}, Atlas {} is installed",
+                version,
+                env!("CARGO_PKG_VERSION")
+            ),
+            Self::ConfigChanged => write!(
+                f,
+                "the Atlas configuration changed since the block was written"
+            ),
+        }
+    }
+}
+
+/// Compare dotted version numbers, ignoring pre-release suffixes
+fn is_older_version(version: &str, than: &str) -> bool {
+    let numbers = |version: &str| -> Vec<u64> {
+        version
+            .split(['.', '-', '+'])
+            .map_while(|part| part.parse().ok())
+            .collect()
+    };
+    numbers(version) < numbers(than)
+}
+
+/// A `# BEGIN ATLAS` ... `# END ATLAS` block in a generated file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManagedBlock {
+    /// Byte range of the block in the file, markers included
+    pub range: std::ops::Range<usize>,
+    pub body: String,
+    /// Checksum of the body when Atlas wrote it
+    pub checksum: Option<String>,
+    pub stamp: Option<Stamp>,
+}
+
+impl ManagedBlock {
+    /// Whether the body was edited since Atlas wrote it
+    pub fn is_modified(&self) -> bool {
+        self.checksum.as_deref() != Some(stable_hash(self.body.as_bytes()).as_str())
+    }
+
+    /// Why the block is out of date compared with `current`, if it is
+    pub fn staleness(&self, current: &Stamp) -> Option<Staleness> {
+        match &self.stamp {
+            None => Some(Staleness::Unstamped),
+            Some(stamp) if is_older_version(&stamp.version, &current.version) => {
+                Some(Staleness::OlderAtlas(stamp.version.clone()))
+            }
+            Some(stamp) if stamp.config_hash != current.config_hash => {
+                Some(Staleness::ConfigChanged)
+            }
+            Some(_) => None,
+        }
+    }
+}
+
+/// Result of writing a managed block into a file's content
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockUpdate {
+    /// The block already has the requested body
+    Unchanged,
+    /// The block was edited by hand and left alone
+    Modified,
+    /// New file content with the block written
+    Updated(String),
+}
+
+fn with_trailing_newline(body: &str) -> String {
+    let mut body = body.to_string();
+    if !body.ends_with('\n') {
+        body.push('\n');
+    }
+    body
+}
+
+/// Render a managed block around `body`
+pub fn render_block(name: &str, body: &str, stamp: &Stamp) -> String {
+    let body = with_trailing_newline(body);
+    format!(
+        "{}{} (checksum {}). Edits inside this block are overwritten by `atlas refresh`\n{}\n{}{}{}\n",
+        BEGIN_MARKER,
+        name,
+        stable_hash(body.as_bytes()),
+        stamp.render(),
+        body,
+        END_MARKER,
+        name
+    )
+}
+
+/// Find the managed block called `name`
+pub fn find_block(content: &str, name: &str) -> OptimizerResult<Option<ManagedBlock>> {
+    let begin = format!("{}{} ", BEGIN_MARKER, name);
+    let end = format!("{}{}", END_MARKER, name);
+    let checksum = Regex::new(r"\(checksum ([0-9a-f]{16})\)").expect("valid checksum regex");
+
+    let mut offset = 0;
+    let mut open: Option<(usize, usize, Option<String>, Option<Stamp>)> = None;
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        match &mut open {
+            None if trimmed.starts_with(&begin) => {
+                let recorded = checksum
+                    .captures(trimmed)
+                    .map(|captures| captures[1].to_string());
+                open = Some((offset, offset + line.len(), recorded, None));
+            }
+            // The stamp directly follows the begin marker and is not part of the body
+            Some((_, body_start, _, stamp @ None))
+                if *body_start == offset && trimmed.starts_with(STAMP_PREFIX) =>
+            {
+                *stamp = Stamp::parse(trimmed);
+                *body_start += line.len();
+            }
+            Some((start, body_start, checksum, stamp)) if trimmed == end => {
+                return Ok(Some(ManagedBlock {
+                    range: *start..offset + line.len(),
+                    body: content[*body_start..offset].to_string(),
+                    checksum: checksum.take(),
+                    stamp: stamp.take(),
+                }));
+            }
+            _ => {}
+        }
+        offset += line.len();
+    }
+
+    match open {
+        Some(_) => Err(OptimizerError::config(format!(
+            "Atlas block '{}' has no '{}' line",
+            name, end
+        ))),
+        None => Ok(None),
+    }
+}
+
+/// Write `body` into the block called `name`, appending the block if it is missing.
+/// A block edited by hand is only replaced with `force`. A block with the same
+/// body from the same Atlas and configuration keeps its stamp.
+pub fn upsert_block(
+    content: &str,
+    name: &str,
+    body: &str,
+    stamp: &Stamp,
+    force: bool,
+) -> OptimizerResult<BlockUpdate> {
+    let rendered = render_block(name, body, stamp);
+    match find_block(content, name)? {
+        Some(block) if block.is_modified() && !force => Ok(BlockUpdate::Modified),
+        Some(block)
+            if block.body == with_trailing_newline(body)
+                && block
+                    .stamp
+                    .as_ref()
+                    .is_some_and(|written| written.same_source(stamp)) =>
+        {
+            Ok(BlockUpdate::Unchanged)
+        }
+        Some(block) => {
+            let mut updated = content.to_string();
+            updated.replace_range(block.range, &rendered);
+            Ok(BlockUpdate::Updated(updated))
+        }
+        None => {
+            let mut updated = content.trim_end().to_string();
+            if !updated.is_empty() {
+                updated.push_str("\n\n");
+            }
+            updated.push_str(&rendered);
+            Ok(BlockUpdate::Updated(updated))
+        }
+    }
+}
+
+/// Offset where Atlas output written before managed blocks existed starts,
+/// for content without a block called `name`
+pub fn legacy_start(content: &str, name: &str) -> Option<usize> {
+    let marker = match name {
+        // The generated Cargo config always was the whole file
+        CARGO_CONFIG_BLOCK => {
+            return content
+                .lines()
+                .take(3)
+                .any(|line| line.trim_end() == "# Generated by Atlas")
+                .then_some(0)
+        }
+        PROFILES_BLOCK => "# Optimized build profiles added by Atlas",
+        _ => return None,
+    };
+
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        if line.trim_end() == marker {
+            return Some(offset);
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// Atlas blocks in the files generated for `project_root` and in the user config
+pub fn generated_blocks(project_root: &Path) -> OptimizerResult<Vec<(PathBuf, ManagedBlock)>> {
+    let files = [
+        (
+            project_root.join(".cargo").join("config.toml"),
+            CARGO_CONFIG_BLOCK,
+        ),
+        (project_root.join("Cargo.toml"), PROFILES_BLOCK),
+        (user_cargo_config_path()?, CARGO_CONFIG_BLOCK),
+    ];
+
+    let mut blocks = Vec::new();
+    for (path, name) in files {
+        if !path.exists() {
+            continue;
+        }
+        if let Some(block) = find_block(&fs::read_to_string(&path)?, name)? {
+            blocks.push((path, block));
+        }
+    }
+    Ok(blocks)
+}
+
+/// Content with every Atlas block removed, i.e. only what the user wrote
+pub fn without_blocks(content: &str) -> OptimizerResult<String> {
+    let mut remaining = content.to_string();
+    for name in [CARGO_CONFIG_BLOCK, PROFILES_BLOCK] {
+        if let Some(block) = find_block(&remaining, name)? {
+            remaining.replace_range(block.range, "");
+        }
+    }
+    Ok(remaining)
+}
+
+/// A file Atlas wrote settings into
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ManagedFile {
+    pub path: PathBuf,
+    pub scope: ConfigScope,
+    /// Dotted keys of the settings Atlas wrote, e.g. `build.jobs`
+    pub settings: Vec<String>,
+}
+
+/// Settings written by Atlas across all scopes, stored next to the Atlas config
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ManagedRegistry {
+    #[serde(default)]
+    pub files: Vec<ManagedFile>,
+}
+
+impl ManagedRegistry {
+    pub fn path() -> OptimizerResult<PathBuf> {
+        Ok(OptimizerConfig::get_config_path()?.with_file_name("managed.toml"))
+    }
+
+    pub fn load() -> OptimizerResult<Self> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(toml::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self) -> OptimizerResult<()> {
+        let path = Self::path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Record that Atlas wrote `content` to `path`, replacing any earlier record
+    pub fn record(
+        &mut self,
+        path: &Path,
+        scope: ConfigScope,
+        content: &str,
+    ) -> OptimizerResult<()> {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let settings = setting_keys(content)?;
+        self.files.retain(|file| file.path != path);
+        self.files.push(ManagedFile {
+            path,
+            scope,
+            settings,
+        });
+        Ok(())
+    }
+
+    /// Drop the record of `path`, once Atlas's settings are gone from it
+    pub fn forget(&mut self, path: &Path) {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        self.files.retain(|file| file.path != path);
+    }
+
+    pub fn files(&self, scope: ConfigScope) -> impl Iterator<Item = &ManagedFile> {
+        self.files.iter().filter(move |file| file.scope == scope)
+    }
+
+    /// Settings in `content` that the other scope already manages, per file
+    pub fn overlapping(
+        &self,
+        scope: ConfigScope,
+        content: &str,
+    ) -> OptimizerResult<Vec<(&ManagedFile, Vec<String>)>> {
+        let keys = setting_keys(content)?;
+        Ok(self
+            .files(scope.other())
+            .filter(|file| file.path.exists())
+            .map(|file| {
+                let shared: Vec<String> = file
+                    .settings
+                    .iter()
+                    .filter(|key| keys.contains(key))
+                    .cloned()
+                    .collect();
+                (file, shared)
+            })
+            .filter(|(_, shared)| !shared.is_empty())
+            .collect())
+    }
+}
+
+/// Dotted keys of every leaf setting in a TOML document
+pub fn setting_keys(content: &str) -> OptimizerResult<Vec<String>> {
+    fn collect(value: &toml::Value, prefix: &str, keys: &mut Vec<String>) {
+        match value.as_table() {
+            Some(table) => {
+                for (key, value) in table {
+                    let key = if prefix.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{}.{}", prefix, key)
+                    };
+                    collect(value, &key, keys);
+                }
+            }
+            None => keys.push(prefix.to_string()),
+        }
+    }
+
+    let value: toml::Value = toml::from_str(content)?;
+    let mut keys = Vec::new();
+    collect(&value, "", &mut keys);
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stamp(version: &str, config_hash: &str) -> Stamp {
+        Stamp {
+            version: version.to_string(),
+            config_hash: config_hash.to_string(),
+            generated: DateTime::parse_from_rfc3339("2026-10-01T12:00:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+        }
+    }
+
+    #[test]
+    fn test_upsert_block_is_idempotent() {
+        let stamp = stamp("0.1.0", "0123456789abcdef");
+        let user = "[alias]\nxt = \"run -p xtask --\"\n";
+        let body = "[build]\njobs = 4\n";
+
+        let BlockUpdate::Updated(first) =
+            upsert_block(user, CARGO_CONFIG_BLOCK, body, &stamp, false).unwrap()
+        else {
+            panic!("block was not written");
+        };
+        assert!(first.starts_with(user));
+        assert_eq!(
+            upsert_block(&first, CARGO_CONFIG_BLOCK, body, &stamp, false).unwrap(),
+            BlockUpdate::Unchanged
+        );
+
+        let BlockUpdate::Updated(second) = upsert_block(
+            &first,
+            CARGO_CONFIG_BLOCK,
+            "[build]\njobs = 8\n",
+            &stamp,
+            false,
+        )
+        .unwrap() else {
+            panic!("block was not updated");
+        };
+        assert_eq!(second.matches(BEGIN_MARKER).count(), 1);
+        assert!(second.contains("jobs = 8") && !second.contains("jobs = 4"));
+        assert_eq!(without_blocks(&second).unwrap().trim_end(), user.trim_end());
+        assert!(toml::from_str::<toml::Value>(&second).is_ok());
+    }
+
+    #[test]
+    fn test_modified_block_is_kept() {
+        let stamp = stamp("0.1.0", "0123456789abcdef");
+        let rendered = render_block(PROFILES_BLOCK, "[profile.dev]\nopt-level = 0\n", &stamp);
+        let edited = rendered.replace("opt-level = 0", "opt-level = 1");
+
+        let block = find_block(&edited, PROFILES_BLOCK).unwrap().unwrap();
+        assert!(block.is_modified());
+        assert_eq!(
+            upsert_block(
+                &edited,
+                PROFILES_BLOCK,
+                "[profile.dev]\nopt-level = 0\n",
+                &stamp,
+                false
+            )
+            .unwrap(),
+            BlockUpdate::Modified
+        );
+        assert_eq!(
+            upsert_block(
+                &edited,
+                PROFILES_BLOCK,
+                "[profile.dev]\nopt-level = 0\n",
+                &stamp,
+                true
+            )
+            .unwrap(),
+            BlockUpdate::Updated(rendered)
+        );
+
+        let legacy = "[package]\nname = \"demo\"\n\n# Optimized build profiles added by Atlas\n[profile.dev]\n";
+        assert_eq!(legacy_start(legacy, PROFILES_BLOCK), Some(25));
+        assert_eq!(legacy_start(&edited, CARGO_CONFIG_BLOCK), None);
+
+        let unterminated = edited.replace("# END ATLAS profiles", "");
+        assert!(find_block(&unterminated, PROFILES_BLOCK).is_err());
+    }
+
+    #[test]
+    fn test_stamp_staleness() {
+        let written = stamp("0.1.0", "0123456789abcdef");
+        let rendered = render_block(CARGO_CONFIG_BLOCK, "[build]\njobs = 4\n", &written);
+        let block = find_block(&rendered, CARGO_CONFIG_BLOCK).unwrap().unwrap();
+        assert_eq!(block.body, "[build]\njobs = 4\n");
+        assert_eq!(block.stamp.as_ref(), Some(&written));
+        assert!(!block.is_modified());
+
+        assert_eq!(block.staleness(&written), None);
+        assert_eq!(
+            block.staleness(&stamp("0.10.0", "0123456789abcdef")),
+            Some(Staleness::OlderAtlas("0.1.0".to_string()))
+        );
+        assert_eq!(
+            block.staleness(&stamp("0.1.0", "fedcba9876543210")),
+            Some(Staleness::ConfigChanged)
+        );
+        assert_eq!(
+            Staleness::ConfigChanged.to_string(),
+            "the Atlas configuration changed since the block was written"
+        );
+
+        // Same body and source later on keeps the original timestamp
+        let later = Stamp {
+            generated: Utc::now(),
+            ..written.clone()
+        };
+        assert_eq!(
+            upsert_block(
+                &rendered,
+                CARGO_CONFIG_BLOCK,
+                "[build]\njobs = 4\n",
+                &later,
+                false
+            )
+            .unwrap(),
+            BlockUpdate::Unchanged
+        );
+
+        let unstamped = rendered.replace(&format!("{}\n", written.render()), "");
+        let block = find_block(&unstamped, CARGO_CONFIG_BLOCK).unwrap().unwrap();
+        assert_eq!(block.staleness(&written), Some(Staleness::Unstamped));
+    }
+
+    #[test]
+    fn test_overlapping_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let user_config = dir.path().join("config.toml");
+        fs::write(&user_config, "").unwrap();
+
+        let mut registry = ManagedRegistry::default();
+        registry
+            .record(
+                &user_config,
+                ConfigScope::User,
+                "[build]\njobs = 8\n[target.x86_64-unknown-linux-gnu]\nrustflags = []\n",
+            )
+            .unwrap();
+
+        let project = "[build]\njobs = 4\npipelining = true\n[net]\nretry = 3\n";
+        let overlap = registry.overlapping(ConfigScope::Project, project).unwrap();
+        assert_eq!(overlap.len(), 1);
+        assert_eq!(overlap[0].1, vec!["build.jobs"]);
+        assert!(registry
+            .overlapping(ConfigScope::User, project)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,540 @@
+//! Edits to `Cargo.toml` and Cargo config files that keep everything they do
+//! not change as it was: comments, key order, quoting, inline tables, dotted
+//! keys and blank lines. Every key-level edit Atlas makes to these files goes
+//! through [`Manifest`]. Keys inside Atlas blocks are left to `atlas refresh`.
+
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::managed::{find_block, CARGO_CONFIG_BLOCK, PROFILES_BLOCK};
+use std::collections::HashSet;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+use toml_edit::{DocumentMut, ImDocument, InlineTable, Item, Key, Table, TableLike, Value};
+
+/// Tables that hold dependencies, directly or under `[target.<cfg>]`
+const DEPENDENCY_TABLES: &[&str] = &["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// Dependency keys that say where a crate comes from, which a workspace
+/// dependency takes over
+const SOURCE_KEYS: &[&str] = &["version", "path", "git", "branch", "tag", "rev", "registry"];
+
+/// Trailing comment on keys Atlas adds to tables the user wrote, which tells
+/// them apart from the user's own keys on later runs
+pub const ADDED_MARKER: &str = "# atlas";
+
+/// What [`Manifest::merge_key`] did
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyMerge {
+    Added,
+    /// A key Atlas added before got a new value
+    Updated,
+    Unchanged,
+    /// The user set the key to something else; holds their value
+    Kept(String),
+}
+
+/// A parsed manifest or config file
+#[derive(Debug, Clone)]
+pub struct Manifest {
+    document: DocumentMut,
+    /// Tables whose header is inside an Atlas block
+    managed_tables: HashSet<String>,
+    /// Keys inside an Atlas block, as (table, key)
+    managed_keys: HashSet<(String, String)>,
+}
+
+impl Manifest {
+    pub fn parse(content: &str) -> OptimizerResult<Self> {
+        let parsed = ImDocument::parse(content.to_string())
+            .map_err(|e| OptimizerError::config(format!("Invalid TOML: {}", e)))?;
+        let blocks: Vec<Range<usize>> = [CARGO_CONFIG_BLOCK, PROFILES_BLOCK]
+            .iter()
+            .filter_map(|name| find_block(content, name).ok().flatten())
+            .map(|block| block.range)
+            .collect();
+
+        let mut manifest = Self {
+            document: DocumentMut::new(),
+            managed_tables: HashSet::new(),
+            managed_keys: HashSet::new(),
+        };
+        if !blocks.is_empty() {
+            manifest.find_managed(parsed.as_table(), "", &blocks);
+        }
+        manifest.document = parsed.into_mut();
+        Ok(manifest)
+    }
+
+    pub fn load(path: &Path) -> OptimizerResult<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: &Path) -> OptimizerResult<()> {
+        crate::audit::write(path, self.to_string())
+    }
+
+    fn find_managed(&mut self, table: &Table, path: &str, blocks: &[Range<usize>]) {
+        let in_block = |span: Option<Range<usize>>| {
+            span.is_some_and(|span| blocks.iter().any(|block| block.contains(&span.start)))
+        };
+        if in_block(table.span()) {
+            self.managed_tables.insert(path.to_string());
+        }
+        for (key, item) in table.iter() {
+            if in_block(table.key(key).and_then(Key::span)) {
+                self.managed_keys
+                    .insert((path.to_string(), key.to_string()));
+            }
+            if let Some(child) = item.as_table() {
+                self.find_managed(child, &join(path, key), blocks);
+            }
+        }
+    }
+
+    fn table(&self, path: &str) -> Option<&dyn TableLike> {
+        let mut table: &dyn TableLike = self.document.as_table();
+        for part in path.split('.') {
+            table = table.get(part)?.as_table_like()?;
+        }
+        Some(table)
+    }
+
+    fn table_mut(&mut self, path: &str) -> Option<&mut dyn TableLike> {
+        let mut table: &mut dyn TableLike = self.document.as_table_mut();
+        for part in path.split('.') {
+            table = table.get_mut(part)?.as_table_like_mut()?;
+        }
+        Some(table)
+    }
+
+    pub fn contains_key(&self, table: &str, key: &str) -> bool {
+        self.table(table)
+            .is_some_and(|table| table.contains_key(key))
+    }
+
+    /// `key` of `table` as written, without comments, e.g. `3` or `"s"`
+    pub fn value(&self, table: &str, key: &str) -> Option<String> {
+        let value = self.table(table)?.get(key)?.as_value()?;
+        Some(value.clone().decorated("", "").to_string())
+    }
+
+    /// Names of the tables directly under `table`
+    pub fn child_tables(&self, table: &str) -> Vec<String> {
+        self.table(table)
+            .map(|table| {
+                table
+                    .iter()
+                    .filter(|(_, item)| item.is_table_like())
+                    .map(|(key, _)| key.to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Remove `key` from `table`, and the table too once it is empty. Keys in
+    /// an Atlas block are not touched.
+    pub fn remove_key(&mut self, table: &str, key: &str) -> bool {
+        if self.is_managed(table, key) {
+            return false;
+        }
+        let Some(parent) = self.table_mut(table) else {
+            return false;
+        };
+        if parent.remove(key).is_none() {
+            return false;
+        }
+        if parent.is_empty() {
+            if let Some((path, name)) = table.rsplit_once('.') {
+                if let Some(grandparent) = self.table_mut(path) {
+                    grandparent.remove(name);
+                }
+            } else {
+                self.document.as_table_mut().remove(table);
+            }
+        }
+        true
+    }
+
+    /// Whether `key` of `table` is written by an Atlas block
+    pub fn is_managed(&self, table: &str, key: &str) -> bool {
+        self.managed_tables.contains(table)
+            || self
+                .managed_keys
+                .contains(&(table.to_string(), key.to_string()))
+    }
+
+    /// Rename `from` to `to` in `table`, keeping its place, value and comments
+    pub fn rename_key(&mut self, table: &str, from: &str, to: &str) -> bool {
+        if self
+            .managed_keys
+            .contains(&(table.to_string(), from.to_string()))
+        {
+            return false;
+        }
+        match self.table_mut(table) {
+            Some(table) if table.contains_key(from) && !table.contains_key(to) => {
+                rename(table, from, to);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// `table`, added at the end with any missing parents when it does not
+    /// exist; `None` when a part of its path is not a table
+    fn insert_table(&mut self, table: &str) -> Option<&mut Table> {
+        let mut current: &mut Table = self.document.as_table_mut();
+        let parts: Vec<&str> = table.split('.').collect();
+        for (index, part) in parts.iter().enumerate() {
+            let last = index + 1 == parts.len();
+            let item = current.entry(part).or_insert_with(|| {
+                let mut table = Table::new();
+                table.set_implicit(!last);
+                Item::Table(table)
+            });
+            current = item.as_table_mut()?;
+        }
+        Some(current)
+    }
+
+    /// Set `key` in `table` to `value`, a TOML literal. A missing table is
+    /// added at the end; a table that only exists in an Atlas block is not
+    /// touched.
+    pub fn set_key(&mut self, table: &str, key: &str, value: &str) -> OptimizerResult<bool> {
+        let value: Value = value.parse().map_err(|e| {
+            OptimizerError::config(format!("Invalid TOML value '{}': {}", value, e))
+        })?;
+        if self.managed_tables.contains(table)
+            || self
+                .managed_keys
+                .contains(&(table.to_string(), key.to_string()))
+        {
+            return Ok(false);
+        }
+
+        let Some(current) = self.insert_table(table) else {
+            return Ok(false);
+        };
+        match current.get_mut(key) {
+            Some(Item::Value(existing)) => {
+                let decor = existing.decor().clone();
+                *existing = value;
+                *existing.decor_mut() = decor;
+            }
+            Some(_) => return Ok(false),
+            None => {
+                current.insert(key, Item::Value(value));
+            }
+        }
+        Ok(true)
+    }
+
+    /// Set `key` in `table`, a table outside Atlas blocks, after its other
+    /// keys, marked `# atlas`. A missing table is added at the
+    /// end. Keys the user set are kept; keys Atlas added on an earlier run
+    /// are updated in place.
+    pub fn merge_key(&mut self, table: &str, key: &str, value: &str) -> OptimizerResult<KeyMerge> {
+        let mut value: Value = value.parse().map_err(|e| {
+            OptimizerError::config(format!("Invalid TOML value '{}': {}", value, e))
+        })?;
+        if self.is_managed(table, key) {
+            return Ok(KeyMerge::Unchanged);
+        }
+        if self.table(table).is_none() {
+            self.insert_table(table);
+        }
+        let Some(parent) = self.table_mut(table) else {
+            return Ok(KeyMerge::Unchanged);
+        };
+        let literal = value.clone().decorated("", "").to_string();
+        match parent.get_mut(key) {
+            None => {
+                value.decor_mut().set_suffix(format!(" {}", ADDED_MARKER));
+                parent.insert(key, Item::Value(value));
+                Ok(KeyMerge::Added)
+            }
+            Some(Item::Value(existing)) => {
+                let current = existing.clone().decorated("", "").to_string();
+                if current == literal {
+                    Ok(KeyMerge::Unchanged)
+                } else if is_added(existing) {
+                    let decor = existing.decor().clone();
+                    *existing = value;
+                    *existing.decor_mut() = decor;
+                    Ok(KeyMerge::Updated)
+                } else {
+                    Ok(KeyMerge::Kept(current))
+                }
+            }
+            Some(item) => Ok(KeyMerge::Kept(item.to_string().trim().to_string())),
+        }
+    }
+
+    /// Remove every key marked `# atlas`, and the tables left
+    /// empty without them; how many keys there were
+    pub fn remove_added_keys(&mut self) -> usize {
+        fn remove(table: &mut dyn TableLike) -> usize {
+            let added: Vec<String> = table
+                .iter()
+                .filter(|(_, item)| item.as_value().is_some_and(is_added))
+                .map(|(key, _)| key.to_string())
+                .collect();
+            for key in &added {
+                table.remove(key);
+            }
+            let mut removed = added.len();
+            let mut emptied = Vec::new();
+            for (key, item) in table.iter_mut() {
+                if let Some(child) = item.as_table_like_mut() {
+                    let count = remove(child);
+                    if count > 0 && child.is_empty() {
+                        emptied.push(key.get().to_string());
+                    }
+                    removed += count;
+                }
+            }
+            for key in &emptied {
+                table.remove(key);
+            }
+            removed
+        }
+        remove(self.document.as_table_mut())
+    }
+
+    /// Paths of the dependency tables, e.g. `dependencies` and
+    /// `target.'cfg(unix)'.dependencies`
+    fn dependency_tables(&self) -> Vec<Vec<String>> {
+        let mut tables: Vec<Vec<String>> = DEPENDENCY_TABLES
+            .iter()
+            .map(|name| vec![name.to_string()])
+            .collect();
+        if let Some(targets) = self.document.get("target").and_then(Item::as_table_like) {
+            for (target, _) in targets.iter() {
+                for name in DEPENDENCY_TABLES {
+                    tables.push(vec![
+                        "target".to_string(),
+                        target.to_string(),
+                        name.to_string(),
+                    ]);
+                }
+            }
+        }
+        tables
+    }
+
+    fn dependency_table_mut(&mut self, path: &[String]) -> Option<&mut dyn TableLike> {
+        let mut table: &mut dyn TableLike = self.document.as_table_mut();
+        for part in path {
+            table = table.get_mut(part)?.as_table_like_mut()?;
+        }
+        Some(table)
+    }
+
+    /// Remove `name` from every dependency table; whether it was found
+    pub fn remove_dependency(&mut self, name: &str) -> bool {
+        let mut removed = false;
+        for path in self.dependency_tables() {
+            if let Some(table) = self.dependency_table_mut(&path) {
+                removed |= table.remove(name).is_some();
+            }
+        }
+        removed
+    }
+
+    /// Point every declaration of `name` at `[workspace.dependencies]`: the
+    /// source keys go, `workspace = true` takes the place of `version`, and
+    /// features, `optional` and `default-features` stay
+    pub fn use_workspace_dependency(&mut self, name: &str) -> bool {
+        let mut changed = false;
+        for path in self.dependency_tables() {
+            let Some(table) = self.dependency_table_mut(&path) else {
+                continue;
+            };
+            let Some(item) = table.get_mut(name) else {
+                continue;
+            };
+            if let Some(dependency) = item.as_table_like_mut() {
+                if dependency.get("workspace").and_then(Item::as_bool) == Some(true) {
+                    continue;
+                }
+                if dependency.contains_key("version") {
+                    rename(dependency, "version", "workspace");
+                }
+                for key in SOURCE_KEYS {
+                    dependency.remove(key);
+                }
+                match dependency.get_mut("workspace") {
+                    Some(workspace) => replace_value(workspace, Value::from(true)),
+                    None => {
+                        dependency.insert("workspace", toml_edit::value(true));
+                    }
+                }
+            } else if item.is_str() {
+                let mut dependency = InlineTable::new();
+                dependency.insert("workspace", Value::from(true));
+                replace_value(item, Value::InlineTable(dependency));
+            } else {
+                continue;
+            }
+            changed = true;
+        }
+        changed
+    }
+
+    /// Declare `name` in `[workspace.dependencies]` with `spec`, a TOML literal
+    /// such as `"1.0"` or `{ version = "1.0", features = ["derive"] }`; an
+    /// existing declaration is kept
+    pub fn add_workspace_dependency(&mut self, name: &str, spec: &str) -> OptimizerResult<bool> {
+        if self.contains_key("workspace.dependencies", name) {
+            return Ok(false);
+        }
+        self.set_key("workspace.dependencies", name, spec)
+    }
+}
+
+impl std::fmt::Display for Manifest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.document)
+    }
+}
+
+fn is_added(value: &Value) -> bool {
+    value
+        .decor()
+        .suffix()
+        .and_then(|suffix| suffix.as_str())
+        .is_some_and(|suffix| suffix.trim() == ADDED_MARKER)
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+/// Replace a value and keep the whitespace and comments around it
+fn replace_value(item: &mut Item, value: Value) {
+    let decor = item.as_value().map(|existing| existing.decor().clone());
+    *item = Item::Value(value);
+    if let (Some(decor), Some(value)) = (decor, item.as_value_mut()) {
+        *value.decor_mut() = decor;
+    }
+}
+
+/// Rename a key in place: entries are kept in insertion order, so the key and
+/// everything after it are removed and put back
+fn rename(table: &mut dyn TableLike, from: &str, to: &str) {
+    let keys: Vec<String> = table.iter().map(|(key, _)| key.to_string()).collect();
+    let Some(start) = keys.iter().position(|key| key == from) else {
+        return;
+    };
+    let mut moved = Vec::new();
+    for key in &keys[start..] {
+        let Some((formatted, _)) = table.get_key_value(key) else {
+            continue;
+        };
+        let mut formatted = formatted.clone();
+        if key == from {
+            formatted = Key::new(to).with_leaf_decor(formatted.leaf_decor().clone());
+        }
+        if let Some(item) = table.remove(key) {
+            moved.push((formatted, item));
+        }
+    }
+    for (key, item) in moved {
+        table.entry_format(&key).or_insert(item);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edits_keep_formatting() {
+        let content = "[package]\nname = \"demo\" # the name\n\n[profile.dev]\nopt_level = 1 # fast\ndebug = false\n";
+        let mut manifest = Manifest::parse(content).unwrap();
+        assert!(manifest.rename_key("profile.dev", "opt_level", "opt-level"));
+        assert!(manifest.set_key("profile.dev", "debug", "true").unwrap());
+        assert_eq!(
+            manifest.to_string(),
+            "[package]\nname = \"demo\" # the name\n\n[profile.dev]\nopt-level = 1 # fast\ndebug = true\n"
+        );
+        assert!(!manifest.rename_key("profile.dev", "missing", "other"));
+    }
+
+    #[test]
+    fn test_merge_key_keeps_user_keys() {
+        let content = "[profile.dev]\n# Faster debug builds\ndebug = 1 # enough for backtraces\nopt-level = 0\n\n[profile.release]\nlto = true\n";
+        let mut manifest = Manifest::parse(content).unwrap();
+        assert_eq!(
+            manifest
+                .merge_key("profile.dev", "codegen-units", "512")
+                .unwrap(),
+            KeyMerge::Added
+        );
+        assert_eq!(
+            manifest.merge_key("profile.dev", "debug", "2").unwrap(),
+            KeyMerge::Kept("1".to_string())
+        );
+        assert_eq!(
+            manifest.merge_key("profile.dev", "opt-level", "0").unwrap(),
+            KeyMerge::Unchanged
+        );
+        let merged = manifest.to_string();
+        assert_eq!(
+            merged,
+            "[profile.dev]\n# Faster debug builds\ndebug = 1 # enough for backtraces\nopt-level = 0\ncodegen-units = 512 # atlas\n\n[profile.release]\nlto = true\n"
+        );
+
+        // A later run updates what it added, and undoing removes it
+        let mut manifest = Manifest::parse(&merged).unwrap();
+        assert_eq!(
+            manifest
+                .merge_key("profile.dev", "codegen-units", "256")
+                .unwrap(),
+            KeyMerge::Updated
+        );
+        assert!(manifest
+            .to_string()
+            .contains("codegen-units = 256 # atlas\n"));
+        assert_eq!(manifest.remove_added_keys(), 1);
+        assert_eq!(manifest.to_string(), content);
+
+        // Tables Atlas had to add go away with their keys
+        let mut manifest = Manifest::parse(content).unwrap();
+        assert_eq!(
+            manifest.merge_key("build", "jobs", "4").unwrap(),
+            KeyMerge::Added
+        );
+        assert!(manifest
+            .to_string()
+            .ends_with("\n[build]\njobs = 4 # atlas\n"));
+        assert_eq!(manifest.remove_added_keys(), 1);
+        assert_eq!(manifest.to_string(), content);
+    }
+
+    #[test]
+    fn test_remove_key_drops_empty_table() {
+        let content = "[package]\nname = \"demo\"\n\n[profile.dev.package.image]\nopt-level = 3 # decoding\n\n[profile.dev.package.regex]\nopt-level = 2\ndebug = false\n";
+        let mut manifest = Manifest::parse(content).unwrap();
+        assert_eq!(
+            manifest.child_tables("profile.dev.package"),
+            ["image", "regex"]
+        );
+        assert_eq!(
+            manifest.value("profile.dev.package.image", "opt-level"),
+            Some("3".to_string())
+        );
+
+        assert!(manifest.remove_key("profile.dev.package.image", "opt-level"));
+        assert!(manifest.remove_key("profile.dev.package.regex", "opt-level"));
+        assert!(!manifest.remove_key("profile.dev.package.regex", "opt-level"));
+        assert_eq!(
+            manifest.to_string(),
+            "[package]\nname = \"demo\"\n\n[profile.dev.package.regex]\ndebug = false\n"
+        );
+    }
+}
@@ -0,0 +1,185 @@
+//! Build matrices: the target, profile and feature combinations listed as
+//! `[[matrix]]` entries in atlas.toml and built in turn by `atlas build matrix`.
+
+use crate::error::{OptimizerError, OptimizerResult};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// One combination to build
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MatrixEntry {
+    /// Label in the report; derived from the other fields when missing
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Cargo subcommand, `build` unless set, e.g. `check` or `test`
+    #[serde(default)]
+    pub command: Option<String>,
+    /// Target triple; the host when missing
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Cargo profile; `dev` when missing
+    #[serde(default)]
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub features: Vec<String>,
+    #[serde(default)]
+    pub no_default_features: bool,
+    #[serde(default)]
+    pub all_features: bool,
+}
+
+impl MatrixEntry {
+    pub fn command(&self) -> &str {
+        self.command.as_deref().unwrap_or("build")
+    }
+
+    pub fn label(&self) -> String {
+        if let Some(name) = &self.name {
+            return name.clone();
+        }
+        let mut label = format!(
+            "{} {}/{}",
+            self.command(),
+            self.target.as_deref().unwrap_or("host"),
+            self.profile.as_deref().unwrap_or("dev")
+        );
+        if self.all_features {
+            label.push_str(" +all-features");
+        } else {
+            if self.no_default_features {
+                label.push_str(" -default");
+            }
+            for feature in &self.features {
+                label.push_str(" +");
+                label.push_str(feature);
+            }
+        }
+        label
+    }
+
+    /// Cargo arguments for this entry. `target_dir` separates entries built
+    /// at the same time, which would otherwise wait on each other's lock.
+    pub fn cargo_args(&self, scope: &[String], target_dir: Option<&Path>) -> Vec<String> {
+        let mut args = vec![self.command().to_string()];
+        args.extend(scope.iter().cloned());
+        if let Some(target) = &self.target {
+            args.push(format!("--target={}", target));
+        }
+        if let Some(profile) = &self.profile {
+            args.push(format!("--profile={}", profile));
+        }
+        if self.all_features {
+            args.push("--all-features".to_string());
+        }
+        if self.no_default_features {
+            args.push("--no-default-features".to_string());
+        }
+        if !self.features.is_empty() {
+            args.push(format!("--features={}", self.features.join(",")));
+        }
+        if let Some(target_dir) = target_dir {
+            args.push(format!("--target-dir={}", target_dir.display()));
+        }
+        args
+    }
+}
+
+pub fn validate(entries: &[MatrixEntry]) -> OptimizerResult<()> {
+    let mut labels = BTreeSet::new();
+    for entry in entries {
+        let label = entry.label();
+        if entry.all_features && (entry.no_default_features || !entry.features.is_empty()) {
+            return Err(OptimizerError::config(format!(
+                "Matrix entry '{}' sets all_features together with other feature options",
+                label
+            )));
+        }
+        if !labels.insert(label.clone()) {
+            return Err(OptimizerError::config(format!(
+                "Matrix entry '{}' is listed twice; give one of them a name",
+                label
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Target directory for worker `slot` of a parallel run. The first worker
+/// shares the project's target directory; the others keep their own under it,
+/// stable between runs so their caches stay warm.
+pub fn slot_target_dir(target_directory: &Path, slot: usize) -> Option<PathBuf> {
+    (slot > 0).then(|| target_directory.join("atlas-matrix").join(slot.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project_config::ProjectConfig;
+
+    #[test]
+    fn test_entry_args_and_labels() {
+        let config: ProjectConfig = toml::from_str(
+            r#"
+[[matrix]]
+target = "aarch64-unknown-linux-gnu"
+profile = "release"
+features = ["tls", "metrics"]
+
+[[matrix]]
+name = "minimal"
+command = "check"
+no_default_features = true
+"#,
+        )
+        .unwrap();
+        let release = &config.matrix[0];
+        assert_eq!(
+            release.label(),
+            "build aarch64-unknown-linux-gnu/release +tls +metrics"
+        );
+        assert_eq!(
+            release.cargo_args(&["--workspace".to_string()], None),
+            vec![
+                "build",
+                "--workspace",
+                "--target=aarch64-unknown-linux-gnu",
+                "--profile=release",
+                "--features=tls,metrics",
+            ]
+        );
+        let minimal = &config.matrix[1];
+        assert_eq!(minimal.label(), "minimal");
+        assert_eq!(
+            minimal.cargo_args(&[], Some(Path::new("/t/atlas-matrix/1"))),
+            vec![
+                "check",
+                "--no-default-features",
+                "--target-dir=/t/atlas-matrix/1"
+            ]
+        );
+        assert_eq!(slot_target_dir(Path::new("/t"), 0), None);
+    }
+
+    #[test]
+    fn test_invalid_matrix() {
+        let duplicate = vec![MatrixEntry::default(), MatrixEntry::default()];
+        assert!(validate(&duplicate).is_err());
+
+        let conflicting = vec![MatrixEntry {
+            all_features: true,
+            features: vec!["tls".to_string()],
+            ..MatrixEntry::default()
+        }];
+        assert!(validate(&conflicting).is_err());
+
+        let named = vec![
+            MatrixEntry::default(),
+            MatrixEntry {
+                name: Some("again".to_string()),
+                ..MatrixEntry::default()
+            },
+        ];
+        assert!(validate(&named).is_ok());
+    }
+}
@@ -0,0 +1,124 @@
+//! Selection of workspace members by group name or glob pattern
+
+use crate::error::{OptimizerError, OptimizerResult};
+use std::collections::BTreeMap;
+
+/// Expand `--members` selectors to the names of the workspace members they match.
+/// A selector is a group from `atlas.toml` or a glob pattern such as `api-*`.
+pub fn select_members(
+    selectors: &[String],
+    groups: &BTreeMap<String, Vec<String>>,
+    members: &[&str],
+) -> OptimizerResult<Vec<String>> {
+    let mut selected: Vec<String> = Vec::new();
+    for selector in selectors {
+        let patterns = match groups.get(selector) {
+            Some(patterns) => patterns.clone(),
+            None => vec![selector.clone()],
+        };
+
+        let matched: Vec<&str> = members
+            .iter()
+            .copied()
+            .filter(|member| patterns.iter().any(|pattern| glob_match(pattern, member)))
+            .collect();
+        if matched.is_empty() {
+            return Err(OptimizerError::invalid_input(format!(
+                "'{}' matches no workspace member. Members: {}{}",
+                selector,
+                members.join(", "),
+                if groups.is_empty() {
+                    String::new()
+                } else {
+                    format!(
+                        ". Groups: {}",
+                        groups.keys().cloned().collect::<Vec<_>>().join(", ")
+                    )
+                }
+            )));
+        }
+
+        for member in matched {
+            if !selected.iter().any(|name| name == member) {
+                selected.push(member.to_string());
+            }
+        }
+    }
+    Ok(selected)
+}
+
+/// Cargo arguments that select `members`
+pub fn package_args(members: &[String]) -> Vec<String> {
+    members
+        .iter()
+        .flat_map(|member| ["-p".to_string(), member.clone()])
+        .collect()
+}
+
+/// Match `name` against a pattern where `*` is any run of characters and `?` one character
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    // Position after the last `*` and the name position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p + 1, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_n)) => {
+                    p = star_p;
+                    n = star_n + 1;
+                    backtrack = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("api-*", "api-server"));
+        assert!(glob_match("*-cli", "atlas-cli"));
+        assert!(glob_match("db?", "db2"));
+        assert!(glob_match("*", "anything"));
+        assert!(glob_match("a*b*c", "axxbyyc"));
+        assert!(!glob_match("api-*", "web"));
+        assert!(!glob_match("db?", "db"));
+    }
+
+    #[test]
+    fn test_select_members() {
+        let members = ["api-server", "api-types", "db", "cli", "xtask"];
+        let groups = BTreeMap::from([(
+            "backend".to_string(),
+            vec!["api-*".to_string(), "db".to_string()],
+        )]);
+
+        let selected = select_members(
+            &["backend".to_string(), "db".to_string(), "cli".to_string()],
+            &groups,
+            &members,
+        )
+        .unwrap();
+        assert_eq!(selected, vec!["api-server", "api-types", "db", "cli"]);
+        assert_eq!(package_args(&selected[2..]), vec!["-p", "db", "-p", "cli"]);
+
+        let error = select_members(&["web-*".to_string()], &groups, &members).unwrap_err();
+        assert!(error.to_string().contains("Groups: backend"));
+    }
+}
@@ -0,0 +1,129 @@
+//! Settings that older Atlas releases generated and Cargo no longer honors
+
+use crate::error::OptimizerResult;
+use crate::managed::setting_keys;
+
+/// A generated setting that is obsolete, and what replaces it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Deprecation {
+    /// Dotted key, e.g. `env.CARGO_BUILD_CACHE`
+    pub key: &'static str,
+    /// Setting that now carries the intent, if any
+    pub replacement: Option<&'static str>,
+    pub reason: &'static str,
+}
+
+impl Deprecation {
+    /// Whether `key` is this setting or part of its table value
+    fn covers(&self, key: &str) -> bool {
+        key.strip_prefix(self.key)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+    }
+
+    /// One-line description of the change `atlas refresh` makes
+    pub fn describe(&self) -> String {
+        match self.replacement {
+            Some(replacement) => format!(
+                "Replaced {} with {}: {}",
+                self.key, replacement, self.reason
+            ),
+            None => format!("Removed {}: {}", self.key, self.reason),
+        }
+    }
+}
+
+pub const DEPRECATED_SETTINGS: &[Deprecation] = &[
+    Deprecation {
+        key: "env.CARGO_BUILD_CACHE",
+        replacement: None,
+        reason: "not a Cargo variable, so it never had any effect",
+    },
+    Deprecation {
+        key: "env.CARGO_INCREMENTAL",
+        replacement: Some("build.incremental"),
+        reason: "[env] only reaches rustc and build scripts, not Cargo itself",
+    },
+    Deprecation {
+        key: "env.CARGO_PROFILE_DEV_INCREMENTAL",
+        replacement: Some("profile.dev.incremental"),
+        reason: "[env] only reaches rustc and build scripts, not Cargo itself",
+    },
+    Deprecation {
+        key: "env.CARGO_NET_RETRY",
+        replacement: Some("net.retry"),
+        reason: "[env] only reaches rustc and build scripts, not Cargo itself",
+    },
+    Deprecation {
+        key: "env.CARGO_NET_GIT_FETCH_WITH_CLI",
+        replacement: Some("net.git-fetch-with-cli"),
+        reason: "[env] only reaches rustc and build scripts, not Cargo itself",
+    },
+    Deprecation {
+        key: "env.CARGO_TARGET_DIR",
+        replacement: None,
+        reason: "[env] has no `condition` option, so rust-analyzer never got its own target directory; set rust-analyzer.cargo.targetDir in your editor instead",
+    },
+    Deprecation {
+        key: "build.pipelining",
+        replacement: None,
+        reason: "pipelining is always on since Cargo 1.40 and the key is ignored",
+    },
+];
+
+/// Deprecated settings present in a TOML document
+pub fn find_deprecated(content: &str) -> OptimizerResult<Vec<&'static Deprecation>> {
+    let keys = setting_keys(content)?;
+    Ok(DEPRECATED_SETTINGS
+        .iter()
+        .filter(|deprecation| keys.iter().any(|key| deprecation.covers(key)))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{generate_cargo_config, ConfigScope, OptimizerConfig};
+
+    #[test]
+    fn test_find_deprecated() {
+        let legacy = "[build]\njobs = 4\npipelining = true\n\n[env]\nCARGO_INCREMENTAL = \"1\"\nCARGO_BUILD_CACHE = \"1\"\nCARGO_TARGET_DIR = { value = \"target/rust-analyzer\", condition = \"cfg(rust_analyzer)\" }\n";
+        let found: Vec<&str> = find_deprecated(legacy)
+            .unwrap()
+            .iter()
+            .map(|deprecation| deprecation.key)
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                "env.CARGO_BUILD_CACHE",
+                "env.CARGO_INCREMENTAL",
+                "env.CARGO_TARGET_DIR",
+                "build.pipelining"
+            ]
+        );
+        assert!(DEPRECATED_SETTINGS[1]
+            .describe()
+            .starts_with("Replaced env.CARGO_INCREMENTAL with build.incremental"));
+    }
+
+    #[test]
+    fn test_generated_config_has_no_deprecated_settings() {
+        let config = OptimizerConfig::default();
+        let system = crate::system::SystemInfo {
+            os: crate::system::OperatingSystem::Linux,
+            arch: crate::system::Architecture::X86_64,
+            cpu_cores: 8,
+            rust_version: None,
+            cargo_version: None,
+            rust_llvm_version: None,
+            linux_package_manager: None,
+            available_tools: Default::default(),
+            rustup_components: None,
+            power: Default::default(),
+        };
+        for scope in [ConfigScope::Project, ConfigScope::User] {
+            let generated = generate_cargo_config(&config, &system, scope);
+            assert!(find_deprecated(&generated).unwrap().is_empty());
+        }
+    }
+}
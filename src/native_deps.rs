@@ -0,0 +1,306 @@
+use crate::analysis::metadata::CargoMetadata;
+use crate::system::SystemInfo;
+use crate::utils::{execute_command, is_tool_available};
+use std::path::Path;
+
+/// What a crate needs from the system to build
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Requirement {
+    /// A library found through pkg-config, or by its header as a fallback
+    Library {
+        pkg_config: &'static str,
+        header: &'static str,
+    },
+    /// An executable on `PATH`
+    Tool(&'static str),
+}
+
+/// Native prerequisites of a well-known crate
+#[derive(Debug, Clone, Copy)]
+pub struct NativeDependency {
+    pub crate_name: &'static str,
+    pub requirement: Requirement,
+    /// The crate can build a bundled copy when the system library is missing
+    pub vendored_fallback: bool,
+    /// Package names for apt, yum/dnf, pacman and Homebrew
+    pub packages: SystemPackages,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SystemPackages {
+    pub apt: Option<&'static str>,
+    pub yum: Option<&'static str>,
+    pub pacman: Option<&'static str>,
+    pub brew: Option<&'static str>,
+}
+
+const fn library(pkg_config: &'static str, header: &'static str) -> Requirement {
+    Requirement::Library { pkg_config, header }
+}
+
+const fn packages(
+    apt: &'static str,
+    yum: &'static str,
+    pacman: &'static str,
+    brew: Option<&'static str>,
+) -> SystemPackages {
+    SystemPackages {
+        apt: Some(apt),
+        yum: Some(yum),
+        pacman: Some(pacman),
+        brew,
+    }
+}
+
+/// Crates whose builds most often fail on a fresh machine
+pub const KNOWN_NATIVE_DEPENDENCIES: &[NativeDependency] = &[
+    NativeDependency {
+        crate_name: "openssl-sys",
+        requirement: library("openssl", "openssl/ssl.h"),
+        vendored_fallback: false,
+        packages: packages("libssl-dev", "openssl-devel", "openssl", Some("openssl@3")),
+    },
+    NativeDependency {
+        crate_name: "libgit2-sys",
+        requirement: library("libgit2", "git2.h"),
+        vendored_fallback: true,
+        packages: packages("libgit2-dev", "libgit2-devel", "libgit2", Some("libgit2")),
+    },
+    NativeDependency {
+        crate_name: "libssh2-sys",
+        requirement: library("libssh2", "libssh2.h"),
+        vendored_fallback: true,
+        packages: packages("libssh2-1-dev", "libssh2-devel", "libssh2", Some("libssh2")),
+    },
+    NativeDependency {
+        crate_name: "libz-sys",
+        requirement: library("zlib", "zlib.h"),
+        vendored_fallback: true,
+        packages: packages("zlib1g-dev", "zlib-devel", "zlib", Some("zlib")),
+    },
+    NativeDependency {
+        crate_name: "curl-sys",
+        requirement: library("libcurl", "curl/curl.h"),
+        vendored_fallback: true,
+        packages: packages(
+            "libcurl4-openssl-dev",
+            "libcurl-devel",
+            "curl",
+            Some("curl"),
+        ),
+    },
+    NativeDependency {
+        crate_name: "libsqlite3-sys",
+        requirement: library("sqlite3", "sqlite3.h"),
+        vendored_fallback: true,
+        packages: packages("libsqlite3-dev", "sqlite-devel", "sqlite", Some("sqlite")),
+    },
+    NativeDependency {
+        crate_name: "pq-sys",
+        requirement: library("libpq", "libpq-fe.h"),
+        vendored_fallback: false,
+        packages: packages("libpq-dev", "libpq-devel", "postgresql-libs", Some("libpq")),
+    },
+    NativeDependency {
+        crate_name: "mysqlclient-sys",
+        requirement: library("mysqlclient", "mysql/mysql.h"),
+        vendored_fallback: false,
+        packages: packages(
+            "libmysqlclient-dev",
+            "mysql-devel",
+            "mariadb-libs",
+            Some("mysql-client"),
+        ),
+    },
+    NativeDependency {
+        crate_name: "libdbus-sys",
+        requirement: library("dbus-1", "dbus/dbus.h"),
+        vendored_fallback: false,
+        packages: packages("libdbus-1-dev", "dbus-devel", "dbus", Some("dbus")),
+    },
+    NativeDependency {
+        crate_name: "alsa-sys",
+        requirement: library("alsa", "alsa/asoundlib.h"),
+        vendored_fallback: false,
+        packages: packages("libasound2-dev", "alsa-lib-devel", "alsa-lib", None),
+    },
+    NativeDependency {
+        crate_name: "libudev-sys",
+        requirement: library("libudev", "libudev.h"),
+        vendored_fallback: false,
+        packages: packages("libudev-dev", "systemd-devel", "systemd-libs", None),
+    },
+    NativeDependency {
+        crate_name: "freetype-sys",
+        requirement: library("freetype2", "ft2build.h"),
+        vendored_fallback: true,
+        packages: packages(
+            "libfreetype6-dev",
+            "freetype-devel",
+            "freetype2",
+            Some("freetype"),
+        ),
+    },
+    NativeDependency {
+        crate_name: "yeslogic-fontconfig-sys",
+        requirement: library("fontconfig", "fontconfig/fontconfig.h"),
+        vendored_fallback: false,
+        packages: packages(
+            "libfontconfig1-dev",
+            "fontconfig-devel",
+            "fontconfig",
+            Some("fontconfig"),
+        ),
+    },
+    NativeDependency {
+        crate_name: "prost-build",
+        requirement: Requirement::Tool("protoc"),
+        vendored_fallback: false,
+        packages: packages(
+            "protobuf-compiler",
+            "protobuf-compiler",
+            "protobuf",
+            Some("protobuf"),
+        ),
+    },
+    NativeDependency {
+        crate_name: "cmake",
+        requirement: Requirement::Tool("cmake"),
+        vendored_fallback: false,
+        packages: packages("cmake", "cmake", "cmake", Some("cmake")),
+    },
+];
+
+/// Outcome of checking one native dependency
+#[derive(Debug, Clone)]
+pub struct NativeCheck {
+    pub dependency: &'static NativeDependency,
+    pub found: bool,
+}
+
+impl NativeDependency {
+    pub fn lookup(crate_name: &str) -> Option<&'static Self> {
+        KNOWN_NATIVE_DEPENDENCIES
+            .iter()
+            .find(|dependency| dependency.crate_name == crate_name)
+    }
+
+    /// Human-readable name of what is required
+    pub fn requirement_name(&self) -> &'static str {
+        match self.requirement {
+            Requirement::Library { pkg_config, .. } => pkg_config,
+            Requirement::Tool(tool) => tool,
+        }
+    }
+
+    /// Whether the requirement is satisfied on this machine
+    pub fn is_satisfied(&self) -> bool {
+        match self.requirement {
+            Requirement::Tool(tool) => is_tool_available(tool),
+            Requirement::Library { pkg_config, header } => {
+                if is_tool_available("pkg-config") {
+                    if let Ok(output) =
+                        execute_command("pkg-config", &["--exists", pkg_config], None)
+                    {
+                        if output.status.success() {
+                            return true;
+                        }
+                    }
+                }
+                header_exists(header)
+            }
+        }
+    }
+
+    /// Package to install with the given package manager
+    pub fn package_for(&self, package_manager: &str) -> Option<&'static str> {
+        match package_manager {
+            "apt" => self.packages.apt,
+            "yum" => self.packages.yum,
+            "pacman" => self.packages.pacman,
+            "brew" => self.packages.brew,
+            _ => None,
+        }
+    }
+}
+
+fn header_exists(header: &str) -> bool {
+    [
+        "/usr/include",
+        "/usr/local/include",
+        "/opt/homebrew/include",
+        "/usr/include/x86_64-linux-gnu",
+        "/usr/include/aarch64-linux-gnu",
+    ]
+    .iter()
+    .any(|dir| Path::new(dir).join(header).exists())
+}
+
+/// Known native dependencies in the resolved dependency tree
+pub fn detect(metadata: &CargoMetadata) -> Vec<&'static NativeDependency> {
+    let graph = metadata.dependency_graph(true);
+    let mut found: Vec<&'static NativeDependency> = graph
+        .nodes
+        .iter()
+        .filter_map(|node| NativeDependency::lookup(&node.name))
+        .collect();
+    found.sort_by_key(|dependency| dependency.crate_name);
+    found.dedup_by_key(|dependency| dependency.crate_name);
+    found
+}
+
+/// Check every native dependency of the project
+pub fn check(metadata: &CargoMetadata) -> Vec<NativeCheck> {
+    detect(metadata)
+        .into_iter()
+        .map(|dependency| NativeCheck {
+            dependency,
+            found: dependency.is_satisfied(),
+        })
+        .collect()
+}
+
+/// Command line that installs `packages` with the system package manager
+pub fn install_command(system_info: &SystemInfo, packages: &[&str]) -> Option<Vec<String>> {
+    let prefix: &[&str] = match system_info.get_package_manager()? {
+        "apt" => &["sudo", "apt-get", "install", "-y"],
+        "yum" => &["sudo", "yum", "install", "-y"],
+        "pacman" => &["sudo", "pacman", "-S", "--noconfirm"],
+        "brew" => &["brew", "install"],
+        _ => return None,
+    };
+    if packages.is_empty() {
+        return None;
+    }
+    Some(
+        prefix
+            .iter()
+            .chain(packages)
+            .map(|part| part.to_string())
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_dependencies_are_unique_and_complete() {
+        for (index, dependency) in KNOWN_NATIVE_DEPENDENCIES.iter().enumerate() {
+            assert!(
+                KNOWN_NATIVE_DEPENDENCIES[index + 1..]
+                    .iter()
+                    .all(|other| other.crate_name != dependency.crate_name),
+                "duplicate entry for {}",
+                dependency.crate_name
+            );
+            assert!(dependency.package_for("apt").is_some());
+        }
+
+        let openssl = NativeDependency::lookup("openssl-sys").unwrap();
+        assert_eq!(openssl.requirement_name(), "openssl");
+        assert_eq!(openssl.package_for("brew"), Some("openssl@3"));
+        assert!(NativeDependency::lookup("serde").is_none());
+    }
+}
@@ -0,0 +1,181 @@
+//! Nix devShell generation for `atlas init --nix`
+
+use crate::bundle::parse_version;
+use crate::commands::tools::is_installable;
+use crate::config::OptimizerConfig;
+use crate::system::SystemInfo;
+use std::path::Path;
+
+/// File holding the Atlas devShell, imported from `flake.nix` or `shell.nix`
+pub const DEVSHELL_FILE: &str = "atlas.nix";
+
+/// First line of every file Atlas generates here
+pub const HEADER: &str = "# Generated by Atlas";
+
+/// nixpkgs branch the generated flake follows; `flake.lock` pins the commit
+const NIXPKGS_BRANCH: &str = "nixos-24.05";
+
+/// nixpkgs attributes for the tools the config asks for. Every tool
+/// `install-tools` knows is packaged in nixpkgs under the same name.
+pub fn tool_packages(config: &OptimizerConfig) -> Vec<String> {
+    let mut packages: Vec<String> = config
+        .tools
+        .preferred_tools
+        .iter()
+        .filter(|tool| is_installable(tool))
+        .cloned()
+        .collect();
+    if config.build.enable_sccache && !packages.iter().any(|tool| tool == "sccache") {
+        packages.insert(0, "sccache".to_string());
+    }
+    packages.retain(|tool| tool != "mold");
+    packages
+}
+
+/// The devShell as a function of `pkgs` and the Rust toolchain, so an existing
+/// flake can use it with its own toolchain
+pub fn devshell(config: &OptimizerConfig) -> String {
+    let packages: String = tool_packages(config)
+        .iter()
+        .map(|package| format!("    pkgs.{}\n", package))
+        .collect();
+    let linker = if config.build.use_fast_linker {
+        "  ] ++ pkgs.lib.optionals pkgs.stdenv.isLinux [\n    pkgs.clang\n    pkgs.mold\n"
+    } else {
+        ""
+    };
+    let wrapper = if config.build.enable_sccache {
+        "\n  RUSTC_WRAPPER = \"sccache\";\n"
+    } else {
+        ""
+    };
+
+    format!(
+        r#"{}. Regenerate with `atlas init --nix --force`
+{{ pkgs, toolchain ? [ pkgs.cargo pkgs.rustc ] }}:
+pkgs.mkShell {{
+  packages = toolchain ++ [
+{}{}  ];
+{}}}
+"#,
+        HEADER, packages, linker, wrapper
+    )
+}
+
+/// Rust toolchain expression for the generated flake: the project's
+/// `rust-toolchain.toml` if it has one, else the detected rustc release
+fn toolchain_expression(project_root: &Path, system_info: &SystemInfo) -> String {
+    if project_root.join("rust-toolchain.toml").exists() {
+        return "pkgs.rust-bin.fromRustupToolchainFile ./rust-toolchain.toml".to_string();
+    }
+    match system_info.rust_version.as_deref().and_then(parse_version) {
+        Some(version) => format!("pkgs.rust-bin.stable.\"{}\".default", version),
+        None => "pkgs.rust-bin.stable.latest.default".to_string(),
+    }
+}
+
+/// A flake exposing the Atlas devShell as its default shell
+pub fn flake(project_root: &Path, system_info: &SystemInfo) -> String {
+    let name = project_root
+        .canonicalize()
+        .unwrap_or_else(|_| project_root.to_path_buf())
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "rust".to_string());
+
+    format!(
+        r#"{}
+{{
+  description = "{} development environment";
+
+  inputs = {{
+    nixpkgs.url = "github:NixOS/nixpkgs/{}";
+    flake-utils.url = "github:numtide/flake-utils";
+    rust-overlay = {{
+      url = "github:oxalica/rust-overlay";
+      inputs.nixpkgs.follows = "nixpkgs";
+    }};
+  }};
+
+  outputs = {{ nixpkgs, flake-utils, rust-overlay, ... }}:
+    flake-utils.lib.eachDefaultSystem (system:
+      let
+        pkgs = import nixpkgs {{
+          inherit system;
+          overlays = [ rust-overlay.overlays.default ];
+        }};
+        toolchain = {};
+      in
+      {{
+        devShells.default = import ./{} {{
+          inherit pkgs;
+          toolchain = [ toolchain ];
+        }};
+      }});
+}}
+"#,
+        HEADER,
+        name,
+        NIXPKGS_BRANCH,
+        toolchain_expression(project_root, system_info),
+        DEVSHELL_FILE
+    )
+}
+
+/// Line that adds the Atlas devShell to an existing flake's outputs
+pub fn flake_devshell_line() -> String {
+    format!(
+        "devShells.${{system}}.atlas = import ./{} {{ inherit pkgs; }};",
+        DEVSHELL_FILE
+    )
+}
+
+/// Whether `content` already imports the Atlas devShell
+pub fn imports_devshell(content: &str) -> bool {
+    content.contains(&format!("./{}", DEVSHELL_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::{Architecture, OperatingSystem};
+
+    #[test]
+    fn test_devshell_follows_config() {
+        let mut config = OptimizerConfig::default();
+        let shell = devshell(&config);
+        assert!(shell.contains("    pkgs.sccache\n    pkgs.cargo-nextest\n"));
+        assert!(shell.contains("pkgs.lib.optionals pkgs.stdenv.isLinux"));
+        assert!(shell.contains("RUSTC_WRAPPER = \"sccache\";"));
+
+        config.build.enable_sccache = false;
+        config.build.use_fast_linker = false;
+        config.tools.preferred_tools = vec!["cargo-watch".to_string(), "gcc".to_string()];
+        let shell = devshell(&config);
+        assert!(shell.contains("toolchain ++ [\n    pkgs.cargo-watch\n  ];\n}\n"));
+        assert!(!shell.contains("sccache"));
+    }
+
+    #[test]
+    fn test_flake_pins_toolchain() {
+        let dir = tempfile::tempdir().unwrap();
+        let system_info = SystemInfo {
+            os: OperatingSystem::Linux,
+            arch: Architecture::X86_64,
+            cpu_cores: 8,
+            rust_version: Some("rustc 1.79.0 (129f3b996 2024-06-10)".to_string()),
+            cargo_version: None,
+            rust_llvm_version: None,
+            linux_package_manager: None,
+            available_tools: Default::default(),
+            rustup_components: None,
+            power: Default::default(),
+        };
+        let flake_nix = flake(dir.path(), &system_info);
+        assert!(flake_nix.contains("toolchain = pkgs.rust-bin.stable.\"1.79.0\".default;"));
+        assert!(imports_devshell(&flake_nix));
+
+        std::fs::write(dir.path().join("rust-toolchain.toml"), "").unwrap();
+        assert!(flake(dir.path(), &system_info).contains("fromRustupToolchainFile"));
+    }
+}
@@ -0,0 +1,168 @@
+//! First-run onboarding: environment summary, telemetry consent and a suggested next command
+
+use crate::config::OptimizerConfig;
+use crate::error::OptimizerResult;
+use crate::managed::{generated_blocks, Stamp};
+use crate::style;
+use crate::system::SystemInfo;
+use crate::utils::{confirm, find_rust_project_root, is_rust_project, print_success};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Recorded once onboarding has run, next to the Atlas config
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OnboardingState {
+    pub completed_at: DateTime<Utc>,
+    /// Atlas version that ran the onboarding
+    pub atlas_version: String,
+    /// Whether the user agreed to share anonymous usage statistics
+    #[serde(default)]
+    pub telemetry: bool,
+}
+
+impl OnboardingState {
+    pub fn new(telemetry: bool) -> Self {
+        Self {
+            completed_at: Utc::now(),
+            atlas_version: env!("CARGO_PKG_VERSION").to_string(),
+            telemetry,
+        }
+    }
+
+    pub fn path() -> OptimizerResult<PathBuf> {
+        Ok(OptimizerConfig::get_config_path()?.with_file_name("onboarding.toml"))
+    }
+
+    /// The recorded state, or `None` before the first run
+    pub fn load() -> OptimizerResult<Option<Self>> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(toml::from_str(&fs::read_to_string(path)?)?))
+    }
+
+    pub fn save(&self) -> OptimizerResult<()> {
+        let path = Self::path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Greet a first-time user: summarize the environment, ask for telemetry
+/// consent (off unless they agree) and suggest what to run in `dir`
+pub fn run(dir: &Path) -> OptimizerResult<OnboardingState> {
+    println!("{}", style::topic("👋 Welcome to Atlas"));
+    let system_info = SystemInfo::detect()?;
+    println!(
+        "   • {} {} with {} CPU cores",
+        system_info.os, system_info.arch, system_info.cpu_cores
+    );
+    println!(
+        "   • {}",
+        system_info
+            .rust_version
+            .as_deref()
+            .unwrap_or("No Rust toolchain found; install one from https://rustup.rs")
+    );
+    let installed: Vec<String> = system_info
+        .available_tools
+        .all()
+        .into_iter()
+        .filter(|tool| tool.is_installed)
+        .map(|tool| tool.name)
+        .collect();
+    println!(
+        "   • Build tools: {}",
+        if installed.is_empty() {
+            "none yet".to_string()
+        } else {
+            installed.join(", ")
+        }
+    );
+    println!();
+
+    println!("Atlas can share anonymous statistics about which optimizations help, to tune its defaults.");
+    println!(
+        "Nothing is shared unless you agree; change it any time with {}",
+        style::code("atlas telemetry on|off")
+    );
+    let state = OnboardingState::new(confirm("Share anonymous usage statistics?")?);
+    state.save()?;
+    print_success(if state.telemetry {
+        "Thanks! Usage statistics are enabled"
+    } else {
+        "Usage statistics stay off"
+    });
+
+    let (command, reason) = suggest_next_command(dir);
+    println!();
+    println!("💡 {}: {}", reason, style::good(command));
+    println!();
+    Ok(state)
+}
+
+/// The most useful command to run next in `dir`, and why
+pub fn suggest_next_command(dir: &Path) -> (&'static str, &'static str) {
+    let Some(project_root) = find_rust_project_root(dir)
+        .ok()
+        .filter(|root| is_rust_project(root))
+    else {
+        return (
+            "atlas install-tools",
+            "Install the build tools Atlas uses, then run `atlas init` inside a Rust project",
+        );
+    };
+
+    let blocks: Vec<_> = generated_blocks(&project_root)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|(path, _)| path.starts_with(&project_root))
+        .collect();
+    if blocks.is_empty() {
+        return ("atlas init", "This project has no Atlas configuration yet");
+    }
+
+    let stale = OptimizerConfig::load_or_default()
+        .and_then(|config| Stamp::current(&config))
+        .map(|current| {
+            blocks
+                .iter()
+                .any(|(_, block)| block.staleness(&current).is_some())
+        })
+        .unwrap_or(false);
+    if stale {
+        (
+            "atlas refresh",
+            "The generated configuration is older than your Atlas setup",
+        )
+    } else {
+        (
+            "atlas build check",
+            "Atlas is set up here; try an optimized check",
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggest_next_command() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(suggest_next_command(dir.path()).0, "atlas install-tools");
+
+        fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        assert_eq!(suggest_next_command(dir.path()).0, "atlas init");
+    }
+}
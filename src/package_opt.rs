@@ -0,0 +1,173 @@
+//! `opt-level` overrides for single dependencies in the dev profile
+//!
+//! Dependencies are compiled once and then come from the target directory,
+//! while their code runs every time the debug build does. For crates that
+//! decode images, match regexes, compress or hash, unoptimized code can be
+//! ten times slower, so `[profile.dev.package.<crate>] opt-level = 3` costs a
+//! longer clean build and makes every run faster. The overrides are edited
+//! in the workspace's `Cargo.toml` and leave the rest of it as it was.
+
+use crate::analysis::profiling::BuildProfile;
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::manifest::Manifest;
+
+/// Table holding the per-package settings of the dev profile
+const PACKAGES: &str = "profile.dev.package";
+
+/// Crates whose unoptimized code is slow enough to notice at runtime, and why
+const RUNTIME_HEAVY: &[(&str, &str)] = &[
+    ("image", "image decoding"),
+    ("png", "image decoding"),
+    ("jpeg-decoder", "image decoding"),
+    ("zune-jpeg", "image decoding"),
+    ("gif", "image decoding"),
+    ("regex", "regex matching"),
+    ("regex-automata", "regex matching"),
+    ("fancy-regex", "regex matching"),
+    ("flate2", "compression"),
+    ("miniz_oxide", "compression"),
+    ("zstd", "compression"),
+    ("brotli", "compression"),
+    ("lz4_flex", "compression"),
+    ("sha2", "hashing"),
+    ("blake3", "hashing"),
+    ("ring", "cryptography"),
+    ("aes-gcm", "cryptography"),
+    ("chacha20poly1305", "cryptography"),
+    ("serde_json", "JSON parsing"),
+    ("simd-json", "JSON parsing"),
+    ("nalgebra", "linear algebra"),
+    ("glam", "linear algebra"),
+    ("ndarray", "numeric arrays"),
+];
+
+/// A dependency that would likely run faster optimized
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub name: String,
+    /// What the crate spends its time on
+    pub reason: &'static str,
+    /// Compile time in the last profiled build, if it was profiled
+    pub compile_secs: Option<f64>,
+}
+
+/// `level` as a TOML value: `0` to `3`, `"s"` or `"z"`
+pub fn level_literal(level: &str) -> OptimizerResult<String> {
+    match level.trim_matches('"') {
+        level @ ("0" | "1" | "2" | "3") => Ok(level.to_string()),
+        level @ ("s" | "z") => Ok(format!("\"{}\"", level)),
+        other => Err(OptimizerError::invalid_input(format!(
+            "'{}' is not an opt-level; use 0, 1, 2, 3, s or z",
+            other
+        ))),
+    }
+}
+
+/// Dependencies with an `opt-level` override, as (crate, level); `*` is the
+/// default for all dependencies
+pub fn overrides(manifest: &Manifest) -> Vec<(String, String)> {
+    manifest
+        .child_tables(PACKAGES)
+        .into_iter()
+        .filter_map(|name| {
+            let level = manifest.value(&table(&name), "opt-level")?;
+            Some((name, level))
+        })
+        .collect()
+}
+
+/// Set the `opt-level` of `name`; false when an Atlas block owns the setting
+pub fn set(manifest: &mut Manifest, name: &str, level: &str) -> OptimizerResult<bool> {
+    manifest.set_key(&table(name), "opt-level", &level_literal(level)?)
+}
+
+/// Remove the `opt-level` override of `name`; false when there is none
+/// outside an Atlas block
+pub fn remove(manifest: &mut Manifest, name: &str) -> bool {
+    manifest.remove_key(&table(name), "opt-level")
+}
+
+/// Whether an Atlas block sets the `opt-level` of `name`
+pub fn is_managed(manifest: &Manifest, name: &str) -> bool {
+    manifest.contains_key(&table(name), "opt-level")
+        && manifest.is_managed(&table(name), "opt-level")
+}
+
+fn table(name: &str) -> String {
+    format!("{}.{}", PACKAGES, name)
+}
+
+/// Runtime-heavy crates among `dependencies` without an override yet. With
+/// a profiled build, the cheapest to compile come first, since optimizing
+/// them costs the least clean-build time.
+pub fn suggest(
+    dependencies: &[String],
+    configured: &[String],
+    profile: Option<&BuildProfile>,
+) -> Vec<Suggestion> {
+    let mut suggestions: Vec<Suggestion> = RUNTIME_HEAVY
+        .iter()
+        .filter(|(name, _)| dependencies.iter().any(|dependency| dependency == name))
+        .filter(|(name, _)| !configured.iter().any(|configured| configured == name))
+        .map(|(name, reason)| Suggestion {
+            name: name.to_string(),
+            reason,
+            compile_secs: profile.and_then(|profile| {
+                profile
+                    .crates
+                    .iter()
+                    .find(|time| time.name == *name)
+                    .map(|time| time.total_secs)
+            }),
+        })
+        .collect();
+    suggestions.sort_by(|a, b| match (a.compile_secs, b.compile_secs) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::timings::TimingReport;
+
+    #[test]
+    fn test_overrides_and_suggestions() {
+        let mut manifest = Manifest::parse(
+            "[package]\nname = \"app\"\n\n[profile.dev.package.\"*\"]\nopt-level = 1\n",
+        )
+        .unwrap();
+        assert!(set(&mut manifest, "regex", "3").unwrap());
+        assert!(set(&mut manifest, "png", "s").unwrap());
+        assert!(set(&mut manifest, "png", "fast").is_err());
+        assert_eq!(
+            overrides(&manifest),
+            [
+                ("*".to_string(), "1".to_string()),
+                ("regex".to_string(), "3".to_string()),
+                ("png".to_string(), "\"s\"".to_string())
+            ]
+        );
+        assert!(remove(&mut manifest, "png"));
+        assert!(!manifest.to_string().contains("png"));
+
+        let report = TimingReport::parse_json_lines(
+            r#"{"reason":"timing-info","package_id":"registry+https://github.com/rust-lang/crates.io-index#image@0.25.0","target":{"name":"image"},"mode":"build","duration":9.0}
+{"reason":"timing-info","package_id":"registry+https://github.com/rust-lang/crates.io-index#sha2@0.10.0","target":{"name":"sha2"},"mode":"build","duration":1.0}"#,
+        );
+        let profile = BuildProfile::from_report(&report);
+        let dependencies: Vec<String> = ["serde", "regex", "image", "sha2", "flate2"]
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        let suggestions = suggest(&dependencies, &["regex".to_string()], Some(&profile));
+        let names: Vec<&str> = suggestions.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["sha2", "image", "flate2"]);
+        assert_eq!(suggestions[1].compile_secs, Some(9.0));
+        assert_eq!(suggestions[1].reason, "image decoding");
+    }
+}
@@ -0,0 +1,247 @@
+//! Battery and power-profile awareness
+//!
+//! On a laptop running on battery, a build that uses every core drains it in
+//! minutes and makes the machine throttle anyway. When `[power]` allows it,
+//! builds then run with fewer jobs and background builds are skipped.
+
+use crate::config::default_true;
+use crate::host::{CommandRunner, Host, SystemProbe};
+use crate::system::OperatingSystem;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+const JOBS_VAR: &str = "CARGO_BUILD_JOBS";
+
+/// How the machine is powered, as far as the platform reports it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PowerState {
+    /// `None` on machines without a battery or where it cannot be read
+    pub on_battery: Option<bool>,
+    /// Battery charge in percent
+    pub charge: Option<u8>,
+    /// macOS Low Power Mode, or the power-saver profile on Linux
+    pub low_power_mode: bool,
+}
+
+impl PowerState {
+    pub fn detect(os: &OperatingSystem, runner: &dyn CommandRunner) -> Self {
+        match os {
+            OperatingSystem::Linux => {
+                let mut state = Self::from_sysfs(Path::new("/sys/class/power_supply"));
+                state.low_power_mode = runner
+                    .output("powerprofilesctl", &["get"])
+                    .is_some_and(|profile| profile.trim() == "power-saver");
+                state
+            }
+            OperatingSystem::MacOS => {
+                let mut state = runner
+                    .output("pmset", &["-g", "batt"])
+                    .map(|output| Self::from_pmset_batt(&output))
+                    .unwrap_or_default();
+                state.low_power_mode = runner
+                    .output("pmset", &["-g"])
+                    .is_some_and(|output| low_power_mode(&output));
+                state
+            }
+            _ => Self::default(),
+        }
+    }
+
+    /// Read `/sys/class/power_supply`: on battery when a battery discharges
+    /// and no mains supply is online
+    pub fn from_sysfs(dir: &Path) -> Self {
+        let read = |supply: &Path, file: &str| {
+            fs::read_to_string(supply.join(file))
+                .map(|value| value.trim().to_string())
+                .unwrap_or_default()
+        };
+        let mut state = Self::default();
+        let mut mains_online = false;
+        let mut discharging = false;
+        for supply in fs::read_dir(dir).into_iter().flatten().flatten() {
+            let supply = supply.path();
+            match read(&supply, "type").as_str() {
+                "Mains" => mains_online |= read(&supply, "online") == "1",
+                // Peripherals such as mice report their batteries with a scope
+                "Battery" if read(&supply, "scope") != "Device" => {
+                    discharging |= read(&supply, "status") == "Discharging";
+                    state.charge = state.charge.or(read(&supply, "capacity").parse().ok());
+                    state.on_battery = Some(false);
+                }
+                _ => {}
+            }
+        }
+        if state.on_battery.is_some() {
+            state.on_battery = Some(discharging && !mains_online);
+        }
+        state
+    }
+
+    /// Parse `pmset -g batt`, whose first line names the power source
+    pub fn from_pmset_batt(output: &str) -> Self {
+        let Some(source) = output.lines().next() else {
+            return Self::default();
+        };
+        let charge = output.split_whitespace().find_map(|word| {
+            word.strip_suffix("%;")
+                .and_then(|percent| percent.parse().ok())
+        });
+        Self {
+            on_battery: charge.map(|_| source.contains("'Battery Power'")),
+            charge,
+            low_power_mode: false,
+        }
+    }
+
+    /// Whether builds should save power
+    pub fn saving(&self, config: &PowerConfig) -> bool {
+        config.enabled
+            && (self.on_battery == Some(true)
+                || (config.low_power_mode_as_battery && self.low_power_mode))
+    }
+}
+
+/// `lowpowermode 1` in `pmset -g`
+fn low_power_mode(output: &str) -> bool {
+    output.lines().any(|line| {
+        let mut fields = line.split_whitespace();
+        fields.next() == Some("lowpowermode") && fields.next() == Some("1")
+    })
+}
+
+impl fmt::Display for PowerState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.on_battery {
+            Some(true) => write!(f, "battery")?,
+            Some(false) => write!(f, "AC")?,
+            None => write!(f, "no battery found")?,
+        }
+        if let Some(charge) = self.charge {
+            write!(f, ", {}%", charge)?;
+        }
+        if self.low_power_mode {
+            write!(f, ", low power mode")?;
+        }
+        Ok(())
+    }
+}
+
+/// The `[power]` section of the Atlas config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerConfig {
+    /// Adapt builds to the power source at all
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Parallel jobs on battery; half the cores when unset
+    #[serde(default)]
+    pub battery_jobs: Option<usize>,
+    /// Skip `--background` builds on battery
+    #[serde(default = "default_true")]
+    pub skip_background_on_battery: bool,
+    /// Treat Low Power Mode or the power-saver profile like battery power
+    #[serde(default = "default_true")]
+    pub low_power_mode_as_battery: bool,
+}
+
+impl Default for PowerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            battery_jobs: None,
+            skip_background_on_battery: true,
+            low_power_mode_as_battery: true,
+        }
+    }
+}
+
+/// Save power in the builds this process starts, if the power source calls
+/// for it: they run with fewer jobs unless `CARGO_BUILD_JOBS` chooses them.
+/// Returns the power state and the jobs set when saving.
+pub fn enter(config: &PowerConfig) -> Option<(PowerState, Option<usize>)> {
+    if !config.enabled {
+        return None;
+    }
+    let state = PowerState::detect(&Host.os(), &Host);
+    if !state.saving(config) {
+        return None;
+    }
+    let jobs = std::env::var_os(JOBS_VAR).is_none().then(|| {
+        let cores = std::thread::available_parallelism().map_or(1, |cores| cores.get());
+        let jobs = config.jobs(cores);
+        std::env::set_var(JOBS_VAR, jobs.to_string());
+        jobs
+    });
+    Some((state, jobs))
+}
+
+impl PowerConfig {
+    /// Parallel jobs while saving power
+    pub fn jobs(&self, cores: usize) -> usize {
+        let cores = cores.max(1);
+        self.battery_jobs.unwrap_or(cores / 2).clamp(1, cores)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_sysfs() {
+        let dir = tempfile::tempdir().unwrap();
+        let supply = |name: &str, files: &[(&str, &str)]| {
+            let path = dir.path().join(name);
+            fs::create_dir(&path).unwrap();
+            for (file, value) in files {
+                fs::write(path.join(file), format!("{}\n", value)).unwrap();
+            }
+        };
+        supply("AC", &[("type", "Mains"), ("online", "0")]);
+        supply(
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("status", "Discharging"),
+                ("capacity", "64"),
+            ],
+        );
+        supply(
+            "hidpp_battery_0",
+            &[
+                ("type", "Battery"),
+                ("scope", "Device"),
+                ("status", "Discharging"),
+            ],
+        );
+        let state = PowerState::from_sysfs(dir.path());
+        assert_eq!(state.on_battery, Some(true));
+        assert_eq!(state.charge, Some(64));
+        assert!(state.saving(&PowerConfig::default()));
+
+        fs::write(dir.path().join("AC").join("online"), "1\n").unwrap();
+        assert_eq!(PowerState::from_sysfs(dir.path()).on_battery, Some(false));
+        assert_eq!(
+            PowerState::from_sysfs(&dir.path().join("none")).on_battery,
+            None
+        );
+    }
+
+    #[test]
+    fn test_from_pmset() {
+        let battery = "Now drawing from 'Battery Power'\n -InternalBattery-0 (id=4653155)\t87%; discharging; 5:12 remaining present: true\n";
+        let state = PowerState::from_pmset_batt(battery);
+        assert_eq!(state.on_battery, Some(true));
+        assert_eq!(state.charge, Some(87));
+
+        let desktop = "Now drawing from 'AC Power'\n";
+        assert_eq!(PowerState::from_pmset_batt(desktop).on_battery, None);
+
+        assert!(low_power_mode(
+            " sleep                1\n lowpowermode         1\n"
+        ));
+        assert!(!low_power_mode(" lowpowermode         0\n"));
+        assert_eq!(PowerConfig::default().jobs(10), 5);
+    }
+}
@@ -0,0 +1,183 @@
+//! Checks before a command starts changing things
+//!
+//! `atlas init` writes several files and may install tools with `sudo`. When
+//! the third file turns out to be read-only, the first two are already
+//! rewritten and the project is half configured. Commands therefore list the
+//! paths they will write and the install commands they will run, and
+//! [`Preflight::check`] reports every problem at once before anything changes.
+
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::utils::cargo_home;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
+/// What a command is about to change
+#[derive(Debug, Default)]
+pub struct Preflight {
+    writes: Vec<PathBuf>,
+    installs: Vec<Vec<String>>,
+}
+
+impl Preflight {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A file that will be created or modified
+    pub fn write(&mut self, path: impl Into<PathBuf>) -> &mut Self {
+        self.writes.push(path.into());
+        self
+    }
+
+    /// Command lines that will install tools, as planned by
+    /// [`crate::commands::tools::planned_install_commands`]
+    pub fn installs(&mut self, commands: Vec<Vec<String>>) -> &mut Self {
+        if commands.iter().any(|line| is_cargo_install(line)) {
+            if let Some(home) = cargo_home() {
+                self.writes.push(home.join("bin"));
+            }
+        }
+        self.installs.extend(commands);
+        self
+    }
+
+    /// Everything that would make the command fail halfway
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems: Vec<String> = self
+            .writes
+            .iter()
+            .filter_map(|path| unwritable(path))
+            .collect();
+        problems.dedup();
+        problems.extend(self.sudo_problem());
+        problems
+    }
+
+    /// Fail with all problems before `action` changes anything
+    pub fn check(&self, action: &str) -> OptimizerResult<()> {
+        let problems = self.problems();
+        if problems.is_empty() {
+            return Ok(());
+        }
+        Err(OptimizerError::permission(format!(
+            "{} would fail, so nothing was changed:\n{}",
+            action,
+            problems
+                .iter()
+                .map(|problem| format!("  • {}", problem))
+                .collect::<Vec<_>>()
+                .join("\n")
+        )))
+    }
+
+    fn sudo_problem(&self) -> Option<String> {
+        let commands: Vec<String> = self
+            .installs
+            .iter()
+            .filter(|line| line.first().is_some_and(|program| program == "sudo"))
+            .map(|line| line[1..].join(" "))
+            .collect();
+        if commands.is_empty() {
+            return None;
+        }
+        let needed = format!("needed for `{}`", commands.join("`, `"));
+        if let Err(e) = crate::allowlist::check("sudo") {
+            return Some(format!("{} ({})", e, needed));
+        }
+        if which::which("sudo").is_err() {
+            return Some(format!("sudo is not installed ({})", needed));
+        }
+        // Without a terminal sudo cannot ask for a password
+        if !std::io::stdin().is_terminal() && !is_root() && !passwordless_sudo() {
+            return Some(format!(
+                "sudo needs a password but there is no terminal to ask for it ({}); \
+                 run the installs by hand or use --no-tools",
+                needed
+            ));
+        }
+        None
+    }
+}
+
+fn is_cargo_install(line: &[String]) -> bool {
+    line.len() > 1 && line[0] == "cargo" && line[1] == "install"
+}
+
+fn passwordless_sudo() -> bool {
+    crate::utils::execute_command("sudo", &["-n", "true"], None)
+        .is_ok_and(|output| output.status.success())
+}
+
+#[cfg(unix)]
+fn is_root() -> bool {
+    unsafe { libc::geteuid() == 0 }
+}
+
+#[cfg(not(unix))]
+fn is_root() -> bool {
+    false
+}
+
+/// Why `path` cannot be written, or created in its closest existing ancestor
+fn unwritable(path: &Path) -> Option<String> {
+    let existing = path.ancestors().find(|ancestor| ancestor.exists())?;
+    if existing != path && !existing.is_dir() {
+        return Some(format!(
+            "{} cannot be created: {} is not a directory",
+            path.display(),
+            existing.display()
+        ));
+    }
+    if is_writable(existing) {
+        return None;
+    }
+    Some(if existing == path {
+        format!("{} is not writable", path.display())
+    } else {
+        format!(
+            "{} cannot be created: {} is not writable",
+            path.display(),
+            existing.display()
+        )
+    })
+}
+
+#[cfg(unix)]
+fn is_writable(path: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+    let Ok(path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    unsafe { libc::access(path.as_ptr(), libc::W_OK) == 0 }
+}
+
+#[cfg(not(unix))]
+fn is_writable(path: &Path) -> bool {
+    std::fs::metadata(path).is_ok_and(|metadata| !metadata.permissions().readonly())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_problems_are_collected() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("Cargo.toml");
+        std::fs::write(&file, "[package]\n").unwrap();
+
+        let mut preflight = Preflight::new();
+        preflight
+            .write(&file)
+            .write(dir.path().join(".cargo").join("config.toml"))
+            .write(file.join("config.toml"))
+            .write(file.join("other.toml"));
+        let problems = preflight.problems();
+        assert_eq!(problems.len(), 2, "{:?}", problems);
+        assert!(problems[0].contains("is not a directory"));
+
+        let error = preflight.check("Initializing").unwrap_err().to_string();
+        assert!(error.contains("nothing was changed"), "{}", error);
+        assert!(error.contains("other.toml"), "{}", error);
+    }
+}
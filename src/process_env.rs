@@ -0,0 +1,150 @@
+//! Environment variables from `[env]` in the Atlas config
+//!
+//! Atlas sets them on every cargo and tool process it starts, so settings
+//! such as `CARGO_TERM_COLOR` or a registry token live in one place instead
+//! of shell profiles and CI scripts. As with Cargo's own `[env]`, a variable
+//! already set in the environment wins unless the entry sets `force`.
+//! Secrets are referenced rather than written out, see [`crate::credentials`].
+
+use crate::credentials::SecretRef;
+use crate::error::OptimizerResult;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::process::Command;
+use std::sync::OnceLock;
+
+static VARIABLES: OnceLock<BTreeMap<String, EnvValue>> = OnceLock::new();
+
+/// The variables once resolved, secrets read
+static EFFECTIVE: OnceLock<Vec<(String, String)>> = OnceLock::new();
+
+/// `NAME = "value"`, `NAME = { value = "value", force = true }` to override
+/// the environment Atlas runs in, or `NAME = { secret = "keychain:name" }`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum EnvValue {
+    Plain(String),
+    Detailed {
+        value: String,
+        #[serde(default)]
+        force: bool,
+    },
+    Secret {
+        secret: SecretRef,
+        #[serde(default)]
+        force: bool,
+    },
+}
+
+impl EnvValue {
+    /// The value as written in the config; `None` for secrets
+    pub fn literal(&self) -> Option<&str> {
+        match self {
+            Self::Plain(value) | Self::Detailed { value, .. } => Some(value),
+            Self::Secret { .. } => None,
+        }
+    }
+
+    pub fn resolve(&self) -> OptimizerResult<String> {
+        match self {
+            Self::Secret { secret, .. } => secret.resolve(),
+            _ => Ok(self.literal().unwrap_or_default().to_string()),
+        }
+    }
+
+    pub fn force(&self) -> bool {
+        matches!(
+            self,
+            Self::Detailed { force: true, .. } | Self::Secret { force: true, .. }
+        )
+    }
+}
+
+/// Set the variables on processes started from now on
+pub fn init(variables: BTreeMap<String, EnvValue>) {
+    let _ = VARIABLES.set(variables);
+}
+
+/// The configured variables and their values, secrets left out
+pub fn literals() -> Vec<(String, String)> {
+    VARIABLES
+        .get()
+        .into_iter()
+        .flatten()
+        .filter_map(|(name, value)| Some((name.clone(), value.literal()?.to_string())))
+        .collect()
+}
+
+/// Variables processes get on top of Atlas's own environment. Secrets are
+/// read the first time a process is started; one that cannot be read is
+/// left unset.
+pub fn variables() -> &'static [(String, String)] {
+    EFFECTIVE.get_or_init(|| {
+        let Some(variables) = VARIABLES.get() else {
+            return Vec::new();
+        };
+        effective(variables, |name| std::env::var_os(name).is_some())
+            .into_iter()
+            .filter_map(|(name, value)| match value.resolve() {
+                Ok(value) => Some((name.to_string(), value)),
+                Err(e) => {
+                    log::warn!("Not setting {} from [env]: {}", name, e);
+                    None
+                }
+            })
+            .collect()
+    })
+}
+
+fn effective(
+    variables: &BTreeMap<String, EnvValue>,
+    is_set: impl Fn(&str) -> bool,
+) -> Vec<(&str, &EnvValue)> {
+    variables
+        .iter()
+        .filter(|(name, value)| value.force() || !is_set(name))
+        .map(|(name, value)| (name.as_str(), value))
+        .collect()
+}
+
+pub fn apply(cmd: &mut Command) {
+    cmd.envs(variables().iter().map(|(name, value)| (name, value)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_environment_wins_unless_forced() {
+        let variables: BTreeMap<String, EnvValue> = toml::from_str(
+            r#"
+            CARGO_TERM_COLOR = "always"
+            RUST_LOG = "debug"
+            CARGO_INCREMENTAL = { value = "0", force = true }
+            CARGO_REGISTRIES_INTERNAL_TOKEN = { secret = "keychain:internal" }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            variables["CARGO_INCREMENTAL"],
+            EnvValue::Detailed {
+                value: "0".to_string(),
+                force: true
+            }
+        );
+
+        assert_eq!(variables["CARGO_REGISTRIES_INTERNAL_TOKEN"].literal(), None);
+
+        let set = [
+            "RUST_LOG",
+            "CARGO_INCREMENTAL",
+            "CARGO_REGISTRIES_INTERNAL_TOKEN",
+        ];
+        let names: Vec<&str> = effective(&variables, |name| set.contains(&name))
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, vec!["CARGO_INCREMENTAL", "CARGO_TERM_COLOR"]);
+    }
+}
@@ -0,0 +1,239 @@
+use crate::artifacts::ArtifactsConfig;
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::matrix::{self, MatrixEntry};
+use crate::release::ReleaseConfig;
+use crate::utils::split_command_line;
+use crate::watchdog::parse_timeout;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// File name of the per-project configuration, stored next to `Cargo.toml`
+pub const PROJECT_CONFIG_FILE: &str = "atlas.toml";
+
+/// Settings that belong to a project and are checked into its repository
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectConfig {
+    /// Named command sequences run with `atlas run <task>`
+    #[serde(default)]
+    pub tasks: BTreeMap<String, Task>,
+    /// Named sets of workspace member patterns, selected with `--members <group>`
+    #[serde(default)]
+    pub groups: BTreeMap<String, Vec<String>>,
+    /// Recommendation rule IDs to silence, e.g. `["ATL003"]`
+    #[serde(default)]
+    pub ignore_rules: Vec<String>,
+    /// Wall-clock limit per cargo command, e.g. `build = "30m"`
+    #[serde(default)]
+    pub timeouts: BTreeMap<String, String>,
+    /// Combinations built by `atlas build matrix`
+    #[serde(default)]
+    pub matrix: Vec<MatrixEntry>,
+    /// Settings for `atlas release`
+    #[serde(default)]
+    pub release: ReleaseConfig,
+    /// Where `atlas artifacts push` and `pull` keep archived builds
+    #[serde(default)]
+    pub artifacts: ArtifactsConfig,
+}
+
+/// A task is a single command, a list of commands, or a table with a description
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum Task {
+    Command(String),
+    Sequence(Vec<String>),
+    Detailed {
+        #[serde(default)]
+        description: Option<String>,
+        steps: Vec<String>,
+    },
+}
+
+impl Task {
+    pub fn steps(&self) -> Vec<&str> {
+        match self {
+            Self::Command(command) => vec![command.as_str()],
+            Self::Sequence(steps) | Self::Detailed { steps, .. } => {
+                steps.iter().map(String::as_str).collect()
+            }
+        }
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        match self {
+            Self::Detailed { description, .. } => description.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+impl ProjectConfig {
+    pub fn path(project_root: &Path) -> PathBuf {
+        project_root.join(PROJECT_CONFIG_FILE)
+    }
+
+    /// Load `atlas.toml`, or an empty config if the project has none
+    pub fn load(project_root: &Path) -> OptimizerResult<Self> {
+        let path = Self::path(project_root);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let config: Self = toml::from_str(&fs::read_to_string(&path)?)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> OptimizerResult<()> {
+        for (name, patterns) in &self.groups {
+            if patterns.is_empty() {
+                return Err(OptimizerError::config(format!(
+                    "Member group '{}' has no patterns",
+                    name
+                )));
+            }
+        }
+
+        for (command, value) in &self.timeouts {
+            if parse_timeout(value).is_none() {
+                return Err(OptimizerError::config(format!(
+                    "Timeout for '{}' must look like \"90s\", \"30m\" or \"2h\", got \"{}\"",
+                    command, value
+                )));
+            }
+        }
+
+        matrix::validate(&self.matrix)?;
+        self.release.validate()?;
+
+        for (name, task) in &self.tasks {
+            let steps = task.steps();
+            if steps.is_empty() {
+                return Err(OptimizerError::config(format!(
+                    "Task '{}' has no steps",
+                    name
+                )));
+            }
+            for step in steps {
+                if split_command_line(step)?.is_empty() {
+                    return Err(OptimizerError::config(format!(
+                        "Task '{}' has an empty step",
+                        name
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Timeout for a cargo subcommand such as `build` or `test`
+    pub fn timeout(&self, command: &str) -> Option<Duration> {
+        self.timeouts
+            .get(command)
+            .and_then(|value| parse_timeout(value))
+    }
+
+    pub fn task(&self, name: &str) -> OptimizerResult<&Task> {
+        self.tasks.get(name).ok_or_else(|| {
+            OptimizerError::invalid_input(format!(
+                "Unknown task '{}'. Available tasks: {}",
+                name,
+                if self.tasks.is_empty() {
+                    "none".to_string()
+                } else {
+                    self.tasks.keys().cloned().collect::<Vec<_>>().join(", ")
+                }
+            ))
+        })
+    }
+}
+
+/// Starter `atlas.toml` written by `atlas init`
+pub fn default_project_config() -> &'static str {
+    r#"# Atlas project configuration
+
+# Recommendations to silence; list them with `atlas doctor --rules`
+# ignore_rules = ["ATL004"]
+
+# Kill cargo commands that hang, e.g. on flaky CI runners
+# [timeouts]
+# build = "30m"
+# test = "1h"
+
+# Run with `atlas run <task>`
+[tasks]
+check = "cargo check --workspace --all-targets"
+build = "cargo build --workspace"
+test = "cargo test --workspace"
+release = "cargo build --workspace --release"
+
+[tasks.ci]
+description = "Format, lint and test like CI does"
+steps = [
+    "atlas --quiet build fmt --check",
+    "cargo clippy --workspace --all-targets -- -D warnings",
+    "cargo test --workspace",
+]
+
+# Named sets of workspace members for `--members`, e.g. `atlas build test --members backend`
+# [groups]
+# backend = ["api-*", "db"]
+
+# Combinations built in turn by `atlas build matrix`
+# [[matrix]]
+# target = "x86_64-unknown-linux-musl"
+# profile = "release"
+#
+# [[matrix]]
+# name = "no-default-features"
+# command = "check"
+# no_default_features = true
+
+# Targets built and packaged by `atlas release`, and steps it never runs
+# [release]
+# targets = ["x86_64-unknown-linux-gnu", "aarch64-apple-darwin"]
+# skip = ["audit"]
+# archive = "tar.gz"
+# symbols_dir = "symbols"
+
+# Archived dependency builds for cold CI runners: `atlas artifacts push` / `pull`
+# [artifacts]
+# store = "s3://ci-cache/atlas"   # or gs://bucket/prefix, or a directory
+# profiles = ["dev"]
+"#
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_forms() {
+        let config: ProjectConfig = toml::from_str(default_project_config()).unwrap();
+        config.validate().unwrap();
+
+        assert_eq!(
+            config.task("build").unwrap().steps(),
+            vec!["cargo build --workspace"]
+        );
+        let ci = config.task("ci").unwrap();
+        assert_eq!(ci.steps().len(), 3);
+        assert_eq!(ci.description(), Some("Format, lint and test like CI does"));
+        assert!(config.task("deploy").is_err());
+    }
+
+    #[test]
+    fn test_empty_task_is_rejected() {
+        let config: ProjectConfig = toml::from_str("[tasks]\nnothing = []\n").unwrap();
+        assert!(config.validate().is_err());
+
+        let config: ProjectConfig = toml::from_str("[groups]\nbackend = []\n").unwrap();
+        assert!(config.validate().is_err());
+
+        let config: ProjectConfig = toml::from_str("[timeouts]\nbuild = \"soon\"\n").unwrap();
+        assert!(config.validate().is_err());
+    }
+}
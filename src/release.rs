@@ -0,0 +1,385 @@
+//! The pre-release gauntlet run by `atlas release`: its steps, cheapest first
+//! so mistakes surface before the long release builds, the record of passed
+//! steps that lets a failed run resume, and the changelog stub it ends with.
+
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::utils::create_parent_dirs;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    Fmt,
+    Audit,
+    Clippy,
+    Test,
+    Doc,
+    Build,
+    Sizes,
+    Package,
+    Changelog,
+}
+
+/// Every step, in the order they run
+pub const STEPS: &[Step] = &[
+    Step::Fmt,
+    Step::Audit,
+    Step::Clippy,
+    Step::Test,
+    Step::Doc,
+    Step::Build,
+    Step::Sizes,
+    Step::Package,
+    Step::Changelog,
+];
+
+impl Step {
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::Fmt => "fmt",
+            Self::Audit => "audit",
+            Self::Clippy => "clippy",
+            Self::Test => "test",
+            Self::Doc => "doc",
+            Self::Build => "build",
+            Self::Sizes => "sizes",
+            Self::Package => "package",
+            Self::Changelog => "changelog",
+        }
+    }
+
+    pub fn parse(id: &str) -> Option<Self> {
+        STEPS.iter().copied().find(|step| step.id() == id)
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Fmt => "Formatting check",
+            Self::Audit => "Security advisories",
+            Self::Clippy => "Clippy with warnings denied",
+            Self::Test => "Tests",
+            Self::Doc => "Documentation build",
+            Self::Build => "Release builds",
+            Self::Sizes => "Binary size report",
+            Self::Package => "Release artifacts and checksums",
+            Self::Changelog => "Changelog stub",
+        }
+    }
+}
+
+/// `[release]` in atlas.toml
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReleaseConfig {
+    /// Target triples to build in release mode; the host when empty
+    #[serde(default)]
+    pub targets: Vec<String>,
+    /// Step IDs never run, e.g. `["audit"]`
+    #[serde(default)]
+    pub skip: Vec<String>,
+    /// Where artifacts are laid out for upload; `<target-dir>/dist` when missing
+    #[serde(default)]
+    pub dist_dir: Option<PathBuf>,
+    /// Strip symbols from the packaged copies of the binaries
+    #[serde(default = "default_strip")]
+    pub strip: bool,
+    /// Compress the packaged binaries with upx when it is installed
+    #[serde(default)]
+    pub upx: bool,
+    #[serde(default)]
+    pub archive: ArchiveFormat,
+    /// Keep debug symbols of the release binaries here, by version and target,
+    /// so crashes in stripped binaries can still be symbolized
+    #[serde(default)]
+    pub symbols_dir: Option<PathBuf>,
+}
+
+impl Default for ReleaseConfig {
+    fn default() -> Self {
+        Self {
+            targets: Vec::new(),
+            skip: Vec::new(),
+            dist_dir: None,
+            strip: default_strip(),
+            upx: false,
+            archive: ArchiveFormat::default(),
+            symbols_dir: None,
+        }
+    }
+}
+
+impl ReleaseConfig {
+    pub fn validate(&self) -> OptimizerResult<()> {
+        validate_step_ids(&self.skip)
+    }
+}
+
+fn default_strip() -> bool {
+    true
+}
+
+/// How each packaged binary is wrapped
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArchiveFormat {
+    #[default]
+    #[serde(rename = "tar.gz")]
+    TarGz,
+    #[serde(rename = "zip")]
+    Zip,
+    /// The bare binary, renamed to carry version and target
+    #[serde(rename = "none")]
+    None,
+}
+
+impl ArchiveFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::TarGz => ".tar.gz",
+            Self::Zip => ".zip",
+            Self::None => "",
+        }
+    }
+}
+
+/// File name of the artifact for binary `name` built for `target`, e.g.
+/// `atlas-1.2.0-x86_64-unknown-linux-gnu.tar.gz`
+pub fn artifact_name(name: &str, version: &str, target: &str, archive: ArchiveFormat) -> String {
+    let exe = if archive == ArchiveFormat::None && target.contains("windows") {
+        ".exe"
+    } else {
+        ""
+    };
+    format!(
+        "{}-{}-{}{}{}",
+        name,
+        version,
+        target,
+        exe,
+        archive.extension()
+    )
+}
+
+/// Where a target's toolchain leaves debug symbols
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolFormat {
+    /// A `.dSYM` bundle next to the binary
+    Dsym,
+    /// A `.pdb` file next to the executable
+    Pdb,
+    /// DWARF inside the binary, split out into a `.debug` file with objcopy
+    Dwarf,
+}
+
+impl SymbolFormat {
+    pub fn for_target(target: &str) -> Self {
+        if target.contains("apple") {
+            Self::Dsym
+        } else if target.contains("windows-msvc") {
+            Self::Pdb
+        } else {
+            Self::Dwarf
+        }
+    }
+
+    /// File holding the symbols of `binary`; cargo names PDBs after the crate
+    pub fn file_name(self, binary: &str) -> String {
+        match self {
+            Self::Dsym => format!("{}.dSYM", binary),
+            Self::Pdb => format!("{}.pdb", binary.replace('-', "_")),
+            Self::Dwarf => format!("{}.debug", binary),
+        }
+    }
+}
+
+/// Cargo arguments that make a release build keep full debug info, packed
+/// next to the binary where the platform supports it
+pub fn symbol_build_args() -> Vec<&'static str> {
+    vec![
+        "--config=profile.release.debug=true",
+        "--config=profile.release.strip=false",
+        "--config=profile.release.split-debuginfo=\"packed\"",
+    ]
+}
+
+/// `SHA256SUMS` in the format `sha256sum --check` reads
+pub fn sha256sums(digests: &BTreeMap<String, String>) -> String {
+    digests
+        .iter()
+        .map(|(file, digest)| format!("{}  {}\n", digest, file))
+        .collect()
+}
+
+pub fn validate_step_ids(ids: &[String]) -> OptimizerResult<()> {
+    match ids.iter().find(|id| Step::parse(id).is_none()) {
+        Some(unknown) => Err(OptimizerError::config(format!(
+            "Unknown release step '{}'. Steps: {}",
+            unknown,
+            STEPS
+                .iter()
+                .map(|step| step.id())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Steps that passed in an unfinished run, so `--resume` can skip them
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReleaseState {
+    /// Commit the steps passed on; a different commit starts over
+    pub head: Option<String>,
+    /// Seconds each passed step took, by step ID
+    pub passed: BTreeMap<String, f64>,
+}
+
+impl ReleaseState {
+    pub fn path(project_root: &Path) -> PathBuf {
+        project_root.join(".atlas").join("release.json")
+    }
+
+    pub fn load(project_root: &Path) -> Option<Self> {
+        let content = fs::read_to_string(Self::path(project_root)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    pub fn save(&self, project_root: &Path) -> OptimizerResult<()> {
+        let path = Self::path(project_root);
+        create_parent_dirs(&path)?;
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn clear(project_root: &Path) {
+        let _ = fs::remove_file(Self::path(project_root));
+    }
+}
+
+/// A Keep a Changelog section for `version` from commit subjects, sorted
+/// into Added, Fixed, Removed and Changed by their first word
+pub fn changelog_stub(version: &str, date: &str, subjects: &[String]) -> String {
+    let mut sections: BTreeMap<usize, (&str, Vec<&str>)> = BTreeMap::new();
+    for subject in subjects {
+        let subject = subject.trim();
+        if subject.is_empty() || subject.starts_with("Merge ") {
+            continue;
+        }
+        let first_word = subject
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        let (order, heading) = match first_word.trim_end_matches(':') {
+            "add" | "adds" | "added" | "feat" | "new" => (0, "Added"),
+            "fix" | "fixes" | "fixed" => (2, "Fixed"),
+            "remove" | "removes" | "removed" | "drop" => (3, "Removed"),
+            _ => (1, "Changed"),
+        };
+        sections
+            .entry(order)
+            .or_insert((heading, Vec::new()))
+            .1
+            .push(subject);
+    }
+
+    let mut stub = format!("## [{}] - {}\n", version, date);
+    for (heading, entries) in sections.values() {
+        stub.push_str(&format!("\n### {}\n", heading));
+        for entry in entries {
+            stub.push_str(&format!("- {}\n", entry));
+        }
+    }
+    stub
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changelog_stub_groups_subjects() {
+        let subjects: Vec<String> = [
+            "Fix panic on empty workspace",
+            "Add build matrix",
+            "Merge pull request #12 from fork/branch",
+            "Speed up metadata parsing",
+            "feat: release command",
+            "Remove legacy flag",
+        ]
+        .iter()
+        .map(|subject| subject.to_string())
+        .collect();
+        assert_eq!(
+            changelog_stub("1.2.0", "2026-10-15", &subjects),
+            "## [1.2.0] - 2026-10-15\n\
+             \n### Added\n- Add build matrix\n- feat: release command\n\
+             \n### Changed\n- Speed up metadata parsing\n\
+             \n### Fixed\n- Fix panic on empty workspace\n\
+             \n### Removed\n- Remove legacy flag\n"
+        );
+    }
+
+    #[test]
+    fn test_step_ids() {
+        for step in STEPS {
+            assert_eq!(Step::parse(step.id()), Some(*step));
+        }
+        assert!(validate_step_ids(&["audit".to_string(), "doc".to_string()]).is_ok());
+        assert!(validate_step_ids(&["deploy".to_string()]).is_err());
+
+        let config: crate::project_config::ProjectConfig =
+            toml::from_str("[release]\nskip = [\"lint\"]\n").unwrap();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_artifact_layout() {
+        let config: crate::project_config::ProjectConfig =
+            toml::from_str("[release]\narchive = \"none\"\nupx = true\n").unwrap();
+        assert!(config.release.strip);
+        assert_eq!(config.release.archive, ArchiveFormat::None);
+        assert_eq!(
+            artifact_name(
+                "atlas",
+                "1.2.0",
+                "x86_64-pc-windows-msvc",
+                ArchiveFormat::None
+            ),
+            "atlas-1.2.0-x86_64-pc-windows-msvc.exe"
+        );
+        assert_eq!(
+            artifact_name(
+                "atlas",
+                "1.2.0",
+                "aarch64-apple-darwin",
+                ArchiveFormat::default()
+            ),
+            "atlas-1.2.0-aarch64-apple-darwin.tar.gz"
+        );
+
+        let digests = BTreeMap::from([
+            ("b.zip".to_string(), "22".to_string()),
+            ("a.tar.gz".to_string(), "11".to_string()),
+        ]);
+        assert_eq!(sha256sums(&digests), "11  a.tar.gz\n22  b.zip\n");
+    }
+
+    #[test]
+    fn test_symbol_formats() {
+        let cases = [
+            ("aarch64-apple-darwin", "my-app.dSYM"),
+            ("x86_64-pc-windows-msvc", "my_app.pdb"),
+            ("x86_64-pc-windows-gnu", "my-app.debug"),
+            ("x86_64-unknown-linux-gnu", "my-app.debug"),
+        ];
+        for (target, file_name) in cases {
+            assert_eq!(
+                SymbolFormat::for_target(target).file_name("my-app"),
+                file_name,
+                "{}",
+                target
+            );
+        }
+    }
+}
@@ -0,0 +1,281 @@
+//! Remote builds: the workspace is copied to a build machine with rsync,
+//! built there over SSH and the finished artifacts are copied back.
+//!
+//! Only what git would track is sent: rsync reads each directory's
+//! `.gitignore`, and `target/` and `.git/` are always left out. The remote
+//! copy is kept between runs, so later syncs and builds are incremental.
+//! Before a host's first use, [`HostProbe`] checks it can build like this
+//! machine does.
+
+use crate::config::RemoteHost;
+use crate::target_dirs::{profile_dir, profile_of};
+use crate::utils::{shell_quote, stable_hash};
+use std::path::{Path, PathBuf};
+
+/// A project and where it is built on the remote host
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteBuild {
+    /// SSH destination, e.g. `me@buildbox`
+    pub destination: String,
+    /// Directory of this project on the remote host
+    pub remote_dir: String,
+    pub project_root: PathBuf,
+    /// Parallel jobs for cargo, if the host sets them
+    pub jobs: Option<usize>,
+}
+
+impl RemoteBuild {
+    pub fn new(host: &RemoteHost, project_root: &Path) -> Self {
+        // Projects with the same directory name get their own remote copy
+        let name = project_root
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "project".to_string());
+        let hash = stable_hash(project_root.to_string_lossy().as_bytes());
+        let remote_dir = format!(
+            "{}/{}-{}",
+            host.path.as_deref().unwrap_or(".").trim_end_matches('/'),
+            name,
+            &hash[..8]
+        );
+        Self {
+            destination: host.destination(),
+            remote_dir,
+            project_root: project_root.to_path_buf(),
+            jobs: host.jobs,
+        }
+    }
+
+    /// `rsync` arguments copying the workspace to the remote host
+    pub fn upload_args(&self) -> Vec<String> {
+        vec![
+            "-az".to_string(),
+            "--delete".to_string(),
+            "--filter=:- .gitignore".to_string(),
+            "--exclude=/target/".to_string(),
+            "--exclude=/.git/".to_string(),
+            format!(
+                "--rsync-path=mkdir -p {} && rsync",
+                shell_quote(&self.remote_dir)
+            ),
+            format!("{}/", self.project_root.display()),
+            format!("{}:{}/", self.destination, self.remote_dir),
+        ]
+    }
+
+    /// Shell command run over SSH for `cargo <args>`, with the host's jobs
+    pub fn remote_command(&self, cargo_args: &[&str]) -> String {
+        let chooses_jobs = cargo_args
+            .iter()
+            .take_while(|arg| **arg != "--")
+            .any(|arg| arg.starts_with("-j") || arg.starts_with("--jobs"));
+        let jobs = self
+            .jobs
+            .filter(|_| !chooses_jobs)
+            .map(|jobs| jobs.to_string());
+        let mut args = cargo_args.to_vec();
+        if let Some(jobs) = &jobs {
+            // Right after the subcommand, before any `--`
+            let at = args.len().min(1);
+            args.splice(at..at, ["--jobs", jobs.as_str()]);
+        }
+        self.shell("cargo", &args)
+    }
+
+    /// Shell command running `program` in the project's remote copy. Programs
+    /// installed by rustup are not on the PATH of non-interactive SSH
+    /// sessions everywhere.
+    pub fn shell(&self, program: &str, args: &[&str]) -> String {
+        let mut command = format!(
+            "cd {} && PATH=\"$HOME/.cargo/bin:$PATH\" {}",
+            shell_quote(&self.remote_dir),
+            program
+        );
+        for arg in args {
+            command.push(' ');
+            command.push_str(&shell_quote(arg));
+        }
+        command
+    }
+
+    /// `rsync` arguments copying the artifacts of the profile `cargo_args`
+    /// builds with into `target/remote/<profile>`, leaving out cargo's
+    /// intermediate files
+    pub fn download_args(&self, cargo_args: &[&str]) -> Vec<String> {
+        let profile_dir = profile_dir(profile_of(cargo_args));
+        vec![
+            "-az".to_string(),
+            "--exclude=/deps/".to_string(),
+            "--exclude=/build/".to_string(),
+            "--exclude=/incremental/".to_string(),
+            "--exclude=/.fingerprint/".to_string(),
+            format!(
+                "{}:{}/target/{}/",
+                self.destination, self.remote_dir, profile_dir
+            ),
+            format!("{}/", self.local_artifacts(cargo_args).display()),
+        ]
+    }
+
+    /// Where the artifacts of a remote build end up locally
+    pub fn local_artifacts(&self, cargo_args: &[&str]) -> PathBuf {
+        self.project_root
+            .join("target")
+            .join("remote")
+            .join(profile_dir(profile_of(cargo_args)))
+    }
+}
+
+/// Tools a generated Cargo configuration can build with; ones installed
+/// here should be on the host too
+pub const BUILD_TOOLS: &[&str] = &["sccache", "mold", "lld", "ld.lld", "clang", "ccache"];
+
+/// What a host has installed, as reported by [`HostProbe::script`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HostProbe {
+    pub rustup: Option<String>,
+    pub rustc: Option<String>,
+    pub cargo: Option<String>,
+    pub rsync: bool,
+    /// Whether the projects directory could be created and written to
+    pub path_writable: bool,
+    /// Installed toolchains, e.g. `stable-x86_64-unknown-linux-gnu`
+    pub toolchains: Vec<String>,
+    /// Which of the requested tools are on the PATH
+    pub tools: Vec<String>,
+}
+
+impl HostProbe {
+    /// Shell script printing one `key=value` line per finding
+    pub fn script(path: &str, tools: &[&str]) -> String {
+        let mut script = String::from("export PATH=\"$HOME/.cargo/bin:$PATH\"\n");
+        script.push_str("echo \"rustup=$(rustup --version 2>/dev/null | head -n 1)\"\n");
+        script.push_str("echo \"rustc=$(rustc --version 2>/dev/null)\"\n");
+        script.push_str("echo \"cargo=$(cargo --version 2>/dev/null)\"\n");
+        script.push_str("command -v rsync >/dev/null 2>&1 && echo rsync=yes\n");
+        script.push_str(&format!(
+            "mkdir -p {path} 2>/dev/null && test -w {path} && echo path=yes\n",
+            path = shell_quote(path)
+        ));
+        script.push_str(
+            "rustup toolchain list 2>/dev/null | while read -r name rest; do echo \"toolchain=$name\"; done\n",
+        );
+        for tool in tools {
+            script.push_str(&format!(
+                "command -v {tool} >/dev/null 2>&1 && echo tool={tool}\n",
+                tool = shell_quote(tool)
+            ));
+        }
+        script
+    }
+
+    pub fn parse(output: &str) -> Self {
+        let mut probe = Self::default();
+        let present = |value: &str| Some(value.to_string()).filter(|value| !value.is_empty());
+        for (key, value) in output
+            .lines()
+            .filter_map(|line| line.trim().split_once('='))
+        {
+            match key {
+                "rustup" => probe.rustup = present(value),
+                "rustc" => probe.rustc = present(value),
+                "cargo" => probe.cargo = present(value),
+                "rsync" => probe.rsync = true,
+                "path" => probe.path_writable = true,
+                "toolchain" => probe.toolchains.push(value.to_string()),
+                "tool" => probe.tools.push(value.to_string()),
+                _ => {}
+            }
+        }
+        probe
+    }
+
+    /// Whether a toolchain such as `stable` or `1.75.0` is installed
+    pub fn has_toolchain(&self, channel: &str) -> bool {
+        self.toolchains
+            .iter()
+            .any(|name| name == channel || name.starts_with(&format!("{}-", channel)))
+    }
+}
+
+/// Toolchain channel pinned by the project's `rust-toolchain.toml` or
+/// `rust-toolchain` file
+pub fn pinned_toolchain(project_root: &Path) -> Option<String> {
+    ["rust-toolchain.toml", "rust-toolchain"]
+        .iter()
+        .find_map(|name| std::fs::read_to_string(project_root.join(name)).ok())
+        .and_then(|content| match content.parse::<toml::Table>() {
+            Ok(table) => table
+                .get("toolchain")?
+                .get("channel")?
+                .as_str()
+                .map(str::to_string),
+            // The legacy file holds only the channel
+            Err(_) => content
+                .lines()
+                .next()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string),
+        })
+}
+
+/// Version number of `rustc --version` output, e.g. `1.75.0`
+pub fn rustc_release(version: &str) -> Option<&str> {
+    version.split_whitespace().nth(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host() -> RemoteHost {
+        RemoteHost {
+            address: "buildbox".to_string(),
+            user: Some("me".to_string()),
+            path: Some("atlas-builds/".to_string()),
+            jobs: Some(32),
+            check_toolchain: true,
+        }
+    }
+
+    #[test]
+    fn test_remote_build_commands() {
+        let build = RemoteBuild::new(&host(), Path::new("/home/me/my app"));
+        assert_eq!(build.destination, "me@buildbox");
+        assert!(build.remote_dir.starts_with("atlas-builds/my app-"));
+        assert_eq!(
+            build.remote_command(&["build", "--release", "--features", "a b"]),
+            format!(
+                "cd '{}' && PATH=\"$HOME/.cargo/bin:$PATH\" cargo build --jobs 32 --release --features 'a b'",
+                build.remote_dir
+            )
+        );
+        assert!(build
+            .remote_command(&["build", "-j4"])
+            .ends_with("cargo build -j4"));
+        assert_eq!(
+            build.local_artifacts(&["build", "--release"]),
+            PathBuf::from("/home/me/my app/target/remote/release")
+        );
+        assert!(build.download_args(&["build"])[5].ends_with("/target/debug/"));
+    }
+
+    #[test]
+    fn test_parse_host_probe() {
+        let probe = HostProbe::parse(
+            "rustup=rustup 1.27.1 (54dd3d00f 2024-04-24)\nrustc=rustc 1.78.0 (9b00956e5 2024-04-29)\ncargo=\nrsync=yes\ntoolchain=stable-x86_64-unknown-linux-gnu\ntoolchain=1.75.0-x86_64-unknown-linux-gnu\ntool=mold\n",
+        );
+        assert_eq!(
+            probe.rustc.as_deref().and_then(rustc_release),
+            Some("1.78.0")
+        );
+        assert_eq!(probe.cargo, None);
+        assert!(probe.rsync);
+        assert!(!probe.path_writable);
+        assert!(probe.has_toolchain("stable"));
+        assert!(probe.has_toolchain("1.75.0"));
+        assert!(!probe.has_toolchain("nightly"));
+        assert_eq!(probe.tools, vec!["mold"]);
+    }
+}
@@ -0,0 +1,852 @@
+//! Recommendation rules behind `atlas doctor` and `atlas status`.
+//!
+//! Each rule has a stable ID that projects can silence with
+//! `ignore_rules = ["ATL003"]` in `atlas.toml`. A rule reports findings,
+//! and a finding may carry a [`Fix`] that `atlas doctor --fix` applies.
+//! Hint rules look at how a command went instead, and suggest a next step
+//! in a footer after it.
+
+use crate::commands::status::{rustflags_conflicts, stale_generated_files};
+use crate::config::OptimizerConfig;
+use crate::contention::{self, Contention};
+use crate::error::OptimizerResult;
+use crate::fixes::Fix;
+use crate::knowledge;
+use crate::project_config::ProjectConfig;
+use crate::stats::BuildRecord;
+use crate::style;
+use crate::system::SystemInfo;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Info => write!(f, "info"),
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl Severity {
+    pub fn colored(self) -> style::ColoredString {
+        match self {
+            Self::Info => style::info(&self.to_string()),
+            Self::Warning => style::caution(&self.to_string()),
+            Self::Error => style::bad(&self.to_string()),
+        }
+    }
+}
+
+/// What a rule inspects
+pub struct Context<'a> {
+    pub project_root: &'a Path,
+    pub system_info: &'a SystemInfo,
+}
+
+/// One problem a rule found
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    /// Command or change that resolves it when there is no automatic fix
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+    #[serde(skip)]
+    pub fix: Option<Fix>,
+}
+
+pub struct Rule {
+    pub id: &'static str,
+    pub severity: Severity,
+    pub title: &'static str,
+    /// Problems found, each with an optional fix or hint
+    pub detect: fn(&Context) -> OptimizerResult<Vec<Detection>>,
+}
+
+/// A rule's raw result before it becomes a [`Finding`]
+pub struct Detection {
+    pub message: String,
+    pub hint: Option<String>,
+    pub fix: Option<Fix>,
+}
+
+impl Detection {
+    fn fix(message: String, fix: Fix) -> Self {
+        Self {
+            message,
+            hint: None,
+            fix: Some(fix),
+        }
+    }
+
+    fn hint(message: String, hint: impl Into<String>) -> Self {
+        Self {
+            message,
+            hint: Some(hint.into()),
+            fix: None,
+        }
+    }
+}
+
+pub const RULES: &[Rule] = &[
+    Rule {
+        id: "ATL001",
+        severity: Severity::Error,
+        title: "Cargo key spelled with underscores is ignored",
+        detect: misspelled_keys,
+    },
+    Rule {
+        id: "ATL002",
+        severity: Severity::Warning,
+        title: "Recommended fast linker is not installed",
+        detect: missing_linker,
+    },
+    Rule {
+        id: "ATL003",
+        severity: Severity::Warning,
+        title: "sccache is installed but not used as rustc-wrapper",
+        detect: unused_sccache,
+    },
+    Rule {
+        id: "ATL004",
+        severity: Severity::Info,
+        title: "Dev profile does not set split-debuginfo",
+        detect: missing_split_debuginfo,
+    },
+    Rule {
+        id: "ATL005",
+        severity: Severity::Warning,
+        title: "Rustflags hidden by higher-precedence flags",
+        detect: rustflags_conflict,
+    },
+    Rule {
+        id: "ATL006",
+        severity: Severity::Warning,
+        title: "Generated configuration is outdated",
+        detect: stale_generated,
+    },
+    Rule {
+        id: "ATL007",
+        severity: Severity::Info,
+        title: "Optimization tools are not installed",
+        detect: missing_tools,
+    },
+    Rule {
+        id: "ATL008",
+        severity: Severity::Warning,
+        title: "rust-analyzer builds into the same target directory as command-line builds",
+        detect: shared_target_dir,
+    },
+    Rule {
+        id: "ATL012",
+        severity: Severity::Warning,
+        title: "Fast linker is installed but builds do not use it",
+        detect: unused_linker,
+    },
+    Rule {
+        id: "ATL013",
+        severity: Severity::Warning,
+        title: "Release profile builds full debug info",
+        detect: release_debuginfo,
+    },
+    Rule {
+        id: "ATL014",
+        severity: Severity::Warning,
+        title: "Development profile limits codegen units",
+        detect: few_codegen_units,
+    },
+    Rule {
+        id: "ATL015",
+        severity: Severity::Warning,
+        title: "crates.io index is fetched over git",
+        detect: git_registry_protocol,
+    },
+    Rule {
+        id: "ATL016",
+        severity: Severity::Warning,
+        title: "Windows Defender scans the target directory",
+        detect: scanned_target_dir,
+    },
+];
+
+/// How a command went, for hint rules
+pub struct Outcome<'a> {
+    /// Command and subcommand, e.g. `build test`
+    pub command: &'a str,
+    /// Arguments after the program name, aliases expanded
+    pub args: &'a [String],
+    pub success: bool,
+    pub duration: Duration,
+    /// The build the command recorded in the stats store, if it built
+    pub build: Option<&'a BuildRecord>,
+}
+
+pub struct HintRule {
+    pub id: &'static str,
+    pub title: &'static str,
+    /// A next step worth taking after this outcome
+    pub detect: fn(&Outcome) -> Option<Detection>,
+}
+
+pub const HINT_RULES: &[HintRule] = &[
+    HintRule {
+        id: "ATL009",
+        title: "Build took long with a low sccache hit rate",
+        detect: low_cache_hit_rate,
+    },
+    HintRule {
+        id: "ATL010",
+        title: "Build compiled dependencies without a compilation cache",
+        detect: uncached_build,
+    },
+    HintRule {
+        id: "ATL011",
+        title: "Tests failed and could run failed tests first",
+        detect: failed_tests_not_first,
+    },
+];
+
+/// Builds shorter than this are not worth a hint about their speed
+const SLOW_BUILD: Duration = Duration::from_secs(60);
+
+pub fn rule(id: &str) -> Option<&'static Rule> {
+    RULES.iter().find(|rule| rule.id.eq_ignore_ascii_case(id))
+}
+
+fn is_known(id: &str) -> bool {
+    rule(id).is_some()
+        || HINT_RULES
+            .iter()
+            .any(|rule| rule.id.eq_ignore_ascii_case(id))
+}
+
+/// Findings of every rule the project does not suppress, most severe first,
+/// and how many findings were suppressed
+pub fn evaluate(context: &Context) -> OptimizerResult<(Vec<Finding>, usize)> {
+    let ignored = ProjectConfig::load(context.project_root)?.ignore_rules;
+    for id in ignored.iter().filter(|id| !is_known(id)) {
+        log::warn!("atlas.toml ignores unknown rule {}", id);
+    }
+
+    let mut findings = Vec::new();
+    let mut suppressed = 0;
+    for rule in RULES {
+        let detections = match (rule.detect)(context) {
+            Ok(detections) => detections,
+            Err(e) => {
+                log::warn!("Rule {} could not run: {}", rule.id, e);
+                continue;
+            }
+        };
+        if ignored.iter().any(|id| id.eq_ignore_ascii_case(rule.id)) {
+            suppressed += detections.len();
+            continue;
+        }
+        findings.extend(detections.into_iter().map(|detection| Finding {
+            rule: rule.id,
+            severity: rule.severity,
+            message: detection.message,
+            hint: detection.hint,
+            fix: detection.fix,
+        }));
+    }
+    findings.sort_by_key(|finding| std::cmp::Reverse(finding.severity));
+    Ok((findings, suppressed))
+}
+
+/// Hints for the outcome of a command, minus those `ignored`
+pub fn hints(outcome: &Outcome, ignored: &[String]) -> Vec<Finding> {
+    HINT_RULES
+        .iter()
+        .filter(|rule| !ignored.iter().any(|id| id.eq_ignore_ascii_case(rule.id)))
+        .filter_map(|rule| {
+            (rule.detect)(outcome).map(|detection| Finding {
+                rule: rule.id,
+                severity: Severity::Info,
+                message: detection.message,
+                hint: detection.hint,
+                fix: detection.fix,
+            })
+        })
+        .collect()
+}
+
+/// Whether a file-editing fix still applies to the file's current content
+fn applies(fix: &Fix) -> bool {
+    match fix.path() {
+        Some(path) => fix
+            .edit(&fs::read_to_string(path).unwrap_or_default())
+            .is_some(),
+        None => true,
+    }
+}
+
+fn misspelled_keys(context: &Context) -> OptimizerResult<Vec<Detection>> {
+    let mut detections = Vec::new();
+    for path in [
+        context.project_root.join(".cargo").join("config.toml"),
+        context.project_root.join("Cargo.toml"),
+    ] {
+        for fix in crate::fixes::misspelled_keys(&path)? {
+            let Fix::RenameKey {
+                table, from, to, ..
+            } = &fix
+            else {
+                continue;
+            };
+            let message = format!(
+                "[{}] {} in {} is ignored by Cargo; it is spelled {}",
+                table,
+                from,
+                path.display(),
+                to
+            );
+            if applies(&fix) {
+                detections.push(Detection::fix(message, fix));
+            } else {
+                detections.push(Detection::hint(
+                    message,
+                    "Fix the Atlas configuration and run `atlas refresh`",
+                ));
+            }
+        }
+    }
+    Ok(detections)
+}
+
+fn missing_linker(context: &Context) -> OptimizerResult<Vec<Detection>> {
+    let Some(linker) = context
+        .system_info
+        .get_recommended_linker()
+        .filter(|linker| *linker != "system")
+    else {
+        return Ok(Vec::new());
+    };
+    if context.system_info.is_tool_installed(linker) {
+        return Ok(Vec::new());
+    }
+
+    let message = format!("{} links much faster than the default linker", linker);
+    Ok(vec![if crate::commands::tools::is_installable(linker) {
+        Detection::fix(message, Fix::InstallTool(linker.to_string()))
+    } else {
+        Detection::hint(message, format!("Install {} manually", linker))
+    }])
+}
+
+fn unused_sccache(context: &Context) -> OptimizerResult<Vec<Detection>> {
+    let wrapper = knowledge::lookup("rustc-wrapper").expect("rustc-wrapper is a known setting");
+    if !context.system_info.is_tool_installed("sccache")
+        || std::env::var_os("RUSTC_WRAPPER").is_some()
+        || !wrapper.current_values(context.project_root)?.is_empty()
+    {
+        return Ok(Vec::new());
+    }
+
+    let fix = Fix::SetKey {
+        path: context.project_root.join(".cargo").join("config.toml"),
+        table: "build".to_string(),
+        key: "rustc-wrapper".to_string(),
+        value: "\"sccache\"".to_string(),
+    };
+    Ok(applies(&fix)
+        .then(|| {
+            Detection::fix(
+                "sccache is installed but builds do not use it".to_string(),
+                fix,
+            )
+        })
+        .into_iter()
+        .collect())
+}
+
+fn missing_split_debuginfo(context: &Context) -> OptimizerResult<Vec<Detection>> {
+    let split = knowledge::lookup("split-debuginfo").expect("split-debuginfo is a known setting");
+    let Some(mode) = OptimizerConfig::load_or_default()?
+        .profiles
+        .split_debuginfo
+        .resolve(&context.system_info.os)
+    else {
+        return Ok(Vec::new());
+    };
+    if !split.current_values(context.project_root)?.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let fix = Fix::SetKey {
+        path: context.project_root.join("Cargo.toml"),
+        table: "profile.dev".to_string(),
+        key: "split-debuginfo".to_string(),
+        value: format!("\"{}\"", mode),
+    };
+    Ok(applies(&fix)
+        .then(|| {
+            Detection::fix(
+                format!(
+                    "split-debuginfo = \"{}\" skips relinking debug info on every build",
+                    mode
+                ),
+                fix,
+            )
+        })
+        .into_iter()
+        .collect())
+}
+
+fn rustflags_conflict(context: &Context) -> OptimizerResult<Vec<Detection>> {
+    Ok(rustflags_conflicts(context.project_root)
+        .iter()
+        .map(|conflict| {
+            Detection::hint(
+                conflict.explanation(),
+                "Atlas build commands pass both through CARGO_ENCODED_RUSTFLAGS; plain cargo does not",
+            )
+        })
+        .collect())
+}
+
+fn stale_generated(context: &Context) -> OptimizerResult<Vec<Detection>> {
+    Ok(stale_generated_files(context.project_root)
+        .iter()
+        .map(|(path, staleness)| {
+            Detection::hint(
+                format!("{}: {}", path.display(), staleness),
+                "atlas refresh",
+            )
+        })
+        .collect())
+}
+
+fn missing_tools(context: &Context) -> OptimizerResult<Vec<Detection>> {
+    // The recommended linker has its own rule
+    let linker = context.system_info.get_recommended_linker();
+    let missing: Vec<String> = context
+        .system_info
+        .available_tools
+        .all()
+        .into_iter()
+        .filter(|tool| !tool.is_installed && Some(tool.name.as_str()) != linker)
+        .map(|tool| tool.name)
+        .collect();
+    if missing.is_empty() {
+        return Ok(Vec::new());
+    }
+    Ok(vec![Detection::hint(
+        format!("Not installed: {}", missing.join(", ")),
+        "atlas install-tools",
+    )])
+}
+
+fn shared_target_dir(context: &Context) -> OptimizerResult<Vec<Detection>> {
+    if contention::editor_separates(context.project_root) == Some(true) {
+        return Ok(Vec::new());
+    }
+    let contention = Contention::detect(&contention::processes(), context.project_root);
+    if !contention.is_running() {
+        return Ok(Vec::new());
+    }
+
+    let pids = |pids: &[u32]| {
+        pids.iter()
+            .map(u32::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let mut message = if contention.shared_builds.is_empty() {
+        format!(
+            "rust-analyzer (pid {}) shares target/ with command-line builds",
+            pids(&contention.analyzers)
+        )
+    } else {
+        format!(
+            "rust-analyzer is running cargo in target/ (pid {}), where command-line builds wait for it",
+            pids(&contention.shared_builds)
+        )
+    };
+    let locked = contention::locked_dirs(&context.project_root.join("target"));
+    if !locked.is_empty() {
+        let dirs: Vec<String> = locked
+            .iter()
+            .filter_map(|dir| dir.strip_prefix(context.project_root).ok())
+            .map(|dir| dir.display().to_string())
+            .collect();
+        message.push_str(&format!("; locked now: {}", dirs.join(", ")));
+    }
+    let hint = if OptimizerConfig::load_or_default()?
+        .build
+        .separate_rust_analyzer_target
+    {
+        "atlas editor vscode, or set rust-analyzer.cargo.targetDir to true in your editor"
+    } else {
+        "Set rust-analyzer.cargo.targetDir to true in your editor"
+    };
+    Ok(vec![Detection::hint(message, hint)])
+}
+
+fn unused_linker(context: &Context) -> OptimizerResult<Vec<Detection>> {
+    let Some(linker) = context
+        .system_info
+        .get_recommended_linker()
+        .filter(|linker| *linker != "system")
+    else {
+        return Ok(Vec::new());
+    };
+    let setting = knowledge::lookup("linker").expect("linker is a known setting");
+    if !context.system_info.is_tool_installed(linker)
+        || !setting.current_values(context.project_root)?.is_empty()
+    {
+        return Ok(Vec::new());
+    }
+
+    let hint = if OptimizerConfig::load_or_default()?.build.use_fast_linker {
+        "atlas refresh"
+    } else {
+        "Set use_fast_linker = true under [build] in the Atlas config, then run `atlas refresh`"
+    };
+    Ok(vec![Detection::hint(
+        format!(
+            "{} is installed, but no -fuse-ld flag in the Cargo config or RUSTFLAGS selects it",
+            linker
+        ),
+        hint,
+    )])
+}
+
+/// Values of a profile setting in `[profile.<profile>]` of Cargo.toml
+fn profile_values(
+    context: &Context,
+    setting: &str,
+    profiles: &[&str],
+) -> OptimizerResult<Vec<(String, String)>> {
+    let setting = knowledge::lookup(setting).expect("profile settings are known");
+    Ok(setting
+        .current_values(context.project_root)?
+        .into_iter()
+        .filter(|(origin, _)| {
+            profiles
+                .iter()
+                .any(|profile| origin.ends_with(&format!("[profile.{}]", profile)))
+        })
+        .collect())
+}
+
+fn release_debuginfo(context: &Context) -> OptimizerResult<Vec<Detection>> {
+    Ok(profile_values(context, "debug", &["release"])?
+        .into_iter()
+        .filter(|(_, value)| matches!(value.as_str(), "true" | "2" | "\"full\""))
+        .map(|(origin, value)| {
+            Detection::hint(
+                format!(
+                    "debug = {} in {} makes release builds write and link full debug info",
+                    value, origin
+                ),
+                "Use debug = \"line-tables-only\" for backtraces with line numbers, or remove it",
+            )
+        })
+        .collect())
+}
+
+/// Fewer codegen units than this serialize code generation noticeably
+const MIN_DEV_CODEGEN_UNITS: i64 = 16;
+
+fn few_codegen_units(context: &Context) -> OptimizerResult<Vec<Detection>> {
+    Ok(profile_values(context, "codegen-units", &["dev", "test"])?
+        .into_iter()
+        .filter(|(_, value)| {
+            value
+                .parse::<i64>()
+                .is_ok_and(|units| units < MIN_DEV_CODEGEN_UNITS)
+        })
+        .map(|(origin, value)| {
+            Detection::hint(
+                format!(
+                    "codegen-units = {} in {} leaves most cores idle during code generation",
+                    value, origin
+                ),
+                "Remove it to use Cargo's default of 256 for incremental builds",
+            )
+        })
+        .collect())
+}
+
+fn git_registry_protocol(context: &Context) -> OptimizerResult<Vec<Detection>> {
+    let setting =
+        knowledge::lookup("registry-protocol").expect("registry-protocol is a known setting");
+    let values = setting.current_values(context.project_root)?;
+    if let Some((origin, _)) = values.iter().find(|(_, value)| value == "\"git\"") {
+        return Ok(vec![Detection::hint(
+            format!("{} sets the crates.io protocol to git", origin),
+            "Set protocol = \"sparse\" under [registries.crates-io], or remove it",
+        )]);
+    }
+    // Cargo before 1.70 still clones the index unless told otherwise
+    let sparse_by_default = context
+        .system_info
+        .cargo_version
+        .as_deref()
+        .and_then(|version| version.split_whitespace().nth(1))
+        .and_then(|version| version.split('.').nth(1)?.parse::<u32>().ok())
+        .map_or(true, |minor| minor >= 70);
+    if sparse_by_default || !values.is_empty() {
+        return Ok(Vec::new());
+    }
+    let fix = Fix::SetKey {
+        path: context.project_root.join(".cargo").join("config.toml"),
+        table: "registries.crates-io".to_string(),
+        key: "protocol".to_string(),
+        value: "\"sparse\"".to_string(),
+    };
+    Ok(applies(&fix)
+        .then(|| {
+            Detection::fix(
+                "This Cargo clones the whole crates.io index instead of fetching what builds need"
+                    .to_string(),
+                fix,
+            )
+        })
+        .into_iter()
+        .collect())
+}
+
+/// Prints whether real-time protection is on, then the excluded paths
+const DEFENDER_QUERY: &str = "if ((Get-MpComputerStatus).RealTimeProtectionEnabled) { 'enabled'; (Get-MpPreference).ExclusionPath }";
+
+fn scanned_target_dir(context: &Context) -> OptimizerResult<Vec<Detection>> {
+    if context.system_info.os != crate::system::OperatingSystem::Windows {
+        return Ok(Vec::new());
+    }
+    let target = context.project_root.join("target");
+    let Some(output) = crate::utils::command_stdout(
+        "powershell",
+        &["-NoProfile", "-Command", DEFENDER_QUERY],
+        context.project_root,
+    ) else {
+        return Ok(Vec::new());
+    };
+    if defender_scans(&output, &target.to_string_lossy()) != Some(true) {
+        return Ok(Vec::new());
+    }
+    Ok(vec![Detection::hint(
+        format!(
+            "Real-time protection scans every file the compiler writes to {}",
+            target.display()
+        ),
+        format!(
+            "As administrator: Add-MpPreference -ExclusionPath \"{}\"",
+            target.display()
+        ),
+    )])
+}
+
+/// Whether Defender, per the output of [`DEFENDER_QUERY`], scans `target`;
+/// `None` when the exclusions cannot be read without administrator rights
+fn defender_scans(output: &str, target: &str) -> Option<bool> {
+    let mut lines = output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty());
+    if lines.next() != Some("enabled") {
+        return Some(false);
+    }
+    let exclusions: Vec<&str> = lines.collect();
+    if exclusions.iter().any(|line| line.starts_with("N/A")) {
+        return None;
+    }
+    let target = target.to_lowercase();
+    Some(!exclusions.iter().any(|exclusion| {
+        target.starts_with(exclusion.trim_end_matches('\\').to_lowercase().as_str())
+    }))
+}
+
+fn low_cache_hit_rate(outcome: &Outcome) -> Option<Detection> {
+    let build = outcome.build?;
+    let sccache = build.sccache?;
+    if outcome.duration < SLOW_BUILD || sccache.requests < 20 {
+        return None;
+    }
+    let hit_rate = sccache.hits as f64 / sccache.requests as f64;
+    (hit_rate < 0.3).then(|| {
+        Detection::hint(
+            format!(
+                "The build took {} and the cache hit rate was {:.0}%",
+                crate::utils::format_duration(outcome.duration),
+                hit_rate * 100.0
+            ),
+            "atlas cache report",
+        )
+    })
+}
+
+fn uncached_build(outcome: &Outcome) -> Option<Detection> {
+    let build = outcome.build?;
+    if outcome.duration < SLOW_BUILD
+        || build.sccache.is_some()
+        || !matches!(
+            build.cause,
+            crate::stats::RebuildCause::Cold | crate::stats::RebuildCause::DependencyChanges
+        )
+    {
+        return None;
+    }
+    let hint = if crate::utils::is_tool_available("sccache") {
+        "atlas doctor --fix"
+    } else {
+        "atlas install-tools --only sccache"
+    };
+    Some(Detection::hint(
+        format!(
+            "{} crates were compiled without sccache, so the next clean build starts over",
+            build.crates_compiled
+        ),
+        hint,
+    ))
+}
+
+fn failed_tests_not_first(outcome: &Outcome) -> Option<Detection> {
+    if outcome.success
+        || outcome.command != "build test"
+        || outcome.args.iter().any(|arg| arg == "--failed-first")
+    {
+        return None;
+    }
+    Some(Detection::hint(
+        "Tests failed".to_string(),
+        "atlas build test --failed-first",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_rule_ids_are_unique() {
+        let ids: HashSet<&str> = RULES.iter().map(|rule| rule.id).collect();
+        assert_eq!(ids.len(), RULES.len());
+        assert!(RULES.iter().all(|rule| rule.id.starts_with("ATL")));
+        assert_eq!(rule("atl003").unwrap().id, "ATL003");
+        assert!(HINT_RULES.iter().all(|hint| rule(hint.id).is_none()));
+    }
+
+    #[test]
+    fn test_hints_for_outcome() {
+        let args = vec!["build".to_string(), "test".to_string()];
+        let mut record = BuildRecord::new(
+            Path::new("/ws"),
+            "build",
+            true,
+            90.0,
+            &crate::stats::CompilationStats::default(),
+            Some(crate::stats::SccacheSnapshot {
+                requests: 50,
+                hits: 6,
+                misses: 44,
+                errors: 0,
+            }),
+            None,
+        );
+        record.cause = crate::stats::RebuildCause::Cold;
+        let outcome = Outcome {
+            command: "build test",
+            args: &args,
+            success: false,
+            duration: Duration::from_secs(90),
+            build: Some(&record),
+        };
+        let ids: Vec<&str> = hints(&outcome, &[]).iter().map(|hint| hint.rule).collect();
+        assert_eq!(ids, vec!["ATL009", "ATL011"]);
+        assert!(hints(&outcome, &[])[0].message.contains("12%"));
+        assert_eq!(hints(&outcome, &["atl009".to_string()]).len(), 1);
+
+        let quick = Outcome {
+            duration: Duration::from_secs(5),
+            success: true,
+            ..outcome
+        };
+        assert!(hints(&quick, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_profile_rules() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n\n[profile.dev]\ncodegen-units = 1\n\n\
+             [profile.release]\ndebug = true\ncodegen-units = 1\n",
+        )
+        .unwrap();
+        let system_info = SystemInfo::detect_with(&crate::host::MockHost::linux());
+        let context = Context {
+            project_root: dir.path(),
+            system_info: &system_info,
+        };
+
+        let debuginfo = release_debuginfo(&context).unwrap();
+        assert_eq!(debuginfo.len(), 1);
+        assert!(debuginfo[0].message.contains("[profile.release]"));
+        // A single codegen unit is a deliberate choice for release builds
+        let codegen = few_codegen_units(&context).unwrap();
+        assert_eq!(codegen.len(), 1);
+        assert!(codegen[0].message.contains("[profile.dev]"));
+    }
+
+    #[test]
+    fn test_defender_exclusions() {
+        let target = r"C:\src\app\target";
+        assert_eq!(defender_scans("enabled\r\n", target), Some(true));
+        assert_eq!(
+            defender_scans("enabled\r\nC:\\src\\\r\nD:\\cache\r\n", target),
+            Some(false)
+        );
+        assert_eq!(
+            defender_scans(
+                "enabled\nN/A: Must be an administrator to view exclusions\n",
+                target
+            ),
+            None
+        );
+        assert_eq!(defender_scans("", target), Some(false));
+    }
+
+    #[test]
+    fn test_ignored_rules_are_suppressed() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n\n[profile.dev]\nopt_level = 1\n",
+        )
+        .unwrap();
+        let system_info = SystemInfo::detect().unwrap();
+        let context = Context {
+            project_root: dir.path(),
+            system_info: &system_info,
+        };
+
+        let (findings, _) = evaluate(&context).unwrap();
+        assert!(findings.iter().any(|finding| finding.rule == "ATL001"));
+
+        fs::write(
+            dir.path().join("atlas.toml"),
+            "ignore_rules = [\"ATL001\"]\n",
+        )
+        .unwrap();
+        let (findings, suppressed) = evaluate(&context).unwrap();
+        assert!(findings.iter().all(|finding| finding.rule != "ATL001"));
+        assert!(suppressed >= 1);
+    }
+}
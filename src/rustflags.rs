@@ -0,0 +1,472 @@
+//! Where rustc flags come from, and which sources Cargo silently ignores
+//!
+//! Cargo takes rustflags from the first of these that is set:
+//! `CARGO_ENCODED_RUSTFLAGS`, `RUSTFLAGS`, `target.<triple>.rustflags`,
+//! `build.rustflags`. Config values are merged across all config files.
+
+use crate::error::OptimizerResult;
+use crate::utils::cargo_home;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+
+pub const RUSTFLAGS: &str = "RUSTFLAGS";
+pub const ENCODED_RUSTFLAGS: &str = "CARGO_ENCODED_RUSTFLAGS";
+
+/// Separator between flags in `CARGO_ENCODED_RUSTFLAGS`
+const ENCODED_SEPARATOR: char = '\x1f';
+
+/// Merged flags for cargo commands that build for the host
+static HOST_FLAGS: OnceLock<HostFlags> = OnceLock::new();
+
+#[derive(Debug)]
+struct HostFlags {
+    host: Option<String>,
+    encoded: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlagsKey {
+    /// `target.<triple>.rustflags`
+    Target,
+    /// `build.rustflags`
+    Build,
+}
+
+/// Rustflags from one config file or config environment variable
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigFlags {
+    /// Path of the config file, or name of the environment variable
+    pub origin: String,
+    pub key: FlagsKey,
+    pub flags: Vec<String>,
+}
+
+/// Rustflags from `RUSTFLAGS` or `CARGO_ENCODED_RUSTFLAGS`
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvFlags {
+    pub variable: &'static str,
+    pub flags: Vec<String>,
+}
+
+/// Config flags that Cargo ignores because a higher-precedence source is set
+#[derive(Debug, Clone)]
+pub enum Conflict {
+    EnvOverridesConfig {
+        variable: &'static str,
+        ignored: Vec<ConfigFlags>,
+    },
+    TargetOverridesBuild {
+        ignored: Vec<ConfigFlags>,
+    },
+}
+
+impl Conflict {
+    pub fn ignored(&self) -> &[ConfigFlags] {
+        match self {
+            Self::EnvOverridesConfig { ignored, .. } | Self::TargetOverridesBuild { ignored } => {
+                ignored
+            }
+        }
+    }
+
+    pub fn explanation(&self) -> String {
+        match self {
+            Self::EnvOverridesConfig { variable, .. } => format!(
+                "{} is set, so Cargo ignores every rustflags setting in its config files, including Atlas's linker settings",
+                variable
+            ),
+            Self::TargetOverridesBuild { .. } => {
+                "target.<triple>.rustflags is set, so Cargo ignores build.rustflags".to_string()
+            }
+        }
+    }
+}
+
+/// All rustflags sources that apply to builds for the host
+#[derive(Debug, Clone, Default)]
+pub struct RustflagsLayers {
+    pub env: Option<EnvFlags>,
+    /// Config sources, highest precedence first
+    pub config: Vec<ConfigFlags>,
+}
+
+impl RustflagsLayers {
+    pub fn detect(project_root: &Path, host: Option<&str>) -> OptimizerResult<Self> {
+        let mut files = Vec::new();
+        for path in config_files(project_root) {
+            let value: toml::Value = toml::from_str(&fs::read_to_string(&path)?)?;
+            files.push((path.display().to_string(), value));
+        }
+        Ok(Self::from_sources(
+            |name| std::env::var(name).ok(),
+            &files,
+            host,
+        ))
+    }
+
+    /// Build the layers from an environment lookup and parsed config files,
+    /// given highest precedence first
+    pub(crate) fn from_sources(
+        env: impl Fn(&str) -> Option<String>,
+        files: &[(String, toml::Value)],
+        host: Option<&str>,
+    ) -> Self {
+        // An empty RUSTFLAGS still overrides the config
+        let env_flags = if let Some(encoded) = env(ENCODED_RUSTFLAGS) {
+            Some(EnvFlags {
+                variable: ENCODED_RUSTFLAGS,
+                flags: decode(&encoded),
+            })
+        } else {
+            env(RUSTFLAGS).map(|flags| EnvFlags {
+                variable: RUSTFLAGS,
+                flags: split_flags(&flags),
+            })
+        };
+
+        let mut config = Vec::new();
+        if let Some(host) = host {
+            let variable = format!(
+                "CARGO_TARGET_{}_RUSTFLAGS",
+                host.to_uppercase().replace(['-', '.'], "_")
+            );
+            if let Some(flags) = env(&variable) {
+                config.push(ConfigFlags {
+                    origin: variable,
+                    key: FlagsKey::Target,
+                    flags: split_flags(&flags),
+                });
+            }
+        }
+        if let Some(flags) = env("CARGO_BUILD_RUSTFLAGS") {
+            config.push(ConfigFlags {
+                origin: "CARGO_BUILD_RUSTFLAGS".to_string(),
+                key: FlagsKey::Build,
+                flags: split_flags(&flags),
+            });
+        }
+
+        for (origin, value) in files {
+            let target = host.and_then(|host| {
+                value
+                    .get("target")
+                    .and_then(|target| target.get(host))
+                    .and_then(|target| target.get("rustflags"))
+            });
+            let build = value.get("build").and_then(|build| build.get("rustflags"));
+
+            for (key, flags) in [(FlagsKey::Target, target), (FlagsKey::Build, build)] {
+                if let Some(flags) = flags.and_then(config_value_flags) {
+                    config.push(ConfigFlags {
+                        origin: origin.clone(),
+                        key,
+                        flags,
+                    });
+                }
+            }
+        }
+
+        Self {
+            env: env_flags,
+            config,
+        }
+    }
+
+    fn sources(&self, key: FlagsKey) -> impl DoubleEndedIterator<Item = &ConfigFlags> {
+        self.config.iter().filter(move |source| source.key == key)
+    }
+
+    /// The key Cargo reads config flags from: `target.<triple>.rustflags`
+    /// hides `build.rustflags`
+    pub fn config_key(&self) -> FlagsKey {
+        if self.sources(FlagsKey::Target).next().is_some() {
+            FlagsKey::Target
+        } else {
+            FlagsKey::Build
+        }
+    }
+
+    /// Flags Cargo takes from its config when no environment override is set.
+    /// Arrays from several files are joined, higher precedence last.
+    pub fn config_flags(&self) -> Vec<String> {
+        self.sources(self.config_key())
+            .rev()
+            .flat_map(|source| source.flags.iter().cloned())
+            .collect()
+    }
+
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        let mut conflicts = Vec::new();
+
+        if let Some(env) = &self.env {
+            let ignored: Vec<ConfigFlags> = self
+                .config
+                .iter()
+                .filter(|source| !source.flags.is_empty())
+                .cloned()
+                .collect();
+            if !ignored.is_empty() {
+                conflicts.push(Conflict::EnvOverridesConfig {
+                    variable: env.variable,
+                    ignored,
+                });
+            }
+            return conflicts;
+        }
+
+        if self.sources(FlagsKey::Target).next().is_some() {
+            let ignored: Vec<ConfigFlags> = self
+                .sources(FlagsKey::Build)
+                .filter(|source| !source.flags.is_empty())
+                .cloned()
+                .collect();
+            if !ignored.is_empty() {
+                conflicts.push(Conflict::TargetOverridesBuild { ignored });
+            }
+        }
+
+        conflicts
+    }
+
+    /// Config flags followed by the environment's, so the environment still wins
+    /// where a later flag overrides an earlier one. `None` when nothing is ignored.
+    pub fn merged(&self) -> Option<Vec<String>> {
+        let env = self.env.as_ref()?;
+        let config = self.config_flags();
+        if config.is_empty() {
+            return None;
+        }
+
+        let mut merged = config;
+        merged.extend(env.flags.iter().cloned());
+        Some(merged)
+    }
+}
+
+/// Cargo config files that apply to `project_root`, highest precedence first
+pub fn config_files(project_root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut dirs: Vec<PathBuf> = project_root
+        .canonicalize()
+        .unwrap_or_else(|_| project_root.to_path_buf())
+        .ancestors()
+        .map(|dir| dir.join(".cargo"))
+        .collect();
+
+    if let Some(cargo_home) = cargo_home() {
+        if !dirs.contains(&cargo_home) {
+            dirs.push(cargo_home);
+        }
+    }
+
+    for dir in dirs {
+        // Cargo reads `config` over `config.toml` when both exist
+        let file = ["config", "config.toml"]
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file());
+        files.extend(file);
+    }
+    files
+}
+
+/// Hand `flags`, merged for `host`, to cargo commands started from now on
+/// that build for the host. Builds for other targets keep the environment,
+/// since the host's `target.<triple>.rustflags` do not apply to them.
+pub fn init(host: Option<String>, flags: &[String]) {
+    let _ = HOST_FLAGS.set(HostFlags {
+        host,
+        encoded: encode(flags),
+    });
+}
+
+/// Target triples a cargo command line builds for
+pub fn targets_of<'a>(args: &[&'a str]) -> Vec<&'a str> {
+    let mut targets = Vec::new();
+    let mut args = args.iter();
+    while let Some(&arg) = args.next() {
+        match arg {
+            "--" => break,
+            "--target" => targets.extend(args.next().copied()),
+            _ => targets.extend(arg.strip_prefix("--target=")),
+        }
+    }
+    targets
+}
+
+/// Set `CARGO_ENCODED_RUSTFLAGS` on a cargo command about to be started,
+/// unless it builds for another target or sets the variable itself
+pub fn apply(cmd: &mut Command) {
+    if let Some(flags) = HOST_FLAGS.get() {
+        apply_host_flags(cmd, flags);
+    }
+}
+
+fn apply_host_flags(cmd: &mut Command, flags: &HostFlags) {
+    if cmd.get_envs().any(|(name, _)| name == ENCODED_RUSTFLAGS) {
+        return;
+    }
+    let args: Vec<String> = cmd
+        .get_args()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let for_host = targets_of(&args)
+        .into_iter()
+        .all(|target| Some(target) == flags.host.as_deref());
+    if for_host {
+        cmd.env_remove(RUSTFLAGS)
+            .env(ENCODED_RUSTFLAGS, &flags.encoded);
+    }
+}
+
+/// Value for `CARGO_ENCODED_RUSTFLAGS`
+pub fn encode(flags: &[String]) -> String {
+    flags.join(&ENCODED_SEPARATOR.to_string())
+}
+
+fn decode(encoded: &str) -> Vec<String> {
+    if encoded.is_empty() {
+        return Vec::new();
+    }
+    encoded
+        .split(ENCODED_SEPARATOR)
+        .map(str::to_string)
+        .collect()
+}
+
+fn split_flags(flags: &str) -> Vec<String> {
+    flags.split_whitespace().map(str::to_string).collect()
+}
+
+fn config_value_flags(value: &toml::Value) -> Option<Vec<String>> {
+    match value {
+        toml::Value::String(flags) => Some(split_flags(flags)),
+        toml::Value::Array(flags) => Some(
+            flags
+                .iter()
+                .filter_map(|flag| flag.as_str().map(str::to_string))
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    const HOST: &str = "x86_64-unknown-linux-gnu";
+
+    #[test]
+    fn test_host_flags_skip_cross_builds() {
+        let flags = HostFlags {
+            host: Some(HOST.to_string()),
+            encoded: encode(&["-Dwarnings".to_string(), "-Ctarget-cpu=native".to_string()]),
+        };
+        let encoded = |args: &[&str]| {
+            let mut cmd = Command::new("cargo");
+            cmd.args(args);
+            apply_host_flags(&mut cmd, &flags);
+            cmd.get_envs()
+                .find(|(name, _)| *name == ENCODED_RUSTFLAGS)
+                .and_then(|(_, value)| value.map(|value| value.to_string_lossy().into_owned()))
+        };
+
+        assert_eq!(
+            encoded(&["build", "--release"]),
+            Some(flags.encoded.clone())
+        );
+        assert_eq!(
+            encoded(&["build", &format!("--target={}", HOST)]),
+            Some(flags.encoded.clone())
+        );
+        assert_eq!(
+            encoded(&["build", "--target", "aarch64-apple-darwin"]),
+            None
+        );
+        assert_eq!(encoded(&["build", "--target=x86_64-pc-windows-msvc"]), None);
+        assert_eq!(
+            targets_of(&["run", "--", "--target", "wasm32-unknown-unknown"]),
+            Vec::<&str>::new()
+        );
+    }
+
+    fn layers(env: &[(&str, &str)], files: &[(&str, &str)]) -> RustflagsLayers {
+        let env: HashMap<String, String> = env
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        let files: Vec<(String, toml::Value)> = files
+            .iter()
+            .map(|(origin, content)| (origin.to_string(), toml::from_str(content).unwrap()))
+            .collect();
+        RustflagsLayers::from_sources(|name| env.get(name).cloned(), &files, Some(HOST))
+    }
+
+    const PROJECT: &str = r#"
+[target.x86_64-unknown-linux-gnu]
+rustflags = ["-C", "link-arg=-fuse-ld=mold"]
+"#;
+
+    #[test]
+    fn test_env_overrides_config() {
+        let layers = layers(
+            &[(RUSTFLAGS, "-C target-cpu=native")],
+            &[("project", PROJECT)],
+        );
+
+        let conflicts = layers.conflicts();
+        assert_eq!(conflicts.len(), 1);
+        assert!(matches!(
+            conflicts[0],
+            Conflict::EnvOverridesConfig {
+                variable: RUSTFLAGS,
+                ..
+            }
+        ));
+        assert_eq!(conflicts[0].ignored()[0].origin, "project");
+
+        let merged = layers.merged().unwrap();
+        assert_eq!(
+            merged,
+            vec!["-C", "link-arg=-fuse-ld=mold", "-C", "target-cpu=native"]
+        );
+        assert_eq!(
+            encode(&merged),
+            "-C\x1flink-arg=-fuse-ld=mold\x1f-C\x1ftarget-cpu=native"
+        );
+    }
+
+    #[test]
+    fn test_config_layering() {
+        let home = "[build]\nrustflags = \"--cfg home\"\n[target.x86_64-unknown-linux-gnu]\nrustflags = [\"--cfg\", \"home_target\"]\n";
+        let layers = layers(&[], &[("project", PROJECT), ("home", home)]);
+
+        assert_eq!(
+            layers.config_flags(),
+            vec!["--cfg", "home_target", "-C", "link-arg=-fuse-ld=mold"]
+        );
+        let conflicts = layers.conflicts();
+        assert_eq!(conflicts.len(), 1);
+        assert!(matches!(
+            conflicts[0],
+            Conflict::TargetOverridesBuild { .. }
+        ));
+        assert!(layers.merged().is_none());
+    }
+
+    #[test]
+    fn test_no_conflict_without_config_flags() {
+        let layers = layers(
+            &[(ENCODED_RUSTFLAGS, "")],
+            &[("project", "[build]\njobs = 4\n")],
+        );
+        assert_eq!(layers.env.as_ref().unwrap().flags, Vec::<String>::new());
+        assert!(layers.conflicts().is_empty());
+        assert!(layers.merged().is_none());
+    }
+}
@@ -0,0 +1,387 @@
+//! Typed JSON documents printed by `status --json`, `ci affected`,
+//! `build matrix --json` and `editor status`, documented in
+//! `docs/JSON_OUTPUT.md`.
+//!
+//! Every document carries `schema_version`. Adding a field is not a breaking
+//! change; renaming, removing or retyping one is, and bumps [`SCHEMA_VERSION`].
+
+use crate::editor::LastBuild;
+use crate::managed::Staleness;
+use crate::rules::{Finding, Severity};
+use crate::rustflags::Conflict;
+use crate::system::{AvailableTool, SystemInfo};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Version of every JSON document Atlas prints. Bumped only on breaking changes.
+pub const SCHEMA_VERSION: u32 = 1;
+
+fn atlas_version() -> String {
+    env!("CARGO_PKG_VERSION").to_string()
+}
+
+/// `atlas status --json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusReport {
+    pub schema_version: u32,
+    pub atlas_version: String,
+    pub system: SystemSummary,
+    /// Build system driving cargo, e.g. `Bazel`, if one was detected
+    pub foreign_build_system: Option<String>,
+    pub tools: Vec<Tool>,
+    pub rustflags_conflicts: Vec<RustflagsConflict>,
+    pub stale_generated_files: Vec<StaleFile>,
+    pub recommendations: Vec<Recommendation>,
+    /// Findings hidden by `[ignore]` rules in atlas.toml
+    pub suppressed_recommendations: usize,
+}
+
+impl StatusReport {
+    pub fn new(
+        system_info: &SystemInfo,
+        foreign_build_system: Option<String>,
+        conflicts: &[Conflict],
+        stale: &[(PathBuf, Staleness)],
+        findings: &[Finding],
+        suppressed: usize,
+    ) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            atlas_version: atlas_version(),
+            system: SystemSummary {
+                os: system_info.os.to_string(),
+                arch: system_info.arch.to_string(),
+                cpu_cores: system_info.cpu_cores,
+                rust_version: system_info.rust_version.clone(),
+                cargo_version: system_info.cargo_version.clone(),
+                rustup_components: system_info.rustup_components.clone(),
+            },
+            foreign_build_system,
+            tools: system_info
+                .available_tools
+                .all()
+                .iter()
+                .map(Tool::from)
+                .collect(),
+            rustflags_conflicts: conflicts
+                .iter()
+                .map(|conflict| RustflagsConflict {
+                    explanation: conflict.explanation(),
+                    ignored: conflict
+                        .ignored()
+                        .iter()
+                        .map(|source| source.origin.clone())
+                        .collect(),
+                })
+                .collect(),
+            stale_generated_files: StaleFile::list(stale),
+            recommendations: findings.iter().map(Recommendation::from).collect(),
+            suppressed_recommendations: suppressed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SystemSummary {
+    pub os: String,
+    pub arch: String,
+    pub cpu_cores: usize,
+    pub rust_version: Option<String>,
+    pub cargo_version: Option<String>,
+    /// `None` when rustup does not manage the toolchain
+    #[serde(default)]
+    pub rustup_components: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tool {
+    pub name: String,
+    pub version: Option<String>,
+    pub path: String,
+    pub is_installed: bool,
+}
+
+impl From<&AvailableTool> for Tool {
+    fn from(tool: &AvailableTool) -> Self {
+        Self {
+            name: tool.name.clone(),
+            version: tool.version.clone(),
+            path: tool.path.clone(),
+            is_installed: tool.is_installed,
+        }
+    }
+}
+
+/// Rustflags from one Cargo config layer that override another
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RustflagsConflict {
+    pub explanation: String,
+    /// Where the overridden flags come from
+    pub ignored: Vec<String>,
+}
+
+/// A file with an Atlas-managed block that no longer matches what Atlas would generate
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StaleFile {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+impl StaleFile {
+    pub fn list(stale: &[(PathBuf, Staleness)]) -> Vec<Self> {
+        stale
+            .iter()
+            .map(|(path, staleness)| Self {
+                path: path.clone(),
+                reason: staleness.to_string(),
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recommendation {
+    pub rule: String,
+    pub severity: Severity,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+impl From<&Finding> for Recommendation {
+    fn from(finding: &Finding) -> Self {
+        Self {
+            rule: finding.rule.to_string(),
+            severity: finding.severity,
+            message: finding.message.clone(),
+            hint: finding.hint.clone(),
+        }
+    }
+}
+
+/// `atlas ci affected`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AffectedReport {
+    pub schema_version: u32,
+    pub atlas_version: String,
+    /// Git revision the changes are measured against
+    pub base: String,
+    /// Changed files, relative to the workspace root
+    pub changed_files: Vec<String>,
+    pub changed_packages: Vec<String>,
+    /// Changed files that affect every member, such as Cargo.lock
+    pub workspace_wide_changes: Vec<String>,
+    /// Members to check: the changed ones and everything depending on them
+    pub packages: Vec<String>,
+    pub test_targets: Vec<TestTarget>,
+    /// `-p` arguments selecting `packages`
+    pub cargo_args: Vec<String>,
+}
+
+impl AffectedReport {
+    pub fn new(base: &str) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            atlas_version: atlas_version(),
+            base: base.to_string(),
+            changed_files: Vec::new(),
+            changed_packages: Vec::new(),
+            workspace_wide_changes: Vec::new(),
+            packages: Vec::new(),
+            test_targets: Vec::new(),
+            cargo_args: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestTarget {
+    pub package: String,
+    pub name: String,
+    pub kind: Vec<String>,
+}
+
+/// `atlas build matrix --json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatrixReport {
+    pub schema_version: u32,
+    pub atlas_version: String,
+    pub entries: Vec<MatrixResult>,
+}
+
+impl MatrixReport {
+    pub fn new(entries: Vec<MatrixResult>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            atlas_version: atlas_version(),
+            entries,
+        }
+    }
+}
+
+/// Outcome of one `[[matrix]]` entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatrixResult {
+    pub label: String,
+    /// Cargo arguments it ran with
+    pub cargo_args: Vec<String>,
+    pub status: MatrixStatus,
+    pub duration_secs: f64,
+    pub warnings: usize,
+    pub errors: usize,
+    /// Why it failed
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatrixStatus {
+    Passed,
+    Failed,
+    /// Not started because an earlier entry failed with `--fail-fast`
+    Skipped,
+}
+
+/// `atlas editor status`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EditorStatus {
+    pub schema_version: u32,
+    pub atlas_version: String,
+    pub project_root: PathBuf,
+    /// Whether Atlas generated configuration inside the project
+    pub initialized: bool,
+    pub stale_generated_files: Vec<StaleFile>,
+    pub last_build: Option<LastBuild>,
+    pub suggestion: Suggestion,
+}
+
+impl EditorStatus {
+    pub fn new(
+        project_root: PathBuf,
+        initialized: bool,
+        stale: &[(PathBuf, Staleness)],
+        last_build: Option<LastBuild>,
+        suggestion: Suggestion,
+    ) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            atlas_version: atlas_version(),
+            project_root,
+            initialized,
+            stale_generated_files: StaleFile::list(stale),
+            last_build,
+            suggestion,
+        }
+    }
+}
+
+/// The command an editor should offer next
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Suggestion {
+    pub command: String,
+    pub reason: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn keys(value: &Value) -> Vec<&str> {
+        let mut keys: Vec<&str> = value
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn test_field_names_are_stable() {
+        // Dashboards parse these documents; changing a name here is a breaking
+        // change and needs a SCHEMA_VERSION bump
+        let mut affected = AffectedReport::new("origin/main");
+        affected.test_targets.push(TestTarget {
+            package: "core".to_string(),
+            name: "core".to_string(),
+            kind: vec!["lib".to_string()],
+        });
+        let value = serde_json::to_value(&affected).unwrap();
+        assert_eq!(
+            keys(&value),
+            vec![
+                "atlas_version",
+                "base",
+                "cargo_args",
+                "changed_files",
+                "changed_packages",
+                "packages",
+                "schema_version",
+                "test_targets",
+                "workspace_wide_changes",
+            ]
+        );
+        assert_eq!(value["schema_version"], json!(SCHEMA_VERSION));
+        assert_eq!(
+            keys(&value["test_targets"][0]),
+            vec!["kind", "name", "package"]
+        );
+
+        let system_info = SystemInfo::detect_with(&crate::host::MockHost::linux());
+        let value =
+            serde_json::to_value(StatusReport::new(&system_info, None, &[], &[], &[], 0)).unwrap();
+        assert_eq!(
+            keys(&value),
+            vec![
+                "atlas_version",
+                "foreign_build_system",
+                "recommendations",
+                "rustflags_conflicts",
+                "schema_version",
+                "stale_generated_files",
+                "suppressed_recommendations",
+                "system",
+                "tools",
+            ]
+        );
+        assert_eq!(
+            keys(&value["system"]),
+            vec![
+                "arch",
+                "cargo_version",
+                "cpu_cores",
+                "os",
+                "rust_version",
+                "rustup_components"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_status_round_trip() {
+        let document = json!({
+            "schema_version": 1,
+            "atlas_version": "0.1.0",
+            "system": {
+                "os": "Linux",
+                "arch": "x86_64",
+                "cpu_cores": 8,
+                "rust_version": "rustc 1.79.0",
+                "cargo_version": null,
+                "rustup_components": ["cargo", "clippy", "rustfmt"]
+            },
+            "foreign_build_system": null,
+            "tools": [{ "name": "mold", "version": "mold 2.30.0", "path": "/usr/bin/mold", "is_installed": true }],
+            "rustflags_conflicts": [],
+            "stale_generated_files": [{ "path": "/p/.cargo/config.toml", "reason": "edited by hand" }],
+            "recommendations": [{ "rule": "linker", "severity": "warning", "message": "Use mold" }],
+            "suppressed_recommendations": 2
+        });
+        let report: StatusReport = serde_json::from_value(document.clone()).unwrap();
+        assert_eq!(report.recommendations[0].severity, Severity::Warning);
+        assert_eq!(report.recommendations[0].hint, None);
+        assert_eq!(serde_json::to_value(&report).unwrap(), document);
+    }
+}
@@ -0,0 +1,159 @@
+//! Session logs: each Atlas run writes its command line, log messages and
+//! outcome, redacted, to a file in the Atlas config directory, so
+//! `atlas debug bundle` can include what happened in the last few runs.
+
+use crate::config::OptimizerConfig;
+use crate::error::OptimizerResult;
+use crate::redact;
+use chrono::Utc;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+/// Number of session logs kept; older ones are deleted when a session starts
+pub const KEEP_SESSIONS: usize = 20;
+
+static LOG: OnceLock<Mutex<File>> = OnceLock::new();
+
+pub fn dir() -> OptimizerResult<PathBuf> {
+    Ok(OptimizerConfig::get_config_path()?.with_file_name("sessions"))
+}
+
+/// Open the log of this run and write its header
+pub fn start(args: &[String]) -> OptimizerResult<()> {
+    let dir = dir()?;
+    fs::create_dir_all(&dir)?;
+    let now = Utc::now();
+    let path = dir.join(format!(
+        "{}-{}.log",
+        now.format("%Y%m%d-%H%M%S"),
+        std::process::id()
+    ));
+    let mut file = File::create(path)?;
+    writeln!(
+        file,
+        "atlas {} on {} {}",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    )?;
+    writeln!(file, "started: {}", now.to_rfc3339())?;
+    writeln!(file, "args: {}", redact::text(&args.join(" ")))?;
+    if let Ok(cwd) = std::env::current_dir() {
+        writeln!(file, "cwd: {}", redact::text(&cwd.to_string_lossy()))?;
+    }
+    writeln!(file)?;
+    let _ = LOG.set(Mutex::new(file));
+
+    prune(&dir, KEEP_SESSIONS)?;
+    Ok(())
+}
+
+/// Write the outcome of the run
+pub fn finish(error: Option<&str>, duration: Duration) {
+    let outcome = match error {
+        Some(error) => format!("failed: {}", error),
+        None => "succeeded".to_string(),
+    };
+    write_line(&format!(
+        "\nfinished after {:.1}s: {}",
+        duration.as_secs_f64(),
+        redact::text(&outcome)
+    ));
+}
+
+fn write_line(line: &str) {
+    if let Some(file) = LOG.get() {
+        if let Ok(mut file) = file.lock() {
+            let _ = writeln!(file, "{}", line);
+        }
+    }
+}
+
+/// Newest session logs first, at most `count`
+pub fn recent(count: usize) -> OptimizerResult<Vec<PathBuf>> {
+    let dir = dir()?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut logs = logs_in(&dir)?;
+    logs.reverse();
+    logs.truncate(count);
+    Ok(logs)
+}
+
+/// Session logs oldest first; their names start with the start time
+fn logs_in(dir: &Path) -> OptimizerResult<Vec<PathBuf>> {
+    let mut logs: Vec<PathBuf> = fs::read_dir(dir)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "log"))
+        .collect();
+    logs.sort();
+    Ok(logs)
+}
+
+fn prune(dir: &Path, keep: usize) -> OptimizerResult<()> {
+    let logs = logs_in(dir)?;
+    let excess = logs.len().saturating_sub(keep);
+    for log in &logs[..excess] {
+        fs::remove_file(log)?;
+    }
+    Ok(())
+}
+
+/// Log target that copies every record into the session log, and to stderr
+/// unless Atlas runs quietly
+pub struct SessionWriter {
+    pub echo: bool,
+}
+
+impl Write for SessionWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.echo {
+            io::stderr().write_all(buf)?;
+        }
+        if let Some(file) = LOG.get() {
+            if let Ok(mut file) = file.lock() {
+                file.write_all(redact::text(&String::from_utf8_lossy(buf)).as_bytes())?;
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stderr().flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prune_keeps_newest() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in [
+            "20260101-090000-1.log",
+            "20260102-090000-7.log",
+            "20260103-090000-3.log",
+            "notes.txt",
+        ] {
+            fs::write(dir.path().join(name), "").unwrap();
+        }
+        prune(dir.path(), 2).unwrap();
+
+        let names: Vec<String> = logs_in(dir.path())
+            .unwrap()
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["20260102-090000-7.log", "20260103-090000-3.log"]
+        );
+        assert!(dir.path().join("notes.txt").exists());
+    }
+}
@@ -0,0 +1,180 @@
+use crate::error::OptimizerError;
+use crate::utils::print_error;
+use indicatif::ProgressBar;
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Exit code conventionally used for processes terminated by SIGINT
+pub const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// How long running children get to exit after SIGTERM before being killed
+const CHILD_GRACE_PERIOD: Duration = Duration::from_secs(3);
+
+type CleanupHook = Box<dyn FnOnce() + Send>;
+
+#[derive(Default)]
+struct InterruptState {
+    children: Vec<u32>,
+    spinners: Vec<ProgressBar>,
+    cleanups: HashMap<u64, CleanupHook>,
+}
+
+static STATE: OnceLock<Mutex<InterruptState>> = OnceLock::new();
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+static NEXT_CLEANUP_ID: AtomicU64 = AtomicU64::new(0);
+
+fn state() -> &'static Mutex<InterruptState> {
+    STATE.get_or_init(|| Mutex::new(InterruptState::default()))
+}
+
+fn with_state<R>(f: impl FnOnce(&mut InterruptState) -> R) -> R {
+    let mut guard = state()
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    f(&mut guard)
+}
+
+/// Install the Ctrl-C handler. Must be called from within the tokio runtime.
+pub fn install_handler() {
+    tokio::spawn(async {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            handle_interrupt();
+        }
+    });
+}
+
+/// Whether the user has interrupted the current invocation
+pub fn is_interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Register a spawned child so it is terminated (with its process group) on Ctrl-C
+pub fn track_child(pid: u32) {
+    with_state(|state| state.children.push(pid));
+}
+
+/// Forget a child once it has been waited on
+pub fn untrack_child(pid: u32) {
+    with_state(|state| state.children.retain(|&child| child != pid));
+}
+
+/// Register a spinner so it is cleared before the process exits on Ctrl-C
+pub fn track_spinner(spinner: &ProgressBar) {
+    with_state(|state| {
+        state.spinners.retain(|spinner| !spinner.is_finished());
+        state.spinners.push(spinner.clone());
+    });
+}
+
+/// Guard returned by [`on_interrupt`]; dropping it unregisters the hook
+pub struct CleanupGuard {
+    id: u64,
+}
+
+impl Drop for CleanupGuard {
+    fn drop(&mut self) {
+        with_state(|state| {
+            state.cleanups.remove(&self.id);
+        });
+    }
+}
+
+/// Run `hook` if the user interrupts while the returned guard is alive.
+/// Used to flush partial statistics before exiting.
+pub fn on_interrupt<F>(hook: F) -> CleanupGuard
+where
+    F: FnOnce() + Send + 'static,
+{
+    let id = NEXT_CLEANUP_ID.fetch_add(1, Ordering::SeqCst);
+    with_state(|state| {
+        state.cleanups.insert(id, Box::new(hook));
+    });
+    CleanupGuard { id }
+}
+
+fn handle_interrupt() {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+
+    let (children, spinners, cleanups) = with_state(|state| {
+        (
+            state.children.clone(),
+            std::mem::take(&mut state.spinners),
+            std::mem::take(&mut state.cleanups),
+        )
+    });
+
+    for spinner in spinners {
+        spinner.finish_and_clear();
+    }
+    restore_terminal();
+
+    for pid in &children {
+        terminate_process_group(*pid, false);
+    }
+    wait_for_children(CHILD_GRACE_PERIOD);
+    for pid in with_state(|state| state.children.clone()) {
+        terminate_process_group(pid, true);
+    }
+
+    for (_, cleanup) in cleanups {
+        cleanup();
+    }
+
+    print_error(&OptimizerError::Cancelled.user_message());
+    std::process::exit(INTERRUPTED_EXIT_CODE);
+}
+
+/// Terminate a tracked child's process group, killing it if it is still
+/// running after the grace period. Used by the build timeout watchdog.
+pub fn terminate_gracefully(pid: u32) {
+    terminate_process_group(pid, false);
+    let start = Instant::now();
+    while start.elapsed() < CHILD_GRACE_PERIOD {
+        if !with_state(|state| state.children.contains(&pid)) {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    terminate_process_group(pid, true);
+}
+
+fn wait_for_children(timeout: Duration) {
+    let start = Instant::now();
+    while start.elapsed() < timeout {
+        if with_state(|state| state.children.is_empty()) {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// Show the cursor again and clear any half-drawn spinner line
+fn restore_terminal() {
+    let mut stderr = std::io::stderr();
+    let _ = write!(stderr, "\r\x1b[2K\x1b[?25h");
+    let _ = stderr.flush();
+    let _ = std::io::stdout().flush();
+}
+
+#[cfg(unix)]
+fn terminate_process_group(pid: u32, force: bool) {
+    let signal = if force { libc::SIGKILL } else { libc::SIGTERM };
+    // Tracked children are spawned as process group leaders, so the negated
+    // pid addresses cargo together with every rustc/linker it started.
+    unsafe {
+        libc::kill(-(pid as libc::pid_t), signal);
+    }
+}
+
+#[cfg(windows)]
+fn terminate_process_group(pid: u32, _force: bool) {
+    let _ = std::process::Command::new("taskkill")
+        .args(["/T", "/F", "/PID", &pid.to_string()])
+        .output();
+}
+
+#[cfg(not(any(unix, windows)))]
+fn terminate_process_group(_pid: u32, _force: bool) {}
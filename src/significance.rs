@@ -0,0 +1,250 @@
+//! Repeated measurements and whether two sets of them differ
+//!
+//! Build times vary from run to run by several percent, so one run before and
+//! one after a change cannot show whether it helped. Samples are trimmed of
+//! outliers with Tukey's fences, and two sets are compared with a Welch
+//! confidence interval for the difference of their means: only when the
+//! interval excludes zero is one side declared faster.
+
+use std::fmt;
+
+/// Durations of one step in seconds, in the order they were measured
+#[derive(Debug, Clone, PartialEq)]
+pub struct Samples(Vec<f64>);
+
+impl Samples {
+    pub fn new(values: Vec<f64>) -> Self {
+        Self(values)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Drop values outside 1.5 interquartile ranges of the quartiles; fewer
+    /// than four values are kept as they are
+    pub fn without_outliers(&self) -> Self {
+        if self.0.len() < 4 {
+            return self.clone();
+        }
+        let sorted = self.sorted();
+        let (q1, q3) = (quantile(&sorted, 0.25), quantile(&sorted, 0.75));
+        let fence = 1.5 * (q3 - q1);
+        Self(
+            self.0
+                .iter()
+                .copied()
+                .filter(|value| (q1 - fence..=q3 + fence).contains(value))
+                .collect(),
+        )
+    }
+
+    pub fn median(&self) -> Option<f64> {
+        (!self.0.is_empty()).then(|| quantile(&self.sorted(), 0.5))
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (!self.0.is_empty()).then(|| self.0.iter().sum::<f64>() / self.0.len() as f64)
+    }
+
+    /// Sample variance; needs two values
+    fn variance(&self) -> Option<f64> {
+        let mean = self.mean()?;
+        (self.0.len() > 1).then(|| {
+            self.0
+                .iter()
+                .map(|value| (value - mean).powi(2))
+                .sum::<f64>()
+                / (self.0.len() - 1) as f64
+        })
+    }
+
+    fn sorted(&self) -> Vec<f64> {
+        let mut sorted = self.0.clone();
+        sorted.sort_by(f64::total_cmp);
+        sorted
+    }
+}
+
+/// Linear interpolation between the closest ranks of sorted values
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let position = q * (sorted.len() - 1) as f64;
+    let (lower, upper) = (position.floor() as usize, position.ceil() as usize);
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower as f64)
+}
+
+/// Outcome of comparing a step before and after a change
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Verdict {
+    Faster,
+    Slower,
+    /// The confidence interval includes no change
+    NoDifference,
+    /// One side has fewer than two runs
+    TooFewRuns,
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Faster => write!(f, "faster"),
+            Self::Slower => write!(f, "slower"),
+            Self::NoDifference => write!(f, "no significant difference"),
+            Self::TooFewRuns => write!(f, "too few runs to tell"),
+        }
+    }
+}
+
+/// Difference between two sets of samples
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    /// Mean durations in seconds before and after
+    pub means: (f64, f64),
+    /// Change of the mean in percent of the mean before
+    pub change: f64,
+    /// Confidence interval of that change, when both sides have two runs
+    pub interval: Option<(f64, f64)>,
+    pub verdict: Verdict,
+}
+
+/// Compare outlier-free samples at `confidence`, e.g. 0.95
+pub fn compare(before: &Samples, after: &Samples, confidence: f64) -> Option<Comparison> {
+    let (mean_before, mean_after) = (before.mean()?, after.mean()?);
+    if mean_before <= 0.0 {
+        return None;
+    }
+    let percent = |seconds: f64| seconds / mean_before * 100.0;
+    let change = percent(mean_after - mean_before);
+
+    let (Some(var_before), Some(var_after)) = (before.variance(), after.variance()) else {
+        return Some(Comparison {
+            means: (mean_before, mean_after),
+            change,
+            interval: None,
+            verdict: Verdict::TooFewRuns,
+        });
+    };
+    let (n_before, n_after) = (before.len() as f64, after.len() as f64);
+    let (se_before, se_after) = (var_before / n_before, var_after / n_after);
+    let standard_error = (se_before + se_after).sqrt();
+    // Welch–Satterthwaite degrees of freedom
+    let freedom = if standard_error > 0.0 {
+        (se_before + se_after).powi(2)
+            / (se_before.powi(2) / (n_before - 1.0) + se_after.powi(2) / (n_after - 1.0))
+    } else {
+        n_before + n_after - 2.0
+    };
+    let margin = student_t_quantile(1.0 - (1.0 - confidence) / 2.0, freedom) * standard_error;
+    let difference = mean_after - mean_before;
+    let interval = (percent(difference - margin), percent(difference + margin));
+    let verdict = if interval.1 < 0.0 {
+        Verdict::Faster
+    } else if interval.0 > 0.0 {
+        Verdict::Slower
+    } else {
+        Verdict::NoDifference
+    };
+    Some(Comparison {
+        means: (mean_before, mean_after),
+        change,
+        interval: Some(interval),
+        verdict,
+    })
+}
+
+/// Quantile of Student's t distribution, from the normal quantile with the
+/// Cornish–Fisher expansion; within 1% of the exact value from 3 degrees of freedom
+fn student_t_quantile(p: f64, freedom: f64) -> f64 {
+    let z = normal_quantile(p);
+    let v = freedom.max(1.0);
+    let (z3, z5, z7) = (z.powi(3), z.powi(5), z.powi(7));
+    z + (z3 + z) / (4.0 * v)
+        + (5.0 * z5 + 16.0 * z3 + 3.0 * z) / (96.0 * v.powi(2))
+        + (3.0 * z7 + 19.0 * z5 + 17.0 * z3 - 15.0 * z) / (384.0 * v.powi(3))
+}
+
+/// Quantile of the standard normal distribution (Acklam's approximation)
+fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969_683_028_665_376e1,
+        2.209_460_984_245_205e2,
+        -2.759_285_104_469_687e2,
+        1.383_577_518_672_69e2,
+        -3.066_479_806_614_716e1,
+        2.506_628_277_459_239,
+    ];
+    const B: [f64; 5] = [
+        -5.447_609_879_822_406e1,
+        1.615_858_368_580_409e2,
+        -1.556_989_798_598_866e2,
+        6.680_131_188_771_972e1,
+        -1.328_068_155_288_572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784_894_002_430_293e-3,
+        -3.223_964_580_411_365e-1,
+        -2.400_758_277_161_838,
+        -2.549_732_539_343_734,
+        4.374_664_141_464_968,
+        2.938_163_982_698_783,
+    ];
+    const D: [f64; 4] = [
+        7.784_695_709_041_462e-3,
+        3.224_671_290_700_398e-1,
+        2.445_134_137_142_996,
+        3.754_408_661_907_416,
+    ];
+    let p = p.clamp(1e-12, 1.0 - 1e-12);
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+    if p < 0.02425 {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - 0.02425 {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outliers_and_quantiles() {
+        let samples = Samples::new(vec![10.0, 10.4, 9.8, 10.1, 31.0]);
+        let trimmed = samples.without_outliers();
+        assert_eq!(trimmed.len(), 4);
+        assert!((trimmed.median().unwrap() - 10.05).abs() < 1e-9);
+        assert_eq!(Samples::new(vec![1.0, 50.0]).without_outliers().len(), 2);
+
+        assert!((normal_quantile(0.975) - 1.959_964).abs() < 1e-4);
+        // Exact values: 4.303 for 2, 2.228 for 10 degrees of freedom
+        assert!((student_t_quantile(0.975, 10.0) - 2.228).abs() < 0.01);
+        assert!((student_t_quantile(0.975, 2.0) - 4.303).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_compare_declares_winner_only_when_significant() {
+        let before = Samples::new(vec![10.0, 10.2, 9.9, 10.1, 10.0]);
+        let faster = Samples::new(vec![8.0, 8.1, 7.9, 8.2, 8.0]);
+        let comparison = compare(&before, &faster, 0.95).unwrap();
+        assert_eq!(comparison.verdict, Verdict::Faster);
+        assert!((comparison.change + 19.92).abs() < 0.01);
+
+        let noisy = Samples::new(vec![9.0, 11.5, 8.7, 11.0, 9.6]);
+        assert_eq!(
+            compare(&before, &noisy, 0.95).unwrap().verdict,
+            Verdict::NoDifference
+        );
+        let single = Samples::new(vec![8.0]);
+        let comparison = compare(&before, &single, 0.95).unwrap();
+        assert_eq!(comparison.verdict, Verdict::TooFewRuns);
+        assert_eq!(comparison.interval, None);
+    }
+}
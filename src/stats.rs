@@ -0,0 +1,488 @@
+use crate::config::OptimizerConfig;
+use crate::error::OptimizerResult;
+use crate::fingerprint::Fingerprint;
+use crate::utils::{execute_command, is_tool_available};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Compilation statistics gathered from cargo's JSON message stream
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CompilationStats {
+    /// Packages with at least one unit rebuilt during this run
+    pub crates_compiled: usize,
+    /// Packages whose units were all reused from previous builds
+    pub crates_fresh: usize,
+    pub warnings: usize,
+    pub errors: usize,
+    pub success: Option<bool>,
+    /// Errors in the order rustc reported them, for failure triage
+    #[serde(skip)]
+    pub error_diagnostics: Vec<ErrorDiagnostic>,
+    /// Target of the most recent compiler artifact
+    #[serde(skip)]
+    pub last_artifact: Option<String>,
+    #[serde(skip)]
+    compiled: HashSet<String>,
+    #[serde(skip)]
+    fresh: HashSet<String>,
+    #[serde(skip)]
+    seen_diagnostics: HashSet<String>,
+}
+
+/// An error reported through cargo's JSON messages
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorDiagnostic {
+    /// Name of the target being compiled, e.g. the crate name
+    pub target: Option<String>,
+    /// `error`, or `error: internal compiler error` for compiler crashes
+    pub level: String,
+    pub message: String,
+    pub rendered: String,
+}
+
+impl CompilationStats {
+    /// Record a single line of `--message-format=json` output.
+    ///
+    /// Returns the rendered diagnostic (if any) so callers can still show
+    /// the usual compiler output to the user.
+    pub fn record_message(&mut self, line: &str) -> Option<String> {
+        let message: Value = serde_json::from_str(line).ok()?;
+
+        match message.get("reason").and_then(Value::as_str)? {
+            "compiler-artifact" => {
+                let package_id = message.get("package_id").and_then(Value::as_str)?;
+                let fresh = message
+                    .get("fresh")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false);
+                self.last_artifact = message
+                    .get("target")
+                    .and_then(|target| target.get("name"))
+                    .and_then(Value::as_str)
+                    .map(str::to_string);
+
+                if fresh {
+                    if !self.compiled.contains(package_id) {
+                        self.fresh.insert(package_id.to_string());
+                    }
+                } else {
+                    self.fresh.remove(package_id);
+                    self.compiled.insert(package_id.to_string());
+                }
+                self.crates_compiled = self.compiled.len();
+                self.crates_fresh = self.fresh.len();
+                None
+            }
+            "compiler-message" => {
+                let diagnostic = message.get("message")?;
+                let rendered = diagnostic
+                    .get("rendered")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string();
+
+                // cargo prints its own per-crate summary on stderr
+                if is_summary_diagnostic(diagnostic) {
+                    return None;
+                }
+
+                // The same diagnostic is reported once per unit (lib, test, bin)
+                if !self.seen_diagnostics.insert(rendered.clone()) {
+                    return None;
+                }
+
+                match diagnostic.get("level").and_then(Value::as_str) {
+                    Some("warning") => self.warnings += 1,
+                    Some(level @ ("error" | "error: internal compiler error")) => {
+                        self.errors += 1;
+                        self.error_diagnostics.push(ErrorDiagnostic {
+                            target: message
+                                .get("target")
+                                .and_then(|target| target.get("name"))
+                                .and_then(Value::as_str)
+                                .map(str::to_string),
+                            level: level.to_string(),
+                            message: diagnostic
+                                .get("message")
+                                .and_then(Value::as_str)
+                                .unwrap_or_default()
+                                .to_string(),
+                            rendered: rendered.clone(),
+                        });
+                    }
+                    _ => {}
+                }
+                Some(rendered)
+            }
+            "build-finished" => {
+                self.success = message.get("success").and_then(Value::as_bool);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Total number of packages seen during the run
+    pub fn total_crates(&self) -> usize {
+        self.crates_compiled + self.crates_fresh
+    }
+
+    /// Percentage of packages reused without recompilation
+    pub fn reuse_percentage(&self) -> f64 {
+        if self.total_crates() == 0 {
+            0.0
+        } else {
+            self.crates_fresh as f64 / self.total_crates() as f64 * 100.0
+        }
+    }
+
+    /// Why the run had to compile anything, judged by which packages it rebuilt
+    pub fn rebuild_cause(&self) -> RebuildCause {
+        if self.compiled.is_empty() {
+            RebuildCause::UpToDate
+        } else if self.compiled.iter().all(|id| is_local_package(id)) {
+            RebuildCause::WorkspaceChanges
+        } else if self.fresh.is_empty() {
+            RebuildCause::Cold
+        } else {
+            RebuildCause::DependencyChanges
+        }
+    }
+}
+
+/// Path dependencies and workspace members, in both package id formats:
+/// `app 0.1.0 (path+file:///ws/app)` and `path+file:///ws/app#0.1.0`
+fn is_local_package(package_id: &str) -> bool {
+    package_id.contains("path+file://")
+}
+
+/// What made a build compile
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RebuildCause {
+    /// Nothing was compiled
+    UpToDate,
+    /// Only workspace members and path dependencies were rebuilt
+    WorkspaceChanges,
+    /// Registry or git dependencies were rebuilt alongside reused ones, e.g.
+    /// after a lockfile update or changed flags
+    DependencyChanges,
+    /// Nothing could be reused: a clean target directory or a new toolchain
+    Cold,
+}
+
+impl std::fmt::Display for RebuildCause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            Self::UpToDate => "up to date",
+            Self::WorkspaceChanges => "workspace changes",
+            Self::DependencyChanges => "dependency changes",
+            Self::Cold => "cold builds",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+/// One `atlas build` or `atlas check` run, kept in the stats store
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildRecord {
+    pub recorded_at: DateTime<Utc>,
+    pub project: PathBuf,
+    pub command: String,
+    pub success: bool,
+    pub duration_secs: f64,
+    pub crates_compiled: usize,
+    pub crates_fresh: usize,
+    pub cause: RebuildCause,
+    /// sccache counters accumulated during the run, when sccache was running
+    pub sccache: Option<SccacheSnapshot>,
+    /// Environment the build ran in; missing in records from older versions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<Fingerprint>,
+    /// Cargo profile built; missing in records from older versions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+}
+
+impl BuildRecord {
+    pub fn new(
+        project: &Path,
+        command: &str,
+        success: bool,
+        duration_secs: f64,
+        compilation: &CompilationStats,
+        sccache: Option<SccacheSnapshot>,
+        fingerprint: Option<Fingerprint>,
+    ) -> Self {
+        Self {
+            recorded_at: Utc::now(),
+            project: fs::canonicalize(project).unwrap_or_else(|_| project.to_path_buf()),
+            command: command.to_string(),
+            success,
+            duration_secs,
+            crates_compiled: compilation.crates_compiled,
+            crates_fresh: compilation.crates_fresh,
+            cause: compilation.rebuild_cause(),
+            sccache,
+            fingerprint,
+            profile: None,
+        }
+    }
+}
+
+/// Build records of every project on this machine, one JSON line per run
+pub struct StatsStore;
+
+impl StatsStore {
+    pub fn path() -> OptimizerResult<PathBuf> {
+        Ok(OptimizerConfig::get_config_path()?.with_file_name("build-stats.jsonl"))
+    }
+
+    pub fn append(record: &BuildRecord) -> OptimizerResult<()> {
+        let path = Self::path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", serde_json::to_string(record)?)?;
+        Ok(())
+    }
+
+    /// Records made at or after `since`; lines that do not parse are skipped
+    pub fn load_since(since: DateTime<Utc>) -> OptimizerResult<Vec<BuildRecord>> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        Ok(fs::read_to_string(path)?
+            .lines()
+            .filter_map(|line| serde_json::from_str::<BuildRecord>(line).ok())
+            .filter(|record| record.recorded_at >= since)
+            .collect())
+    }
+
+    /// Drop records made before `since` and lines that do not parse,
+    /// returning how many records are kept and how many lines are dropped
+    pub fn compact(since: DateTime<Utc>) -> OptimizerResult<(usize, usize)> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok((0, 0));
+        }
+        let content = fs::read_to_string(&path)?;
+        let lines: Vec<&str> = content.lines().filter(|line| !line.is_empty()).collect();
+        let kept: Vec<&str> = lines
+            .iter()
+            .copied()
+            .filter(|line| {
+                serde_json::from_str::<BuildRecord>(line)
+                    .is_ok_and(|record| record.recorded_at >= since)
+            })
+            .collect();
+        let dropped = lines.len() - kept.len();
+        if dropped > 0 {
+            // Written next to the store and renamed over it, so a build
+            // appending meanwhile never sees half a file
+            let partial = path.with_extension("jsonl.partial");
+            let mut content = kept.join("\n");
+            if !content.is_empty() {
+                content.push('\n');
+            }
+            fs::write(&partial, content)?;
+            fs::rename(&partial, &path)?;
+        }
+        Ok((kept.len(), dropped))
+    }
+}
+
+/// rustc emits "N warnings emitted" / "aborting due to" summaries as diagnostics
+fn is_summary_diagnostic(diagnostic: &Value) -> bool {
+    let has_spans = diagnostic
+        .get("spans")
+        .and_then(Value::as_array)
+        .map(|spans| !spans.is_empty())
+        .unwrap_or(false);
+    let text = diagnostic
+        .get("message")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+
+    !has_spans
+        && (text.ends_with("emitted")
+            || text.starts_with("aborting due to")
+            || text.starts_with("Some errors have detailed explanations")
+            || text.starts_with("For more information about"))
+}
+
+/// Point-in-time sccache counters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct SccacheSnapshot {
+    pub requests: u64,
+    pub hits: u64,
+    pub misses: u64,
+    pub errors: u64,
+}
+
+impl SccacheSnapshot {
+    /// Capture current counters from the running sccache server
+    pub fn capture() -> Option<Self> {
+        if !is_tool_available("sccache") {
+            return None;
+        }
+
+        let output =
+            execute_command("sccache", &["--show-stats", "--stats-format=json"], None).ok()?;
+        if !output.status.success() {
+            return None;
+        }
+
+        Self::parse(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /// Parse `sccache --show-stats --stats-format=json` output
+    pub fn parse(json: &str) -> Option<Self> {
+        let value: Value = serde_json::from_str(json).ok()?;
+        let stats = value.get("stats")?;
+
+        Some(Self {
+            requests: stats
+                .get("compile_requests")
+                .and_then(Value::as_u64)
+                .unwrap_or(0),
+            hits: sum_language_counts(stats.get("cache_hits")),
+            misses: sum_language_counts(stats.get("cache_misses")),
+            errors: sum_language_counts(stats.get("cache_errors")),
+        })
+    }
+
+    /// Capture counters now and return what changed since `before`
+    pub fn delta_from(before: Option<Self>) -> Option<Self> {
+        Some(Self::capture()?.delta_since(&before?))
+    }
+
+    /// Counters accumulated between `before` and `self`. Counters lower than
+    /// before mean the server restarted in between and counts from zero again,
+    /// so everything it reports happened since.
+    pub fn delta_since(&self, before: &Self) -> Self {
+        let restarted = self.requests < before.requests
+            || self.hits < before.hits
+            || self.misses < before.misses
+            || self.errors < before.errors;
+        if restarted {
+            return *self;
+        }
+        Self {
+            requests: self.requests.saturating_sub(before.requests),
+            hits: self.hits.saturating_sub(before.hits),
+            misses: self.misses.saturating_sub(before.misses),
+            errors: self.errors.saturating_sub(before.errors),
+        }
+    }
+
+    /// Hit rate over cacheable lookups (hits + misses)
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64 * 100.0
+        }
+    }
+}
+
+/// sccache reports per-language counters as `{"counts": {"Rust": n, ...}}`
+fn sum_language_counts(value: Option<&Value>) -> u64 {
+    value
+        .and_then(|value| value.get("counts"))
+        .and_then(Value::as_object)
+        .map(|counts| counts.values().filter_map(Value::as_u64).sum())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_artifacts_and_diagnostics() {
+        let mut stats = CompilationStats::default();
+        let lines = [
+            r#"{"reason":"compiler-artifact","package_id":"dep 1.0","fresh":true}"#,
+            r#"{"reason":"compiler-artifact","package_id":"app 0.1","fresh":true}"#,
+            r#"{"reason":"compiler-message","package_id":"app 0.1","message":{"level":"warning","message":"unused variable","rendered":"warning: unused variable `x`","spans":[{}]}}"#,
+            r#"{"reason":"compiler-message","package_id":"app 0.1","message":{"level":"warning","message":"unused variable","rendered":"warning: unused variable `x`","spans":[{}]}}"#,
+            r#"{"reason":"compiler-message","package_id":"app 0.1","message":{"level":"warning","message":"1 warning emitted","rendered":"warning: 1 warning emitted","spans":[]}}"#,
+            r#"{"reason":"compiler-artifact","package_id":"app 0.1","fresh":false}"#,
+            r#"{"reason":"build-finished","success":true}"#,
+        ];
+        for line in lines {
+            stats.record_message(line);
+        }
+
+        assert_eq!(stats.crates_compiled, 1);
+        assert_eq!(stats.crates_fresh, 1);
+        assert_eq!(stats.warnings, 1);
+        assert_eq!(stats.errors, 0);
+        assert_eq!(stats.success, Some(true));
+    }
+
+    #[test]
+    fn test_rebuild_cause() {
+        let record = |lines: &[&str]| {
+            let mut stats = CompilationStats::default();
+            for line in lines {
+                stats.record_message(line);
+            }
+            stats.rebuild_cause()
+        };
+        let dep_fresh = r#"{"reason":"compiler-artifact","package_id":"registry+https://github.com/rust-lang/crates.io-index#serde@1.0.200","fresh":true}"#;
+        let dep_built = r#"{"reason":"compiler-artifact","package_id":"registry+https://github.com/rust-lang/crates.io-index#serde@1.0.200","fresh":false}"#;
+        let app_fresh = r#"{"reason":"compiler-artifact","package_id":"path+file:///ws/app#0.1.0","fresh":true}"#;
+        let app_built = r#"{"reason":"compiler-artifact","package_id":"path+file:///ws/app#0.1.0","fresh":false}"#;
+
+        assert_eq!(record(&[dep_fresh, app_fresh]), RebuildCause::UpToDate);
+        assert_eq!(
+            record(&[dep_fresh, app_built]),
+            RebuildCause::WorkspaceChanges
+        );
+        assert_eq!(
+            record(&[dep_built, app_fresh]),
+            RebuildCause::DependencyChanges
+        );
+        assert_eq!(record(&[dep_built, app_built]), RebuildCause::Cold);
+    }
+
+    #[test]
+    fn test_sccache_delta() {
+        let before = SccacheSnapshot::parse(
+            r#"{"stats":{"compile_requests":10,"cache_hits":{"counts":{"Rust":4}},"cache_misses":{"counts":{"Rust":6}}}}"#,
+        )
+        .unwrap();
+        let after = SccacheSnapshot::parse(
+            r#"{"stats":{"compile_requests":25,"cache_hits":{"counts":{"Rust":12,"C/C++":1}},"cache_misses":{"counts":{"Rust":12}},"cache_errors":{"counts":{"Rust":1}}}}"#,
+        )
+        .unwrap();
+
+        let delta = after.delta_since(&before);
+        assert_eq!(delta.requests, 15);
+        assert_eq!(delta.hits, 9);
+        assert_eq!(delta.misses, 6);
+        assert_eq!(delta.errors, 1);
+        assert_eq!(delta.hit_rate(), 60.0);
+
+        // The server restarted during the build and counts from zero again
+        let restarted = SccacheSnapshot::parse(
+            r#"{"stats":{"compile_requests":5,"cache_hits":{"counts":{"Rust":3}},"cache_misses":{"counts":{"Rust":2}}}}"#,
+        )
+        .unwrap();
+        let delta = restarted.delta_since(&after);
+        assert_eq!(delta.requests, 5);
+        assert_eq!(delta.hits, 3);
+        assert_eq!(delta.misses, 2);
+        assert_eq!(delta.errors, 0);
+    }
+}
@@ -0,0 +1,151 @@
+//! Terminal styling. Colored and emoji-decorated output goes through these
+//! helpers so `--no-color`, `NO_COLOR` and `--no-emoji` apply everywhere:
+//! colors are switched off for Atlas and the cargo commands it runs, and
+//! emoji are dropped for screen readers and plain log collectors.
+
+pub use colored::ColoredString;
+use colored::Colorize;
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static EMOJI: AtomicBool = AtomicBool::new(true);
+
+/// Apply the output preferences of this run
+pub fn init(no_color: bool, no_emoji: bool) {
+    let no_color = no_color || std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    if no_color {
+        colored::control::set_override(false);
+        // Child processes: cargo reads CARGO_TERM_COLOR, most other tools NO_COLOR
+        std::env::set_var("NO_COLOR", "1");
+        if std::env::var_os("CARGO_TERM_COLOR").is_none() {
+            std::env::set_var("CARGO_TERM_COLOR", "never");
+        }
+    }
+    EMOJI.store(!no_emoji, Ordering::Relaxed);
+}
+
+pub fn emoji_enabled() -> bool {
+    EMOJI.load(Ordering::Relaxed)
+}
+
+/// Pictographs, dingbats and the joiners and selectors that combine them;
+/// punctuation such as `•` and `→` stays
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1F000..=0x1FAFF | 0x2600..=0x27BF | 0x2B00..=0x2BFF | 0x23E9..=0x23FA | 0xFE0F | 0x200D | 0x20E3
+    )
+}
+
+/// `text` without emoji, and without the spaces that set them apart
+fn strip_emoji(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if is_emoji(c) {
+            while chars
+                .next_if(|next| *next == ' ' || is_emoji(*next))
+                .is_some()
+            {}
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+/// Plain text as it should be shown: emoji removed with `--no-emoji`
+pub fn text(text: &str) -> Cow<'_, str> {
+    if emoji_enabled() || !text.chars().any(is_emoji) {
+        Cow::Borrowed(text)
+    } else {
+        Cow::Owned(strip_emoji(text))
+    }
+}
+
+/// Heading of a command's output
+pub fn title(text: &str) -> ColoredString {
+    self::text(text).bright_blue().bold()
+}
+
+/// Heading of a group of results
+pub fn section(text: &str) -> ColoredString {
+    self::text(text).bright_green().bold()
+}
+
+/// Heading of something that needs attention
+pub fn attention(text: &str) -> ColoredString {
+    self::text(text).bright_yellow().bold()
+}
+
+/// Heading of reference material, e.g. explanations and onboarding
+pub fn topic(text: &str) -> ColoredString {
+    self::text(text).bright_cyan().bold()
+}
+
+/// Commands, settings, crate and tool names
+pub fn code(text: &str) -> ColoredString {
+    self::text(text).bright_cyan()
+}
+
+pub fn good(text: &str) -> ColoredString {
+    self::text(text).bright_green()
+}
+
+pub fn bad(text: &str) -> ColoredString {
+    self::text(text).bright_red()
+}
+
+pub fn caution(text: &str) -> ColoredString {
+    self::text(text).bright_yellow()
+}
+
+pub fn info(text: &str) -> ColoredString {
+    self::text(text).bright_blue()
+}
+
+pub fn alert(text: &str) -> ColoredString {
+    self::text(text).bright_red().bold()
+}
+
+pub fn strong(text: &str) -> ColoredString {
+    self::text(text).bold()
+}
+
+/// Unstyled, for values that only sometimes stand out
+pub fn plain(text: &str) -> ColoredString {
+    self::text(text).normal()
+}
+
+/// Secondary details
+pub fn dim(text: &str) -> ColoredString {
+    self::text(text).bright_black()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_emoji() {
+        assert_eq!(strip_emoji("✅ Build completed"), "Build completed");
+        assert_eq!(
+            strip_emoji("⚠️  Rustflags Conflicts"),
+            "Rustflags Conflicts"
+        );
+        assert_eq!(strip_emoji("  ⏱️  Duration: 3s"), "  Duration: 3s");
+        assert_eq!(
+            strip_emoji("   • sccache - ❌ Not installed"),
+            "   • sccache - Not installed"
+        );
+        assert_eq!(strip_emoji("a → b"), "a → b");
+    }
+
+    #[test]
+    fn test_colors_off() {
+        colored::control::set_override(false);
+        assert_eq!(title("Status").to_string(), "Status");
+        assert_eq!(code("atlas refresh").to_string(), "atlas refresh");
+        colored::control::unset_override();
+    }
+}
@@ -1,7 +1,11 @@
-use crate::error::OptimizerResult;
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::host::{CommandRunner, Host, SystemProbe};
+use crate::power::PowerState;
+use crate::system_cache;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::process::Command;
-use which::which;
+use std::sync::Mutex;
+use std::thread;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SystemInfo {
@@ -10,7 +14,20 @@ pub struct SystemInfo {
     pub cpu_cores: usize,
     pub rust_version: Option<String>,
     pub cargo_version: Option<String>,
-    pub available_tools: Vec<AvailableTool>,
+    /// LLVM version rustc was built against, from `rustc -vV`
+    #[serde(default)]
+    pub rust_llvm_version: Option<String>,
+    /// Package manager found on Linux; other platforms have a fixed one
+    #[serde(default)]
+    pub linux_package_manager: Option<String>,
+    /// Tools probed so far
+    pub available_tools: Tools,
+    /// Components of the active rustup toolchain; `None` without rustup
+    #[serde(default)]
+    pub rustup_components: Option<Vec<String>>,
+    /// Battery or AC, and whether a low power mode is on
+    #[serde(default)]
+    pub power: PowerState,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -37,21 +54,76 @@ pub struct AvailableTool {
 }
 
 impl SystemInfo {
+    /// Detect the machine, reusing the on-disk cache while it is valid.
+    /// The power state is always read afresh, and tools are probed when
+    /// first looked up.
     pub fn detect() -> OptimizerResult<Self> {
-        let os = detect_os();
-        let arch = detect_architecture();
-        let cpu_cores = detect_cpu_cores();
-        let rust_version = detect_rust_version();
-        let cargo_version = detect_cargo_version();
-        let available_tools = detect_available_tools();
-
-        Ok(SystemInfo {
-            os,
-            arch,
-            cpu_cores,
-            rust_version,
-            cargo_version,
-            available_tools,
+        let key = system_cache::key(&std::env::current_dir()?);
+        if let Some(mut info) = system_cache::load(&key) {
+            info.power = PowerState::detect(&info.os, &Host);
+            info.available_tools = Tools::on_demand(info.available_tools.all(), key);
+            return Ok(info);
+        }
+        Ok(Self::refresh_with_key(key))
+    }
+
+    /// Detect the machine without the cache and store the result, e.g. after
+    /// installing tools
+    pub fn refresh() -> OptimizerResult<Self> {
+        Ok(Self::refresh_with_key(system_cache::key(
+            &std::env::current_dir()?,
+        )))
+    }
+
+    fn refresh_with_key(key: String) -> Self {
+        let mut info = Self::detect_toolchain(&Host);
+        if let Err(e) = system_cache::store(&info, &key) {
+            log::debug!("Could not cache system detection: {}", e);
+        }
+        info.available_tools = Tools::on_demand(Vec::new(), key);
+        info
+    }
+
+    /// Detect through `host`, probing every tool upfront; tests pass a
+    /// `MockHost`
+    pub fn detect_with<H: SystemProbe + CommandRunner + Sync>(host: &H) -> Self {
+        Self {
+            available_tools: probe_tools(host, DETECTED_TOOLS).into(),
+            ..Self::detect_toolchain(host)
+        }
+    }
+
+    /// Everything but the tools. Version probes run in parallel, as each
+    /// starts a process.
+    fn detect_toolchain<H: SystemProbe + CommandRunner + Sync>(host: &H) -> Self {
+        let os = host.os();
+        let linux_package_manager = match os {
+            OperatingSystem::Linux => LINUX_PACKAGE_MANAGERS
+                .iter()
+                .find(|(program, _)| host.find_program(program).is_some())
+                .map(|(_, name)| name.to_string()),
+            _ => None,
+        };
+
+        thread::scope(|scope| {
+            let rust_version = scope.spawn(|| first_line(host, "rustc", &["--version"]));
+            let cargo_version = scope.spawn(|| first_line(host, "cargo", &["--version"]));
+            let rust_llvm_version = scope.spawn(|| rustc_verbose_field(host, "LLVM version"));
+            let power = scope.spawn(|| PowerState::detect(&os, host));
+            let rustup_components = scope.spawn(|| rustup_components(host));
+
+            SystemInfo {
+                os: os.clone(),
+                arch: host.arch(),
+                cpu_cores: host.cpu_cores(),
+                rust_version: rust_version.join().unwrap_or_default(),
+                cargo_version: cargo_version.join().unwrap_or_default(),
+                rust_llvm_version: rust_llvm_version.join().unwrap_or_default(),
+                linux_package_manager,
+                available_tools: Tools::default(),
+                rustup_components: rustup_components.join().unwrap_or_default(),
+                power: power.join().unwrap_or_default(),
+            }
         })
     }
 
@@ -69,16 +141,11 @@ impl SystemInfo {
         match self.os {
             OperatingSystem::MacOS => Some("brew"),
             OperatingSystem::Linux => {
-                // Try to detect Linux distribution
-                if which("apt-get").is_ok() {
-                    Some("apt")
-                } else if which("yum").is_ok() {
-                    Some("yum")
-                } else if which("pacman").is_ok() {
-                    Some("pacman")
-                } else {
-                    None
-                }
+                let detected = self.linux_package_manager.as_deref()?;
+                LINUX_PACKAGE_MANAGERS
+                    .iter()
+                    .map(|(_, name)| *name)
+                    .find(|name| *name == detected)
             }
             OperatingSystem::Windows => Some("winget"),
             _ => None,
@@ -89,8 +156,8 @@ impl SystemInfo {
         self.get_recommended_linker().is_some()
     }
 
-    pub fn get_tool(&self, name: &str) -> Option<&AvailableTool> {
-        self.available_tools.iter().find(|tool| tool.name == name)
+    pub fn get_tool(&self, name: &str) -> Option<AvailableTool> {
+        self.available_tools.get(name)
     }
 
     pub fn is_tool_installed(&self, name: &str) -> bool {
@@ -98,125 +165,299 @@ impl SystemInfo {
             .map(|tool| tool.is_installed)
             .unwrap_or(false)
     }
-}
 
-fn detect_os() -> OperatingSystem {
-    match std::env::consts::OS {
-        "macos" => OperatingSystem::MacOS,
-        "linux" => OperatingSystem::Linux,
-        "windows" => OperatingSystem::Windows,
-        other => OperatingSystem::Unknown(other.to_string()),
+    /// Verify that clang, lld and llvm-ar share rustc's LLVM major version, which
+    /// linker-plugin LTO requires. Returns that major version.
+    pub fn linker_plugin_lto_llvm(&self) -> OptimizerResult<u32> {
+        if self.os != OperatingSystem::Linux {
+            return Err(OptimizerError::unsupported_platform(format!(
+                "linker-plugin LTO is only configured on Linux, not {}",
+                self.os
+            )));
+        }
+
+        let rust = self
+            .rust_llvm_version
+            .as_deref()
+            .and_then(|version| llvm_major(version, ""))
+            .ok_or_else(|| OptimizerError::tool_not_found("rustc LLVM version"))?;
+
+        for (tool, marker) in [
+            ("clang", "clang version "),
+            ("ld.lld", "LLD "),
+            ("llvm-ar", "LLVM version "),
+        ] {
+            let version = self
+                .get_tool(tool)
+                .filter(|tool| tool.is_installed)
+                .and_then(|tool| tool.version)
+                .ok_or_else(|| OptimizerError::tool_not_found(tool))?;
+            match llvm_major(&version, marker) {
+                Some(major) if major == rust => {}
+                Some(major) => {
+                    return Err(OptimizerError::config(format!(
+                        "{} uses LLVM {} but rustc uses LLVM {}",
+                        tool, major, rust
+                    )))
+                }
+                None => {
+                    return Err(OptimizerError::config(format!(
+                        "Could not determine the LLVM version of {} ({})",
+                        tool, version
+                    )))
+                }
+            }
+        }
+
+        Ok(rust)
     }
 }
 
-fn detect_architecture() -> Architecture {
-    match std::env::consts::ARCH {
-        "x86_64" => Architecture::X86_64,
-        "aarch64" => Architecture::Aarch64,
-        other => Architecture::Unknown(other.to_string()),
+/// Major LLVM version following `marker` in a version string.
+/// Apple's toolchain numbers its own releases, so it never matches.
+fn llvm_major(version: &str, marker: &str) -> Option<u32> {
+    if version.starts_with("Apple") {
+        return None;
     }
+    let pattern = Regex::new(&format!(r"{}(\d+)", regex::escape(marker))).ok()?;
+    pattern.captures(version)?[1].parse().ok()
 }
 
-fn detect_cpu_cores() -> usize {
-    num_cpus::get()
+/// Package managers Atlas installs tools with on Linux: (program, name)
+const LINUX_PACKAGE_MANAGERS: &[(&str, &str)] =
+    &[("apt-get", "apt"), ("yum", "yum"), ("pacman", "pacman")];
+
+/// Tools Atlas detects and recommends
+const DETECTED_TOOLS: &[&str] = &[
+    "rustfmt",
+    "cargo-clippy",
+    "rust-analyzer",
+    "sccache",
+    "cargo-nextest",
+    "cargo-udeps",
+    "cargo-hakari",
+    "cargo-watch",
+    "cargo-expand",
+    "cargo-bloat",
+    "lld",
+    "ld.lld",
+    "llvm-ar",
+    "mold",
+    "zld",
+    "clang",
+    "gcc",
+    "ccache",
+];
+
+/// Tools that rustup installs as components: (program, component). Their
+/// rustup proxies exist even without the component, and fail when run.
+const RUSTUP_COMPONENT_TOOLS: &[(&str, &str)] = &[
+    ("rustfmt", "rustfmt"),
+    ("cargo-clippy", "clippy"),
+    ("rust-analyzer", "rust-analyzer"),
+];
+
+/// The rustup component that provides `tool`, e.g. `clippy` for `cargo-clippy`
+pub fn rustup_component(tool: &str) -> Option<&'static str> {
+    RUSTUP_COMPONENT_TOOLS
+        .iter()
+        .find(|(program, _)| *program == tool)
+        .map(|(_, component)| *component)
 }
 
-fn detect_rust_version() -> Option<String> {
-    Command::new("rustc")
-        .arg("--version")
-        .output()
-        .ok()
-        .and_then(|output| {
-            if output.status.success() {
-                String::from_utf8(output.stdout)
-                    .ok()
-                    .map(|s| s.trim().to_string())
-            } else {
-                None
-            }
-        })
+/// Installed rustup components of the active toolchain, with the host's
+/// target triple left off, e.g. `clippy` and `rust-std-wasm32-unknown-unknown`
+fn rustup_components(runner: &dyn CommandRunner) -> Option<Vec<String>> {
+    let output = runner.output("rustup", &["component", "list", "--installed"])?;
+    let suffix = rustc_verbose_field(runner, "host").map(|host| format!("-{}", host));
+    Some(
+        output
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                suffix
+                    .as_deref()
+                    .and_then(|suffix| line.strip_suffix(suffix))
+                    .unwrap_or(line)
+                    .to_string()
+            })
+            .collect(),
+    )
+}
+
+/// Trimmed first line of a successful run's stdout
+fn first_line(runner: &dyn CommandRunner, program: &str, args: &[&str]) -> Option<String> {
+    runner
+        .output(program, args)?
+        .lines()
+        .next()
+        .map(|line| line.trim().to_string())
+}
+
+/// Target triple of the host, e.g. `x86_64-unknown-linux-gnu`
+pub fn host_triple() -> Option<String> {
+    rustc_verbose_field(&Host, "host")
+}
+
+/// A `name: value` field from `rustc -vV`
+fn rustc_verbose_field(runner: &dyn CommandRunner, name: &str) -> Option<String> {
+    let output = runner.output("rustc", &["-vV"])?;
+    let prefix = format!("{}: ", name);
+    output
+        .lines()
+        .find_map(|line| line.strip_prefix(prefix.as_str()))
+        .map(|value| value.trim().to_string())
 }
 
-fn detect_cargo_version() -> Option<String> {
-    Command::new("cargo")
-        .arg("--version")
-        .output()
-        .ok()
-        .and_then(|output| {
-            if output.status.success() {
-                String::from_utf8(output.stdout)
-                    .ok()
-                    .map(|s| s.trim().to_string())
-            } else {
-                None
+/// Tools Atlas knows about, each probed the first time it is looked up.
+/// Detection through [`SystemInfo::detect`] probes on demand on this machine
+/// and adds what it finds to the detection cache; everywhere else, such as
+/// with a mock host or a config bundle, the tools are known upfront.
+#[derive(Debug, Default)]
+pub struct Tools {
+    probed: Mutex<Vec<AvailableTool>>,
+    /// Cache key of the detection, set while tools are probed on demand
+    on_demand: Option<String>,
+}
+
+impl Tools {
+    fn on_demand(probed: Vec<AvailableTool>, cache_key: String) -> Self {
+        Self {
+            probed: Mutex::new(probed),
+            on_demand: Some(cache_key),
+        }
+    }
+
+    /// `name`, probing it now if it was not yet
+    pub fn get(&self, name: &str) -> Option<AvailableTool> {
+        if let Some(tool) = self.find(name) {
+            return Some(tool);
+        }
+        self.on_demand.as_ref()?;
+        self.remember(probe_tools(&Host, &[name]));
+        self.find(name)
+    }
+
+    /// Every tool Atlas detects and recommends, probing the ones not yet
+    /// known in parallel, followed by any other tool looked up
+    pub fn all(&self) -> Vec<AvailableTool> {
+        if self.on_demand.is_some() {
+            let missing: Vec<&str> = DETECTED_TOOLS
+                .iter()
+                .copied()
+                .filter(|name| self.find(name).is_none())
+                .collect();
+            if !missing.is_empty() {
+                self.remember(probe_tools(&Host, &missing));
             }
-        })
+        }
+        let probed = self.probed.lock().unwrap().clone();
+        let position = |tool: &AvailableTool| {
+            DETECTED_TOOLS
+                .iter()
+                .position(|name| *name == tool.name)
+                .unwrap_or(DETECTED_TOOLS.len())
+        };
+        let mut all = probed;
+        all.sort_by_key(position);
+        all
+    }
+
+    /// Probe `name` again on its next lookup, e.g. after installing it
+    pub fn forget(&self, name: &str) {
+        self.probed.lock().unwrap().retain(|tool| tool.name != name);
+    }
+
+    fn find(&self, name: &str) -> Option<AvailableTool> {
+        let probed = self.probed.lock().unwrap();
+        probed.iter().find(|tool| tool.name == name).cloned()
+    }
+
+    fn remember(&self, tools: Vec<AvailableTool>) {
+        if let Some(key) = &self.on_demand {
+            if let Err(e) = system_cache::remember_tools(key, &tools) {
+                log::debug!("Could not cache tool detection: {}", e);
+            }
+        }
+        let mut probed = self.probed.lock().unwrap();
+        for tool in tools {
+            probed.retain(|known| known.name != tool.name);
+            probed.push(tool);
+        }
+    }
+}
+
+impl From<Vec<AvailableTool>> for Tools {
+    fn from(probed: Vec<AvailableTool>) -> Self {
+        Self {
+            probed: Mutex::new(probed),
+            on_demand: None,
+        }
+    }
 }
 
-fn detect_available_tools() -> Vec<AvailableTool> {
-    let tools = vec![
-        "sccache",
-        "cargo-nextest",
-        "cargo-udeps",
-        "cargo-hakari",
-        "cargo-watch",
-        "cargo-expand",
-        "cargo-bloat",
-        "lld",
-        "mold",
-        "zld",
-        "clang",
-        "gcc",
-    ];
-
-    tools
-        .into_iter()
-        .map(|name| {
-            let (is_installed, path, version) = if let Ok(tool_path) = which(name) {
-                let version = get_tool_version(name);
-                (true, tool_path.to_string_lossy().to_string(), version)
-            } else {
-                (false, String::new(), None)
-            };
+impl Clone for Tools {
+    fn clone(&self) -> Self {
+        Self {
+            probed: Mutex::new(self.probed.lock().unwrap().clone()),
+            on_demand: self.on_demand.clone(),
+        }
+    }
+}
+
+/// Written as the tools probed so far
+impl Serialize for Tools {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.probed.lock().unwrap().serialize(serializer)
+    }
+}
 
+impl<'de> Deserialize<'de> for Tools {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<AvailableTool>::deserialize(deserializer).map(Self::from)
+    }
+}
+
+fn probe_tools<H: SystemProbe + CommandRunner + Sync>(
+    host: &H,
+    names: &[&str],
+) -> Vec<AvailableTool> {
+    thread::scope(|scope| {
+        let probes: Vec<_> = names
+            .iter()
+            .map(|name| scope.spawn(move || probe_tool(host, name)))
+            .collect();
+        probes
+            .into_iter()
+            .zip(names)
+            .map(|(probe, name)| probe.join().unwrap_or_else(|_| missing_tool(name)))
+            .collect()
+    })
+}
+
+fn probe_tool<H: SystemProbe + CommandRunner>(host: &H, name: &str) -> AvailableTool {
+    match host.find_program(name) {
+        Some(tool_path) => {
+            let version = first_line(host, name, &["--version"]);
             AvailableTool {
                 name: name.to_string(),
+                is_installed: version.is_some() || rustup_component(name).is_none(),
                 version,
-                path,
-                is_installed,
+                path: tool_path.to_string_lossy().to_string(),
             }
-        })
-        .collect()
-}
-
-fn get_tool_version(tool: &str) -> Option<String> {
-    let version_args = match tool {
-        "sccache" => vec!["--version"],
-        "cargo-nextest" | "cargo-udeps" | "cargo-hakari" | "cargo-watch" | "cargo-expand"
-        | "cargo-bloat" => {
-            vec!["--version"]
-        }
-        "lld" => vec!["--version"],
-        "mold" => vec!["--version"],
-        "zld" => vec!["--version"],
-        "clang" | "gcc" => vec!["--version"],
-        _ => return None,
-    };
-
-    Command::new(tool)
-        .args(&version_args)
-        .output()
-        .ok()
-        .and_then(|output| {
-            if output.status.success() {
-                String::from_utf8(output.stdout).ok().and_then(|s| {
-                    // Extract version number from output
-                    s.lines().next().map(|line| line.trim().to_string())
-                })
-            } else {
-                None
-            }
-        })
+        }
+        None => missing_tool(name),
+    }
+}
+
+fn missing_tool(name: &str) -> AvailableTool {
+    AvailableTool {
+        name: name.to_string(),
+        version: None,
+        path: String::new(),
+        is_installed: false,
+    }
 }
 
 impl std::fmt::Display for OperatingSystem {
@@ -240,5 +481,80 @@ impl std::fmt::Display for Architecture {
     }
 }
 
-// Add num_cpus dependency to Cargo.toml
-extern crate num_cpus;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_llvm_major() {
+        assert_eq!(llvm_major("18.1.7", ""), Some(18));
+        assert_eq!(
+            llvm_major("Ubuntu clang version 18.1.3 (1ubuntu1)", "clang version "),
+            Some(18)
+        );
+        assert_eq!(
+            llvm_major("Ubuntu LLD 17.0.6 (compatible with GNU linkers)", "LLD "),
+            Some(17)
+        );
+        assert_eq!(
+            llvm_major(
+                "Apple clang version 15.0.0 (clang-1500.3.9.4)",
+                "clang version "
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_tools_known_upfront() {
+        let host = crate::host::MockHost::linux()
+            .with_program("mold", Some("mold 2.30.0"))
+            .with_program("sccache", Some("sccache 0.8.1"));
+        let tools = Tools::from(probe_tools(&host, &["mold", "cargo-llvm-lines", "sccache"]));
+        let names: Vec<String> = tools.all().into_iter().map(|tool| tool.name).collect();
+        // In the order Atlas lists them, with other tools last
+        assert_eq!(names, vec!["sccache", "mold", "cargo-llvm-lines"]);
+        assert!(!tools.get("cargo-llvm-lines").unwrap().is_installed);
+        // Not probed, and without probing on demand never will be
+        assert!(tools.get("clang").is_none());
+
+        let json = serde_json::to_string(&tools).unwrap();
+        let restored: Tools = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            restored.get("mold").unwrap().version.as_deref(),
+            Some("mold 2.30.0")
+        );
+        restored.forget("mold");
+        assert!(restored.get("mold").is_none());
+    }
+
+    #[test]
+    fn test_rustup_components() {
+        let host = crate::host::MockHost::linux()
+            .with_output("rustc -vV", "rustc 1.80.0\nhost: x86_64-unknown-linux-gnu\n")
+            .with_output(
+                "rustup component list --installed",
+                "cargo-x86_64-unknown-linux-gnu\nclippy-x86_64-unknown-linux-gnu\nrust-src\nrust-std-wasm32-unknown-unknown\n",
+            )
+            .with_program("cargo-clippy", Some("clippy 0.1.80 (05147895 2024-07-21)"))
+            // The rustup proxy is on PATH without the component
+            .with_program("rustfmt", None);
+        let system_info = SystemInfo::detect_with(&host);
+        assert_eq!(
+            system_info.rustup_components.clone().unwrap(),
+            vec![
+                "cargo",
+                "clippy",
+                "rust-src",
+                "rust-std-wasm32-unknown-unknown"
+            ]
+        );
+        assert!(system_info.is_tool_installed("cargo-clippy"));
+        assert!(!system_info.is_tool_installed("rustfmt"));
+        assert_eq!(rustup_component("cargo-clippy"), Some("clippy"));
+        assert_eq!(
+            SystemInfo::detect_with(&crate::host::MockHost::linux()).rustup_components,
+            None
+        );
+    }
+}
@@ -0,0 +1,172 @@
+//! On-disk cache of [`SystemInfo`]
+//!
+//! Detection runs a dozen `--version` probes, which adds noticeable latency
+//! to every command. The result is kept in `system-info.json` next to the
+//! Atlas config for a day, and dropped earlier when anything that decides
+//! what detection finds changes: PATH, the contents of a directory on it,
+//! rustup's toolchains, their components and the default, or the toolchain
+//! file of the project.
+
+use crate::config::OptimizerConfig;
+use crate::error::OptimizerResult;
+use crate::system::{AvailableTool, SystemInfo};
+use crate::utils::stable_hash;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// How long a detection stays valid when nothing it depends on changed
+const TTL_HOURS: i64 = 24;
+
+/// Files that pin a project's toolchain, in the order rustup looks for them
+const TOOLCHAIN_FILES: [&str; 2] = ["rust-toolchain", "rust-toolchain.toml"];
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedSystemInfo {
+    detected_at: DateTime<Utc>,
+    /// [`key`] when it was detected
+    key: String,
+    info: SystemInfo,
+}
+
+pub fn path() -> OptimizerResult<PathBuf> {
+    Ok(OptimizerConfig::get_config_path()?.with_file_name("system-info.json"))
+}
+
+/// The cached detection, if it was made under `key` within the last day
+pub fn load(key: &str) -> Option<SystemInfo> {
+    let cached: CachedSystemInfo =
+        serde_json::from_str(&fs::read_to_string(path().ok()?).ok()?).ok()?;
+    fresh(&cached, key, Utc::now()).then_some(cached.info)
+}
+
+fn fresh(cached: &CachedSystemInfo, key: &str, now: DateTime<Utc>) -> bool {
+    cached.key == key
+        && cached.detected_at <= now
+        && now - cached.detected_at < Duration::hours(TTL_HOURS)
+}
+
+pub fn store(info: &SystemInfo, key: &str) -> OptimizerResult<()> {
+    let path = path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let cached = CachedSystemInfo {
+        detected_at: Utc::now(),
+        key: key.to_string(),
+        info: info.clone(),
+    };
+    fs::write(path, serde_json::to_string(&cached)?)?;
+    Ok(())
+}
+
+/// Add tools probed on demand to the cached detection made under `key`
+pub fn remember_tools(key: &str, tools: &[AvailableTool]) -> OptimizerResult<()> {
+    let path = path()?;
+    let Ok(contents) = fs::read_to_string(&path) else {
+        return Ok(());
+    };
+    let mut cached: CachedSystemInfo = serde_json::from_str(&contents)?;
+    if cached.key != key {
+        return Ok(());
+    }
+    let mut known = cached.info.available_tools.all();
+    known.retain(|known| !tools.iter().any(|tool| tool.name == known.name));
+    known.extend(tools.iter().cloned());
+    cached.info.available_tools = known.into();
+    fs::write(path, serde_json::to_string(&cached)?)?;
+    Ok(())
+}
+
+/// Hash of what detection in `project_dir` depends on. Installing a tool
+/// changes the modification time of the directory it lands in, and rustup
+/// records toolchains and overrides under its home.
+pub fn key(project_dir: &Path) -> String {
+    let mut parts: Vec<String> = ["PATH", "RUSTUP_TOOLCHAIN", "RUSTUP_HOME", "CARGO_HOME"]
+        .iter()
+        .map(|var| format!("{}={}", var, std::env::var(var).unwrap_or_default()))
+        .collect();
+    if let Some(path) = std::env::var_os("PATH") {
+        parts.extend(std::env::split_paths(&path).map(|dir| modified(&dir)));
+    }
+    if let Some(rustup_home) = std::env::var_os("RUSTUP_HOME")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".rustup")))
+    {
+        let toolchains = rustup_home.join("toolchains");
+        parts.push(modified(&toolchains));
+        // `rustup component add` rewrites this list in the toolchain
+        let mut components: Vec<String> = fs::read_dir(&toolchains)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|toolchain| modified(&toolchain.path().join("lib/rustlib/components")))
+            .collect();
+        components.sort();
+        parts.extend(components);
+        parts.push(fs::read_to_string(rustup_home.join("settings.toml")).unwrap_or_default());
+    }
+    if let Some(toolchain_file) = toolchain_file(project_dir) {
+        parts.push(toolchain_file.display().to_string());
+        parts.push(fs::read_to_string(toolchain_file).unwrap_or_default());
+    }
+    stable_hash(parts.join("\n").as_bytes())
+}
+
+/// `path` with its modification time, or alone when it does not exist
+fn modified(path: &Path) -> String {
+    let nanos = fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|since| since.as_nanos());
+    match nanos {
+        Some(nanos) => format!("{} {}", path.display(), nanos),
+        None => path.display().to_string(),
+    }
+}
+
+/// The closest toolchain file in `dir` or above it
+fn toolchain_file(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors().find_map(|dir| {
+        TOOLCHAIN_FILES
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|file| file.is_file())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host::MockHost;
+
+    #[test]
+    fn test_cache_freshness_and_toolchain_file() {
+        let now = Utc::now();
+        let cached = CachedSystemInfo {
+            detected_at: now - Duration::hours(2),
+            key: "abc".to_string(),
+            info: SystemInfo::detect_with(&MockHost::linux()),
+        };
+        assert!(fresh(&cached, "abc", now));
+        assert!(!fresh(&cached, "def", now));
+        assert!(!fresh(&cached, "abc", now + Duration::hours(TTL_HOURS)));
+
+        let dir = tempfile::tempdir().unwrap();
+        let member = dir.path().join("crates").join("core");
+        fs::create_dir_all(&member).unwrap();
+        assert_eq!(toolchain_file(&member), None);
+        fs::write(
+            dir.path().join("rust-toolchain.toml"),
+            "[toolchain]\nchannel = \"1.79\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            toolchain_file(&member),
+            Some(dir.path().join("rust-toolchain.toml"))
+        );
+    }
+}
@@ -0,0 +1,306 @@
+//! Tables for lists and reports, sized to the terminal: columns are as wide as
+//! their content, and when a table does not fit, its widest columns wrap at
+//! word boundaries. `--wide`, and output that is not a terminal, never wrap.
+
+use crate::style::{self, ColoredString};
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+const COLUMN_GAP: usize = 2;
+/// Columns are never wrapped narrower than this, so words stay readable
+const MIN_WRAP_WIDTH: usize = 10;
+
+static WIDE: AtomicBool = AtomicBool::new(false);
+
+/// Apply `--wide` for this run
+pub fn init(wide: bool) {
+    WIDE.store(wide, Ordering::Relaxed);
+}
+
+/// Width to fit tables into; `None` means unlimited
+pub fn terminal_width() -> Option<usize> {
+    if WIDE.load(Ordering::Relaxed) {
+        return None;
+    }
+    if let Some(columns) = std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+    {
+        return Some(columns);
+    }
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+    query_width()
+}
+
+#[cfg(unix)]
+fn query_width() -> Option<usize> {
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    let result = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) };
+    (result == 0 && size.ws_col > 0).then_some(size.ws_col as usize)
+}
+
+#[cfg(not(unix))]
+fn query_width() -> Option<usize> {
+    None
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+/// One cell: its text and how to style it
+#[derive(Clone)]
+pub struct Cell {
+    text: String,
+    style: Option<fn(&str) -> ColoredString>,
+}
+
+impl Cell {
+    pub fn styled(text: impl Into<String>, style: fn(&str) -> ColoredString) -> Self {
+        Self {
+            text: text.into(),
+            style: Some(style),
+        }
+    }
+
+    fn width(&self) -> usize {
+        style::text(&self.text).width()
+    }
+}
+
+impl From<String> for Cell {
+    fn from(text: String) -> Self {
+        Self { text, style: None }
+    }
+}
+
+impl From<&str> for Cell {
+    fn from(text: &str) -> Self {
+        Self::from(text.to_string())
+    }
+}
+
+pub struct Table {
+    headers: Vec<String>,
+    align: Vec<Align>,
+    rows: Vec<Vec<Cell>>,
+    indent: usize,
+}
+
+impl Table {
+    /// A table with these column headers; empty headers leave out the header row
+    pub fn new(headers: &[&str]) -> Self {
+        Self {
+            headers: headers.iter().map(|header| header.to_string()).collect(),
+            align: vec![Align::Left; headers.len()],
+            rows: Vec::new(),
+            indent: 2,
+        }
+    }
+
+    /// Right-align a column, e.g. durations and counts
+    pub fn right(mut self, column: usize) -> Self {
+        self.align[column] = Align::Right;
+        self
+    }
+
+    pub fn indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    pub fn add_row(&mut self, row: Vec<Cell>) {
+        debug_assert_eq!(row.len(), self.headers.len());
+        self.rows.push(row);
+    }
+
+    fn has_header(&self) -> bool {
+        self.headers.iter().any(|header| !header.is_empty())
+    }
+
+    /// Natural column widths, then narrowed until the table fits `limit`
+    fn column_widths(&self, limit: Option<usize>) -> Vec<usize> {
+        let mut widths: Vec<usize> = self
+            .headers
+            .iter()
+            .map(|header| style::text(header).width())
+            .collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.width());
+            }
+        }
+        let Some(limit) = limit else {
+            return widths;
+        };
+        let gaps = COLUMN_GAP * widths.len().saturating_sub(1);
+        while self.indent + gaps + widths.iter().sum::<usize>() > limit {
+            // Narrow the widest column that can still wrap
+            let Some((widest, _)) = widths
+                .iter()
+                .enumerate()
+                .filter(|(_, width)| **width > MIN_WRAP_WIDTH)
+                .max_by_key(|(index, width)| (**width, std::cmp::Reverse(*index)))
+            else {
+                break;
+            };
+            widths[widest] -= 1;
+        }
+        widths
+    }
+
+    /// The table's lines for a terminal `limit` columns wide
+    pub fn render(&self, limit: Option<usize>) -> Vec<String> {
+        let widths = self.column_widths(limit);
+        let mut lines = Vec::new();
+        if self.has_header() {
+            let header: Vec<Cell> = self
+                .headers
+                .iter()
+                .map(|header| Cell::styled(header.as_str(), style::strong))
+                .collect();
+            self.render_row(&header, &widths, &mut lines);
+        }
+        for row in &self.rows {
+            self.render_row(row, &widths, &mut lines);
+        }
+        lines
+    }
+
+    fn render_row(&self, row: &[Cell], widths: &[usize], lines: &mut Vec<String>) {
+        let wrapped: Vec<Vec<String>> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| wrap(&style::text(&cell.text), *width))
+            .collect();
+        let height = wrapped.iter().map(Vec::len).max().unwrap_or(1);
+        for line_index in 0..height {
+            let mut line = " ".repeat(self.indent);
+            for (column, (cell, parts)) in row.iter().zip(&wrapped).enumerate() {
+                let part = parts.get(line_index).map(String::as_str).unwrap_or("");
+                let padding = " ".repeat(widths[column].saturating_sub(part.width()));
+                let text = match cell.style {
+                    Some(style) if !part.is_empty() => style(part).to_string(),
+                    _ => part.to_string(),
+                };
+                let last = column + 1 == row.len();
+                match self.align[column] {
+                    Align::Right => {
+                        line.push_str(&padding);
+                        line.push_str(&text);
+                    }
+                    Align::Left => {
+                        line.push_str(&text);
+                        if !last {
+                            line.push_str(&padding);
+                        }
+                    }
+                }
+                if !last {
+                    line.push_str(&" ".repeat(COLUMN_GAP));
+                }
+            }
+            lines.push(line.trim_end().to_string());
+        }
+    }
+
+    pub fn print(&self) {
+        for line in self.render(terminal_width()) {
+            println!("{}", line);
+        }
+    }
+}
+
+/// `text` broken into lines at most `width` wide, at spaces where possible
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    if text.width() <= width {
+        return vec![text.to_string()];
+    }
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split(' ') {
+        let mut word = word.to_string();
+        loop {
+            let needed = if line.is_empty() {
+                word.width()
+            } else {
+                line.width() + 1 + word.width()
+            };
+            if needed <= width {
+                if !line.is_empty() {
+                    line.push(' ');
+                }
+                line.push_str(&word);
+                break;
+            }
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+                continue;
+            }
+            // A word longer than the column is split
+            let mut head = String::new();
+            let mut chars = word.chars().peekable();
+            while let Some(c) = chars.next_if(|c| head.width() + c.width().unwrap_or(0) <= width) {
+                head.push(c);
+            }
+            let rest: String = chars.collect();
+            if head.is_empty() || rest.is_empty() {
+                line = word;
+                break;
+            }
+            lines.push(head);
+            word = rest;
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Table {
+        let mut table = Table::new(&["Tool", "Description", "Time"]).right(2);
+        table.add_row(vec![
+            "sccache".into(),
+            "Shared compilation cache for Rust and C".into(),
+            "1.2s".into(),
+        ]);
+        table.add_row(vec!["mold".into(), "Fast linker".into(), "12.0s".into()]);
+        table
+    }
+
+    #[test]
+    fn test_render_natural_width() {
+        colored::control::set_override(false);
+        let lines = sample().render(None);
+        colored::control::unset_override();
+        assert_eq!(
+            lines,
+            vec![
+                "  Tool     Description                               Time",
+                "  sccache  Shared compilation cache for Rust and C   1.2s",
+                "  mold     Fast linker                              12.0s",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_wraps_to_width() {
+        colored::control::set_override(false);
+        let lines = sample().render(Some(40));
+        colored::control::unset_override();
+        assert!(lines.iter().all(|line| line.width() <= 40), "{:#?}", lines);
+        assert_eq!(lines[1], "  sccache  Shared compilation       1.2s");
+        assert_eq!(lines[2], "           cache for Rust and C");
+        assert_eq!(wrap("abcdefghij", 4), vec!["abcd", "efgh", "ij"]);
+    }
+}
@@ -0,0 +1,131 @@
+//! Per-profile target directories, e.g. release builds on a big slow disk
+//! while dev builds stay on fast local storage.
+//!
+//! Cargo has a single target directory per invocation, so the routing is
+//! applied per process: every cargo command Atlas starts gets
+//! `CARGO_TARGET_DIR` for the profile it selects. An explicit `--target-dir`
+//! or a `CARGO_TARGET_DIR` already in the environment always wins.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+
+static ROUTES: OnceLock<BTreeMap<String, PathBuf>> = OnceLock::new();
+
+/// Route cargo processes started from now on by profile name
+pub fn init(routes: BTreeMap<String, PathBuf>) {
+    let _ = ROUTES.set(routes);
+}
+
+/// Profile a cargo command line builds with
+pub fn profile_of<'a>(args: &[&'a str]) -> &'a str {
+    let mut profile = match args.first() {
+        // Benchmarks build with the bench profile, which lands in release
+        Some(&"bench") => "release",
+        _ => "dev",
+    };
+    let mut args = args.iter();
+    while let Some(&arg) = args.next() {
+        match arg {
+            "--" => break,
+            "--release" | "-r" => profile = "release",
+            "--profile" => {
+                if let Some(&name) = args.next() {
+                    profile = name;
+                }
+            }
+            _ => {
+                if let Some(name) = arg.strip_prefix("--profile=") {
+                    profile = name;
+                }
+            }
+        }
+    }
+    profile
+}
+
+/// Target directory `args` are routed to, unless they choose one themselves
+pub fn route<'a>(routes: &'a BTreeMap<String, PathBuf>, args: &[&str]) -> Option<&'a Path> {
+    if args
+        .iter()
+        .take_while(|arg| **arg != "--")
+        .any(|arg| arg.starts_with("--target-dir"))
+    {
+        return None;
+    }
+    routes.get(profile_of(args)).map(PathBuf::as_path)
+}
+
+/// Set `CARGO_TARGET_DIR` on a cargo command about to be started
+pub fn apply(cmd: &mut Command) {
+    let Some(routes) = ROUTES.get() else {
+        return;
+    };
+    if routes.is_empty() || std::env::var_os("CARGO_TARGET_DIR").is_some() {
+        return;
+    }
+    let args: Vec<String> = cmd
+        .get_args()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    if let Some(dir) = route(routes, &args) {
+        log::debug!("Building {} in {}", profile_of(&args), dir.display());
+        cmd.env("CARGO_TARGET_DIR", dir);
+    }
+}
+
+/// Directory under the target directory cargo writes `profile` builds to
+pub fn profile_dir(profile: &str) -> &str {
+    match profile {
+        "dev" | "test" => "debug",
+        "release" | "bench" => "release",
+        other => other,
+    }
+}
+
+/// Where cargo puts `profile` builds run from `project_root`, given the
+/// target directory cargo metadata reports
+pub fn for_profile(project_root: &Path, profile: &str, target_directory: &Path) -> PathBuf {
+    match ROUTES.get().and_then(|routes| routes.get(profile)) {
+        Some(dir) if std::env::var_os("CARGO_TARGET_DIR").is_none() => project_root.join(dir),
+        _ => target_directory.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_of() {
+        assert_eq!(profile_of(&["build"]), "dev");
+        assert_eq!(
+            profile_of(&["build", "--workspace", "--release"]),
+            "release"
+        );
+        assert_eq!(profile_of(&["run", "-r", "--", "--profile=ci"]), "release");
+        assert_eq!(profile_of(&["build", "--profile", "dist"]), "dist");
+        assert_eq!(profile_of(&["check", "--profile=ci"]), "ci");
+        assert_eq!(profile_of(&["bench"]), "release");
+    }
+
+    #[test]
+    fn test_route() {
+        let routes = BTreeMap::from([("release".to_string(), PathBuf::from("/mnt/big/target"))]);
+        assert_eq!(
+            route(&routes, &["build", "--release"]),
+            Some(Path::new("/mnt/big/target"))
+        );
+        assert_eq!(route(&routes, &["build"]), None);
+        assert_eq!(
+            route(&routes, &["build", "--release", "--target-dir=/tmp/t"]),
+            None
+        );
+        assert_eq!(
+            route(&routes, &["run", "--release", "--", "--target-dir"]),
+            Some(Path::new("/mnt/big/target"))
+        );
+    }
+}
@@ -0,0 +1,271 @@
+//! Opt-in usage statistics: which setting changes improved which measurements.
+//!
+//! Observations stay in the Atlas config directory. Only aggregates per
+//! setting name leave the machine, and only through `atlas telemetry submit`.
+//! Nothing is recorded unless the user agreed during onboarding or with
+//! `atlas telemetry on`.
+
+use crate::baseline::{percent_change, BaselineMetadata, Measurements};
+use crate::config::OptimizerConfig;
+use crate::error::OptimizerResult;
+use crate::onboarding::OnboardingState;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// One baseline comparison, without anything identifying the project
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Observation {
+    pub recorded_at: DateTime<Utc>,
+    /// Names of the settings that differ between the two measurements
+    pub changed_settings: Vec<String>,
+    /// Percentage change per measurement, e.g. `"Incremental build": -35.2`
+    pub changes: BTreeMap<String, f64>,
+}
+
+/// Mean effect of changing one setting on its own
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SettingAggregate {
+    pub setting: String,
+    pub samples: usize,
+    pub mean_changes: BTreeMap<String, f64>,
+}
+
+/// What `atlas telemetry submit` sends
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub atlas_version: String,
+    pub os: String,
+    pub arch: String,
+    pub settings: Vec<SettingAggregate>,
+}
+
+/// Sum and count of the changes per measurement label
+type ChangeSums<'a> = BTreeMap<&'a str, (f64, usize)>;
+
+/// Observations recorded on this machine
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TelemetryStore {
+    #[serde(default)]
+    pub observations: Vec<Observation>,
+}
+
+impl TelemetryStore {
+    pub fn path() -> OptimizerResult<PathBuf> {
+        Ok(OptimizerConfig::get_config_path()?.with_file_name("telemetry.json"))
+    }
+
+    pub fn load() -> OptimizerResult<Self> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self) -> OptimizerResult<()> {
+        let path = Self::path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Delete every recorded observation. Returns whether there was anything to delete.
+    pub fn purge() -> OptimizerResult<bool> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(false);
+        }
+        fs::remove_file(path)?;
+        Ok(true)
+    }
+
+    /// Mean changes per setting, from observations where only that setting changed
+    pub fn aggregate(&self) -> Vec<SettingAggregate> {
+        let mut sums: BTreeMap<&str, (usize, ChangeSums)> = BTreeMap::new();
+        for observation in &self.observations {
+            let [setting] = observation.changed_settings.as_slice() else {
+                continue;
+            };
+            let (samples, changes) = sums.entry(setting).or_default();
+            *samples += 1;
+            for (label, change) in &observation.changes {
+                let (sum, count) = changes.entry(label).or_default();
+                *sum += change;
+                *count += 1;
+            }
+        }
+
+        sums.into_iter()
+            .map(|(setting, (samples, changes))| SettingAggregate {
+                setting: setting.to_string(),
+                samples,
+                mean_changes: changes
+                    .into_iter()
+                    .map(|(label, (sum, count))| (label.to_string(), sum / count as f64))
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+/// Whether the user agreed to usage statistics
+pub fn enabled() -> bool {
+    OnboardingState::load()
+        .ok()
+        .flatten()
+        .is_some_and(|state| state.telemetry)
+}
+
+/// Set the user's consent, creating the onboarding state if needed
+pub fn set_enabled(telemetry: bool) -> OptimizerResult<()> {
+    let state = match OnboardingState::load()? {
+        Some(state) => OnboardingState { telemetry, ..state },
+        None => OnboardingState::new(telemetry),
+    };
+    state.save()
+}
+
+/// The observation for a baseline comparison, or `None` if no setting changed
+/// or the two were measured in different environments
+pub fn observe(
+    before: (&BaselineMetadata, &Measurements),
+    after: (&BaselineMetadata, &Measurements),
+) -> Option<Observation> {
+    if !before
+        .0
+        .fingerprint
+        .incompatibilities(&after.0.fingerprint)
+        .is_empty()
+    {
+        return None;
+    }
+    let changed_settings: Vec<String> = after
+        .0
+        .settings
+        .iter()
+        .filter(|(name, value)| {
+            before
+                .0
+                .settings
+                .get(name.as_str())
+                .is_some_and(|old| old != *value)
+        })
+        .map(|(name, _)| name.clone())
+        .collect();
+    if changed_settings.is_empty() {
+        return None;
+    }
+
+    let changes = before
+        .1
+        .durations()
+        .into_iter()
+        .zip(after.1.durations())
+        .filter_map(|((label, old), (_, new))| {
+            Some((label.to_string(), percent_change(old?, new?)?))
+        })
+        .collect();
+    Some(Observation {
+        recorded_at: Utc::now(),
+        changed_settings,
+        changes,
+    })
+}
+
+/// Record a baseline comparison if the user opted in. Never fails the command.
+pub fn record_comparison(
+    before: (&BaselineMetadata, &Measurements),
+    after: (&BaselineMetadata, &Measurements),
+) {
+    if !enabled() {
+        return;
+    }
+    let Some(observation) = observe(before, after) else {
+        return;
+    };
+
+    let result = TelemetryStore::load().and_then(|mut store| {
+        store.observations.push(observation);
+        store.save()
+    });
+    if let Err(e) = result {
+        log::warn!("Could not record usage statistics: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(settings: &[(&str, &str)]) -> BaselineMetadata {
+        BaselineMetadata {
+            fingerprint: crate::fingerprint::Fingerprint::default(),
+            settings: settings
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+
+    fn measurements(check: f64) -> Measurements {
+        Measurements {
+            check_secs: Some(check),
+            ..Measurements::default()
+        }
+    }
+
+    #[test]
+    fn test_observations_hold_only_setting_names() {
+        let before = metadata(&[("linker", "rustflags: cc"), ("lto", "unset")]);
+        let after = metadata(&[("linker", "rustflags: mold"), ("lto", "unset")]);
+        let observation =
+            observe((&before, &measurements(10.0)), (&after, &measurements(8.0))).unwrap();
+        assert_eq!(observation.changed_settings, vec!["linker"]);
+        assert_eq!(observation.changes["Check"], -20.0);
+        assert!(!serde_json::to_string(&observation)
+            .unwrap()
+            .contains("mold"));
+
+        assert!(observe(
+            (&before, &measurements(10.0)),
+            (&before, &measurements(9.0))
+        )
+        .is_none());
+
+        let mut upgraded = after.clone();
+        upgraded.fingerprint.toolchain = Some("rustc 1.80.0".to_string());
+        let mut older = before.clone();
+        older.fingerprint.toolchain = Some("rustc 1.79.0".to_string());
+        assert!(observe(
+            (&older, &measurements(10.0)),
+            (&upgraded, &measurements(8.0))
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_aggregate_single_setting_changes() {
+        let observation = |settings: &[&str], check: f64| Observation {
+            recorded_at: Utc::now(),
+            changed_settings: settings.iter().map(|name| name.to_string()).collect(),
+            changes: BTreeMap::from([("Check".to_string(), check)]),
+        };
+        let store = TelemetryStore {
+            observations: vec![
+                observation(&["linker"], -20.0),
+                observation(&["linker"], -30.0),
+                observation(&["linker", "lto"], 50.0),
+            ],
+        };
+
+        let aggregates = store.aggregate();
+        assert_eq!(aggregates.len(), 1);
+        assert_eq!(aggregates[0].samples, 2);
+        assert_eq!(aggregates[0].mean_changes["Check"], -25.0);
+    }
+}
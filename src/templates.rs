@@ -0,0 +1,509 @@
+//! User templates that replace the content Atlas generates, so an
+//! organization can encode its own standards. A template is a file in the
+//! `templates` directory next to the Atlas config, named after what it
+//! replaces (see [`Generated`]) with a `.hbs` extension.
+//!
+//! Templates use a subset of Handlebars: `{{path.to.value}}`, `{{#if}}`,
+//! `{{#unless}}`, `{{#each}}` with `{{this}}`, `{{@index}}` and `{{@key}}`,
+//! `{{else}}`, `{{! comments }}` and `~` whitespace control. Nothing is
+//! HTML-escaped, and a name that is not in the context is an error rather
+//! than an empty string, so typos surface at `atlas init`.
+
+use crate::config::OptimizerConfig;
+use crate::error::{OptimizerError, OptimizerResult};
+use crate::system::{OperatingSystem, SystemInfo};
+use serde_json::{json, Map, Value};
+use std::fs;
+use std::path::PathBuf;
+
+/// Generated content a template can replace
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Generated {
+    /// The Atlas block of `.cargo/config.toml`
+    CargoConfig,
+    /// The Atlas block of build profiles in `Cargo.toml`
+    Profiles,
+    /// The starter `atlas.toml`
+    ProjectConfig,
+    /// The Nix devShell, `atlas.nix`
+    NixShell,
+}
+
+pub const GENERATED: &[Generated] = &[
+    Generated::CargoConfig,
+    Generated::Profiles,
+    Generated::ProjectConfig,
+    Generated::NixShell,
+];
+
+impl Generated {
+    /// Template file name
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Self::CargoConfig => "cargo-config.toml.hbs",
+            Self::Profiles => "profiles.toml.hbs",
+            Self::ProjectConfig => "atlas.toml.hbs",
+            Self::NixShell => "atlas.nix.hbs",
+        }
+    }
+}
+
+impl std::fmt::Display for Generated {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CargoConfig => write!(f, "Cargo config block"),
+            Self::Profiles => write!(f, "Cargo.toml profiles block"),
+            Self::ProjectConfig => write!(f, "starter atlas.toml"),
+            Self::NixShell => write!(f, "Nix devShell"),
+        }
+    }
+}
+
+/// The kind whose template is called `name`, with or without `.hbs`
+pub fn find(name: &str) -> Option<Generated> {
+    GENERATED.iter().copied().find(|kind| {
+        kind.file_name() == name || kind.file_name().strip_suffix(".hbs") == Some(name)
+    })
+}
+
+/// Directory holding the user's templates
+pub fn templates_dir() -> OptimizerResult<PathBuf> {
+    Ok(OptimizerConfig::get_config_path()?.with_file_name("templates"))
+}
+
+/// Path and source of the user's template for `kind`, if installed
+pub fn template(kind: Generated) -> Option<(PathBuf, String)> {
+    let path = templates_dir().ok()?.join(kind.file_name());
+    let source = fs::read_to_string(&path).ok()?;
+    Some((path, source))
+}
+
+/// Content of every installed template, so generated files go stale when a
+/// template changes; empty without templates
+pub fn fingerprint() -> String {
+    GENERATED
+        .iter()
+        .filter_map(|kind| template(*kind))
+        .map(|(_, source)| source)
+        .collect()
+}
+
+/// `builtin`, or the user's template for `kind` rendered with the system,
+/// the Atlas configuration and `builtin` itself as context
+pub fn render_generated(
+    kind: Generated,
+    config: &OptimizerConfig,
+    system_info: Option<&SystemInfo>,
+    builtin: String,
+) -> OptimizerResult<String> {
+    let Some((path, source)) = template(kind) else {
+        return Ok(builtin);
+    };
+    let context = json!({
+        "atlas_version": env!("CARGO_PKG_VERSION"),
+        "builtin": builtin,
+        "config": serde_json::to_value(config)?,
+        "system": system_info.map(system_context).unwrap_or(Value::Null),
+    });
+    render(&source, &context)
+        .map_err(|e| OptimizerError::config(format!("Template {}: {}", path.display(), e)))
+}
+
+/// System information with flags templates can test with `{{#if}}`
+fn system_context(system_info: &SystemInfo) -> Value {
+    json!({
+        "os": system_info.os.to_string(),
+        "arch": system_info.arch.to_string(),
+        "cpu_cores": system_info.cpu_cores,
+        "rust_version": system_info.rust_version,
+        "cargo_version": system_info.cargo_version,
+        "is_linux": system_info.os == OperatingSystem::Linux,
+        "is_macos": system_info.os == OperatingSystem::MacOS,
+        "is_windows": system_info.os == OperatingSystem::Windows,
+        "tools": system_info
+            .available_tools
+            .all()
+            .into_iter()
+            .filter(|tool| tool.is_installed)
+            .map(|tool| (tool.name, Value::Bool(true)))
+            .collect::<Map<String, Value>>(),
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Value { path: String, line: usize },
+    Block(Block),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Block {
+    helper: Helper,
+    path: String,
+    line: usize,
+    body: Vec<Node>,
+    otherwise: Vec<Node>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Helper {
+    If,
+    Unless,
+    Each,
+}
+
+#[derive(Debug)]
+enum Token {
+    Text(String),
+    Tag { content: String, line: usize },
+}
+
+/// Render a template against a JSON context
+pub fn render(source: &str, context: &Value) -> Result<String, String> {
+    let nodes = parse(tokenize(source)?)?;
+    let mut output = String::new();
+    let mut scopes = vec![Scope {
+        value: context.clone(),
+        index: None,
+        key: None,
+        length: 0,
+    }];
+    render_nodes(&nodes, &mut scopes, &mut output)?;
+    Ok(output)
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    let mut line = 1;
+    while let Some(start) = rest.find("{{") {
+        let (text, after) = rest.split_at(start);
+        let triple = after.starts_with("{{{");
+        let (open, close) = if triple { (3, "}}}") } else { (2, "}}") };
+        let end = after[open..]
+            .find(close)
+            .ok_or_else(|| format!("line {}: unclosed {{{{", line))?;
+        let mut content = after[open..open + end].to_string();
+        let mut text = text.to_string();
+        if let Some(trimmed) = content.strip_prefix('~') {
+            text.truncate(text.trim_end().len());
+            content = trimmed.to_string();
+        }
+        let trim_after = content.ends_with('~');
+        if trim_after {
+            content.pop();
+        }
+        line += text.matches('\n').count();
+        tokens.push(Token::Text(text));
+        tokens.push(Token::Tag {
+            content: content.trim().to_string(),
+            line,
+        });
+        line += content.matches('\n').count();
+        rest = &after[open + end + close.len()..];
+        if trim_after {
+            let trimmed = rest.trim_start();
+            line += rest[..rest.len() - trimmed.len()].matches('\n').count();
+            rest = trimmed;
+        }
+    }
+    tokens.push(Token::Text(rest.to_string()));
+    remove_standalone_lines(&mut tokens);
+    Ok(tokens)
+}
+
+/// Block tags and comments alone on a line take the whole line with them,
+/// as in Handlebars
+fn remove_standalone_lines(tokens: &mut [Token]) {
+    // Whether the text token at an index starts at the beginning of a line
+    let mut starts_line = vec![false; tokens.len()];
+    starts_line[0] = true;
+    for index in (1..tokens.len().saturating_sub(1)).step_by(2) {
+        let Token::Tag { content, .. } = &tokens[index] else {
+            continue;
+        };
+        if !(content.starts_with(['#', '/', '!']) || content == "else") {
+            continue;
+        }
+        let (before, after) = match (&tokens[index - 1], &tokens[index + 1]) {
+            (Token::Text(before), Token::Text(after)) => (before, after),
+            _ => continue,
+        };
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let at_start =
+            before[line_start..].trim().is_empty() && (line_start > 0 || starts_line[index - 1]);
+        let newline = after.find('\n');
+        let at_end = match newline {
+            Some(end) => after[..end].trim().is_empty(),
+            None => after.trim().is_empty() && index + 2 == tokens.len(),
+        };
+        if at_start && at_end {
+            let before = before[..line_start].to_string();
+            let after = match newline {
+                Some(end) => after[end + 1..].to_string(),
+                None => String::new(),
+            };
+            tokens[index - 1] = Token::Text(before);
+            tokens[index + 1] = Token::Text(after);
+            starts_line[index + 1] = true;
+        }
+    }
+}
+
+fn parse(tokens: Vec<Token>) -> Result<Vec<Node>, String> {
+    // Open blocks, innermost last, each collecting into its body or else branch
+    let mut stack: Vec<(Block, bool)> = Vec::new();
+    let mut root = Vec::new();
+    fn push(stack: &mut [(Block, bool)], root: &mut Vec<Node>, node: Node) {
+        match stack.last_mut() {
+            Some((block, false)) => block.body.push(node),
+            Some((block, true)) => block.otherwise.push(node),
+            None => root.push(node),
+        }
+    }
+
+    for token in tokens {
+        let (content, line) = match token {
+            Token::Text(text) => {
+                if !text.is_empty() {
+                    push(&mut stack, &mut root, Node::Text(text));
+                }
+                continue;
+            }
+            Token::Tag { content, line } => (content, line),
+        };
+        if content.starts_with('!') {
+            continue;
+        }
+        if let Some(open) = content.strip_prefix('#') {
+            let (name, path) = open.split_once(char::is_whitespace).unwrap_or((open, ""));
+            let helper = match name {
+                "if" => Helper::If,
+                "unless" => Helper::Unless,
+                "each" => Helper::Each,
+                other => return Err(format!("line {}: unknown helper #{}", line, other)),
+            };
+            let path = path.trim();
+            if path.is_empty() {
+                return Err(format!("line {}: #{} needs a value", line, name));
+            }
+            stack.push((
+                Block {
+                    helper,
+                    path: path.to_string(),
+                    line,
+                    body: Vec::new(),
+                    otherwise: Vec::new(),
+                },
+                false,
+            ));
+        } else if content == "else" {
+            match stack.last_mut() {
+                Some((_, otherwise @ false)) => *otherwise = true,
+                _ => return Err(format!("line {}: else outside a block", line)),
+            }
+        } else if let Some(close) = content.strip_prefix('/') {
+            let (block, _) = stack
+                .pop()
+                .ok_or_else(|| format!("line {}: /{} closes nothing", line, close))?;
+            let expected = match block.helper {
+                Helper::If => "if",
+                Helper::Unless => "unless",
+                Helper::Each => "each",
+            };
+            if close.trim() != expected {
+                return Err(format!(
+                    "line {}: /{} closes #{} from line {}",
+                    line,
+                    close.trim(),
+                    expected,
+                    block.line
+                ));
+            }
+            push(&mut stack, &mut root, Node::Block(block));
+        } else {
+            push(
+                &mut stack,
+                &mut root,
+                Node::Value {
+                    path: content,
+                    line,
+                },
+            );
+        }
+    }
+    match stack.last() {
+        Some((block, _)) => Err(format!("line {}: block is never closed", block.line)),
+        None => Ok(root),
+    }
+}
+
+struct Scope {
+    value: Value,
+    index: Option<usize>,
+    key: Option<String>,
+    length: usize,
+}
+
+/// Value at `path`, looked up in the innermost scope that has it
+fn lookup(scopes: &[Scope], path: &str, line: usize) -> Result<Value, String> {
+    let missing = || format!("line {}: no value named '{}'", line, path);
+    let current = scopes.last().ok_or_else(missing)?;
+    match path {
+        "@index" => return current.index.map(Value::from).ok_or_else(missing),
+        "@key" => return current.key.clone().map(Value::from).ok_or_else(missing),
+        "@first" => {
+            return current
+                .index
+                .map(|i| Value::from(i == 0))
+                .ok_or_else(missing)
+        }
+        "@last" => {
+            return current
+                .index
+                .map(|i| Value::from(i + 1 == current.length))
+                .ok_or_else(missing)
+        }
+        _ => {}
+    }
+    let (path, only_current) = match path.strip_prefix("this") {
+        Some("") => return Ok(current.value.clone()),
+        Some(rest) if rest.starts_with('.') => (&rest[1..], true),
+        _ => (path, false),
+    };
+    let resolve = |value: &Value| {
+        path.split('.')
+            .try_fold(value, |value, part| value.get(part))
+            .cloned()
+    };
+    if only_current {
+        return resolve(&current.value).ok_or_else(missing);
+    }
+    scopes
+        .iter()
+        .rev()
+        .find_map(|scope| resolve(&scope.value))
+        .ok_or_else(missing)
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(value) => *value,
+        Value::Number(number) => number.as_f64() != Some(0.0),
+        Value::String(text) => !text.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
+fn render_nodes(
+    nodes: &[Node],
+    scopes: &mut Vec<Scope>,
+    output: &mut String,
+) -> Result<(), String> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Value { path, line } => match lookup(scopes, path, *line)? {
+                Value::Null => {}
+                Value::String(text) => output.push_str(&text),
+                value @ (Value::Bool(_) | Value::Number(_)) => output.push_str(&value.to_string()),
+                _ => {
+                    return Err(format!(
+                        "line {}: '{}' is a list or table; use #each",
+                        line, path
+                    ))
+                }
+            },
+            Node::Block(block) => render_block(block, scopes, output)?,
+        }
+    }
+    Ok(())
+}
+
+fn render_block(block: &Block, scopes: &mut Vec<Scope>, output: &mut String) -> Result<(), String> {
+    let value = lookup(scopes, &block.path, block.line)?;
+    match block.helper {
+        Helper::If | Helper::Unless => {
+            let branch = if truthy(&value) == (block.helper == Helper::If) {
+                &block.body
+            } else {
+                &block.otherwise
+            };
+            render_nodes(branch, scopes, output)
+        }
+        Helper::Each => {
+            let items: Vec<(Option<String>, Value)> = match value {
+                Value::Array(items) => items.into_iter().map(|item| (None, item)).collect(),
+                Value::Object(map) => map.into_iter().map(|(k, v)| (Some(k), v)).collect(),
+                Value::Null => Vec::new(),
+                _ => {
+                    return Err(format!(
+                        "line {}: #each needs a list or table, '{}' is neither",
+                        block.line, block.path
+                    ))
+                }
+            };
+            if items.is_empty() {
+                return render_nodes(&block.otherwise, scopes, output);
+            }
+            let length = items.len();
+            for (index, (key, item)) in items.into_iter().enumerate() {
+                scopes.push(Scope {
+                    value: item,
+                    index: Some(index),
+                    key,
+                    length,
+                });
+                let result = render_nodes(&block.body, scopes, output);
+                scopes.pop();
+                result?;
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_template() {
+        let context = json!({
+            "builtin": "[build]\njobs = 8\n",
+            "system": { "is_linux": true, "cpu_cores": 16 },
+            "config": { "optimization": { "hot_dependencies": ["image", "regex"] } },
+            "tools": {},
+        });
+        let template = "\
+{{! company standard }}
+{{builtin}}
+{{#if system.is_linux}}
+[target.x86_64-unknown-linux-gnu]
+linker = \"clang\"
+{{else}}
+# not linux
+{{/if}}
+{{#each config.optimization.hot_dependencies}}
+[profile.dev.package.{{this}}]
+opt-level = 3 # {{@index}} of {{system.cpu_cores}}
+{{/each}}
+{{#each tools}}{{@key}}{{else}}# no tools{{/each}}
+";
+        assert_eq!(
+            render(template, &context).unwrap(),
+            "[build]\njobs = 8\n\n[target.x86_64-unknown-linux-gnu]\nlinker = \"clang\"\n[profile.dev.package.image]\nopt-level = 3 # 0 of 16\n[profile.dev.package.regex]\nopt-level = 3 # 1 of 16\n# no tools\n"
+        );
+
+        assert!(render("{{sytem.os}}", &context)
+            .unwrap_err()
+            .contains("no value named 'sytem.os'"));
+        assert!(render("{{#if builtin}}\n", &context)
+            .unwrap_err()
+            .contains("never closed"));
+        assert!(render("{{#each builtin}}{{/if}}", &context).is_err());
+    }
+}
@@ -0,0 +1,142 @@
+//! Tests that failed in a project's last `atlas build test` run, kept in
+//! `.atlas/failed-tests.json` so the next run can start with them.
+
+use crate::error::OptimizerResult;
+use crate::utils::create_parent_dirs;
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FailedTests {
+    pub recorded_at: Option<DateTime<Utc>>,
+    /// Full test names, e.g. `parser::tests::test_empty_input`
+    pub tests: Vec<String>,
+}
+
+impl FailedTests {
+    pub fn path(project_root: &Path) -> PathBuf {
+        project_root.join(".atlas").join("failed-tests.json")
+    }
+
+    /// The recorded failures; none when nothing was recorded or the file is unreadable
+    pub fn load(project_root: &Path) -> Self {
+        fs::read_to_string(Self::path(project_root))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, project_root: &Path) -> OptimizerResult<()> {
+        let path = Self::path(project_root);
+        create_parent_dirs(&path)?;
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tests.is_empty()
+    }
+
+    /// Note the failure reported on an output line of `cargo test` or `cargo nextest`
+    pub fn record_line(&mut self, line: &str, parser: &FailureParser) {
+        if let Some(test) = parser.parse(line) {
+            if !self.tests.contains(&test) {
+                self.tests.push(test);
+                self.recorded_at = Some(Utc::now());
+            }
+        }
+    }
+
+    /// Arguments after the package selection that run only these tests
+    pub fn filter_args(&self, nextest: bool) -> Vec<String> {
+        if nextest {
+            let expression = self
+                .tests
+                .iter()
+                .map(|test| format!("test(={})", test))
+                .collect::<Vec<_>>()
+                .join(" | ");
+            vec!["-E".to_string(), expression]
+        } else {
+            let mut args = vec!["--".to_string(), "--exact".to_string()];
+            args.extend(self.tests.iter().cloned());
+            args
+        }
+    }
+}
+
+/// Recognizes failed tests in the output of libtest and nextest
+pub struct FailureParser {
+    libtest: Regex,
+    nextest: Regex,
+}
+
+impl FailureParser {
+    pub fn new() -> Self {
+        Self {
+            // test parser::tests::test_empty_input ... FAILED
+            libtest: Regex::new(r"^test (\S+) \.\.\. FAILED$").expect("valid libtest regex"),
+            //         FAIL [   0.004s] my-crate::bin/tool parser::tests::test_empty_input
+            nextest: Regex::new(r"^\s*(?:TRY \d+ )?FAIL \[\s*[\d.]+s\] \S+ (\S+)$")
+                .expect("valid nextest regex"),
+        }
+    }
+
+    pub fn parse(&self, line: &str) -> Option<String> {
+        let line = line.trim_end();
+        self.libtest
+            .captures(line)
+            .or_else(|| self.nextest.captures(line))
+            .map(|captures| captures[1].to_string())
+    }
+}
+
+impl Default for FailureParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_failures() {
+        let parser = FailureParser::new();
+        let mut failed = FailedTests::default();
+        for line in [
+            "test parser::tests::test_ok ... ok",
+            "test parser::tests::test_empty_input ... FAILED",
+            "        FAIL [   0.004s] my-crate::bin/tool cli::tests::test_args",
+            "  TRY 2 FAIL [   0.010s] my-crate cli::tests::test_args",
+            "        PASS [   0.002s] my-crate cli::tests::test_help",
+            "test result: FAILED. 1 passed; 1 failed",
+        ] {
+            failed.record_line(line, &parser);
+        }
+        assert_eq!(
+            failed.tests,
+            vec!["parser::tests::test_empty_input", "cli::tests::test_args"]
+        );
+        assert_eq!(
+            failed.filter_args(true),
+            vec![
+                "-E",
+                "test(=parser::tests::test_empty_input) | test(=cli::tests::test_args)"
+            ]
+        );
+        assert_eq!(
+            failed.filter_args(false),
+            vec![
+                "--",
+                "--exact",
+                "parser::tests::test_empty_input",
+                "cli::tests::test_args"
+            ]
+        );
+    }
+}
@@ -0,0 +1,327 @@
+//! Thermal throttling during benchmark runs
+//!
+//! A laptop that heats up during a clean build runs the next step at a lower
+//! clock speed, which makes comparisons between runs meaningless. While a
+//! step runs, the CPU is sampled where the platform allows it: throttle
+//! counters, clock speed and temperature from sysfs on Linux, the speed limit
+//! from `pmset -g therm` on macOS.
+
+use crate::utils::execute_command;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Time between samples while a step runs
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+/// Clock speed of the fastest core, as a share of its maximum, below which a
+/// busy CPU is considered throttled
+const THROTTLED_FREQUENCY: f64 = 0.6;
+/// Temperature in °C at which laptop CPUs start to throttle
+const THROTTLED_TEMPERATURE: f64 = 95.0;
+/// Cooling down ends early within this many degrees of the starting temperature
+const COOLED_MARGIN: f64 = 3.0;
+
+/// One reading of the CPU's thermal state; every field is optional as
+/// platforms report different subsets
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Sample {
+    /// Clock speed of the fastest core as a share of its maximum; a build
+    /// keeps at least one core busy, while the others may idle
+    pub frequency: Option<f64>,
+    /// Hottest CPU temperature in °C
+    pub temperature: Option<f64>,
+    /// Throttling events counted by the kernel since boot
+    pub throttle_count: Option<u64>,
+    /// Speed limit in percent the OS imposes to cool down (macOS)
+    pub speed_limit: Option<u32>,
+}
+
+impl Sample {
+    pub fn take() -> Self {
+        if Path::new("/sys/devices/system/cpu").is_dir() {
+            Self::from_sysfs(Path::new("/sys"))
+        } else if cfg!(target_os = "macos") {
+            execute_command("pmset", &["-g", "therm"], None)
+                .ok()
+                .map(|output| Self {
+                    speed_limit: speed_limit(&String::from_utf8_lossy(&output.stdout)),
+                    ..Self::default()
+                })
+                .unwrap_or_default()
+        } else {
+            Self::default()
+        }
+    }
+
+    /// Read a sysfs tree mounted at `sys`
+    pub fn from_sysfs(sys: &Path) -> Self {
+        let read =
+            |path: &Path| -> Option<f64> { fs::read_to_string(path).ok()?.trim().parse().ok() };
+        let cpus: Vec<_> = fs::read_dir(sys.join("devices/system/cpu"))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .and_then(|name| name.strip_prefix("cpu"))
+                    .is_some_and(|index| index.parse::<u32>().is_ok())
+            })
+            .collect();
+
+        let ratios: Vec<f64> = cpus
+            .iter()
+            .filter_map(|cpu| {
+                let current = read(&cpu.join("cpufreq/scaling_cur_freq"))?;
+                let max = read(&cpu.join("cpufreq/cpuinfo_max_freq"))?;
+                (max > 0.0).then(|| current / max)
+            })
+            .collect();
+        let throttle_counts: Vec<u64> = cpus
+            .iter()
+            .flat_map(|cpu| {
+                ["core_throttle_count", "package_throttle_count"]
+                    .map(|counter| read(&cpu.join("thermal_throttle").join(counter)))
+            })
+            .flatten()
+            .map(|count| count as u64)
+            .collect();
+        let temperature = fs::read_dir(sys.join("class/thermal"))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|zone| {
+                fs::read_to_string(zone.path().join("type"))
+                    .is_ok_and(|kind| is_cpu_zone(kind.trim()))
+            })
+            .filter_map(|zone| read(&zone.path().join("temp")))
+            .map(|millidegrees| millidegrees / 1000.0)
+            .reduce(f64::max);
+
+        Self {
+            frequency: ratios.into_iter().reduce(f64::max),
+            temperature,
+            throttle_count: (!throttle_counts.is_empty()).then(|| throttle_counts.iter().sum()),
+            speed_limit: None,
+        }
+    }
+}
+
+/// Thermal zones that measure the CPU rather than the battery or a disk
+fn is_cpu_zone(kind: &str) -> bool {
+    ["x86_pkg_temp", "cpu", "soc", "k10temp", "coretemp", "tcpu"]
+        .iter()
+        .any(|name| kind.to_ascii_lowercase().contains(name))
+}
+
+/// `CPU_Speed_Limit = 80` in `pmset -g therm`
+fn speed_limit(output: &str) -> Option<u32> {
+    output.lines().find_map(|line| {
+        let (key, value) = line.split_once('=')?;
+        (key.trim() == "CPU_Speed_Limit")
+            .then(|| value.trim().parse().ok())
+            .flatten()
+    })
+}
+
+/// Thermal state over one measured step
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Report {
+    /// Median clock speed of the fastest core while the step ran
+    pub frequency: Option<f64>,
+    pub highest_temperature: Option<f64>,
+    /// Throttling events the kernel counted during the step
+    pub throttle_events: u64,
+    pub lowest_speed_limit: Option<u32>,
+}
+
+impl Report {
+    /// Summarize samples taken when a step started and while it ran
+    pub fn from_samples(samples: &[Sample]) -> Self {
+        let counts: Vec<u64> = samples.iter().filter_map(|s| s.throttle_count).collect();
+        // The first sample is taken before the CPU got busy
+        let mut frequencies: Vec<f64> = samples
+            .iter()
+            .skip(1)
+            .filter_map(|sample| sample.frequency)
+            .collect();
+        frequencies.sort_by(f64::total_cmp);
+        Self {
+            frequency: frequencies.get(frequencies.len() / 2).copied(),
+            highest_temperature: samples
+                .iter()
+                .filter_map(|sample| sample.temperature)
+                .reduce(f64::max),
+            throttle_events: match (counts.first(), counts.last()) {
+                (Some(first), Some(last)) => last.saturating_sub(*first),
+                _ => 0,
+            },
+            lowest_speed_limit: samples.iter().filter_map(|sample| sample.speed_limit).min(),
+        }
+    }
+
+    pub fn throttled(&self) -> bool {
+        self.throttle_events > 0
+            || self.lowest_speed_limit.is_some_and(|limit| limit < 100)
+            || self
+                .frequency
+                .is_some_and(|frequency| frequency < THROTTLED_FREQUENCY)
+            || self
+                .highest_temperature
+                .is_some_and(|temperature| temperature >= THROTTLED_TEMPERATURE)
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if self.throttle_events > 0 {
+            parts.push(format!("{} throttling event(s)", self.throttle_events));
+        }
+        if let Some(limit) = self.lowest_speed_limit.filter(|limit| *limit < 100) {
+            parts.push(format!("speed limited to {}%", limit));
+        }
+        if let Some(frequency) = self.frequency {
+            parts.push(format!("clock at {:.0}% of maximum", frequency * 100.0));
+        }
+        if let Some(temperature) = self.highest_temperature {
+            parts.push(format!("up to {:.0}°C", temperature));
+        }
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+/// Samples the CPU in the background until finished
+pub struct Monitor {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<Vec<Sample>>,
+}
+
+impl Monitor {
+    pub fn start() -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::clone(&stop);
+        let handle = thread::spawn(move || {
+            let mut samples = vec![Sample::take()];
+            while !stopped.load(Ordering::Relaxed) {
+                thread::sleep(SAMPLE_INTERVAL);
+                samples.push(Sample::take());
+            }
+            samples
+        });
+        Self { stop, handle }
+    }
+
+    pub fn finish(self) -> Report {
+        self.stop.store(true, Ordering::Relaxed);
+        Report::from_samples(&self.handle.join().unwrap_or_default())
+    }
+}
+
+/// Pause for up to `limit` between steps, ending early once the CPU is back
+/// within a few degrees of `idle_temperature`. Returns how long it waited.
+pub fn cool_down(limit: Duration, idle_temperature: Option<f64>) -> Duration {
+    let start = Instant::now();
+    while start.elapsed() < limit {
+        let cooled = match (idle_temperature, Sample::take().temperature) {
+            (Some(idle), Some(now)) => now <= idle + COOLED_MARGIN,
+            _ => false,
+        };
+        if cooled {
+            break;
+        }
+        thread::sleep(SAMPLE_INTERVAL.min(limit.saturating_sub(start.elapsed())));
+    }
+    start.elapsed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_from_sysfs() {
+        let sys = tempfile::tempdir().unwrap();
+        let write = |path: &str, value: &str| {
+            let path = sys.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, format!("{}\n", value)).unwrap();
+        };
+        write(
+            "devices/system/cpu/cpu0/cpufreq/scaling_cur_freq",
+            "1200000",
+        );
+        write(
+            "devices/system/cpu/cpu0/cpufreq/cpuinfo_max_freq",
+            "4000000",
+        );
+        write(
+            "devices/system/cpu/cpu1/cpufreq/scaling_cur_freq",
+            "2000000",
+        );
+        write(
+            "devices/system/cpu/cpu1/cpufreq/cpuinfo_max_freq",
+            "4000000",
+        );
+        write(
+            "devices/system/cpu/cpu0/thermal_throttle/package_throttle_count",
+            "7",
+        );
+        write("devices/system/cpu/cpufreq/boost", "1");
+        write("class/thermal/thermal_zone0/type", "x86_pkg_temp");
+        write("class/thermal/thermal_zone0/temp", "97000");
+        write("class/thermal/thermal_zone1/type", "acpitz");
+        write("class/thermal/thermal_zone1/temp", "40000");
+
+        let sample = Sample::from_sysfs(sys.path());
+        assert_eq!(sample.frequency, Some(0.5));
+        assert_eq!(sample.temperature, Some(97.0));
+        assert_eq!(sample.throttle_count, Some(7));
+        assert_eq!(
+            Sample::from_sysfs(&sys.path().join("none")),
+            Sample::default()
+        );
+        assert_eq!(
+            speed_limit(
+                "Note: No thermal warning level has been recorded\n CPU_Speed_Limit \t= 80\n"
+            ),
+            Some(80)
+        );
+    }
+
+    #[test]
+    fn test_report_flags_throttling() {
+        let sample = |frequency: f64, temperature: f64, count: u64| Sample {
+            frequency: Some(frequency),
+            temperature: Some(temperature),
+            throttle_count: Some(count),
+            speed_limit: None,
+        };
+        // The first sample is taken while the CPU idles
+        let cool = Report::from_samples(&[
+            sample(0.2, 45.0, 3),
+            sample(0.9, 70.0, 3),
+            sample(0.85, 72.0, 3),
+        ]);
+        assert!(!cool.throttled());
+
+        let hot = Report::from_samples(&[
+            sample(0.2, 45.0, 3),
+            sample(0.5, 88.0, 5),
+            sample(0.55, 91.0, 9),
+        ]);
+        assert!(hot.throttled());
+        assert_eq!(hot.throttle_events, 6);
+        assert_eq!(
+            hot.to_string(),
+            "6 throttling event(s), clock at 55% of maximum, up to 91°C"
+        );
+        // Too hot, even without throttle counters or a sample while busy
+        assert!(Report::from_samples(&[sample(0.9, 96.0, 0)]).throttled());
+    }
+}
@@ -0,0 +1,171 @@
+//! Build times of a project week over week
+//!
+//! Every `atlas build` and `atlas check` run is kept in the stats store;
+//! `atlas status --history` groups a project's runs by kind (command and
+//! profile) and calendar week, so it shows whether builds are getting faster
+//! rather than how long the last one took. Medians are used because one cold
+//! build after a toolchain update would swamp a week's average.
+
+use crate::significance::Samples;
+use crate::stats::{BuildRecord, RebuildCause, SccacheSnapshot};
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Runs of one kind in one week
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WeekSummary {
+    /// Monday the week starts on
+    pub week: NaiveDate,
+    pub builds: usize,
+    pub failed: usize,
+    /// Median duration of the successful runs
+    pub median_secs: Option<f64>,
+    /// Average number of crates compiled per run
+    pub crates_compiled: f64,
+    pub cold_builds: usize,
+    /// Percentage of sccache requests served from the cache; `None` without sccache
+    pub sccache_hit_rate: Option<f64>,
+}
+
+/// The weeks of one kind of run, oldest first
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Trend {
+    /// E.g. `build (release)`
+    pub kind: String,
+    pub weeks: Vec<WeekSummary>,
+}
+
+impl Trend {
+    /// Change of the median duration from the first week to the last, in
+    /// percent; negative is faster
+    pub fn change(&self) -> Option<f64> {
+        let first = self.weeks.first()?.median_secs?;
+        let last = self.weeks.last()?.median_secs?;
+        (self.weeks.len() > 1 && first > 0.0).then(|| (last - first) / first * 100.0)
+    }
+}
+
+/// One trend per kind of run in `records`
+pub fn weekly(records: &[BuildRecord]) -> Vec<Trend> {
+    let mut grouped: BTreeMap<String, BTreeMap<NaiveDate, Vec<&BuildRecord>>> = BTreeMap::new();
+    for record in records {
+        grouped
+            .entry(kind(record))
+            .or_default()
+            .entry(week_of(record.recorded_at.date_naive()))
+            .or_default()
+            .push(record);
+    }
+    grouped
+        .into_iter()
+        .map(|(kind, weeks)| Trend {
+            kind,
+            weeks: weeks
+                .into_iter()
+                .map(|(week, records)| summarize(week, &records))
+                .collect(),
+        })
+        .collect()
+}
+
+fn kind(record: &BuildRecord) -> String {
+    match &record.profile {
+        Some(profile) => format!("{} ({})", record.command, profile),
+        None => record.command.clone(),
+    }
+}
+
+fn week_of(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(i64::from(date.weekday().num_days_from_monday()))
+}
+
+fn summarize(week: NaiveDate, records: &[&BuildRecord]) -> WeekSummary {
+    let durations = records
+        .iter()
+        .filter(|record| record.success)
+        .map(|record| record.duration_secs)
+        .collect();
+    let mut sccache = SccacheSnapshot::default();
+    for snapshot in records.iter().filter_map(|record| record.sccache) {
+        sccache.requests += snapshot.requests;
+        sccache.hits += snapshot.hits;
+        sccache.misses += snapshot.misses;
+        sccache.errors += snapshot.errors;
+    }
+    WeekSummary {
+        week,
+        builds: records.len(),
+        failed: records.iter().filter(|record| !record.success).count(),
+        median_secs: Samples::new(durations).median(),
+        crates_compiled: records
+            .iter()
+            .map(|record| record.crates_compiled as f64)
+            .sum::<f64>()
+            / records.len() as f64,
+        cold_builds: records
+            .iter()
+            .filter(|record| record.cause == RebuildCause::Cold)
+            .count(),
+        sccache_hit_rate: (sccache.requests > 0).then(|| sccache.hit_rate()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use std::path::PathBuf;
+
+    fn record(day: u32, profile: &str, duration_secs: f64, success: bool) -> BuildRecord {
+        BuildRecord {
+            recorded_at: Utc.with_ymd_and_hms(2026, 3, day, 12, 0, 0).unwrap(),
+            project: PathBuf::from("/work/app"),
+            command: "build".to_string(),
+            success,
+            duration_secs,
+            crates_compiled: 4,
+            crates_fresh: 100,
+            cause: RebuildCause::WorkspaceChanges,
+            sccache: None,
+            fingerprint: None,
+            profile: Some(profile.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_weekly_medians_per_kind() {
+        // 2026-03-02 is a Monday
+        let records = vec![
+            record(2, "dev", 30.0, true),
+            record(4, "dev", 50.0, true),
+            record(8, "dev", 40.0, true),
+            record(9, "dev", 20.0, true),
+            record(10, "dev", 24.0, true),
+            record(11, "dev", 5.0, false),
+            record(10, "release", 200.0, true),
+        ];
+        let trends = weekly(&records);
+        assert_eq!(trends.len(), 2);
+
+        let dev = &trends[0];
+        assert_eq!(dev.kind, "build (dev)");
+        let weeks: Vec<NaiveDate> = dev.weeks.iter().map(|week| week.week).collect();
+        assert_eq!(
+            weeks,
+            [
+                NaiveDate::from_ymd_opt(2026, 3, 2).unwrap(),
+                NaiveDate::from_ymd_opt(2026, 3, 9).unwrap()
+            ]
+        );
+        assert_eq!(dev.weeks[0].median_secs, Some(40.0));
+        // Failed runs count as builds but not towards the median
+        assert_eq!(dev.weeks[1].builds, 3);
+        assert_eq!(dev.weeks[1].failed, 1);
+        assert_eq!(dev.weeks[1].median_secs, Some(22.0));
+        assert_eq!(dev.change(), Some(-45.0));
+
+        assert_eq!(trends[1].kind, "build (release)");
+        assert_eq!(trends[1].change(), None);
+    }
+}
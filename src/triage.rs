@@ -0,0 +1,340 @@
+//! Failure triage: classify a failed cargo run and suggest targeted next steps
+//! instead of only reporting that cargo exited with an error.
+
+use crate::stats::{CompilationStats, ErrorDiagnostic};
+use crate::utils::execute_command;
+use regex::Regex;
+use std::fmt;
+use std::path::Path;
+
+/// Why a cargo run failed
+#[derive(Debug, Clone, PartialEq)]
+pub enum FailureKind {
+    /// Cargo.lock would have to change, but `--locked` or `--frozen` forbids it
+    LockfileOutdated,
+    /// Cargo could not load the manifest or resolve the dependency graph
+    DependencyResolution { error: String },
+    /// The linker binary configured for the target does not exist
+    LinkerMissing { linker: String },
+    /// The linker was killed, usually by the out-of-memory killer
+    LinkerKilled,
+    /// The linker could not find a native library, e.g. `-lssl`
+    MissingLibrary { library: String },
+    /// Any other linker failure
+    Linker,
+    /// rustc crashed
+    InternalCompilerError { target: Option<String> },
+    /// The kernel killed a process for running out of memory during the build
+    OutOfMemory,
+    /// Cargo failed without a compiler diagnostic, e.g. in a build script
+    Unreported,
+}
+
+impl fmt::Display for FailureKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LockfileOutdated => write!(
+                f,
+                "Cargo.lock is out of date and {} forbids updating it",
+                crate::lockfile::mode().flag().unwrap_or("--locked")
+            ),
+            Self::DependencyResolution { error } => {
+                write!(f, "cargo could not resolve the project: {}", error)
+            }
+            Self::LinkerMissing { linker } => write!(f, "linker `{}` not found", linker),
+            Self::LinkerKilled => write!(f, "the linker was killed (likely out of memory)"),
+            Self::MissingLibrary { library } => {
+                write!(f, "the linker could not find native library `{}`", library)
+            }
+            Self::Linker => write!(f, "linking failed"),
+            Self::InternalCompilerError {
+                target: Some(target),
+            } => {
+                write!(f, "the compiler crashed while building `{}`", target)
+            }
+            Self::InternalCompilerError { target: None } => write!(f, "the compiler crashed"),
+            Self::OutOfMemory => write!(
+                f,
+                "the kernel killed a build process for running out of memory"
+            ),
+            Self::Unreported => write!(f, "cargo failed without a compiler diagnostic"),
+        }
+    }
+}
+
+impl FailureKind {
+    /// Whether running out of memory ended the build
+    pub fn is_out_of_memory(&self) -> bool {
+        matches!(self, Self::OutOfMemory | Self::LinkerKilled)
+    }
+
+    /// What to try next
+    pub fn next_steps(&self) -> Vec<String> {
+        let steps: &[&str] = match self {
+            Self::LockfileOutdated => &[
+                "Run `cargo update --workspace` where dependencies may change, then commit Cargo.lock",
+                "Check that Cargo.lock is committed and not listed in .gitignore",
+            ],
+            Self::DependencyResolution { .. } => &[
+                "Check the version requirements with `cargo tree -i <crate>`",
+                "Relax or update the conflicting requirement, then run `cargo update -p <crate>`",
+                "Offline? Retry with network access or vendor dependencies with `cargo vendor`",
+            ],
+            Self::LinkerMissing { .. } => &[
+                "Install it with `atlas install-tools`, or `atlas doctor --fix` for the recommended linker",
+                "Or see where it is configured with `atlas explain linker` and remove the setting",
+            ],
+            Self::LinkerKilled | Self::OutOfMemory => &[
+                "Lower parallelism, e.g. `CARGO_BUILD_JOBS=2` or `jobs` in .cargo/config.toml",
+                "Switch to a lighter linker: mold or lld use far less memory than GNU ld (`atlas explain linker`)",
+                "Reduce debug info with `dev_debug = \"line-tables-only\"` under [profiles] in the Atlas config",
+            ],
+            Self::MissingLibrary { .. } => &[
+                "Install the system library and its development headers; `atlas doctor --native-deps` lists known packages",
+                "If it is installed in an unusual place, point PKG_CONFIG_PATH or the crate's *_DIR variable at it",
+            ],
+            Self::Linker => &[
+                "Read the linker output above; undefined symbols usually mean a missing native library or feature",
+                "Check the configured linker and its flags with `atlas explain linker`",
+            ],
+            Self::Unreported => &[
+                "Look above for `failed to run custom build command`: a build script failed, often for a missing system library (`atlas doctor --native-deps`)",
+                "If a process ended with `signal: 9, SIGKILL`, it ran out of memory: lower parallelism with CARGO_BUILD_JOBS=2",
+            ],
+            Self::InternalCompilerError { .. } => &[
+                "Stale incremental data often triggers compiler crashes: run `cargo clean` and build again",
+                "If it persists, build with CARGO_INCREMENTAL=0 or another toolchain",
+                "Report it at https://github.com/rust-lang/rust/issues with the rustc-ice-*.txt file",
+            ],
+        };
+        steps.iter().map(|step| step.to_string()).collect()
+    }
+}
+
+fn strip_ansi(text: &str) -> String {
+    Regex::new(r"\x1b\[[0-9;]*m")
+        .expect("valid ANSI regex")
+        .replace_all(text, "")
+        .into_owned()
+}
+
+/// Classify a linker failure from rustc's `linking with ... failed` diagnostic
+fn classify_linker(diagnostic: &ErrorDiagnostic) -> FailureKind {
+    let text = strip_ansi(&diagnostic.rendered);
+    if text.contains("signal: 9")
+        || text.contains("SIGKILL")
+        || text.contains("terminated with signal 9")
+    {
+        return FailureKind::LinkerKilled;
+    }
+    let missing = Regex::new(r"(?:cannot find|library not found for) -l([\w\-+.]+)")
+        .expect("valid library regex");
+    if let Some(captures) = missing.captures(&text) {
+        return FailureKind::MissingLibrary {
+            library: captures[1].to_string(),
+        };
+    }
+    FailureKind::Linker
+}
+
+/// Classify from the diagnostics cargo reported. `resolution_error` is the
+/// first error line of `cargo metadata`, when dependency resolution fails;
+/// `oom_killed` is whether the kernel killed a process during the run.
+pub fn classify(
+    stats: &CompilationStats,
+    resolution_error: Option<String>,
+    oom_killed: bool,
+) -> Option<FailureKind> {
+    let linker_not_found = Regex::new(r"linker `([^`]+)` not found").expect("valid linker regex");
+    for diagnostic in &stats.error_diagnostics {
+        if diagnostic.level == "error: internal compiler error" {
+            return Some(FailureKind::InternalCompilerError {
+                target: diagnostic.target.clone(),
+            });
+        }
+        if let Some(captures) = linker_not_found.captures(&diagnostic.message) {
+            return Some(FailureKind::LinkerMissing {
+                linker: captures[1].to_string(),
+            });
+        }
+        if diagnostic.message.starts_with("linking with") {
+            return Some(classify_linker(diagnostic));
+        }
+    }
+    if !stats.error_diagnostics.is_empty() {
+        // Ordinary compile errors are already shown with their explanations
+        return None;
+    }
+    if oom_killed {
+        return Some(FailureKind::OutOfMemory);
+    }
+    Some(match resolution_error {
+        Some(error) if crate::lockfile::is_outdated_error(&error) => FailureKind::LockfileOutdated,
+        Some(error) => FailureKind::DependencyResolution { error },
+        None => FailureKind::Unreported,
+    })
+}
+
+/// Processes killed by the kernel's out-of-memory killer since boot, where the
+/// platform reports it (Linux `/proc/vmstat`)
+pub fn oom_kills() -> Option<u64> {
+    let vmstat = std::fs::read_to_string("/proc/vmstat").ok()?;
+    vmstat
+        .lines()
+        .find_map(|line| line.strip_prefix("oom_kill "))
+        .and_then(|count| count.trim().parse().ok())
+}
+
+/// Job count for a retry after running out of memory: half of what ran,
+/// given Cargo's `jobs` value (negative counts back from the cores) or the core count
+pub fn backoff_jobs(configured: Option<&str>, cores: usize) -> usize {
+    let ran = match configured.and_then(|value| value.trim().parse::<i64>().ok()) {
+        Some(jobs) if jobs > 0 => jobs as usize,
+        Some(jobs) => (cores as i64 + jobs).max(1) as usize,
+        None => cores,
+    };
+    (ran / 2).max(1)
+}
+
+/// First error of `cargo metadata`, which fails exactly when the dependency
+/// graph cannot be resolved
+fn resolution_error(project_root: &Path) -> Option<String> {
+    let output = execute_command(
+        "cargo",
+        &["metadata", "--format-version", "1"],
+        Some(project_root),
+    )
+    .ok()?;
+    if output.status.success() {
+        return None;
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    stderr
+        .lines()
+        .find(|line| line.trim_start().starts_with("error"))
+        .or_else(|| stderr.lines().find(|line| !line.trim().is_empty()))
+        .map(|line| line.trim().trim_start_matches("error: ").to_string())
+}
+
+/// Classify a failed cargo run. `oom_kills_before` is [`oom_kills`] from when the run started.
+pub fn diagnose(
+    project_root: &Path,
+    stats: &CompilationStats,
+    oom_kills_before: Option<u64>,
+) -> Option<FailureKind> {
+    let oom_killed = oom_kills_before
+        .zip(oom_kills())
+        .is_some_and(|(before, after)| after > before);
+    let resolution = if stats.error_diagnostics.is_empty() && !oom_killed {
+        resolution_error(project_root)
+    } else {
+        None
+    };
+    classify(stats, resolution, oom_killed)
+}
+
+/// Print the likely cause of a failure and what to try next
+pub fn print(kind: &FailureKind) {
+    crate::utils::print_warning(&format!("Likely cause: {}", kind));
+    for step in kind.next_steps() {
+        println!("   • {}", step);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats_with(level: &str, message: &str, rendered: &str) -> CompilationStats {
+        let mut stats = CompilationStats::default();
+        stats.error_diagnostics.push(ErrorDiagnostic {
+            target: Some("demo".to_string()),
+            level: level.to_string(),
+            message: message.to_string(),
+            rendered: rendered.to_string(),
+        });
+        stats
+    }
+
+    #[test]
+    fn test_classify_linker_failures() {
+        let killed = stats_with(
+            "error",
+            "linking with `cc` failed: signal: 9 (SIGKILL)",
+            "error: linking with `cc` failed: signal: 9 (SIGKILL)\n  = note: collect2: fatal error: ld terminated with signal 9 [Killed]",
+        );
+        assert_eq!(
+            classify(&killed, None, false),
+            Some(FailureKind::LinkerKilled)
+        );
+
+        let missing = stats_with(
+            "error",
+            "linking with `cc` failed: exit status: 1",
+            "\x1b[1merror\x1b[0m: linking with `cc` failed\n  = note: /usr/bin/ld: cannot find -lssl: No such file or directory",
+        );
+        assert_eq!(
+            classify(&missing, None, false),
+            Some(FailureKind::MissingLibrary {
+                library: "ssl".to_string()
+            })
+        );
+
+        let not_found = stats_with("error", "linker `mold` not found", "");
+        assert_eq!(
+            classify(&not_found, None, false),
+            Some(FailureKind::LinkerMissing {
+                linker: "mold".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_backoff_jobs() {
+        assert_eq!(backoff_jobs(None, 8), 4);
+        assert_eq!(backoff_jobs(Some("6"), 8), 3);
+        assert_eq!(backoff_jobs(Some("-2"), 8), 3);
+        assert_eq!(backoff_jobs(Some("1"), 8), 1);
+        assert_eq!(backoff_jobs(Some("\"default\""), 8), 4);
+    }
+
+    #[test]
+    fn test_classify_without_linker_errors() {
+        let ice = stats_with("error: internal compiler error", "unexpected panic", "");
+        assert!(matches!(
+            classify(&ice, None, true),
+            Some(FailureKind::InternalCompilerError { .. })
+        ));
+
+        let compile_error = stats_with("error", "mismatched types", "");
+        assert_eq!(classify(&compile_error, None, false), None);
+
+        let resolution = classify(
+            &CompilationStats::default(),
+            Some("failed to select a version for `serde`".to_string()),
+            false,
+        );
+        assert!(matches!(
+            resolution,
+            Some(FailureKind::DependencyResolution { .. })
+        ));
+        assert_eq!(
+            classify(
+                &CompilationStats::default(),
+                Some("the lock file /ws/Cargo.lock needs to be updated but --locked was passed to prevent this".to_string()),
+                false,
+            ),
+            Some(FailureKind::LockfileOutdated)
+        );
+
+        let silent = CompilationStats::default();
+        assert_eq!(
+            classify(&silent, None, true),
+            Some(FailureKind::OutOfMemory)
+        );
+        assert_eq!(
+            classify(&silent, None, false),
+            Some(FailureKind::Unreported)
+        );
+    }
+}
@@ -1,29 +1,34 @@
 use crate::error::{OptimizerError, OptimizerResult};
-use colored::*;
+use crate::style;
+use crate::watchdog::Watchdog;
 use indicatif::{ProgressBar, ProgressStyle};
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::{Command, Output};
-use std::time::{Duration, Instant};
+use std::process::{Child, Command, Output, Stdio};
+use std::time::Duration;
 
 /// Print a status message with colored output
 pub fn print_status(message: &str) {
-    println!("{} {}", "[INFO]".bright_blue().bold(), message);
+    crate::events::message(crate::events::MessageLevel::Info, message);
+    println!("{} {}", style::title("[INFO]"), style::text(message));
 }
 
 /// Print a success message with colored output
 pub fn print_success(message: &str) {
-    println!("{} {}", "[SUCCESS]".bright_green().bold(), message);
+    crate::events::message(crate::events::MessageLevel::Success, message);
+    println!("{} {}", style::section("[SUCCESS]"), style::text(message));
 }
 
 /// Print a warning message with colored output
 pub fn print_warning(message: &str) {
-    println!("{} {}", "[WARNING]".bright_yellow().bold(), message);
+    crate::events::message(crate::events::MessageLevel::Warning, message);
+    println!("{} {}", style::attention("[WARNING]"), style::text(message));
 }
 
 /// Print an error message with colored output
 pub fn print_error(message: &str) {
-    eprintln!("{} {}", "[ERROR]".bright_red().bold(), message);
+    crate::events::message(crate::events::MessageLevel::Error, message);
+    eprintln!("{} {}", style::alert("[ERROR]"), style::text(message));
 }
 
 /// Check if we're in a Rust project directory
@@ -50,53 +55,128 @@ pub fn find_rust_project_root<P: AsRef<Path>>(start_path: P) -> OptimizerResult<
     }
 }
 
+/// Arguments a command runs with: cargo commands get the lockfile flag in force
+fn run_args<'a>(command: &str, args: &[&'a str]) -> Vec<&'a str> {
+    if command == "cargo" {
+        crate::lockfile::with_flag(crate::lockfile::mode(), args)
+    } else {
+        args.to_vec()
+    }
+}
+
 /// Execute a command and return the result
 pub fn execute_command(
     command: &str,
     args: &[&str],
     working_dir: Option<&Path>,
+) -> OptimizerResult<Output> {
+    execute_command_with_env(command, args, working_dir, &[])
+}
+
+/// Execute a command with extra environment variables and return the result
+pub fn execute_command_with_env(
+    command: &str,
+    args: &[&str],
+    working_dir: Option<&Path>,
+    env: &[(String, String)],
 ) -> OptimizerResult<Output> {
     let mut cmd = Command::new(command);
-    cmd.args(args);
+    cmd.args(run_args(command, args))
+        .envs(env.iter().map(|(name, value)| (name, value)))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
 
     if let Some(dir) = working_dir {
         cmd.current_dir(dir);
     }
 
-    let output = cmd.output().map_err(|e| {
+    let child = spawn_tracked(&mut cmd, command)?;
+    let pid = child.id();
+    let output = child.wait_with_output();
+    crate::signal::untrack_child(pid);
+
+    output.map_err(|e| {
+        OptimizerError::command_failed(format!("Failed to execute {}: {}", command, e))
+    })
+}
+
+/// Trimmed stdout of a successful command, or `None` if it failed or printed nothing
+pub fn command_stdout(command: &str, args: &[&str], working_dir: &Path) -> Option<String> {
+    let output = execute_command(command, args, Some(working_dir)).ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!stdout.is_empty()).then_some(stdout)
+}
+
+/// Spawn a command in its own process group and register it for Ctrl-C cleanup
+fn spawn_tracked(cmd: &mut Command, command: &str) -> OptimizerResult<Child> {
+    crate::allowlist::check(command)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    crate::process_env::apply(cmd);
+    if command == "cargo" {
+        crate::target_dirs::apply(cmd);
+        crate::rustflags::apply(cmd);
+    }
+
+    let child = cmd.spawn().map_err(|e| {
         OptimizerError::command_failed(format!("Failed to execute {}: {}", command, e))
     })?;
+    crate::signal::track_child(child.id());
 
-    Ok(output)
+    Ok(child)
 }
 
-/// Execute a command and return success status
-pub fn execute_command_success(
+/// Execute a command with real-time output
+pub fn execute_command_with_output(
     command: &str,
     args: &[&str],
     working_dir: Option<&Path>,
-) -> OptimizerResult<bool> {
-    let output = execute_command(command, args, working_dir)?;
-    Ok(output.status.success())
+) -> OptimizerResult<()> {
+    execute_command_with_timeout(command, args, working_dir, None)
 }
 
-/// Execute a command with real-time output
-pub fn execute_command_with_output(
+/// Execute a command with real-time output, killing it and its children once
+/// `timeout` has passed
+pub fn execute_command_with_timeout(
     command: &str,
     args: &[&str],
     working_dir: Option<&Path>,
+    timeout: Option<Duration>,
 ) -> OptimizerResult<()> {
     let mut cmd = Command::new(command);
-    cmd.args(args);
+    cmd.args(run_args(command, args));
 
     if let Some(dir) = working_dir {
         cmd.current_dir(dir);
     }
 
-    let status = cmd.status().map_err(|e| {
+    let mut child = spawn_tracked(&mut cmd, command)?;
+    let pid = child.id();
+    let watchdog = timeout.map(|timeout| Watchdog::start(pid, timeout));
+    let status = child.wait();
+    crate::signal::untrack_child(pid);
+
+    let status = status.map_err(|e| {
         OptimizerError::command_failed(format!("Failed to execute {}: {}", command, e))
     })?;
 
+    if crate::signal::is_interrupted() {
+        return Err(OptimizerError::Cancelled);
+    }
+
+    if let Some(report) = watchdog.and_then(Watchdog::finish) {
+        return Err(OptimizerError::timed_out(format!(
+            "{}: {}",
+            command, report
+        )));
+    }
+
     if !status.success() {
         return Err(OptimizerError::command_failed(format!(
             "Command {} failed with exit code: {:?}",
@@ -108,17 +188,130 @@ pub fn execute_command_with_output(
     Ok(())
 }
 
-/// Create a progress bar with a custom style
-pub fn create_progress_bar(len: u64, message: &str) -> ProgressBar {
-    let pb = ProgressBar::new(len);
-    pb.set_style(
-        ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}")
-            .unwrap()
-            .progress_chars("#>-"),
-    );
-    pb.set_message(message.to_string());
-    pb
+/// Output stream of a child process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// Execute a command, handing each line of stdout to `on_line` as it arrives.
+/// Stderr is inherited so cargo's progress output stays visible, unless
+/// `capture_stderr` is set, in which case its lines are handed over as well.
+/// With a `timeout`, the command and its children are killed once it has passed.
+pub fn execute_command_streaming<F>(
+    command: &str,
+    args: &[&str],
+    working_dir: Option<&Path>,
+    timeout: Option<Duration>,
+    capture_stderr: bool,
+    mut on_line: F,
+) -> OptimizerResult<()>
+where
+    F: FnMut(Stream, &str),
+{
+    use std::io::{BufRead, BufReader, Read};
+    use std::sync::mpsc;
+
+    let mut cmd = Command::new(command);
+    cmd.args(run_args(command, args)).stdout(Stdio::piped());
+    if capture_stderr {
+        cmd.stderr(Stdio::piped());
+    }
+
+    if let Some(dir) = working_dir {
+        cmd.current_dir(dir);
+    }
+
+    let mut child = spawn_tracked(&mut cmd, command)?;
+    let pid = child.id();
+    let watchdog = timeout.map(|timeout| Watchdog::start(pid, timeout));
+
+    // Each pipe is read on its own thread so neither can fill up and block the child
+    let (lines, received) = mpsc::channel();
+    let forward = |stream: Stream, pipe: Box<dyn Read + Send>| {
+        let lines = lines.clone();
+        std::thread::spawn(move || {
+            for line in BufReader::new(pipe).lines().map_while(Result::ok) {
+                if lines.send((stream, line)).is_err() {
+                    break;
+                }
+            }
+        })
+    };
+    let mut readers = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        readers.push(forward(Stream::Stdout, Box::new(stdout)));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        readers.push(forward(Stream::Stderr, Box::new(stderr)));
+    }
+    drop(lines);
+    for (stream, line) in received {
+        on_line(stream, &line);
+    }
+    for reader in readers {
+        let _ = reader.join();
+    }
+
+    let status = child.wait();
+    crate::signal::untrack_child(pid);
+
+    let status = status.map_err(|e| {
+        OptimizerError::command_failed(format!("Failed to execute {}: {}", command, e))
+    })?;
+
+    if crate::signal::is_interrupted() {
+        return Err(OptimizerError::Cancelled);
+    }
+
+    if let Some(report) = watchdog.and_then(Watchdog::finish) {
+        return Err(OptimizerError::timed_out(format!(
+            "{}: {}",
+            command, report
+        )));
+    }
+
+    if !status.success() {
+        return Err(OptimizerError::command_failed(format!(
+            "Command {} failed with exit code: {:?}",
+            command,
+            status.code()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Execute a command that needs the terminal (password prompts, editors).
+/// It stays in the foreground process group so it receives Ctrl-C directly.
+pub fn execute_interactive_command(
+    command: &str,
+    args: &[&str],
+    working_dir: Option<&Path>,
+) -> OptimizerResult<()> {
+    crate::allowlist::check(command)?;
+    let mut cmd = Command::new(command);
+    cmd.args(args);
+    crate::process_env::apply(&mut cmd);
+
+    if let Some(dir) = working_dir {
+        cmd.current_dir(dir);
+    }
+
+    let status = cmd.status().map_err(|e| {
+        OptimizerError::command_failed(format!("Failed to execute {}: {}", command, e))
+    })?;
+
+    if !status.success() {
+        return Err(OptimizerError::command_failed(format!(
+            "Command {} failed with exit code: {:?}",
+            command,
+            status.code()
+        )));
+    }
+
+    Ok(())
 }
 
 /// Create an indeterminate progress spinner
@@ -131,6 +324,7 @@ pub fn create_spinner(message: &str) -> ProgressBar {
     );
     pb.set_message(message.to_string());
     pb.enable_steady_tick(Duration::from_millis(100));
+    crate::signal::track_spinner(&pb);
     pb
 }
 
@@ -151,7 +345,7 @@ pub fn backup_file<P: AsRef<Path>>(path: P) -> OptimizerResult<PathBuf> {
             .unwrap_or("")
     ));
 
-    fs::copy(original, &backup_path)?;
+    crate::audit::write(&backup_path, fs::read(original)?)?;
     print_status(&format!(
         "Backed up {} to {}",
         original.display(),
@@ -161,6 +355,25 @@ pub fn backup_file<P: AsRef<Path>>(path: P) -> OptimizerResult<PathBuf> {
     Ok(backup_path)
 }
 
+/// Create the directories of `path`. A `.atlas` directory among them gets a
+/// `.gitignore` ignoring everything, so Atlas state stays out of commits
+pub fn create_parent_dirs(path: &Path) -> OptimizerResult<()> {
+    let Some(parent) = path.parent() else {
+        return Ok(());
+    };
+    fs::create_dir_all(parent)?;
+    if let Some(state_dir) = parent
+        .ancestors()
+        .find(|dir| dir.file_name().is_some_and(|name| name == ".atlas"))
+    {
+        let ignore = state_dir.join(".gitignore");
+        if !ignore.exists() {
+            fs::write(ignore, "*\n")?;
+        }
+    }
+    Ok(())
+}
+
 /// Get the size of a directory in bytes
 pub fn get_directory_size<P: AsRef<Path>>(path: P) -> OptimizerResult<u64> {
     let mut size = 0;
@@ -212,76 +425,90 @@ pub fn format_duration(duration: Duration) -> String {
     }
 }
 
-/// Measure execution time of a function
-pub fn measure_time<F, R>(f: F) -> (R, Duration)
-where
-    F: FnOnce() -> R,
-{
-    let start = Instant::now();
-    let result = f();
-    let duration = start.elapsed();
-    (result, duration)
-}
-
-/// Check if a tool is available in PATH
-pub fn is_tool_available(tool: &str) -> bool {
-    which::which(tool).is_ok()
-}
-
-/// Get the version of a tool
-pub fn get_tool_version(tool: &str) -> Option<String> {
-    let output = Command::new(tool).arg("--version").output().ok()?;
-
-    if output.status.success() {
-        String::from_utf8(output.stdout)
-            .ok()
-            .and_then(|s| s.lines().next().map(|line| line.trim().to_string()))
+/// Quote `arg` for a POSIX shell
+pub fn shell_quote(arg: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c);
+    if !arg.is_empty() && arg.chars().all(safe) {
+        arg.to_string()
     } else {
-        None
+        format!("'{}'", arg.replace('\'', "'\\''"))
     }
 }
 
-/// Clean old files in a directory based on age
-pub fn clean_old_files<P: AsRef<Path>>(
-    directory: P,
-    max_age_days: u32,
-    pattern: Option<&str>,
-) -> OptimizerResult<u64> {
-    let max_age = Duration::from_secs(max_age_days as u64 * 24 * 60 * 60);
-    let now = std::time::SystemTime::now();
-    let mut cleaned_size = 0u64;
-
-    for entry in walkdir::WalkDir::new(directory) {
-        let entry = entry.map_err(|e| OptimizerError::Io(e.into()))?;
-
-        if !entry.file_type().is_file() {
-            continue;
-        }
-
-        // Check pattern if provided
-        if let Some(pattern) = pattern {
-            if !entry.file_name().to_string_lossy().contains(pattern) {
-                continue;
+/// Split a command line into arguments, honouring single and double quotes
+/// and backslash escapes outside single quotes. No shell expansion is done.
+pub fn split_command_line(line: &str) -> OptimizerResult<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some('\''), '\'') => quote = None,
+            (Some('\''), _) => current.push(c),
+            (_, '\\') => match chars.next() {
+                Some(escaped) => {
+                    current.push(escaped);
+                    in_arg = true;
+                }
+                None => current.push(c),
+            },
+            (Some('"'), '"') => quote = None,
+            (Some(_), _) => current.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_arg = true;
             }
-        }
-
-        let metadata = entry.metadata().map_err(|e| OptimizerError::Io(e.into()))?;
-        let modified = metadata
-            .modified()
-            .map_err(|e| OptimizerError::Io(e.into()))?;
-
-        if let Ok(age) = now.duration_since(modified) {
-            if age > max_age {
-                let size = metadata.len();
-                if fs::remove_file(entry.path()).is_ok() {
-                    cleaned_size += size;
-                    log::debug!("Cleaned old file: {}", entry.path().display());
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
                 }
             }
+            (None, _) => {
+                current.push(c);
+                in_arg = true;
+            }
         }
     }
 
-    Ok(cleaned_size)
+    if quote.is_some() {
+        return Err(OptimizerError::invalid_input(format!(
+            "Unterminated quote in command: {}",
+            line
+        )));
+    }
+    if in_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+/// Stable 64-bit FNV-1a hash rendered as hex.
+/// Unlike `DefaultHasher`, the output is identical across Rust releases,
+/// so it is safe to persist.
+pub fn stable_hash(bytes: &[u8]) -> String {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let hash = bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    });
+    format!("{:016x}", hash)
+}
+
+/// Check if a tool is available in PATH
+pub fn is_tool_available(tool: &str) -> bool {
+    which::which(tool).is_ok()
+}
+
+/// Cargo's home directory, which holds the user-wide `config.toml`
+pub fn cargo_home() -> Option<PathBuf> {
+    std::env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".cargo")))
 }
 
 /// Confirm an action with the user
@@ -295,20 +522,6 @@ pub fn confirm(message: &str) -> OptimizerResult<bool> {
         .map_err(|_| OptimizerError::Cancelled)
 }
 
-/// Select from a list of options
-pub fn select_from_list<T: ToString>(message: &str, items: &[T]) -> OptimizerResult<usize> {
-    use dialoguer::Select;
-
-    let items: Vec<String> = items.iter().map(|item| item.to_string()).collect();
-
-    Select::new()
-        .with_prompt(message)
-        .items(&items)
-        .default(0)
-        .interact()
-        .map_err(|_| OptimizerError::Cancelled)
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -321,6 +534,39 @@ mod tests {
         assert_eq!(format_bytes(1048576), "1.0 MB");
     }
 
+    #[test]
+    fn test_create_parent_dirs_ignores_atlas_state() {
+        let dir = tempfile::tempdir().unwrap();
+        create_parent_dirs(&dir.path().join(".atlas/baselines/main.json")).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join(".atlas/.gitignore")).unwrap(),
+            "*\n"
+        );
+
+        create_parent_dirs(&dir.path().join("target/report.json")).unwrap();
+        assert!(dir.path().join("target").is_dir());
+        assert!(!dir.path().join("target/.gitignore").exists());
+    }
+
+    #[test]
+    fn test_stable_hash() {
+        assert_eq!(stable_hash(b""), "cbf29ce484222325");
+        assert_eq!(stable_hash(b"a"), "af63dc4c8601ec8c");
+    }
+
+    #[test]
+    fn test_split_command_line() {
+        assert_eq!(
+            split_command_line("cargo test  --workspace").unwrap(),
+            vec!["cargo", "test", "--workspace"]
+        );
+        assert_eq!(
+            split_command_line(r#"echo "hello world" 'a "b"' c\ d """#).unwrap(),
+            vec!["echo", "hello world", "a \"b\"", "c d", ""]
+        );
+        assert!(split_command_line("echo 'oops").is_err());
+    }
+
     #[test]
     fn test_format_duration() {
         assert_eq!(format_duration(Duration::from_millis(500)), "500ms");
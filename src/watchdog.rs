@@ -0,0 +1,230 @@
+//! Wall-clock timeout for cargo runs. When a run exceeds its limit the
+//! watchdog records which processes were still running, so the report can
+//! say what hung, and then terminates cargo with everything it started.
+
+use std::fmt;
+use std::path::Path;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// What was running when a timeout fired
+#[derive(Debug, Clone, PartialEq)]
+pub struct HangReport {
+    pub timeout: Duration,
+    /// Stages of the processes still running, e.g. `linking demo`
+    pub stages: Vec<String>,
+}
+
+impl fmt::Display for HangReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no result after {}", format_timeout(self.timeout))?;
+        if !self.stages.is_empty() {
+            write!(f, "; still {}", self.stages.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Kills a process group once `timeout` has passed, unless finished first
+pub struct Watchdog {
+    stop: Sender<()>,
+    report: Arc<Mutex<Option<HangReport>>>,
+    thread: JoinHandle<()>,
+}
+
+impl Watchdog {
+    /// Watch the process group led by `pid`, a child spawned by `spawn_tracked`
+    pub fn start(pid: u32, timeout: Duration) -> Self {
+        let (stop, stopped) = mpsc::channel();
+        let report = Arc::new(Mutex::new(None));
+        let fired = Arc::clone(&report);
+        let thread = std::thread::spawn(move || {
+            if let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(timeout) {
+                let stages = running_stages(pid);
+                *fired
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner()) =
+                    Some(HangReport { timeout, stages });
+                crate::signal::terminate_gracefully(pid);
+            }
+        });
+        Self {
+            stop,
+            report,
+            thread,
+        }
+    }
+
+    /// Stop watching; returns the report if the timeout fired
+    pub fn finish(self) -> Option<HangReport> {
+        let _ = self.stop.send(());
+        let _ = self.thread.join();
+        let report = self
+            .report
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take();
+        report
+    }
+}
+
+/// Parse a timeout such as `90s`, `30m` or `2h`; a bare number is minutes
+pub fn parse_timeout(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (number, unit_secs) = match value.char_indices().last()? {
+        (at, 's') => (&value[..at], 1),
+        (at, 'm') => (&value[..at], 60),
+        (at, 'h') => (&value[..at], 3600),
+        _ => (value, 60),
+    };
+    let number: u64 = number.trim().parse().ok()?;
+    (number > 0).then(|| Duration::from_secs(number * unit_secs))
+}
+
+fn format_timeout(timeout: Duration) -> String {
+    let secs = timeout.as_secs();
+    if secs % 3600 == 0 {
+        format!("{}h", secs / 3600)
+    } else if secs % 60 == 0 {
+        format!("{}m", secs / 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+/// What a build process is doing, from its command line
+pub fn describe(cmdline: &[String]) -> Option<String> {
+    let program = Path::new(cmdline.first()?)
+        .file_name()?
+        .to_string_lossy()
+        .into_owned();
+    let arg_after = |flag: &str| {
+        cmdline
+            .iter()
+            .position(|arg| arg == flag)
+            .and_then(|at| cmdline.get(at + 1))
+    };
+
+    match program.as_str() {
+        "cargo" | "sccache" | "ccache" => None,
+        "rustc" => {
+            let krate = arg_after("--crate-name").map_or("a crate", String::as_str);
+            if cmdline.iter().any(|arg| arg == "--test") {
+                Some(format!("compiling tests of {}", krate))
+            } else {
+                Some(format!("compiling {}", krate))
+            }
+        }
+        "cc" | "gcc" | "clang" | "ld" | "ld.lld" | "lld" | "ld64.lld" | "mold" | "collect2"
+        | "ld.gold" | "ld.bfd" => {
+            let output = arg_after("-o")
+                .and_then(|output| Path::new(output).file_stem())
+                .map(|stem| stem.to_string_lossy().into_owned());
+            Some(match output {
+                Some(output) => format!("linking {}", output),
+                None => format!("running {}", program),
+            })
+        }
+        name if name.starts_with("build-script-") => {
+            // target/debug/build/<package>-<hash>/build-script-build
+            let package = Path::new(&cmdline[0])
+                .parent()
+                .and_then(Path::file_name)
+                .map(|dir| dir.to_string_lossy())
+                .and_then(|dir| dir.rsplit_once('-').map(|(name, _)| name.to_string()));
+            Some(format!(
+                "running the build script of {}",
+                package.as_deref().unwrap_or("a package")
+            ))
+        }
+        _ => Some(format!("running {}", program)),
+    }
+}
+
+/// Stages of every process in the group led by `pid`
+#[cfg(target_os = "linux")]
+fn running_stages(pid: u32) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    let mut stages = Vec::new();
+    for entry in entries.flatten() {
+        let dir = entry.path();
+        // The process group is the fifth field; the name before it may contain spaces
+        let in_group = std::fs::read_to_string(dir.join("stat"))
+            .ok()
+            .and_then(|stat| {
+                stat.rsplit_once(')')
+                    .and_then(|(_, rest)| rest.split_whitespace().nth(2)?.parse::<u32>().ok())
+            })
+            .is_some_and(|group| group == pid);
+        if !in_group {
+            continue;
+        }
+        let cmdline: Vec<String> = std::fs::read(dir.join("cmdline"))
+            .unwrap_or_default()
+            .split(|byte| *byte == 0)
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| String::from_utf8_lossy(arg).into_owned())
+            .collect();
+        if let Some(stage) = describe(&cmdline) {
+            if !stages.contains(&stage) {
+                stages.push(stage);
+            }
+        }
+    }
+    stages.sort();
+    stages
+}
+
+#[cfg(not(target_os = "linux"))]
+fn running_stages(_pid: u32) -> Vec<String> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_parse_timeout() {
+        assert_eq!(parse_timeout("90s"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_timeout("30m"), Some(Duration::from_secs(1800)));
+        assert_eq!(parse_timeout("2h"), Some(Duration::from_secs(7200)));
+        assert_eq!(parse_timeout("45"), Some(Duration::from_secs(2700)));
+        assert_eq!(parse_timeout("0m"), None);
+        assert_eq!(parse_timeout("soon"), None);
+        assert_eq!(format_timeout(Duration::from_secs(1800)), "30m");
+    }
+
+    #[test]
+    fn test_describe_stages() {
+        assert_eq!(
+            describe(&args(
+                "/home/u/.rustup/toolchains/x/bin/rustc --crate-name serde --edition=2021"
+            )),
+            Some("compiling serde".to_string())
+        );
+        assert_eq!(
+            describe(&args("rustc --crate-name demo --test src/lib.rs")),
+            Some("compiling tests of demo".to_string())
+        );
+        assert_eq!(
+            describe(&args("cc -m64 a.o -o /p/target/debug/deps/demo-0f1e2d")),
+            Some("linking demo-0f1e2d".to_string())
+        );
+        assert_eq!(
+            describe(&args(
+                "/p/target/debug/build/openssl-sys-3a4b/build-script-build"
+            )),
+            Some("running the build script of openssl-sys".to_string())
+        );
+        assert_eq!(describe(&args("cargo build")), None);
+    }
+}
@@ -0,0 +1,70 @@
+//! End-to-end tests of the `atlas` binary, for output meant to be
+//! redirected into a file and for input rejected before any build
+
+mod common;
+
+use assert_cmd::Command;
+use common::{lock, Fixture};
+
+fn atlas(args: &[&str]) -> Command {
+    let mut command = Command::cargo_bin("atlas").expect("atlas binary");
+    command.args(args);
+    command
+}
+
+#[test]
+fn test_bundle_export_to_stdout_imports() {
+    let _guard = lock();
+    let exported = atlas(&["config", "bundle", "export"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let exported = String::from_utf8(exported).expect("UTF-8 bundle");
+    assert!(exported.starts_with("# Atlas config bundle"));
+
+    let dir = tempfile::tempdir().expect("temporary directory");
+    let bundle = dir.path().join("bundle.toml");
+    std::fs::write(&bundle, &exported).expect("bundle written");
+    atlas(&["config", "bundle", "import", "--force"])
+        .arg(&bundle)
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_graph_to_stdout_is_only_the_graph() {
+    let _guard = lock();
+    let fixture = Fixture::single_crate();
+    for (format, header) in [("dot", "digraph"), ("mermaid", "flowchart")] {
+        let rendered = atlas(&["analyze", "graph", "--format", format])
+            .current_dir(&fixture.root)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        let rendered = String::from_utf8(rendered).expect("UTF-8 graph");
+        assert!(
+            rendered.starts_with(header),
+            "{} output starts with {:?}",
+            format,
+            rendered.lines().next()
+        );
+    }
+}
+
+#[test]
+fn test_bad_baseline_name_fails_without_building() {
+    let _guard = lock();
+    let fixture = Fixture::single_crate();
+    for action in ["record", "compare"] {
+        atlas(&["baseline", action, "../escape"])
+            .current_dir(&fixture.root)
+            .assert()
+            .failure();
+    }
+    assert!(!fixture.root.join("target").exists());
+    assert!(!fixture.root.join(".atlas").join("baselines").exists());
+}
@@ -0,0 +1,156 @@
+//! Fixture projects for end-to-end tests of the Atlas library API.
+//!
+//! Every fixture lives in its own temporary directory. Atlas also keeps
+//! global state in the user's config directory, so tests share one isolated
+//! config directory and run their commands one at a time through [`lock`].
+
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use tempfile::TempDir;
+
+static GLOBAL_STATE: OnceLock<(TempDir, Mutex<()>)> = OnceLock::new();
+
+/// Point Atlas at a private config directory and hold the lock on it
+pub fn lock() -> MutexGuard<'static, ()> {
+    let (_, mutex) = GLOBAL_STATE.get_or_init(|| {
+        let home = tempfile::tempdir().expect("temporary config directory");
+        // Not XDG_CONFIG_HOME, which only Linux follows
+        std::env::set_var("ATLAS_CONFIG_DIR", home.path().join("atlas"));
+        std::env::set_var("CARGO_HOME", home.path().join("cargo"));
+        (home, Mutex::new(()))
+    });
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// A project on disk, removed when dropped
+pub struct Fixture {
+    _dir: TempDir,
+    pub root: PathBuf,
+}
+
+impl Fixture {
+    /// An empty project directory; `name` may contain spaces
+    fn empty(name: &str) -> Self {
+        let dir = tempfile::tempdir().expect("temporary fixture directory");
+        let root = dir.path().join(name);
+        fs::create_dir_all(&root).expect("fixture root");
+        Self { _dir: dir, root }
+    }
+
+    /// A binary crate with no dependencies
+    pub fn single_crate() -> Self {
+        let fixture = Self::empty("app");
+        fixture.write("Cargo.toml", &package_manifest("app"));
+        fixture.write("src/main.rs", "fn main() {\n    println!(\"hello\");\n}\n");
+        fixture
+    }
+
+    /// A virtual workspace with `members` library crates under `crates/`
+    pub fn virtual_workspace(members: usize) -> Self {
+        let fixture = Self::empty("workspace");
+        fixture.write(
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"crates/*\"]\nresolver = \"2\"\n",
+        );
+        for i in 0..members {
+            let name = format!("member-{:02}", i);
+            fixture.write(
+                &format!("crates/{}/Cargo.toml", name),
+                &package_manifest(&name),
+            );
+            fixture.write(
+                &format!("crates/{}/src/lib.rs", name),
+                "pub fn id() -> u32 {\n    1\n}\n",
+            );
+        }
+        fixture
+    }
+
+    /// A crate in a directory with spaces whose Cargo config uses Windows paths
+    pub fn windows_paths() -> Self {
+        let fixture = Self::empty("My Projects");
+        fixture.write("Cargo.toml", &package_manifest("app"));
+        fixture.write("src/main.rs", "fn main() {}\n");
+        fixture.write(
+            ".cargo/config.toml",
+            r#"[target.x86_64-pc-windows-msvc]
+linker = 'C:\Program Files\LLVM\bin\lld-link.exe'
+
+[env]
+OPENSSL_DIR = "C:\\vcpkg\\installed\\x64-windows"
+"#,
+        );
+        fixture
+    }
+
+    /// Add an existing hand-written `.cargo/config.toml`
+    pub fn with_cargo_config(self, content: &str) -> Self {
+        self.write(".cargo/config.toml", content);
+        self
+    }
+
+    pub fn write(&self, path: &str, content: &str) {
+        let path = self.root.join(path);
+        fs::create_dir_all(path.parent().expect("parent directory")).expect("fixture directory");
+        fs::write(path, content).expect("fixture file");
+    }
+
+    pub fn read(&self, path: &str) -> String {
+        fs::read_to_string(self.root.join(path))
+            .unwrap_or_else(|e| panic!("reading {}: {}", path, e))
+    }
+
+    pub fn path(&self, path: &str) -> PathBuf {
+        self.root.join(path)
+    }
+
+    pub fn dir(&self) -> Option<PathBuf> {
+        Some(self.root.clone())
+    }
+
+    /// Work from the project directory until the guard is dropped. Outside
+    /// the `atlas` binary, Atlas finds the project's config layers from the
+    /// working directory.
+    pub fn enter(&self) -> WorkingDir {
+        let previous = std::env::current_dir().expect("working directory");
+        std::env::set_current_dir(&self.root).expect("entering the fixture");
+        WorkingDir(previous)
+    }
+}
+
+/// Restores the previous working directory when dropped
+pub struct WorkingDir(PathBuf);
+
+impl Drop for WorkingDir {
+    fn drop(&mut self) {
+        let _ = std::env::set_current_dir(&self.0);
+    }
+}
+
+fn package_manifest(name: &str) -> String {
+    format!(
+        "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+        name
+    )
+}
+
+/// Parse a generated file, failing the test if it is not valid TOML
+pub fn parse_toml(path: &Path) -> toml::Table {
+    let content = fs::read_to_string(path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+    toml::from_str(&content)
+        .unwrap_or_else(|e| panic!("{} is not valid TOML: {}", path.display(), e))
+}
+
+/// Run an async Atlas command to completion
+pub fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("tokio runtime")
+        .block_on(future)
+}
@@ -0,0 +1,288 @@
+//! End-to-end tests of `init`, `build` and `status` against fixture projects
+
+mod common;
+
+use atlas::commands::{build, initialize, refresh, status};
+use atlas::{
+    find_block, without_blocks, BuildCommands, ConfigScope, LastBuild, CARGO_CONFIG_BLOCK,
+    INIT_RECORD_FILE, PROFILES_BLOCK, PROJECT_CONFIG_FILE,
+};
+use common::{block_on, lock, parse_toml, Fixture};
+
+fn init(fixture: &Fixture, force: bool) {
+    init_with(fixture, force, false);
+}
+
+fn init_with(fixture: &Fixture, force: bool, minimal: bool) {
+    block_on(initialize::run(
+        fixture.dir(),
+        initialize::InitOptions {
+            no_backup: true,
+            no_tools: true,
+            force,
+            scope: ConfigScope::Project,
+            nix: false,
+            full: false,
+            minimal,
+        },
+    ))
+    .expect("atlas init");
+}
+
+fn init_with_backups(fixture: &Fixture, force: bool) {
+    block_on(initialize::run(
+        fixture.dir(),
+        initialize::InitOptions {
+            no_backup: false,
+            no_tools: true,
+            force,
+            scope: ConfigScope::Project,
+            nix: false,
+            full: false,
+            minimal: false,
+        },
+    ))
+    .expect("atlas init");
+}
+
+#[test]
+fn test_init_single_crate() {
+    let _guard = lock();
+    let fixture = Fixture::single_crate();
+    init(&fixture, false);
+
+    let cargo_config = fixture.read(".cargo/config.toml");
+    assert!(find_block(&cargo_config, CARGO_CONFIG_BLOCK)
+        .unwrap()
+        .is_some());
+    parse_toml(&fixture.path(".cargo/config.toml"));
+
+    let manifest = fixture.read("Cargo.toml");
+    assert!(find_block(&manifest, PROFILES_BLOCK).unwrap().is_some());
+    assert!(manifest.starts_with("[package]\nname = \"app\""));
+    let profiles = parse_toml(&fixture.path("Cargo.toml"));
+    assert!(profiles["profile"].get("dev").is_some());
+    assert!(fixture.path(PROJECT_CONFIG_FILE).exists());
+
+    // Running init again rewrites the blocks in place
+    init(&fixture, false);
+    let rerun = fixture.read("Cargo.toml");
+    assert_eq!(rerun.matches("[profile.dev]").count(), 1);
+    assert_eq!(
+        find_block(&rerun, PROFILES_BLOCK)
+            .unwrap()
+            .unwrap()
+            .range
+            .len(),
+        find_block(&manifest, PROFILES_BLOCK)
+            .unwrap()
+            .unwrap()
+            .range
+            .len()
+    );
+}
+
+#[test]
+fn test_init_merges_into_existing_profiles() {
+    let _guard = lock();
+    let fixture = Fixture::single_crate();
+    let manifest = format!(
+        "{}\n[profile.dev]\n# Enough for backtraces\ndebug = 1\n\n[profile.release]\nlto = true # measured\n",
+        fixture.read("Cargo.toml")
+    );
+    fixture.write("Cargo.toml", &manifest);
+    let _cwd = fixture.enter();
+    init(&fixture, false);
+
+    let merged = fixture.read("Cargo.toml");
+    assert!(merged.starts_with("[package]\nname = \"app\""));
+    assert!(merged.contains("[profile.dev]\n# Enough for backtraces\ndebug = 1\n"));
+    assert_eq!(merged.matches("[profile.dev]").count(), 1);
+    assert_eq!(merged.matches("[profile.release]").count(), 1);
+    assert!(merged.contains("lto = true # measured\n"));
+    assert!(merged.contains("codegen-units = 512 # atlas\n"));
+    let profiles = parse_toml(&fixture.path("Cargo.toml"));
+    assert_eq!(profiles["profile"]["dev"]["debug"].as_integer(), Some(1));
+    assert_eq!(profiles["profile"]["release"]["lto"].as_bool(), Some(true));
+    assert!(profiles["profile"]["dev"]["package"].get("*").is_some());
+
+    // Running init again leaves the merged keys as they are, and does not
+    // take them for the user's own settings
+    init(&fixture, false);
+    assert_eq!(
+        without_blocks(&fixture.read("Cargo.toml")).unwrap(),
+        without_blocks(&merged).unwrap()
+    );
+    let config_hash = |manifest: &str| {
+        find_block(manifest, PROFILES_BLOCK)
+            .unwrap()
+            .and_then(|block| block.stamp)
+            .map(|stamp| stamp.config_hash)
+            .expect("stamped profiles block")
+    };
+    assert_eq!(
+        config_hash(&fixture.read("Cargo.toml")),
+        config_hash(&merged)
+    );
+    assert!(status::stale_generated_files(&fixture.root).is_empty());
+}
+
+#[test]
+fn test_init_undo_restores_project() {
+    let _guard = lock();
+    let fixture = Fixture::single_crate();
+    let manifest = fixture.read("Cargo.toml");
+    for _ in 0..2 {
+        init_with_backups(&fixture, false);
+    }
+    assert!(fixture.path("Cargo.toml.backup").exists());
+
+    initialize::undo(fixture.dir()).expect("atlas init --undo");
+    assert_eq!(fixture.read("Cargo.toml"), manifest);
+    for removed in [
+        ".cargo",
+        "Cargo.toml.backup",
+        PROJECT_CONFIG_FILE,
+        INIT_RECORD_FILE,
+    ] {
+        assert!(!fixture.path(removed).exists(), "{} remains", removed);
+    }
+    assert!(initialize::undo(fixture.dir()).is_err());
+}
+
+#[test]
+fn test_init_minimal_leaves_manifest_alone() {
+    let _guard = lock();
+    let fixture = Fixture::single_crate();
+    let manifest = fixture.read("Cargo.toml");
+    init_with(&fixture, false, true);
+
+    assert_eq!(fixture.read("Cargo.toml"), manifest);
+    let cargo_config = parse_toml(&fixture.path(".cargo/config.toml"));
+    assert_eq!(
+        cargo_config["registries"]["crates-io"]["protocol"].as_str(),
+        Some("sparse")
+    );
+    assert!(cargo_config.get("target").is_none());
+    parse_toml(&fixture.path(".config/nextest.toml"));
+}
+
+#[test]
+fn test_init_virtual_workspace() {
+    let _guard = lock();
+    let fixture = Fixture::virtual_workspace(40);
+    init(&fixture, false);
+
+    let manifest = fixture.read("Cargo.toml");
+    assert!(find_block(&manifest, PROFILES_BLOCK).unwrap().is_some());
+    let root = parse_toml(&fixture.path("Cargo.toml"));
+    assert_eq!(root["workspace"]["members"][0].as_str(), Some("crates/*"));
+
+    // Profiles only take effect at the workspace root; members stay untouched
+    for member in ["member-00", "member-39"] {
+        let member_manifest = fixture.read(&format!("crates/{}/Cargo.toml", member));
+        assert!(!member_manifest.contains("[profile"));
+    }
+}
+
+#[test]
+fn test_init_imports_existing_cargo_config() {
+    let _guard = lock();
+    let fixture = Fixture::single_crate().with_cargo_config("[build]\njobs = 3\n");
+    let _cwd = fixture.enter();
+    init(&fixture, true);
+
+    let cargo_config = parse_toml(&fixture.path(".cargo/config.toml"));
+    assert_eq!(cargo_config["build"]["jobs"].as_integer(), Some(3));
+    assert!(fixture
+        .read("atlas.toml")
+        .contains("[build]\nparallel_jobs = 3 # atlas\n"));
+
+    // Later regenerations keep the imported value, and the blocks are current
+    block_on(refresh::run(false, fixture.dir())).expect("atlas refresh");
+    let cargo_config = parse_toml(&fixture.path(".cargo/config.toml"));
+    assert_eq!(cargo_config["build"]["jobs"].as_integer(), Some(3));
+    assert!(status::stale_generated_files(&fixture.root).is_empty());
+    block_on(status::run(false, false, fixture.dir())).expect("atlas status");
+}
+
+#[test]
+fn test_undo_after_refresh_restores_imported_cargo_config() {
+    let _guard = lock();
+    let original = "[build]\njobs = 4\nrustc-wrapper = \"sccache\"\n";
+    let fixture = Fixture::single_crate().with_cargo_config(original);
+    let _cwd = fixture.enter();
+    init_with_backups(&fixture, true);
+
+    // A config change that refresh writes into the generated block
+    let project_config = fixture
+        .read(PROJECT_CONFIG_FILE)
+        .replace("parallel_jobs = 4 # atlas", "parallel_jobs = 2");
+    fixture.write(PROJECT_CONFIG_FILE, &project_config);
+    block_on(refresh::run(false, fixture.dir())).expect("atlas refresh");
+    let cargo_config = parse_toml(&fixture.path(".cargo/config.toml"));
+    assert_eq!(cargo_config["build"]["jobs"].as_integer(), Some(2));
+
+    initialize::undo(fixture.dir()).expect("atlas init --undo");
+    assert_eq!(fixture.read(".cargo/config.toml"), original);
+    assert!(!fixture.path(INIT_RECORD_FILE).exists());
+}
+
+#[test]
+fn test_init_keeps_config_with_windows_paths() {
+    let _guard = lock();
+    let fixture = Fixture::windows_paths();
+    let original = fixture.read(".cargo/config.toml");
+    init(&fixture, false);
+
+    // Settings Atlas cannot express are never dropped without --force
+    assert_eq!(fixture.read(".cargo/config.toml"), original);
+    let cargo_config = parse_toml(&fixture.path(".cargo/config.toml"));
+    assert_eq!(
+        cargo_config["env"]["OPENSSL_DIR"].as_str(),
+        Some(r"C:\vcpkg\installed\x64-windows")
+    );
+    assert!(find_block(&fixture.read("Cargo.toml"), PROFILES_BLOCK)
+        .unwrap()
+        .is_some());
+}
+
+#[test]
+fn test_build_and_status_after_init() {
+    let _guard = lock();
+    let fixture = Fixture::single_crate();
+    init(&fixture, false);
+
+    block_on(build::run(
+        BuildCommands::Check {
+            stats: false,
+            members: None,
+        },
+        fixture.dir(),
+    ))
+    .expect("atlas build check");
+    let last_build = LastBuild::load(&fixture.root).expect("last build recorded");
+    assert!(last_build.success);
+    assert_eq!(last_build.command, "check");
+
+    block_on(status::run(false, true, fixture.dir())).expect("atlas status --json");
+}
+
+#[test]
+fn test_generate_through_library() {
+    let _guard = lock();
+    let system_info = atlas::SystemInfo::detect().expect("system detection");
+    let config = atlas::OptimizerConfig::default();
+
+    let cargo_config =
+        atlas::generate::cargo_config(&config, &system_info, ConfigScope::Project, false)
+            .expect("generated cargo config");
+    toml::from_str::<toml::Table>(&cargo_config).expect("generated cargo config parses");
+
+    // Profiles the manifest already defines are left to it
+    let manifest = "[package]\nname = \"app\"\n\n[profile.dev]\nopt-level = 1\n";
+    let profiles = atlas::generate::profiles_for_manifest(&config, &system_info, manifest)
+        .expect("generated profiles");
+    assert!(!profiles.contains("[profile.dev]"));
+    assert!(profiles.contains("[profile.release]"));
+}
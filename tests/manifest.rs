@@ -0,0 +1,85 @@
+//! Golden-file tests of manifest edits: each fixture in `tests/fixtures/manifests`
+//! is edited and compared with its `.expected.toml`. Run with
+//! `ATLAS_BLESS=1` to rewrite the expected files after an intended change.
+
+use atlas::Manifest;
+use std::fs;
+use std::path::PathBuf;
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("manifests")
+        .join(name)
+}
+
+fn check_golden(name: &str, edit: impl FnOnce(&mut Manifest)) {
+    let input = fs::read_to_string(fixture(&format!("{}.toml", name))).expect("fixture");
+    let mut manifest = Manifest::parse(&input).expect("fixture parses");
+    edit(&mut manifest);
+    let output = manifest.to_string();
+    toml::from_str::<toml::Table>(&output).expect("edited manifest parses");
+
+    let expected_path = fixture(&format!("{}.expected.toml", name));
+    if std::env::var_os("ATLAS_BLESS").is_some() {
+        fs::write(&expected_path, &output).expect("write expected");
+        return;
+    }
+    let expected = fs::read_to_string(&expected_path).expect("expected output");
+    assert_eq!(
+        output, expected,
+        "{} differs from {}.expected.toml",
+        name, name
+    );
+}
+
+#[test]
+fn test_profile_edits() {
+    check_golden("profiles", |manifest| {
+        assert!(manifest.rename_key("profile.dev", "codegen_units", "codegen-units"));
+        assert!(manifest
+            .set_key("profile.dev", "debug", "\"line-tables-only\"")
+            .unwrap());
+        assert!(manifest
+            .set_key("profile.release", "lto", "\"fat\"")
+            .unwrap());
+        assert!(manifest.set_key("profile.bench", "debug", "true").unwrap());
+    });
+}
+
+#[test]
+fn test_dependency_edits() {
+    check_golden("dependencies", |manifest| {
+        assert!(manifest.remove_dependency("unused-crate"));
+        for name in ["serde", "tokio", "log", "regex"] {
+            assert!(manifest.use_workspace_dependency(name), "{}", name);
+        }
+        assert!(!manifest.use_workspace_dependency("serde"));
+        assert!(!manifest.remove_dependency("missing"));
+    });
+}
+
+#[test]
+fn test_workspace_dependencies() {
+    check_golden("workspace", |manifest| {
+        assert!(manifest
+            .add_workspace_dependency("serde", "{ version = \"1.0\", features = [\"derive\"] }")
+            .unwrap());
+        assert!(manifest
+            .add_workspace_dependency("tokio", "\"1.35\"")
+            .unwrap());
+        assert!(!manifest
+            .add_workspace_dependency("anyhow", "\"2.0\"")
+            .unwrap());
+    });
+}
+
+#[test]
+fn test_atlas_blocks_are_left_alone() {
+    check_golden("atlas_block", |manifest| {
+        assert!(!manifest.rename_key("profile.dev", "opt_level", "opt-level"));
+        assert!(!manifest.set_key("profile.dev", "debug", "0").unwrap());
+        assert!(manifest.rename_key("profile.release", "codegen_units", "codegen-units"));
+    });
+}